@@ -1619,6 +1619,7 @@ pub fn process_logs(config: &CliConfig, filter: &RpcTransactionLogsFilter) -> Pr
         filter.clone(),
         RpcTransactionLogsConfig {
             commitment: Some(config.commitment),
+            log_prefix: None,
         },
     )?;
 
@@ -2029,6 +2030,7 @@ pub fn process_transaction_history(
             until,
             limit: Some(limit),
             commitment: Some(CommitmentConfig::confirmed()),
+            ..GetConfirmedSignaturesForAddress2Config::default()
         },
     )?;
 
@@ -2067,6 +2069,7 @@ pub fn process_transaction_history(
                         encoding: Some(UiTransactionEncoding::Base64),
                         commitment: Some(CommitmentConfig::confirmed()),
                         max_supported_transaction_version: Some(0),
+                        decode_instruction_error: None,
                     },
                 ) {
                     Ok(confirmed_transaction) => {
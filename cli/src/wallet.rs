@@ -563,6 +563,7 @@ pub fn process_confirm(
                             encoding: Some(UiTransactionEncoding::Base64),
                             commitment: Some(CommitmentConfig::confirmed()),
                             max_supported_transaction_version: Some(0),
+                            decode_instruction_error: None,
                         },
                     ) {
                         Ok(confirmed_transaction) => {
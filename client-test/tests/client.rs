@@ -277,6 +277,7 @@ fn test_block_subscription() {
             transaction_details: Some(TransactionDetails::Signatures),
             show_rewards: None,
             max_supported_transaction_version: None,
+            resume_from: None,
         }),
     )
     .unwrap();
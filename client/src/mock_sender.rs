@@ -6,12 +6,17 @@ use {
         rpc_config::RpcBlockProductionConfig,
         rpc_request::RpcRequest,
         rpc_response::{
-            Response, RpcAccountBalance, RpcBlockProduction, RpcBlockProductionRange, RpcBlockhash,
-            RpcConfirmedTransactionStatusWithSignature, RpcContactInfo, RpcFees, RpcIdentity,
-            RpcInflationGovernor, RpcInflationRate, RpcInflationReward, RpcKeyedAccount,
-            RpcPerfSample, RpcResponseContext, RpcSimulateTransactionResult, RpcSnapshotSlotInfo,
-            RpcStakeActivation, RpcSupply, RpcVersionInfo, RpcVoteAccountInfo,
-            RpcVoteAccountStatus, StakeActivationState,
+            Response, RpcAccountBalance, RpcAccountsHashHealth, RpcBlockProduction,
+            RpcBlockProductionRange, RpcBlockhash, RpcBufferedPrioritizationFees,
+            RpcConfirmedTransactionStatusWithSignature, RpcContactInfo, RpcEpochStakeBreakdown,
+            RpcEpochStakeHistoryEntry, RpcEstimatedBlockTime, RpcFees, RpcGossipHealth,
+            RpcHealthDetailed, RpcHealthState, RpcIdentity, RpcInflationGovernor, RpcInflationRate,
+            RpcInflationReward, RpcKeyedAccount, RpcKeyedAccountsPage, RpcPerfSample,
+            RpcReplayHealth, RpcResponseContext, RpcSimulateTransactionResult, RpcSlotTimeEstimate,
+            RpcSlotTimeEstimates, RpcSnapshotHealth, RpcSnapshotSlotInfo, RpcStakeActivation,
+            RpcStakeDelegationSnapshot, RpcStakeSnapshot, RpcSupply, RpcValidatorStakeDelta,
+            RpcValidatorStakeSnapshot, RpcVersionInfo, RpcVoteAccountInfo, RpcVoteAccountStatus,
+            RpcVoteLatencies, RpcVoteLatency, RpcVotingHealth, StakeActivationState,
         },
         rpc_sender::*,
     },
@@ -230,6 +235,7 @@ impl RpcSender for MockSender {
                             rewards: None,
                             loaded_addresses: None,
                             return_data: None,
+                            instruction_error_detail: None,
                         }),
                 },
                 block_time: Some(1628633791),
@@ -239,6 +245,34 @@ impl RpcSender for MockSender {
             "getMaxShredInsertSlot" => json![0],
             "requestAirdrop" => Value::String(Signature::new(&[8; 64]).to_string()),
             "getSnapshotSlot" => Value::Number(Number::from(0)),
+            "getHealthDetailed" => json!(RpcHealthDetailed {
+                status: RpcHealthState::Ok,
+                replay: RpcReplayHealth {
+                    status: RpcHealthState::Ok,
+                    processed_slot: 1234,
+                    max_shred_insert_slot: 1235,
+                    slots_behind: 1,
+                },
+                snapshot: RpcSnapshotHealth {
+                    status: RpcHealthState::Ok,
+                    full_snapshot_slot: Some(100),
+                    incremental_snapshot_slot: Some(110),
+                    slots_behind_root: Some(92),
+                },
+                accounts_hash: RpcAccountsHashHealth {
+                    status: RpcHealthState::Ok,
+                    slots_behind_known_validators: None,
+                },
+                gossip: RpcGossipHealth {
+                    status: RpcHealthState::Ok,
+                    num_peers: 42,
+                },
+                voting: RpcVotingHealth {
+                    status: RpcHealthState::Ok,
+                    last_vote_slot: Some(1233),
+                    slots_behind: Some(1),
+                },
+            }),
             "getHighestSnapshotSlot" => json!(RpcSnapshotSlotInfo {
                 full: 100,
                 incremental: Some(110),
@@ -288,6 +322,43 @@ impl RpcSender for MockSender {
                 active: 123,
                 inactive: 12,
             }),
+            "getStakeMinimumDelegationHistory" => json!(vec![RpcEpochStakeHistoryEntry {
+                epoch: 1,
+                minimum_delegation: 1,
+                effective: 123,
+                activating: 12,
+                deactivating: 0,
+            }]),
+            "getEpochStakeBreakdown" => json!(Some(RpcEpochStakeBreakdown {
+                epoch: 2,
+                total_stake: 123,
+                total_stake_delta: Some(12),
+                validators: vec![RpcValidatorStakeDelta {
+                    vote_pubkey: PUBKEY.to_string(),
+                    node_pubkey: Some(PUBKEY.to_string()),
+                    activated_stake: 123,
+                    stake_delta: Some(12),
+                }],
+            })),
+            "getStakeSnapshot" => json!(Some(RpcStakeSnapshot {
+                epoch: 2,
+                total_activated_stake: 123,
+                validators: vec![RpcValidatorStakeSnapshot {
+                    vote_pubkey: PUBKEY.to_string(),
+                    node_pubkey: Some(PUBKEY.to_string()),
+                    activated_stake: 123,
+                    delegations: vec![RpcStakeDelegationSnapshot {
+                        stake_pubkey: PUBKEY.to_string(),
+                        stake: 135,
+                        activation_epoch: 1,
+                        deactivation_epoch: u64::MAX,
+                        state: StakeActivationState::Activating,
+                        effective: 123,
+                        activating: 12,
+                        deactivating: 0,
+                    }],
+                }],
+            })),
             "getSupply" => json!(Response {
                 context: RpcResponseContext { slot: 1, api_version: None },
                 value: RpcSupply {
@@ -340,10 +411,24 @@ impl RpcSender for MockSender {
                     err: None,
                     logs: None,
                     accounts: None,
+                    account_diffs: None,
+                    account_state_diffs: None,
                     units_consumed: None,
                     return_data: None,
                 },
             })?,
+            "simulateBundle" => serde_json::to_value(Response {
+                context: RpcResponseContext { slot: 1, api_version: None },
+                value: vec![RpcSimulateTransactionResult {
+                    err: None,
+                    logs: None,
+                    accounts: None,
+                    account_diffs: None,
+                    account_state_diffs: None,
+                    units_consumed: None,
+                    return_data: None,
+                }],
+            })?,
             "getMinimumBalanceForRentExemption" => json![20],
             "getVersion" => {
                 let version = Version::default();
@@ -404,14 +489,55 @@ impl RpcSender for MockSender {
                     confirmation_status: Some(TransactionConfirmationStatus::Finalized),
                 }])?
             }
-            "getBlockTime" => serde_json::to_value(UnixTimestamp::default())?,
+            "getBlockTime" => {
+                if params[1]["allowEstimated"] == Value::Bool(true) {
+                    serde_json::to_value(RpcEstimatedBlockTime {
+                        block_time: UnixTimestamp::default(),
+                        estimated: false,
+                    })?
+                } else {
+                    serde_json::to_value(UnixTimestamp::default())?
+                }
+            }
             "getEpochSchedule" => serde_json::to_value(EpochSchedule::default())?,
+            "getBufferedPrioritizationFees" => serde_json::to_value(RpcBufferedPrioritizationFees {
+                num_packets: 100,
+                min_compute_unit_price: 0,
+                median_compute_unit_price: 1_000,
+                p90_compute_unit_price: 10_000,
+            })?,
+            "getVoteLatencies" => serde_json::to_value(RpcVoteLatencies {
+                first_slot: 0,
+                last_slot: 4_096,
+                validators: vec![RpcVoteLatency {
+                    vote_pubkey: PUBKEY.to_string(),
+                    num_votes: 3,
+                    mean_latency: 2.0,
+                    median_latency: 2,
+                    p90_latency: 3,
+                    max_latency: 3,
+                    latency_counts: vec![1, 1, 1],
+                }],
+            })?,
             "getRecentPerformanceSamples" => serde_json::to_value(vec![RpcPerfSample {
                 slot: 347873,
                 num_transactions: 125,
                 num_slots: 123,
                 sample_period_secs: 60,
             }])?,
+            "getSlotTimeEstimates" => serde_json::to_value(Response {
+                context: RpcResponseContext { slot: 1, api_version: None },
+                value: RpcSlotTimeEstimates {
+                    ms_per_slot: 500,
+                    skip_rate: 0.05,
+                    confirmation_eta_ms: 1500,
+                    slots: vec![RpcSlotTimeEstimate {
+                        slot: 10,
+                        eta_ms: 4500,
+                        estimated_time: 1628633796,
+                    }],
+                },
+            })?,
             "getIdentity" => serde_json::to_value(RpcIdentity {
                 identity: PUBKEY.to_string(),
             })?,
@@ -466,6 +592,32 @@ impl RpcSender for MockSender {
                     }
                 ])?
             },
+            "getProgramAccountsPaginated" => {
+                let pubkey = Pubkey::from_str(PUBKEY).unwrap();
+                let account = Account {
+                    lamports: 1_000_000,
+                    data: vec![],
+                    owner: pubkey,
+                    executable: false,
+                    rent_epoch: 0,
+                };
+                serde_json::to_value(Response {
+                    context: RpcResponseContext { slot: 1, api_version: None },
+                    value: RpcKeyedAccountsPage {
+                        accounts: vec![RpcKeyedAccount {
+                            pubkey: PUBKEY.to_string(),
+                            account: UiAccount::encode(
+                                &pubkey,
+                                &account,
+                                UiAccountEncoding::Base64,
+                                None,
+                                None,
+                            ),
+                        }],
+                        cursor: None,
+                    },
+                })?
+            },
             _ => Value::Null,
         };
         Ok(val)
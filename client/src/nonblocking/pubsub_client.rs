@@ -3,12 +3,13 @@ use {
         http_sender::RpcErrorObject,
         rpc_config::{
             RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
-            RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+            RpcPrioritizationFeeSubscribeConfig, RpcProgramAccountsConfig,
+            RpcSignatureSubscribeConfig, RpcSubscribeResumeConfig, RpcTransactionLogsConfig,
             RpcTransactionLogsFilter,
         },
         rpc_response::{
             Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
-            RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
+            RpcPrioritizationFeeUpdate, RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
         },
     },
     futures_util::{
@@ -144,6 +145,13 @@ impl PubsubClient {
         self.subscribe("logs", json!([filter, config])).await
     }
 
+    pub async fn prioritization_fee_subscribe(
+        &self,
+        config: Option<RpcPrioritizationFeeSubscribeConfig>,
+    ) -> SubscribeResult<'_, RpcPrioritizationFeeUpdate> {
+        self.subscribe("prioritizationFee", json!([config])).await
+    }
+
     pub async fn program_subscribe(
         &self,
         pubkey: &Pubkey,
@@ -161,6 +169,14 @@ impl PubsubClient {
         self.subscribe("root", json!([])).await
     }
 
+    /// Subscribes to roots, replaying first those from `config.resume_from` onwards
+    pub async fn root_subscribe_with_config(
+        &self,
+        config: RpcSubscribeResumeConfig,
+    ) -> SubscribeResult<'_, Slot> {
+        self.subscribe("root", json!([config])).await
+    }
+
     pub async fn signature_subscribe(
         &self,
         signature: &Signature,
@@ -174,6 +190,14 @@ impl PubsubClient {
         self.subscribe("slot", json!([])).await
     }
 
+    /// Subscribes to slots, replaying first those from `config.resume_from` onwards
+    pub async fn slot_subscribe_with_config(
+        &self,
+        config: RpcSubscribeResumeConfig,
+    ) -> SubscribeResult<'_, SlotInfo> {
+        self.subscribe("slot", json!([config])).await
+    }
+
     pub async fn slot_updates_subscribe(&self) -> SubscribeResult<'_, SlotUpdate> {
         self.subscribe("slotsUpdates", json!([])).await
     }
@@ -19,7 +19,10 @@ use {
         mock_sender::MockSender,
         rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClientConfig},
         rpc_config::{RpcAccountInfoConfig, *},
-        rpc_request::{RpcError, RpcRequest, RpcResponseErrorData, TokenAccountsFilter},
+        rpc_request::{
+            RpcError, RpcRequest, RpcResponseErrorData, TokenAccountsFilter,
+            MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT,
+        },
         rpc_response::*,
         rpc_sender::*,
         spinner,
@@ -1389,6 +1392,81 @@ impl RpcClient {
         .await
     }
 
+    /// Simulates sending an ordered list of transactions against a single bank.
+    ///
+    /// Each transaction observes the account changes made by the successful
+    /// transactions before it, so transactions that depend on each other can
+    /// be simulated together. One [`RpcSimulateTransactionResult`] is returned
+    /// per transaction, in order. The [`accounts`] field of the config applies
+    /// to every transaction in the bundle.
+    ///
+    /// [`accounts`]: crate::rpc_config::RpcSimulateTransactionConfig::accounts
+    ///
+    /// # RPC Reference
+    ///
+    /// This method is built on the [`simulateBundle`] RPC method.
+    ///
+    /// [`simulateBundle`]: https://docs.solana.com/developing/clients/jsonrpc-api#simulatebundle
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// #     rpc_config::RpcSimulateTransactionConfig,
+    /// # };
+    /// # use solana_sdk::{
+    /// #     signature::Signer,
+    /// #     signer::keypair::Keypair,
+    /// #     system_transaction,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// // Alice funds Bob, who then pays Carol
+    /// #     let alice = Keypair::new();
+    /// #     let bob = Keypair::new();
+    /// #     let carol = Keypair::new();
+    /// let latest_blockhash = rpc_client.get_latest_blockhash().await?;
+    /// let fund_bob = system_transaction::transfer(&alice, &bob.pubkey(), 100, latest_blockhash);
+    /// let pay_carol = system_transaction::transfer(&bob, &carol.pubkey(), 50, latest_blockhash);
+    /// let results = rpc_client.simulate_bundle_with_config(
+    ///     &[fund_bob, pay_carol],
+    ///     RpcSimulateTransactionConfig::default(),
+    /// ).await?;
+    /// assert!(results.value.iter().all(|result| result.err.is_none()));
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn simulate_bundle_with_config(
+        &self,
+        transactions: &[Transaction],
+        config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<Vec<RpcSimulateTransactionResult>> {
+        let encoding = if let Some(encoding) = config.encoding {
+            encoding
+        } else {
+            self.default_cluster_transaction_encoding().await?
+        };
+        let commitment = config.commitment.unwrap_or_default();
+        let commitment = self.maybe_map_commitment(commitment).await?;
+        let config = RpcSimulateTransactionConfig {
+            encoding: Some(encoding),
+            commitment: Some(commitment),
+            ..config
+        };
+        let serialized_encoded = transactions
+            .iter()
+            .map(|transaction| serialize_and_encode::<Transaction>(transaction, encoding))
+            .collect::<ClientResult<Vec<_>>>()?;
+        self.send(
+            RpcRequest::SimulateBundle,
+            json!([serialized_encoded, config]),
+        )
+        .await
+    }
+
     /// Returns the highest slot information that the node has snapshots for.
     ///
     /// This will find the highest full snapshot slot, and the highest incremental snapshot slot
@@ -2881,6 +2959,77 @@ impl RpcClient {
         .await
     }
 
+    /// Returns a list of confirmed blocks, configured by `config`.
+    ///
+    /// With [`descending`] set, up to `limit` blocks at or below `start_slot`
+    /// are returned, most recent first. Blocks above the slot reached by the
+    /// configured commitment level are never returned, so a `start_slot` of
+    /// `u64::MAX` lists the most recent blocks.
+    ///
+    /// [`descending`]: RpcBlocksWithLimitConfig::descending
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error if the limit is greater than 500,000 slots.
+    ///
+    /// This method returns an error if the given [commitment level][cl] is below
+    /// [`Confirmed`].
+    ///
+    /// [cl]: https://docs.solana.com/developing/clients/jsonrpc-api#configuring-state-commitment
+    /// [`Confirmed`]: CommitmentLevel::Confirmed
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getBlocksWithLimit`] RPC method.
+    ///
+    /// [`getBlocksWithLimit`]: https://docs.solana.com/developing/clients/jsonrpc-api#getblockswithlimit
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_sdk::commitment_config::CommitmentConfig;
+    /// # use solana_client::{
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// #     rpc_config::RpcBlocksWithLimitConfig,
+    /// #     client_error::ClientError,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// // Get the 10 most recent confirmed blocks
+    /// let config = RpcBlocksWithLimitConfig {
+    ///     commitment: Some(CommitmentConfig::confirmed()),
+    ///     descending: Some(true),
+    /// };
+    /// let blocks = rpc_client.get_blocks_with_limit_and_config(
+    ///     u64::MAX,
+    ///     10,
+    ///     config,
+    /// ).await?;
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_blocks_with_limit_and_config(
+        &self,
+        start_slot: Slot,
+        limit: usize,
+        config: RpcBlocksWithLimitConfig,
+    ) -> ClientResult<Vec<Slot>> {
+        let commitment = match config.commitment {
+            Some(commitment) => Some(self.maybe_map_commitment(commitment).await?),
+            None => None,
+        };
+        let config = RpcBlocksWithLimitConfig {
+            commitment,
+            ..config
+        };
+        self.send(
+            RpcRequest::GetBlocksWithLimit,
+            json!([start_slot, limit, config]),
+        )
+        .await
+    }
+
     #[deprecated(since = "1.7.0", note = "Please use RpcClient::get_blocks() instead")]
     #[allow(deprecated)]
     pub async fn get_confirmed_blocks(
@@ -3057,6 +3206,8 @@ impl RpcClient {
     ///     until: None,
     ///     limit: Some(3),
     ///     commitment: Some(CommitmentConfig::confirmed()),
+    ///     before_time: None,
+    ///     after_time: None,
     /// };
     /// let signatures = rpc_client.get_signatures_for_address_with_config(
     ///     &alice.pubkey(),
@@ -3077,6 +3228,8 @@ impl RpcClient {
             limit: config.limit,
             commitment: config.commitment,
             min_context_slot: None,
+            before_time: config.before_time,
+            after_time: config.after_time,
         };
 
         let result: Vec<RpcConfirmedTransactionStatusWithSignature> = self
@@ -3238,6 +3391,7 @@ impl RpcClient {
     ///     encoding: Some(UiTransactionEncoding::Json),
     ///     commitment: Some(CommitmentConfig::confirmed()),
     ///     max_supported_transaction_version: Some(0),
+    ///     decode_instruction_error: None,
     /// };
     /// let transaction = rpc_client.get_transaction_with_config(
     ///     &signature,
@@ -3334,6 +3488,48 @@ impl RpcClient {
             .map_err(|err| err.into_with_request(request))?
     }
 
+    /// Returns the production time of a block, or an estimate of it if the node did not record
+    /// it.
+    ///
+    /// Nodes estimate the block times they lack only if they run with
+    /// `--enable-rpc-block-time-backfill`.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getBlockTime`] RPC method.
+    ///
+    /// [`getBlockTime`]: https://docs.solana.com/developing/clients/jsonrpc-api#getblocktime
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// let block_time = rpc_client.get_block_time_with_estimate(100).await?;
+    /// if block_time.estimated {
+    ///     println!("Slot 100 was produced around {}", block_time.block_time);
+    /// }
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_block_time_with_estimate(
+        &self,
+        slot: Slot,
+    ) -> ClientResult<RpcEstimatedBlockTime> {
+        let request = RpcRequest::GetBlockTime;
+        let config = RpcBlockTimeConfig {
+            allow_estimated: Some(true),
+        };
+        let result: Option<RpcEstimatedBlockTime> =
+            self.send(request, json!([slot, config])).await?;
+        result.ok_or_else(|| RpcError::ForUser(format!("Block Not Found: slot={}", slot)).into())
+    }
+
     /// Returns information about the current epoch.
     ///
     /// This method uses the configured default [commitment level][cl].
@@ -3586,6 +3782,282 @@ impl RpcClient {
             .await
     }
 
+    /// Returns the compute unit prices offered by the transactions the node currently
+    /// holds in its banking stage buffers.
+    ///
+    /// Unlike the fees of recent blocks, these show how congested the queue of the node
+    /// is while it waits to lead, so a transaction can bid enough to be scheduled.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getBufferedPrioritizationFees`] RPC method.
+    ///
+    /// [`getBufferedPrioritizationFees`]: https://docs.solana.com/developing/clients/jsonrpc-api#getbufferedprioritizationfees
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// let fees = rpc_client.get_buffered_prioritization_fees().await?;
+    /// let compute_unit_price = fees.p90_compute_unit_price;
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_buffered_prioritization_fees(
+        &self,
+    ) -> ClientResult<RpcBufferedPrioritizationFees> {
+        self.send(RpcRequest::GetBufferedPrioritizationFees, Value::Null)
+            .await
+    }
+
+    /// Returns the stake delegations exported by the node at the start of the given epoch.
+    ///
+    /// Delegations are grouped by the vote account they are delegated to, along with
+    /// their activation state as of that epoch. Returns `None` if the node has not
+    /// exported a snapshot for the epoch.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getStakeSnapshot`] RPC method.
+    ///
+    /// [`getStakeSnapshot`]: https://docs.solana.com/developing/clients/jsonrpc-api#getstakesnapshot
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// let epoch_info = rpc_client.get_epoch_info().await?;
+    /// let stake_snapshot = rpc_client.get_stake_snapshot(epoch_info.epoch).await?;
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_stake_snapshot(&self, epoch: Epoch) -> ClientResult<Option<RpcStakeSnapshot>> {
+        self.send(RpcRequest::GetStakeSnapshot, json!([epoch]))
+            .await
+    }
+
+    /// Returns the total effective, activating and deactivating stake of each epoch
+    /// recorded in the stake history sysvar, along with the minimum stake delegation
+    /// in force during the epoch, newest epoch first.
+    ///
+    /// This method uses the configured default [commitment level][cl].
+    ///
+    /// [cl]: https://docs.solana.com/developing/clients/jsonrpc-api#configuring-state-commitment
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getStakeMinimumDelegationHistory`] RPC method.
+    ///
+    /// [`getStakeMinimumDelegationHistory`]: https://docs.solana.com/developing/clients/jsonrpc-api#getstakeminimumdelegationhistory
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// let stake_history = rpc_client.get_stake_minimum_delegation_history().await?;
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_stake_minimum_delegation_history(
+        &self,
+    ) -> ClientResult<Vec<RpcEpochStakeHistoryEntry>> {
+        self.send(
+            RpcRequest::GetStakeMinimumDelegationHistory,
+            json!([self.maybe_map_commitment(self.commitment()).await?]),
+        )
+        .await
+    }
+
+    /// Returns the stake activated for each vote account in the given epoch, or in the
+    /// current epoch if `None`, along with its change since the previous epoch.
+    ///
+    /// Returns `None` if the stakes of the epoch are not available, as the node only
+    /// keeps the stakes of the epochs around the current one.
+    ///
+    /// This method uses the configured default [commitment level][cl].
+    ///
+    /// [cl]: https://docs.solana.com/developing/clients/jsonrpc-api#configuring-state-commitment
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getEpochStakeBreakdown`] RPC method.
+    ///
+    /// [`getEpochStakeBreakdown`]: https://docs.solana.com/developing/clients/jsonrpc-api#getepochstakebreakdown
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// let breakdown = rpc_client.get_epoch_stake_breakdown(None).await?;
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_epoch_stake_breakdown(
+        &self,
+        epoch: Option<Epoch>,
+    ) -> ClientResult<Option<RpcEpochStakeBreakdown>> {
+        let config = RpcEpochConfig {
+            epoch,
+            commitment: Some(self.maybe_map_commitment(self.commitment()).await?),
+            min_context_slot: None,
+        };
+        self.send(RpcRequest::GetEpochStakeBreakdown, json!([config]))
+            .await
+    }
+
+    /// Returns the distribution of the latencies of the votes of the given vote accounts, or of
+    /// every vote account if `None`, over the recent slots replayed by the node.
+    ///
+    /// The latency of a vote is the number of slots between the slot voted on and the
+    /// slot of the block the vote landed in.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getVoteLatencies`] RPC method.
+    ///
+    /// [`getVoteLatencies`]: https://docs.solana.com/developing/clients/jsonrpc-api#getvotelatencies
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// # };
+    /// # use solana_sdk::pubkey::Pubkey;
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// #     let vote_pubkey = Pubkey::new_unique();
+    /// let vote_latencies = rpc_client.get_vote_latencies(Some(&[vote_pubkey])).await?;
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_vote_latencies(
+        &self,
+        vote_pubkeys: Option<&[Pubkey]>,
+    ) -> ClientResult<RpcVoteLatencies> {
+        let vote_pubkeys = vote_pubkeys.map(|vote_pubkeys| {
+            vote_pubkeys
+                .iter()
+                .map(|vote_pubkey| vote_pubkey.to_string())
+                .collect::<Vec<_>>()
+        });
+        self.send(RpcRequest::GetVoteLatencies, json!([vote_pubkeys]))
+            .await
+    }
+
+    /// Returns estimates of when the given slots will be reached, and of how long
+    /// a transaction sent now takes to reach the configured [commitment level][cl].
+    ///
+    /// [cl]: https://docs.solana.com/developing/clients/jsonrpc-api#configuring-state-commitment
+    ///
+    /// Estimates are derived from the recent average slot time and leader skip
+    /// rate observed by the node.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getSlotTimeEstimates`] RPC method.
+    ///
+    /// [`getSlotTimeEstimates`]: https://docs.solana.com/developing/clients/jsonrpc-api#getslottimeestimates
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// let slot = rpc_client.get_slot().await?;
+    /// let estimates = rpc_client.get_slot_time_estimates(&[slot + 10]).await?;
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_slot_time_estimates(
+        &self,
+        slots: &[Slot],
+    ) -> ClientResult<RpcSlotTimeEstimates> {
+        self.get_slot_time_estimates_with_commitment(slots, self.commitment())
+            .await
+    }
+
+    /// Returns estimates of when the given slots will be reached, and of how long
+    /// a transaction sent now takes to reach the given [commitment level][cl].
+    ///
+    /// [cl]: https://docs.solana.com/developing/clients/jsonrpc-api#configuring-state-commitment
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getSlotTimeEstimates`] RPC method.
+    ///
+    /// [`getSlotTimeEstimates`]: https://docs.solana.com/developing/clients/jsonrpc-api#getslottimeestimates
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// # };
+    /// # use solana_sdk::commitment_config::CommitmentConfig;
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// let commitment_config = CommitmentConfig::finalized();
+    /// let estimates = rpc_client.get_slot_time_estimates_with_commitment(
+    ///     &[100, 200],
+    ///     commitment_config,
+    /// ).await?;
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_slot_time_estimates_with_commitment(
+        &self,
+        slots: &[Slot],
+        commitment_config: CommitmentConfig,
+    ) -> ClientResult<RpcSlotTimeEstimates> {
+        Ok(self
+            .send::<Response<RpcSlotTimeEstimates>>(
+                RpcRequest::GetSlotTimeEstimates,
+                json!([
+                    slots,
+                    RpcContextConfig {
+                        commitment: Some(self.maybe_map_commitment(commitment_config).await?),
+                        min_context_slot: None,
+                    }
+                ]),
+            )
+            .await?
+            .value)
+    }
+
     /// Returns the identity pubkey for the current node.
     ///
     /// # RPC Reference
@@ -4514,6 +4986,84 @@ impl RpcClient {
         parse_keyed_accounts(accounts, RpcRequest::GetProgramAccounts)
     }
 
+    /// Returns a page of the accounts owned by the provided program pubkey, in account
+    /// address order, along with the cursor of the next page.
+    ///
+    /// The first page is returned when `config.cursor` is `None`. The returned cursor is
+    /// `None` once the last page is reached. Pages may be served from different slots, so
+    /// accounts created or closed while paging may or may not be returned.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method is built on the [`getProgramAccountsPaginated`] RPC method.
+    ///
+    /// [`getProgramAccountsPaginated`]: https://docs.solana.com/developing/clients/jsonrpc-api#getprogramaccountspaginated
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// #     client_error::ClientError,
+    /// #     rpc_config::RpcProgramAccountsPaginatedConfig,
+    /// # };
+    /// # use solana_sdk::{
+    /// #     signature::Signer,
+    /// #     signer::keypair::Keypair,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// #     let alice = Keypair::new();
+    /// let mut cursor = None;
+    /// loop {
+    ///     let config = RpcProgramAccountsPaginatedConfig {
+    ///         limit: Some(100),
+    ///         cursor,
+    ///         ..RpcProgramAccountsPaginatedConfig::default()
+    ///     };
+    ///     let (accounts, next_cursor) = rpc_client
+    ///         .get_program_accounts_paginated(&alice.pubkey(), config)
+    ///         .await?;
+    ///     cursor = next_cursor;
+    ///     if cursor.is_none() {
+    ///         break;
+    ///     }
+    /// }
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_program_accounts_paginated(
+        &self,
+        pubkey: &Pubkey,
+        config: RpcProgramAccountsPaginatedConfig,
+    ) -> ClientResult<(Vec<(Pubkey, Account)>, Option<String>)> {
+        let commitment = config
+            .account_config
+            .commitment
+            .unwrap_or_else(|| self.commitment());
+        let commitment = self.maybe_map_commitment(commitment).await?;
+        let account_config = RpcAccountInfoConfig {
+            commitment: Some(commitment),
+            ..config.account_config
+        };
+        let config = RpcProgramAccountsPaginatedConfig {
+            account_config,
+            ..config
+        };
+        let Response {
+            value: RpcKeyedAccountsPage { accounts, cursor },
+            ..
+        } = self
+            .send(
+                RpcRequest::GetProgramAccountsPaginated,
+                json!([pubkey.to_string(), config]),
+            )
+            .await?;
+        let accounts = parse_keyed_accounts(accounts, RpcRequest::GetProgramAccountsPaginated)?;
+        Ok((accounts, cursor))
+    }
+
     /// Returns the stake minimum delegation, in lamports.
     pub async fn get_stake_minimum_delegation(&self) -> ClientResult<u64> {
         let instruction = solana_sdk::stake::instruction::get_minimum_delegation();
@@ -4812,6 +5362,38 @@ impl RpcClient {
             .map(|_| ())
     }
 
+    /// Returns the readiness of each subsystem of the node: replay, snapshots, accounts hash
+    /// verification, gossip and voting.
+    ///
+    /// Unlike [`get_health`](RpcClient::get_health), this succeeds whatever the state of the
+    /// node is.
+    ///
+    /// # RPC Reference
+    ///
+    /// This method corresponds directly to the [`getHealthDetailed`] RPC method.
+    ///
+    /// [`getHealthDetailed`]: https://docs.solana.com/developing/clients/jsonrpc-api#gethealthdetailed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use solana_client::{
+    /// #     client_error::ClientError,
+    /// #     nonblocking::rpc_client::RpcClient,
+    /// #     rpc_response::RpcHealthState,
+    /// # };
+    /// # futures::executor::block_on(async {
+    /// #     let rpc_client = RpcClient::new_mock("succeeds".to_string());
+    /// let health = rpc_client.get_health_detailed().await?;
+    /// assert_eq!(health.status, RpcHealthState::Ok);
+    /// #     Ok::<(), ClientError>(())
+    /// # })?;
+    /// # Ok::<(), ClientError>(())
+    /// ```
+    pub async fn get_health_detailed(&self) -> ClientResult<RpcHealthDetailed> {
+        self.send(RpcRequest::GetHealthDetailed, Value::Null).await
+    }
+
     pub async fn get_token_account(&self, pubkey: &Pubkey) -> ClientResult<Option<UiTokenAccount>> {
         Ok(self
             .get_token_account_with_commitment(pubkey, self.commitment())
@@ -4983,6 +5565,90 @@ impl RpcClient {
         .await
     }
 
+    /// Returns a page of the token accounts of `owner`, in account address order, and the cursor
+    /// to pass in `config` to fetch the next page.
+    ///
+    /// The cursor is `None` once the last page is reached. Pages may be served from different
+    /// slots, so accounts created or closed while paging may or may not be returned.
+    pub async fn get_token_accounts_by_owner_paginated(
+        &self,
+        owner: &Pubkey,
+        token_account_filter: TokenAccountsFilter,
+        config: RpcTokenAccountsConfig,
+    ) -> ClientResult<(Vec<RpcKeyedAccount>, Option<String>)> {
+        let token_account_filter = match token_account_filter {
+            TokenAccountsFilter::Mint(mint) => RpcTokenAccountsFilter::Mint(mint.to_string()),
+            TokenAccountsFilter::ProgramId(program_id) => {
+                RpcTokenAccountsFilter::ProgramId(program_id.to_string())
+            }
+        };
+        let commitment = config
+            .account_config
+            .commitment
+            .unwrap_or_else(|| self.commitment());
+        let commitment = self.maybe_map_commitment(commitment).await?;
+        let config = RpcTokenAccountsConfig {
+            account_config: RpcAccountInfoConfig {
+                commitment: Some(commitment),
+                ..config.account_config
+            },
+            page_config: RpcTokenAccountsPageConfig {
+                limit: Some(
+                    config
+                        .page_config
+                        .limit
+                        .unwrap_or(MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT),
+                ),
+                count_only: None,
+                ..config.page_config
+            },
+        };
+        let Response {
+            value: RpcKeyedAccountsPage { accounts, cursor },
+            ..
+        } = self
+            .send(
+                RpcRequest::GetTokenAccountsByOwner,
+                json!([owner.to_string(), token_account_filter, config]),
+            )
+            .await?;
+        Ok((accounts, cursor))
+    }
+
+    /// Returns the number of token accounts of `owner`.
+    pub async fn get_token_accounts_by_owner_count(
+        &self,
+        owner: &Pubkey,
+        token_account_filter: TokenAccountsFilter,
+    ) -> ClientResult<usize> {
+        let token_account_filter = match token_account_filter {
+            TokenAccountsFilter::Mint(mint) => RpcTokenAccountsFilter::Mint(mint.to_string()),
+            TokenAccountsFilter::ProgramId(program_id) => {
+                RpcTokenAccountsFilter::ProgramId(program_id.to_string())
+            }
+        };
+        let config = RpcTokenAccountsConfig {
+            account_config: RpcAccountInfoConfig {
+                commitment: Some(self.maybe_map_commitment(self.commitment()).await?),
+                ..RpcAccountInfoConfig::default()
+            },
+            page_config: RpcTokenAccountsPageConfig {
+                count_only: Some(true),
+                ..RpcTokenAccountsPageConfig::default()
+            },
+        };
+        let Response {
+            value: RpcAccountsCount { count },
+            ..
+        } = self
+            .send(
+                RpcRequest::GetTokenAccountsByOwner,
+                json!([owner.to_string(), token_account_filter, config]),
+            )
+            .await?;
+        Ok(count)
+    }
+
     pub async fn get_token_supply(&self, mint: &Pubkey) -> ClientResult<UiTokenAmount> {
         Ok(self
             .get_token_supply_with_commitment(mint, self.commitment())
@@ -2,7 +2,7 @@ use {
     crate::rpc_filter::RpcFilterType,
     solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig},
     solana_sdk::{
-        clock::{Epoch, Slot},
+        clock::{Epoch, Slot, UnixTimestamp},
         commitment_config::{CommitmentConfig, CommitmentLevel},
     },
     solana_transaction_status::{TransactionDetails, UiTransactionEncoding},
@@ -23,6 +23,19 @@ pub struct RpcSendTransactionConfig {
     pub encoding: Option<UiTransactionEncoding>,
     pub max_retries: Option<usize>,
     pub min_context_slot: Option<Slot>,
+    /// Bank to run the preflight checks against, in place of `preflight_commitment`
+    pub simulation_bank: Option<RpcSimulationBank>,
+}
+
+/// Bank that a transaction is simulated against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcSimulationBank {
+    Processed,
+    Confirmed,
+    Finalized,
+    /// The frozen bank of the given slot
+    Slot(Slot),
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -30,6 +43,9 @@ pub struct RpcSendTransactionConfig {
 pub struct RpcSimulateTransactionAccountsConfig {
     pub encoding: Option<UiAccountEncoding>,
     pub addresses: Vec<String>,
+    /// Return the changes made to the accounts rather than their full state
+    #[serde(default)]
+    pub diff: bool,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -44,6 +60,14 @@ pub struct RpcSimulateTransactionConfig {
     pub encoding: Option<UiTransactionEncoding>,
     pub accounts: Option<RpcSimulateTransactionAccountsConfig>,
     pub min_context_slot: Option<Slot>,
+    /// Bank to simulate the transaction against, in place of `commitment`
+    pub simulation_bank: Option<RpcSimulationBank>,
+    /// Report the writable accounts changed by the transaction, with their state before and
+    /// after it
+    #[serde(default)]
+    pub return_account_diffs: bool,
+    /// Slice of the account data included in the reported account changes
+    pub account_diffs_data_slice: Option<UiDataSliceConfig>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -147,6 +171,14 @@ pub struct RpcAccountInfoConfig {
     pub min_context_slot: Option<Slot>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountSubscribeConfig {
+    pub filters: Option<Vec<RpcFilterType>>,
+    #[serde(flatten)]
+    pub account_config: RpcAccountInfoConfig,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcProgramAccountsConfig {
@@ -156,19 +188,64 @@ pub struct RpcProgramAccountsConfig {
     pub with_context: Option<bool>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcProgramAccountsPaginatedConfig {
+    pub filters: Option<Vec<RpcFilterType>>,
+    #[serde(flatten)]
+    pub account_config: RpcAccountInfoConfig,
+    /// Maximum number of accounts in the page
+    pub limit: Option<usize>,
+    /// Continuation token of the previous page; the first page is returned if `None`
+    pub cursor: Option<String>,
+}
+
+/// Pagination options of `getTokenAccountsByOwner` and `getTokenLargestAccounts`. Without any of
+/// them, every matching account is returned at once.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTokenAccountsPageConfig {
+    /// Maximum number of accounts in the page
+    pub limit: Option<usize>,
+    /// Continuation token of the previous page; the first page is returned if `None`
+    pub cursor: Option<String>,
+    /// Only return the number of matching accounts
+    pub count_only: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTokenAccountsConfig {
+    #[serde(flatten)]
+    pub account_config: RpcAccountInfoConfig,
+    #[serde(flatten)]
+    pub page_config: RpcTokenAccountsPageConfig,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTokenLargestAccountsConfig {
+    #[serde(flatten)]
+    pub commitment: Option<CommitmentConfig>,
+    #[serde(flatten)]
+    pub page_config: RpcTokenAccountsPageConfig,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum RpcTransactionLogsFilter {
     All,
     AllWithVotes,
-    Mentions(Vec<String>), // base58-encoded list of addresses
+    Mentions(Vec<String>), // base58-encoded list of addresses, any of which must be mentioned
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcTransactionLogsConfig {
     #[serde(flatten)]
     pub commitment: Option<CommitmentConfig>,
+    /// Only notify transactions that output a log message starting with this prefix
+    pub log_prefix: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -186,11 +263,26 @@ pub struct RpcSignatureSubscribeConfig {
     pub enable_received_notification: Option<bool>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcPrioritizationFeeSubscribeConfig {
+    pub accounts: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSubscribeResumeConfig {
+    /// Replay the notifications for slots from this one onwards, before live notifications
+    pub resume_from: Option<Slot>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum RpcBlockSubscribeFilter {
     All,
     MentionsAccountOrProgram(String),
+    /// Transactions with an instruction, or inner instruction, that invokes this program
+    MentionsProgram(String),
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -202,6 +294,8 @@ pub struct RpcBlockSubscribeConfig {
     pub transaction_details: Option<TransactionDetails>,
     pub show_rewards: Option<bool>,
     pub max_supported_transaction_version: Option<u8>,
+    /// Replay the notifications for blocks from this slot onwards, before live notifications
+    pub resume_from: Option<Slot>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -213,6 +307,10 @@ pub struct RpcSignaturesForAddressConfig {
     #[serde(flatten)]
     pub commitment: Option<CommitmentConfig>,
     pub min_context_slot: Option<Slot>,
+    /// Only return signatures of transactions in blocks produced before this time
+    pub before_time: Option<UnixTimestamp>,
+    /// Only return signatures of transactions in blocks produced at or after this time
+    pub after_time: Option<UnixTimestamp>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -296,6 +394,9 @@ pub struct RpcTransactionConfig {
     #[serde(flatten)]
     pub commitment: Option<CommitmentConfig>,
     pub max_supported_transaction_version: Option<u8>,
+    /// Add the failing program and a description of the error to the metadata of transactions
+    /// that failed in an instruction
+    pub decode_instruction_error: Option<bool>,
 }
 
 impl EncodingConfig for RpcTransactionConfig {
@@ -323,6 +424,15 @@ impl RpcBlocksConfigWrapper {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlocksWithLimitConfig {
+    #[serde(flatten)]
+    pub commitment: Option<CommitmentConfig>,
+    /// List blocks at or below the start slot, most recent first
+    pub descending: Option<bool>,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcContextConfig {
@@ -330,3 +440,11 @@ pub struct RpcContextConfig {
     pub commitment: Option<CommitmentConfig>,
     pub min_context_slot: Option<Slot>,
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBlockTimeConfig {
+    /// Return an estimate when the block time was not recorded, flagging whether the result is
+    /// estimated
+    pub allow_estimated: Option<bool>,
+}
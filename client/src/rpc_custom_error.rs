@@ -23,6 +23,10 @@ pub const JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_LEN_MISMATCH: i64 = -32013
 pub const JSON_RPC_SERVER_ERROR_BLOCK_STATUS_NOT_AVAILABLE_YET: i64 = -32014;
 pub const JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION: i64 = -32015;
 pub const JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED: i64 = -32016;
+pub const JSON_RPC_SERVER_ERROR_STAKE_SNAPSHOT_NOT_AVAILABLE: i64 = -32017;
+pub const JSON_RPC_SERVER_ERROR_VOTE_LATENCIES_NOT_AVAILABLE: i64 = -32018;
+pub const JSON_RPC_SERVER_ERROR_SIMULATION_BANK_NOT_AVAILABLE: i64 = -32019;
+pub const JSON_RPC_SERVER_ERROR_METHOD_NOT_AUTHORIZED: i64 = -32020;
 
 #[derive(Error, Debug)]
 pub enum RpcCustomError {
@@ -64,6 +68,14 @@ pub enum RpcCustomError {
     UnsupportedTransactionVersion(u8),
     #[error("MinContextSlotNotReached")]
     MinContextSlotNotReached { context_slot: Slot },
+    #[error("StakeSnapshotNotAvailable")]
+    StakeSnapshotNotAvailable,
+    #[error("VoteLatenciesNotAvailable")]
+    VoteLatenciesNotAvailable,
+    #[error("SimulationBankNotAvailable")]
+    SimulationBankNotAvailable { slot: Slot },
+    #[error("MethodNotAuthorized")]
+    MethodNotAuthorized { method: String },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -204,6 +216,26 @@ impl From<RpcCustomError> for Error {
                     context_slot,
                 })),
             },
+            RpcCustomError::StakeSnapshotNotAvailable => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_STAKE_SNAPSHOT_NOT_AVAILABLE),
+                message: "Stake snapshots are not available from this node".to_string(),
+                data: None,
+            },
+            RpcCustomError::VoteLatenciesNotAvailable => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_VOTE_LATENCIES_NOT_AVAILABLE),
+                message: "Vote latencies are not tracked by this node".to_string(),
+                data: None,
+            },
+            RpcCustomError::SimulationBankNotAvailable { slot } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_SIMULATION_BANK_NOT_AVAILABLE),
+                message: format!("Bank of slot {} is not available for simulation", slot),
+                data: None,
+            },
+            RpcCustomError::MethodNotAuthorized { method } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_METHOD_NOT_AUTHORIZED),
+                message: format!("Not authorized to call {}", method),
+                data: None,
+            },
         }
     }
 }
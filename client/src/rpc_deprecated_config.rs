@@ -100,6 +100,7 @@ impl From<RpcConfirmedTransactionConfig> for RpcTransactionConfig {
             encoding: config.encoding,
             commitment: config.commitment,
             max_supported_transaction_version: None,
+            decode_instruction_error: None,
         }
     }
 }
@@ -14,6 +14,7 @@ const MAX_DATA_BASE64_SIZE: usize = 172;
 #[serde(rename_all = "camelCase")]
 pub enum RpcFilterType {
     DataSize(u64),
+    DataSizeRange(DataSizeRange),
     Memcmp(Memcmp),
     TokenAccountState,
 }
@@ -22,6 +23,10 @@ impl RpcFilterType {
     pub fn verify(&self) -> Result<(), RpcFilterError> {
         match self {
             RpcFilterType::DataSize(_) => Ok(()),
+            RpcFilterType::DataSizeRange(range) => match (range.min, range.max) {
+                (Some(min), Some(max)) if min > max => Err(RpcFilterError::InvalidDataSizeRange),
+                _ => Ok(()),
+            },
             RpcFilterType::Memcmp(compare) => {
                 let encoding = compare.encoding.as_ref().unwrap_or(&MemcmpEncoding::Binary);
                 match encoding {
@@ -81,6 +86,7 @@ impl RpcFilterType {
     pub fn allows(&self, account: &AccountSharedData) -> bool {
         match self {
             RpcFilterType::DataSize(size) => account.data().len() as u64 == *size,
+            RpcFilterType::DataSizeRange(range) => range.contains(account.data().len() as u64),
             RpcFilterType::Memcmp(compare) => compare.bytes_match(account.data()),
             RpcFilterType::TokenAccountState => Account::valid_account_data(account.data()),
         }
@@ -91,6 +97,8 @@ impl RpcFilterType {
 pub enum RpcFilterError {
     #[error("encoded binary data should be less than 129 bytes")]
     DataTooLarge,
+    #[error("data size range minimum should not exceed its maximum")]
+    InvalidDataSizeRange,
     #[deprecated(
         since = "1.8.1",
         note = "Error for MemcmpEncodedBytes::Binary which is deprecated"
@@ -109,6 +117,22 @@ pub enum RpcFilterError {
     Base64DecodeError(#[from] base64::DecodeError),
 }
 
+/// Range of account data sizes, bounds included
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DataSizeRange {
+    /// Smallest size allowed, or no lower bound
+    pub min: Option<u64>,
+    /// Largest size allowed, or no upper bound
+    pub max: Option<u64>,
+}
+
+impl DataSizeRange {
+    pub fn contains(&self, size: u64) -> bool {
+        self.min.map(|min| size >= min).unwrap_or(true)
+            && self.max.map(|max| size <= max).unwrap_or(true)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum MemcmpEncoding {
@@ -269,4 +293,33 @@ mod tests {
             Err(RpcFilterError::DataTooLarge)
         );
     }
+
+    #[test]
+    fn test_data_size_range() {
+        let account = AccountSharedData::new(1, 10, &solana_sdk::pubkey::new_rand());
+        let filter = |min, max| RpcFilterType::DataSizeRange(DataSizeRange { min, max });
+
+        assert!(filter(None, None).allows(&account));
+        assert!(filter(Some(10), Some(10)).allows(&account));
+        assert!(filter(Some(5), None).allows(&account));
+        assert!(filter(None, Some(20)).allows(&account));
+        assert!(!filter(Some(11), None).allows(&account));
+        assert!(!filter(None, Some(9)).allows(&account));
+
+        assert_eq!(filter(Some(10), Some(10)).verify(), Ok(()));
+        assert_eq!(
+            filter(Some(11), Some(10)).verify(),
+            Err(RpcFilterError::InvalidDataSizeRange)
+        );
+
+        assert_eq!(
+            serde_json::from_str::<RpcFilterType>(r#"{"dataSizeRange":{"min":10,"max":20}}"#)
+                .unwrap(),
+            filter(Some(10), Some(20))
+        );
+        assert_eq!(
+            serde_json::from_str::<RpcFilterType>(r#"{"dataSizeRange":{"max":20}}"#).unwrap(),
+            filter(None, Some(20))
+        );
+    }
 }
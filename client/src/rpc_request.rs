@@ -20,6 +20,7 @@ pub enum RpcRequest {
     GetBlocks,
     GetBlocksWithLimit,
     GetBlockTime,
+    GetBufferedPrioritizationFees,
     GetClusterNodes,
     #[deprecated(since = "1.7.0", note = "Please use RpcRequest::GetBlock instead")]
     GetConfirmedBlock,
@@ -42,6 +43,7 @@ pub enum RpcRequest {
     GetConfirmedTransaction,
     GetEpochInfo,
     GetEpochSchedule,
+    GetEpochStakeBreakdown,
     #[deprecated(
         since = "1.9.0",
         note = "Please use RpcRequest::GetFeeForMessage instead"
@@ -61,6 +63,7 @@ pub enum RpcRequest {
     GetFirstAvailableBlock,
     GetGenesisHash,
     GetHealth,
+    GetHealthDetailed,
     GetIdentity,
     GetInflationGovernor,
     GetInflationRate,
@@ -73,6 +76,7 @@ pub enum RpcRequest {
     GetMinimumBalanceForRentExemption,
     GetMultipleAccounts,
     GetProgramAccounts,
+    GetProgramAccountsPaginated,
     #[deprecated(
         since = "1.9.0",
         note = "Please use RpcRequest::GetLatestBlockhash instead"
@@ -93,7 +97,10 @@ pub enum RpcRequest {
     GetStorageTurn,
     GetStorageTurnRate,
     GetSlotsPerSegment,
+    GetSlotTimeEstimates,
     GetStakeActivation,
+    GetStakeMinimumDelegationHistory,
+    GetStakeSnapshot,
     GetStoragePubkeysForSlot,
     GetSupply,
     GetTokenAccountBalance,
@@ -104,11 +111,13 @@ pub enum RpcRequest {
     GetTransactionCount,
     GetVersion,
     GetVoteAccounts,
+    GetVoteLatencies,
     IsBlockhashValid,
     MinimumLedgerSlot,
     RegisterNode,
     RequestAirdrop,
     SendTransaction,
+    SimulateBundle,
     SimulateTransaction,
     SignVote,
 }
@@ -127,6 +136,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetBlocks => "getBlocks",
             RpcRequest::GetBlocksWithLimit => "getBlocksWithLimit",
             RpcRequest::GetBlockTime => "getBlockTime",
+            RpcRequest::GetBufferedPrioritizationFees => "getBufferedPrioritizationFees",
             RpcRequest::GetClusterNodes => "getClusterNodes",
             RpcRequest::GetConfirmedBlock => "getConfirmedBlock",
             RpcRequest::GetConfirmedBlocks => "getConfirmedBlocks",
@@ -135,6 +145,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetConfirmedTransaction => "getConfirmedTransaction",
             RpcRequest::GetEpochInfo => "getEpochInfo",
             RpcRequest::GetEpochSchedule => "getEpochSchedule",
+            RpcRequest::GetEpochStakeBreakdown => "getEpochStakeBreakdown",
             RpcRequest::GetFeeCalculatorForBlockhash => "getFeeCalculatorForBlockhash",
             RpcRequest::GetFeeForMessage => "getFeeForMessage",
             RpcRequest::GetFeeRateGovernor => "getFeeRateGovernor",
@@ -142,6 +153,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetFirstAvailableBlock => "getFirstAvailableBlock",
             RpcRequest::GetGenesisHash => "getGenesisHash",
             RpcRequest::GetHealth => "getHealth",
+            RpcRequest::GetHealthDetailed => "getHealthDetailed",
             RpcRequest::GetIdentity => "getIdentity",
             RpcRequest::GetInflationGovernor => "getInflationGovernor",
             RpcRequest::GetInflationRate => "getInflationRate",
@@ -154,6 +166,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetMinimumBalanceForRentExemption => "getMinimumBalanceForRentExemption",
             RpcRequest::GetMultipleAccounts => "getMultipleAccounts",
             RpcRequest::GetProgramAccounts => "getProgramAccounts",
+            RpcRequest::GetProgramAccountsPaginated => "getProgramAccountsPaginated",
             RpcRequest::GetRecentBlockhash => "getRecentBlockhash",
             RpcRequest::GetRecentPerformanceSamples => "getRecentPerformanceSamples",
             RpcRequest::GetHighestSnapshotSlot => "getHighestSnapshotSlot",
@@ -164,9 +177,12 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetSlotLeader => "getSlotLeader",
             RpcRequest::GetSlotLeaders => "getSlotLeaders",
             RpcRequest::GetStakeActivation => "getStakeActivation",
+            RpcRequest::GetStakeMinimumDelegationHistory => "getStakeMinimumDelegationHistory",
+            RpcRequest::GetStakeSnapshot => "getStakeSnapshot",
             RpcRequest::GetStorageTurn => "getStorageTurn",
             RpcRequest::GetStorageTurnRate => "getStorageTurnRate",
             RpcRequest::GetSlotsPerSegment => "getSlotsPerSegment",
+            RpcRequest::GetSlotTimeEstimates => "getSlotTimeEstimates",
             RpcRequest::GetStoragePubkeysForSlot => "getStoragePubkeysForSlot",
             RpcRequest::GetSupply => "getSupply",
             RpcRequest::GetTokenAccountBalance => "getTokenAccountBalance",
@@ -177,11 +193,13 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetTransactionCount => "getTransactionCount",
             RpcRequest::GetVersion => "getVersion",
             RpcRequest::GetVoteAccounts => "getVoteAccounts",
+            RpcRequest::GetVoteLatencies => "getVoteLatencies",
             RpcRequest::IsBlockhashValid => "isBlockhashValid",
             RpcRequest::MinimumLedgerSlot => "minimumLedgerSlot",
             RpcRequest::RegisterNode => "registerNode",
             RpcRequest::RequestAirdrop => "requestAirdrop",
             RpcRequest::SendTransaction => "sendTransaction",
+            RpcRequest::SimulateBundle => "simulateBundle",
             RpcRequest::SimulateTransaction => "simulateTransaction",
             RpcRequest::SignVote => "signVote",
         };
@@ -198,7 +216,14 @@ pub const MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT: usize = 1_000;
 pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
 pub const NUM_LARGEST_ACCOUNTS: usize = 20;
 pub const MAX_GET_PROGRAM_ACCOUNT_FILTERS: usize = 4;
+pub const MAX_GET_PROGRAM_ACCOUNTS_PAGE_LIMIT: usize = 1_000;
 pub const MAX_GET_SLOT_LEADERS: usize = 5000;
+pub const MAX_GET_SLOT_TIME_ESTIMATES_SLOTS: usize = 256;
+pub const MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT: usize = 1_000;
+pub const MAX_SIMULATE_BUNDLE_TRANSACTIONS: usize = 16;
+pub const MAX_PRIORITIZATION_FEE_SUBSCRIBE_ACCOUNTS: usize = 128;
+pub const MAX_LOGS_SUBSCRIBE_MENTIONS: usize = 128;
+pub const MAX_SUBSCRIPTION_REPLAY_SLOTS: u64 = 150;
 
 // Validators that are this number of slots behind are considered delinquent
 pub const DELINQUENT_VALIDATOR_SLOT_DISTANCE: u64 = 128;
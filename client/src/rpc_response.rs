@@ -175,6 +175,15 @@ pub struct RpcKeyedAccount {
     pub account: UiAccount,
 }
 
+/// A page of the accounts of a program, in account address order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcKeyedAccountsPage {
+    pub accounts: Vec<RpcKeyedAccount>,
+    /// Continuation token to pass to fetch the next page, or `None` if this is the last page
+    pub cursor: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SlotInfo {
     pub slot: Slot,
@@ -398,10 +407,67 @@ pub struct RpcSimulateTransactionResult {
     pub err: Option<TransactionError>,
     pub logs: Option<Vec<String>>,
     pub accounts: Option<Vec<Option<UiAccount>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_diffs: Option<Vec<Option<RpcAccountDiff>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_state_diffs: Option<Vec<RpcAccountStateDiff>>,
     pub units_consumed: Option<u64>,
     pub return_data: Option<RpcTransactionReturnData>,
 }
 
+/// A writable account changed by a simulated transaction
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountStateDiff {
+    pub pubkey: String,
+    /// State of the account before the transaction, `None` if it didn't exist
+    pub pre: Option<UiAccount>,
+    /// State of the account after the transaction, `None` if it was closed
+    pub post: Option<UiAccount>,
+    /// Change of the account balance, in lamports
+    pub lamports_delta: i64,
+}
+
+/// State of an account after a simulated transaction, with its data given as the ranges that
+/// differ from the data before the transaction
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountDiff {
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: Epoch,
+    /// Length of the account data after the transaction
+    pub data_len: usize,
+    /// Changed ranges of the account data, in increasing order of offset. Data beyond the
+    /// end of the previous data is compared against zeroes.
+    pub data_changes: Vec<RpcAccountDataChange>,
+}
+
+impl RpcAccountDiff {
+    /// Reconstructs the account data after the transaction from the data before it. Returns
+    /// `None` if the diff doesn't fit the data.
+    pub fn apply(&self, pre_data: &[u8]) -> Option<Vec<u8>> {
+        let mut data = pre_data.to_vec();
+        data.resize(self.data_len, 0);
+        for data_change in &self.data_changes {
+            let bytes = base64::decode(&data_change.data).ok()?;
+            let end = data_change.offset.checked_add(bytes.len())?;
+            data.get_mut(data_change.offset..end)?
+                .copy_from_slice(&bytes);
+        }
+        Some(data)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountDataChange {
+    pub offset: usize,
+    /// New contents of the range, as base-64 encoded binary data
+    pub data: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcTransactionReturnData {
@@ -464,6 +530,71 @@ pub struct RpcStakeActivation {
     pub inactive: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcStakeSnapshot {
+    pub epoch: Epoch,
+    pub total_activated_stake: u64,
+    pub validators: Vec<RpcValidatorStakeSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcValidatorStakeSnapshot {
+    pub vote_pubkey: String,
+    pub node_pubkey: Option<String>,
+    pub activated_stake: u64,
+    pub delegations: Vec<RpcStakeDelegationSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcStakeDelegationSnapshot {
+    pub stake_pubkey: String,
+    pub stake: u64,
+    pub activation_epoch: Epoch,
+    pub deactivation_epoch: Epoch,
+    pub state: StakeActivationState,
+    pub effective: u64,
+    pub activating: u64,
+    pub deactivating: u64,
+}
+
+/// Stake activation totals of an epoch, from the stake history sysvar
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcEpochStakeHistoryEntry {
+    pub epoch: Epoch,
+    /// Minimum stake delegation in force during the epoch, in lamports
+    pub minimum_delegation: u64,
+    pub effective: u64,
+    pub activating: u64,
+    pub deactivating: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcEpochStakeBreakdown {
+    pub epoch: Epoch,
+    pub total_stake: u64,
+    /// Change of the total stake since the previous epoch, `None` if the stakes of the
+    /// previous epoch are no longer available
+    pub total_stake_delta: Option<i64>,
+    /// Sorted by vote account address
+    pub validators: Vec<RpcValidatorStakeDelta>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcValidatorStakeDelta {
+    pub vote_pubkey: String,
+    pub node_pubkey: Option<String>,
+    pub activated_stake: u64,
+    /// Change of the activated stake since the previous epoch, `None` if the stakes of the
+    /// previous epoch are no longer available
+    pub stake_delta: Option<i64>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcTokenAccountBalance {
@@ -472,6 +603,41 @@ pub struct RpcTokenAccountBalance {
     pub amount: UiTokenAmount,
 }
 
+/// A page of the accounts of a token mint, in decreasing balance order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTokenAccountBalancesPage {
+    pub accounts: Vec<RpcTokenAccountBalance>,
+    /// Continuation token to pass to fetch the next page, or `None` if this is the last page
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountsCount {
+    pub count: usize,
+}
+
+/// Accounts returned by `getTokenAccountsByOwner`, in the shape selected by the pagination
+/// options of the request
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RpcTokenAccounts {
+    Accounts(Vec<RpcKeyedAccount>),
+    Page(RpcKeyedAccountsPage),
+    Count(RpcAccountsCount),
+}
+
+/// Accounts returned by `getTokenLargestAccounts`, in the shape selected by the pagination
+/// options of the request
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum RpcTokenAccountBalances {
+    Balances(Vec<RpcTokenAccountBalance>),
+    Page(RpcTokenAccountBalancesPage),
+    Count(RpcAccountsCount),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcConfirmedTransactionStatusWithSignature {
@@ -492,6 +658,93 @@ pub struct RpcPerfSample {
     pub sample_period_secs: u16,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSlotTimeEstimates {
+    /// Recent average time between slots, in milliseconds
+    pub ms_per_slot: u64,
+    /// Recent fraction of slots whose leader did not produce a rooted block
+    pub skip_rate: f64,
+    /// Estimated milliseconds until a transaction sent now reaches the requested commitment
+    pub confirmation_eta_ms: u64,
+    pub slots: Vec<RpcSlotTimeEstimate>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSlotTimeEstimate {
+    pub slot: Slot,
+    /// Estimated milliseconds until the slot is reached; zero for slots already reached
+    pub eta_ms: u64,
+    /// Estimated Unix timestamp at which the slot is reached
+    pub estimated_time: UnixTimestamp,
+}
+
+/// Compute unit prices, in micro-lamports, offered by the transactions the node holds in its
+/// banking stage buffers, not counting simple votes
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBufferedPrioritizationFees {
+    pub num_packets: usize,
+    pub min_compute_unit_price: u64,
+    pub median_compute_unit_price: u64,
+    pub p90_compute_unit_price: u64,
+}
+
+/// Compute unit prices, in micro-lamports, paid by the transactions that landed in a frozen bank,
+/// not counting simple votes
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcPrioritizationFeeUpdate {
+    pub slot: Slot,
+    pub num_transactions: usize,
+    pub min_compute_unit_price: u64,
+    pub median_compute_unit_price: u64,
+    pub p90_compute_unit_price: u64,
+    pub max_compute_unit_price: u64,
+    /// Prices paid by the transactions write locking each account the subscription asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accounts: Option<Vec<RpcAccountPrioritizationFees>>,
+}
+
+/// Compute unit prices, in micro-lamports, paid by the landed transactions that write lock
+/// `account`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountPrioritizationFees {
+    pub account: String,
+    pub num_transactions: usize,
+    pub min_compute_unit_price: u64,
+    pub median_compute_unit_price: u64,
+    pub p90_compute_unit_price: u64,
+    pub max_compute_unit_price: u64,
+}
+
+/// Latencies of the votes that landed in the replayed blocks between `first_slot` and `last_slot`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcVoteLatencies {
+    pub first_slot: Slot,
+    pub last_slot: Slot,
+    pub validators: Vec<RpcVoteLatency>,
+}
+
+/// Distribution of the number of slots between the slot a validator voted on and the slot its
+/// vote landed in
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcVoteLatency {
+    pub vote_pubkey: String,
+    pub num_votes: u64,
+    pub mean_latency: f64,
+    pub median_latency: u64,
+    pub p90_latency: u64,
+    pub max_latency: u64,
+    /// Number of votes landing 1, 2, ... slots after the slot voted on, the last entry also
+    /// counting every longer latency
+    pub latency_counts: Vec<u64>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcInflationReward {
@@ -544,3 +797,88 @@ pub struct RpcSnapshotSlotInfo {
     pub full: Slot,
     pub incremental: Option<Slot>,
 }
+
+/// Readiness of a node subsystem, ordered from the healthiest to the least healthy
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcHealthState {
+    Ok,
+    Degraded,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHealthDetailed {
+    /// The least healthy state of the subsystems
+    pub status: RpcHealthState,
+    pub replay: RpcReplayHealth,
+    pub snapshot: RpcSnapshotHealth,
+    pub accounts_hash: RpcAccountsHashHealth,
+    pub gossip: RpcGossipHealth,
+    pub voting: RpcVotingHealth,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReplayHealth {
+    pub status: RpcHealthState,
+    pub processed_slot: Slot,
+    pub max_shred_insert_slot: Slot,
+    /// Number of slots between the highest slot received and the processed slot
+    pub slots_behind: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSnapshotHealth {
+    pub status: RpcHealthState,
+    pub full_snapshot_slot: Option<Slot>,
+    pub incremental_snapshot_slot: Option<Slot>,
+    /// Number of slots between the root and the latest snapshot archive, if any
+    pub slots_behind_root: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountsHashHealth {
+    pub status: RpcHealthState,
+    /// Number of slots the latest accounts hash of the node lags behind the latest accounts hash
+    /// of the known validators, if it lags too far behind
+    pub slots_behind_known_validators: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcGossipHealth {
+    pub status: RpcHealthState,
+    pub num_peers: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcVotingHealth {
+    pub status: RpcHealthState,
+    /// Last slot voted on by the vote account of the node, if it has one
+    pub last_vote_slot: Option<Slot>,
+    /// Number of slots between the processed slot and the last vote
+    pub slots_behind: Option<u64>,
+}
+
+/// Block time returned by `getBlockTime`, which is flagged as estimated or not if the request
+/// allows estimates
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RpcBlockTime {
+    Recorded(UnixTimestamp),
+    MaybeEstimated(RpcEstimatedBlockTime),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcEstimatedBlockTime {
+    pub block_time: UnixTimestamp,
+    /// Whether the block time was estimated from the vote timestamps and block times around the
+    /// slot, rather than recorded when the block was replayed
+    pub estimated: bool,
+}
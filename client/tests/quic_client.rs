@@ -7,7 +7,7 @@ mod tests {
             tpu_connection::TpuConnection,
         },
         solana_sdk::{packet::PACKET_DATA_SIZE, quic::QUIC_PORT_OFFSET, signature::Keypair},
-        solana_streamer::quic::spawn_server,
+        solana_streamer::quic::{spawn_server, QuicServerQos},
         std::{
             collections::HashMap,
             net::{SocketAddr, UdpSocket},
@@ -29,15 +29,21 @@ mod tests {
         let ip = "127.0.0.1".parse().unwrap();
         let staked_nodes = Arc::new(RwLock::new(HashMap::new()));
         let t = spawn_server(
+            "quic_client_test",
             s.try_clone().unwrap(),
             &keypair,
             ip,
             sender,
             exit.clone(),
-            1,
             staked_nodes,
-            10,
-            10,
+            QuicServerQos {
+                max_connections_per_ip: 1,
+                max_staked_connections: 10,
+                max_unstaked_connections: 10,
+                max_staked_streams_per_second: 0,
+                max_unstaked_streams_per_second: 0,
+            },
+            None,
         )
         .unwrap();
 
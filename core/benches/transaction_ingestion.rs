@@ -0,0 +1,349 @@
+#![allow(clippy::integer_arithmetic)]
+//! Feeds synthetic packet batches through signature verification, the banking stage packet
+//! buffer and a mock execution stage, reporting the throughput and latency of each stage.
+//!
+//! Run with `cargo bench --bench transaction_ingestion -- [OPTIONS]`
+use {
+    clap::{Arg, ArgEnum, Command},
+    histogram::Histogram,
+    rand::{thread_rng, Rng},
+    rayon::prelude::*,
+    solana_core::unprocessed_packet_batches::{
+        deserialize_packets, PacketAccountLocks, UnprocessedPacketBatches,
+    },
+    solana_measure::measure::Measure,
+    solana_perf::{
+        packet::{to_packet_batches, PacketBatch},
+        sigverify,
+    },
+    solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    },
+    std::{
+        fmt,
+        time::{Duration, Instant},
+    },
+};
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+enum PriorityDistribution {
+    /// All transactions offer the maximum compute unit price.
+    Constant,
+    /// Compute unit prices are spread evenly up to the maximum.
+    Uniform,
+    /// Most transactions offer a low compute unit price, a few bid close to the maximum.
+    Skewed,
+}
+
+impl PriorityDistribution {
+    fn possible_values<'a>() -> impl Iterator<Item = clap::PossibleValue<'a>> {
+        Self::value_variants()
+            .iter()
+            .filter_map(|v| v.to_possible_value())
+    }
+
+    fn sample(&self, rng: &mut impl Rng, max_compute_unit_price: u64) -> u64 {
+        match self {
+            Self::Constant => max_compute_unit_price,
+            Self::Uniform => rng.gen_range(0, max_compute_unit_price + 1),
+            Self::Skewed => {
+                let x: f64 = rng.gen();
+                (x.powi(4) * max_compute_unit_price as f64) as u64
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for PriorityDistribution {
+    type Err = String;
+    fn from_str(input: &str) -> Result<Self, String> {
+        ArgEnum::from_str(input, false)
+    }
+}
+
+/// Per batch timings of one stage of the pipeline
+struct StageStats {
+    name: &'static str,
+    num_packets: usize,
+    elapsed: Duration,
+    batch_us: Histogram,
+}
+
+impl StageStats {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            num_packets: 0,
+            elapsed: Duration::default(),
+            batch_us: Histogram::new(),
+        }
+    }
+
+    fn record(&mut self, num_packets: usize, elapsed: Duration) {
+        self.num_packets += num_packets;
+        self.elapsed += elapsed;
+        let _ = self.batch_us.increment(elapsed.as_micros() as u64);
+    }
+}
+
+impl fmt::Display for StageStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let packets_per_second = if self.elapsed.is_zero() {
+            0.0
+        } else {
+            self.num_packets as f64 / self.elapsed.as_secs_f64()
+        };
+        write!(
+            f,
+            "{:<10} packets: {:>9} elapsed: {:>8}ms throughput: {:>12.0} packets/s \
+             batch latency p50: {:>6}us p99: {:>6}us max: {:>6}us",
+            self.name,
+            self.num_packets,
+            self.elapsed.as_millis(),
+            packets_per_second,
+            self.batch_us.percentile(50.0).unwrap_or(0),
+            self.batch_us.percentile(99.0).unwrap_or(0),
+            self.batch_us.maximum().unwrap_or(0),
+        )
+    }
+}
+
+/// Transfers that bid a compute unit price drawn from `priority_distribution`. A
+/// `conflict_ratio` share of them write to one of `num_hot_accounts` shared accounts.
+fn make_transactions(
+    num_transactions: usize,
+    priority_distribution: PriorityDistribution,
+    max_compute_unit_price: u64,
+    conflict_ratio: f64,
+    num_hot_accounts: usize,
+) -> Vec<Transaction> {
+    let hot_accounts: Vec<_> = (0..num_hot_accounts.max(1))
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    let recent_blockhash = Hash::new_unique();
+    (0..num_transactions)
+        .into_par_iter()
+        .map(|_| {
+            let mut rng = thread_rng();
+            let payer = Keypair::new();
+            let to = if rng.gen_bool(conflict_ratio) {
+                hot_accounts[rng.gen_range(0, hot_accounts.len())]
+            } else {
+                Pubkey::new_unique()
+            };
+            let compute_unit_price = priority_distribution.sample(&mut rng, max_compute_unit_price);
+            Transaction::new_signed_with_payer(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                    system_instruction::transfer(&payer.pubkey(), &to, 1),
+                ],
+                Some(&payer.pubkey()),
+                &[&payer],
+                recent_blockhash,
+            )
+        })
+        .collect()
+}
+
+/// Stands in for transaction execution by spinning for `cost_per_transaction` per packet
+fn mock_execute(num_packets: usize, cost_per_transaction: Duration) {
+    let start = Instant::now();
+    let cost = cost_per_transaction * num_packets as u32;
+    while start.elapsed() < cost {
+        std::hint::spin_loop();
+    }
+}
+
+/// Pops a batch of non-conflicting packets from the buffer and executes it. Returns the number
+/// of packets executed.
+fn execute_batch(
+    buffered_packets: &mut UnprocessedPacketBatches,
+    execution_batch_size: usize,
+    cost_per_transaction: Duration,
+    execute_stats: &mut StageStats,
+) -> usize {
+    let mut execute_time = Measure::start("execute");
+    let packets = buffered_packets
+        .pop_max_n_nonconflicting(execution_batch_size, &PacketAccountLocks::default())
+        .unwrap_or_default();
+    mock_execute(packets.len(), cost_per_transaction);
+    execute_time.stop();
+    execute_stats.record(packets.len(), Duration::from_micros(execute_time.as_us()));
+    packets.len()
+}
+
+fn main() {
+    solana_logger::setup();
+
+    let matches = Command::new("transaction_ingestion")
+        .about("Benchmark of the transaction ingestion pipeline")
+        .arg(
+            Arg::new("num_batches")
+                .long("num-batches")
+                .takes_value(true)
+                .value_name("COUNT")
+                .default_value("1000")
+                .help("Number of packet batches to ingest"),
+        )
+        .arg(
+            Arg::new("packets_per_batch")
+                .long("packets-per-batch")
+                .takes_value(true)
+                .value_name("SIZE")
+                .default_value("128")
+                .help("Packets per batch"),
+        )
+        .arg(
+            Arg::new("priority_distribution")
+                .long("priority-distribution")
+                .takes_value(true)
+                .possible_values(PriorityDistribution::possible_values())
+                .default_value("uniform")
+                .help("Distribution of the compute unit prices bid by transactions"),
+        )
+        .arg(
+            Arg::new("max_compute_unit_price")
+                .long("max-compute-unit-price")
+                .takes_value(true)
+                .value_name("MICRO_LAMPORTS")
+                .default_value("10000")
+                .help("Highest compute unit price bid by a transaction"),
+        )
+        .arg(
+            Arg::new("conflict_ratio")
+                .long("conflict-ratio")
+                .takes_value(true)
+                .value_name("RATIO")
+                .default_value("0")
+                .help("Share of transactions, between 0 and 1, that write to a hot account"),
+        )
+        .arg(
+            Arg::new("num_hot_accounts")
+                .long("num-hot-accounts")
+                .takes_value(true)
+                .value_name("COUNT")
+                .default_value("8")
+                .help("Number of accounts shared by conflicting transactions"),
+        )
+        .arg(
+            Arg::new("buffer_capacity")
+                .long("buffer-capacity")
+                .takes_value(true)
+                .value_name("PACKETS")
+                .default_value("100000")
+                .help("Capacity of the packet buffer"),
+        )
+        .arg(
+            Arg::new("execution_batch_size")
+                .long("execution-batch-size")
+                .takes_value(true)
+                .value_name("SIZE")
+                .default_value("64")
+                .help("Maximum number of transactions executed together"),
+        )
+        .arg(
+            Arg::new("execution_us_per_tx")
+                .long("execution-us-per-tx")
+                .takes_value(true)
+                .value_name("MICROSECONDS")
+                .default_value("1")
+                .help("Time the mock execution stage spends on each transaction"),
+        )
+        // passed by `cargo bench`
+        .arg(Arg::new("bench").long("bench").hide(true))
+        .get_matches();
+
+    let num_batches = matches.value_of_t_or_exit::<usize>("num_batches");
+    let packets_per_batch = matches.value_of_t_or_exit::<usize>("packets_per_batch");
+    let priority_distribution =
+        matches.value_of_t_or_exit::<PriorityDistribution>("priority_distribution");
+    let max_compute_unit_price = matches.value_of_t_or_exit::<u64>("max_compute_unit_price");
+    let conflict_ratio = matches.value_of_t_or_exit::<f64>("conflict_ratio");
+    let num_hot_accounts = matches.value_of_t_or_exit::<usize>("num_hot_accounts");
+    let buffer_capacity = matches.value_of_t_or_exit::<usize>("buffer_capacity");
+    let execution_batch_size = matches.value_of_t_or_exit::<usize>("execution_batch_size");
+    let cost_per_transaction =
+        Duration::from_micros(matches.value_of_t_or_exit::<u64>("execution_us_per_tx"));
+    assert!(
+        (0.0..=1.0).contains(&conflict_ratio),
+        "--conflict-ratio must be between 0 and 1"
+    );
+
+    let transactions = make_transactions(
+        num_batches * packets_per_batch,
+        priority_distribution,
+        max_compute_unit_price,
+        conflict_ratio,
+        num_hot_accounts,
+    );
+    let packet_batches: Vec<PacketBatch> = to_packet_batches(&transactions, packets_per_batch);
+    drop(transactions);
+
+    let mut sigverify_stats = StageStats::new("sigverify");
+    let mut buffer_stats = StageStats::new("buffer");
+    let mut execute_stats = StageStats::new("execute");
+    let mut buffered_packets = UnprocessedPacketBatches::with_capacity(buffer_capacity);
+    let mut num_dropped = 0;
+    let mut num_executed = 0;
+
+    let mut total_time = Measure::start("transaction_ingestion");
+    for packet_batch in packet_batches {
+        let mut packet_batches = vec![packet_batch];
+        let num_packets = packet_batches[0].len();
+        let mut sigverify_time = Measure::start("sigverify");
+        sigverify::ed25519_verify_cpu(&mut packet_batches, false, num_packets);
+        sigverify_time.stop();
+        sigverify_stats.record(num_packets, Duration::from_micros(sigverify_time.as_us()));
+
+        let packet_batch = &packet_batches[0];
+        let packet_indexes: Vec<_> = packet_batch
+            .iter()
+            .enumerate()
+            .filter(|(_, packet)| !packet.meta.discard())
+            .map(|(index, _)| index)
+            .collect();
+        let mut buffer_time = Measure::start("buffer");
+        num_dropped +=
+            buffered_packets.insert_batch(deserialize_packets(packet_batch, &packet_indexes));
+        buffer_time.stop();
+        buffer_stats.record(
+            packet_indexes.len(),
+            Duration::from_micros(buffer_time.as_us()),
+        );
+
+        // keep up with ingestion, like a banking thread that is leader
+        while buffered_packets.len() >= execution_batch_size {
+            num_executed += execute_batch(
+                &mut buffered_packets,
+                execution_batch_size,
+                cost_per_transaction,
+                &mut execute_stats,
+            );
+        }
+    }
+    while !buffered_packets.is_empty() {
+        num_executed += execute_batch(
+            &mut buffered_packets,
+            execution_batch_size,
+            cost_per_transaction,
+            &mut execute_stats,
+        );
+    }
+    total_time.stop();
+
+    println!("{}", sigverify_stats);
+    println!("{}", buffer_stats);
+    println!("{}", execute_stats);
+    println!(
+        "total      executed: {} dropped: {} elapsed: {}ms throughput: {:.0} packets/s",
+        num_executed,
+        num_dropped,
+        total_time.as_ms(),
+        num_executed as f64 / total_time.as_s() as f64,
+    );
+}
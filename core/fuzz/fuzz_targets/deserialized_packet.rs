@@ -0,0 +1,15 @@
+//! Feeds raw bytes, as received from the network, through `DeserializedPacket::new`
+#![no_main]
+use {
+    libfuzzer_sys::fuzz_target,
+    solana_core::unprocessed_packet_batches::check_packet_deserialization,
+    solana_perf::packet::{Packet, PACKET_DATA_SIZE},
+};
+
+fuzz_target!(|data: &[u8]| {
+    let size = data.len().min(PACKET_DATA_SIZE);
+    let mut packet = Packet::default();
+    packet.buffer_mut()[..size].copy_from_slice(&data[..size]);
+    packet.meta.size = size;
+    check_packet_deserialization(packet);
+});
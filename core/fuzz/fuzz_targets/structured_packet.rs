@@ -0,0 +1,118 @@
+//! Builds well-formed transactions carrying arbitrary compute budget instructions, optionally
+//! corrupting a few bytes, so the fuzzer reaches `get_priority` far more often than it would
+//! from raw bytes alone.
+//!
+//! Setting `SOLANA_FUZZ_REGRESSION_DIR` writes the packet bytes of every input to that
+//! directory; running a crashing artifact with it pointed at
+//! `core/fuzz/regressions/deserialized_packet` turns the crash into a unit test.
+#![no_main]
+use {
+    arbitrary::Arbitrary,
+    libfuzzer_sys::fuzz_target,
+    solana_core::unprocessed_packet_batches::check_packet_deserialization,
+    solana_perf::packet::{Packet, PacketFlags},
+    solana_sdk::{
+        compute_budget::{self, ComputeBudgetInstruction},
+        hash::{hash, Hash},
+        instruction::{AccountMeta, Instruction},
+        message::Message,
+        pubkey::Pubkey,
+        transaction::Transaction,
+    },
+    std::{env, fs, path::Path},
+};
+
+#[derive(Arbitrary, Debug)]
+enum FuzzComputeBudgetInstruction {
+    RequestUnitsDeprecated { units: u32, additional_fee: u32 },
+    RequestHeapFrame(u32),
+    SetComputeUnitLimit(u32),
+    SetComputeUnitPrice(u64),
+    Raw(Vec<u8>),
+}
+
+impl FuzzComputeBudgetInstruction {
+    fn to_instruction(&self) -> Instruction {
+        match self {
+            Self::RequestUnitsDeprecated {
+                units,
+                additional_fee,
+            } => Instruction::new_with_borsh(
+                compute_budget::id(),
+                &ComputeBudgetInstruction::RequestUnitsDeprecated {
+                    units: *units,
+                    additional_fee: *additional_fee,
+                },
+                vec![],
+            ),
+            Self::RequestHeapFrame(bytes) => ComputeBudgetInstruction::request_heap_frame(*bytes),
+            Self::SetComputeUnitLimit(units) => {
+                ComputeBudgetInstruction::set_compute_unit_limit(*units)
+            }
+            Self::SetComputeUnitPrice(micro_lamports) => {
+                ComputeBudgetInstruction::set_compute_unit_price(*micro_lamports)
+            }
+            Self::Raw(data) => Instruction::new_with_bytes(compute_budget::id(), data, vec![]),
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzPacket {
+    payer: [u8; 32],
+    program: [u8; 32],
+    recent_blockhash: [u8; 32],
+    compute_budget_instructions: Vec<FuzzComputeBudgetInstruction>,
+    program_instructions: Vec<Vec<u8>>,
+    sender_stake: u64,
+    is_simple_vote: bool,
+    /// `(offset, value)` byte overwrites applied to the serialized transaction
+    corruptions: Vec<(u16, u8)>,
+}
+
+impl FuzzPacket {
+    fn to_packet(&self) -> Option<Packet> {
+        let payer = Pubkey::new_from_array(self.payer);
+        let program = Pubkey::new_from_array(self.program);
+        let instructions: Vec<_> = self
+            .compute_budget_instructions
+            .iter()
+            .map(FuzzComputeBudgetInstruction::to_instruction)
+            .chain(self.program_instructions.iter().map(|data| {
+                Instruction::new_with_bytes(program, data, vec![AccountMeta::new(payer, true)])
+            }))
+            .collect();
+        let message = Message::new_with_blockhash(
+            &instructions,
+            Some(&payer),
+            &Hash::new_from_array(self.recent_blockhash),
+        );
+        let mut packet = Packet::from_data(None, &Transaction::new_unsigned(message)).ok()?;
+        let size = packet.meta.size;
+        for (offset, value) in &self.corruptions {
+            if let Some(byte) = packet.buffer_mut()[..size].get_mut(usize::from(*offset)) {
+                *byte = *value;
+            }
+        }
+        packet.meta.sender_stake = self.sender_stake;
+        packet
+            .meta
+            .flags
+            .set(PacketFlags::SIMPLE_VOTE_TX, self.is_simple_vote);
+        Some(packet)
+    }
+}
+
+fuzz_target!(|input: FuzzPacket| {
+    if let Some(packet) = input.to_packet() {
+        if let Ok(regression_dir) = env::var("SOLANA_FUZZ_REGRESSION_DIR") {
+            let data = packet.data();
+            fs::write(
+                Path::new(&regression_dir).join(hash(data).to_string()),
+                data,
+            )
+            .unwrap();
+        }
+        check_packet_deserialization(packet);
+    }
+});
@@ -2,13 +2,14 @@
 //
 // Each interval, publish the snapshot hash which is the full accounts state
 // hash on gossip. Monitor gossip for messages from validators in the `--known-validator`s
-// set and halt the node if a mismatch is detected.
+// set and halt the node if the mismatches detected satisfy the verification policy.
 
 use {
     solana_gossip::cluster_info::{ClusterInfo, MAX_SNAPSHOT_HASHES},
     solana_measure::measure::Measure,
     solana_runtime::{
         accounts_hash::{CalcAccountsHashConfig, HashStats},
+        bank_forks::BankForks,
         snapshot_config::SnapshotConfig,
         snapshot_package::{
             AccountsPackage, PendingAccountsPackage, PendingSnapshotPackage, SnapshotPackage,
@@ -25,25 +26,99 @@ use {
         collections::{HashMap, HashSet},
         sync::{
             atomic::{AtomicBool, Ordering},
-            Arc,
+            Arc, RwLock,
         },
         thread::{self, Builder, JoinHandle},
         time::Duration,
     },
 };
 
+/// Decides which accounts hash mismatches published by the known validators halt the node
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountsHashVerificationPolicy {
+    /// Known validators that must have published a hash for one of our slots before a
+    /// mismatch halts the node
+    pub min_known_validators: usize,
+    /// Fraction of the stake of the known validators that published a hash for one of our
+    /// slots that must have published a conflicting hash for the node to halt
+    pub mismatch_stake_fraction: f64,
+    /// Hashes published for slots more than this many slots older than our newest hash
+    /// are ignored
+    pub max_age_slots: Slot,
+}
+
+impl Default for AccountsHashVerificationPolicy {
+    fn default() -> Self {
+        // Halt on the first mismatch, whatever its age
+        Self {
+            min_known_validators: 1,
+            mismatch_stake_fraction: 0.0,
+            max_age_slots: Slot::MAX,
+        }
+    }
+}
+
+/// A hash published by a known validator that conflicts with the one we know for its slot
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountsHashMismatch {
+    pub known_validator: Pubkey,
+    pub slot: Slot,
+    pub hash: Hash,
+    pub reference_hash: Hash,
+}
+
+/// Outcome of comparing the hashes published by the known validators with ours
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountsHashVerificationReport {
+    /// Known validators whose hashes all matched the ones we know
+    pub matched: Vec<Pubkey>,
+    /// Every conflicting hash, possibly several for the same known validator
+    pub mismatched: Vec<AccountsHashMismatch>,
+    /// Known validators that published no hash for a slot we know the hash of
+    pub unverified: Vec<Pubkey>,
+    pub matched_stake: u64,
+    pub mismatched_stake: u64,
+    /// Hashes that matched the ones we know
+    pub verified_count: usize,
+    /// Highest slot of the hashes published by the known validators that we did not know
+    pub highest_slot: Slot,
+}
+
+impl AccountsHashVerificationReport {
+    pub fn mismatched_known_validators(&self) -> HashSet<Pubkey> {
+        self.mismatched
+            .iter()
+            .map(|mismatch| mismatch.known_validator)
+            .collect()
+    }
+
+    pub fn should_halt(&self, policy: &AccountsHashVerificationPolicy) -> bool {
+        if self.mismatched.is_empty() {
+            return false;
+        }
+        let num_verified = self.matched.len() + self.mismatched_known_validators().len();
+        let verified_stake = self.matched_stake.saturating_add(self.mismatched_stake);
+        num_verified >= policy.min_known_validators
+            && self.mismatched_stake as f64
+                >= policy.mismatch_stake_fraction * verified_stake as f64
+    }
+}
+
 pub struct AccountsHashVerifier {
     t_accounts_hash_verifier: JoinHandle<()>,
 }
 
 impl AccountsHashVerifier {
+    /// `halt_policy` is None unless the node halts on known validators accounts hash mismatch
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         pending_accounts_package: PendingAccountsPackage,
         pending_snapshot_package: Option<PendingSnapshotPackage>,
         exit: &Arc<AtomicBool>,
         cluster_info: &Arc<ClusterInfo>,
+        bank_forks: Arc<RwLock<BankForks>>,
         known_validators: Option<HashSet<Pubkey>>,
-        halt_on_known_validators_accounts_hash_mismatch: bool,
+        halt_policy: Option<AccountsHashVerificationPolicy>,
         fault_injection_rate_slots: u64,
         snapshot_config: Option<SnapshotConfig>,
     ) -> Self {
@@ -64,12 +139,14 @@ impl AccountsHashVerifier {
                         continue;
                     }
                     let accounts_package = accounts_package.unwrap();
+                    let staked_nodes = bank_forks.read().unwrap().root_bank().staked_nodes();
 
                     Self::process_accounts_package(
                         accounts_package,
                         &cluster_info,
                         known_validators.as_ref(),
-                        halt_on_known_validators_accounts_hash_mismatch,
+                        &staked_nodes,
+                        halt_policy.as_ref(),
                         pending_snapshot_package.as_ref(),
                         &mut hashes,
                         &exit,
@@ -89,7 +166,8 @@ impl AccountsHashVerifier {
         accounts_package: AccountsPackage,
         cluster_info: &ClusterInfo,
         known_validators: Option<&HashSet<Pubkey>>,
-        halt_on_known_validator_accounts_hash_mismatch: bool,
+        staked_nodes: &HashMap<Pubkey, u64>,
+        halt_policy: Option<&AccountsHashVerificationPolicy>,
         pending_snapshot_package: Option<&PendingSnapshotPackage>,
         hashes: &mut Vec<(Slot, Hash)>,
         exit: &Arc<AtomicBool>,
@@ -102,7 +180,8 @@ impl AccountsHashVerifier {
             &accounts_package,
             cluster_info,
             known_validators,
-            halt_on_known_validator_accounts_hash_mismatch,
+            staked_nodes,
+            halt_policy,
             hashes,
             exit,
             fault_injection_rate_slots,
@@ -173,11 +252,13 @@ impl AccountsHashVerifier {
         accounts_hash
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn push_accounts_hashes_to_cluster(
         accounts_package: &AccountsPackage,
         cluster_info: &ClusterInfo,
         known_validators: Option<&HashSet<Pubkey>>,
-        halt_on_known_validator_accounts_hash_mismatch: bool,
+        staked_nodes: &HashMap<Pubkey, u64>,
+        halt_policy: Option<&AccountsHashVerificationPolicy>,
         hashes: &mut Vec<(Slot, Hash)>,
         exit: &Arc<AtomicBool>,
         fault_injection_rate_slots: u64,
@@ -203,12 +284,18 @@ impl AccountsHashVerifier {
             hashes.remove(0);
         }
 
-        if halt_on_known_validator_accounts_hash_mismatch {
+        if let Some(halt_policy) = halt_policy {
             let mut slot_to_hash = HashMap::new();
             for (slot, hash) in hashes.iter() {
                 slot_to_hash.insert(*slot, *hash);
             }
-            if Self::should_halt(cluster_info, known_validators, &mut slot_to_hash) {
+            if Self::should_halt(
+                cluster_info,
+                known_validators,
+                staked_nodes,
+                halt_policy,
+                &mut slot_to_hash,
+            ) {
                 exit.store(true, Ordering::Relaxed);
             }
         }
@@ -254,47 +341,116 @@ impl AccountsHashVerifier {
     fn should_halt(
         cluster_info: &ClusterInfo,
         known_validators: Option<&HashSet<Pubkey>>,
+        staked_nodes: &HashMap<Pubkey, u64>,
+        policy: &AccountsHashVerificationPolicy,
         slot_to_hash: &mut HashMap<Slot, Hash>,
     ) -> bool {
-        let mut verified_count = 0;
-        let mut highest_slot = 0;
-        if let Some(known_validators) = known_validators {
-            for known_validator in known_validators {
-                let is_conflicting = cluster_info.get_accounts_hash_for_node(known_validator, |accounts_hashes|
-                {
-                    accounts_hashes.iter().any(|(slot, hash)| {
-                        if let Some(reference_hash) = slot_to_hash.get(slot) {
-                            if *hash != *reference_hash {
-                                error!("Known validator {} produced conflicting hashes for slot: {} ({} != {})",
-                                    known_validator,
-                                    slot,
-                                    hash,
-                                    reference_hash,
-                                );
-                                true
-                            } else {
-                                verified_count += 1;
-                                false
-                            }
-                        } else {
-                            highest_slot = std::cmp::max(*slot, highest_slot);
+        let report = Self::verify_known_validators_accounts_hashes(
+            cluster_info,
+            known_validators,
+            staked_nodes,
+            policy,
+            slot_to_hash,
+        );
+        for mismatch in &report.mismatched {
+            error!(
+                "Known validator {} produced conflicting hashes for slot: {} ({} != {})",
+                mismatch.known_validator, mismatch.slot, mismatch.hash, mismatch.reference_hash,
+            );
+        }
+        let should_halt = report.should_halt(policy);
+        if !report.mismatched.is_empty() {
+            warn!(
+                "Known validators accounts hash verification: matched: {:?}, mismatched: {:?}, \
+                 unverified: {:?}, halting: {}",
+                report.matched,
+                report.mismatched_known_validators(),
+                report.unverified,
+                should_halt,
+            );
+        }
+        inc_new_counter_info!(
+            "accounts_hash_verifier-hashes_verified",
+            report.verified_count
+        );
+        datapoint_info!(
+            "accounts_hash_verifier",
+            ("highest_slot_verified", report.highest_slot, i64),
+            ("matched_known_validators", report.matched.len(), i64),
+            (
+                "mismatched_known_validators",
+                report.mismatched_known_validators().len(),
+                i64
+            ),
+            ("unverified_known_validators", report.unverified.len(), i64),
+            ("matched_stake", report.matched_stake, i64),
+            ("mismatched_stake", report.mismatched_stake, i64),
+        );
+        should_halt
+    }
+
+    /// Compares the hashes published by each known validator with the ones in
+    /// `slot_to_hash`, to which the hashes of the slots it misses are added so that the
+    /// known validators are checked against each other as well.
+    fn verify_known_validators_accounts_hashes(
+        cluster_info: &ClusterInfo,
+        known_validators: Option<&HashSet<Pubkey>>,
+        staked_nodes: &HashMap<Pubkey, u64>,
+        policy: &AccountsHashVerificationPolicy,
+        slot_to_hash: &mut HashMap<Slot, Hash>,
+    ) -> AccountsHashVerificationReport {
+        let mut report = AccountsHashVerificationReport::default();
+        let known_validators = match known_validators {
+            Some(known_validators) => known_validators,
+            None => return report,
+        };
+        let oldest_slot = slot_to_hash
+            .keys()
+            .max()
+            .map(|newest_slot| newest_slot.saturating_sub(policy.max_age_slots))
+            .unwrap_or_default();
+        for known_validator in known_validators {
+            let mut mismatches = vec![];
+            let mut verified_count = 0;
+            cluster_info.get_accounts_hash_for_node(known_validator, |accounts_hashes| {
+                for (slot, hash) in accounts_hashes {
+                    if *slot < oldest_slot {
+                        continue;
+                    }
+                    match slot_to_hash.get(slot) {
+                        Some(reference_hash) if hash != reference_hash => {
+                            mismatches.push(AccountsHashMismatch {
+                                known_validator: *known_validator,
+                                slot: *slot,
+                                hash: *hash,
+                                reference_hash: *reference_hash,
+                            });
+                        }
+                        Some(_) => verified_count += 1,
+                        None => {
+                            report.highest_slot = std::cmp::max(*slot, report.highest_slot);
                             slot_to_hash.insert(*slot, *hash);
-                            false
                         }
-                    })
-                }).unwrap_or(false);
-
-                if is_conflicting {
-                    return true;
+                    }
                 }
+            });
+
+            let stake = staked_nodes
+                .get(known_validator)
+                .copied()
+                .unwrap_or_default();
+            report.verified_count += verified_count;
+            if !mismatches.is_empty() {
+                report.mismatched.extend(mismatches);
+                report.mismatched_stake = report.mismatched_stake.saturating_add(stake);
+            } else if verified_count > 0 {
+                report.matched.push(*known_validator);
+                report.matched_stake = report.matched_stake.saturating_add(stake);
+            } else {
+                report.unverified.push(*known_validator);
             }
         }
-        inc_new_counter_info!("accounts_hash_verifier-hashes_verified", verified_count);
-        datapoint_info!(
-            "accounts_hash_verifier",
-            ("highest_slot_verified", highest_slot, i64),
-        );
-        false
+        report
     }
 
     pub fn join(self) -> thread::Result<()> {
@@ -339,9 +495,12 @@ mod tests {
 
         let mut known_validators = HashSet::new();
         let mut slot_to_hash = HashMap::new();
+        let policy = AccountsHashVerificationPolicy::default();
         assert!(!AccountsHashVerifier::should_halt(
             &cluster_info,
             Some(&known_validators),
+            &HashMap::new(),
+            &policy,
             &mut slot_to_hash,
         ));
 
@@ -358,10 +517,103 @@ mod tests {
         assert!(AccountsHashVerifier::should_halt(
             &cluster_info,
             Some(&known_validators),
+            &HashMap::new(),
+            &policy,
             &mut slot_to_hash,
         ));
     }
 
+    #[test]
+    fn test_accounts_hash_verification_policy() {
+        let keypair = Keypair::new();
+        let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), 0);
+        let cluster_info = new_test_cluster_info(contact_info);
+
+        let hash1 = hash(&[1]);
+        let hash2 = hash(&[2]);
+        let matching_validator = Keypair::new();
+        let mismatching_validator = Keypair::new();
+        let silent_validator = Keypair::new();
+        for (validator, hashes) in [
+            (&matching_validator, vec![(10, hash1), (20, hash1)]),
+            (&mismatching_validator, vec![(10, hash2), (20, hash1)]),
+        ] {
+            let message = make_accounts_hashes_message(validator, hashes).unwrap();
+            cluster_info.push_message(message);
+        }
+        cluster_info.flush_push_queue();
+        let known_validators: HashSet<_> = [
+            matching_validator.pubkey(),
+            mismatching_validator.pubkey(),
+            silent_validator.pubkey(),
+        ]
+        .into_iter()
+        .collect();
+        let staked_nodes: HashMap<_, _> = [
+            (matching_validator.pubkey(), 300),
+            (mismatching_validator.pubkey(), 100),
+        ]
+        .into_iter()
+        .collect();
+        let slot_to_hash: HashMap<_, _> = [(10, hash1), (20, hash1)].into_iter().collect();
+        let verify = |policy: &AccountsHashVerificationPolicy| {
+            AccountsHashVerifier::verify_known_validators_accounts_hashes(
+                &cluster_info,
+                Some(&known_validators),
+                &staked_nodes,
+                policy,
+                &mut slot_to_hash.clone(),
+            )
+        };
+
+        let policy = AccountsHashVerificationPolicy::default();
+        let report = verify(&policy);
+        assert_eq!(report.matched, vec![matching_validator.pubkey()]);
+        assert_eq!(
+            report.mismatched,
+            vec![AccountsHashMismatch {
+                known_validator: mismatching_validator.pubkey(),
+                slot: 10,
+                hash: hash2,
+                reference_hash: hash1,
+            }]
+        );
+        assert_eq!(report.unverified, vec![silent_validator.pubkey()]);
+        assert_eq!(report.matched_stake, 300);
+        assert_eq!(report.mismatched_stake, 100);
+        assert_eq!(report.verified_count, 3);
+        assert!(report.should_halt(&policy));
+
+        // Only two known validators published hashes for our slots
+        let policy = AccountsHashVerificationPolicy {
+            min_known_validators: 3,
+            ..AccountsHashVerificationPolicy::default()
+        };
+        assert!(!verify(&policy).should_halt(&policy));
+
+        // The mismatching known validator holds a quarter of the stake
+        let policy = AccountsHashVerificationPolicy {
+            mismatch_stake_fraction: 0.25,
+            ..AccountsHashVerificationPolicy::default()
+        };
+        assert!(verify(&policy).should_halt(&policy));
+        let policy = AccountsHashVerificationPolicy {
+            mismatch_stake_fraction: 0.5,
+            ..AccountsHashVerificationPolicy::default()
+        };
+        assert!(!verify(&policy).should_halt(&policy));
+
+        // The conflicting hash is too old to be compared
+        let policy = AccountsHashVerificationPolicy {
+            max_age_slots: 5,
+            ..AccountsHashVerificationPolicy::default()
+        };
+        let report = verify(&policy);
+        assert_eq!(report.matched.len(), 2);
+        assert!(report.mismatched.is_empty());
+        assert!(!report.should_halt(&policy));
+    }
+
     #[test]
     fn test_max_hashes() {
         solana_logger::setup();
@@ -407,7 +659,8 @@ mod tests {
                 accounts_package,
                 &cluster_info,
                 Some(&known_validators),
-                false,
+                &HashMap::new(),
+                None,
                 None,
                 &mut hashes,
                 &exit,
@@ -927,6 +927,7 @@ mod test {
                 Some(blockstore),
                 requests_receiver,
                 response_sender,
+                None,
                 &exit,
             );
 
@@ -1,8 +1,11 @@
 //! The `banking_stage` processes Transaction messages. It is intended to be used
 //! to construct a software pipeline. The stage uses all available CPU cores and
 //! can do its processing in parallel with signature verification on the GPU.
+#[cfg(feature = "fault-injection")]
+use crate::packet_fault_injection::PacketFaultInjectionConfig;
 use {
     crate::{
+        forward_pacer::{ForwardPacer, ForwardPacingConfig},
         leader_slot_banking_stage_metrics::{LeaderSlotMetricsTracker, ProcessTransactionsSummary},
         leader_slot_banking_stage_timing_metrics::{
             LeaderExecuteAndCommitTimings, RecordTransactionsTimings,
@@ -16,8 +19,11 @@ use {
     },
     histogram::Histogram,
     itertools::Itertools,
-    min_max_heap::MinMaxHeap,
-    solana_client::{connection_cache::get_connection, tpu_connection::TpuConnection},
+    rayon::{ThreadPool, ThreadPoolBuilder},
+    solana_client::{
+        connection_cache::get_connection, rpc_response::RpcBufferedPrioritizationFees,
+        tpu_connection::TpuConnection,
+    },
     solana_entry::entry::hash_transactions,
     solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfo},
     solana_ledger::blockstore_processor::TransactionStatusSender,
@@ -30,6 +36,7 @@ use {
     },
     solana_poh::poh_recorder::{BankStart, PohRecorder, PohRecorderError, TransactionRecorder},
     solana_program_runtime::timings::ExecuteTimings,
+    solana_rpc::buffered_prioritization_fees::BufferedPrioritizationFees,
     solana_runtime::{
         bank::{
             Bank, CommitTransactionCounts, LoadAndExecuteTransactionsOutput,
@@ -47,6 +54,7 @@ use {
             MAX_TRANSACTION_FORWARDING_DELAY_GPU,
         },
         feature_set,
+        hash::Hash,
         pubkey::Pubkey,
         saturating_add_assign,
         timing::{duration_as_ms, timestamp, AtomicInterval},
@@ -55,15 +63,16 @@ use {
         },
         transport::TransportError,
     },
+    solana_streamer::quic::TransactionDropReason,
     solana_transaction_status::token_balances::{
         collect_token_balances, TransactionTokenBalancesSet,
     },
     std::{
         cmp,
-        collections::HashMap,
-        env,
+        collections::{HashMap, HashSet},
+        env, fs,
         net::SocketAddr,
-        rc::Rc,
+        path::{Path, PathBuf},
         sync::{
             atomic::{AtomicU64, AtomicUsize, Ordering},
             Arc, Mutex, RwLock,
@@ -73,6 +82,8 @@ use {
     },
 };
 
+mod scripted_timeline;
+
 /// Transaction forwarding
 pub const FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET: u64 = 2;
 pub const HOLD_TRANSACTIONS_SLOT_OFFSET: u64 = 20;
@@ -90,6 +101,16 @@ const MIN_TOTAL_THREADS: u32 = NUM_VOTE_PROCESSING_THREADS + MIN_THREADS_BANKING
 const UNPROCESSED_BUFFER_STEP_SIZE: usize = 128;
 
 const SLOT_BOUNDARY_CHECK_PERIOD: Duration = Duration::from_millis(10);
+
+/// How often the banking threads publish the prices offered by their buffered packets to RPC
+const BUFFERED_FEES_PUBLISH_PERIOD: Duration = Duration::from_millis(400);
+
+/// Number of senders with the most duplicate packets reported per banking thread
+const MAX_REPORTED_DUPLICATE_SOURCES: usize = 5;
+
+/// Threads shared by the banking threads to deserialize received packets
+const NUM_PACKET_DESERIALIZE_THREADS: usize = 4;
+
 pub type BankingPacketBatch = (Vec<PacketBatch>, Option<TransactionTracerPacketStats>);
 pub type BankingPacketSender = CrossbeamSender<BankingPacketBatch>;
 pub type BankingPacketReceiver = CrossbeamReceiver<BankingPacketBatch>;
@@ -143,11 +164,21 @@ pub struct BankingStageStats {
     pub(crate) dropped_duplicated_packets_count: AtomicUsize,
     newly_buffered_packets_count: AtomicUsize,
     current_buffered_packets_count: AtomicUsize,
+    current_buffered_bytes: AtomicUsize,
     current_buffered_packet_batches_count: AtomicUsize,
     rebuffered_packets_count: AtomicUsize,
     consumed_buffered_packets_count: AtomicUsize,
     end_of_slot_filtered_invalid_count: AtomicUsize,
+    already_processed_purged_count: AtomicUsize,
+    stale_nonce_purged_count: AtomicUsize,
+    filtered_packets_count: AtomicUsize,
+    byte_evicted_packets_count: AtomicUsize,
+    rate_limited_packets_count: AtomicUsize,
+    coalesced_packets_count: AtomicUsize,
+    too_many_account_locks_packets_count: AtomicUsize,
     batch_packet_indexes_len: Histogram,
+    dedup_stats: DedupStats,
+    deserializer_stats: PacketDeserializerStats,
 
     // Timing
     consume_buffered_packets_elapsed: AtomicU64,
@@ -157,6 +188,7 @@ pub struct BankingStageStats {
     packet_conversion_elapsed: AtomicU64,
     unprocessed_packet_conversion_elapsed: AtomicU64,
     transaction_processing_elapsed: AtomicU64,
+    purge_already_processed_elapsed: AtomicU64,
 }
 
 impl BankingStageStats {
@@ -181,11 +213,21 @@ impl BankingStageStats {
                 .load(Ordering::Relaxed) as u64
             + self.newly_buffered_packets_count.load(Ordering::Relaxed) as u64
             + self.current_buffered_packets_count.load(Ordering::Relaxed) as u64
+            + self.current_buffered_bytes.load(Ordering::Relaxed) as u64
             + self
                 .current_buffered_packet_batches_count
                 .load(Ordering::Relaxed) as u64
             + self.rebuffered_packets_count.load(Ordering::Relaxed) as u64
             + self.consumed_buffered_packets_count.load(Ordering::Relaxed) as u64
+            + self.already_processed_purged_count.load(Ordering::Relaxed) as u64
+            + self.stale_nonce_purged_count.load(Ordering::Relaxed) as u64
+            + self.filtered_packets_count.load(Ordering::Relaxed) as u64
+            + self.byte_evicted_packets_count.load(Ordering::Relaxed) as u64
+            + self.rate_limited_packets_count.load(Ordering::Relaxed) as u64
+            + self.coalesced_packets_count.load(Ordering::Relaxed) as u64
+            + self
+                .too_many_account_locks_packets_count
+                .load(Ordering::Relaxed) as u64
             + self
                 .consume_buffered_packets_elapsed
                 .load(Ordering::Relaxed)
@@ -201,7 +243,10 @@ impl BankingStageStats {
                 .unprocessed_packet_conversion_elapsed
                 .load(Ordering::Relaxed)
             + self.transaction_processing_elapsed.load(Ordering::Relaxed)
+            + self.purge_already_processed_elapsed.load(Ordering::Relaxed)
             + self.batch_packet_indexes_len.entries()
+            + self.dedup_stats.num_duplicates as u64
+            + self.deserializer_stats.num_failures() as u64
     }
 
     fn report(&mut self, report_interval_ms: u64) {
@@ -247,6 +292,11 @@ impl BankingStageStats {
                         .swap(0, Ordering::Relaxed) as i64,
                     i64
                 ),
+                (
+                    "current_buffered_bytes",
+                    self.current_buffered_bytes.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
                 (
                     "rebuffered_packets_count",
                     self.rebuffered_packets_count.swap(0, Ordering::Relaxed) as i64,
@@ -264,6 +314,43 @@ impl BankingStageStats {
                         .swap(0, Ordering::Relaxed) as i64,
                     i64
                 ),
+                (
+                    "already_processed_purged_count",
+                    self.already_processed_purged_count
+                        .swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
+                (
+                    "stale_nonce_purged_count",
+                    self.stale_nonce_purged_count.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
+                (
+                    "filtered_packets_count",
+                    self.filtered_packets_count.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
+                (
+                    "byte_evicted_packets_count",
+                    self.byte_evicted_packets_count.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
+                (
+                    "rate_limited_packets_count",
+                    self.rate_limited_packets_count.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
+                (
+                    "coalesced_packets_count",
+                    self.coalesced_packets_count.swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
+                (
+                    "too_many_account_locks_packets_count",
+                    self.too_many_account_locks_packets_count
+                        .swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
                 (
                     "consume_buffered_packets_elapsed",
                     self.consume_buffered_packets_elapsed
@@ -305,6 +392,12 @@ impl BankingStageStats {
                         .swap(0, Ordering::Relaxed) as i64,
                     i64
                 ),
+                (
+                    "purge_already_processed_elapsed",
+                    self.purge_already_processed_elapsed
+                        .swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
                 (
                     "packet_batch_indices_len_min",
                     self.batch_packet_indexes_len.minimum().unwrap_or(0) as i64,
@@ -324,9 +417,48 @@ impl BankingStageStats {
                     "packet_batch_indices_len_90pct",
                     self.batch_packet_indexes_len.percentile(90.0).unwrap_or(0) as i64,
                     i64
+                ),
+                (
+                    "deserialize_short_vec_errors",
+                    self.deserializer_stats.short_vec_errors as i64,
+                    i64
+                ),
+                (
+                    "deserialize_deserialization_errors",
+                    self.deserializer_stats.deserialization_errors as i64,
+                    i64
+                ),
+                (
+                    "deserialize_signature_overflows",
+                    self.deserializer_stats.signature_overflows as i64,
+                    i64
+                ),
+                (
+                    "deserialize_sanitize_errors",
+                    self.deserializer_stats.sanitize_errors as i64,
+                    i64
+                ),
+                (
+                    "deserialize_prioritization_failures",
+                    self.deserializer_stats.prioritization_failures as i64,
+                    i64
                 )
             );
             self.batch_packet_indexes_len.clear();
+            self.deserializer_stats = PacketDeserializerStats::default();
+
+            // Senders resubmitting the same transactions the most since the last report
+            for (source, num_duplicates) in
+                self.dedup_stats.top_sources(MAX_REPORTED_DUPLICATE_SOURCES)
+            {
+                datapoint_info!(
+                    "banking_stage-duplicate-sources",
+                    ("id", self.id as i64, i64),
+                    ("source", source.to_string(), String),
+                    ("num_duplicates", num_duplicates as i64, i64)
+                );
+            }
+            self.dedup_stats = DedupStats::default();
         }
     }
 }
@@ -361,6 +493,26 @@ struct EndOfSlot {
     working_bank: Option<Arc<Bank>>,
 }
 
+/// Tunables of the banking stage. The default is what `BankingStage::new()` runs with.
+#[derive(Clone, Default)]
+pub struct BankingStageConfig {
+    pub forward_pacing_config: ForwardPacingConfig,
+    pub priority_aging_config: PriorityAgingConfig,
+    pub pop_policy_config: PopPolicyConfig,
+    /// Rejects transaction packets before they are buffered
+    pub packet_filter: Option<Arc<dyn PacketFilter>>,
+    /// Transaction packets buffered per second from each source address; unlimited if None
+    pub source_packets_per_second: Option<u64>,
+    /// Transaction packets buffered for each fee payer and set of writable accounts;
+    /// unlimited if None
+    pub max_packets_per_lock_set: Option<usize>,
+    /// Notified of the transaction packets dropped from the buffer
+    pub drop_notifier: Option<Arc<dyn PacketDropNotifier>>,
+    /// Directory that the transaction packets still buffered on shutdown are saved to, to be
+    /// buffered again on startup
+    pub packet_buffer_snapshot_dir: Option<PathBuf>,
+}
+
 /// Stores the stage's thread handle and output receiver.
 pub struct BankingStage {
     bank_thread_hdls: Vec<JoinHandle<()>>,
@@ -404,6 +556,8 @@ impl BankingStage {
             transaction_status_sender,
             gossip_vote_sender,
             cost_model,
+            Arc::default(),
+            BankingStageConfig::default(),
         )
     }
 
@@ -418,14 +572,41 @@ impl BankingStage {
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: ReplayVoteSender,
         cost_model: Arc<RwLock<CostModel>>,
+        buffered_prioritization_fees: Arc<BufferedPrioritizationFees>,
+        config: BankingStageConfig,
     ) -> Self {
         assert!(num_threads >= MIN_TOTAL_THREADS);
+        let BankingStageConfig {
+            forward_pacing_config,
+            priority_aging_config,
+            pop_policy_config,
+            packet_filter,
+            source_packets_per_second,
+            max_packets_per_lock_set,
+            drop_notifier,
+            packet_buffer_snapshot_dir,
+        } = config;
+        if let Some(dir) = &packet_buffer_snapshot_dir {
+            if let Err(err) = fs::create_dir_all(dir) {
+                warn!(
+                    "Unable to create packet buffer snapshot dir {:?}: {}",
+                    dir, err
+                );
+            }
+        }
         // Single thread to generate entries from many banks.
         // This thread talks to poh_service and broadcasts the entries once they have been recorded.
         // Once an entry has been recorded, its blockhash is registered with the bank.
         let data_budget = Arc::new(DataBudget::default());
         let batch_limit =
             TOTAL_BUFFERED_PACKETS / ((num_threads - NUM_VOTE_PROCESSING_THREADS) as usize);
+        let deserialize_thread_pool = Arc::new(
+            ThreadPoolBuilder::new()
+                .num_threads(NUM_PACKET_DESERIALIZE_THREADS)
+                .thread_name(|i| format!("solana-banking-deserialize-{}", i))
+                .build()
+                .unwrap(),
+        );
         // Many banks that process transactions in parallel.
         let bank_thread_hdls: Vec<JoinHandle<()>> = (0..num_threads)
             .map(|i| {
@@ -441,6 +622,14 @@ impl BankingStage {
                     ),
                     _ => (verified_receiver.clone(), ForwardOption::ForwardTransaction),
                 };
+                // Votes are bounded by the vote QUIC limits and the number of voters, and their
+                // senders don't resubmit dropped votes
+                let (source_packets_per_second, drop_notifier) = match forward_option {
+                    ForwardOption::ForwardTransaction => {
+                        (source_packets_per_second, drop_notifier.clone())
+                    }
+                    _ => (None, None),
+                };
 
                 let poh_recorder = poh_recorder.clone();
                 let cluster_info = cluster_info.clone();
@@ -449,6 +638,19 @@ impl BankingStage {
                 let gossip_vote_sender = gossip_vote_sender.clone();
                 let data_budget = data_budget.clone();
                 let cost_model = cost_model.clone();
+                let forward_pacer = ForwardPacer::new(forward_pacing_config.clone());
+                let priority_aging_config = priority_aging_config.clone();
+                let pop_policy = pop_policy_config.policy_for(i);
+                let deserialize_thread_pool = deserialize_thread_pool.clone();
+                let packet_filter = packet_filter.clone();
+                let buffered_prioritization_fees = buffered_prioritization_fees.clone();
+                // Votes are stale by the time the validator is back up
+                let packet_buffer_snapshot_path = match forward_option {
+                    ForwardOption::ForwardTransaction => packet_buffer_snapshot_dir
+                        .as_ref()
+                        .map(|dir| dir.join(format!("banking_stage_buffer_{}.bin", i))),
+                    _ => None,
+                };
                 Builder::new()
                     .name(format!("solana-banking-stage-tx-{}", i))
                     .spawn(move || {
@@ -463,7 +665,17 @@ impl BankingStage {
                             transaction_status_sender,
                             gossip_vote_sender,
                             &data_budget,
+                            forward_pacer,
+                            priority_aging_config,
+                            pop_policy,
                             cost_model,
+                            &deserialize_thread_pool,
+                            packet_filter,
+                            source_packets_per_second,
+                            max_packets_per_lock_set,
+                            drop_notifier,
+                            &buffered_prioritization_fees,
+                            packet_buffer_snapshot_path,
                         );
                     })
                     .unwrap()
@@ -472,20 +684,6 @@ impl BankingStage {
         Self { bank_thread_hdls }
     }
 
-    fn filter_valid_packets_for_forwarding<'a>(
-        deserialized_packets: impl Iterator<Item = &'a DeserializedPacket>,
-    ) -> Vec<&'a Packet> {
-        deserialized_packets
-            .filter_map(|deserialized_packet| {
-                if !deserialized_packet.forwarded {
-                    Some(deserialized_packet.immutable_section().original_packet())
-                } else {
-                    None
-                }
-            })
-            .collect()
-    }
-
     /// Forwards all valid, unprocessed packets in the buffer, up to a rate limit. Returns
     /// the number of successfully forwarded packets in second part of tuple
     fn forward_buffered_packets(
@@ -566,14 +764,9 @@ impl BankingStage {
         let mut proc_start = Measure::start("consume_buffered_process");
         let mut reached_end_of_slot: Option<EndOfSlot> = None;
 
-        let mut retryable_packets = MinMaxHeap::with_capacity(buffered_packet_batches.capacity());
-        std::mem::swap(
-            &mut buffered_packet_batches.packet_priority_queue,
-            &mut retryable_packets,
-        );
-
-        let mut retryable_packets: MinMaxHeap<Rc<ImmutableDeserializedPacket>> = retryable_packets
-            .drain_desc()
+        let retryable_packets: Vec<PrioritizedPacket> = buffered_packet_batches
+            .take_packets()
+            .into_iter()
             .chunks(num_packets_to_process_per_iteration)
             .into_iter()
             .flat_map(|packets_to_process| {
@@ -638,7 +831,7 @@ impl BankingStage {
 
                             reached_end_of_slot = Some(EndOfSlot {
                                 next_slot_leader: poh_recorder_locked.next_slot_leader(),
-                                working_bank: Some(working_bank),
+                                working_bank: Some(working_bank.clone()),
                             });
                             poh_recorder_lock_time
                         };
@@ -677,16 +870,27 @@ impl BankingStage {
 
                     // Remove the non-retryable packets, packets that were either:
                     // 1) Successfully processed
-                    // 2) Failed but not retryable
+                    // 2) Failed but not retryable, in which case the senders of the ones that
+                    // expired are notified
+                    let notify_expired = buffered_packet_batches.has_drop_notifier();
                     Self::filter_processed_packets(
                         retryable_transaction_indexes
                             .iter()
                             .chain(std::iter::once(&packets_to_process.len())),
                         |start, end| {
                             for processed_packet in &packets_to_process[start..end] {
-                                buffered_packet_batches
-                                    .message_hash_to_transaction
-                                    .remove(processed_packet.message_hash());
+                                buffered_packet_batches.remove_popped(processed_packet);
+                                if notify_expired
+                                    && Self::is_dropped_for_expired_blockhash(
+                                        &working_bank,
+                                        processed_packet,
+                                    )
+                                {
+                                    buffered_packet_batches.notify_dropped(
+                                        processed_packet,
+                                        TransactionDropReason::BlockhashExpired,
+                                    );
+                                }
                             }
                         },
                     );
@@ -718,11 +922,7 @@ impl BankingStage {
                 }
             })
             .collect();
-
-        std::mem::swap(
-            &mut retryable_packets,
-            &mut buffered_packet_batches.packet_priority_queue,
-        );
+        buffered_packet_batches.reinsert_packets(retryable_packets);
 
         if let Some(end_of_slot) = &reached_end_of_slot {
             slot_metrics_tracker
@@ -775,7 +975,7 @@ impl BankingStage {
 
         // Assert unprocessed queue is still consistent
         assert_eq!(
-            buffered_packet_batches.packet_priority_queue.len(),
+            buffered_packet_batches.len(),
             buffered_packet_batches.message_hash_to_transaction.len()
         );
         banking_stage_stats
@@ -834,6 +1034,7 @@ impl BankingStage {
         banking_stage_stats: &BankingStageStats,
         recorder: &TransactionRecorder,
         data_budget: &DataBudget,
+        forward_pacer: &mut ForwardPacer,
         qos_service: &QosService,
         slot_metrics_tracker: &mut LeaderSlotMetricsTracker,
     ) {
@@ -907,6 +1108,7 @@ impl BankingStage {
                             poh_recorder,
                             false,
                             data_budget,
+                            forward_pacer,
                             slot_metrics_tracker,
                         )
                     },
@@ -925,6 +1127,7 @@ impl BankingStage {
                             poh_recorder,
                             true,
                             data_budget,
+                            forward_pacer,
                             slot_metrics_tracker,
                         )
                     },
@@ -937,6 +1140,9 @@ impl BankingStage {
         }
     }
 
+    /// Forwards buffered packets to the upcoming leader, highest priority first, as far as
+    /// `forward_pacer` allows. Packets held back by pacing stay in the buffer and are
+    /// forwarded on a later iteration.
     fn handle_forwarding(
         forward_option: &ForwardOption,
         cluster_info: &ClusterInfo,
@@ -944,6 +1150,7 @@ impl BankingStage {
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         hold: bool,
         data_budget: &DataBudget,
+        forward_pacer: &mut ForwardPacer,
         slot_metrics_tracker: &mut LeaderSlotMetricsTracker,
     ) {
         let addr = match forward_option {
@@ -962,14 +1169,51 @@ impl BankingStage {
             Some(addr) => addr,
             None => return,
         };
+        let (leader_pubkey, leader_stake, total_stake) = match next_leader_stake(poh_recorder) {
+            Some(leader_stake) => leader_stake,
+            None => return,
+        };
+        forward_pacer.refill();
+        forward_pacer.set_leader(&leader_pubkey, leader_stake, total_stake);
 
-        let forwardable_packets =
-            Self::filter_valid_packets_for_forwarding(buffered_packet_batches.iter());
-        let forwardable_packets_len = forwardable_packets.len();
-        let (_forward_result, sucessful_forwarded_packets_count) =
-            Self::forward_buffered_packets(&addr, forwardable_packets, data_budget);
+        let packet_batches = buffered_packet_batches.take_packets_to_forward(
+            forward_pacer.bytes_available(),
+            forward_pacer.packets_available(),
+        );
+        let paced_packets_len: usize = packet_batches.iter().map(PacketBatch::len).sum();
+        forward_pacer.consume(
+            paced_packets_len,
+            packet_batches
+                .iter()
+                .flat_map(PacketBatch::iter)
+                .map(|packet| packet.meta.size)
+                .sum(),
+        );
+        let deferred_packets_count = buffered_packet_batches
+            .iter()
+            .filter(|deserialized_packet| !deserialized_packet.forwarded)
+            .count();
+        if deferred_packets_count > 0 {
+            inc_new_counter_info!(
+                "banking_stage-forward_deferred_by_pacing",
+                deferred_packets_count
+            );
+        }
+
+        let sucessful_forwarded_packets_count: usize = packet_batches
+            .iter()
+            .map(|packet_batch| {
+                let (_forward_result, sucessful_forwarded_packets_count) =
+                    Self::forward_buffered_packets(
+                        &addr,
+                        packet_batch.iter().collect(),
+                        data_budget,
+                    );
+                sucessful_forwarded_packets_count
+            })
+            .sum();
         let failed_forwarded_packets_count =
-            forwardable_packets_len.saturating_sub(sucessful_forwarded_packets_count);
+            paced_packets_len.saturating_sub(sucessful_forwarded_packets_count);
 
         if failed_forwarded_packets_count > 0 {
             slot_metrics_tracker
@@ -983,14 +1227,12 @@ impl BankingStage {
             );
         }
 
-        if hold {
-            for deserialized_packet in buffered_packet_batches.iter_mut() {
-                deserialized_packet.forwarded = true;
-            }
-        } else {
-            slot_metrics_tracker
-                .increment_cleared_from_buffer_after_forward_count(forwardable_packets_len as u64);
-            buffered_packet_batches.clear();
+        if !hold {
+            let buffered_packets_len = buffered_packet_batches.len();
+            buffered_packet_batches.retain(|deserialized_packet| !deserialized_packet.forwarded);
+            slot_metrics_tracker.increment_cleared_from_buffer_after_forward_count(
+                buffered_packets_len.saturating_sub(buffered_packet_batches.len()) as u64,
+            );
         }
     }
 
@@ -1006,19 +1248,53 @@ impl BankingStage {
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: ReplayVoteSender,
         data_budget: &DataBudget,
+        mut forward_pacer: ForwardPacer,
+        priority_aging_config: PriorityAgingConfig,
+        pop_policy: PopPolicy,
         cost_model: Arc<RwLock<CostModel>>,
+        deserialize_thread_pool: &ThreadPool,
+        packet_filter: Option<Arc<dyn PacketFilter>>,
+        source_packets_per_second: Option<u64>,
+        max_packets_per_lock_set: Option<usize>,
+        drop_notifier: Option<Arc<dyn PacketDropNotifier>>,
+        buffered_prioritization_fees: &BufferedPrioritizationFees,
+        packet_buffer_snapshot_path: Option<PathBuf>,
     ) {
         let recorder = poh_recorder.lock().unwrap().recorder();
-        let mut buffered_packet_batches = UnprocessedPacketBatches::with_capacity(batch_limit);
+        let mut buffered_packet_batches =
+            UnprocessedPacketBatches::with_capacity(batch_limit).with_nonce_check();
+        if let Some(packets_per_second) = source_packets_per_second {
+            buffered_packet_batches =
+                buffered_packet_batches.with_source_rate_limit(packets_per_second);
+        }
+        if let Some(max_packets) = max_packets_per_lock_set {
+            buffered_packet_batches =
+                buffered_packet_batches.with_max_packets_per_lock_set(max_packets);
+        }
+        #[cfg(feature = "fault-injection")]
+        if let Some(config) = PacketFaultInjectionConfig::from_env() {
+            warn!("banking stage {} injecting packet faults: {:?}", id, config);
+            buffered_packet_batches = buffered_packet_batches.with_fault_injection(config);
+        }
+        buffered_packet_batches.set_priority_aging_config(priority_aging_config);
+        buffered_packet_batches.set_pop_policy(pop_policy);
+        buffered_packet_batches.set_packet_filter(packet_filter);
+        buffered_packet_batches.set_drop_notifier(drop_notifier);
+        if let Some(path) = packet_buffer_snapshot_path.as_ref() {
+            Self::restore_packet_buffer(id, &mut buffered_packet_batches, path);
+        }
         let mut banking_stage_stats = BankingStageStats::new(id);
         let qos_service = QosService::new(cost_model, id);
 
         let mut slot_metrics_tracker = LeaderSlotMetricsTracker::new(id);
         let mut last_metrics_update = Instant::now();
+        let mut last_purged_slot = None;
+        let mut last_fees_publish = Instant::now();
 
         loop {
             let my_pubkey = cluster_info.id();
             if !buffered_packet_batches.is_empty() {
+                buffered_packet_batches.age_priorities();
                 let (_, process_buffered_packets_time) = Measure::this(
                     |_| {
                         Self::process_buffered_packets(
@@ -1032,6 +1308,7 @@ impl BankingStage {
                             &banking_stage_stats,
                             &recorder,
                             data_budget,
+                            &mut forward_pacer,
                             &qos_service,
                             &mut slot_metrics_tracker,
                         )
@@ -1044,21 +1321,45 @@ impl BankingStage {
             }
 
             if last_metrics_update.elapsed() >= SLOT_BOUNDARY_CHECK_PERIOD {
-                let (_, slot_metrics_checker_check_slot_boundary_time) = Measure::this(
-                    |_| {
-                        let current_poh_bank = {
-                            let poh = poh_recorder.lock().unwrap();
-                            poh.bank_start()
-                        };
-                        slot_metrics_tracker.update_on_leader_slot_boundary(&current_poh_bank);
-                    },
-                    (),
-                    "slot_metrics_checker_check_slot_boundary",
-                );
+                let (current_poh_bank, slot_metrics_checker_check_slot_boundary_time) =
+                    Measure::this(
+                        |_| {
+                            let current_poh_bank = {
+                                let poh = poh_recorder.lock().unwrap();
+                                poh.bank_start()
+                            };
+                            slot_metrics_tracker.update_on_leader_slot_boundary(&current_poh_bank);
+                            current_poh_bank
+                        },
+                        (),
+                        "slot_metrics_checker_check_slot_boundary",
+                    );
                 slot_metrics_tracker.increment_slot_metrics_check_slot_boundary_us(
                     slot_metrics_checker_check_slot_boundary_time.as_us(),
                 );
 
+                if let Some(BankStart { working_bank, .. }) = current_poh_bank {
+                    if last_purged_slot != Some(working_bank.slot()) {
+                        last_purged_slot = Some(working_bank.slot());
+                        // The lock limit depends on the features active in the bank
+                        buffered_packet_batches.set_transaction_account_lock_limit(Some(
+                            working_bank.get_transaction_account_lock_limit(),
+                        ));
+                        let purged_count = Self::purge_already_processed_packets(
+                            &working_bank,
+                            &mut buffered_packet_batches,
+                            &banking_stage_stats,
+                        );
+                        slot_metrics_tracker
+                            .increment_already_processed_purged_count(purged_count as u64);
+                        let stale_nonce_count =
+                            buffered_packet_batches.retain_valid_nonces(&working_bank);
+                        banking_stage_stats
+                            .stale_nonce_purged_count
+                            .fetch_add(stale_nonce_count, Ordering::Relaxed);
+                    }
+                }
+
                 last_metrics_update = Instant::now();
             }
 
@@ -1083,6 +1384,7 @@ impl BankingStage {
                         &mut buffered_packet_batches,
                         &mut banking_stage_stats,
                         &mut slot_metrics_tracker,
+                        deserialize_thread_pool,
                     )
                 },
                 (),
@@ -1095,10 +1397,99 @@ impl BankingStage {
                 Ok(()) | Err(RecvTimeoutError::Timeout) => (),
                 Err(RecvTimeoutError::Disconnected) => break,
             }
+            // Vote threads hold no packets bidding for block space
+            if matches!(forward_option, ForwardOption::ForwardTransaction)
+                && last_fees_publish.elapsed() >= BUFFERED_FEES_PUBLISH_PERIOD
+            {
+                Self::publish_buffered_prioritization_fees(
+                    id,
+                    &buffered_packet_batches,
+                    buffered_prioritization_fees,
+                );
+                last_fees_publish = Instant::now();
+            }
             banking_stage_stats.report(1000);
+            buffered_packet_batches.report_queue_latency_stats(id, 1000);
+            #[cfg(feature = "fault-injection")]
+            if let Some(stats) = buffered_packet_batches.fault_injection_stats_mut() {
+                stats.report(id, 1000);
+            }
+        }
+
+        if let Some(path) = packet_buffer_snapshot_path.as_ref() {
+            Self::save_packet_buffer(id, &buffered_packet_batches, path);
         }
     }
 
+    /// Buffers the packets that were buffered when the validator last shut down
+    fn restore_packet_buffer(
+        id: u32,
+        buffered_packet_batches: &mut UnprocessedPacketBatches,
+        path: &Path,
+    ) {
+        if !path.exists() {
+            return;
+        }
+        match buffered_packet_batches.restore_from_file(path) {
+            Ok(restored_packets_count) => {
+                info!(
+                    "banking stage {} restored {} buffered packets",
+                    id, restored_packets_count
+                );
+                inc_new_counter_info!(
+                    "banking_stage-restored_buffered_packets",
+                    restored_packets_count
+                );
+            }
+            Err(err) => {
+                warn!(
+                    "banking stage {} failed to restore buffered packets from {:?}: {}",
+                    id, path, err
+                );
+                // Don't retry a file that can't be read on every restart
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    /// Saves the buffered packets on shutdown, to buffer them again once restarted
+    fn save_packet_buffer(
+        id: u32,
+        buffered_packet_batches: &UnprocessedPacketBatches,
+        path: &Path,
+    ) {
+        if buffered_packet_batches.is_empty() {
+            return;
+        }
+        match buffered_packet_batches.save_to_file(path) {
+            Ok(saved_packets_count) => info!(
+                "banking stage {} saved {} buffered packets",
+                id, saved_packets_count
+            ),
+            Err(err) => warn!(
+                "banking stage {} failed to save buffered packets to {:?}: {}",
+                id, path, err
+            ),
+        }
+    }
+
+    fn publish_buffered_prioritization_fees(
+        id: u32,
+        buffered_packet_batches: &UnprocessedPacketBatches,
+        buffered_prioritization_fees: &BufferedPrioritizationFees,
+    ) {
+        let fees = buffered_packet_batches
+            .priority_quantiles()
+            .map(|quantiles| RpcBufferedPrioritizationFees {
+                num_packets: quantiles.num_packets,
+                min_compute_unit_price: quantiles.min,
+                median_compute_unit_price: quantiles.median,
+                p90_compute_unit_price: quantiles.p90,
+            })
+            .unwrap_or_default();
+        buffered_prioritization_fees.publish(id, fees);
+    }
+
     pub fn num_threads() -> u32 {
         cmp::max(
             env::var("SOLANA_BANKING_THREADS")
@@ -1752,7 +2143,7 @@ impl BankingStage {
         }
 
         let tx = SanitizedTransaction::try_new(
-            deserialized_packet.transaction().clone(),
+            deserialized_packet.transaction()?.clone(),
             *deserialized_packet.message_hash(),
             deserialized_packet.is_simple_vote(),
             address_loader,
@@ -1777,30 +2168,41 @@ impl BankingStage {
             Self::prepare_filter_for_pending_transactions(transactions.len(), pending_indexes);
 
         let mut error_counters = TransactionErrorMetrics::default();
-        // The following code also checks if the blockhash for a transaction is too old
-        // The check accounts for
-        //  1. Transaction forwarding delay
-        //  2. The slot at which the next leader will actually process the transaction
-        // Drop the transaction if it will expire by the time the next node receives and processes it
-        let api = perf_libs::api();
-        let max_tx_fwd_delay = if api.is_none() {
-            MAX_TRANSACTION_FORWARDING_DELAY
-        } else {
-            MAX_TRANSACTION_FORWARDING_DELAY_GPU
-        };
-
         let results = bank.check_transactions(
             transactions,
             &filter,
-            (MAX_PROCESSING_AGE)
-                .saturating_sub(max_tx_fwd_delay)
-                .saturating_sub(FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET as usize),
+            Self::max_pending_age(),
             &mut error_counters,
         );
 
         Self::filter_valid_transaction_indexes(&results, transaction_to_packet_indexes)
     }
 
+    // The max age of the blockhash of a pending transaction, which accounts for
+    //  1. Transaction forwarding delay
+    //  2. The slot at which the next leader will actually process the transaction
+    // Drop the transaction if it will expire by the time the next node receives and processes it
+    fn max_pending_age() -> usize {
+        let api = perf_libs::api();
+        let max_tx_fwd_delay = if api.is_none() {
+            MAX_TRANSACTION_FORWARDING_DELAY
+        } else {
+            MAX_TRANSACTION_FORWARDING_DELAY_GPU
+        };
+        MAX_PROCESSING_AGE
+            .saturating_sub(max_tx_fwd_delay)
+            .saturating_sub(FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET as usize)
+    }
+
+    /// Returns true if `packet`, which was processed but is not retryable, was dropped
+    /// because its blockhash expired rather than committed to `bank` or rejected for another
+    /// reason. Durable nonce transactions never expire this way.
+    fn is_dropped_for_expired_blockhash(bank: &Bank, packet: &ImmutableDeserializedPacket) -> bool {
+        packet.durable_nonce_account().is_none()
+            && !bank.is_hash_valid_for_age(packet.recent_blockhash(), Self::max_pending_age())
+            && !bank.has_signature(&packet.packet_view().signature())
+    }
+
     fn filter_processed_packets<'a, F>(
         retryable_transaction_indexes: impl Iterator<Item = &'a usize>,
         mut f: F,
@@ -1833,6 +2235,7 @@ impl BankingStage {
         slot_metrics_tracker: &'a mut LeaderSlotMetricsTracker,
     ) -> ProcessTransactionsSummary {
         // Convert packets to transactions
+        let transaction_account_lock_limit = bank.get_transaction_account_lock_limit();
         let ((transactions, transaction_to_packet_indexes), packet_conversion_time): (
             (Vec<SanitizedTransaction>, Vec<usize>),
             _,
@@ -1847,6 +2250,13 @@ impl BankingStage {
                             bank.vote_only_bank(),
                             bank.as_ref(),
                         )
+                        .filter(|transaction| {
+                            SanitizedTransaction::validate_account_locks(
+                                transaction.message(),
+                                transaction_account_lock_limit,
+                            )
+                            .is_ok()
+                        })
                         .map(|transaction| (transaction, i))
                     })
                     .unzip()
@@ -1960,6 +2370,7 @@ impl BankingStage {
             let mut unprocessed_packet_conversion_time =
                 Measure::start("unprocessed_packet_conversion");
 
+            let transaction_account_lock_limit = bank.get_transaction_account_lock_limit();
             let should_retain = |deserialized_packet: &mut DeserializedPacket| {
                 Self::transaction_from_deserialized_packet(
                     deserialized_packet.immutable_section(),
@@ -1967,6 +2378,13 @@ impl BankingStage {
                     bank.vote_only_bank(),
                     bank.as_ref(),
                 )
+                .filter(|transaction| {
+                    SanitizedTransaction::validate_account_locks(
+                        transaction.message(),
+                        transaction_account_lock_limit,
+                    )
+                    .is_ok()
+                })
                 .is_some()
             };
             unprocessed_packets.retain(should_retain);
@@ -1982,6 +2400,58 @@ impl BankingStage {
         original_unprocessed_packets_len.saturating_sub(unprocessed_packets.len())
     }
 
+    /// Drops buffered packets whose messages already landed on `bank`'s fork, since executing
+    /// them would only fail with `AlreadyProcessed`. Returns the number of purged packets.
+    fn purge_already_processed_packets(
+        bank: &Bank,
+        buffered_packet_batches: &mut UnprocessedPacketBatches,
+        banking_stage_stats: &BankingStageStats,
+    ) -> usize {
+        if buffered_packet_batches.is_empty() {
+            return 0;
+        }
+        let mut purge_time = Measure::start("purge_already_processed_packets");
+        let already_processed_message_hashes: HashSet<Hash> = {
+            let messages: Vec<_> = buffered_packet_batches
+                .iter()
+                .map(|deserialized_packet| {
+                    let immutable_section = deserialized_packet.immutable_section();
+                    (
+                        *immutable_section.message_hash(),
+                        *immutable_section.recent_blockhash(),
+                    )
+                })
+                .collect();
+            let already_processed = bank.check_messages_already_processed(
+                messages
+                    .iter()
+                    .map(|(message_hash, recent_blockhash)| (message_hash, recent_blockhash)),
+            );
+            messages
+                .into_iter()
+                .zip(already_processed)
+                .filter(|(_, already_processed)| *already_processed)
+                .map(|((message_hash, _), _)| message_hash)
+                .collect()
+        };
+
+        let purged_count = already_processed_message_hashes.len();
+        if purged_count > 0 {
+            buffered_packet_batches.retain(|deserialized_packet| {
+                !already_processed_message_hashes
+                    .contains(deserialized_packet.immutable_section().message_hash())
+            });
+        }
+        purge_time.stop();
+        banking_stage_stats
+            .already_processed_purged_count
+            .fetch_add(purged_count, Ordering::Relaxed);
+        banking_stage_stats
+            .purge_already_processed_elapsed
+            .fetch_add(purge_time.as_us(), Ordering::Relaxed);
+        purged_count
+    }
+
     fn generate_packet_indexes(vers: &PacketBatch) -> Vec<usize> {
         vers.iter()
             .enumerate()
@@ -2028,6 +2498,7 @@ impl BankingStage {
         buffered_packet_batches: &mut UnprocessedPacketBatches,
         banking_stage_stats: &mut BankingStageStats,
         slot_metrics_tracker: &mut LeaderSlotMetricsTracker,
+        deserialize_thread_pool: &ThreadPool,
     ) -> Result<(), RecvTimeoutError> {
         let mut recv_time = Measure::start("receive_and_buffer_packets_recv");
         let packet_batches = Self::receive_until(
@@ -2067,6 +2538,7 @@ impl BankingStage {
                 &mut newly_buffered_packets_count,
                 banking_stage_stats,
                 slot_metrics_tracker,
+                deserialize_thread_pool,
             )
         }
         proc_start.stop();
@@ -2091,16 +2563,25 @@ impl BankingStage {
         banking_stage_stats
             .newly_buffered_packets_count
             .fetch_add(newly_buffered_packets_count, Ordering::Relaxed);
+        let dedup_stats = buffered_packet_batches.take_dedup_stats();
+        banking_stage_stats
+            .dropped_duplicated_packets_count
+            .fetch_add(dedup_stats.num_duplicates, Ordering::Relaxed);
+        banking_stage_stats.dedup_stats.merge(dedup_stats);
         banking_stage_stats
             .current_buffered_packet_batches_count
             .swap(buffered_packet_batches.len(), Ordering::Relaxed);
         banking_stage_stats
             .current_buffered_packets_count
             .swap(buffered_packet_batches.len(), Ordering::Relaxed);
+        banking_stage_stats
+            .current_buffered_bytes
+            .swap(buffered_packet_batches.buffered_bytes(), Ordering::Relaxed);
         *recv_start = Instant::now();
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn push_unprocessed(
         unprocessed_packet_batches: &mut UnprocessedPacketBatches,
         packet_batch: &PacketBatch,
@@ -2109,6 +2590,7 @@ impl BankingStage {
         newly_buffered_packets_count: &mut usize,
         banking_stage_stats: &mut BankingStageStats,
         slot_metrics_tracker: &mut LeaderSlotMetricsTracker,
+        deserialize_thread_pool: &ThreadPool,
     ) {
         if !packet_indexes.is_empty() {
             let _ = banking_stage_stats
@@ -2121,10 +2603,39 @@ impl BankingStage {
 
             let number_of_dropped_packets = unprocessed_packet_batches.insert_batch(
                 // Passing `None` for bank for now will make all packet weights 0
-                unprocessed_packet_batches::deserialize_packets(packet_batch, packet_indexes),
+                unprocessed_packet_batches::deserialize_packets_par(
+                    deserialize_thread_pool,
+                    packet_batch,
+                    packet_indexes,
+                    DESERIALIZE_PACKETS_PARALLEL_THRESHOLD,
+                    &mut banking_stage_stats.deserializer_stats,
+                )
+                .into_iter(),
             );
 
             saturating_add_assign!(*dropped_packets_count, number_of_dropped_packets);
+            banking_stage_stats.filtered_packets_count.fetch_add(
+                unprocessed_packet_batches.take_filtered_packets_count(),
+                Ordering::Relaxed,
+            );
+            banking_stage_stats.byte_evicted_packets_count.fetch_add(
+                unprocessed_packet_batches.take_byte_evicted_packets_count(),
+                Ordering::Relaxed,
+            );
+            banking_stage_stats.rate_limited_packets_count.fetch_add(
+                unprocessed_packet_batches.take_rate_limited_packets_count(),
+                Ordering::Relaxed,
+            );
+            banking_stage_stats.coalesced_packets_count.fetch_add(
+                unprocessed_packet_batches.take_coalesced_packets_count(),
+                Ordering::Relaxed,
+            );
+            banking_stage_stats
+                .too_many_account_locks_packets_count
+                .fetch_add(
+                    unprocessed_packet_batches.take_too_many_account_locks_packets_count(),
+                    Ordering::Relaxed,
+                );
             slot_metrics_tracker.increment_exceeded_buffer_limit_dropped_packets_count(
                 number_of_dropped_packets as u64,
             );
@@ -2160,6 +2671,22 @@ pub(crate) fn next_leader_tpu_vote(
     next_leader_x(cluster_info, poh_recorder, |leader| leader.tpu_vote)
 }
 
+/// Returns the leader `FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET` slots from now along with
+/// its stake and the total stake of the current epoch
+fn next_leader_stake(poh_recorder: &Mutex<PohRecorder>) -> Option<(Pubkey, u64, u64)> {
+    let (leader_pubkey, bank) = {
+        let poh = poh_recorder.lock().unwrap();
+        let leader_pubkey =
+            poh.leader_after_n_slots(FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET)?;
+        (leader_pubkey, poh.get_poh_recorder_bank().bank().clone())
+    };
+    let leader_stake = bank
+        .epoch_vote_accounts_for_node_id(&leader_pubkey)
+        .map(|node_vote_accounts| node_vote_accounts.total_stake)
+        .unwrap_or_default();
+    Some((leader_pubkey, leader_stake, bank.total_epoch_stake()))
+}
+
 fn next_leader_x<F>(
     cluster_info: &ClusterInfo,
     poh_recorder: &Mutex<PohRecorder>,
@@ -2184,7 +2711,6 @@ mod tests {
     use {
         super::*,
         crossbeam_channel::{unbounded, Receiver},
-        itertools::Itertools,
         solana_address_lookup_table_program::state::{AddressLookupTable, LookupTableMeta},
         solana_entry::entry::{next_entry, next_versioned_entry, Entry, EntrySlice},
         solana_gossip::{cluster_info::Node, contact_info::ContactInfo},
@@ -2540,6 +3066,8 @@ mod tests {
                     None,
                     gossip_vote_sender,
                     Arc::new(RwLock::new(CostModel::default())),
+                    Arc::default(),
+                    BankingStageConfig::default(),
                 );
 
                 // wait for banking_stage to eat the packets
@@ -3137,7 +3665,7 @@ mod tests {
         Blockstore::destroy(ledger_path.path()).unwrap();
     }
 
-    fn simulate_poh(
+    pub(super) fn simulate_poh(
         record_receiver: CrossbeamReceiver<Record>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
     ) -> JoinHandle<()> {
@@ -3233,41 +3761,6 @@ mod tests {
         Blockstore::destroy(ledger_path.path()).unwrap();
     }
 
-    #[test]
-    fn test_filter_valid_packets() {
-        solana_logger::setup();
-        let mut packets: Vec<DeserializedPacket> = (0..256)
-            .map(|packets_id| {
-                // packets are deserialized upon receiving, failed packets will not be
-                // forwarded; Therefore we need to create real packets here.
-                let keypair = Keypair::new();
-                let pubkey = solana_sdk::pubkey::new_rand();
-                let blockhash = Hash::new_unique();
-                let transaction = system_transaction::transfer(&keypair, &pubkey, 1, blockhash);
-                let mut p = Packet::from_data(None, &transaction).unwrap();
-                p.meta.port = packets_id;
-                DeserializedPacket::new(p).unwrap()
-            })
-            .collect_vec();
-
-        let result = BankingStage::filter_valid_packets_for_forwarding(packets.iter());
-        assert_eq!(result.len(), 256);
-
-        // packets in a batch are forwarded in arbitrary order; verify the ports match after
-        // sorting
-        let expected_ports: Vec<_> = (0..256).collect();
-        let mut forwarded_ports: Vec<_> = result.into_iter().map(|p| p.meta.port).collect();
-        forwarded_ports.sort_unstable();
-        assert_eq!(expected_ports, forwarded_ports);
-
-        let num_already_forwarded = 16;
-        for packet in &mut packets[0..num_already_forwarded] {
-            packet.forwarded = true;
-        }
-        let result = BankingStage::filter_valid_packets_for_forwarding(packets.iter());
-        assert_eq!(result.len(), packets.len() - num_already_forwarded);
-    }
-
     #[test]
     fn test_process_transactions_returns_unprocessed_txs() {
         solana_logger::setup();
@@ -4080,6 +4573,7 @@ mod tests {
                     &poh_recorder,
                     true,
                     &data_budget,
+                    &mut ForwardPacer::default(),
                     &mut LeaderSlotMetricsTracker::new(0),
                 );
 
@@ -4187,6 +4681,7 @@ mod tests {
                     &poh_recorder,
                     hold,
                     &DataBudget::default(),
+                    &mut ForwardPacer::default(),
                     &mut LeaderSlotMetricsTracker::new(0),
                 );
 
@@ -4223,6 +4718,51 @@ mod tests {
         Blockstore::destroy(ledger_path.path()).unwrap();
     }
 
+    #[test]
+    fn test_purge_already_processed_packets() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Bank::new_no_wallclock_throttle_for_tests(&genesis_config);
+        let processed_tx = system_transaction::transfer(
+            &mint_keypair,
+            &solana_sdk::pubkey::new_rand(),
+            1,
+            genesis_config.hash(),
+        );
+        let unprocessed_tx = system_transaction::transfer(
+            &mint_keypair,
+            &solana_sdk::pubkey::new_rand(),
+            2,
+            genesis_config.hash(),
+        );
+        assert_eq!(bank.process_transaction(&processed_tx), Ok(()));
+
+        let mut buffered_packet_batches = UnprocessedPacketBatches::from_iter(
+            transactions_to_deserialized_packets(&[processed_tx, unprocessed_tx.clone()]).unwrap(),
+            2,
+        );
+        assert_eq!(
+            BankingStage::purge_already_processed_packets(
+                &bank,
+                &mut buffered_packet_batches,
+                &BankingStageStats::default(),
+            ),
+            1
+        );
+        assert_eq!(buffered_packet_batches.len(), 1);
+        assert_eq!(
+            buffered_packet_batches
+                .pop_max()
+                .unwrap()
+                .immutable_section()
+                .message_hash(),
+            &unprocessed_tx.message().hash()
+        );
+    }
+
     #[cfg(test)]
     fn make_test_packets(
         transactions: Vec<Transaction>,
@@ -0,0 +1,445 @@
+//! Drives the buffered packet handling of the banking stage through a scripted timeline, so
+//! tests can assert which packets get included, forwarded, held or dropped as the leader
+//! schedule unfolds. Each step of the timeline delivers packets, moves the node to a leader
+//! phase and then runs one iteration of the banking loop. Nothing depends on wall clock time
+//! or on packets arriving over sockets, so the outcomes are deterministic.
+#![cfg(test)]
+use {
+    super::{tests::simulate_poh, *},
+    crossbeam_channel::unbounded,
+    solana_gossip::cluster_info::Node,
+    solana_ledger::{
+        blockstore::Blockstore,
+        genesis_utils::{create_genesis_config, GenesisConfigInfo},
+        get_tmp_ledger_path_auto_delete,
+        leader_schedule_cache::LeaderScheduleCache,
+    },
+    solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        native_token::LAMPORTS_PER_SOL,
+        poh_config::PohConfig,
+        signature::{Keypair, Signature, Signer},
+        system_instruction, system_program,
+        transaction::Transaction,
+    },
+    solana_streamer::socket::SocketAddrSpace,
+    std::{collections::BTreeSet, sync::atomic::AtomicBool},
+    unprocessed_packet_batches::DeserializedPacket,
+};
+
+/// Where the node stands in the leader schedule during a step of the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LeaderPhase {
+    /// The upcoming leader is unknown, so packets are held
+    Unknown,
+    /// Another node leads the upcoming slots, so packets are forwarded to it and dropped
+    OtherLeader,
+    /// This node leads within `HOLD_TRANSACTIONS_SLOT_OFFSET` slots, so packets are forwarded
+    /// to the current leader and held in case its slots are skipped
+    UpcomingLeader,
+    /// This node leads within `FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET` slots, so
+    /// packets are held for its own slots
+    ImminentLeader,
+    /// This node is leader and its working bank is available, so packets are consumed
+    Leader,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScriptedPacket {
+    name: &'static str,
+    compute_unit_price: u64,
+    /// Packets writing the same account conflict with each other
+    write_account: &'static str,
+}
+
+impl ScriptedPacket {
+    fn new(name: &'static str, compute_unit_price: u64, write_account: &'static str) -> Self {
+        Self {
+            name,
+            compute_unit_price,
+            write_account,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TimelineStep {
+    arrivals: Vec<ScriptedPacket>,
+    phase: Option<LeaderPhase>,
+}
+
+/// Steps of a timeline, each of which runs one iteration of the banking loop. The leader
+/// phase carries over to the following steps until changed.
+#[derive(Debug, Default)]
+struct Timeline {
+    steps: Vec<TimelineStep>,
+}
+
+impl Timeline {
+    /// Delivers `packets` at `step`
+    fn arrive(mut self, step: usize, packets: Vec<ScriptedPacket>) -> Self {
+        self.step_mut(step).arrivals.extend(packets);
+        self
+    }
+
+    /// Moves the node to `phase` at `step`
+    fn phase(mut self, step: usize, phase: LeaderPhase) -> Self {
+        self.step_mut(step).phase = Some(phase);
+        self
+    }
+
+    fn step_mut(&mut self, step: usize) -> &mut TimelineStep {
+        if self.steps.len() <= step {
+            self.steps.resize_with(step + 1, TimelineStep::default);
+        }
+        &mut self.steps[step]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PacketOutcome {
+    /// Committed to the working bank at the given step
+    Included(usize),
+    /// Forwarded to the leader and not included afterwards
+    Forwarded,
+    /// Still buffered at the end of the timeline
+    Buffered,
+    /// Neither included nor forwarded, and no longer buffered
+    Dropped,
+}
+
+#[derive(Debug, Default)]
+struct TimelineOutcome {
+    outcomes: HashMap<&'static str, PacketOutcome>,
+}
+
+impl TimelineOutcome {
+    fn outcome(&self, name: &str) -> PacketOutcome {
+        self.outcomes[name]
+    }
+
+    fn included(&self) -> BTreeSet<&'static str> {
+        self.packets_where(|outcome| matches!(outcome, PacketOutcome::Included(_)))
+    }
+
+    fn forwarded(&self) -> BTreeSet<&'static str> {
+        self.packets_where(|outcome| outcome == PacketOutcome::Forwarded)
+    }
+
+    fn buffered(&self) -> BTreeSet<&'static str> {
+        self.packets_where(|outcome| outcome == PacketOutcome::Buffered)
+    }
+
+    fn dropped(&self) -> BTreeSet<&'static str> {
+        self.packets_where(|outcome| outcome == PacketOutcome::Dropped)
+    }
+
+    fn packets_where(&self, predicate: impl Fn(PacketOutcome) -> bool) -> BTreeSet<&'static str> {
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| predicate(**outcome))
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+struct TimelineHarness {
+    buffer_capacity: usize,
+    packets_per_iteration: usize,
+}
+
+impl Default for TimelineHarness {
+    fn default() -> Self {
+        Self {
+            buffer_capacity: 100,
+            packets_per_iteration: UNPROCESSED_BUFFER_STEP_SIZE,
+        }
+    }
+}
+
+impl TimelineHarness {
+    fn run(&self, timeline: Timeline) -> TimelineOutcome {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let mut genesis_config_info = create_genesis_config(1_000 * LAMPORTS_PER_SOL);
+        // Leave room for every step in the one slot of the working bank
+        genesis_config_info.genesis_config.ticks_per_slot *= 8;
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            validator_pubkey,
+            ..
+        } = &genesis_config_info;
+        let bank = Arc::new(Bank::new_no_wallclock_throttle_for_tests(genesis_config));
+        let blockstore = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+        let exit = Arc::new(AtomicBool::default());
+        let (poh_recorder, _entry_receiver, record_receiver) = PohRecorder::new(
+            bank.tick_height(),
+            bank.last_blockhash(),
+            bank.clone(),
+            Some((4, 4)),
+            bank.ticks_per_slot(),
+            &Pubkey::default(),
+            &blockstore,
+            &Arc::new(LeaderScheduleCache::new_from_bank(&bank)),
+            &Arc::new(PohConfig::default()),
+            exit,
+        );
+        let poh_recorder = Arc::new(Mutex::new(poh_recorder));
+        let poh_simulator = simulate_poh(record_receiver, &poh_recorder);
+        let recorder = poh_recorder.lock().unwrap().recorder();
+
+        // The bootstrap validator leads every slot, so this node must be some other one
+        let my_pubkey = Pubkey::new_unique();
+        let leader_node = Node::new_localhost_with_pubkey(validator_pubkey);
+        let cluster_info = ClusterInfo::new(
+            leader_node.info,
+            Arc::new(Keypair::new()),
+            SocketAddrSpace::Unspecified,
+        );
+        let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+        let banking_stage_stats = BankingStageStats::default();
+        let qos_service = QosService::new(Arc::new(RwLock::new(CostModel::default())), 1);
+        let data_budget = DataBudget::default();
+        let mut forward_pacer = ForwardPacer::default();
+        let mut slot_metrics_tracker = LeaderSlotMetricsTracker::new(0);
+
+        let mut buffer: UnprocessedPacketBatches =
+            UnprocessedPacketBatches::with_capacity(self.buffer_capacity);
+        let mut signatures: HashMap<&'static str, Signature> = HashMap::new();
+        let mut message_hashes: HashMap<Hash, &'static str> = HashMap::new();
+        let mut included: HashMap<&'static str, usize> = HashMap::new();
+        let mut forwarded: HashSet<&'static str> = HashSet::new();
+        let mut phase = LeaderPhase::Unknown;
+
+        for (step, timeline_step) in timeline.steps.into_iter().enumerate() {
+            for scripted_packet in timeline_step.arrivals {
+                let transaction = scripted_transaction(&bank, mint_keypair, &scripted_packet);
+                signatures.insert(scripted_packet.name, transaction.signatures[0]);
+                let packet = Packet::from_data(None, &transaction).unwrap();
+                let deserialized_packet = DeserializedPacket::new(packet).unwrap();
+                message_hashes.insert(
+                    *deserialized_packet.immutable_section().message_hash(),
+                    scripted_packet.name,
+                );
+                buffer.push(deserialized_packet);
+            }
+            if let Some(next_phase) = timeline_step.phase {
+                if next_phase == LeaderPhase::Leader && !poh_recorder.lock().unwrap().has_bank() {
+                    poh_recorder.lock().unwrap().set_bank(&bank);
+                }
+                phase = next_phase;
+            }
+
+            let (leader_pubkey, working_bank, would_be_leader, would_be_leader_shortly) =
+                match phase {
+                    LeaderPhase::Unknown => (None, None, false, false),
+                    LeaderPhase::OtherLeader => (Some(*validator_pubkey), None, false, false),
+                    LeaderPhase::UpcomingLeader => (Some(*validator_pubkey), None, true, false),
+                    LeaderPhase::ImminentLeader => (Some(*validator_pubkey), None, true, true),
+                    LeaderPhase::Leader => (Some(my_pubkey), Some(&bank), true, true),
+                };
+            let decision = BankingStage::consume_or_forward_packets(
+                &my_pubkey,
+                leader_pubkey,
+                working_bank,
+                would_be_leader,
+                would_be_leader_shortly,
+            );
+            let buffered_before: HashSet<Hash> = buffer
+                .iter()
+                .filter(|deserialized_packet| !deserialized_packet.forwarded)
+                .map(|deserialized_packet| *deserialized_packet.immutable_section().message_hash())
+                .collect();
+            let hold = match decision {
+                BufferedPacketsDecision::Consume(_) => {
+                    BankingStage::consume_buffered_packets(
+                        &my_pubkey,
+                        // Steps don't take wall clock time, so the slot never runs out
+                        u128::MAX,
+                        &poh_recorder,
+                        &mut buffer,
+                        None,
+                        &gossip_vote_sender,
+                        None::<Box<dyn Fn()>>,
+                        &banking_stage_stats,
+                        &recorder,
+                        &qos_service,
+                        &mut slot_metrics_tracker,
+                        self.packets_per_iteration,
+                    );
+                    None
+                }
+                BufferedPacketsDecision::Forward => Some(false),
+                BufferedPacketsDecision::ForwardAndHold => Some(true),
+                BufferedPacketsDecision::Hold => None,
+            };
+            if let Some(hold) = hold {
+                BankingStage::handle_forwarding(
+                    &ForwardOption::ForwardTransaction,
+                    &cluster_info,
+                    &mut buffer,
+                    &poh_recorder,
+                    hold,
+                    &data_budget,
+                    &mut forward_pacer,
+                    &mut slot_metrics_tracker,
+                );
+                let still_unforwarded: HashSet<Hash> = buffer
+                    .iter()
+                    .filter(|deserialized_packet| !deserialized_packet.forwarded)
+                    .map(|deserialized_packet| {
+                        *deserialized_packet.immutable_section().message_hash()
+                    })
+                    .collect();
+                forwarded.extend(
+                    buffered_before
+                        .difference(&still_unforwarded)
+                        .map(|message_hash| message_hashes[message_hash]),
+                );
+            }
+
+            for (name, signature) in &signatures {
+                if !included.contains_key(name) && bank.get_signature_status(signature).is_some() {
+                    included.insert(name, step);
+                }
+            }
+        }
+
+        let still_buffered: HashSet<&'static str> = buffer
+            .iter()
+            .map(|deserialized_packet| {
+                message_hashes[deserialized_packet.immutable_section().message_hash()]
+            })
+            .collect();
+        let outcomes = signatures
+            .keys()
+            .map(|name| {
+                let outcome = if let Some(step) = included.get(name) {
+                    PacketOutcome::Included(*step)
+                } else if still_buffered.contains(name) {
+                    PacketOutcome::Buffered
+                } else if forwarded.contains(name) {
+                    PacketOutcome::Forwarded
+                } else {
+                    PacketOutcome::Dropped
+                };
+                (*name, outcome)
+            })
+            .collect();
+
+        poh_recorder
+            .lock()
+            .unwrap()
+            .is_exited
+            .store(true, Ordering::Relaxed);
+        poh_simulator.join().unwrap();
+        TimelineOutcome { outcomes }
+    }
+}
+
+/// Returns a transaction for `scripted_packet` paid for by a freshly funded fee payer, so
+/// that only the write account of the packet can make it conflict with others
+fn scripted_transaction(
+    bank: &Bank,
+    mint_keypair: &Keypair,
+    scripted_packet: &ScriptedPacket,
+) -> Transaction {
+    let payer = Keypair::new();
+    bank.transfer(LAMPORTS_PER_SOL, mint_keypair, &payer.pubkey())
+        .unwrap();
+    let write_account = Pubkey::create_with_seed(
+        &mint_keypair.pubkey(),
+        scripted_packet.write_account,
+        &system_program::id(),
+    )
+    .unwrap();
+    Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_price(scripted_packet.compute_unit_price),
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &write_account,
+                bank.get_minimum_balance_for_rent_exemption(0),
+            ),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer],
+        bank.last_blockhash(),
+    )
+}
+
+#[test]
+fn test_timeline_holds_packets_until_leader() {
+    let timeline = Timeline::default()
+        .arrive(0, vec![ScriptedPacket::new("a", 1, "x")])
+        .phase(1, LeaderPhase::ImminentLeader)
+        .arrive(1, vec![ScriptedPacket::new("b", 1, "y")])
+        .phase(2, LeaderPhase::Leader);
+    let outcome = TimelineHarness::default().run(timeline);
+    assert_eq!(outcome.outcome("a"), PacketOutcome::Included(2));
+    assert_eq!(outcome.outcome("b"), PacketOutcome::Included(2));
+}
+
+#[test]
+fn test_timeline_conflicts_resolve_by_priority() {
+    let timeline = Timeline::default()
+        .phase(0, LeaderPhase::Leader)
+        .arrive(
+            0,
+            vec![
+                ScriptedPacket::new("low", 1, "x"),
+                ScriptedPacket::new("high", 10, "x"),
+                ScriptedPacket::new("other", 1, "y"),
+            ],
+        )
+        .phase(1, LeaderPhase::Leader);
+    let outcome = TimelineHarness::default().run(timeline);
+    assert_eq!(outcome.outcome("high"), PacketOutcome::Included(0));
+    assert_eq!(outcome.outcome("other"), PacketOutcome::Included(0));
+    // The lower priority packet loses the lock on `x` and is retried on the next iteration
+    assert_eq!(outcome.outcome("low"), PacketOutcome::Included(1));
+}
+
+#[test]
+fn test_timeline_forwards_to_other_leaders() {
+    let timeline = Timeline::default()
+        .phase(0, LeaderPhase::OtherLeader)
+        .arrive(0, vec![ScriptedPacket::new("dropped", 1, "x")])
+        .phase(1, LeaderPhase::UpcomingLeader)
+        .arrive(1, vec![ScriptedPacket::new("held", 1, "y")])
+        .phase(2, LeaderPhase::Unknown)
+        .arrive(2, vec![ScriptedPacket::new("late", 1, "z")]);
+    let outcome = TimelineHarness::default().run(timeline);
+    assert_eq!(outcome.forwarded(), BTreeSet::from(["dropped"]));
+    // Forwarded packets held for this node's own slots stay buffered
+    assert_eq!(outcome.buffered(), BTreeSet::from(["held", "late"]));
+    assert!(outcome.included().is_empty());
+
+    let timeline = Timeline::default()
+        .phase(0, LeaderPhase::UpcomingLeader)
+        .arrive(0, vec![ScriptedPacket::new("held", 1, "x")])
+        .phase(1, LeaderPhase::Leader);
+    let outcome = TimelineHarness::default().run(timeline);
+    assert_eq!(outcome.outcome("held"), PacketOutcome::Included(1));
+}
+
+#[test]
+fn test_timeline_full_buffer_drops_lowest_priority() {
+    let timeline = Timeline::default()
+        .arrive(
+            0,
+            vec![
+                ScriptedPacket::new("a", 5, "x"),
+                ScriptedPacket::new("b", 1, "y"),
+                ScriptedPacket::new("c", 3, "z"),
+            ],
+        )
+        .phase(1, LeaderPhase::Leader);
+    let harness = TimelineHarness {
+        buffer_capacity: 2,
+        ..TimelineHarness::default()
+    };
+    let outcome = harness.run(timeline);
+    assert_eq!(outcome.included(), BTreeSet::from(["a", "c"]));
+    assert_eq!(outcome.dropped(), BTreeSet::from(["b"]));
+}
@@ -59,6 +59,17 @@ pub(crate) type RetransmitSlotsReceiver = Receiver<Slot>;
 pub(crate) type RecordReceiver = Receiver<(Arc<Vec<Shred>>, Option<BroadcastShredBatchInfo>)>;
 pub(crate) type TransmitReceiver = Receiver<(Arc<Vec<Shred>>, Option<BroadcastShredBatchInfo>)>;
 
+/// Tuning of the standard broadcast stage
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BroadcastConfig {
+    /// Number of threads generating coding shreds; the shredder's thread pool, shared with
+    /// data shredding, is used if unset
+    pub coding_threads: Option<usize>,
+    /// Generate coding shreds on a thread of their own, so that shredding the entries of a
+    /// slot doesn't wait for the coding shreds of the previous slot's tail
+    pub pipeline_coding: bool,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum BroadcastStageReturnType {
     ChannelDisconnected,
@@ -84,6 +95,7 @@ impl BroadcastStageType {
         blockstore: &Arc<Blockstore>,
         bank_forks: &Arc<RwLock<BankForks>>,
         shred_version: u16,
+        config: &BroadcastConfig,
     ) -> BroadcastStage {
         match self {
             BroadcastStageType::Standard => BroadcastStage::new(
@@ -94,7 +106,7 @@ impl BroadcastStageType {
                 exit_sender,
                 blockstore,
                 bank_forks,
-                StandardBroadcastRun::new(shred_version),
+                StandardBroadcastRun::new_with_config(shred_version, config),
             ),
 
             BroadcastStageType::FailEntryVerification => BroadcastStage::new(
@@ -8,12 +8,13 @@ use {
     crate::{
         broadcast_stage::broadcast_utils::UnfinishedSlotInfo, cluster_nodes::ClusterNodesCache,
     },
+    rayon::ThreadPool,
     solana_entry::entry::Entry,
     solana_ledger::shred::{
         ProcessShredsStats, Shred, ShredFlags, Shredder, MAX_DATA_SHREDS_PER_FEC_BLOCK,
     },
     solana_sdk::{
-        signature::Keypair,
+        signature::{Keypair, Signer},
         timing::{duration_as_us, AtomicInterval},
     },
     std::{sync::RwLock, time::Duration},
@@ -31,14 +32,43 @@ pub struct StandardBroadcastRun {
     last_datapoint_submit: Arc<AtomicInterval>,
     num_batches: usize,
     cluster_nodes_cache: Arc<ClusterNodesCache<BroadcastStage>>,
+    coding_thread_pool: Option<Arc<ThreadPool>>,
+    pipeline_coding: bool,
+    // Sends batches to the coding thread, which is started by the first batch
+    coding_sender: Option<Sender<CodingJob>>,
+    coding_keypair: Option<Arc<Keypair>>,
 }
 
+/// Data shreds taken from the buffer of an unfinished slot, to be erasure coded
+#[derive(Default)]
+struct CodingBatch {
+    data_shreds: Vec<Shred>,
+    is_slot_end: bool,
+    next_code_index: u32,
+}
+
+type CodingJob = (Arc<Keypair>, CodingBatch, Option<BroadcastShredBatchInfo>);
+
 impl StandardBroadcastRun {
+    #[cfg(test)]
     pub(super) fn new(shred_version: u16) -> Self {
+        Self::new_with_config(shred_version, &BroadcastConfig::default())
+    }
+
+    pub(super) fn new_with_config(shred_version: u16, config: &BroadcastConfig) -> Self {
         let cluster_nodes_cache = Arc::new(ClusterNodesCache::<BroadcastStage>::new(
             CLUSTER_NODES_CACHE_NUM_EPOCH_CAP,
             CLUSTER_NODES_CACHE_TTL,
         ));
+        let coding_thread_pool = config.coding_threads.map(|num_threads| {
+            Arc::new(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(num_threads)
+                    .thread_name(|ix| format!("broadcast_coding_{}", ix))
+                    .build()
+                    .unwrap(),
+            )
+        });
         Self {
             process_shreds_stats: ProcessShredsStats::default(),
             transmit_shreds_stats: Arc::default(),
@@ -50,9 +80,45 @@ impl StandardBroadcastRun {
             last_datapoint_submit: Arc::default(),
             num_batches: 0,
             cluster_nodes_cache,
+            coding_thread_pool,
+            pipeline_coding: config.pipeline_coding,
+            coding_sender: None,
+            coding_keypair: None,
         }
     }
 
+    // Hands the batch to the coding thread, which sends the coding shreds on once generated.
+    fn send_coding_batch(
+        &mut self,
+        keypair: &Keypair,
+        coding_batch: CodingBatch,
+        batch_info: Option<BroadcastShredBatchInfo>,
+        socket_sender: &Sender<(Arc<Vec<Shred>>, Option<BroadcastShredBatchInfo>)>,
+        blockstore_sender: &Sender<(Arc<Vec<Shred>>, Option<BroadcastShredBatchInfo>)>,
+    ) -> Result<()> {
+        // The identity may be changed at runtime
+        let coding_keypair = match &self.coding_keypair {
+            Some(coding_keypair) if coding_keypair.pubkey() == keypair.pubkey() => {
+                coding_keypair.clone()
+            }
+            _ => {
+                let coding_keypair = Arc::new(Keypair::from_bytes(&keypair.to_bytes()).unwrap());
+                self.coding_keypair = Some(coding_keypair.clone());
+                coding_keypair
+            }
+        };
+        let coding_thread_pool = &self.coding_thread_pool;
+        let coding_sender = self.coding_sender.get_or_insert_with(|| {
+            spawn_coding_thread(
+                coding_thread_pool.clone(),
+                socket_sender.clone(),
+                blockstore_sender.clone(),
+            )
+        });
+        coding_sender.send((coding_keypair, coding_batch, batch_info))?;
+        Ok(())
+    }
+
     // If the current slot has changed, generates an empty shred indicating
     // last shred in the previous slot, along with coding shreds for the data
     // shreds buffered.
@@ -84,10 +150,13 @@ impl StandardBroadcastRun {
                 );
                 shred.sign(keypair);
                 state.data_shreds_buffer.push(shred.clone());
-                let mut shreds = make_coding_shreds(
-                    keypair,
+                let mut shreds = take_coding_batch(
                     &mut self.unfinished_slot,
                     true, // is_last_in_slot
+                )
+                .make_coding_shreds(
+                    keypair,
+                    self.coding_thread_pool.as_deref(),
                     stats,
                 );
                 shreds.insert(0, shred);
@@ -283,18 +352,28 @@ impl StandardBroadcastRun {
         blockstore_sender.send((data_shreds, batch_info.clone()))?;
 
         // Create and send coding shreds
-        let coding_shreds = make_coding_shreds(
-            keypair,
-            &mut self.unfinished_slot,
-            is_last_in_slot,
-            &mut process_stats,
-        );
-        let coding_shreds = Arc::new(coding_shreds);
-        debug_assert!(coding_shreds
-            .iter()
-            .all(|shred| shred.slot() == bank.slot()));
-        socket_sender.send((coding_shreds.clone(), batch_info.clone()))?;
-        blockstore_sender.send((coding_shreds, batch_info))?;
+        let coding_batch = take_coding_batch(&mut self.unfinished_slot, is_last_in_slot);
+        if self.pipeline_coding {
+            self.send_coding_batch(
+                keypair,
+                coding_batch,
+                batch_info,
+                socket_sender,
+                blockstore_sender,
+            )?;
+        } else {
+            let coding_shreds = coding_batch.make_coding_shreds(
+                keypair,
+                self.coding_thread_pool.as_deref(),
+                &mut process_stats,
+            );
+            let coding_shreds = Arc::new(coding_shreds);
+            debug_assert!(coding_shreds
+                .iter()
+                .all(|shred| shred.slot() == bank.slot()));
+            socket_sender.send((coding_shreds.clone(), batch_info.clone()))?;
+            blockstore_sender.send((coding_shreds, batch_info))?;
+        }
 
         coding_send_time.stop();
 
@@ -410,15 +489,43 @@ impl StandardBroadcastRun {
     }
 }
 
-// Consumes data_shreds_buffer returning corresponding coding shreds.
-fn make_coding_shreds(
-    keypair: &Keypair,
+impl CodingBatch {
+    fn make_coding_shreds(
+        &self,
+        keypair: &Keypair,
+        thread_pool: Option<&ThreadPool>,
+        stats: &mut ProcessShredsStats,
+    ) -> Vec<Shred> {
+        match thread_pool {
+            Some(thread_pool) => Shredder::data_shreds_to_coding_shreds_with_thread_pool(
+                thread_pool,
+                keypair,
+                &self.data_shreds,
+                self.is_slot_end,
+                self.next_code_index,
+                stats,
+            ),
+            None => Shredder::data_shreds_to_coding_shreds(
+                keypair,
+                &self.data_shreds,
+                self.is_slot_end,
+                self.next_code_index,
+                stats,
+            ),
+        }
+        .unwrap()
+    }
+}
+
+// Consumes data_shreds_buffer, returning the batch to generate the corresponding coding
+// shreds from. The next code index is advanced past those coding shreds up front, so that
+// the batch can be erasure coded while the next one is taken.
+fn take_coding_batch(
     unfinished_slot: &mut Option<UnfinishedSlotInfo>,
     is_slot_end: bool,
-    stats: &mut ProcessShredsStats,
-) -> Vec<Shred> {
+) -> CodingBatch {
     let unfinished_slot = match unfinished_slot {
-        None => return Vec::default(),
+        None => return CodingBatch::default(),
         Some(state) => state,
     };
     let data_shreds: Vec<_> = {
@@ -434,23 +541,65 @@ fn make_coding_shreds(
             .drain(0..size - offset)
             .collect()
     };
-    let shreds = Shredder::data_shreds_to_coding_shreds(
-        keypair,
-        &data_shreds,
+    let next_code_index = unfinished_slot.next_code_index;
+    if !data_shreds.is_empty() {
+        // Each FEC set starts MAX_DATA_SHREDS_PER_FEC_BLOCK code indices after the previous
+        // one; see Shredder::data_shreds_to_coding_shreds.
+        let fec_set_size = MAX_DATA_SHREDS_PER_FEC_BLOCK as usize;
+        let num_leading_fec_sets = (data_shreds.len() - 1) / fec_set_size;
+        let last_fec_set_size = data_shreds.len() - num_leading_fec_sets * fec_set_size;
+        let num_code_indices = num_leading_fec_sets * fec_set_size
+            + Shredder::num_coding_shreds(last_fec_set_size, is_slot_end);
+        unfinished_slot.next_code_index = next_code_index + num_code_indices as u32;
+    }
+    CodingBatch {
+        data_shreds,
         is_slot_end,
-        unfinished_slot.next_code_index,
-        stats,
-    )
-    .unwrap();
-    if let Some(index) = shreds
-        .iter()
-        .filter(|shred| shred.is_code())
-        .map(Shred::index)
-        .max()
-    {
-        unfinished_slot.next_code_index = unfinished_slot.next_code_index.max(index + 1);
+        next_code_index,
     }
-    shreds
+}
+
+// Erasure codes batches off the broadcast thread, so that shredding the entries of the next
+// slot doesn't wait for the coding shreds of the previous one. The thread exits once the
+// broadcast thread drops its sender.
+fn spawn_coding_thread(
+    thread_pool: Option<Arc<ThreadPool>>,
+    socket_sender: Sender<(Arc<Vec<Shred>>, Option<BroadcastShredBatchInfo>)>,
+    blockstore_sender: Sender<(Arc<Vec<Shred>>, Option<BroadcastShredBatchInfo>)>,
+) -> Sender<CodingJob> {
+    let (sender, receiver) = unbounded::<CodingJob>();
+    Builder::new()
+        .name("solana-broadcaster-coding".to_string())
+        .spawn(move || {
+            let mut stats = ProcessShredsStats::default();
+            for (keypair, coding_batch, batch_info) in receiver {
+                let coding_shreds = Arc::new(coding_batch.make_coding_shreds(
+                    &keypair,
+                    thread_pool.as_deref(),
+                    &mut stats,
+                ));
+                if coding_batch.is_slot_end {
+                    if let Some(batch_info) = &batch_info {
+                        datapoint_info!(
+                            "broadcast-pipelined-coding-stats",
+                            ("slot", batch_info.slot, i64),
+                            ("gen_coding_time", stats.gen_coding_elapsed, i64),
+                            ("sign_coding_time", stats.sign_coding_elapsed, i64),
+                        );
+                    }
+                    stats = ProcessShredsStats::default();
+                }
+                if socket_sender
+                    .send((coding_shreds.clone(), batch_info.clone()))
+                    .is_err()
+                    || blockstore_sender.send((coding_shreds, batch_info)).is_err()
+                {
+                    break;
+                }
+            }
+        })
+        .unwrap();
+    sender
 }
 
 impl BroadcastRun for StandardBroadcastRun {
@@ -771,6 +920,71 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_pipelined_coding_shreds() {
+        let num_shreds_per_slot = 2;
+        let (
+            _blockstore,
+            genesis_config,
+            _cluster_info,
+            bank,
+            leader_keypair,
+            _socket,
+            _bank_forks,
+        ) = setup(num_shreds_per_slot);
+        let broadcast_shreds = |config: &BroadcastConfig| {
+            // The first shred is inserted, which would offset the shreds of the next run
+            let blockstore = Arc::new(Blockstore::open(&get_tmp_ledger_path!()).unwrap());
+            let (bsend, brecv) = unbounded();
+            let (ssend, _srecv) = unbounded();
+            let mut standard_broadcast_run = StandardBroadcastRun::new_with_config(0, config);
+            let mut last_tick_height = 0;
+            for i in 0..4 {
+                let ticks = create_ticks((i + 1) * 100, 0, genesis_config.hash());
+                last_tick_height += (ticks.len() - 1) as u64;
+                let receive_results = ReceiveResults {
+                    entries: ticks,
+                    time_elapsed: Duration::new(1, 0),
+                    bank: bank.clone(),
+                    last_tick_height,
+                };
+                standard_broadcast_run
+                    .process_receive_results(
+                        &leader_keypair,
+                        &blockstore,
+                        &ssend,
+                        &bsend,
+                        receive_results,
+                    )
+                    .unwrap();
+            }
+            let mut coding_shreds = Vec::<Shred>::new();
+            while let Ok((recv_shreds, _)) = brecv.recv_timeout(Duration::from_secs(1)) {
+                coding_shreds.extend(recv_shreds.iter().filter(|shred| shred.is_code()).cloned());
+            }
+            coding_shreds.sort_by_key(Shred::index);
+            (
+                coding_shreds,
+                standard_broadcast_run
+                    .unfinished_slot
+                    .as_ref()
+                    .unwrap()
+                    .next_code_index,
+            )
+        };
+
+        let (coding_shreds, next_code_index) = broadcast_shreds(&BroadcastConfig::default());
+        assert!(coding_shreds.len() >= 32);
+        assert_eq!(next_code_index, coding_shreds.last().unwrap().index() + 1);
+        assert_eq!(
+            broadcast_shreds(&BroadcastConfig {
+                coding_threads: Some(2),
+                pipeline_coding: true,
+            }),
+            (coding_shreds, next_code_index)
+        );
+    }
+
     #[test]
     fn test_slot_finish() {
         // Setup
@@ -1,15 +1,15 @@
 //! this service receives instruction ExecuteTimings from replay_stage,
 //! update cost_model which is shared with banking_stage to optimize
 //! packing transactions into block; it also triggers persisting cost
-//! table to blockstore.
+//! table to blockstore, along with the aggregated cost of each frozen block.
 
 use {
     crossbeam_channel::Receiver,
-    solana_ledger::blockstore::Blockstore,
+    solana_ledger::{blockstore::Blockstore, blockstore_meta::BlockCost},
     solana_measure::measure::Measure,
     solana_program_runtime::timings::ExecuteTimings,
-    solana_runtime::{bank::Bank, cost_model::CostModel},
-    solana_sdk::timing::timestamp,
+    solana_runtime::{bank::Bank, cost_model::CostModel, cost_tracker::CostTracker},
+    solana_sdk::{pubkey::Pubkey, timing::timestamp},
     std::{
         sync::{Arc, RwLock},
         thread::{self, Builder, JoinHandle},
@@ -55,6 +55,8 @@ impl CostUpdateServiceTiming {
 pub enum CostUpdate {
     FrozenBank {
         bank: Arc<Bank>,
+        /// Compute units consumed by each program when replaying the bank
+        program_compute_units: Vec<(Pubkey, u64)>,
     },
     ExecuteTiming {
         execute_timings: Box<ExecuteTimings>,
@@ -89,15 +91,30 @@ impl CostUpdateService {
     }
 
     fn service_loop(
-        _blockstore: Arc<Blockstore>,
+        blockstore: Arc<Blockstore>,
         cost_model: Arc<RwLock<CostModel>>,
         cost_update_receiver: CostUpdateReceiver,
     ) {
         let mut cost_update_service_timing = CostUpdateServiceTiming::default();
         for cost_update in cost_update_receiver.iter() {
             match cost_update {
-                CostUpdate::FrozenBank { bank } => {
-                    bank.read_cost_tracker().unwrap().report_stats(bank.slot());
+                CostUpdate::FrozenBank {
+                    bank,
+                    program_compute_units,
+                } => {
+                    let block_cost = {
+                        let cost_tracker = bank.read_cost_tracker().unwrap();
+                        cost_tracker.report_stats(bank.slot());
+                        Self::block_cost(&bank, &cost_tracker, program_compute_units)
+                    };
+                    if let Err(err) = blockstore.write_block_cost(bank.slot(), &block_cost) {
+                        warn!(
+                            "failed to write the cost of slot {}: {:?}",
+                            bank.slot(),
+                            err
+                        );
+                    }
+                    bank.report_write_lock_heat_map();
                 }
                 CostUpdate::ExecuteTiming {
                     mut execute_timings,
@@ -113,6 +130,23 @@ impl CostUpdateService {
         }
     }
 
+    fn block_cost(
+        bank: &Bank,
+        cost_tracker: &CostTracker,
+        mut program_compute_units: Vec<(Pubkey, u64)>,
+    ) -> BlockCost {
+        program_compute_units.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        BlockCost {
+            compute_units: program_compute_units
+                .iter()
+                .fold(0, |total, (_, units)| total.saturating_add(*units)),
+            program_compute_units,
+            block_cost: cost_tracker.block_cost(),
+            block_cost_limit: cost_tracker.block_cost_limit(),
+            fees: bank.collector_fees(),
+        }
+    }
+
     fn update_cost_model(
         cost_model: &RwLock<CostModel>,
         execute_timings: &mut ExecuteTimings,
@@ -142,6 +176,30 @@ impl CostUpdateService {
 mod tests {
     use {super::*, solana_program_runtime::timings::ProgramTiming, solana_sdk::pubkey::Pubkey};
 
+    #[test]
+    fn test_block_cost() {
+        let bank = Bank::default_for_tests();
+        let cost_tracker = CostTracker::default();
+        let program_key_1 = Pubkey::new_unique();
+        let program_key_2 = Pubkey::new_unique();
+
+        let block_cost = CostUpdateService::block_cost(
+            &bank,
+            &cost_tracker,
+            vec![(program_key_1, 100), (program_key_2, 300)],
+        );
+        assert_eq!(
+            block_cost,
+            BlockCost {
+                compute_units: 400,
+                program_compute_units: vec![(program_key_2, 300), (program_key_1, 100)],
+                block_cost: 0,
+                block_cost_limit: cost_tracker.block_cost_limit(),
+                fees: 0,
+            }
+        );
+    }
+
     #[test]
     fn test_update_cost_model_with_empty_execute_timings() {
         let cost_model = Arc::new(RwLock::new(CostModel::default()));
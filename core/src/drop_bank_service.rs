@@ -1,13 +1,27 @@
+//! The `drop_bank_service` drops the banks pruned from bank forks on a background thread.
+//! Tearing down a bank frees its state and queues its accounts-cache slot for purging, which
+//! adds up when a large fork is pruned at once, so banks are dropped in time-bounded rounds on
+//! a low-priority thread to keep fork churn from stalling replay.
 use {
     crossbeam_channel::Receiver,
     solana_measure::measure::Measure,
+    solana_perf::thread::renice_this_thread,
     solana_runtime::bank::Bank,
     std::{
+        collections::VecDeque,
         sync::Arc,
         thread::{self, Builder, JoinHandle},
+        time::{Duration, Instant},
     },
 };
 
+/// Time spent dropping banks in one round
+const DROP_BANKS_TIME_BUDGET: Duration = Duration::from_millis(20);
+/// Pause between rounds while more banks are queued
+const DROP_BANKS_PAUSE: Duration = Duration::from_millis(20);
+/// Nice value adjustment of the service thread, so bank teardown yields the CPU to replay
+const DROP_BANK_SERVICE_NICENESS_ADJ: i8 = 10;
+
 pub struct DropBankService {
     thread_hdl: JoinHandle<()>,
 }
@@ -17,17 +31,24 @@ impl DropBankService {
         let thread_hdl = Builder::new()
             .name("sol-drop-b-service".to_string())
             .spawn(move || {
-                for banks in bank_receiver.iter() {
-                    let len = banks.len();
-                    let mut dropped_banks_time = Measure::start("drop_banks");
-                    drop(banks);
-                    dropped_banks_time.stop();
-                    if dropped_banks_time.as_ms() > 10 {
-                        datapoint_info!(
-                            "handle_new_root-dropped_banks",
-                            ("elapsed_ms", dropped_banks_time.as_ms(), i64),
-                            ("len", len, i64)
-                        );
+                if let Err(err) = renice_this_thread(DROP_BANK_SERVICE_NICENESS_ADJ) {
+                    warn!(
+                        "Unable to lower the priority of the drop bank service: {}",
+                        err
+                    );
+                }
+                let mut pending_banks = VecDeque::new();
+                loop {
+                    if pending_banks.is_empty() {
+                        match bank_receiver.recv() {
+                            Ok(banks) => pending_banks.extend(banks),
+                            Err(_) => break,
+                        }
+                    }
+                    pending_banks.extend(bank_receiver.try_iter().flatten());
+                    Self::drop_banks(&mut pending_banks, DROP_BANKS_TIME_BUDGET);
+                    if !pending_banks.is_empty() {
+                        thread::sleep(DROP_BANKS_PAUSE);
                     }
                 }
             })
@@ -35,7 +56,78 @@ impl DropBankService {
         Self { thread_hdl }
     }
 
+    /// Drops queued banks, oldest first, until `time_budget` is used up. At least one bank is
+    /// dropped per call. Returns the number of banks dropped.
+    fn drop_banks(pending_banks: &mut VecDeque<Arc<Bank>>, time_budget: Duration) -> usize {
+        let mut dropped_banks_time = Measure::start("drop_banks");
+        let start = Instant::now();
+        let mut num_dropped = 0;
+        while let Some(bank) = pending_banks.pop_front() {
+            drop(bank);
+            num_dropped += 1;
+            if start.elapsed() >= time_budget {
+                break;
+            }
+        }
+        dropped_banks_time.stop();
+        if dropped_banks_time.as_ms() > 10 || !pending_banks.is_empty() {
+            datapoint_info!(
+                "handle_new_root-dropped_banks",
+                ("elapsed_ms", dropped_banks_time.as_ms(), i64),
+                ("len", num_dropped, i64),
+                ("pending", pending_banks.len(), i64)
+            );
+        }
+        num_dropped
+    }
+
     pub fn join(self) -> thread::Result<()> {
         self.thread_hdl.join()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crossbeam_channel::unbounded,
+        solana_runtime::genesis_utils::create_genesis_config, solana_sdk::pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_drop_banks_time_budget() {
+        let genesis_config = create_genesis_config(100).genesis_config;
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let mut pending_banks: VecDeque<_> = (1..=3)
+            .map(|slot| Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), slot)))
+            .collect();
+        let bank3 = Arc::downgrade(&pending_banks[2]);
+
+        // an exhausted budget still makes progress
+        assert_eq!(
+            DropBankService::drop_banks(&mut pending_banks, Duration::ZERO),
+            1
+        );
+        assert_eq!(pending_banks.len(), 2);
+        assert_eq!(
+            DropBankService::drop_banks(&mut pending_banks, Duration::from_secs(60)),
+            2
+        );
+        assert!(pending_banks.is_empty());
+        assert!(bank3.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_drop_bank_service() {
+        let genesis_config = create_genesis_config(100).genesis_config;
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
+        let bank1_weak = Arc::downgrade(&bank1);
+
+        let (drop_bank_sender, drop_bank_receiver) = unbounded();
+        let drop_bank_service = DropBankService::new(drop_bank_receiver);
+        drop_bank_sender.send(vec![bank1]).unwrap();
+        drop(drop_bank_sender);
+        drop_bank_service.join().unwrap();
+        assert!(bank1_weak.upgrade().is_none());
+    }
+}
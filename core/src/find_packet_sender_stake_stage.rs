@@ -1,5 +1,6 @@
 use {
     crossbeam_channel::{Receiver, RecvTimeoutError, Sender},
+    solana_geyser_plugin_manager::ingest_plugin_manager::IngestPluginManager,
     solana_measure::measure::Measure,
     solana_perf::packet::PacketBatch,
     solana_sdk::timing::timestamp,
@@ -31,6 +32,8 @@ struct FindPacketSenderStakeStats {
     total_packets: u64,
     total_discard_random: usize,
     total_discard_random_time_us: usize,
+    total_ingest_rejected: usize,
+    total_ingest_plugin_time_us: usize,
 }
 
 impl FindPacketSenderStakeStats {
@@ -64,6 +67,12 @@ impl FindPacketSenderStakeStats {
                     self.total_discard_random_time_us,
                     i64
                 ),
+                ("total_ingest_rejected", self.total_ingest_rejected, i64),
+                (
+                    "total_ingest_plugin_time_us",
+                    self.total_ingest_plugin_time_us,
+                    i64
+                ),
             );
             *self = FindPacketSenderStakeStats::default();
             self.last_print = now;
@@ -80,6 +89,7 @@ impl FindPacketSenderStakeStage {
         packet_receiver: streamer::PacketBatchReceiver,
         sender: FindPacketSenderStakeSender,
         staked_nodes: Arc<RwLock<HashMap<IpAddr, u64>>>,
+        ingest_plugin_manager: Option<Arc<IngestPluginManager>>,
         name: &'static str,
     ) -> Self {
         let mut stats = FindPacketSenderStakeStats::default();
@@ -110,6 +120,16 @@ impl FindPacketSenderStakeStage {
                         apply_stake();
                         apply_sender_stakes_time.stop();
 
+                        // Plugins see the stake of the senders
+                        let mut ingest_plugin_time = Measure::start("ingest_plugin_time");
+                        let num_ingest_rejected = ingest_plugin_manager
+                            .as_ref()
+                            .map(|ingest_plugin_manager| {
+                                Self::apply_ingest_plugins(&mut batches, ingest_plugin_manager)
+                            })
+                            .unwrap_or_default();
+                        ingest_plugin_time.stop();
+
                         let mut send_batches_time = Measure::start("send_batches_time");
                         if let Err(e) = sender.send(batches) {
                             info!("Sender error: {:?}", e);
@@ -131,6 +151,8 @@ impl FindPacketSenderStakeStage {
                             stats.total_packets.saturating_add(num_packets as u64);
                         stats.total_discard_random_time_us += discard_random_time.as_us() as usize;
                         stats.total_discard_random += num_discarded_randomly;
+                        stats.total_ingest_plugin_time_us += ingest_plugin_time.as_us() as usize;
+                        stats.total_ingest_rejected += num_ingest_rejected;
                     }
                     Err(e) => match e {
                         StreamerError::RecvTimeout(RecvTimeoutError::Disconnected) => break,
@@ -157,6 +179,25 @@ impl FindPacketSenderStakeStage {
             });
     }
 
+    // Discards the packets rejected by the ingest plugins, returning their number
+    fn apply_ingest_plugins(
+        batches: &mut [PacketBatch],
+        ingest_plugin_manager: &IngestPluginManager,
+    ) -> usize {
+        let mut num_rejected = 0;
+        batches
+            .iter_mut()
+            .flat_map(|batch| batch.iter_mut())
+            .filter(|packet| !packet.meta.discard())
+            .for_each(|packet| {
+                if !ingest_plugin_manager.admit_packet(packet) {
+                    packet.meta.set_discard(true);
+                    num_rejected += 1;
+                }
+            });
+        num_rejected
+    }
+
     pub fn join(self) -> thread::Result<()> {
         self.thread_hdl.join()
     }
@@ -0,0 +1,293 @@
+//! The `forward_pacer` module spreads the forwarding of buffered packets to
+//! upcoming leaders over time instead of dumping the whole buffer at a slot
+//! boundary. Each leader is also given a byte budget that scales with its
+//! share of the stake, so a single leader doesn't absorb the entire buffer.
+use {
+    solana_sdk::pubkey::Pubkey,
+    std::time::{Duration, Instant},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardPacingConfig {
+    /// Maximum number of packets forwarded per second
+    pub max_packets_per_second: u64,
+    /// Maximum number of bytes forwarded per second
+    pub max_bytes_per_second: u64,
+    /// Window over which unused pacing tokens can accumulate; bounds the size of a burst
+    pub burst_window: Duration,
+    /// Bytes every leader may receive from this node, regardless of its stake
+    pub min_leader_budget_bytes: u64,
+    /// Additional bytes a leader may receive, scaled by its share of the total stake
+    pub stake_weighted_leader_budget_bytes: u64,
+}
+
+impl Default for ForwardPacingConfig {
+    fn default() -> Self {
+        Self {
+            max_packets_per_second: 10_000,
+            max_bytes_per_second: 10_000 * 1200,
+            burst_window: Duration::from_millis(100),
+            min_leader_budget_bytes: 100 * 1200,
+            stake_weighted_leader_budget_bytes: 40_000 * 1200,
+        }
+    }
+}
+
+impl ForwardPacingConfig {
+    /// Returns the number of bytes a leader holding `leader_stake` out of
+    /// `total_stake` may receive while it is the forwarding target
+    pub fn leader_budget_bytes(&self, leader_stake: u64, total_stake: u64) -> u64 {
+        let stake_weighted = if total_stake == 0 {
+            0
+        } else {
+            (u128::from(self.stake_weighted_leader_budget_bytes) * u128::from(leader_stake)
+                / u128::from(total_stake)) as u64
+        };
+        self.min_leader_budget_bytes.saturating_add(stake_weighted)
+    }
+}
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Token bucket refilled continuously at `rate` tokens per second, starting out full
+#[derive(Debug)]
+struct TokenBucket {
+    rate: u64,
+    capacity: u64,
+    tokens: u64,
+    /// Fraction of a token accumulated by past refills, in `rate` * nanoseconds, so that
+    /// frequent refills add up to whole tokens instead of each rounding down to none
+    partial_token: u128,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, burst_window: Duration) -> Self {
+        let capacity = (u128::from(rate) * burst_window.as_micros() / 1_000_000) as u64;
+        Self {
+            rate,
+            capacity: capacity.max(1),
+            tokens: capacity.max(1),
+            partial_token: 0,
+        }
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        let accumulated = u128::from(self.rate)
+            .saturating_mul(elapsed.as_nanos())
+            .saturating_add(self.partial_token);
+        let new_tokens = u64::try_from(accumulated / NANOS_PER_SECOND).unwrap_or(u64::MAX);
+        self.tokens = self.tokens.saturating_add(new_tokens).min(self.capacity);
+        // A full bucket doesn't accumulate anything, not even part of a token
+        self.partial_token = if self.tokens == self.capacity {
+            0
+        } else {
+            accumulated % NANOS_PER_SECOND
+        };
+    }
+}
+
+#[derive(Debug)]
+pub struct ForwardPacer {
+    config: ForwardPacingConfig,
+    packets: TokenBucket,
+    bytes: TokenBucket,
+    last_refill: Instant,
+    current_leader: Option<Pubkey>,
+    leader_bytes_remaining: u64,
+}
+
+impl Default for ForwardPacer {
+    fn default() -> Self {
+        Self::new(ForwardPacingConfig::default())
+    }
+}
+
+impl ForwardPacer {
+    pub fn new(config: ForwardPacingConfig) -> Self {
+        Self {
+            packets: TokenBucket::new(config.max_packets_per_second, config.burst_window),
+            bytes: TokenBucket::new(config.max_bytes_per_second, config.burst_window),
+            last_refill: Instant::now(),
+            current_leader: None,
+            leader_bytes_remaining: 0,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &ForwardPacingConfig {
+        &self.config
+    }
+
+    /// Sets the leader packets will be forwarded to. The leader's budget is
+    /// reset only when the leader changes.
+    pub fn set_leader(&mut self, leader: &Pubkey, leader_stake: u64, total_stake: u64) {
+        if self.current_leader.as_ref() != Some(leader) {
+            self.current_leader = Some(*leader);
+            self.leader_bytes_remaining =
+                self.config.leader_budget_bytes(leader_stake, total_stake);
+        }
+    }
+
+    /// Adds the tokens accumulated since the last refill
+    pub fn refill(&mut self) {
+        self.refill_at(Instant::now());
+    }
+
+    fn refill_at(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.packets.refill(elapsed);
+        self.bytes.refill(elapsed);
+        self.last_refill = now;
+    }
+
+    /// Consumes budget for one packet of `size` bytes. Returns false, without
+    /// consuming anything, if any of the pacing or leader budgets are exhausted.
+    #[must_use]
+    pub fn try_take(&mut self, size: usize) -> bool {
+        let size = size as u64;
+        if self.packets.tokens == 0
+            || self.bytes.tokens < size
+            || self.leader_bytes_remaining < size
+        {
+            return false;
+        }
+        self.packets.tokens -= 1;
+        self.bytes.tokens -= size;
+        self.leader_bytes_remaining -= size;
+        true
+    }
+
+    pub fn leader_bytes_remaining(&self) -> u64 {
+        self.leader_bytes_remaining
+    }
+
+    /// Number of packets that may be forwarded right now
+    pub fn packets_available(&self) -> usize {
+        self.packets.tokens as usize
+    }
+
+    /// Number of bytes that may be forwarded right now, within the leader's budget
+    pub fn bytes_available(&self) -> usize {
+        self.bytes.tokens.min(self.leader_bytes_remaining) as usize
+    }
+
+    /// Consumes budget for `num_packets` packets totalling `num_bytes`, which must be
+    /// within `packets_available()` and `bytes_available()`
+    pub fn consume(&mut self, num_packets: usize, num_bytes: usize) {
+        let num_bytes = num_bytes as u64;
+        self.packets.tokens = self.packets.tokens.saturating_sub(num_packets as u64);
+        self.bytes.tokens = self.bytes.tokens.saturating_sub(num_bytes);
+        self.leader_bytes_remaining = self.leader_bytes_remaining.saturating_sub(num_bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leader_budget_bytes() {
+        let config = ForwardPacingConfig {
+            min_leader_budget_bytes: 10,
+            stake_weighted_leader_budget_bytes: 100,
+            ..ForwardPacingConfig::default()
+        };
+        assert_eq!(config.leader_budget_bytes(0, 0), 10);
+        assert_eq!(config.leader_budget_bytes(0, 100), 10);
+        assert_eq!(config.leader_budget_bytes(25, 100), 35);
+        assert_eq!(config.leader_budget_bytes(100, 100), 110);
+    }
+
+    #[test]
+    fn test_forward_pacer_packet_limit() {
+        let mut pacer = ForwardPacer::new(ForwardPacingConfig {
+            max_packets_per_second: 20,
+            burst_window: Duration::from_millis(100),
+            ..ForwardPacingConfig::default()
+        });
+        pacer.set_leader(&Pubkey::new_unique(), 1, 1);
+        assert!(pacer.try_take(1));
+        assert!(pacer.try_take(1));
+        assert!(!pacer.try_take(1));
+    }
+
+    #[test]
+    fn test_forward_pacer_refill_in_small_steps() {
+        let mut pacer = ForwardPacer::new(ForwardPacingConfig {
+            max_packets_per_second: 10_000,
+            max_bytes_per_second: 10_000 * 1200,
+            burst_window: Duration::from_millis(100),
+            ..ForwardPacingConfig::default()
+        });
+        pacer.consume(pacer.packets_available(), 0);
+        assert_eq!(pacer.packets_available(), 0);
+
+        // Each refill is worth only a fraction of a packet, yet they add up
+        let mut now = pacer.last_refill;
+        for _ in 0..1000 {
+            now += Duration::from_micros(30);
+            pacer.refill_at(now);
+        }
+        assert_eq!(pacer.packets_available(), 300);
+
+        // Unless the bucket is full, in which case the time is lost
+        let mut pacer = ForwardPacer::new(ForwardPacingConfig {
+            max_packets_per_second: 10,
+            burst_window: Duration::from_secs(1),
+            ..ForwardPacingConfig::default()
+        });
+        let now = pacer.last_refill + Duration::from_millis(50);
+        pacer.refill_at(now);
+        pacer.consume(1, 0);
+        pacer.refill_at(now + Duration::from_millis(50));
+        assert_eq!(pacer.packets_available(), 9);
+        pacer.refill_at(now + Duration::from_millis(100));
+        assert_eq!(pacer.packets_available(), 10);
+    }
+
+    #[test]
+    fn test_forward_pacer_leader_budget() {
+        let mut pacer = ForwardPacer::new(ForwardPacingConfig {
+            min_leader_budget_bytes: 100,
+            stake_weighted_leader_budget_bytes: 0,
+            ..ForwardPacingConfig::default()
+        });
+        let leader = Pubkey::new_unique();
+        pacer.set_leader(&leader, 1, 1);
+        assert!(pacer.try_take(60));
+        assert!(!pacer.try_take(60));
+
+        // Same leader does not reset the budget
+        pacer.set_leader(&leader, 1, 1);
+        assert!(!pacer.try_take(60));
+        assert!(pacer.try_take(40));
+
+        // New leader gets a fresh budget
+        pacer.set_leader(&Pubkey::new_unique(), 1, 1);
+        assert_eq!(pacer.leader_bytes_remaining(), 100);
+        assert!(pacer.try_take(60));
+    }
+
+    #[test]
+    fn test_forward_pacer_consume() {
+        let mut pacer = ForwardPacer::new(ForwardPacingConfig {
+            max_packets_per_second: 20,
+            burst_window: Duration::from_millis(100),
+            min_leader_budget_bytes: 100,
+            stake_weighted_leader_budget_bytes: 0,
+            ..ForwardPacingConfig::default()
+        });
+        pacer.set_leader(&Pubkey::new_unique(), 1, 1);
+        assert_eq!(pacer.packets_available(), 2);
+        assert_eq!(pacer.bytes_available(), 100);
+
+        pacer.consume(1, 60);
+        assert_eq!(pacer.packets_available(), 1);
+        assert_eq!(pacer.bytes_available(), 40);
+        assert!(!pacer.try_take(60));
+
+        pacer.consume(2, 60);
+        assert_eq!(pacer.packets_available(), 0);
+        assert_eq!(pacer.bytes_available(), 0);
+    }
+}
@@ -124,6 +124,9 @@ struct LeaderSlotPacketCountMetrics {
 
     // total number of packets removed at the end of the slot due to being too old, duplicate, etc.
     end_of_slot_filtered_invalid_count: u64,
+
+    // total number of buffered packets purged because they already landed on the current fork
+    already_processed_purged_count: u64,
 }
 
 impl LeaderSlotPacketCountMetrics {
@@ -226,6 +229,11 @@ impl LeaderSlotPacketCountMetrics {
                 self.end_of_slot_filtered_invalid_count as i64,
                 i64
             ),
+            (
+                "already_processed_purged_count",
+                self.already_processed_purged_count as i64,
+                i64
+            ),
             (
                 "end_of_slot_unprocessed_buffer_len",
                 self.end_of_slot_unprocessed_buffer_len as i64,
@@ -563,6 +571,17 @@ impl LeaderSlotMetricsTracker {
         }
     }
 
+    pub(crate) fn increment_already_processed_purged_count(&mut self, count: u64) {
+        if let Some(leader_slot_metrics) = &mut self.leader_slot_metrics {
+            saturating_add_assign!(
+                leader_slot_metrics
+                    .packet_count_metrics
+                    .already_processed_purged_count,
+                count
+            );
+        }
+    }
+
     pub(crate) fn set_end_of_slot_unprocessed_buffer_len(&mut self, len: u64) {
         if let Some(leader_slot_metrics) = &mut self.leader_slot_metrics {
             leader_slot_metrics
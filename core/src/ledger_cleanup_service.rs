@@ -3,24 +3,33 @@
 //! can be done quickly and should have a fairly stable correlation to actual bytes.
 //! Once the shred count (and thus roughly the byte count) reaches a threshold,
 //! the services begins removing data in FIFO order.
+//!
+//! With a byte budget, the service also measures the bytes the blockstore columns hold,
+//! transaction statuses and address signatures included, and derives from them how many shreds
+//! fit in the budget. Only rooted slots older than the latest root are removed, oldest first,
+//! and the columns not keyed by slot are reclaimed as the compaction filter drops the entries
+//! of the removed slots.
 
 use {
     crossbeam_channel::{Receiver, RecvTimeoutError},
     rand::{thread_rng, Rng},
+    solana_geyser_plugin_manager::slot_purge_notifier::SlotPurgeNotifierImpl,
     solana_ledger::{
         blockstore::{Blockstore, PurgeType},
         blockstore_db::Result as BlockstoreResult,
+        blockstore_meta::SlotPurgeReason,
     },
     solana_measure::measure::Measure,
     solana_sdk::clock::{Slot, DEFAULT_TICKS_PER_SLOT, TICKS_PER_DAY},
     std::{
+        cmp::min,
         string::ToString,
         sync::{
             atomic::{AtomicBool, AtomicU64, Ordering},
-            Arc,
+            Arc, Mutex, RwLock,
         },
         thread::{self, sleep, Builder, JoinHandle},
-        time::Duration,
+        time::{Duration, Instant},
     },
 };
 
@@ -37,6 +46,9 @@ pub const DEFAULT_MAX_LEDGER_SHREDS: u64 = 200_000_000;
 // Allow down to 50m, or 3.5 days at idle, 1hr at 50k load, around ~100GB
 pub const DEFAULT_MIN_MAX_LEDGER_SHREDS: u64 = 50_000_000;
 
+// A byte budget below 100GB would keep about as few slots as `DEFAULT_MIN_MAX_LEDGER_SHREDS`
+pub const DEFAULT_MIN_MAX_LEDGER_BYTES: u64 = 100 * 1024 * 1024 * 1024;
+
 // Check for removing slots at this interval so we don't purge too often
 // and starve other blockstore users.
 pub const DEFAULT_PURGE_SLOT_INTERVAL: u64 = 512;
@@ -45,6 +57,100 @@ pub const DEFAULT_PURGE_SLOT_INTERVAL: u64 = 512;
 // Once a day should be ample
 const DEFAULT_COMPACTION_SLOT_INTERVAL: u64 = TICKS_PER_DAY / DEFAULT_TICKS_PER_SLOT;
 
+// How long listeners may hold off a purge before it goes ahead regardless
+pub const DEFAULT_SLOT_PURGE_GRACE_WINDOW: Duration = Duration::from_secs(300);
+
+/// Receives notice of slot ranges purged by the `LedgerCleanupService`, e.g. an
+/// external indexer or archive uploader that must copy the data out first
+pub trait SlotPurgeListener: Send + Sync {
+    /// Called before slots `first_slot..=last_slot` are purged. Returning false
+    /// holds the purge off; it is retried on later roots until the listeners
+    /// approve it or the grace window elapses.
+    fn approve_slots_purge(&self, first_slot: Slot, last_slot: Slot) -> bool;
+
+    /// Called after slots `first_slot..=last_slot` have been purged
+    fn notify_slots_purged(&self, first_slot: Slot, last_slot: Slot);
+}
+
+impl SlotPurgeListener for SlotPurgeNotifierImpl {
+    fn approve_slots_purge(&self, first_slot: Slot, last_slot: Slot) -> bool {
+        SlotPurgeNotifierImpl::approve_slots_purge(self, first_slot, last_slot)
+    }
+
+    fn notify_slots_purged(&self, first_slot: Slot, last_slot: Slot) {
+        SlotPurgeNotifierImpl::notify_slots_purged(self, first_slot, last_slot)
+    }
+}
+
+/// Registry of the `SlotPurgeListener`s consulted by the `LedgerCleanupService`.
+/// Clones share the same registry.
+#[derive(Clone)]
+pub struct SlotPurgeListeners {
+    listeners: Arc<RwLock<Vec<Arc<dyn SlotPurgeListener>>>>,
+    grace_window: Duration,
+    held_off_since: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Default for SlotPurgeListeners {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOT_PURGE_GRACE_WINDOW)
+    }
+}
+
+impl SlotPurgeListeners {
+    pub fn new(grace_window: Duration) -> Self {
+        Self {
+            listeners: Arc::default(),
+            grace_window,
+            held_off_since: Arc::default(),
+        }
+    }
+
+    pub fn register(&self, listener: Arc<dyn SlotPurgeListener>) {
+        self.listeners.write().unwrap().push(listener);
+    }
+
+    pub fn grace_window(&self) -> Duration {
+        self.grace_window
+    }
+
+    /// Returns true if slots `first_slot..=last_slot` may be purged now: either
+    /// every listener approved, or the purge has been held off for longer than
+    /// the grace window
+    pub fn approve_purge(&self, first_slot: Slot, last_slot: Slot) -> bool {
+        let approved = self
+            .listeners
+            .read()
+            .unwrap()
+            .iter()
+            .fold(true, |approved, listener| {
+                listener.approve_slots_purge(first_slot, last_slot) && approved
+            });
+        let mut held_off_since = self.held_off_since.lock().unwrap();
+        if approved {
+            *held_off_since = None;
+            return true;
+        }
+        let held_off_for = held_off_since.get_or_insert_with(Instant::now).elapsed();
+        if held_off_for < self.grace_window {
+            inc_new_counter_info!("ledger_cleanup_service-purge_held_off", 1);
+            return false;
+        }
+        warn!(
+            "purging slots {}..={} despite listeners holding it off for {:?}",
+            first_slot, last_slot, held_off_for
+        );
+        *held_off_since = None;
+        true
+    }
+
+    pub fn notify_purged(&self, first_slot: Slot, last_slot: Slot) {
+        for listener in self.listeners.read().unwrap().iter() {
+            listener.notify_slots_purged(first_slot, last_slot);
+        }
+    }
+}
+
 pub struct LedgerCleanupService {
     t_cleanup: JoinHandle<()>,
     t_compact: JoinHandle<()>,
@@ -55,9 +161,11 @@ impl LedgerCleanupService {
         new_root_receiver: Receiver<Slot>,
         blockstore: Arc<Blockstore>,
         max_ledger_shreds: u64,
+        max_ledger_bytes: Option<u64>,
         exit: &Arc<AtomicBool>,
         compaction_interval: Option<u64>,
         max_compaction_jitter: Option<u64>,
+        slot_purge_listeners: SlotPurgeListeners,
     ) -> Self {
         let exit = exit.clone();
         let mut last_purge_slot = 0;
@@ -68,8 +176,9 @@ impl LedgerCleanupService {
         let last_compact_slot2 = last_compact_slot.clone();
 
         info!(
-            "LedgerCleanupService active. max ledger shreds={}, compaction interval={}",
-            max_ledger_shreds, compaction_interval,
+            "LedgerCleanupService active. max ledger shreds={}, max ledger bytes={:?}, \
+             compaction interval={}",
+            max_ledger_shreds, max_ledger_bytes, compaction_interval,
         );
 
         let exit_compact = exit.clone();
@@ -85,9 +194,11 @@ impl LedgerCleanupService {
                     &new_root_receiver,
                     &blockstore,
                     max_ledger_shreds,
+                    max_ledger_bytes,
                     &mut last_purge_slot,
                     DEFAULT_PURGE_SLOT_INTERVAL,
                     &last_compact_slot,
+                    &slot_purge_listeners,
                 ) {
                     match e {
                         RecvTimeoutError::Disconnected => break,
@@ -134,10 +245,15 @@ impl LedgerCleanupService {
     ///   range of the clean up.
     /// - `total_shreds` (u64): the total estimated number of shreds before the
     ///   `root`.
+    ///
+    /// With `max_ledger_bytes`, the number of shreds kept is also bounded by
+    /// how many fit in that many bytes, and the slots from `root` onwards are
+    /// never cleaned.
     fn find_slots_to_clean(
         blockstore: &Arc<Blockstore>,
         root: Slot,
         max_ledger_shreds: u64,
+        max_ledger_bytes: Option<u64>,
     ) -> (bool, Slot, Slot, u64) {
         let mut total_slots = Vec::new();
         let mut iterate_time = Measure::start("iterate_time");
@@ -164,6 +280,12 @@ impl LedgerCleanupService {
             max_ledger_shreds,
             iterate_time
         );
+        let max_ledger_shreds = match max_ledger_bytes.and_then(|max_ledger_bytes| {
+            Self::max_shreds_for_bytes(blockstore, max_ledger_bytes, total_shreds)
+        }) {
+            Some(max_shreds_for_bytes) => min(max_ledger_shreds, max_shreds_for_bytes),
+            None => max_ledger_shreds,
+        };
         if (total_shreds as u64) < max_ledger_shreds {
             return (false, 0, 0, total_shreds);
         }
@@ -176,10 +298,44 @@ impl LedgerCleanupService {
                 break;
             }
         }
+        if max_ledger_bytes.is_some() {
+            // Keep the root and the slots that may still become rooted
+            if root <= first_slot {
+                return (false, 0, 0, total_shreds);
+            }
+            lowest_cleanup_slot = min(lowest_cleanup_slot, root - 1);
+        }
 
         (true, first_slot, lowest_cleanup_slot, total_shreds)
     }
 
+    /// Returns how many shreds fit in `max_ledger_bytes`, assuming each of
+    /// the `total_shreds` of the ledger accounts for the same share of the
+    /// bytes held by the blockstore columns
+    fn max_shreds_for_bytes(
+        blockstore: &Blockstore,
+        max_ledger_bytes: u64,
+        total_shreds: u64,
+    ) -> Option<u64> {
+        let ledger_bytes: u64 = match blockstore.column_storage_sizes() {
+            Ok(column_sizes) => column_sizes.iter().map(|(_, size)| size).sum(),
+            Err(err) => {
+                warn!("purge: failed to measure the ledger size: {:?}", err);
+                return None;
+            }
+        };
+        info!(
+            "ledger_bytes={} max_ledger_bytes={}",
+            ledger_bytes, max_ledger_bytes
+        );
+        if ledger_bytes == 0 {
+            return None;
+        }
+        let max_shreds =
+            u128::from(max_ledger_bytes) * u128::from(total_shreds) / u128::from(ledger_bytes);
+        Some(u64::try_from(max_shreds).unwrap_or(u64::MAX))
+    }
+
     fn receive_new_roots(new_root_receiver: &Receiver<Slot>) -> Result<Slot, RecvTimeoutError> {
         let root = new_root_receiver.recv_timeout(Duration::from_secs(1))?;
         // Get the newest root
@@ -194,6 +350,9 @@ impl LedgerCleanupService {
     /// [`new_root_receiver`]: signal receiver which contains the information
     ///   about what `Slot` is the current root.
     /// [`max_ledger_shreds`]: the number of shreds to keep since the new root.
+    /// [`max_ledger_bytes`]: if set, the number of bytes the blockstore
+    ///   columns may hold.  Fewer shreds than `max_ledger_shreds` are kept if
+    ///   they take more space, and the new root is never purged.
     /// [`last_purge_slot`]: an both an input and output parameter indicating
     ///   the id of the last purged slot.  As an input parameter, it works
     ///   together with `purge_interval` on whether it is too early to perform
@@ -208,15 +367,21 @@ impl LedgerCleanupService {
     ///   slot which has been cleaned up after this call.  If this parameter is
     ///   updated after this function call, it means the ledger cleanup has
     ///   been performed.
+    /// [`slot_purge_listeners`]: the listeners that must approve a purge
+    ///   before it happens, and are notified once it is done.  A purge they
+    ///   hold off is retried on the next root rather than after another
+    ///   `purge_interval`.
     ///
     /// Also see `blockstore::purge_slot`.
     pub fn cleanup_ledger(
         new_root_receiver: &Receiver<Slot>,
         blockstore: &Arc<Blockstore>,
         max_ledger_shreds: u64,
+        max_ledger_bytes: Option<u64>,
         last_purge_slot: &mut u64,
         purge_interval: u64,
         last_compact_slot: &Arc<AtomicU64>,
+        slot_purge_listeners: &SlotPurgeListeners,
     ) -> Result<(), RecvTimeoutError> {
         let root = Self::receive_new_roots(new_root_receiver)?;
         if root - *last_purge_slot <= purge_interval {
@@ -229,16 +394,27 @@ impl LedgerCleanupService {
             root, last_purge_slot, purge_interval, disk_utilization_pre
         );
 
-        *last_purge_slot = root;
-
         let (slots_to_clean, purge_first_slot, lowest_cleanup_slot, total_shreds) =
-            Self::find_slots_to_clean(blockstore, root, max_ledger_shreds);
+            Self::find_slots_to_clean(blockstore, root, max_ledger_shreds, max_ledger_bytes);
+
+        if slots_to_clean
+            && !slot_purge_listeners.approve_purge(purge_first_slot, lowest_cleanup_slot)
+        {
+            info!(
+                "purge: slots {} to {} held off by listeners",
+                purge_first_slot, lowest_cleanup_slot
+            );
+            return Ok(());
+        }
+
+        *last_purge_slot = root;
 
         if slots_to_clean {
             let purge_complete = Arc::new(AtomicBool::new(false));
             let blockstore = blockstore.clone();
             let purge_complete1 = purge_complete.clone();
             let last_compact_slot1 = last_compact_slot.clone();
+            let slot_purge_listeners = slot_purge_listeners.clone();
             let _t_purge = Builder::new()
                 .name("solana-ledger-purge".to_string())
                 .spawn(move || {
@@ -270,12 +446,37 @@ impl LedgerCleanupService {
                     // transaction_status and address_signatures CFs. These are fine because they
                     // don't require strong consistent view for their operation.
                     blockstore.set_max_expired_slot(lowest_cleanup_slot);
+                    if let Err(err) = blockstore.record_slot_purge(
+                        purge_first_slot,
+                        lowest_cleanup_slot,
+                        SlotPurgeReason::LedgerCleanup,
+                    ) {
+                        warn!(
+                            "failed to record purge of slots {} to {}: {:?}",
+                            purge_first_slot, lowest_cleanup_slot, err
+                        );
+                    }
+                    if max_ledger_bytes.is_some() {
+                        // Reclaim the purged slots now rather than at the next
+                        // compaction, or the next cleanup would measure them
+                        // and purge more than needed
+                        if let Err(err) =
+                            blockstore.compact_storage(purge_first_slot, lowest_cleanup_slot)
+                        {
+                            warn!(
+                                "failed to compact slots {} to {}: {:?}",
+                                purge_first_slot, lowest_cleanup_slot, err
+                            );
+                        }
+                    }
 
                     purge_time.stop();
                     info!("{}", purge_time);
 
                     last_compact_slot1.store(lowest_cleanup_slot, Ordering::Relaxed);
 
+                    slot_purge_listeners.notify_purged(purge_first_slot, lowest_cleanup_slot);
+
                     purge_complete1.store(true, Ordering::Relaxed);
                 })
                 .unwrap();
@@ -375,9 +576,11 @@ mod tests {
             &receiver,
             &blockstore,
             5,
+            None,
             &mut last_purge_slot,
             10,
             &highest_compaction_slot,
+            &SlotPurgeListeners::default(),
         )
         .unwrap();
         assert_eq!(last_purge_slot, 50);
@@ -405,6 +608,161 @@ mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_cleanup_byte_budget() {
+        solana_logger::setup();
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 50, 5);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let blockstore = Arc::new(blockstore);
+        let ledger_bytes: u64 = blockstore
+            .column_storage_sizes()
+            .unwrap()
+            .iter()
+            .map(|(_, size)| size)
+            .sum();
+        assert!(ledger_bytes > 0);
+
+        // The whole ledger fits in the budget
+        let (slots_to_clean, ..) = LedgerCleanupService::find_slots_to_clean(
+            &blockstore,
+            50,
+            u64::MAX,
+            Some(ledger_bytes),
+        );
+        assert!(!slots_to_clean);
+
+        // Half of the ledger fits in the budget, which is stricter than the shred limit
+        let (slots_to_clean, first_slot, lowest_cleanup_slot, _) =
+            LedgerCleanupService::find_slots_to_clean(
+                &blockstore,
+                50,
+                u64::MAX,
+                Some(ledger_bytes / 2),
+            );
+        assert!(slots_to_clean);
+        assert_eq!(first_slot, 0);
+        assert!((20..30).contains(&lowest_cleanup_slot));
+
+        // Slots from the root onwards are kept regardless of the budget
+        let (sender, receiver) = unbounded();
+        let mut last_purge_slot = 0;
+        let highest_compaction_slot = Arc::new(AtomicU64::new(0));
+        sender.send(40).unwrap();
+        LedgerCleanupService::cleanup_ledger(
+            &receiver,
+            &blockstore,
+            u64::MAX,
+            Some(1),
+            &mut last_purge_slot,
+            10,
+            &highest_compaction_slot,
+            &SlotPurgeListeners::default(),
+        )
+        .unwrap();
+        assert_eq!(highest_compaction_slot.load(Ordering::Relaxed), 39);
+        assert_eq!(
+            blockstore.slot_meta_iterator(0).unwrap().next().unwrap().0,
+            40
+        );
+
+        drop(blockstore);
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
+    #[derive(Default)]
+    struct TestSlotPurgeListener {
+        approve: AtomicBool,
+        purged: Mutex<Vec<(Slot, Slot)>>,
+    }
+
+    impl SlotPurgeListener for TestSlotPurgeListener {
+        fn approve_slots_purge(&self, _first_slot: Slot, _last_slot: Slot) -> bool {
+            self.approve.load(Ordering::Relaxed)
+        }
+
+        fn notify_slots_purged(&self, first_slot: Slot, last_slot: Slot) {
+            self.purged.lock().unwrap().push((first_slot, last_slot));
+        }
+    }
+
+    #[test]
+    fn test_cleanup_held_off_by_listener() {
+        solana_logger::setup();
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 50, 5);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let blockstore = Arc::new(blockstore);
+        let (sender, receiver) = unbounded();
+
+        let listener = Arc::new(TestSlotPurgeListener::default());
+        let slot_purge_listeners = SlotPurgeListeners::new(Duration::from_secs(3600));
+        slot_purge_listeners.register(listener.clone());
+
+        // The listener holds the purge off
+        let mut last_purge_slot = 0;
+        let highest_compaction_slot = Arc::new(AtomicU64::new(0));
+        sender.send(50).unwrap();
+        LedgerCleanupService::cleanup_ledger(
+            &receiver,
+            &blockstore,
+            5,
+            None,
+            &mut last_purge_slot,
+            10,
+            &highest_compaction_slot,
+            &slot_purge_listeners,
+        )
+        .unwrap();
+        assert_eq!(last_purge_slot, 0);
+        assert_eq!(highest_compaction_slot.load(Ordering::Relaxed), 0);
+        assert_eq!(
+            blockstore.slot_meta_iterator(0).unwrap().next().unwrap().0,
+            0
+        );
+        assert!(listener.purged.lock().unwrap().is_empty());
+
+        // Once approved, the purge is retried on the next root
+        listener.approve.store(true, Ordering::Relaxed);
+        sender.send(51).unwrap();
+        LedgerCleanupService::cleanup_ledger(
+            &receiver,
+            &blockstore,
+            5,
+            None,
+            &mut last_purge_slot,
+            10,
+            &highest_compaction_slot,
+            &slot_purge_listeners,
+        )
+        .unwrap();
+        assert_eq!(last_purge_slot, 51);
+        assert_eq!(highest_compaction_slot.load(Ordering::Relaxed), 44);
+        assert_eq!(*listener.purged.lock().unwrap(), vec![(0, 44)]);
+
+        drop(blockstore);
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
+    #[test]
+    fn test_slot_purge_listeners_grace_window() {
+        let listener = Arc::new(TestSlotPurgeListener::default());
+        let slot_purge_listeners = SlotPurgeListeners::new(Duration::ZERO);
+        assert!(slot_purge_listeners.approve_purge(0, 10));
+
+        // A purge held off for longer than the grace window goes ahead
+        slot_purge_listeners.register(listener.clone());
+        assert!(slot_purge_listeners.approve_purge(0, 10));
+
+        let slot_purge_listeners = SlotPurgeListeners::new(Duration::from_secs(3600));
+        slot_purge_listeners.register(listener.clone());
+        assert!(!slot_purge_listeners.approve_purge(0, 10));
+        listener.approve.store(true, Ordering::Relaxed);
+        assert!(slot_purge_listeners.approve_purge(0, 10));
+    }
+
     #[test]
     fn test_cleanup_speed() {
         solana_logger::setup();
@@ -445,9 +803,11 @@ mod tests {
                 &receiver,
                 &blockstore,
                 initial_slots,
+                None,
                 &mut last_purge_slot,
                 10,
                 &last_compaction_slot,
+                &SlotPurgeListeners::default(),
             )
             .unwrap();
             time.stop();
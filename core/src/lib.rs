@@ -26,6 +26,7 @@ pub mod duplicate_repair_status;
 pub mod fetch_stage;
 pub mod find_packet_sender_stake_stage;
 pub mod fork_choice;
+pub mod forward_pacer;
 pub mod gen_keys;
 pub mod heaviest_subtree_fork_choice;
 pub mod latest_validator_votes_for_frozen_banks;
@@ -35,8 +36,11 @@ pub mod ledger_cleanup_service;
 pub mod ledger_metric_report_service;
 pub mod optimistic_confirmation_verifier;
 pub mod outstanding_requests;
+#[cfg(feature = "fault-injection")]
+pub mod packet_fault_injection;
 pub mod packet_hasher;
 pub mod packet_threshold;
+pub mod packet_view;
 pub mod poh_timing_report_service;
 pub mod poh_timing_reporter;
 pub mod progress_map;
@@ -53,8 +57,10 @@ pub mod retransmit_stage;
 pub mod rewards_recorder_service;
 pub mod sample_performance_service;
 pub mod serve_repair;
+pub mod serve_repair_admission;
 pub mod serve_repair_service;
 pub mod shred_fetch_stage;
+pub mod shutdown_coordinator;
 pub mod sigverify;
 pub mod sigverify_shreds;
 pub mod sigverify_stage;
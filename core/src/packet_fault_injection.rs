@@ -0,0 +1,201 @@
+//! Fault injection into the packet buffers of the banking stage, so the retry and forwarding
+//! logic downstream of them can be chaos-tested against lost, late and duplicated packets.
+//! Only built with the `fault-injection` feature, and only active in banking threads when
+//! `SOLANA_PACKET_FAULT_INJECTION` is set.
+use {
+    crate::unprocessed_packet_batches::DeserializedPacket,
+    rand::{thread_rng, Rng},
+    solana_sdk::timing::AtomicInterval,
+    std::{env, str::FromStr, thread::sleep, time::Duration},
+};
+
+/// Faults to inject, as a comma-separated list of `name=value` pairs such as
+/// `drop=0.01,duplicate=0.01,pop-delay-us=100,capacity=1000`
+pub const PACKET_FAULT_INJECTION_ENV_VAR: &str = "SOLANA_PACKET_FAULT_INJECTION";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PacketFaultInjectionConfig {
+    /// Probability of dropping a packet pushed into the buffer
+    pub drop_rate: f64,
+    /// Probability of delivering a popped packet again with the next inserted batch
+    pub duplicate_rate: f64,
+    /// Delay added to every popped packet
+    pub pop_delay: Duration,
+    /// Capacity each queue is held to, below the one it was created with
+    pub capacity: Option<usize>,
+}
+
+impl PacketFaultInjectionConfig {
+    /// Reads the faults to inject from `PACKET_FAULT_INJECTION_ENV_VAR`, if set. Panics if the
+    /// variable can't be parsed, so a misspelled chaos test doesn't silently run without faults.
+    pub fn from_env() -> Option<Self> {
+        let faults = env::var(PACKET_FAULT_INJECTION_ENV_VAR).ok()?;
+        Some(
+            faults.parse().unwrap_or_else(|err| {
+                panic!("Invalid {}: {}", PACKET_FAULT_INJECTION_ENV_VAR, err)
+            }),
+        )
+    }
+}
+
+impl FromStr for PacketFaultInjectionConfig {
+    type Err = String;
+
+    fn from_str(faults: &str) -> Result<Self, Self::Err> {
+        fn parse_rate(name: &str, value: &str) -> Result<f64, String> {
+            value
+                .parse()
+                .ok()
+                .filter(|rate| (0.0..=1.0).contains(rate))
+                .ok_or_else(|| format!("{} must be a probability between 0 and 1", name))
+        }
+
+        let mut config = Self::default();
+        for fault in faults.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let (name, value) = fault
+                .split_once('=')
+                .ok_or_else(|| format!("expected name=value, found {:?}", fault))?;
+            match name {
+                "drop" => config.drop_rate = parse_rate(name, value)?,
+                "duplicate" => config.duplicate_rate = parse_rate(name, value)?,
+                "pop-delay-us" => {
+                    let delay_us = value
+                        .parse()
+                        .map_err(|_| format!("{} must be a number of microseconds", name))?;
+                    config.pop_delay = Duration::from_micros(delay_us);
+                }
+                "capacity" => {
+                    let capacity = value
+                        .parse()
+                        .map_err(|_| format!("{} must be a number of packets", name))?;
+                    config.capacity = Some(capacity);
+                }
+                _ => return Err(format!("unknown fault {:?}", name)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Number of faults injected, reported and cleared periodically
+#[derive(Debug, Default)]
+pub struct PacketFaultInjectionStats {
+    last_report: AtomicInterval,
+    pub dropped_count: usize,
+    pub delayed_count: usize,
+    pub duplicated_count: usize,
+}
+
+impl PacketFaultInjectionStats {
+    pub fn is_empty(&self) -> bool {
+        self.dropped_count == 0 && self.delayed_count == 0 && self.duplicated_count == 0
+    }
+
+    /// Reports the faults injected since the last report at most once per
+    /// `report_interval_ms`, then clears them
+    pub fn report(&mut self, id: u32, report_interval_ms: u64) {
+        if self.is_empty() || !self.last_report.should_update(report_interval_ms) {
+            return;
+        }
+        datapoint_info!(
+            "banking_stage-fault_injection",
+            ("id", id as i64, i64),
+            ("dropped_count", self.dropped_count as i64, i64),
+            ("delayed_count", self.delayed_count as i64, i64),
+            ("duplicated_count", self.duplicated_count as i64, i64),
+        );
+        self.dropped_count = 0;
+        self.delayed_count = 0;
+        self.duplicated_count = 0;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PacketFaultInjector {
+    config: PacketFaultInjectionConfig,
+    /// Copies of popped packets to deliver again with the next inserted batch
+    redeliveries: Vec<DeserializedPacket>,
+    stats: PacketFaultInjectionStats,
+}
+
+impl PacketFaultInjector {
+    pub fn new(config: PacketFaultInjectionConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn config(&self) -> &PacketFaultInjectionConfig {
+        &self.config
+    }
+
+    pub fn stats_mut(&mut self) -> &mut PacketFaultInjectionStats {
+        &mut self.stats
+    }
+
+    fn roll(rate: f64) -> bool {
+        rate > 0.0 && thread_rng().gen_bool(rate)
+    }
+
+    /// Returns whether the packet being pushed should be dropped
+    pub fn inject_drop(&mut self) -> bool {
+        let drop = Self::roll(self.config.drop_rate);
+        if drop {
+            self.stats.dropped_count = self.stats.dropped_count.saturating_add(1);
+        }
+        drop
+    }
+
+    /// Returns the limit a queue created with `queue_limit` is held to
+    pub fn queue_limit(&self, queue_limit: usize) -> usize {
+        self.config
+            .capacity
+            .map_or(queue_limit, |capacity| capacity.min(queue_limit))
+    }
+
+    /// Delays the pop of `deserialized_packet`, and may schedule it for another delivery
+    pub fn inject_pop_faults(&mut self, deserialized_packet: &DeserializedPacket) {
+        if !self.config.pop_delay.is_zero() {
+            sleep(self.config.pop_delay);
+            self.stats.delayed_count = self.stats.delayed_count.saturating_add(1);
+        }
+        if Self::roll(self.config.duplicate_rate) {
+            self.redeliveries.push(deserialized_packet.clone());
+            self.stats.duplicated_count = self.stats.duplicated_count.saturating_add(1);
+        }
+    }
+
+    /// Takes the packets to deliver again
+    pub fn take_redeliveries(&mut self) -> Vec<DeserializedPacket> {
+        std::mem::take(&mut self.redeliveries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_packet_fault_injection_config() {
+        assert_eq!(
+            "".parse::<PacketFaultInjectionConfig>().unwrap(),
+            PacketFaultInjectionConfig::default()
+        );
+        assert_eq!(
+            "drop=0.1, duplicate=1,pop-delay-us=50,capacity=10"
+                .parse::<PacketFaultInjectionConfig>()
+                .unwrap(),
+            PacketFaultInjectionConfig {
+                drop_rate: 0.1,
+                duplicate_rate: 1.0,
+                pop_delay: Duration::from_micros(50),
+                capacity: Some(10),
+            }
+        );
+        assert!("drop=2".parse::<PacketFaultInjectionConfig>().is_err());
+        assert!("drop".parse::<PacketFaultInjectionConfig>().is_err());
+        assert!("delay=1".parse::<PacketFaultInjectionConfig>().is_err());
+        assert!("capacity=-1".parse::<PacketFaultInjectionConfig>().is_err());
+    }
+}
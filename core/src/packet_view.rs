@@ -0,0 +1,527 @@
+//! The `packet_view` module reads the transaction serialized in a packet in place. Buffering a
+//! packet only takes its signature count, message hash, fee payer and compute budget
+//! instructions, so the banking stage parses those out of the packet bytes and leaves
+//! deserializing the full `VersionedTransaction` until the packet is scheduled.
+use {
+    crate::unprocessed_packet_batches::DeserializedPacketError,
+    solana_sdk::{
+        hash::{Hash, HASH_BYTES},
+        message::{MessageHeader, MESSAGE_VERSION_PREFIX},
+        program_utils::limited_deserialize,
+        pubkey::{Pubkey, PUBKEY_BYTES},
+        sanitize::SanitizeError,
+        short_vec::decode_shortu16_len,
+        signature::{Signature, SIGNATURE_BYTES},
+        system_instruction::SystemInstruction,
+        system_program,
+    },
+};
+
+/// Account keys can be referenced by `u8` indexes only
+const MAX_ACCOUNT_KEYS: usize = 256;
+
+/// Instruction of a transaction, borrowed from the packet it was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionView<'a> {
+    pub program_id_index: u8,
+    pub accounts: &'a [u8],
+    pub data: &'a [u8],
+}
+
+/// Transaction read in place from the bytes of a packet. Construction fails on the same
+/// packets that fail to deserialize into a `SanitizedVersionedTransaction`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacketView<'a> {
+    num_signatures: usize,
+    signatures: &'a [u8],
+    message_bytes: &'a [u8],
+    header: MessageHeader,
+    account_keys: &'a [u8],
+    num_lookup_keys: usize,
+    recent_blockhash: Hash,
+    num_instructions: usize,
+    instructions: &'a [u8],
+}
+
+impl<'a> PacketView<'a> {
+    pub fn new(packet_data: &'a [u8]) -> Result<Self, DeserializedPacketError> {
+        let mut reader = Reader::new(packet_data);
+        let num_signatures = reader.read_len()?;
+        let signatures = num_signatures
+            .checked_mul(SIGNATURE_BYTES)
+            .and_then(|signatures_len| reader.read_bytes(signatures_len).ok())
+            .ok_or(DeserializedPacketError::SignatureOverflowed(num_signatures))?;
+        let message_bytes = reader.bytes;
+
+        let prefix = reader.read_u8()?;
+        let (is_versioned, num_required_signatures) = if prefix & MESSAGE_VERSION_PREFIX != 0 {
+            if prefix & !MESSAGE_VERSION_PREFIX != 0 {
+                return Err(deserialization_error("unsupported message version"));
+            }
+            (true, reader.read_u8()?)
+        } else {
+            (false, prefix)
+        };
+        let header = MessageHeader {
+            num_required_signatures,
+            num_readonly_signed_accounts: reader.read_u8()?,
+            num_readonly_unsigned_accounts: reader.read_u8()?,
+        };
+        let num_account_keys = reader.read_len()?;
+        let account_keys = reader.read_array(num_account_keys, PUBKEY_BYTES)?;
+        let recent_blockhash = Hash::new(reader.read_bytes(HASH_BYTES)?);
+
+        let num_instructions = reader.read_len()?;
+        let instructions_start = reader.bytes;
+        let mut has_payer_program_id = false;
+        let mut max_program_id_index = None;
+        let mut max_account_index = None;
+        for _ in 0..num_instructions {
+            let instruction = reader.read_instruction()?;
+            has_payer_program_id |= instruction.program_id_index == 0;
+            max_program_id_index = max_program_id_index.max(Some(instruction.program_id_index));
+            max_account_index = max_account_index.max(instruction.accounts.iter().max().copied());
+        }
+        let instructions =
+            &instructions_start[..instructions_start.len().saturating_sub(reader.bytes.len())];
+
+        let mut num_lookup_keys: usize = 0;
+        let mut has_unused_lookup = false;
+        if is_versioned {
+            let num_lookups = reader.read_len()?;
+            for _ in 0..num_lookups {
+                reader.read_bytes(PUBKEY_BYTES)?;
+                let num_writable_indexes = reader.read_len()?;
+                reader.read_bytes(num_writable_indexes)?;
+                let num_readonly_indexes = reader.read_len()?;
+                reader.read_bytes(num_readonly_indexes)?;
+                let num_indexes = num_writable_indexes.saturating_add(num_readonly_indexes);
+                has_unused_lookup |= num_indexes == 0;
+                num_lookup_keys = num_lookup_keys.saturating_add(num_indexes);
+            }
+        }
+        if !reader.bytes.is_empty() {
+            return Err(deserialization_error("trailing bytes after message"));
+        }
+
+        // Sanitize as `SanitizedVersionedTransaction::try_new()` does, signatures first
+        let num_required_signatures = usize::from(num_required_signatures);
+        if num_required_signatures > num_signatures || num_signatures > num_account_keys {
+            return Err(SanitizeError::IndexOutOfBounds.into());
+        }
+        if num_required_signatures < num_signatures {
+            return Err(SanitizeError::InvalidValue.into());
+        }
+        if num_required_signatures
+            .saturating_add(usize::from(header.num_readonly_unsigned_accounts))
+            > num_account_keys
+        {
+            return Err(SanitizeError::IndexOutOfBounds.into());
+        }
+        if header.num_readonly_signed_accounts >= header.num_required_signatures {
+            return Err(if is_versioned {
+                SanitizeError::InvalidValue
+            } else {
+                SanitizeError::IndexOutOfBounds
+            }
+            .into());
+        }
+        if has_unused_lookup {
+            return Err(SanitizeError::InvalidValue.into());
+        }
+        let num_total_keys = num_account_keys.saturating_add(num_lookup_keys);
+        if num_total_keys > MAX_ACCOUNT_KEYS
+            || has_payer_program_id
+            || max_program_id_index.map_or(false, |index| usize::from(index) >= num_account_keys)
+            || max_account_index.map_or(false, |index| usize::from(index) >= num_total_keys)
+        {
+            return Err(SanitizeError::IndexOutOfBounds.into());
+        }
+
+        Ok(Self {
+            num_signatures,
+            signatures,
+            message_bytes,
+            header,
+            account_keys,
+            num_lookup_keys,
+            recent_blockhash,
+            num_instructions,
+            instructions,
+        })
+    }
+
+    pub fn num_signatures(&self) -> usize {
+        self.num_signatures
+    }
+
+    /// The first signature, which identifies the transaction. Sanitization guarantees there
+    /// is at least one.
+    pub fn signature(&self) -> Signature {
+        Signature::new(&self.signatures[..SIGNATURE_BYTES])
+    }
+
+    /// The serialized message, which is what the signatures sign
+    pub fn message_bytes(&self) -> &'a [u8] {
+        self.message_bytes
+    }
+
+    pub fn header(&self) -> &MessageHeader {
+        &self.header
+    }
+
+    pub fn static_account_keys(&self) -> impl Iterator<Item = Pubkey> + 'a {
+        self.account_keys
+            .chunks_exact(PUBKEY_BYTES)
+            .map(Pubkey::new)
+    }
+
+    pub fn static_account_key(&self, index: usize) -> Option<Pubkey> {
+        let start = index.checked_mul(PUBKEY_BYTES)?;
+        let end = start.checked_add(PUBKEY_BYTES)?;
+        self.account_keys.get(start..end).map(Pubkey::new)
+    }
+
+    /// Number of accounts the transaction locks: its static account keys plus the ones it loads
+    /// through address table lookups
+    pub fn num_account_keys(&self) -> usize {
+        (self.account_keys.len() / PUBKEY_BYTES).saturating_add(self.num_lookup_keys)
+    }
+
+    /// Static account keys the header marks writable. Keys demoted to read-only at execution,
+    /// such as invoked programs, are included.
+    pub fn writable_static_account_keys(&self) -> impl Iterator<Item = Pubkey> + 'a {
+        let num_account_keys = self.account_keys.len() / PUBKEY_BYTES;
+        let num_signed = usize::from(self.header.num_required_signatures);
+        let num_writable_signed =
+            num_signed.saturating_sub(usize::from(self.header.num_readonly_signed_accounts));
+        let num_writable = num_account_keys
+            .saturating_sub(usize::from(self.header.num_readonly_unsigned_accounts));
+        self.static_account_keys()
+            .enumerate()
+            .filter(move |(index, _)| {
+                *index < num_writable_signed || (num_signed..num_writable).contains(index)
+            })
+            .map(|(_, account_key)| account_key)
+    }
+
+    /// The fee payer of the transaction, which is always its first account key
+    pub fn fee_payer(&self) -> Pubkey {
+        // Sanitization guarantees at least one account key, for the fee payer
+        Pubkey::new(&self.account_keys[..PUBKEY_BYTES])
+    }
+
+    pub fn recent_blockhash(&self) -> &Hash {
+        &self.recent_blockhash
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = InstructionView<'a>> {
+        let mut reader = Reader::new(self.instructions);
+        (0..self.num_instructions).map_while(move |_| reader.read_instruction().ok())
+    }
+
+    /// Instructions along with the ids of the programs they invoke
+    pub fn program_instructions(&self) -> impl Iterator<Item = (Pubkey, InstructionView<'a>)> + '_ {
+        self.instructions().filter_map(move |instruction| {
+            let program_id = self.static_account_key(usize::from(instruction.program_id_index))?;
+            Some((program_id, instruction))
+        })
+    }
+
+    /// The nonce account of a durable-nonce transaction, which advances it with its first
+    /// instruction. Nonce accounts loaded from address lookup tables are not detected.
+    pub fn durable_nonce_account(&self) -> Option<Pubkey> {
+        let (program_id, instruction) = self.program_instructions().next()?;
+        if !system_program::check_id(&program_id) {
+            return None;
+        }
+        match limited_deserialize(instruction.data) {
+            Ok(SystemInstruction::AdvanceNonceAccount) => {
+                self.static_account_key(usize::from(*instruction.accounts.first()?))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn deserialization_error(message: &str) -> DeserializedPacketError {
+    DeserializedPacketError::DeserializationError(Box::new(bincode::ErrorKind::Custom(
+        message.to_string(),
+    )))
+}
+
+/// Reads the fields of a transaction in the order bincode serializes them
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DeserializedPacketError> {
+        if len > self.bytes.len() {
+            return Err(deserialization_error("unexpected end of packet"));
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DeserializedPacketError> {
+        self.read_bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn read_len(&mut self) -> Result<usize, DeserializedPacketError> {
+        let (len, size) =
+            decode_shortu16_len(self.bytes).map_err(DeserializedPacketError::ShortVecError)?;
+        self.bytes = &self.bytes[size..];
+        Ok(len)
+    }
+
+    fn read_array(
+        &mut self,
+        num_elements: usize,
+        element_size: usize,
+    ) -> Result<&'a [u8], DeserializedPacketError> {
+        let len = num_elements
+            .checked_mul(element_size)
+            .ok_or_else(|| deserialization_error("array length overflowed"))?;
+        self.read_bytes(len)
+    }
+
+    fn read_instruction(&mut self) -> Result<InstructionView<'a>, DeserializedPacketError> {
+        let program_id_index = self.read_u8()?;
+        let num_accounts = self.read_len()?;
+        let accounts = self.read_bytes(num_accounts)?;
+        let data_len = self.read_len()?;
+        let data = self.read_bytes(data_len)?;
+        Ok(InstructionView {
+            program_id_index,
+            accounts,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_perf::packet::Packet,
+        solana_sdk::{
+            compute_budget::ComputeBudgetInstruction,
+            instruction::{AccountMeta, Instruction},
+            message::{
+                v0::{self, MessageAddressTableLookup},
+                Message, SanitizedVersionedMessage, VersionedMessage,
+            },
+            signature::{Keypair, Signature, Signer},
+            system_instruction,
+            transaction::{SanitizedVersionedTransaction, Transaction, VersionedTransaction},
+        },
+    };
+
+    fn assert_matches_transaction(transaction: VersionedTransaction) {
+        let packet = Packet::from_data(None, &transaction).unwrap();
+        let view = PacketView::new(packet.data()).unwrap();
+        let transaction = SanitizedVersionedTransaction::try_from(transaction).unwrap();
+        let message = transaction.get_message();
+
+        assert_eq!(view.num_signatures(), transaction.signatures.len());
+        assert_eq!(view.signature(), transaction.signatures[0]);
+        assert_eq!(view.message_bytes(), message.message.serialize());
+        assert_eq!(view.header(), message.header());
+        assert_eq!(
+            view.static_account_keys().collect::<Vec<_>>(),
+            message.message.static_account_keys()
+        );
+        assert_eq!(
+            view.num_account_keys(),
+            message.message.static_account_keys().len()
+                + message
+                    .message
+                    .address_table_lookups()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+                    .sum::<usize>()
+        );
+        assert_eq!(
+            view.writable_static_account_keys().collect::<Vec<_>>(),
+            message
+                .message
+                .static_account_keys()
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| message.message.is_maybe_writable(*index))
+                .map(|(_, account_key)| *account_key)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(view.fee_payer(), message.message.static_account_keys()[0]);
+        assert_eq!(view.recent_blockhash(), message.message.recent_blockhash());
+        assert_eq!(
+            view.program_instructions()
+                .map(|(program_id, instruction)| (
+                    program_id,
+                    instruction.program_id_index,
+                    instruction.accounts.to_vec(),
+                    instruction.data.to_vec()
+                ))
+                .collect::<Vec<_>>(),
+            message
+                .program_instructions_iter()
+                .map(|(program_id, instruction)| (
+                    *program_id,
+                    instruction.program_id_index,
+                    instruction.accounts.clone(),
+                    instruction.data.clone()
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn test_instructions(payer: &Pubkey) -> Vec<Instruction> {
+        vec![
+            ComputeBudgetInstruction::set_compute_unit_price(100),
+            system_instruction::transfer(payer, &Pubkey::new_unique(), 1),
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1, 2, 3],
+                vec![AccountMeta::new_readonly(Pubkey::new_unique(), false)],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_packet_view_legacy() {
+        let keypair = Keypair::new();
+        let message = Message::new(
+            &test_instructions(&keypair.pubkey()),
+            Some(&keypair.pubkey()),
+        );
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&keypair]).unwrap();
+        assert_matches_transaction(transaction);
+    }
+
+    #[test]
+    fn test_packet_view_v0() {
+        let keypair = Keypair::new();
+        let legacy_message = Message::new(
+            &test_instructions(&keypair.pubkey()),
+            Some(&keypair.pubkey()),
+        );
+        let message = v0::Message {
+            header: legacy_message.header,
+            account_keys: legacy_message.account_keys,
+            recent_blockhash: Hash::new_unique(),
+            instructions: legacy_message.instructions,
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1, 2],
+            }],
+        };
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&keypair]).unwrap();
+        assert_matches_transaction(transaction);
+    }
+
+    #[test]
+    fn test_packet_view_durable_nonce_account() {
+        let keypair = Keypair::new();
+        let nonce_pubkey = Pubkey::new_unique();
+        let message = Message::new_with_nonce(
+            test_instructions(&keypair.pubkey()),
+            Some(&keypair.pubkey()),
+            &nonce_pubkey,
+            &keypair.pubkey(),
+        );
+        let transaction = Transaction::new(&[&keypair], message, Hash::new_unique());
+        let packet = Packet::from_data(None, &transaction).unwrap();
+        assert_eq!(
+            PacketView::new(packet.data())
+                .unwrap()
+                .durable_nonce_account(),
+            Some(nonce_pubkey)
+        );
+
+        // Advancing a nonce after another instruction doesn't make a durable-nonce transaction
+        let mut instructions = test_instructions(&keypair.pubkey());
+        instructions.push(system_instruction::advance_nonce_account(
+            &nonce_pubkey,
+            &keypair.pubkey(),
+        ));
+        let message = Message::new(&instructions, Some(&keypair.pubkey()));
+        let transaction = Transaction::new(&[&keypair], message, Hash::new_unique());
+        let packet = Packet::from_data(None, &transaction).unwrap();
+        assert_eq!(
+            PacketView::new(packet.data())
+                .unwrap()
+                .durable_nonce_account(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_packet_view_rejects_invalid_packets() {
+        let keypair = Keypair::new();
+        let message = Message::new(
+            &test_instructions(&keypair.pubkey()),
+            Some(&keypair.pubkey()),
+        );
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&keypair]).unwrap();
+        let data = bincode::serialize(&transaction).unwrap();
+
+        assert!(matches!(
+            PacketView::new(&data[..data.len() - 1]),
+            Err(DeserializedPacketError::DeserializationError(_))
+        ));
+        let mut trailing_data = data.clone();
+        trailing_data.push(0);
+        assert!(matches!(
+            PacketView::new(&trailing_data),
+            Err(DeserializedPacketError::DeserializationError(_))
+        ));
+        assert!(matches!(
+            PacketView::new(&[0x80]),
+            Err(DeserializedPacketError::ShortVecError(()))
+        ));
+
+        // One signature short of the number the message requires
+        let mut unsigned_transaction = transaction.clone();
+        unsigned_transaction.signatures.clear();
+        assert!(matches!(
+            PacketView::new(&bincode::serialize(&unsigned_transaction).unwrap()),
+            Err(DeserializedPacketError::SanitizeError(
+                SanitizeError::IndexOutOfBounds
+            ))
+        ));
+        let mut oversigned_transaction = transaction.clone();
+        oversigned_transaction.signatures.push(Signature::default());
+        assert!(matches!(
+            PacketView::new(&bincode::serialize(&oversigned_transaction).unwrap()),
+            Err(DeserializedPacketError::SanitizeError(
+                SanitizeError::InvalidValue
+            ))
+        ));
+
+        // Programs can't pay fees
+        let mut payer_program_transaction = transaction;
+        if let VersionedMessage::Legacy(message) = &mut payer_program_transaction.message {
+            message.instructions[0].program_id_index = 0;
+        }
+        let data = bincode::serialize(&payer_program_transaction).unwrap();
+        assert!(matches!(
+            PacketView::new(&data),
+            Err(DeserializedPacketError::SanitizeError(
+                SanitizeError::IndexOutOfBounds
+            ))
+        ));
+        let versioned_transaction: VersionedTransaction = bincode::deserialize(&data).unwrap();
+        assert!(SanitizedVersionedMessage::try_from(versioned_transaction.message).is_err());
+    }
+}
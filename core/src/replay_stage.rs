@@ -34,6 +34,7 @@ use {
     solana_ledger::{
         block_error::BlockError,
         blockstore::Blockstore,
+        blockstore_meta::DeadSlotCause,
         blockstore_processor::{self, BlockstoreProcessorError, TransactionStatusSender},
         leader_schedule_cache::LeaderScheduleCache,
         leader_schedule_utils::first_of_consecutive_leader_slots,
@@ -1730,7 +1731,7 @@ impl ReplayStage {
         }
         progress.get_mut(&slot).unwrap().is_dead = true;
         blockstore
-            .set_dead_slot(slot)
+            .set_dead_slot_with_cause(slot, DeadSlotCause::from(err))
             .expect("Failed to mark slot as dead in blockstore");
 
         blockstore.slots_stats.mark_dead(slot);
@@ -2273,9 +2274,25 @@ impl ReplayStage {
                     transaction_status_sender.send_transaction_status_freeze_message(&bank);
                 }
                 bank.freeze();
-                // report cost tracker stats
+                let program_compute_units = bank_progress
+                    .replay_stats
+                    .execute_timings
+                    .details
+                    .per_program_timings
+                    .iter()
+                    .map(|(program_id, program_timing)| {
+                        let units = program_timing
+                            .accumulated_units
+                            .saturating_add(program_timing.total_errored_units);
+                        (*program_id, units)
+                    })
+                    .collect();
+                // report cost tracker stats, and record the cost of the block
                 cost_update_sender
-                    .send(CostUpdate::FrozenBank { bank: bank.clone() })
+                    .send(CostUpdate::FrozenBank {
+                        bank: bank.clone(),
+                        program_compute_units,
+                    })
                     .unwrap_or_else(|err| {
                         warn!("cost_update_sender failed sending bank stats: {:?}", err)
                     });
@@ -7,6 +7,7 @@ use {
         repair_service::{OutstandingShredRepairs, RepairStats},
         request_response::RequestResponse,
         result::{Error, Result},
+        serve_repair_admission::{Requester, ServeRepairAdmission},
     },
     bincode::serialize,
     lru::LruCache,
@@ -150,6 +151,7 @@ pub struct ServeRepairStats {
     pub highest_window_index: usize,
     pub orphan: usize,
     pub ancestor_hashes: usize,
+    pub throttled: usize,
 }
 
 /// Window protocol messages
@@ -227,6 +229,30 @@ impl ServeRepair {
         }
     }
 
+    /// The sender claimed by `request` if gossip lists the IP of `from_addr` among its repair
+    /// addresses, otherwise just that IP
+    fn verified_requester(
+        me: &Arc<RwLock<Self>>,
+        request: &RepairProtocol,
+        from_addr: &SocketAddr,
+    ) -> Requester {
+        let id = Self::get_repair_sender(request).id;
+        let is_verified = me
+            .read()
+            .unwrap()
+            .cluster_info
+            .lookup_contact_info(&id, |contact_info| {
+                contact_info.repair.ip() == from_addr.ip()
+                    || contact_info.serve_repair.ip() == from_addr.ip()
+            })
+            .unwrap_or_default();
+        if is_verified {
+            Requester::Node(id)
+        } else {
+            Requester::Address(from_addr.ip())
+        }
+    }
+
     fn handle_repair(
         me: &Arc<RwLock<Self>>,
         recycler: &PacketBatchRecycler,
@@ -315,6 +341,7 @@ impl ServeRepair {
     }
 
     /// Process messages from the network
+    #[allow(clippy::too_many_arguments)]
     fn run_listen(
         obj: &Arc<RwLock<Self>>,
         recycler: &PacketBatchRecycler,
@@ -323,6 +350,7 @@ impl ServeRepair {
         response_sender: &PacketBatchSender,
         stats: &mut ServeRepairStats,
         packet_threshold: &mut DynamicPacketToProcessThreshold,
+        admission: &mut Option<ServeRepairAdmission>,
     ) -> Result<()> {
         //TODO cache connections
         let timeout = Duration::new(1, 0);
@@ -344,7 +372,15 @@ impl ServeRepair {
 
         let timer = Instant::now();
         for reqs in reqs_v {
-            Self::handle_packets(obj, recycler, blockstore, reqs, response_sender, stats);
+            Self::handle_packets(
+                obj,
+                recycler,
+                blockstore,
+                reqs,
+                response_sender,
+                stats,
+                admission,
+            );
         }
         packet_threshold.update(total_packets, timer.elapsed());
         Ok(())
@@ -362,6 +398,7 @@ impl ServeRepair {
 
         inc_new_counter_info!("serve_repair-total_packets", stats.total_packets);
         inc_new_counter_info!("serve_repair-dropped_packets", stats.dropped_packets);
+        inc_new_counter_info!("serve_repair-throttled_requests", stats.throttled);
 
         debug!(
             "repair_listener: total_packets: {} passed: {}",
@@ -386,6 +423,7 @@ impl ServeRepair {
         blockstore: Option<Arc<Blockstore>>,
         requests_receiver: PacketBatchReceiver,
         response_sender: PacketBatchSender,
+        mut admission: Option<ServeRepairAdmission>,
         exit: &Arc<AtomicBool>,
     ) -> JoinHandle<()> {
         let exit = exit.clone();
@@ -405,6 +443,7 @@ impl ServeRepair {
                         &response_sender,
                         &mut stats,
                         &mut packet_threshold,
+                        &mut admission,
                     );
                     match result {
                         Err(Error::RecvTimeout(_)) | Ok(_) => {}
@@ -429,14 +468,28 @@ impl ServeRepair {
         packet_batch: PacketBatch,
         response_sender: &PacketBatchSender,
         stats: &mut ServeRepairStats,
+        admission: &mut Option<ServeRepairAdmission>,
     ) {
         // iter over the packets
         packet_batch.iter().for_each(|packet| {
             if let Ok(request) = packet.deserialize_slice(..) {
-                stats.processed += 1;
                 let from_addr = packet.meta.socket_addr();
+                let requester = admission
+                    .as_ref()
+                    .map(|_| Self::verified_requester(me, &request, &from_addr));
+                if let (Some(admission), Some(requester)) = (admission.as_mut(), &requester) {
+                    if !admission.admit(requester, Instant::now()) {
+                        stats.throttled += 1;
+                        return;
+                    }
+                }
+                stats.processed += 1;
                 let rsp = Self::handle_repair(me, recycler, &from_addr, blockstore, request, stats);
                 if let Some(rsp) = rsp {
+                    if let (Some(admission), Some(requester)) = (admission.as_mut(), &requester) {
+                        let num_bytes = rsp.iter().map(|packet| packet.meta.size).sum();
+                        admission.charge(requester, num_bytes);
+                    }
                     let _ignore_disconnect = response_sender.send(rsp);
                 }
             }
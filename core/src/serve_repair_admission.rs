@@ -0,0 +1,297 @@
+//! The `serve_repair_admission` module budgets the repair responses served to each requester.
+//! Staked requesters share a bandwidth budget in proportion to their stake, while unstaked
+//! requesters are held to a small individual budget and a common one, so they can't exhaust
+//! the node's repair-serving capacity during cluster-wide catch-up.
+//!
+//! The identity a request claims is only trusted if it was sent from an address that node
+//! advertises in gossip. Other requests are budgeted by their source address as unstaked.
+use {
+    lru::LruCache,
+    solana_runtime::bank_forks::BankForks,
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{HashMap, HashSet},
+        net::IpAddr,
+        sync::{Arc, RwLock},
+        time::{Duration, Instant},
+    },
+};
+
+/// Requesters whose budgets are tracked at once; the least recently seen are evicted first
+const MAX_TRACKED_REQUESTERS: usize = 8192;
+/// How often the stakes of requesters are reloaded from the root bank
+const STAKES_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+pub struct ServeRepairAdmissionConfig {
+    /// Response bytes per second shared by staked requesters in proportion to their stake
+    pub staked_bytes_per_second: u64,
+    /// Response bytes per second shared by all unstaked requesters
+    pub unstaked_bytes_per_second: u64,
+    /// Response bytes per second available to any single unstaked requester
+    pub unstaked_requester_bytes_per_second: u64,
+    /// How long a budget may accumulate unused bandwidth, bounding request bursts
+    pub burst: Duration,
+    /// Requesters that are served without limit
+    pub whitelist: HashSet<Pubkey>,
+}
+
+impl Default for ServeRepairAdmissionConfig {
+    fn default() -> Self {
+        Self {
+            staked_bytes_per_second: 200_000_000,
+            unstaked_bytes_per_second: 10_000_000,
+            unstaked_requester_bytes_per_second: 1_000_000,
+            burst: Duration::from_secs(1),
+            whitelist: HashSet::default(),
+        }
+    }
+}
+
+/// Who a repair request is budgeted to
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Requester {
+    /// A node whose identity was checked against the addresses it advertises in gossip
+    Node(Pubkey),
+    /// The source address of a request whose claimed identity could not be checked
+    Address(IpAddr),
+}
+
+/// Bytes a requester may still be sent. Responses are charged after they are built, so the
+/// balance can go negative, in which case requests are rejected until it is refilled.
+#[derive(Debug)]
+struct Budget {
+    bytes: i64,
+    last_refill: Instant,
+}
+
+impl Budget {
+    /// Creates a budget holding a full burst
+    fn new(now: Instant, bytes_per_second: u64, burst: Duration) -> Self {
+        Self {
+            bytes: Self::bytes_over(bytes_per_second, burst),
+            last_refill: now,
+        }
+    }
+
+    fn bytes_over(bytes_per_second: u64, duration: Duration) -> i64 {
+        (bytes_per_second as f64 * duration.as_secs_f64()) as i64
+    }
+
+    fn refill(&mut self, now: Instant, bytes_per_second: u64, burst: Duration) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.last_refill = now;
+        self.bytes = self
+            .bytes
+            .saturating_add(Self::bytes_over(bytes_per_second, elapsed))
+            .min(Self::bytes_over(bytes_per_second, burst));
+    }
+
+    fn is_available(&self) -> bool {
+        self.bytes > 0
+    }
+
+    fn charge(&mut self, num_bytes: usize) {
+        self.bytes = self.bytes.saturating_sub(num_bytes as i64);
+    }
+}
+
+pub struct ServeRepairAdmission {
+    config: ServeRepairAdmissionConfig,
+    bank_forks: Option<Arc<RwLock<BankForks>>>,
+    stakes: Arc<HashMap<Pubkey, u64>>,
+    total_stake: u64,
+    last_stakes_refresh: Option<Instant>,
+    budgets: LruCache<Requester, Budget>,
+    unstaked_budget: Budget,
+}
+
+impl ServeRepairAdmission {
+    /// Stakes are read from the root bank of `bank_forks`; without it all requesters are
+    /// treated as unstaked
+    pub fn new(
+        config: ServeRepairAdmissionConfig,
+        bank_forks: Option<Arc<RwLock<BankForks>>>,
+    ) -> Self {
+        let unstaked_budget = Budget::new(
+            Instant::now(),
+            config.unstaked_bytes_per_second,
+            config.burst,
+        );
+        Self {
+            config,
+            bank_forks,
+            stakes: Arc::default(),
+            total_stake: 0,
+            last_stakes_refresh: None,
+            budgets: LruCache::new(MAX_TRACKED_REQUESTERS),
+            unstaked_budget,
+        }
+    }
+
+    fn maybe_refresh_stakes(&mut self, now: Instant) {
+        if let Some(last_refresh) = self.last_stakes_refresh {
+            if now.saturating_duration_since(last_refresh) < STAKES_REFRESH_INTERVAL {
+                return;
+            }
+        }
+        self.last_stakes_refresh = Some(now);
+        if let Some(bank_forks) = &self.bank_forks {
+            let stakes = bank_forks.read().unwrap().root_bank().staked_nodes();
+            self.set_stakes(stakes);
+        }
+    }
+
+    fn set_stakes(&mut self, stakes: Arc<HashMap<Pubkey, u64>>) {
+        self.total_stake = stakes.values().sum();
+        self.stakes = stakes;
+    }
+
+    fn stake(&self, requester: &Requester) -> u64 {
+        match requester {
+            Requester::Node(pubkey) => self.stakes.get(pubkey).copied().unwrap_or_default(),
+            Requester::Address(_) => 0,
+        }
+    }
+
+    fn is_whitelisted(&self, requester: &Requester) -> bool {
+        match requester {
+            Requester::Node(pubkey) => self.config.whitelist.contains(pubkey),
+            Requester::Address(_) => false,
+        }
+    }
+
+    /// Bandwidth budgeted to `requester` on its own
+    fn requester_bytes_per_second(&self, requester: &Requester) -> u64 {
+        match self.stake(requester) {
+            0 => self.config.unstaked_requester_bytes_per_second,
+            stake => {
+                let staked_share = (self.config.staked_bytes_per_second as u128)
+                    .saturating_mul(stake as u128)
+                    .checked_div(self.total_stake as u128)
+                    .unwrap_or_default() as u64;
+                staked_share.max(self.config.unstaked_requester_bytes_per_second)
+            }
+        }
+    }
+
+    /// Returns true if a request from `requester` should be served
+    pub fn admit(&mut self, requester: &Requester, now: Instant) -> bool {
+        if self.is_whitelisted(requester) {
+            return true;
+        }
+        self.maybe_refresh_stakes(now);
+        let bytes_per_second = self.requester_bytes_per_second(requester);
+        let burst = self.config.burst;
+        if self.stake(requester) == 0 {
+            self.unstaked_budget
+                .refill(now, self.config.unstaked_bytes_per_second, burst);
+            if !self.unstaked_budget.is_available() {
+                return false;
+            }
+        }
+        match self.budgets.get_mut(requester) {
+            Some(budget) => {
+                budget.refill(now, bytes_per_second, burst);
+                budget.is_available()
+            }
+            None => {
+                self.budgets
+                    .put(*requester, Budget::new(now, bytes_per_second, burst));
+                true
+            }
+        }
+    }
+
+    /// Charges the `num_bytes` of the response served to `requester` against its budgets
+    pub fn charge(&mut self, requester: &Requester, num_bytes: usize) {
+        if self.is_whitelisted(requester) {
+            return;
+        }
+        if self.stake(requester) == 0 {
+            self.unstaked_budget.charge(num_bytes);
+        }
+        if let Some(budget) = self.budgets.get_mut(requester) {
+            budget.charge(num_bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serve_repair_admission() {
+        let staked = Pubkey::new_unique();
+        let unstaked = Requester::Node(Pubkey::new_unique());
+        let whitelisted = Pubkey::new_unique();
+        let config = ServeRepairAdmissionConfig {
+            staked_bytes_per_second: 10_000,
+            unstaked_bytes_per_second: 2_000,
+            unstaked_requester_bytes_per_second: 1_000,
+            burst: Duration::from_secs(1),
+            whitelist: HashSet::from([whitelisted]),
+        };
+        let mut admission = ServeRepairAdmission::new(config, None);
+        let now = Instant::now();
+        admission.last_stakes_refresh = Some(now);
+        admission.set_stakes(Arc::new(HashMap::from([(staked, 100)])));
+
+        // an unstaked requester is cut off once its own budget is spent
+        assert!(admission.admit(&unstaked, now));
+        admission.charge(&unstaked, 1_000);
+        assert!(!admission.admit(&unstaked, now));
+        // and is served again after the budget is refilled
+        assert!(admission.admit(&unstaked, now + Duration::from_millis(500)));
+
+        // the staked requester gets the whole staked budget
+        let staked = Requester::Node(staked);
+        assert!(admission.admit(&staked, now));
+        admission.charge(&staked, 9_000);
+        assert!(admission.admit(&staked, now));
+        admission.charge(&staked, 1_000);
+        assert!(!admission.admit(&staked, now));
+
+        // unstaked requesters together can't exceed the common unstaked budget
+        for _ in 0..2 {
+            let requester = Requester::Node(Pubkey::new_unique());
+            assert!(admission.admit(&requester, now + Duration::from_millis(500)));
+            admission.charge(&requester, 1_000);
+        }
+        let requester = Requester::Node(Pubkey::new_unique());
+        assert!(!admission.admit(&requester, now + Duration::from_millis(500)));
+
+        // whitelisted requesters are never throttled
+        let whitelisted = Requester::Node(whitelisted);
+        for _ in 0..10 {
+            assert!(admission.admit(&whitelisted, now));
+            admission.charge(&whitelisted, 1_000_000);
+        }
+    }
+
+    #[test]
+    fn test_serve_repair_admission_unverified_requester() {
+        let staked = Pubkey::new_unique();
+        let config = ServeRepairAdmissionConfig {
+            staked_bytes_per_second: 10_000,
+            unstaked_bytes_per_second: 2_000,
+            unstaked_requester_bytes_per_second: 1_000,
+            burst: Duration::from_secs(1),
+            whitelist: HashSet::from([staked]),
+        };
+        let mut admission = ServeRepairAdmission::new(config, None);
+        let now = Instant::now();
+        admission.last_stakes_refresh = Some(now);
+        admission.set_stakes(Arc::new(HashMap::from([(staked, 100)])));
+
+        // a source address is held to the unstaked budget, whoever it claims to be
+        let requester = Requester::Address(IpAddr::from([10, 0, 0, 1]));
+        assert!(admission.admit(&requester, now));
+        admission.charge(&requester, 1_000);
+        assert!(!admission.admit(&requester, now));
+        // while other addresses have budgets of their own
+        let requester = Requester::Address(IpAddr::from([10, 0, 0, 2]));
+        assert!(admission.admit(&requester, now));
+    }
+}
@@ -1,5 +1,5 @@
 use {
-    crate::serve_repair::ServeRepair,
+    crate::{serve_repair::ServeRepair, serve_repair_admission::ServeRepairAdmission},
     crossbeam_channel::{unbounded, Sender},
     solana_ledger::blockstore::Blockstore,
     solana_perf::recycler::Recycler,
@@ -25,6 +25,7 @@ impl ServeRepairService {
         serve_repair_socket: UdpSocket,
         socket_addr_space: SocketAddrSpace,
         stats_reporter_sender: Sender<Box<dyn FnOnce() + Send>>,
+        admission: Option<ServeRepairAdmission>,
         exit: &Arc<AtomicBool>,
     ) -> Self {
         let (request_sender, request_receiver) = unbounded();
@@ -57,6 +58,7 @@ impl ServeRepairService {
             blockstore,
             request_receiver,
             response_sender,
+            admission,
             exit,
         );
 
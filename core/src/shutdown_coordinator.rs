@@ -0,0 +1,237 @@
+//! The `shutdown_coordinator` module stops the services of a validator one after another, in
+//! dependency order, giving each a deadline to exit. A service that misses its deadline is
+//! reported and left behind rather than hanging shutdown, so the validator still exits and
+//! operators don't have to kill it, which risks a lengthy blockstore recovery on restart.
+use {
+    crossbeam_channel::{bounded, RecvTimeoutError},
+    std::{
+        collections::HashMap,
+        thread::{self, Builder},
+        time::{Duration, Instant},
+    },
+};
+
+/// Time a service is given to exit unless it has its own deadline
+pub const DEFAULT_SERVICE_SHUTDOWN_DEADLINE: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug)]
+pub struct ShutdownConfig {
+    pub default_deadline: Duration,
+    /// Deadlines of individual services, by service name
+    pub service_deadlines: HashMap<String, Duration>,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            default_deadline: DEFAULT_SERVICE_SHUTDOWN_DEADLINE,
+            // Both may be in the middle of flushing or archiving a snapshot
+            service_deadlines: HashMap::from([
+                (
+                    "accounts_background_service".to_string(),
+                    Duration::from_secs(300),
+                ),
+                (
+                    "snapshot_packager_service".to_string(),
+                    Duration::from_secs(300),
+                ),
+            ]),
+        }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn deadline(&self, service: &str) -> Duration {
+        self.service_deadlines
+            .get(service)
+            .copied()
+            .unwrap_or(self.default_deadline)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShutdownOutcome {
+    Stopped,
+    Panicked,
+    /// The service did not exit by its deadline and was left running
+    TimedOut,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServiceShutdown {
+    pub service: &'static str,
+    pub outcome: ShutdownOutcome,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    pub services: Vec<ServiceShutdown>,
+}
+
+impl ShutdownReport {
+    /// Services that did not exit by their deadline
+    pub fn timed_out_services(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.services_with(ShutdownOutcome::TimedOut)
+    }
+
+    pub fn panicked_services(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.services_with(ShutdownOutcome::Panicked)
+    }
+
+    fn services_with(&self, outcome: ShutdownOutcome) -> impl Iterator<Item = &'static str> + '_ {
+        self.services
+            .iter()
+            .filter(move |service_shutdown| service_shutdown.outcome == outcome)
+            .map(|service_shutdown| service_shutdown.service)
+    }
+}
+
+pub struct ShutdownCoordinator {
+    config: ShutdownConfig,
+    start: Instant,
+    report: ShutdownReport,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(config: ShutdownConfig) -> Self {
+        Self {
+            config,
+            start: Instant::now(),
+            report: ShutdownReport::default(),
+        }
+    }
+
+    /// Waits up to the deadline of `service` for `join` to return. The services must have
+    /// been signaled to exit beforehand; `join` runs on a thread of its own, which is left
+    /// behind if the deadline passes.
+    pub fn join<F>(&mut self, service: &'static str, join: F) -> ShutdownOutcome
+    where
+        F: FnOnce() -> thread::Result<()> + Send + 'static,
+    {
+        let deadline = self.config.deadline(service);
+        let start = Instant::now();
+        let (sender, receiver) = bounded(1);
+        Builder::new()
+            .name("sol-shutdown".to_string())
+            .spawn(move || {
+                let _ = sender.send(join());
+            })
+            .unwrap();
+        let outcome = match receiver.recv_timeout(deadline) {
+            Ok(Ok(())) => ShutdownOutcome::Stopped,
+            Ok(Err(_)) | Err(RecvTimeoutError::Disconnected) => {
+                error!("{} panicked during shutdown", service);
+                ShutdownOutcome::Panicked
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                error!(
+                    "{} did not stop within {:?}, leaving it behind",
+                    service, deadline
+                );
+                ShutdownOutcome::TimedOut
+            }
+        };
+        let elapsed = start.elapsed();
+        datapoint_info!(
+            "validator-shutdown-service",
+            ("service", service, String),
+            ("outcome", format!("{:?}", outcome), String),
+            ("elapsed_ms", elapsed.as_millis() as i64, i64),
+        );
+        self.report.services.push(ServiceShutdown {
+            service,
+            outcome,
+            elapsed,
+        });
+        outcome
+    }
+
+    pub fn finish(self) -> ShutdownReport {
+        let timed_out_services: Vec<_> = self.report.timed_out_services().collect();
+        if timed_out_services.is_empty() {
+            info!("Shutdown completed in {:?}", self.start.elapsed());
+        } else {
+            warn!(
+                "Shutdown completed in {:?}, blocked by {:?}",
+                self.start.elapsed(),
+                timed_out_services
+            );
+        }
+        datapoint_info!(
+            "validator-shutdown",
+            ("elapsed_ms", self.start.elapsed().as_millis() as i64, i64),
+            ("num_services", self.report.services.len(), i64),
+            ("num_timed_out", timed_out_services.len(), i64),
+            ("num_panicked", self.report.panicked_services().count(), i64),
+        );
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    #[test]
+    fn test_shutdown_coordinator() {
+        let config = ShutdownConfig {
+            default_deadline: Duration::from_secs(10),
+            service_deadlines: HashMap::from([("stuck".to_string(), Duration::from_millis(10))]),
+        };
+        let mut shutdown_coordinator = ShutdownCoordinator::new(config);
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stopped = stopped.clone();
+            Builder::new()
+                .spawn(move || stopped.store(true, Ordering::Relaxed))
+                .unwrap()
+        };
+        assert_eq!(
+            shutdown_coordinator.join("stopping", move || handle.join()),
+            ShutdownOutcome::Stopped
+        );
+        assert!(stopped.load(Ordering::Relaxed));
+
+        let release = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let release = release.clone();
+            Builder::new()
+                .spawn(move || {
+                    while !release.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            shutdown_coordinator.join("stuck", move || handle.join()),
+            ShutdownOutcome::TimedOut
+        );
+        release.store(true, Ordering::Relaxed);
+
+        let handle = Builder::new().spawn(|| panic!("service failed")).unwrap();
+        assert_eq!(
+            shutdown_coordinator.join("panicking", move || handle.join()),
+            ShutdownOutcome::Panicked
+        );
+
+        let report = shutdown_coordinator.finish();
+        assert_eq!(report.services.len(), 3);
+        assert_eq!(
+            report.timed_out_services().collect::<Vec<_>>(),
+            vec!["stuck"]
+        );
+        assert_eq!(
+            report.panicked_services().collect::<Vec<_>>(),
+            vec!["panicking"]
+        );
+    }
+}
@@ -3,8 +3,10 @@
 
 use {
     crate::{
-        banking_stage::BankingStage,
-        broadcast_stage::{BroadcastStage, BroadcastStageType, RetransmitSlotsReceiver},
+        banking_stage::{BankingStage, BankingStageConfig},
+        broadcast_stage::{
+            BroadcastConfig, BroadcastStage, BroadcastStageType, RetransmitSlotsReceiver,
+        },
         cluster_info_vote_listener::{
             ClusterInfoVoteListener, GossipDuplicateConfirmedSlotsSender,
             GossipVerifiedVoteHashSender, VerifiedVoteSender, VoteTracker,
@@ -14,12 +16,15 @@ use {
         sigverify::TransactionSigVerifier,
         sigverify_stage::SigVerifyStage,
         staked_nodes_updater_service::StakedNodesUpdaterService,
+        unprocessed_packet_batches::PacketDropNotifier,
     },
     crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError},
+    solana_geyser_plugin_manager::ingest_plugin_manager::IngestPluginManager,
     solana_gossip::cluster_info::ClusterInfo,
     solana_ledger::{blockstore::Blockstore, blockstore_processor::TransactionStatusSender},
     solana_poh::poh_recorder::{PohRecorder, WorkingBankEntry},
     solana_rpc::{
+        buffered_prioritization_fees::BufferedPrioritizationFees,
         optimistically_confirmed_bank_tracker::BankNotificationSender,
         rpc_subscriptions::RpcSubscriptions,
     },
@@ -29,7 +34,10 @@ use {
         vote_sender_types::{ReplayVoteReceiver, ReplayVoteSender},
     },
     solana_sdk::signature::Keypair,
-    solana_streamer::quic::{spawn_server, MAX_STAKED_CONNECTIONS, MAX_UNSTAKED_CONNECTIONS},
+    solana_streamer::quic::{
+        spawn_server, QuicDropNotifier, QuicServerQos, MAX_STAKED_CONNECTIONS,
+        MAX_UNSTAKED_CONNECTIONS,
+    },
     std::{
         collections::HashMap,
         net::UdpSocket,
@@ -47,12 +55,34 @@ const TPU_THREADS_JOIN_TIMEOUT_SECONDS: u64 = 10;
 // allow multiple connections for NAT and any open/close overlap
 pub const MAX_QUIC_CONNECTIONS_PER_IP: usize = 8;
 
+/// Limits of the QUIC server on the transaction port
+pub const TPU_QUIC_QOS: QuicServerQos = QuicServerQos {
+    max_connections_per_ip: MAX_QUIC_CONNECTIONS_PER_IP,
+    max_staked_connections: MAX_STAKED_CONNECTIONS,
+    max_unstaked_connections: MAX_UNSTAKED_CONNECTIONS,
+    max_staked_streams_per_second: 0,
+    max_unstaked_streams_per_second: 0,
+};
+
+/// Limits of the QUIC server on the vote port. Votes are sent by staked validators, which
+/// get a connection each without a stream limit; the few unstaked senders share a small
+/// connection quota and are throttled to a handful of votes per second, so they can't crowd
+/// out staked voters the way transaction senders could on a shared port.
+pub const TPU_VOTE_QUIC_QOS: QuicServerQos = QuicServerQos {
+    max_connections_per_ip: MAX_QUIC_CONNECTIONS_PER_IP,
+    max_staked_connections: MAX_STAKED_CONNECTIONS,
+    max_unstaked_connections: 64,
+    max_staked_streams_per_second: 0,
+    max_unstaked_streams_per_second: 8,
+};
+
 pub struct TpuSockets {
     pub transactions: Vec<UdpSocket>,
     pub transaction_forwards: Vec<UdpSocket>,
     pub vote: Vec<UdpSocket>,
     pub broadcast: Vec<UdpSocket>,
     pub transactions_quic: UdpSocket,
+    pub vote_quic: UdpSocket,
 }
 
 pub struct Tpu {
@@ -63,6 +93,7 @@ pub struct Tpu {
     cluster_info_vote_listener: ClusterInfoVoteListener,
     broadcast_stage: BroadcastStage,
     tpu_quic_t: thread::JoinHandle<()>,
+    tpu_vote_quic_t: thread::JoinHandle<()>,
     find_packet_sender_stake_stage: FindPacketSenderStakeStage,
     vote_find_packet_sender_stake_stage: FindPacketSenderStakeStage,
     staked_nodes_updater_service: StakedNodesUpdaterService,
@@ -82,6 +113,7 @@ impl Tpu {
         broadcast_type: &BroadcastStageType,
         exit: &Arc<AtomicBool>,
         shred_version: u16,
+        broadcast_config: &BroadcastConfig,
         vote_tracker: Arc<VoteTracker>,
         bank_forks: Arc<RwLock<BankForks>>,
         verified_vote_sender: VerifiedVoteSender,
@@ -93,6 +125,10 @@ impl Tpu {
         cluster_confirmed_slot_sender: GossipDuplicateConfirmedSlotsSender,
         cost_model: &Arc<RwLock<CostModel>>,
         keypair: &Keypair,
+        mut banking_stage_config: BankingStageConfig,
+        banking_notify_dropped_transactions: bool,
+        buffered_prioritization_fees: Arc<BufferedPrioritizationFees>,
+        ingest_plugin_manager: Option<Arc<IngestPluginManager>>,
     ) -> Self {
         let TpuSockets {
             transactions: transactions_sockets,
@@ -100,6 +136,7 @@ impl Tpu {
             vote: tpu_vote_sockets,
             broadcast: broadcast_sockets,
             transactions_quic: transactions_quic_sockets,
+            vote_quic: vote_quic_sockets,
         } = sockets;
 
         let (packet_sender, packet_receiver) = unbounded();
@@ -130,6 +167,7 @@ impl Tpu {
             packet_receiver,
             find_packet_sender_stake_sender,
             staked_nodes.clone(),
+            ingest_plugin_manager,
             "tpu-find-packet-sender-stake",
         );
 
@@ -140,21 +178,40 @@ impl Tpu {
             vote_packet_receiver,
             vote_find_packet_sender_stake_sender,
             staked_nodes.clone(),
+            None, // votes are not screened by ingest plugins
             "tpu-vote-find-packet-sender-stake",
         );
 
         let (verified_sender, verified_receiver) = unbounded();
 
+        let drop_notifier =
+            banking_notify_dropped_transactions.then(|| Arc::new(QuicDropNotifier::default()));
+        banking_stage_config.drop_notifier = drop_notifier
+            .clone()
+            .map(|drop_notifier| drop_notifier as Arc<dyn PacketDropNotifier>);
         let tpu_quic_t = spawn_server(
+            "quic-connections",
             transactions_quic_sockets,
             keypair,
             cluster_info.my_contact_info().tpu.ip(),
             packet_sender,
             exit.clone(),
-            MAX_QUIC_CONNECTIONS_PER_IP,
+            staked_nodes.clone(),
+            TPU_QUIC_QOS,
+            drop_notifier,
+        )
+        .unwrap();
+
+        let tpu_vote_quic_t = spawn_server(
+            "quic-vote-connections",
+            vote_quic_sockets,
+            keypair,
+            cluster_info.my_contact_info().tpu_vote.ip(),
+            vote_packet_sender,
+            exit.clone(),
             staked_nodes,
-            MAX_STAKED_CONNECTIONS,
-            MAX_UNSTAKED_CONNECTIONS,
+            TPU_VOTE_QUIC_QOS,
+            None,
         )
         .unwrap();
 
@@ -193,15 +250,18 @@ impl Tpu {
             cluster_confirmed_slot_sender,
         );
 
-        let banking_stage = BankingStage::new(
+        let banking_stage = BankingStage::new_num_threads(
             cluster_info,
             poh_recorder,
             verified_receiver,
             verified_tpu_vote_packets_receiver,
             verified_gossip_vote_packets_receiver,
+            BankingStage::num_threads(),
             transaction_status_sender,
             replay_vote_sender,
             cost_model.clone(),
+            buffered_prioritization_fees,
+            banking_stage_config,
         );
 
         let broadcast_stage = broadcast_type.new_broadcast_stage(
@@ -213,6 +273,7 @@ impl Tpu {
             blockstore,
             &bank_forks,
             shred_version,
+            broadcast_config,
         );
 
         Self {
@@ -223,6 +284,7 @@ impl Tpu {
             cluster_info_vote_listener,
             broadcast_stage,
             tpu_quic_t,
+            tpu_vote_quic_t,
             find_packet_sender_stake_stage,
             vote_find_packet_sender_stake_stage,
             staked_nodes_updater_service,
@@ -257,6 +319,7 @@ impl Tpu {
             self.staked_nodes_updater_service.join(),
         ];
         self.tpu_quic_t.join()?;
+        self.tpu_vote_quic_t.join()?;
         let broadcast_result = self.broadcast_stage.join();
         for result in results {
             result?;
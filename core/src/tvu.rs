@@ -14,7 +14,7 @@ use {
         consensus::Tower,
         cost_update_service::CostUpdateService,
         drop_bank_service::DropBankService,
-        ledger_cleanup_service::LedgerCleanupService,
+        ledger_cleanup_service::{LedgerCleanupService, SlotPurgeListeners},
         replay_stage::{ReplayStage, ReplayStageConfig},
         retransmit_stage::RetransmitStage,
         rewards_recorder_service::RewardsRecorderSender,
@@ -84,11 +84,13 @@ pub struct TvuSockets {
 #[derive(Default)]
 pub struct TvuConfig {
     pub max_ledger_shreds: Option<u64>,
+    pub max_ledger_bytes: Option<u64>,
     pub shred_version: u16,
     pub repair_validators: Option<HashSet<Pubkey>>,
     pub rocksdb_compaction_interval: Option<u64>,
     pub rocksdb_max_compaction_jitter: Option<u64>,
     pub wait_for_vote_to_start_leader: bool,
+    pub slot_purge_listeners: SlotPurgeListeners,
 }
 
 impl Tvu {
@@ -278,14 +280,18 @@ impl Tvu {
             transaction_cost_metrics_sender,
         );
 
-        let ledger_cleanup_service = tvu_config.max_ledger_shreds.map(|max_ledger_shreds| {
+        let ledger_cleanup_service = (tvu_config.max_ledger_shreds.is_some()
+            || tvu_config.max_ledger_bytes.is_some())
+        .then(|| {
             LedgerCleanupService::new(
                 ledger_cleanup_slot_receiver,
                 blockstore.clone(),
-                max_ledger_shreds,
+                tvu_config.max_ledger_shreds.unwrap_or(u64::MAX),
+                tvu_config.max_ledger_bytes,
                 exit,
                 tvu_config.rocksdb_compaction_interval,
                 tvu_config.rocksdb_max_compaction_jitter,
+                tvu_config.slot_purge_listeners.clone(),
             )
         });
 
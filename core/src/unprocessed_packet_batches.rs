@@ -1,20 +1,51 @@
+#[cfg(feature = "fault-injection")]
+use crate::packet_fault_injection::{
+    PacketFaultInjectionConfig, PacketFaultInjectionStats, PacketFaultInjector,
+};
 use {
+    crate::packet_view::PacketView,
+    histogram::Histogram,
+    itertools::Itertools,
     min_max_heap::MinMaxHeap,
-    solana_perf::packet::{Packet, PacketBatch},
-    solana_program_runtime::compute_budget::ComputeBudget,
+    once_cell::sync::OnceCell,
+    rand::{thread_rng, Rng},
+    rayon::{prelude::*, ThreadPool},
+    solana_perf::packet::{
+        Meta, Packet, PacketBatch, PacketFlags, PACKETS_PER_BATCH, PACKET_DATA_SIZE,
+    },
+    solana_program_runtime::compute_budget::{
+        ComputeBudget, DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+    },
+    solana_runtime::bank::Bank,
     solana_sdk::{
-        hash::Hash,
-        message::{Message, SanitizedVersionedMessage},
+        borsh::try_from_slice_unchecked,
+        compute_budget::{self, ComputeBudgetInstruction},
+        fee_calculator::DEFAULT_TARGET_LAMPORTS_PER_SIGNATURE,
+        hash::{hashv, Hash},
+        instruction::CompiledInstruction,
+        message::{Message, SanitizedVersionedMessage, VersionedMessage},
+        nonce_account,
+        pubkey::Pubkey,
         sanitize::SanitizeError,
         short_vec::decode_shortu16_len,
         signature::Signature,
+        timing::AtomicInterval,
         transaction::{SanitizedVersionedTransaction, Transaction, VersionedTransaction},
     },
+    solana_streamer::quic::{QuicDropNotifier, TransactionDropNotification, TransactionDropReason},
+    solana_transaction_status::extract_memos::{spl_memo_id_v1, spl_memo_id_v3},
     std::{
         cmp::Ordering,
-        collections::{hash_map::Entry, HashMap},
+        collections::{hash_map::Entry, HashMap, HashSet},
+        fs::{self, File},
+        io::{self, BufReader, BufWriter, Write},
         mem::size_of,
-        rc::Rc,
+        net::{IpAddr, Ipv4Addr},
+        ops::Deref,
+        path::Path,
+        str::FromStr,
+        sync::{Arc, Mutex, MutexGuard},
+        time::{Duration, Instant},
     },
     thiserror::Error,
 };
@@ -34,13 +65,182 @@ pub enum DeserializedPacketError {
     PrioritizationFailure,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Signature fee assumed when estimating what a buffered transaction will pay
+const ESTIMATED_LAMPORTS_PER_SIGNATURE: u64 = DEFAULT_TARGET_LAMPORTS_PER_SIGNATURE / 2;
+
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// Priority of simple vote transactions, which never request compute budget
+pub const SIMPLE_VOTE_PRIORITY: u64 = 0;
+
+/// Default batch size from which `deserialize_packets_par` deserializes on its thread pool
+pub const DESERIALIZE_PACKETS_PARALLEL_THRESHOLD: usize = 32;
+
+/// Prefix of a memo carrying a sequence hint, formatted as `seq:<stream>:<sequence>`
+pub const SEQUENCE_HINT_MEMO_PREFIX: &str = "seq:";
+
+/// Ordering hint an application attaches to a transaction with a memo instruction. Among the
+/// transactions of one fee payer on the same `stream`, the one with the highest `sequence`
+/// supersedes the others, so a sender can replace a stale order without both landing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceHint {
+    pub stream: u64,
+    pub sequence: u64,
+}
+
+/// Buffered packets a packet that passed the admission checks displaces once it's buffered
+struct Admission {
+    /// Packet of the same fee payer and stream with a lower sequence
+    superseded_hash: Option<Hash>,
+    /// Lowest priority packet of the same lock set, if the lock set is at its limit
+    coalesced_hash: Option<Hash>,
+}
+
+/// Identifies the buffered transactions a replace-by-fee packet may replace: those of the same
+/// fee payer and recent blockhash, which is the nonce for durable nonce transactions, whose
+/// messages are identical apart from the compute unit price they offer. Raising the price
+/// changes every signature of a transaction, so signatures can't tell such bumps apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReplacementKey {
+    pub fee_payer: Pubkey,
+    pub recent_blockhash: Hash,
+    /// Hash of the message with its compute unit price instructions left out
+    pub unpriced_message_hash: Hash,
+}
+
+impl ReplacementKey {
+    pub fn new(message: &SanitizedVersionedMessage) -> Self {
+        let static_account_keys = message.message.static_account_keys();
+        let is_priced = |instruction: &CompiledInstruction| {
+            instruction.program_id(static_account_keys) == &compute_budget::id()
+                && matches!(
+                    try_from_slice_unchecked::<ComputeBudgetInstruction>(&instruction.data),
+                    Ok(ComputeBudgetInstruction::SetComputeUnitPrice(_))
+                )
+        };
+        let mut unpriced_message = message.message.clone();
+        match &mut unpriced_message {
+            VersionedMessage::Legacy(message) => message
+                .instructions
+                .retain(|instruction| !is_priced(instruction)),
+            VersionedMessage::V0(message) => message
+                .instructions
+                .retain(|instruction| !is_priced(instruction)),
+        }
+        Self {
+            fee_payer: static_account_keys[0],
+            recent_blockhash: *message.message.recent_blockhash(),
+            unpriced_message_hash: unpriced_message.hash(),
+        }
+    }
+}
+
+/// How the priority of a packet is derived from its compute budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityMode {
+    /// The compute unit price requested by the transaction
+    ComputeUnitPrice,
+    /// The estimated fee paid per requested compute unit, so transactions requesting far more
+    /// compute units than they use can't outrank small ones paying the same total fee
+    FeePerComputeUnit,
+}
+
+impl Default for PriorityMode {
+    fn default() -> Self {
+        Self::ComputeUnitPrice
+    }
+}
+
+/// How a leader picks the buffered packets it executes next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopPolicy {
+    /// Highest priority first
+    Greedy,
+    /// Sampled with probability proportional to priority, so that outbidding the other
+    /// packets by the smallest margin doesn't guarantee being executed first
+    WeightedRandom,
+}
+
+impl Default for PopPolicy {
+    fn default() -> Self {
+        Self::Greedy
+    }
+}
+
+impl FromStr for PopPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "greedy" => Ok(Self::Greedy),
+            "weighted-random" => Ok(Self::WeightedRandom),
+            _ => Err(format!("unknown pop policy: {}", s)),
+        }
+    }
+}
+
+/// Selects the `PopPolicy` of every banking thread
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PopPolicyConfig {
+    pub default_policy: PopPolicy,
+    /// Policies of individual banking threads, by thread id
+    pub thread_policies: HashMap<u32, PopPolicy>,
+}
+
+impl PopPolicyConfig {
+    pub fn policy_for(&self, id: u32) -> PopPolicy {
+        self.thread_policies
+            .get(&id)
+            .copied()
+            .unwrap_or(self.default_policy)
+    }
+}
+
+/// Estimated fee per requested compute unit, in micro-lamports so that cheap transactions
+/// don't all round down to zero
+fn fee_per_compute_unit(estimated_fee: u64, compute_unit_limit: u64) -> u64 {
+    estimated_fee
+        .saturating_mul(MICRO_LAMPORTS_PER_LAMPORT)
+        .checked_div(compute_unit_limit)
+        .unwrap_or(u64::MAX)
+}
+
+/// Compute units and fee requested by the compute budget instructions of a transaction
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudgetDetails {
+    pub priority: u64,
+    pub compute_unit_limit: u64,
+    /// Price per compute unit, in micro-lamports
+    pub compute_unit_price: u64,
+    /// Signature fee plus prioritization fee, in lamports
+    pub estimated_fee: u64,
+}
+
+impl ComputeBudgetDetails {
+    pub fn priority_for(&self, priority_mode: PriorityMode) -> u64 {
+        match priority_mode {
+            PriorityMode::ComputeUnitPrice => self.priority,
+            PriorityMode::FeePerComputeUnit => {
+                fee_per_compute_unit(self.estimated_fee, self.compute_unit_limit)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct ImmutableDeserializedPacket {
     original_packet: Packet,
-    transaction: SanitizedVersionedTransaction,
+    arrival: Instant,
+    /// Deserialized from `original_packet` on first use
+    transaction: OnceCell<Option<SanitizedVersionedTransaction>>,
     message_hash: Hash,
+    fee_payer: Pubkey,
+    recent_blockhash: Hash,
     is_simple_vote: bool,
     priority: u64,
+    compute_unit_limit: u64,
+    compute_unit_price: u64,
+    estimated_fee: u64,
 }
 
 impl ImmutableDeserializedPacket {
@@ -48,14 +248,44 @@ impl ImmutableDeserializedPacket {
         &self.original_packet
     }
 
-    pub fn transaction(&self) -> &SanitizedVersionedTransaction {
-        &self.transaction
+    /// Estimated memory held for the packet while it is buffered: the packet itself, the
+    /// entries tracking it, and its transaction, which takes about as much heap once
+    /// deserialized as its payload
+    pub fn buffered_size(&self) -> usize {
+        size_of::<Self>()
+            + size_of::<Hash>()
+            + size_of::<DeserializedPacket>()
+            + size_of::<PrioritizedPacket>()
+            + self.original_packet.meta.size
+    }
+
+    /// The transaction of the packet, which is only deserialized when first requested, as
+    /// most buffered packets are dropped or forwarded without being scheduled. Returns `None`
+    /// if the packet doesn't deserialize, which `PacketView` rules out on arrival.
+    pub fn transaction(&self) -> Option<&SanitizedVersionedTransaction> {
+        self.transaction
+            .get_or_init(|| {
+                let versioned_transaction: VersionedTransaction =
+                    self.original_packet.deserialize_slice(..).ok()?;
+                SanitizedVersionedTransaction::try_from(versioned_transaction).ok()
+            })
+            .as_ref()
+    }
+
+    /// Reads the transaction of the packet in place
+    pub fn packet_view(&self) -> PacketView<'_> {
+        PacketView::new(self.original_packet.data()).expect("packet was parsed on arrival")
     }
 
     pub fn sender_stake(&self) -> u64 {
         self.original_packet.meta.sender_stake
     }
 
+    /// When the packet was deserialized on arrival at the banking stage
+    pub fn arrival(&self) -> Instant {
+        self.arrival
+    }
+
     pub fn message_hash(&self) -> &Hash {
         &self.message_hash
     }
@@ -67,13 +297,190 @@ impl ImmutableDeserializedPacket {
     pub fn priority(&self) -> u64 {
         self.priority
     }
+
+    /// Compute units the transaction may consume, as requested by its compute budget
+    /// instructions or derived from the default per-instruction limit
+    pub fn compute_unit_limit(&self) -> u64 {
+        self.compute_unit_limit
+    }
+
+    /// Price per compute unit offered by the transaction, in micro-lamports
+    pub fn compute_unit_price(&self) -> u64 {
+        self.compute_unit_price
+    }
+
+    /// Signature fee plus prioritization fee the transaction is expected to pay, in lamports
+    pub fn estimated_fee(&self) -> u64 {
+        self.estimated_fee
+    }
+
+    /// Estimated fee per requested compute unit, in micro-lamports
+    pub fn fee_per_compute_unit(&self) -> u64 {
+        fee_per_compute_unit(self.estimated_fee, self.compute_unit_limit)
+    }
+
+    pub fn compute_budget_details(&self) -> ComputeBudgetDetails {
+        ComputeBudgetDetails {
+            priority: self.priority,
+            compute_unit_limit: self.compute_unit_limit,
+            compute_unit_price: self.compute_unit_price,
+            estimated_fee: self.estimated_fee,
+        }
+    }
+
+    /// Priority of the packet when derived according to `priority_mode`
+    pub fn priority_for(&self, priority_mode: PriorityMode) -> u64 {
+        self.compute_budget_details().priority_for(priority_mode)
+    }
+
+    /// Copy of the packet with its compute budget details derived under `compute_budget`
+    /// rather than the default one. Returns `None` if the transaction no longer prioritizes.
+    fn reprioritized(&self, compute_budget: &ComputeBudget) -> Option<Self> {
+        let packet_view = self.packet_view();
+        let compute_budget_details = if self.is_simple_vote {
+            simple_vote_compute_budget_details(packet_view.header().num_required_signatures)
+        } else {
+            get_packet_compute_budget_details_with_budget(&packet_view, compute_budget)?
+        };
+        Some(Self {
+            original_packet: self.original_packet.clone(),
+            arrival: self.arrival,
+            transaction: self.transaction.clone(),
+            message_hash: self.message_hash,
+            fee_payer: self.fee_payer,
+            recent_blockhash: self.recent_blockhash,
+            is_simple_vote: self.is_simple_vote,
+            priority: compute_budget_details.priority,
+            compute_unit_limit: compute_budget_details.compute_unit_limit,
+            compute_unit_price: compute_budget_details.compute_unit_price,
+            estimated_fee: compute_budget_details.estimated_fee,
+        })
+    }
+
+    /// The fee payer of the transaction, which is always its first account key
+    pub fn fee_payer(&self) -> &Pubkey {
+        &self.fee_payer
+    }
+
+    pub fn recent_blockhash(&self) -> &Hash {
+        &self.recent_blockhash
+    }
+
+    /// Sequence hint of the transaction, read without deserializing it
+    pub fn sequence_hint(&self) -> Option<SequenceHint> {
+        memo_sequence_hint(
+            self.packet_view()
+                .program_instructions()
+                .map(|(program_id, instruction)| (program_id, instruction.data)),
+        )
+    }
+
+    /// The nonce account of the transaction if it uses a durable nonce, read without
+    /// deserializing it
+    pub fn durable_nonce_account(&self) -> Option<Pubkey> {
+        self.packet_view().durable_nonce_account()
+    }
+
+    /// Returns the static account keys of the message along with whether each may be
+    /// write-locked. Accounts loaded from address lookup tables are not included. Deserializes
+    /// the transaction, so should only be called for packets being scheduled.
+    pub fn static_account_keys_with_writability(&self) -> impl Iterator<Item = (&Pubkey, bool)> {
+        self.transaction().into_iter().flat_map(|transaction| {
+            let message = &transaction.get_message().message;
+            message
+                .static_account_keys()
+                .iter()
+                .enumerate()
+                .map(move |(index, key)| (key, message.is_maybe_writable(index)))
+        })
+    }
+}
+
+/// Screens packets before they are buffered, so rejected transactions take up neither buffer
+/// capacity nor banking cycles
+pub trait PacketFilter: Send + Sync {
+    /// Returns true if `packet` may be buffered
+    fn allow(&self, packet: &ImmutableDeserializedPacket) -> bool;
+}
+
+/// Rejects packets whose static account keys, which include the ids of the programs they
+/// invoke, contain a denied address
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccountDenylist {
+    denied_keys: HashSet<Pubkey>,
+}
+
+impl AccountDenylist {
+    pub fn new(denied_keys: HashSet<Pubkey>) -> Self {
+        Self { denied_keys }
+    }
+}
+
+impl PacketFilter for AccountDenylist {
+    fn allow(&self, packet: &ImmutableDeserializedPacket) -> bool {
+        !packet
+            .packet_view()
+            .static_account_keys()
+            .any(|key| self.denied_keys.contains(&key))
+    }
+}
+
+/// Tells the senders of buffered packets that their transactions were dropped without being
+/// processed, so they can resubmit them without waiting for confirmation to time out
+pub trait PacketDropNotifier: Send + Sync {
+    fn notify_dropped(&self, packet: &ImmutableDeserializedPacket, reason: TransactionDropReason);
+}
+
+impl PacketDropNotifier for QuicDropNotifier {
+    fn notify_dropped(&self, packet: &ImmutableDeserializedPacket, reason: TransactionDropReason) {
+        let meta = &packet.original_packet().meta;
+        // Forwarded packets arrive over the connection of the forwarding leader
+        if meta.forwarded() {
+            return;
+        }
+        let notification = TransactionDropNotification {
+            reason,
+            signature: packet.packet_view().signature(),
+        };
+        self.notify(&meta.socket_addr(), notification);
+    }
+}
+
+/// Set of account locks held by transactions that are already scheduled for execution
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PacketAccountLocks {
+    pub write_locks: HashSet<Pubkey>,
+    pub read_locks: HashSet<Pubkey>,
+}
+
+impl PacketAccountLocks {
+    /// Returns true if the static accounts of `packet` can be locked without conflicting with
+    /// the held locks
+    pub fn can_lock(&self, packet: &ImmutableDeserializedPacket) -> bool {
+        packet
+            .static_account_keys_with_writability()
+            .all(|(key, is_writable)| {
+                !self.write_locks.contains(key) && (!is_writable || !self.read_locks.contains(key))
+            })
+    }
+
+    /// Adds the static accounts of `packet` to the held locks
+    pub fn lock(&mut self, packet: &ImmutableDeserializedPacket) {
+        for (key, is_writable) in packet.static_account_keys_with_writability() {
+            if is_writable {
+                self.write_locks.insert(*key);
+            } else {
+                self.read_locks.insert(*key);
+            }
+        }
+    }
 }
 
 /// Holds deserialized messages, as well as computed message_hash and other things needed to create
 /// SanitizedTransaction
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DeserializedPacket {
-    immutable_section: Rc<ImmutableDeserializedPacket>,
+    immutable_section: Arc<ImmutableDeserializedPacket>,
     pub forwarded: bool,
 }
 
@@ -91,34 +498,89 @@ impl DeserializedPacket {
         packet: Packet,
         priority: Option<u64>,
     ) -> Result<Self, DeserializedPacketError> {
-        let versioned_transaction: VersionedTransaction = packet.deserialize_slice(..)?;
-        let sanitized_transaction = SanitizedVersionedTransaction::try_from(versioned_transaction)?;
-        let message_bytes = packet_message(&packet)?;
-        let message_hash = Message::hash_raw_message(message_bytes);
+        let packet_view = PacketView::new(packet.data())?;
+        let message_hash = Message::hash_raw_message(packet_view.message_bytes());
+        let fee_payer = packet_view.fee_payer();
+        let recent_blockhash = *packet_view.recent_blockhash();
         let is_simple_vote = packet.meta.is_simple_vote_tx();
 
-        // drop transaction if prioritization fails.
-        let priority = priority
-            .or_else(|| get_priority(sanitized_transaction.get_message()))
-            .ok_or(DeserializedPacketError::PrioritizationFailure)?;
+        // Simple votes consist of a single vote instruction, so there are no compute budget
+        // instructions to look for. The flag is only trusted for messages of that shape.
+        // Otherwise drop transaction if prioritization fails.
+        let compute_budget_details = if is_simple_vote && is_single_vote_instruction(&packet_view) {
+            simple_vote_compute_budget_details(packet_view.header().num_required_signatures)
+        } else {
+            get_packet_compute_budget_details(&packet_view)
+                .ok_or(DeserializedPacketError::PrioritizationFailure)?
+        };
+        let priority = priority.unwrap_or(compute_budget_details.priority);
 
         Ok(Self {
-            immutable_section: Rc::new(ImmutableDeserializedPacket {
+            immutable_section: Arc::new(ImmutableDeserializedPacket {
                 original_packet: packet,
-                transaction: sanitized_transaction,
+                arrival: Instant::now(),
+                transaction: OnceCell::new(),
                 message_hash,
+                fee_payer,
+                recent_blockhash,
                 is_simple_vote,
                 priority,
+                compute_unit_limit: compute_budget_details.compute_unit_limit,
+                compute_unit_price: compute_budget_details.compute_unit_price,
+                estimated_fee: compute_budget_details.estimated_fee,
             }),
             forwarded: false,
         })
     }
 
-    pub fn immutable_section(&self) -> &Rc<ImmutableDeserializedPacket> {
+    pub fn immutable_section(&self) -> &Arc<ImmutableDeserializedPacket> {
         &self.immutable_section
     }
 }
 
+/// A buffered packet as written to disk by `UnprocessedPacketBatches::save_to_file()`
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedPacket {
+    data: Vec<u8>,
+    addr: IpAddr,
+    port: u16,
+    flags: u8,
+    sender_stake: u64,
+    forwarded: bool,
+}
+
+impl From<&DeserializedPacket> for SavedPacket {
+    fn from(deserialized_packet: &DeserializedPacket) -> Self {
+        let packet = deserialized_packet.immutable_section().original_packet();
+        Self {
+            data: packet.data().to_vec(),
+            addr: packet.meta.addr,
+            port: packet.meta.port,
+            flags: packet.meta.flags.bits(),
+            sender_stake: packet.meta.sender_stake,
+            forwarded: deserialized_packet.forwarded,
+        }
+    }
+}
+
+impl SavedPacket {
+    fn into_packet(self) -> Option<Packet> {
+        if self.data.len() > PACKET_DATA_SIZE {
+            return None;
+        }
+        let mut packet = Packet::default();
+        packet.buffer_mut()[..self.data.len()].copy_from_slice(&self.data);
+        packet.meta = Meta {
+            size: self.data.len(),
+            addr: self.addr,
+            port: self.port,
+            flags: PacketFlags::from_bits_truncate(self.flags),
+            sender_stake: self.sender_stake,
+        };
+        Some(packet)
+    }
+}
+
 impl PartialOrd for DeserializedPacket {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -141,6 +603,24 @@ impl Ord for DeserializedPacket {
     }
 }
 
+// The arrival time and the lazily deserialized transaction are excluded, so packets
+// deserialized from the same bytes compare equal
+impl PartialEq for ImmutableDeserializedPacket {
+    fn eq(&self, other: &Self) -> bool {
+        self.original_packet == other.original_packet
+            && self.message_hash == other.message_hash
+            && self.fee_payer == other.fee_payer
+            && self.recent_blockhash == other.recent_blockhash
+            && self.is_simple_vote == other.is_simple_vote
+            && self.priority == other.priority
+            && self.compute_unit_limit == other.compute_unit_limit
+            && self.compute_unit_price == other.compute_unit_price
+            && self.estimated_fee == other.estimated_fee
+    }
+}
+
+impl Eq for ImmutableDeserializedPacket {}
+
 impl PartialOrd for ImmutableDeserializedPacket {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -156,372 +636,4277 @@ impl Ord for ImmutableDeserializedPacket {
     }
 }
 
-/// Currently each banking_stage thread has a `UnprocessedPacketBatches` buffer to store
-/// PacketBatch's received from sigverify. Banking thread continuously scans the buffer
-/// to pick proper packets to add to the block.
-#[derive(Default)]
-pub struct UnprocessedPacketBatches {
-    pub packet_priority_queue: MinMaxHeap<Rc<ImmutableDeserializedPacket>>,
-    pub message_hash_to_transaction: HashMap<Hash, DeserializedPacket>,
-    batch_limit: usize,
+/// Describes how buffered packets gain priority the longer they wait, so that low-priority
+/// packets are not starved forever by a steady stream of higher-priority ones. The default
+/// applies no boost, which is strict fee ordering.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PriorityAgingConfig {
+    /// Time a packet must spend in the buffer to earn each boost
+    pub interval: Duration,
+    /// Priority added for every `interval` a packet spends in the buffer
+    pub boost_per_interval: u64,
+    /// Upper bound on the total boost a packet can accrue
+    pub max_boost: u64,
 }
 
-impl UnprocessedPacketBatches {
-    pub fn from_iter<I: IntoIterator<Item = DeserializedPacket>>(iter: I, capacity: usize) -> Self {
-        let mut unprocessed_packet_batches = Self::with_capacity(capacity);
-        for deserialized_packet in iter.into_iter() {
-            unprocessed_packet_batches.push(deserialized_packet);
-        }
-
-        unprocessed_packet_batches
+impl PriorityAgingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.boost_per_interval > 0 && self.max_boost > 0 && !self.interval.is_zero()
     }
 
-    pub fn with_capacity(capacity: usize) -> Self {
-        UnprocessedPacketBatches {
-            packet_priority_queue: MinMaxHeap::with_capacity(capacity),
-            message_hash_to_transaction: HashMap::with_capacity(capacity),
-            batch_limit: capacity,
+    /// Returns the priority boost for a packet that has been buffered for `age`
+    pub fn boost(&self, age: Duration) -> u64 {
+        if !self.is_enabled() {
+            return 0;
         }
+        let intervals = age.as_nanos() / self.interval.as_nanos();
+        let intervals = u64::try_from(intervals).unwrap_or(u64::MAX);
+        intervals
+            .saturating_mul(self.boost_per_interval)
+            .min(self.max_boost)
     }
+}
 
-    pub fn clear(&mut self) {
-        self.packet_priority_queue.clear();
-        self.message_hash_to_transaction.clear();
-    }
+/// Entry of the priority queue. Packets are ordered by their effective priority, which is the
+/// transaction priority plus any boost accrued while waiting in the buffer. Packets of equal
+/// effective priority are ordered by the weight assigned by the `PacketWeightingPolicy` of
+/// the buffer when the packet was pushed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrioritizedPacket {
+    immutable_section: Arc<ImmutableDeserializedPacket>,
+    buffered_at: Instant,
+    priority: u64,
+    effective_priority: u64,
+    weight: u64,
+}
 
-    /// Insert new `deserialized_packet_batch` into inner `MinMaxHeap<DeserializedPacket>`,
-    /// weighted first by the tx priority, then the stake of the sender.
-    /// If buffer is at the max limit, the lowest weighted packet is dropped
-    ///
-    /// Returns tuple of number of packets dropped
-    pub fn insert_batch(
-        &mut self,
-        deserialized_packets: impl Iterator<Item = DeserializedPacket>,
-    ) -> usize {
-        let mut num_dropped_packets = 0;
-        for deserialized_packet in deserialized_packets {
-            if self.push(deserialized_packet).is_some() {
-                num_dropped_packets += 1;
-            }
+impl PrioritizedPacket {
+    fn new(
+        immutable_section: Arc<ImmutableDeserializedPacket>,
+        priority: u64,
+        weight: u64,
+    ) -> Self {
+        Self {
+            immutable_section,
+            buffered_at: Instant::now(),
+            priority,
+            effective_priority: priority,
+            weight,
         }
-        num_dropped_packets
     }
 
-    pub fn push(&mut self, deserialized_packet: DeserializedPacket) -> Option<DeserializedPacket> {
-        if self
-            .message_hash_to_transaction
-            .contains_key(deserialized_packet.immutable_section().message_hash())
-        {
-            return None;
-        }
-
-        if self.len() == self.batch_limit {
-            // Optimized to not allocate by calling `MinMaxHeap::push_pop_min()`
-            Some(self.push_pop_min(deserialized_packet))
-        } else {
-            self.push_internal(deserialized_packet);
-            None
-        }
+    pub fn immutable_section(&self) -> &Arc<ImmutableDeserializedPacket> {
+        &self.immutable_section
     }
 
-    pub fn iter(&mut self) -> impl Iterator<Item = &DeserializedPacket> {
-        self.message_hash_to_transaction.values()
+    pub fn buffered_at(&self) -> Instant {
+        self.buffered_at
     }
 
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut DeserializedPacket> {
-        self.message_hash_to_transaction.iter_mut().map(|(_k, v)| v)
+    /// Priority of the packet under the `PriorityMode` of the buffer, before aging
+    pub fn priority(&self) -> u64 {
+        self.priority
     }
 
-    pub fn retain<F>(&mut self, mut f: F)
-    where
-        F: FnMut(&mut DeserializedPacket) -> bool,
-    {
-        // TODO: optimize this only when number of packets
-        // with oudated blockhash is high
-        let new_packet_priority_queue: MinMaxHeap<Rc<ImmutableDeserializedPacket>> = self
-            .packet_priority_queue
-            .drain()
-            .filter(|immutable_packet| {
-                match self
-                    .message_hash_to_transaction
-                    .entry(*immutable_packet.message_hash())
-                {
-                    Entry::Vacant(_vacant_entry) => {
-                        panic!(
-                            "entry {} must exist to be consistent with `packet_priority_queue`",
-                            immutable_packet.message_hash()
-                        );
-                    }
-                    Entry::Occupied(mut occupied_entry) => {
-                        let should_retain = f(occupied_entry.get_mut());
-                        if !should_retain {
-                            occupied_entry.remove_entry();
-                        }
-                        should_retain
-                    }
-                }
-            })
-            .collect();
-        self.packet_priority_queue = new_packet_priority_queue;
+    pub fn effective_priority(&self) -> u64 {
+        self.effective_priority
     }
 
-    pub fn len(&self) -> usize {
-        self.packet_priority_queue.len()
+    pub fn weight(&self) -> u64 {
+        self.weight
     }
+}
 
-    pub fn is_empty(&self) -> bool {
-        self.packet_priority_queue.is_empty()
+impl Deref for PrioritizedPacket {
+    type Target = ImmutableDeserializedPacket;
+
+    fn deref(&self) -> &Self::Target {
+        &self.immutable_section
     }
+}
 
-    fn push_internal(&mut self, deserialized_packet: DeserializedPacket) {
-        // Push into the priority queue
-        self.packet_priority_queue
-            .push(deserialized_packet.immutable_section().clone());
+impl PartialOrd for PrioritizedPacket {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        // Keep track of the original packet in the tracking hashmap
-        self.message_hash_to_transaction.insert(
-            *deserialized_packet.immutable_section().message_hash(),
-            deserialized_packet,
-        );
+impl Ord for PrioritizedPacket {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.effective_priority
+            .cmp(&other.effective_priority)
+            .then(self.weight.cmp(&other.weight))
+            .then_with(|| self.immutable_section.cmp(&other.immutable_section))
     }
+}
 
-    /// Returns the popped minimum packet from the priority queue.
-    fn push_pop_min(&mut self, deserialized_packet: DeserializedPacket) -> DeserializedPacket {
-        let immutable_packet = deserialized_packet.immutable_section().clone();
+/// Breaks ties between buffered packets of equal priority. The policy assigns every packet a
+/// weight as it is pushed into an `UnprocessedPacketBatches`; among packets of the same
+/// effective priority, higher weights are popped first and lower weights are dropped first
+/// when the buffer is full.
+pub trait PacketWeightingPolicy: Default {
+    fn weight(&mut self, packet: &ImmutableDeserializedPacket) -> u64;
+}
 
-        // Push into the priority queue
-        let popped_immutable_packet = self.packet_priority_queue.push_pop_min(immutable_packet);
+/// Prefers packets from senders with more stake. This is the default policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakeWeightedPolicy;
 
-        if popped_immutable_packet.message_hash()
-            != deserialized_packet.immutable_section().message_hash()
-        {
-            // Remove the popped entry from the tracking hashmap. Unwrap call is safe
-            // because the priority queue and hashmap are kept consistent at all times.
-            let removed_min = self
-                .message_hash_to_transaction
-                .remove(popped_immutable_packet.message_hash())
-                .unwrap();
+impl PacketWeightingPolicy for StakeWeightedPolicy {
+    fn weight(&mut self, packet: &ImmutableDeserializedPacket) -> u64 {
+        packet.sender_stake()
+    }
+}
 
-            // Keep track of the original packet in the tracking hashmap
-            self.message_hash_to_transaction.insert(
-                *deserialized_packet.immutable_section().message_hash(),
-                deserialized_packet,
-            );
-            removed_min
-        } else {
-            deserialized_packet
-        }
+/// Prefers packets that arrived earlier
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArrivalOrderPolicy {
+    num_arrived: u64,
+}
+
+impl PacketWeightingPolicy for ArrivalOrderPolicy {
+    fn weight(&mut self, _packet: &ImmutableDeserializedPacket) -> u64 {
+        let weight = u64::MAX.saturating_sub(self.num_arrived);
+        self.num_arrived = self.num_arrived.saturating_add(1);
+        weight
     }
+}
 
-    pub fn pop_max(&mut self) -> Option<DeserializedPacket> {
-        self.packet_priority_queue
-            .pop_max()
-            .map(|immutable_packet| {
-                self.message_hash_to_transaction
-                    .remove(immutable_packet.message_hash())
-                    .unwrap()
-            })
+/// Orders packets of equal priority uniformly at random
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RandomPolicy;
+
+impl PacketWeightingPolicy for RandomPolicy {
+    fn weight(&mut self, _packet: &ImmutableDeserializedPacket) -> u64 {
+        rand::random()
     }
+}
 
-    /// Pop up to the next `n` highest priority transactions from the queue.
-    /// Returns `None` if the queue is empty
-    pub fn pop_max_n(&mut self, n: usize) -> Option<Vec<DeserializedPacket>> {
-        let current_len = self.len();
-        if self.is_empty() {
-            None
+/// Prefers packets paying the highest estimated fee, including signature fees, per requested
+/// compute unit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeePerComputeUnitPolicy;
+
+impl PacketWeightingPolicy for FeePerComputeUnitPolicy {
+    fn weight(&mut self, packet: &ImmutableDeserializedPacket) -> u64 {
+        packet.fee_per_compute_unit()
+    }
+}
+
+/// Senders tracked individually in `DedupStats`; duplicates from further senders are only
+/// counted in the total, so a flood of spoofed addresses can't grow the map without bound
+pub const MAX_DEDUP_STATS_SOURCES: usize = 1024;
+
+/// Counts of pushed packets that were dropped because the same message was already buffered
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DedupStats {
+    pub num_duplicates: usize,
+    pub duplicates_by_source: HashMap<IpAddr, usize>,
+}
+
+impl DedupStats {
+    fn record(&mut self, source: IpAddr) {
+        self.num_duplicates = self.num_duplicates.saturating_add(1);
+        let num_sources = self.duplicates_by_source.len();
+        match self.duplicates_by_source.entry(source) {
+            Entry::Occupied(mut entry) => *entry.get_mut() += 1,
+            Entry::Vacant(entry) => {
+                if num_sources < MAX_DEDUP_STATS_SOURCES {
+                    entry.insert(1);
+                }
+            }
+        }
+    }
+
+    /// Adds the counts of `other` into `self`, keeping within `MAX_DEDUP_STATS_SOURCES`
+    pub fn merge(&mut self, other: DedupStats) {
+        self.num_duplicates = self.num_duplicates.saturating_add(other.num_duplicates);
+        for (source, count) in other.duplicates_by_source {
+            let num_sources = self.duplicates_by_source.len();
+            match self.duplicates_by_source.entry(source) {
+                Entry::Occupied(mut entry) => *entry.get_mut() += count,
+                Entry::Vacant(entry) => {
+                    if num_sources < MAX_DEDUP_STATS_SOURCES {
+                        entry.insert(count);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns up to `n` senders with the most duplicates, most duplicates first
+    pub fn top_sources(&self, n: usize) -> Vec<(IpAddr, usize)> {
+        let mut sources: Vec<_> = self
+            .duplicates_by_source
+            .iter()
+            .map(|(source, count)| (*source, *count))
+            .collect();
+        sources.sort_unstable_by(|(a_source, a_count), (b_source, b_count)| {
+            b_count.cmp(a_count).then(a_source.cmp(b_source))
+        });
+        sources.truncate(n);
+        sources
+    }
+}
+
+/// Sources tracked individually by `SourceRateLimiter`; packets from further sources share a
+/// single budget, so a flood of spoofed addresses can't grow the map without bound
+pub const MAX_RATE_LIMITED_SOURCES: usize = 16 * 1024;
+
+const SOURCE_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+// Budget shared by the sources beyond `MAX_RATE_LIMITED_SOURCES`
+const OVERFLOW_SOURCE: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+/// Limits the packets admitted from each source address to a number per second. The rate is
+/// estimated over a sliding window by weighting the count of the previous one-second window
+/// by how much of it still overlaps the sliding window.
+#[derive(Debug)]
+pub struct SourceRateLimiter {
+    packets_per_second: u64,
+    window_start: Instant,
+    /// Packets admitted from each source in the current and the previous window
+    counts: HashMap<IpAddr, (u64, u64)>,
+}
+
+impl SourceRateLimiter {
+    pub fn new(packets_per_second: u64) -> Self {
+        Self {
+            packets_per_second,
+            window_start: Instant::now(),
+            counts: HashMap::default(),
+        }
+    }
+
+    pub fn packets_per_second(&self) -> u64 {
+        self.packets_per_second
+    }
+
+    /// Returns whether a packet from `source` arriving at `now` is within the budget of the
+    /// source, counting it if so
+    pub fn allow(&mut self, source: IpAddr, now: Instant) -> bool {
+        self.advance_window(now);
+        let elapsed = now.saturating_duration_since(self.window_start);
+        let previous_weight = 1.0 - elapsed.as_secs_f64() / SOURCE_RATE_LIMIT_WINDOW.as_secs_f64();
+        let source =
+            if self.counts.contains_key(&source) || self.counts.len() < MAX_RATE_LIMITED_SOURCES {
+                source
+            } else {
+                OVERFLOW_SOURCE
+            };
+        let (current, previous) = self.counts.entry(source).or_default();
+        let estimated_rate = *current as f64 + *previous as f64 * previous_weight;
+        if estimated_rate >= self.packets_per_second as f64 {
+            return false;
+        }
+        *current = current.saturating_add(1);
+        true
+    }
+
+    fn advance_window(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < SOURCE_RATE_LIMIT_WINDOW {
+            return;
+        }
+        if elapsed < SOURCE_RATE_LIMIT_WINDOW * 2 {
+            self.window_start += SOURCE_RATE_LIMIT_WINDOW;
+            // Sources idle for a whole window no longer weigh on their rate
+            self.counts.retain(|_, (current, previous)| {
+                *previous = std::mem::take(current);
+                *previous > 0
+            });
         } else {
-            let num_to_pop = std::cmp::min(current_len, n);
-            Some(
-                std::iter::from_fn(|| Some(self.pop_max().unwrap()))
-                    .take(num_to_pop)
-                    .collect::<Vec<DeserializedPacket>>(),
-            )
+            self.window_start = now;
+            self.counts.clear();
+        }
+    }
+}
+
+/// Time packets spent in the buffer since they were last pushed into it, recorded when they
+/// leave it. A packet pushed back after being popped starts waiting anew, so that its first
+/// wait is not counted again.
+#[derive(Debug)]
+pub struct QueueLatencyStats {
+    last_report: AtomicInterval,
+    /// Microseconds waited by packets popped for processing or forwarding
+    pub popped_us: Histogram,
+    /// Microseconds waited by packets evicted to make room for higher priority ones
+    pub evicted_us: Histogram,
+}
+
+impl Default for QueueLatencyStats {
+    fn default() -> Self {
+        Self {
+            last_report: AtomicInterval::default(),
+            popped_us: Histogram::new(),
+            evicted_us: Histogram::new(),
+        }
+    }
+}
+
+impl QueueLatencyStats {
+    fn record(histogram: &mut Histogram, packet: &PrioritizedPacket) {
+        let buffered_time = Instant::now().saturating_duration_since(packet.buffered_at());
+        let _ = histogram.increment(buffered_time.as_micros() as u64);
+    }
+
+    fn record_popped(&mut self, packet: &PrioritizedPacket) {
+        Self::record(&mut self.popped_us, packet);
+    }
+
+    fn record_evicted(&mut self, packet: &PrioritizedPacket) {
+        Self::record(&mut self.evicted_us, packet);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.popped_us.entries() == 0 && self.evicted_us.entries() == 0
+    }
+
+    /// Reports the latencies recorded since the last report at most once per
+    /// `report_interval_ms`, then clears them
+    pub fn report(&mut self, id: u32, report_interval_ms: u64) {
+        if self.is_empty() || !self.last_report.should_update(report_interval_ms) {
+            return;
+        }
+        datapoint_info!(
+            "banking_stage-queue_latency",
+            ("id", id as i64, i64),
+            ("popped_count", self.popped_us.entries() as i64, i64),
+            (
+                "popped_us_50pct",
+                self.popped_us.percentile(50.0).unwrap_or(0) as i64,
+                i64
+            ),
+            (
+                "popped_us_90pct",
+                self.popped_us.percentile(90.0).unwrap_or(0) as i64,
+                i64
+            ),
+            (
+                "popped_us_99pct",
+                self.popped_us.percentile(99.0).unwrap_or(0) as i64,
+                i64
+            ),
+            (
+                "popped_us_max",
+                self.popped_us.maximum().unwrap_or(0) as i64,
+                i64
+            ),
+            ("evicted_count", self.evicted_us.entries() as i64, i64),
+            (
+                "evicted_us_50pct",
+                self.evicted_us.percentile(50.0).unwrap_or(0) as i64,
+                i64
+            ),
+            (
+                "evicted_us_90pct",
+                self.evicted_us.percentile(90.0).unwrap_or(0) as i64,
+                i64
+            ),
+            (
+                "evicted_us_99pct",
+                self.evicted_us.percentile(99.0).unwrap_or(0) as i64,
+                i64
+            ),
+            (
+                "evicted_us_max",
+                self.evicted_us.maximum().unwrap_or(0) as i64,
+                i64
+            ),
+        );
+        self.popped_us.clear();
+        self.evicted_us.clear();
+    }
+}
+
+/// Distribution of the compute unit prices offered by buffered packets, which shows how
+/// congested the queue of the leader is before any of them land
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityQuantiles {
+    pub num_packets: usize,
+    pub min: u64,
+    pub median: u64,
+    pub p90: u64,
+}
+
+impl PriorityQuantiles {
+    /// Returns `None` if there are no prices
+    pub fn from_prices(mut prices: Vec<u64>) -> Option<Self> {
+        if prices.is_empty() {
+            return None;
+        }
+        prices.sort_unstable();
+        // Nearest rank, so every quantile is a price actually offered
+        let quantile = |percent: usize| prices[(prices.len() * percent + 99) / 100 - 1];
+        Some(Self {
+            num_packets: prices.len(),
+            min: prices[0],
+            median: quantile(50),
+            p90: quantile(90),
+        })
+    }
+}
+
+/// Currently each banking_stage thread has a `UnprocessedPacketBatches` buffer to store
+/// PacketBatch's received from sigverify. Banking thread continuously scans the buffer
+/// to pick proper packets to add to the block.
+///
+/// In dual-queue mode simple-vote packets are kept in a separate bounded queue, so a flood
+/// of user transactions can never evict votes and vice versa.
+#[derive(Default)]
+pub struct UnprocessedPacketBatches<P: PacketWeightingPolicy = StakeWeightedPolicy> {
+    pub packet_priority_queue: MinMaxHeap<PrioritizedPacket>,
+    pub message_hash_to_transaction: HashMap<Hash, DeserializedPacket>,
+    batch_limit: usize,
+    /// Queue for simple-vote packets, only present in dual-queue mode
+    vote_packet_priority_queue: Option<MinMaxHeap<PrioritizedPacket>>,
+    vote_batch_limit: usize,
+    /// Estimated memory of the buffered packets, see `ImmutableDeserializedPacket::buffered_size()`
+    buffered_bytes: usize,
+    /// Limit on `buffered_bytes`, only present if the buffer is capped by size
+    byte_limit: Option<usize>,
+    byte_evicted_packets_count: usize,
+    priority_aging_config: PriorityAgingConfig,
+    last_aging: Option<Instant>,
+    dedup_stats: DedupStats,
+    weighting_policy: P,
+    priority_mode: PriorityMode,
+    packet_filter: Option<Arc<dyn PacketFilter>>,
+    filtered_packets_count: usize,
+    drop_notifier: Option<Arc<dyn PacketDropNotifier>>,
+    queue_latency_stats: QueueLatencyStats,
+    /// Sequence and message hash of the latest packet buffered for each fee payer and stream,
+    /// only present if sequence hints are honored
+    sequenced_packets: Option<HashMap<(Pubkey, u64), (u64, Hash)>>,
+    superseded_packets_count: usize,
+    /// Message hash of the packet last pushed with `push_with_replacement()` for each key
+    replaceable_packets: HashMap<ReplacementKey, Hash>,
+    replaced_packets_count: usize,
+    pop_policy: PopPolicy,
+    /// Nonce account of every buffered durable-nonce packet, only present if nonce checks are
+    /// enabled. May also hold packets dropped by `retain()` until the next nonce check.
+    nonce_packets: Option<HashMap<Hash, Pubkey>>,
+    /// Only present if the packets inserted per source are rate limited
+    source_rate_limiter: Option<SourceRateLimiter>,
+    rate_limited_packets_count: usize,
+    /// Limit on the buffered packets sharing a fee payer and writable accounts
+    max_packets_per_lock_set: Option<usize>,
+    /// Message hashes of the packets buffered for each fee payer and writable accounts, only
+    /// present if limited. May also hold packets no longer buffered.
+    lock_set_packets: Option<HashMap<(Pubkey, Hash), Vec<Hash>>>,
+    coalesced_packets_count: usize,
+    /// Limit on the accounts a buffered packet may lock, only present if checked on arrival
+    transaction_account_lock_limit: Option<usize>,
+    too_many_account_locks_packets_count: usize,
+    #[cfg(feature = "fault-injection")]
+    fault_injector: Option<PacketFaultInjector>,
+}
+
+impl UnprocessedPacketBatches {
+    pub fn from_iter<I: IntoIterator<Item = DeserializedPacket>>(iter: I, capacity: usize) -> Self {
+        let mut unprocessed_packet_batches = Self::with_capacity(capacity);
+        for deserialized_packet in iter.into_iter() {
+            unprocessed_packet_batches.push(deserialized_packet);
+        }
+
+        unprocessed_packet_batches
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_policy(capacity, StakeWeightedPolicy)
+    }
+
+    /// Creates a buffer in dual-queue mode, where simple-vote packets are held in their own
+    /// queue of `vote_capacity` and all other packets in a queue of `capacity`
+    pub fn with_vote_capacity(capacity: usize, vote_capacity: usize) -> Self {
+        Self::with_vote_capacity_and_policy(capacity, vote_capacity, StakeWeightedPolicy)
+    }
+}
+
+impl<P: PacketWeightingPolicy> UnprocessedPacketBatches<P> {
+    pub fn with_capacity_and_policy(capacity: usize, weighting_policy: P) -> Self {
+        UnprocessedPacketBatches {
+            packet_priority_queue: MinMaxHeap::with_capacity(capacity),
+            message_hash_to_transaction: HashMap::with_capacity(capacity),
+            batch_limit: capacity,
+            vote_packet_priority_queue: None,
+            vote_batch_limit: 0,
+            buffered_bytes: 0,
+            byte_limit: None,
+            byte_evicted_packets_count: 0,
+            priority_aging_config: PriorityAgingConfig::default(),
+            last_aging: None,
+            dedup_stats: DedupStats::default(),
+            weighting_policy,
+            priority_mode: PriorityMode::default(),
+            packet_filter: None,
+            filtered_packets_count: 0,
+            drop_notifier: None,
+            queue_latency_stats: QueueLatencyStats::default(),
+            sequenced_packets: None,
+            superseded_packets_count: 0,
+            replaceable_packets: HashMap::default(),
+            replaced_packets_count: 0,
+            pop_policy: PopPolicy::default(),
+            nonce_packets: None,
+            source_rate_limiter: None,
+            rate_limited_packets_count: 0,
+            max_packets_per_lock_set: None,
+            lock_set_packets: None,
+            coalesced_packets_count: 0,
+            transaction_account_lock_limit: None,
+            too_many_account_locks_packets_count: 0,
+            #[cfg(feature = "fault-injection")]
+            fault_injector: None,
+        }
+    }
+
+    /// Like `with_vote_capacity()`, ordering packets of equal priority by `weighting_policy`
+    pub fn with_vote_capacity_and_policy(
+        capacity: usize,
+        vote_capacity: usize,
+        weighting_policy: P,
+    ) -> Self {
+        let mut unprocessed_packet_batches =
+            Self::with_capacity_and_policy(capacity, weighting_policy);
+        unprocessed_packet_batches
+            .message_hash_to_transaction
+            .reserve(vote_capacity);
+        unprocessed_packet_batches.vote_packet_priority_queue =
+            Some(MinMaxHeap::with_capacity(vote_capacity));
+        unprocessed_packet_batches.vote_batch_limit = vote_capacity;
+        unprocessed_packet_batches
+    }
+
+    pub fn dedup_stats(&self) -> &DedupStats {
+        &self.dedup_stats
+    }
+
+    /// Returns the dedup stats gathered since the last call, resetting them
+    pub fn take_dedup_stats(&mut self) -> DedupStats {
+        std::mem::take(&mut self.dedup_stats)
+    }
+
+    pub fn is_dual_queue(&self) -> bool {
+        self.vote_packet_priority_queue.is_some()
+    }
+
+    /// Derives the priority of packets pushed into the buffer according to `priority_mode`
+    /// rather than from their compute unit price. Must be chosen before any packet is pushed.
+    pub fn with_priority_mode(mut self, priority_mode: PriorityMode) -> Self {
+        assert!(self.is_empty());
+        self.priority_mode = priority_mode;
+        self
+    }
+
+    pub fn priority_mode(&self) -> PriorityMode {
+        self.priority_mode
+    }
+
+    /// Parses the sequence hint of every packet pushed into the buffer, keeping only the
+    /// highest sequence buffered per fee payer and stream. A packet with a higher sequence
+    /// replaces the buffered one, which takes time linear in the size of its queue. Must be
+    /// enabled before any packet is pushed.
+    pub fn with_sequence_hints(mut self) -> Self {
+        assert!(self.is_empty());
+        self.sequenced_packets = Some(HashMap::default());
+        self
+    }
+
+    /// Detects the durable-nonce transactions among the packets pushed into the buffer, so
+    /// `retain_valid_nonces()` can drop the ones whose nonce has advanced. Must be enabled
+    /// before any packet is pushed.
+    pub fn with_nonce_check(mut self) -> Self {
+        assert!(self.is_empty());
+        self.nonce_packets = Some(HashMap::default());
+        self
+    }
+
+    /// Caps the estimated memory of the buffered packets at `byte_limit` on top of the packet
+    /// count capacity, evicting the lowest priority packets across all queues once a push
+    /// exceeds it. Must be set before any packet is pushed.
+    pub fn with_byte_capacity(mut self, byte_limit: usize) -> Self {
+        assert!(self.is_empty());
+        self.byte_limit = Some(byte_limit);
+        self
+    }
+
+    /// Rejects the packets inserted by `insert_batch()` from a source address that already
+    /// had `packets_per_second` packets inserted over the last second. Must be set before any
+    /// packet is pushed.
+    pub fn with_source_rate_limit(mut self, packets_per_second: u64) -> Self {
+        assert!(self.is_empty());
+        self.source_rate_limiter = Some(SourceRateLimiter::new(packets_per_second));
+        self
+    }
+
+    /// Buffers at most `max_packets` of the packets sharing a fee payer and writable static
+    /// accounts, keeping the ones of highest priority. Such packets execute one after another,
+    /// so a wallet spraying near-identical transactions can't take more than its share of the
+    /// buffer. Evicting a buffered packet takes time linear in the size of its queue. Simple
+    /// votes are exempt. Must be set before any packet is pushed.
+    pub fn with_max_packets_per_lock_set(mut self, max_packets: usize) -> Self {
+        assert!(self.is_empty());
+        assert!(max_packets > 0);
+        self.max_packets_per_lock_set = Some(max_packets);
+        self.lock_set_packets = Some(HashMap::default());
+        self
+    }
+
+    /// Rejects the packets pushed from now on that would lock more than
+    /// `transaction_account_lock_limit` accounts, counting the ones loaded through address table
+    /// lookups, instead of buffering them until they fail to lock. Packets already buffered are
+    /// kept. None admits packets however many accounts they lock.
+    pub fn set_transaction_account_lock_limit(
+        &mut self,
+        transaction_account_lock_limit: Option<usize>,
+    ) {
+        self.transaction_account_lock_limit = transaction_account_lock_limit;
+    }
+
+    /// Injects the faults of `config` into the packets pushed into and popped from the buffer.
+    /// Must be set before any packet is pushed.
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_injection(mut self, config: PacketFaultInjectionConfig) -> Self {
+        assert!(self.is_empty());
+        self.fault_injector = Some(PacketFaultInjector::new(config));
+        self
+    }
+
+    #[cfg(feature = "fault-injection")]
+    pub fn fault_injection_stats_mut(&mut self) -> Option<&mut PacketFaultInjectionStats> {
+        self.fault_injector
+            .as_mut()
+            .map(PacketFaultInjector::stats_mut)
+    }
+
+    #[cfg(feature = "fault-injection")]
+    fn inject_drop(&mut self) -> bool {
+        self.fault_injector
+            .as_mut()
+            .map_or(false, PacketFaultInjector::inject_drop)
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    fn inject_drop(&mut self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "fault-injection")]
+    fn injected_queue_limit(&self, queue_limit: usize) -> usize {
+        self.fault_injector
+            .as_ref()
+            .map_or(queue_limit, |fault_injector| {
+                fault_injector.queue_limit(queue_limit)
+            })
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    fn injected_queue_limit(&self, queue_limit: usize) -> usize {
+        queue_limit
+    }
+
+    #[cfg(feature = "fault-injection")]
+    fn inject_pop_faults(&mut self, deserialized_packet: &DeserializedPacket) {
+        if let Some(fault_injector) = &mut self.fault_injector {
+            fault_injector.inject_pop_faults(deserialized_packet);
+        }
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    fn inject_pop_faults(&mut self, _deserialized_packet: &DeserializedPacket) {}
+
+    #[cfg(feature = "fault-injection")]
+    fn take_redeliveries(&mut self) -> Vec<DeserializedPacket> {
+        self.fault_injector
+            .as_mut()
+            .map(PacketFaultInjector::take_redeliveries)
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    fn take_redeliveries(&mut self) -> Vec<DeserializedPacket> {
+        Vec::new()
+    }
+
+    /// Returns the number of packets rejected for locking too many accounts since the last
+    /// call, resetting it
+    pub fn take_too_many_account_locks_packets_count(&mut self) -> usize {
+        std::mem::take(&mut self.too_many_account_locks_packets_count)
+    }
+
+    /// Returns the number of packets dropped or evicted because more packets sharing their fee
+    /// payer and writable accounts were buffered at a higher priority since the last call,
+    /// resetting it
+    pub fn take_coalesced_packets_count(&mut self) -> usize {
+        std::mem::take(&mut self.coalesced_packets_count)
+    }
+
+    /// Returns the number of packets rejected by the source rate limit since the last call,
+    /// resetting it
+    pub fn take_rate_limited_packets_count(&mut self) -> usize {
+        std::mem::take(&mut self.rate_limited_packets_count)
+    }
+
+    pub fn byte_capacity(&self) -> Option<usize> {
+        self.byte_limit
+    }
+
+    /// Estimated memory held by the buffered packets
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Returns the number of packets evicted to keep the buffer within its byte capacity
+    /// since the last call, resetting it
+    pub fn take_byte_evicted_packets_count(&mut self) -> usize {
+        std::mem::take(&mut self.byte_evicted_packets_count)
+    }
+
+    /// Returns the number of packets dropped or replaced because a packet with a higher
+    /// sequence hint was pushed since the last call, resetting it
+    pub fn take_superseded_packets_count(&mut self) -> usize {
+        std::mem::take(&mut self.superseded_packets_count)
+    }
+
+    /// Returns the number of packets replaced by a packet offering a higher compute unit price
+    /// since the last call, resetting it
+    pub fn take_replaced_packets_count(&mut self) -> usize {
+        std::mem::take(&mut self.replaced_packets_count)
+    }
+
+    /// Screens every packet pushed from now on with `packet_filter`, dropping the ones it
+    /// rejects
+    pub fn set_packet_filter(&mut self, packet_filter: Option<Arc<dyn PacketFilter>>) {
+        self.packet_filter = packet_filter;
+    }
+
+    /// Notifies the senders of packets evicted from now on with `drop_notifier`
+    pub fn set_drop_notifier(&mut self, drop_notifier: Option<Arc<dyn PacketDropNotifier>>) {
+        self.drop_notifier = drop_notifier;
+    }
+
+    pub fn has_drop_notifier(&self) -> bool {
+        self.drop_notifier.is_some()
+    }
+
+    /// Notifies the sender of `packet`, if a drop notifier is set, that it was dropped for
+    /// `reason`
+    pub fn notify_dropped(
+        &self,
+        packet: &ImmutableDeserializedPacket,
+        reason: TransactionDropReason,
+    ) {
+        if let Some(drop_notifier) = &self.drop_notifier {
+            drop_notifier.notify_dropped(packet, reason);
+        }
+    }
+
+    /// Returns the number of packets rejected by the packet filter since the last call,
+    /// resetting it
+    pub fn take_filtered_packets_count(&mut self) -> usize {
+        std::mem::take(&mut self.filtered_packets_count)
+    }
+
+    pub fn queue_latency_stats(&self) -> &QueueLatencyStats {
+        &self.queue_latency_stats
+    }
+
+    pub fn report_queue_latency_stats(&mut self, id: u32, report_interval_ms: u64) {
+        self.queue_latency_stats.report(id, report_interval_ms);
+    }
+
+    pub fn set_pop_policy(&mut self, pop_policy: PopPolicy) {
+        self.pop_policy = pop_policy;
+    }
+
+    pub fn pop_policy(&self) -> PopPolicy {
+        self.pop_policy
+    }
+
+    pub fn set_priority_aging_config(&mut self, priority_aging_config: PriorityAgingConfig) {
+        self.priority_aging_config = priority_aging_config;
+    }
+
+    pub fn priority_aging_config(&self) -> &PriorityAgingConfig {
+        &self.priority_aging_config
+    }
+
+    /// Recomputes the effective priority of every buffered packet according to the
+    /// configured aging curve. This rebuilds the priority queues, so it runs at most once
+    /// per aging interval. Returns true if the priorities were updated.
+    pub fn age_priorities(&mut self) -> bool {
+        if !self.priority_aging_config.is_enabled() {
+            return false;
+        }
+        let now = Instant::now();
+        if let Some(last_aging) = self.last_aging {
+            if now.saturating_duration_since(last_aging) < self.priority_aging_config.interval {
+                return false;
+            }
+        }
+        self.last_aging = Some(now);
+
+        let priority_aging_config = &self.priority_aging_config;
+        let age_queue = |packet_priority_queue: &mut MinMaxHeap<PrioritizedPacket>| {
+            *packet_priority_queue = packet_priority_queue
+                .drain()
+                .map(|mut prioritized_packet| {
+                    let boost = priority_aging_config
+                        .boost(now.saturating_duration_since(prioritized_packet.buffered_at));
+                    prioritized_packet.effective_priority =
+                        prioritized_packet.priority().saturating_add(boost);
+                    prioritized_packet
+                })
+                .collect();
+        };
+        age_queue(&mut self.packet_priority_queue);
+        if let Some(vote_packet_priority_queue) = &mut self.vote_packet_priority_queue {
+            age_queue(vote_packet_priority_queue);
+        }
+        true
+    }
+
+    /// Recomputes the priority of every buffered packet from its compute budget instructions
+    /// under `compute_budget`, so that a change of the compute budget or fee configuration
+    /// applies to the packets already buffered rather than only to new arrivals. Packets keep
+    /// their aging boost. Packets that no longer prioritize are dropped; returns their number.
+    pub fn recompute_priorities(&mut self, compute_budget: &ComputeBudget) -> usize {
+        let prioritized_packets: Vec<_> = self.packet_priority_queue.drain().collect();
+        let (packet_priority_queue, mut num_dropped_packets) =
+            self.reprioritize(prioritized_packets, compute_budget);
+        self.packet_priority_queue = packet_priority_queue;
+
+        if let Some(vote_packet_priority_queue) = &mut self.vote_packet_priority_queue {
+            let prioritized_packets: Vec<_> = vote_packet_priority_queue.drain().collect();
+            let (vote_packet_priority_queue, num_dropped_vote_packets) =
+                self.reprioritize(prioritized_packets, compute_budget);
+            self.vote_packet_priority_queue = Some(vote_packet_priority_queue);
+            num_dropped_packets += num_dropped_vote_packets;
+        }
+        num_dropped_packets
+    }
+
+    // Returns the queue of `prioritized_packets` reprioritized under `compute_budget`, and
+    // the number of packets dropped because they no longer prioritize
+    fn reprioritize(
+        &mut self,
+        prioritized_packets: Vec<PrioritizedPacket>,
+        compute_budget: &ComputeBudget,
+    ) -> (MinMaxHeap<PrioritizedPacket>, usize) {
+        let mut packet_priority_queue = MinMaxHeap::with_capacity(prioritized_packets.len());
+        let mut num_dropped_packets = 0;
+        for prioritized_packet in prioritized_packets {
+            let message_hash = *prioritized_packet.message_hash();
+            let immutable_section = match prioritized_packet.reprioritized(compute_budget) {
+                Some(immutable_section) => Arc::new(immutable_section),
+                None => {
+                    self.untrack(&message_hash);
+                    num_dropped_packets += 1;
+                    continue;
+                }
+            };
+            let boost = prioritized_packet
+                .effective_priority
+                .saturating_sub(prioritized_packet.priority);
+            let mut reprioritized_packet = self.prioritize(&immutable_section);
+            reprioritized_packet.buffered_at = prioritized_packet.buffered_at;
+            reprioritized_packet.effective_priority =
+                reprioritized_packet.priority.saturating_add(boost);
+            if let Some(deserialized_packet) =
+                self.message_hash_to_transaction.get_mut(&message_hash)
+            {
+                deserialized_packet.immutable_section = immutable_section;
+            }
+            packet_priority_queue.push(reprioritized_packet);
+        }
+        (packet_priority_queue, num_dropped_packets)
+    }
+
+    pub fn clear(&mut self) {
+        self.packet_priority_queue.clear();
+        if let Some(vote_packet_priority_queue) = &mut self.vote_packet_priority_queue {
+            vote_packet_priority_queue.clear();
+        }
+        self.message_hash_to_transaction.clear();
+        self.buffered_bytes = 0;
+        if let Some(sequenced_packets) = &mut self.sequenced_packets {
+            sequenced_packets.clear();
+        }
+        self.replaceable_packets.clear();
+        if let Some(nonce_packets) = &mut self.nonce_packets {
+            nonce_packets.clear();
+        }
+        if let Some(lock_set_packets) = &mut self.lock_set_packets {
+            lock_set_packets.clear();
+        }
+    }
+
+    /// Writes the buffered packets, and whether they were forwarded, to `path` so that they can
+    /// be buffered again after a restart. Returns the number of packets written.
+    pub fn save_to_file(&self, path: &Path) -> io::Result<usize> {
+        let saved_packets: Vec<_> = self
+            .message_hash_to_transaction
+            .values()
+            .map(SavedPacket::from)
+            .collect();
+        // Write to a temporary file first, so that a partially written file is never restored
+        let tmp_path = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        bincode::serialize_into(&mut writer, &saved_packets)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        writer.flush()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(saved_packets.len())
+    }
+
+    /// Buffers the packets written to `path` by `save_to_file()`, then removes the file.
+    /// Packets that no longer deserialize are skipped. Returns the number of packets buffered.
+    pub fn restore_from_file(&mut self, path: &Path) -> io::Result<usize> {
+        let saved_packets: Vec<SavedPacket> =
+            bincode::deserialize_from(BufReader::new(File::open(path)?))
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::remove_file(path)?;
+        let mut restored_packets_count = 0;
+        for saved_packet in saved_packets {
+            let forwarded = saved_packet.forwarded;
+            let deserialized_packet = match saved_packet
+                .into_packet()
+                .and_then(|packet| DeserializedPacket::new(packet).ok())
+            {
+                Some(deserialized_packet) => deserialized_packet,
+                None => continue,
+            };
+            let message_hash = *deserialized_packet.immutable_section().message_hash();
+            self.push(deserialized_packet);
+            if let Some(deserialized_packet) =
+                self.message_hash_to_transaction.get_mut(&message_hash)
+            {
+                deserialized_packet.forwarded = forwarded;
+                restored_packets_count += 1;
+            }
+        }
+        Ok(restored_packets_count)
+    }
+
+    /// Insert new `deserialized_packet_batch` into inner `MinMaxHeap<DeserializedPacket>`,
+    /// weighted first by the tx priority, then the weight assigned by the weighting policy.
+    /// If buffer is at the max limit, the lowest weighted packet is dropped. Packets over the
+    /// source rate limit, if set, are rejected and not counted as dropped. The senders of
+    /// dropped packets are notified if a drop notifier is set.
+    ///
+    /// Returns tuple of number of packets dropped
+    pub fn insert_batch(
+        &mut self,
+        deserialized_packets: impl Iterator<Item = DeserializedPacket>,
+    ) -> usize {
+        let mut num_dropped_packets = 0;
+        let now = Instant::now();
+        let redeliveries = self.take_redeliveries();
+        for deserialized_packet in redeliveries.into_iter().chain(deserialized_packets) {
+            if let Some(source_rate_limiter) = &mut self.source_rate_limiter {
+                let source = deserialized_packet
+                    .immutable_section()
+                    .original_packet()
+                    .meta
+                    .addr;
+                if !source_rate_limiter.allow(source, now) {
+                    self.rate_limited_packets_count =
+                        self.rate_limited_packets_count.saturating_add(1);
+                    continue;
+                }
+            }
+            if let Some(dropped_packet) = self.push(deserialized_packet) {
+                self.notify_dropped(
+                    dropped_packet.immutable_section(),
+                    TransactionDropReason::Evicted,
+                );
+                num_dropped_packets += 1;
+            }
+        }
+        num_dropped_packets
+    }
+
+    pub fn push(&mut self, deserialized_packet: DeserializedPacket) -> Option<DeserializedPacket> {
+        if self.inject_drop() {
+            return Some(deserialized_packet);
+        }
+        match self.check_admission(deserialized_packet.immutable_section(), None) {
+            Some(admission) => self.admit(deserialized_packet, admission),
+            None => None,
+        }
+    }
+
+    /// Runs the admission checks of `push()` without changing the buffered packets, treating
+    /// the packet with `excluded_hash`, if any, as already removed. Returns None, after
+    /// counting the rejection, if `immutable_section` must be dropped, and otherwise the
+    /// buffered packets it displaces.
+    fn check_admission(
+        &mut self,
+        immutable_section: &ImmutableDeserializedPacket,
+        excluded_hash: Option<&Hash>,
+    ) -> Option<Admission> {
+        if let Some(packet_filter) = &self.packet_filter {
+            if !packet_filter.allow(immutable_section) {
+                self.filtered_packets_count = self.filtered_packets_count.saturating_add(1);
+                return None;
+            }
+        }
+        if let Some(transaction_account_lock_limit) = self.transaction_account_lock_limit {
+            if immutable_section.packet_view().num_account_keys() > transaction_account_lock_limit {
+                self.too_many_account_locks_packets_count =
+                    self.too_many_account_locks_packets_count.saturating_add(1);
+                return None;
+            }
+        }
+        if self
+            .message_hash_to_transaction
+            .contains_key(immutable_section.message_hash())
+        {
+            self.dedup_stats
+                .record(immutable_section.original_packet().meta.addr);
+            return None;
+        }
+        let superseded_hash = match self.check_sequenced(immutable_section, excluded_hash) {
+            Some(superseded_hash) => superseded_hash,
+            None => {
+                self.superseded_packets_count = self.superseded_packets_count.saturating_add(1);
+                return None;
+            }
+        };
+        let displaced_hashes = [excluded_hash, superseded_hash.as_ref()];
+        let coalesced_hash = match self.check_lock_set(immutable_section, &displaced_hashes) {
+            Some(coalesced_hash) => coalesced_hash,
+            None => {
+                self.coalesced_packets_count = self.coalesced_packets_count.saturating_add(1);
+                return None;
+            }
+        };
+        Some(Admission {
+            superseded_hash,
+            coalesced_hash,
+        })
+    }
+
+    /// Buffers `deserialized_packet` once it passed `check_admission()`, removing the packets
+    /// it displaces first
+    fn admit(
+        &mut self,
+        deserialized_packet: DeserializedPacket,
+        admission: Admission,
+    ) -> Option<DeserializedPacket> {
+        if let Some(superseded_hash) = admission.superseded_hash {
+            self.remove_buffered(&superseded_hash);
+            self.superseded_packets_count = self.superseded_packets_count.saturating_add(1);
+        }
+        if let Some(coalesced_hash) = admission.coalesced_hash {
+            self.remove_buffered(&coalesced_hash);
+            self.coalesced_packets_count = self.coalesced_packets_count.saturating_add(1);
+        }
+        self.record_sequenced(deserialized_packet.immutable_section());
+        self.record_lock_set(deserialized_packet.immutable_section());
+
+        let is_vote_lane = self.is_vote_lane(deserialized_packet.immutable_section());
+        let (queue_len, queue_limit) = if is_vote_lane {
+            (self.votes_len(), self.vote_batch_limit)
+        } else {
+            (self.packet_priority_queue.len(), self.batch_limit)
+        };
+        let dropped_packet = if queue_len >= self.injected_queue_limit(queue_limit) {
+            // Optimized to not allocate by calling `MinMaxHeap::push_pop_min()`
+            Some(self.push_pop_min(deserialized_packet))
+        } else {
+            self.push_internal(deserialized_packet);
+            None
+        };
+        self.evict_over_byte_limit(dropped_packet)
+    }
+
+    /// Evicts the lowest priority packets across all queues until the buffer is back within
+    /// its byte capacity. Returns `dropped_packet` if any, and otherwise the first packet
+    /// evicted; further evicted packets are dropped, and only show in the byte eviction count
+    /// and to the drop notifier.
+    fn evict_over_byte_limit(
+        &mut self,
+        mut dropped_packet: Option<DeserializedPacket>,
+    ) -> Option<DeserializedPacket> {
+        let byte_limit = match self.byte_limit {
+            Some(byte_limit) => byte_limit,
+            None => return dropped_packet,
+        };
+        while self.buffered_bytes > byte_limit {
+            let evicted_packet = match self.pop_min_prioritized() {
+                Some(prioritized_packet) => {
+                    self.queue_latency_stats.record_evicted(&prioritized_packet);
+                    self.untrack(prioritized_packet.message_hash()).unwrap()
+                }
+                None => break,
+            };
+            self.byte_evicted_packets_count = self.byte_evicted_packets_count.saturating_add(1);
+            if dropped_packet.is_some() {
+                self.notify_dropped(
+                    evicted_packet.immutable_section(),
+                    TransactionDropReason::Evicted,
+                );
+            } else {
+                dropped_packet = Some(evicted_packet);
+            }
+        }
+        dropped_packet
+    }
+
+    /// Like `push()`, except that `deserialized_packet` replaces the packet previously pushed
+    /// with the same `ReplacementKey` if it offers a higher compute unit price, and is dropped
+    /// otherwise. Only packets pushed through this method can be replaced. Replacing a packet
+    /// takes time linear in the size of its queue.
+    ///
+    /// Returns the packet dropped, which is either the replaced packet, `deserialized_packet`
+    /// itself, or the lowest priority packet evicted to make room for it
+    pub fn push_with_replacement(
+        &mut self,
+        deserialized_packet: DeserializedPacket,
+    ) -> Option<DeserializedPacket> {
+        let immutable_section = deserialized_packet.immutable_section().clone();
+        let key = match immutable_section.transaction() {
+            Some(transaction) => ReplacementKey::new(transaction.get_message()),
+            None => return Some(deserialized_packet),
+        };
+        let message_hash = *immutable_section.message_hash();
+        let replaced_hash = match self
+            .replaceable_packets
+            .get(&key)
+            .and_then(|message_hash| self.message_hash_to_transaction.get(message_hash))
+        {
+            Some(buffered_packet)
+                if buffered_packet.immutable_section().message_hash() != &message_hash =>
+            {
+                if buffered_packet.immutable_section().compute_unit_price()
+                    >= immutable_section.compute_unit_price()
+                {
+                    return Some(deserialized_packet);
+                }
+                Some(*buffered_packet.immutable_section().message_hash())
+            }
+            _ => None,
+        };
+        if self.inject_drop() {
+            return Some(deserialized_packet);
+        }
+        // The replaced packet keeps its place unless the replacement is admitted
+        let admission = self.check_admission(&immutable_section, replaced_hash.as_ref())?;
+        let replaced_packet =
+            replaced_hash.and_then(|message_hash| self.remove_buffered(&message_hash));
+
+        let dropped_packet = self.admit(deserialized_packet, admission);
+        if !self.message_hash_to_transaction.contains_key(&message_hash) {
+            // Evicted for capacity right away, so both packets are dropped
+            return match (replaced_packet, dropped_packet) {
+                (Some(replaced_packet), Some(dropped_packet)) => {
+                    self.notify_dropped(
+                        dropped_packet.immutable_section(),
+                        TransactionDropReason::Evicted,
+                    );
+                    Some(replaced_packet)
+                }
+                (replaced_packet, dropped_packet) => replaced_packet.or(dropped_packet),
+            };
+        }
+        self.replaceable_packets.insert(key, message_hash);
+        self.prune_replaceable_packets();
+        if replaced_packet.is_some() {
+            self.replaced_packets_count = self.replaced_packets_count.saturating_add(1);
+        }
+        replaced_packet.or(dropped_packet)
+    }
+
+    /// Checks `immutable_section` against the latest buffered packet of its fee payer and
+    /// stream, other than the one with `excluded_hash`. Returns None if that packet has the
+    /// same or a higher sequence, in which case `immutable_section` must be dropped, and
+    /// otherwise the packet it supersedes, if any.
+    fn check_sequenced(
+        &self,
+        immutable_section: &ImmutableDeserializedPacket,
+        excluded_hash: Option<&Hash>,
+    ) -> Option<Option<Hash>> {
+        let (sequenced_packets, sequence_hint) =
+            match (&self.sequenced_packets, immutable_section.sequence_hint()) {
+                (Some(sequenced_packets), Some(sequence_hint)) => {
+                    (sequenced_packets, sequence_hint)
+                }
+                _ => return Some(None),
+            };
+        let key = (*immutable_section.fee_payer(), sequence_hint.stream);
+        match sequenced_packets.get(&key) {
+            Some((sequence, message_hash))
+                if Some(message_hash) != excluded_hash
+                    && self.message_hash_to_transaction.contains_key(message_hash) =>
+            {
+                (*sequence < sequence_hint.sequence).then(|| Some(*message_hash))
+            }
+            _ => Some(None),
+        }
+    }
+
+    /// Records `immutable_section` as the latest packet of its fee payer and stream
+    fn record_sequenced(&mut self, immutable_section: &ImmutableDeserializedPacket) {
+        if let (Some(sequenced_packets), Some(sequence_hint)) = (
+            &mut self.sequenced_packets,
+            immutable_section.sequence_hint(),
+        ) {
+            sequenced_packets.insert(
+                (*immutable_section.fee_payer(), sequence_hint.stream),
+                (sequence_hint.sequence, *immutable_section.message_hash()),
+            );
+        }
+    }
+
+    /// Checks `immutable_section` against the packets buffered for its fee payer and writable
+    /// accounts, other than `displaced_hashes`. Returns None if they are at the limit and all
+    /// have the same or a higher priority, in which case `immutable_section` must be dropped,
+    /// and otherwise the lowest priority one it evicts, if they are at the limit.
+    fn check_lock_set(
+        &self,
+        immutable_section: &ImmutableDeserializedPacket,
+        displaced_hashes: &[Option<&Hash>],
+    ) -> Option<Option<Hash>> {
+        let (max_packets, lock_set_packets) =
+            match (self.max_packets_per_lock_set, &self.lock_set_packets) {
+                (Some(max_packets), Some(lock_set_packets)) => (max_packets, lock_set_packets),
+                _ => return Some(None),
+            };
+        if immutable_section.is_simple_vote() {
+            return Some(None);
+        }
+        let message_hashes = match lock_set_packets.get(&lock_set_key(immutable_section)) {
+            Some(message_hashes) => message_hashes,
+            None => return Some(None),
+        };
+        let buffered_packets: Vec<_> = message_hashes
+            .iter()
+            .filter(|message_hash| !displaced_hashes.contains(&Some(*message_hash)))
+            .filter_map(|message_hash| self.message_hash_to_transaction.get(message_hash))
+            .collect();
+        if buffered_packets.len() < max_packets {
+            return Some(None);
+        }
+
+        let priority_mode = self.priority_mode;
+        let lowest_packet = buffered_packets
+            .into_iter()
+            .min_by_key(|buffered_packet| {
+                buffered_packet
+                    .immutable_section()
+                    .priority_for(priority_mode)
+            })
+            .expect("max_packets is positive");
+        let lowest_section = lowest_packet.immutable_section();
+        (lowest_section.priority_for(priority_mode) < immutable_section.priority_for(priority_mode))
+            .then(|| Some(*lowest_section.message_hash()))
+    }
+
+    /// Records `immutable_section` among the packets buffered for its fee payer and writable
+    /// accounts
+    fn record_lock_set(&mut self, immutable_section: &ImmutableDeserializedPacket) {
+        let lock_set_packets = match (self.max_packets_per_lock_set, &mut self.lock_set_packets) {
+            (Some(_), Some(lock_set_packets)) => lock_set_packets,
+            _ => return,
+        };
+        if immutable_section.is_simple_vote() {
+            return;
+        }
+        let message_hash_to_transaction = &self.message_hash_to_transaction;
+        let message_hashes = lock_set_packets
+            .entry(lock_set_key(immutable_section))
+            .or_default();
+        message_hashes
+            .retain(|message_hash| message_hash_to_transaction.contains_key(message_hash));
+        message_hashes.push(*immutable_section.message_hash());
+    }
+
+    /// Removes a packet from the buffer, rebuilding its priority queue
+    fn remove_buffered(&mut self, message_hash: &Hash) -> Option<DeserializedPacket> {
+        let deserialized_packet = self.untrack(message_hash)?;
+        let is_vote_lane = self.is_vote_lane(deserialized_packet.immutable_section());
+        let packet_priority_queue = self.priority_queue_for_mut(is_vote_lane);
+        *packet_priority_queue = packet_priority_queue
+            .drain()
+            .filter(|prioritized_packet| prioritized_packet.message_hash() != message_hash)
+            .collect();
+        Some(deserialized_packet)
+    }
+
+    /// Drops the sequence records of packets that are no longer buffered once they outnumber
+    /// the buffered packets
+    fn prune_sequenced_packets(&mut self) {
+        let message_hash_to_transaction = &self.message_hash_to_transaction;
+        if let Some(sequenced_packets) = &mut self.sequenced_packets {
+            if sequenced_packets.len() > message_hash_to_transaction.len().saturating_mul(2) {
+                sequenced_packets.retain(|_, (_, message_hash)| {
+                    message_hash_to_transaction.contains_key(message_hash)
+                });
+            }
+        }
+    }
+
+    /// Drops the lock sets without buffered packets once they outnumber the buffered packets
+    fn prune_lock_set_packets(&mut self) {
+        let message_hash_to_transaction = &self.message_hash_to_transaction;
+        if let Some(lock_set_packets) = &mut self.lock_set_packets {
+            if lock_set_packets.len() > message_hash_to_transaction.len().saturating_mul(2) {
+                lock_set_packets.retain(|_, message_hashes| {
+                    message_hashes.retain(|message_hash| {
+                        message_hash_to_transaction.contains_key(message_hash)
+                    });
+                    !message_hashes.is_empty()
+                });
+            }
+        }
+    }
+
+    /// Drops the replacement keys of packets that are no longer buffered once they outnumber
+    /// the buffered packets
+    fn prune_replaceable_packets(&mut self) {
+        let message_hash_to_transaction = &self.message_hash_to_transaction;
+        if self.replaceable_packets.len() > message_hash_to_transaction.len().saturating_mul(2) {
+            self.replaceable_packets
+                .retain(|_, message_hash| message_hash_to_transaction.contains_key(message_hash));
+        }
+    }
+
+    /// Drops the buffered durable-nonce packets that can no longer execute, as their nonce
+    /// account in `bank` has advanced past the nonce they were signed with and their blockhash
+    /// isn't recent either. Loads each nonce account once. Does nothing unless nonce checks
+    /// are enabled. Returns the number of packets dropped.
+    pub fn retain_valid_nonces(&mut self, bank: &Bank) -> usize {
+        let nonce_packets = match self.nonce_packets.take() {
+            Some(nonce_packets) => nonce_packets,
+            None => return 0,
+        };
+        let mut nonce_accounts = HashMap::new();
+        let mut valid_nonce_packets = HashMap::with_capacity(nonce_packets.len());
+        let mut stale_message_hashes = HashSet::new();
+        for (message_hash, nonce_pubkey) in nonce_packets {
+            let recent_blockhash = match self.message_hash_to_transaction.get(&message_hash) {
+                Some(deserialized_packet) => {
+                    *deserialized_packet.immutable_section().recent_blockhash()
+                }
+                None => continue,
+            };
+            let nonce_account = nonce_accounts.entry(nonce_pubkey).or_insert_with(|| {
+                bank.get_account_with_fixed_root(&nonce_pubkey)
+                    .unwrap_or_default()
+            });
+            if nonce_account::verify_nonce_account(nonce_account, &recent_blockhash)
+                || bank.is_blockhash_valid(&recent_blockhash)
+            {
+                valid_nonce_packets.insert(message_hash, nonce_pubkey);
+            } else {
+                stale_message_hashes.insert(message_hash);
+            }
+        }
+        self.nonce_packets = Some(valid_nonce_packets);
+
+        if !stale_message_hashes.is_empty() {
+            self.retain(|deserialized_packet| {
+                !stale_message_hashes
+                    .contains(deserialized_packet.immutable_section().message_hash())
+            });
+        }
+        stale_message_hashes.len()
+    }
+
+    pub fn iter(&mut self) -> impl Iterator<Item = &DeserializedPacket> {
+        self.message_hash_to_transaction.values()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut DeserializedPacket> {
+        self.message_hash_to_transaction.iter_mut().map(|(_k, v)| v)
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut DeserializedPacket) -> bool,
+    {
+        Self::retain_queue(
+            &mut self.packet_priority_queue,
+            &mut self.message_hash_to_transaction,
+            &mut self.buffered_bytes,
+            &mut f,
+        );
+        if let Some(vote_packet_priority_queue) = &mut self.vote_packet_priority_queue {
+            Self::retain_queue(
+                vote_packet_priority_queue,
+                &mut self.message_hash_to_transaction,
+                &mut self.buffered_bytes,
+                &mut f,
+            );
+        }
+    }
+
+    fn retain_queue<F>(
+        packet_priority_queue: &mut MinMaxHeap<PrioritizedPacket>,
+        message_hash_to_transaction: &mut HashMap<Hash, DeserializedPacket>,
+        buffered_bytes: &mut usize,
+        f: &mut F,
+    ) where
+        F: FnMut(&mut DeserializedPacket) -> bool,
+    {
+        // TODO: optimize this only when number of packets
+        // with oudated blockhash is high
+        let new_packet_priority_queue: MinMaxHeap<PrioritizedPacket> = packet_priority_queue
+            .drain()
+            .filter(|immutable_packet| {
+                match message_hash_to_transaction.entry(*immutable_packet.message_hash()) {
+                    Entry::Vacant(_vacant_entry) => {
+                        panic!(
+                            "entry {} must exist to be consistent with `packet_priority_queue`",
+                            immutable_packet.message_hash()
+                        );
+                    }
+                    Entry::Occupied(mut occupied_entry) => {
+                        let should_retain = f(occupied_entry.get_mut());
+                        if !should_retain {
+                            let (_, removed_packet) = occupied_entry.remove_entry();
+                            *buffered_bytes = buffered_bytes
+                                .saturating_sub(removed_packet.immutable_section().buffered_size());
+                        }
+                        should_retain
+                    }
+                }
+            })
+            .collect();
+        *packet_priority_queue = new_packet_priority_queue;
+    }
+
+    pub fn len(&self) -> usize {
+        self.packet_priority_queue.len() + self.votes_len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of packets held in the vote queue; always zero outside of dual-queue mode
+    pub fn votes_len(&self) -> usize {
+        self.vote_packet_priority_queue
+            .as_ref()
+            .map(MinMaxHeap::len)
+            .unwrap_or_default()
+    }
+
+    fn is_vote_lane(&self, immutable_packet: &ImmutableDeserializedPacket) -> bool {
+        self.is_dual_queue() && immutable_packet.is_simple_vote()
+    }
+
+    fn priority_queue_for_mut(&mut self, is_vote_lane: bool) -> &mut MinMaxHeap<PrioritizedPacket> {
+        match &mut self.vote_packet_priority_queue {
+            Some(vote_packet_priority_queue) if is_vote_lane => vote_packet_priority_queue,
+            _ => &mut self.packet_priority_queue,
+        }
+    }
+
+    fn prioritize(
+        &mut self,
+        immutable_section: &Arc<ImmutableDeserializedPacket>,
+    ) -> PrioritizedPacket {
+        let priority = immutable_section.priority_for(self.priority_mode);
+        let weight = self.weighting_policy.weight(immutable_section);
+        PrioritizedPacket::new(immutable_section.clone(), priority, weight)
+    }
+
+    fn push_internal(&mut self, deserialized_packet: DeserializedPacket) {
+        // Push into the priority queue
+        let is_vote_lane = self.is_vote_lane(deserialized_packet.immutable_section());
+        let prioritized_packet = self.prioritize(deserialized_packet.immutable_section());
+        self.priority_queue_for_mut(is_vote_lane)
+            .push(prioritized_packet);
+
+        self.track(deserialized_packet);
+        self.prune_sequenced_packets();
+        self.prune_lock_set_packets();
+    }
+
+    /// Keeps track of the original packet in the tracking hashmap
+    fn track(&mut self, deserialized_packet: DeserializedPacket) {
+        self.buffered_bytes = self
+            .buffered_bytes
+            .saturating_add(deserialized_packet.immutable_section().buffered_size());
+        if let Some(nonce_packets) = &mut self.nonce_packets {
+            let immutable_section = deserialized_packet.immutable_section();
+            if let Some(nonce_pubkey) = immutable_section.durable_nonce_account() {
+                nonce_packets.insert(*immutable_section.message_hash(), nonce_pubkey);
+            }
+        }
+        self.message_hash_to_transaction.insert(
+            *deserialized_packet.immutable_section().message_hash(),
+            deserialized_packet,
+        );
+    }
+
+    fn untrack(&mut self, message_hash: &Hash) -> Option<DeserializedPacket> {
+        let deserialized_packet = self.message_hash_to_transaction.remove(message_hash)?;
+        self.buffered_bytes = self
+            .buffered_bytes
+            .saturating_sub(deserialized_packet.immutable_section().buffered_size());
+        if let Some(nonce_packets) = &mut self.nonce_packets {
+            nonce_packets.remove(message_hash);
+        }
+        Some(deserialized_packet)
+    }
+
+    /// Returns the popped minimum packet from the priority queue.
+    fn push_pop_min(&mut self, deserialized_packet: DeserializedPacket) -> DeserializedPacket {
+        let is_vote_lane = self.is_vote_lane(deserialized_packet.immutable_section());
+        let prioritized_packet = self.prioritize(deserialized_packet.immutable_section());
+
+        // Push into the priority queue
+        let popped_immutable_packet = self
+            .priority_queue_for_mut(is_vote_lane)
+            .push_pop_min(prioritized_packet);
+
+        if popped_immutable_packet.message_hash()
+            != deserialized_packet.immutable_section().message_hash()
+        {
+            // Remove the popped entry from the tracking hashmap. Unwrap call is safe
+            // because the priority queue and hashmap are kept consistent at all times.
+            let removed_min = self
+                .untrack(popped_immutable_packet.message_hash())
+                .unwrap();
+            self.queue_latency_stats
+                .record_evicted(&popped_immutable_packet);
+
+            self.track(deserialized_packet);
+            removed_min
+        } else {
+            deserialized_packet
+        }
+    }
+
+    /// Pops the highest priority packet across all queues
+    pub fn pop_max(&mut self) -> Option<DeserializedPacket> {
+        self.pop_max_prioritized()
+            .map(|prioritized_packet| self.remove_popped(&prioritized_packet))
+    }
+
+    /// Stops tracking a packet whose queue entry was removed from the priority queues for
+    /// processing, recording how long it was buffered. Panics if the packet is not tracked.
+    pub fn remove_popped(&mut self, prioritized_packet: &PrioritizedPacket) -> DeserializedPacket {
+        let deserialized_packet = self.untrack(prioritized_packet.message_hash()).unwrap();
+        self.queue_latency_stats.record_popped(prioritized_packet);
+        self.inject_pop_faults(&deserialized_packet);
+        deserialized_packet
+    }
+
+    /// Returns the highest priority queue entry across all queues without removing it
+    pub fn peek_max(&self) -> Option<&PrioritizedPacket> {
+        let max_vote_priority = self
+            .vote_packet_priority_queue
+            .as_ref()
+            .and_then(MinMaxHeap::peek_max);
+        match (max_vote_priority, self.packet_priority_queue.peek_max()) {
+            (Some(max_vote), Some(max_tx)) => Some(max_vote.max(max_tx)),
+            (max_vote, None) => max_vote,
+            (None, max_tx) => max_tx,
+        }
+    }
+
+    /// Pops the lowest priority queue entry across all queues, leaving the packet tracked in
+    /// `message_hash_to_transaction`
+    fn pop_min_prioritized(&mut self) -> Option<PrioritizedPacket> {
+        let min_vote_priority = self
+            .vote_packet_priority_queue
+            .as_ref()
+            .and_then(MinMaxHeap::peek_min);
+        let pop_vote = match (min_vote_priority, self.packet_priority_queue.peek_min()) {
+            (Some(min_vote), Some(min_tx)) => min_vote < min_tx,
+            (min_vote, _) => min_vote.is_some(),
+        };
+        self.priority_queue_for_mut(pop_vote).pop_min()
+    }
+
+    /// Pops the highest priority queue entry across all queues, leaving the packet tracked in
+    /// `message_hash_to_transaction`
+    fn pop_max_prioritized(&mut self) -> Option<PrioritizedPacket> {
+        let pop_vote = self.is_vote_lane(self.peek_max()?);
+        self.priority_queue_for_mut(pop_vote).pop_max()
+    }
+
+    fn pop_max_from(&mut self, is_vote_lane: bool) -> Option<DeserializedPacket> {
+        self.priority_queue_for_mut(is_vote_lane)
+            .pop_max()
+            .map(|prioritized_packet| self.remove_popped(&prioritized_packet))
+    }
+
+    /// Pop up to the next `n` highest priority transactions from the queue.
+    /// Returns `None` if the queue is empty
+    pub fn pop_max_n(&mut self, n: usize) -> Option<Vec<DeserializedPacket>> {
+        let current_len = self.len();
+        if self.is_empty() {
+            None
+        } else {
+            let num_to_pop = std::cmp::min(current_len, n);
+            Some(
+                std::iter::from_fn(|| Some(self.pop_max().unwrap()))
+                    .take(num_to_pop)
+                    .collect::<Vec<DeserializedPacket>>(),
+            )
+        }
+    }
+
+    /// Pop up to the next `n` highest priority packets whose static account keys conflict
+    /// neither with `locked_accounts` nor with each other. Conflicting packets that are
+    /// skipped over stay in the buffer. Returns `None` if the buffer is empty
+    pub fn pop_max_n_nonconflicting(
+        &mut self,
+        n: usize,
+        locked_accounts: &PacketAccountLocks,
+    ) -> Option<Vec<DeserializedPacket>> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut batch_locks = PacketAccountLocks::default();
+        let mut selected_packets = Vec::with_capacity(std::cmp::min(n, self.len()));
+        let mut conflicting_packets = vec![];
+        while selected_packets.len() < n {
+            let prioritized_packet = match self.pop_max_prioritized() {
+                Some(prioritized_packet) => prioritized_packet,
+                None => break,
+            };
+            if locked_accounts.can_lock(&prioritized_packet)
+                && batch_locks.can_lock(&prioritized_packet)
+            {
+                batch_locks.lock(&prioritized_packet);
+                selected_packets.push(self.remove_popped(&prioritized_packet));
+            } else {
+                conflicting_packets.push(prioritized_packet);
+            }
+        }
+        self.reinsert_packets(conflicting_packets);
+        Some(selected_packets)
+    }
+
+    /// Pop the highest priority packets until their combined compute unit limits would exceed
+    /// `max_compute_units`. The first packet that does not fit stays in the buffer.
+    /// Returns `None` if the buffer is empty
+    pub fn pop_until_compute_limit(
+        &mut self,
+        max_compute_units: u64,
+    ) -> Option<Vec<DeserializedPacket>> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut packets = vec![];
+        let mut total_compute_units: u64 = 0;
+        while let Some(compute_unit_limit) = self
+            .peek_max()
+            .map(|prioritized_packet| prioritized_packet.compute_unit_limit())
+        {
+            match total_compute_units.checked_add(compute_unit_limit) {
+                Some(new_total) if new_total <= max_compute_units => {
+                    total_compute_units = new_total;
+                }
+                _ => break,
+            }
+            packets.push(self.pop_max().unwrap());
+        }
+        Some(packets)
+    }
+
+    /// Pop up to the next `n` highest priority packets that have not been forwarded yet.
+    /// Forwarded packets that are skipped over stay in the buffer, still tracked for
+    /// deduplication. Returns `None` if the buffer is empty
+    pub fn pop_max_n_unforwarded(&mut self, n: usize) -> Option<Vec<DeserializedPacket>> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut unforwarded_packets = Vec::with_capacity(std::cmp::min(n, self.len()));
+        let mut forwarded_packets = vec![];
+        while unforwarded_packets.len() < n {
+            let prioritized_packet = match self.pop_max_prioritized() {
+                Some(prioritized_packet) => prioritized_packet,
+                None => break,
+            };
+            let forwarded = self
+                .message_hash_to_transaction
+                .get(prioritized_packet.message_hash())
+                .unwrap()
+                .forwarded;
+            if forwarded {
+                forwarded_packets.push(prioritized_packet);
+            } else {
+                unforwarded_packets.push(self.remove_popped(&prioritized_packet));
+            }
+        }
+        self.reinsert_packets(forwarded_packets);
+        Some(unforwarded_packets)
+    }
+
+    /// Selects the highest priority packets that have not been forwarded yet, up to
+    /// `max_count` packets and `max_bytes` of packet data, stopping at the first packet that
+    /// doesn't fit. The selected packets are marked forwarded and stay buffered until the
+    /// caller drops them. Packets already forwarded by the node they came from are marked
+    /// without being selected, as leaders don't accept packets forwarded twice.
+    ///
+    /// Returns the original packets of the selection, highest priority first, in batches of
+    /// at most `PACKETS_PER_BATCH` packets
+    pub fn take_packets_to_forward(
+        &mut self,
+        max_bytes: usize,
+        max_count: usize,
+    ) -> Vec<PacketBatch> {
+        let mut packets = vec![];
+        let mut total_bytes: usize = 0;
+        let mut popped_packets = vec![];
+        while packets.len() < max_count {
+            let prioritized_packet = match self.pop_max_prioritized() {
+                Some(prioritized_packet) => prioritized_packet,
+                None => break,
+            };
+            let deserialized_packet = self
+                .message_hash_to_transaction
+                .get_mut(prioritized_packet.message_hash())
+                .unwrap();
+            let packet = prioritized_packet.original_packet();
+            if !deserialized_packet.forwarded && !packet.meta.forwarded() {
+                match total_bytes.checked_add(packet.meta.size) {
+                    Some(new_total_bytes) if new_total_bytes <= max_bytes => {
+                        total_bytes = new_total_bytes;
+                    }
+                    _ => {
+                        popped_packets.push(prioritized_packet);
+                        break;
+                    }
+                }
+                packets.push(packet.clone());
+            }
+            deserialized_packet.forwarded = true;
+            popped_packets.push(prioritized_packet);
+        }
+        self.reinsert_packets(popped_packets);
+        packets
+            .chunks(PACKETS_PER_BATCH)
+            .map(|packets| PacketBatch::new(packets.to_vec()))
+            .collect()
+    }
+
+    /// Pop up to the next `n` highest priority packets from the vote queue.
+    /// Returns `None` if the vote queue is empty or the buffer is not in dual-queue mode
+    pub fn pop_max_votes_n(&mut self, n: usize) -> Option<Vec<DeserializedPacket>> {
+        self.pop_max_n_from(true, n)
+    }
+
+    /// Pop up to the next `n` highest priority packets from the non-vote queue.
+    /// Outside of dual-queue mode this queue holds every packet.
+    /// Returns `None` if the queue is empty
+    pub fn pop_max_txs_n(&mut self, n: usize) -> Option<Vec<DeserializedPacket>> {
+        self.pop_max_n_from(false, n)
+    }
+
+    fn pop_max_n_from(&mut self, is_vote_lane: bool, n: usize) -> Option<Vec<DeserializedPacket>> {
+        if is_vote_lane && !self.is_dual_queue() {
+            return None;
+        }
+        let current_len = self.priority_queue_for_mut(is_vote_lane).len();
+        if current_len == 0 {
+            None
+        } else {
+            let num_to_pop = std::cmp::min(current_len, n);
+            Some(
+                std::iter::from_fn(|| self.pop_max_from(is_vote_lane))
+                    .take(num_to_pop)
+                    .collect::<Vec<DeserializedPacket>>(),
+            )
+        }
+    }
+
+    /// Removes every packet from the priority queues and returns them highest priority first,
+    /// votes ahead of other transactions. The packets stay tracked in
+    /// `message_hash_to_transaction`; the ones that remain pending must be handed back
+    /// through `reinsert_packets()`.
+    pub fn take_packets_desc(&mut self) -> Vec<PrioritizedPacket> {
+        let mut packets = Vec::with_capacity(self.len());
+        if let Some(vote_packet_priority_queue) = &mut self.vote_packet_priority_queue {
+            packets.extend(vote_packet_priority_queue.drain_desc());
+        }
+        packets.extend(self.packet_priority_queue.drain_desc());
+        packets
+    }
+
+    /// Like `take_packets_desc()`, except that packets other than votes are ordered by
+    /// sampling them with probability proportional to their effective priority
+    pub fn take_packets_weighted_random<R: Rng>(&mut self, rng: &mut R) -> Vec<PrioritizedPacket> {
+        let mut packets = Vec::with_capacity(self.len());
+        if let Some(vote_packet_priority_queue) = &mut self.vote_packet_priority_queue {
+            packets.extend(vote_packet_priority_queue.drain_desc());
+        }
+        // Weighted sampling without replacement as in Efraimidis and Spirakis, 2006: sorting
+        // on u^(1 / weight), for u drawn uniformly from (0, 1], yields the sampling order.
+        // Packets offering no priority fee still get a small weight.
+        let mut keyed_packets: Vec<_> = self
+            .packet_priority_queue
+            .drain()
+            .map(|prioritized_packet| {
+                let weight = prioritized_packet.effective_priority() as f64 + 1.0;
+                let key = rng.gen_range(f64::MIN_POSITIVE, 1.0).ln() / weight;
+                (key, prioritized_packet)
+            })
+            .collect();
+        keyed_packets
+            .sort_unstable_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        packets.extend(
+            keyed_packets
+                .into_iter()
+                .map(|(_, prioritized_packet)| prioritized_packet),
+        );
+        packets
+    }
+
+    /// Removes every packet from the priority queues in the order of the pop policy of the
+    /// buffer, as described by `take_packets_desc()`
+    pub fn take_packets(&mut self) -> Vec<PrioritizedPacket> {
+        match self.pop_policy {
+            PopPolicy::Greedy => self.take_packets_desc(),
+            PopPolicy::WeightedRandom => self.take_packets_weighted_random(&mut thread_rng()),
+        }
+    }
+
+    /// Pops up to `n` packets, votes first, sampling the others with probability proportional
+    /// to their effective priority. This visits every buffered packet. Returns `None` if the
+    /// buffer is empty
+    pub fn pop_weighted_random_n(&mut self, n: usize) -> Option<Vec<DeserializedPacket>> {
+        self.pop_weighted_random_n_with_rng(n, &mut thread_rng())
+    }
+
+    fn pop_weighted_random_n_with_rng<R: Rng>(
+        &mut self,
+        n: usize,
+        rng: &mut R,
+    ) -> Option<Vec<DeserializedPacket>> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut packets = self.take_packets_weighted_random(rng);
+        let remaining_packets = packets.split_off(n.min(packets.len()));
+        self.reinsert_packets(remaining_packets);
+        Some(
+            packets
+                .into_iter()
+                .map(|prioritized_packet| self.remove_popped(&prioritized_packet))
+                .collect(),
+        )
+    }
+
+    /// Puts packets previously returned by `take_packets_desc()` back into their queues
+    pub fn reinsert_packets(&mut self, packets: impl IntoIterator<Item = PrioritizedPacket>) {
+        for prioritized_packet in packets {
+            let is_vote_lane = self.is_vote_lane(&prioritized_packet);
+            self.priority_queue_for_mut(is_vote_lane)
+                .push(prioritized_packet);
+        }
+    }
+
+    /// Iterates over the queue entries of every buffered packet, in no particular order
+    pub fn prioritized_packets(&self) -> impl Iterator<Item = &PrioritizedPacket> {
+        self.packet_priority_queue.iter().chain(
+            self.vote_packet_priority_queue
+                .iter()
+                .flat_map(MinMaxHeap::iter),
+        )
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.packet_priority_queue.capacity()
+            + self
+                .vote_packet_priority_queue
+                .as_ref()
+                .map(MinMaxHeap::capacity)
+                .unwrap_or_default()
+    }
+
+    /// Quantiles of the compute unit prices of the buffered packets other than simple votes,
+    /// whatever the priority mode of the buffer. Takes time linear-logarithmic in the number
+    /// of buffered packets. Returns `None` if no such packet is buffered
+    pub fn priority_quantiles(&self) -> Option<PriorityQuantiles> {
+        PriorityQuantiles::from_prices(
+            self.message_hash_to_transaction
+                .values()
+                .map(DeserializedPacket::immutable_section)
+                .filter(|immutable_section| !immutable_section.is_simple_vote())
+                .map(|immutable_section| immutable_section.compute_unit_price())
+                .collect(),
+        )
+    }
+}
+
+/// Thread-safe packet buffer that lets several banking threads drain a single global
+/// priority queue. Packets are spread over independently locked `UnprocessedPacketBatches`
+/// shards by message hash, so inserts only contend on one shard, while pops merge the
+/// shards to hand out packets in global priority order.
+pub struct SharedUnprocessedPacketBatches {
+    shards: Vec<Mutex<UnprocessedPacketBatches>>,
+}
+
+impl SharedUnprocessedPacketBatches {
+    /// Creates a buffer of `num_shards` shards holding up to `capacity` packets in total
+    pub fn new(num_shards: usize, capacity: usize) -> Self {
+        assert!(num_shards > 0, "at least one shard is required");
+        let shards = (0..num_shards)
+            .map(|shard_index| {
+                let shard_capacity =
+                    capacity / num_shards + usize::from(shard_index < capacity % num_shards);
+                Mutex::new(UnprocessedPacketBatches::with_capacity(shard_capacity))
+            })
+            .collect();
+        Self { shards }
+    }
+
+    pub fn num_shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard_index(&self, message_hash: &Hash) -> usize {
+        let mut prefix = [0u8; size_of::<u64>()];
+        prefix.copy_from_slice(&message_hash.as_ref()[..size_of::<u64>()]);
+        (u64::from_le_bytes(prefix) % self.shards.len() as u64) as usize
+    }
+
+    /// Locks every shard, always in the same order so concurrent callers can't deadlock
+    fn lock_all(&self) -> Vec<MutexGuard<UnprocessedPacketBatches>> {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap())
+            .collect()
+    }
+
+    /// Inserts `deserialized_packets`, locking each shard at most once.
+    /// Returns the number of packets dropped because their shard was full
+    pub fn insert_batch(
+        &self,
+        deserialized_packets: impl Iterator<Item = DeserializedPacket>,
+    ) -> usize {
+        let mut packets_by_shard = vec![vec![]; self.shards.len()];
+        for deserialized_packet in deserialized_packets {
+            let shard_index =
+                self.shard_index(deserialized_packet.immutable_section().message_hash());
+            packets_by_shard[shard_index].push(deserialized_packet);
+        }
+        self.shards
+            .iter()
+            .zip(packets_by_shard)
+            .filter(|(_, packets)| !packets.is_empty())
+            .map(|(shard, packets)| shard.lock().unwrap().insert_batch(packets.into_iter()))
+            .sum()
+    }
+
+    pub fn push(&self, deserialized_packet: DeserializedPacket) -> Option<DeserializedPacket> {
+        let shard_index = self.shard_index(deserialized_packet.immutable_section().message_hash());
+        self.shards[shard_index]
+            .lock()
+            .unwrap()
+            .push(deserialized_packet)
+    }
+
+    /// Recomputes the priorities of the packets of every shard under `compute_budget`.
+    /// Returns the number of packets dropped because they no longer prioritize.
+    pub fn recompute_priorities(&self, compute_budget: &ComputeBudget) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().recompute_priorities(compute_budget))
+            .sum()
+    }
+
+    /// Returns the dedup stats of all shards gathered since the last call, resetting them
+    pub fn take_dedup_stats(&self) -> DedupStats {
+        let mut dedup_stats = DedupStats::default();
+        for shard in &self.shards {
+            dedup_stats.merge(shard.lock().unwrap().take_dedup_stats());
+        }
+        dedup_stats
+    }
+
+    /// Returns the `n` highest priority packets across all shards, highest first, leaving
+    /// them buffered
+    pub fn peek_top_n(&self, n: usize) -> Vec<Arc<ImmutableDeserializedPacket>> {
+        let shards = self.lock_all();
+        MergedPacketView::new(shards.iter().map(|shard| &**shard))
+            .peek_top_n(n)
+            .into_iter()
+            .map(|prioritized_packet| prioritized_packet.immutable_section().clone())
+            .collect()
+    }
+
+    /// Pop up to the next `n` highest priority packets across all shards.
+    /// Returns `None` if the buffer is empty
+    pub fn pop_max_n(&self, n: usize) -> Option<Vec<DeserializedPacket>> {
+        let mut shards = self.lock_all();
+        if shards.iter().all(|shard| shard.is_empty()) {
+            return None;
+        }
+        let mut packets = Vec::with_capacity(n.min(shards.iter().map(|shard| shard.len()).sum()));
+        while packets.len() < n {
+            let max_shard_index = shards
+                .iter()
+                .enumerate()
+                .filter_map(|(shard_index, shard)| {
+                    shard
+                        .peek_max()
+                        .map(|prioritized_packet| (shard_index, prioritized_packet))
+                })
+                .max_by(|(_, a), (_, b)| a.cmp(b))
+                .map(|(shard_index, _)| shard_index);
+            match max_shard_index {
+                Some(shard_index) => packets.push(shards[shard_index].pop_max().unwrap()),
+                None => break,
+            }
+        }
+        Some(packets)
+    }
+
+    pub fn retain<F>(&self, mut f: F)
+    where
+        F: FnMut(&mut DeserializedPacket) -> bool,
+    {
+        for shard in &self.shards {
+            shard.lock().unwrap().retain(&mut f);
+        }
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.lock().unwrap().clear();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards
+            .iter()
+            .all(|shard| shard.lock().unwrap().is_empty())
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().capacity())
+            .sum()
+    }
+}
+
+/// Read-only view over several buffers, such as the ones of the banking threads, that orders
+/// their packets by priority as if they were held in a single buffer
+pub struct MergedPacketView<'a, P: PacketWeightingPolicy = StakeWeightedPolicy> {
+    buffers: Vec<&'a UnprocessedPacketBatches<P>>,
+}
+
+impl<'a, P: PacketWeightingPolicy> MergedPacketView<'a, P> {
+    pub fn new(buffers: impl IntoIterator<Item = &'a UnprocessedPacketBatches<P>>) -> Self {
+        Self {
+            buffers: buffers.into_iter().collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffers.iter().map(|buffer| buffer.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.iter().all(|buffer| buffer.is_empty())
+    }
+
+    /// Iterates over the packets of all buffers, highest priority first
+    pub fn iter(&self) -> impl Iterator<Item = &'a PrioritizedPacket> {
+        self.merge_top_n(usize::MAX)
+    }
+
+    /// Returns the `n` highest priority packets across all buffers, highest first. Only the
+    /// `n` highest priority packets of each buffer are sorted.
+    pub fn peek_top_n(&self, n: usize) -> Vec<&'a PrioritizedPacket> {
+        self.merge_top_n(n).take(n).collect()
+    }
+
+    fn merge_top_n(&self, n: usize) -> impl Iterator<Item = &'a PrioritizedPacket> {
+        self.buffers
+            .iter()
+            .map(|buffer| Self::top_n_desc(buffer, n))
+            .kmerge_by(|a: &&PrioritizedPacket, b: &&PrioritizedPacket| a > b)
+    }
+
+    fn top_n_desc(buffer: &'a UnprocessedPacketBatches<P>, n: usize) -> Vec<&'a PrioritizedPacket> {
+        if n == 0 {
+            return vec![];
+        }
+        let mut packets: Vec<_> = buffer.prioritized_packets().collect();
+        if n < packets.len() {
+            packets.select_nth_unstable_by(n - 1, |a, b| b.cmp(a));
+            packets.truncate(n);
+        }
+        packets.sort_unstable_by(|a, b| b.cmp(a));
+        packets
+    }
+}
+
+/// Counts of packets that failed to deserialize, by cause
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PacketDeserializerStats {
+    pub short_vec_errors: usize,
+    pub deserialization_errors: usize,
+    pub signature_overflows: usize,
+    pub sanitize_errors: usize,
+    pub prioritization_failures: usize,
+}
+
+impl PacketDeserializerStats {
+    pub fn record(&mut self, err: &DeserializedPacketError) {
+        let count = match err {
+            DeserializedPacketError::ShortVecError(_) => &mut self.short_vec_errors,
+            DeserializedPacketError::DeserializationError(_) => &mut self.deserialization_errors,
+            DeserializedPacketError::SignatureOverflowed(_) => &mut self.signature_overflows,
+            DeserializedPacketError::SanitizeError(_) => &mut self.sanitize_errors,
+            DeserializedPacketError::PrioritizationFailure => &mut self.prioritization_failures,
+        };
+        *count = count.saturating_add(1);
+    }
+
+    pub fn num_failures(&self) -> usize {
+        self.short_vec_errors
+            .saturating_add(self.deserialization_errors)
+            .saturating_add(self.signature_overflows)
+            .saturating_add(self.sanitize_errors)
+            .saturating_add(self.prioritization_failures)
+    }
+}
+
+pub fn deserialize_packets<'a>(
+    packet_batch: &'a PacketBatch,
+    packet_indexes: &'a [usize],
+) -> impl Iterator<Item = DeserializedPacket> + 'a {
+    packet_indexes.iter().filter_map(move |packet_index| {
+        DeserializedPacket::new(packet_batch[*packet_index].clone()).ok()
+    })
+}
+
+/// Like `deserialize_packets`, but counts the packets that fail to deserialize in `stats`
+pub fn deserialize_packets_with_stats<'a>(
+    packet_batch: &'a PacketBatch,
+    packet_indexes: &'a [usize],
+    stats: &'a mut PacketDeserializerStats,
+) -> impl Iterator<Item = DeserializedPacket> + 'a {
+    packet_indexes.iter().filter_map(move |packet_index| {
+        DeserializedPacket::new(packet_batch[*packet_index].clone())
+            .map_err(|err| stats.record(&err))
+            .ok()
+    })
+}
+
+/// Deserializes the packets at `packet_indexes` on `thread_pool`, returning them in packet order.
+/// Batches with fewer than `parallel_threshold` packets are deserialized on the calling thread,
+/// where handing them off to the pool would cost more than it saves. Packets that fail to
+/// deserialize are counted in `stats`.
+pub fn deserialize_packets_par(
+    thread_pool: &ThreadPool,
+    packet_batch: &PacketBatch,
+    packet_indexes: &[usize],
+    parallel_threshold: usize,
+    stats: &mut PacketDeserializerStats,
+) -> Vec<DeserializedPacket> {
+    if packet_indexes.len() < parallel_threshold {
+        return deserialize_packets_with_stats(packet_batch, packet_indexes, stats).collect();
+    }
+    let results: Vec<_> = thread_pool.install(|| {
+        packet_indexes
+            .par_iter()
+            .map(|packet_index| DeserializedPacket::new(packet_batch[*packet_index].clone()))
+            .collect()
+    });
+    results
+        .into_iter()
+        .filter_map(|result| result.map_err(|err| stats.record(&err)).ok())
+        .collect()
+}
+
+/// Read the transaction message from packet data
+pub fn packet_message(packet: &Packet) -> Result<&[u8], DeserializedPacketError> {
+    let (sig_len, sig_size) =
+        decode_shortu16_len(packet.data()).map_err(DeserializedPacketError::ShortVecError)?;
+    sig_len
+        .checked_mul(size_of::<Signature>())
+        .and_then(|v| v.checked_add(sig_size))
+        .and_then(|msg_start| packet.data().get(msg_start..))
+        .ok_or(DeserializedPacketError::SignatureOverflowed(sig_size))
+}
+
+pub fn get_priority(message: &SanitizedVersionedMessage) -> Option<u64> {
+    get_priority_with_mode(message, PriorityMode::default())
+}
+
+pub fn get_priority_with_mode(
+    message: &SanitizedVersionedMessage,
+    priority_mode: PriorityMode,
+) -> Option<u64> {
+    get_compute_budget_details(message).map(|details| details.priority_for(priority_mode))
+}
+
+/// Returns the compute units and fee requested by the compute budget instructions of `message`
+pub fn get_compute_budget_details(
+    message: &SanitizedVersionedMessage,
+) -> Option<ComputeBudgetDetails> {
+    compute_budget_details(
+        message.program_instructions_iter(),
+        message.header().num_required_signatures,
+        ComputeBudget::default(),
+    )
+}
+
+/// Like `get_compute_budget_details()`, but reads the instructions of the transaction in
+/// place. Only the data of compute budget instructions is copied, as the others only count
+/// towards the default compute unit limit.
+pub fn get_packet_compute_budget_details(packet_view: &PacketView) -> Option<ComputeBudgetDetails> {
+    get_packet_compute_budget_details_with_budget(packet_view, &ComputeBudget::default())
+}
+
+/// Like `get_packet_compute_budget_details()`, processing the instructions under
+/// `compute_budget` rather than the default one
+pub fn get_packet_compute_budget_details_with_budget(
+    packet_view: &PacketView,
+    compute_budget: &ComputeBudget,
+) -> Option<ComputeBudgetDetails> {
+    let instructions: Vec<_> = packet_view
+        .program_instructions()
+        .map(|(program_id, instruction)| {
+            let data = if compute_budget::check_id(&program_id) {
+                instruction.data.to_vec()
+            } else {
+                vec![]
+            };
+            (
+                program_id,
+                CompiledInstruction::new_from_raw_parts(instruction.program_id_index, data, vec![]),
+            )
+        })
+        .collect();
+    compute_budget_details(
+        instructions
+            .iter()
+            .map(|(program_id, instruction)| (program_id, instruction)),
+        packet_view.header().num_required_signatures,
+        *compute_budget,
+    )
+}
+
+/// Whether the transaction of `packet_view` consists of a single vote program instruction
+fn is_single_vote_instruction(packet_view: &PacketView) -> bool {
+    packet_view.instructions().count() == 1
+        && packet_view
+            .program_instructions()
+            .next()
+            .map_or(false, |(program_id, _)| {
+                solana_sdk::vote::program::check_id(&program_id)
+            })
+}
+
+/// Compute budget of a simple vote transaction, which is what `compute_budget_details()`
+/// returns for its single vote instruction
+fn simple_vote_compute_budget_details(num_required_signatures: u8) -> ComputeBudgetDetails {
+    ComputeBudgetDetails {
+        priority: SIMPLE_VOTE_PRIORITY,
+        compute_unit_limit: u64::from(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT),
+        compute_unit_price: 0,
+        estimated_fee: u64::from(num_required_signatures)
+            .saturating_mul(ESTIMATED_LAMPORTS_PER_SIGNATURE),
+    }
+}
+
+fn compute_budget_details<'a>(
+    program_instructions: impl Iterator<Item = (&'a Pubkey, &'a CompiledInstruction)>,
+    num_required_signatures: u8,
+    mut compute_budget: ComputeBudget,
+) -> Option<ComputeBudgetDetails> {
+    let prioritization_fee_details = compute_budget
+        .process_instructions(
+            program_instructions,
+            true, // don't reject txs that use request heap size ix
+            true, // use default units per instruction
+            true, // don't reject txs that use set compute unit price ix
+        )
+        .ok()?;
+    let signature_fee =
+        u64::from(num_required_signatures).saturating_mul(ESTIMATED_LAMPORTS_PER_SIGNATURE);
+    Some(ComputeBudgetDetails {
+        priority: prioritization_fee_details.get_priority(),
+        compute_unit_limit: compute_budget.compute_unit_limit,
+        compute_unit_price: prioritization_fee_details.get_priority(),
+        estimated_fee: signature_fee.saturating_add(prioritization_fee_details.get_fee()),
+    })
+}
+
+/// Fee payer of a packet along with a hash of the writable static accounts of its transaction,
+/// in any order. Packets with the same key contend for the same write locks.
+fn lock_set_key(immutable_section: &ImmutableDeserializedPacket) -> (Pubkey, Hash) {
+    let mut writable_account_keys: Vec<_> = immutable_section
+        .packet_view()
+        .writable_static_account_keys()
+        .collect();
+    writable_account_keys.sort_unstable();
+    let writable_account_keys: Vec<_> = writable_account_keys
+        .iter()
+        .map(|account_key| account_key.as_ref())
+        .collect();
+    (
+        *immutable_section.fee_payer(),
+        hashv(&writable_account_keys),
+    )
+}
+
+/// Returns the sequence hint carried by the first memo instruction of `message` that starts
+/// with `SEQUENCE_HINT_MEMO_PREFIX`, if any
+pub fn get_sequence_hint(message: &SanitizedVersionedMessage) -> Option<SequenceHint> {
+    memo_sequence_hint(
+        message
+            .program_instructions_iter()
+            .map(|(program_id, instruction)| (*program_id, instruction.data.as_slice())),
+    )
+}
+
+fn memo_sequence_hint<'a>(
+    program_instructions_data: impl Iterator<Item = (Pubkey, &'a [u8])>,
+) -> Option<SequenceHint> {
+    let memo_program_ids = [spl_memo_id_v1(), spl_memo_id_v3()];
+    program_instructions_data
+        .filter(|(program_id, _)| memo_program_ids.contains(program_id))
+        .find_map(|(_, data)| {
+            let memo = std::str::from_utf8(data).ok()?;
+            let (stream, sequence) = memo
+                .strip_prefix(SEQUENCE_HINT_MEMO_PREFIX)?
+                .split_once(':')?;
+            Some(SequenceHint {
+                stream: stream.parse().ok()?,
+                sequence: sequence.parse().ok()?,
+            })
+        })
+}
+
+/// Runs an untrusted `packet` through the deserialization path and asserts the invariants
+/// that must hold for any input. Shared by the fuzz targets in `core/fuzz` and the unit
+/// test replaying their regression inputs; `cargo fuzz` builds with `--cfg fuzzing`.
+#[cfg(any(test, fuzzing))]
+pub fn check_packet_deserialization(packet: Packet) {
+    let message_bytes = packet_message(&packet).map(<[u8]>::to_vec);
+    let is_simple_vote = packet.meta.is_simple_vote_tx();
+    // `PacketView` must accept exactly the packets that deserialize in full
+    let transaction =
+        packet
+            .deserialize_slice(..)
+            .ok()
+            .and_then(|transaction: VersionedTransaction| {
+                SanitizedVersionedTransaction::try_from(transaction).ok()
+            });
+    match DeserializedPacket::new(packet) {
+        Ok(deserialized_packet) => {
+            let immutable_section = deserialized_packet.immutable_section();
+            let message_bytes = message_bytes.expect("deserialized packet must have a message");
+            let transaction = transaction.expect("deserialized packet must have a transaction");
+            assert_eq!(
+                packet_message(immutable_section.original_packet()).unwrap(),
+                message_bytes
+            );
+            assert_eq!(
+                *immutable_section.message_hash(),
+                Message::hash_raw_message(&message_bytes)
+            );
+            assert_eq!(immutable_section.transaction(), Some(&transaction));
+            assert_eq!(
+                immutable_section.fee_payer(),
+                &transaction.get_message().message.static_account_keys()[0]
+            );
+            assert_eq!(
+                get_compute_budget_details(transaction.get_message()),
+                Some(ComputeBudgetDetails {
+                    priority: immutable_section.priority(),
+                    compute_unit_limit: immutable_section.compute_unit_limit(),
+                    compute_unit_price: immutable_section.compute_unit_price(),
+                    estimated_fee: immutable_section.estimated_fee(),
+                })
+            );
+            assert_eq!(immutable_section.is_simple_vote(), is_simple_vote);
+        }
+        Err(DeserializedPacketError::PrioritizationFailure) => {
+            let transaction = transaction.expect("prioritized packet must have a transaction");
+            assert_eq!(get_compute_budget_details(transaction.get_message()), None);
+        }
+        Err(_) => assert_eq!(transaction, None),
+    }
+}
+
+pub fn transactions_to_deserialized_packets(
+    transactions: &[Transaction],
+) -> Result<Vec<DeserializedPacket>, DeserializedPacketError> {
+    transactions
+        .iter()
+        .map(|transaction| {
+            let packet = Packet::from_data(None, transaction)?;
+            DeserializedPacket::new(packet)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        rand::SeedableRng,
+        rand_chacha::ChaChaRng,
+        solana_perf::packet::{PacketFlags, PACKET_DATA_SIZE},
+        solana_runtime::genesis_utils::create_genesis_config,
+        solana_sdk::{
+            account::AccountSharedData,
+            compute_budget::ComputeBudgetInstruction,
+            instruction::Instruction,
+            message::VersionedMessage,
+            nonce,
+            signature::{Keypair, Signer},
+            system_instruction, system_program, system_transaction,
+        },
+    };
+
+    fn packet_with_sender_stake(sender_stake: u64, ip: Option<IpAddr>) -> DeserializedPacket {
+        let tx = system_transaction::transfer(
+            &Keypair::new(),
+            &solana_sdk::pubkey::new_rand(),
+            1,
+            Hash::new_unique(),
+        );
+        let mut packet = Packet::from_data(None, &tx).unwrap();
+        packet.meta.sender_stake = sender_stake;
+        if let Some(ip) = ip {
+            packet.meta.addr = ip;
+        }
+        DeserializedPacket::new(packet).unwrap()
+    }
+
+    fn packet_with_priority(priority: u64) -> DeserializedPacket {
+        let tx = system_transaction::transfer(
+            &Keypair::new(),
+            &solana_sdk::pubkey::new_rand(),
+            1,
+            Hash::new_unique(),
+        );
+        let packet = Packet::from_data(None, &tx).unwrap();
+        DeserializedPacket::new_with_priority(packet, priority).unwrap()
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_insert_pop_same_packet() {
+        let packet = packet_with_sender_stake(1, None);
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
+        unprocessed_packet_batches.push(packet.clone());
+        unprocessed_packet_batches.push(packet.clone());
+
+        // There was only one unique packet, so that one should be the
+        // only packet returned
+        assert_eq!(
+            unprocessed_packet_batches.pop_max_n(2).unwrap(),
+            vec![packet]
+        );
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_insert_minimum_packet_over_capacity() {
+        let heavier_packet_weight = 2;
+        let heavier_packet = packet_with_priority(heavier_packet_weight);
+
+        let lesser_packet_weight = heavier_packet_weight - 1;
+        let lesser_packet = packet_with_priority(lesser_packet_weight);
+
+        // Test that the heavier packet is actually heavier
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
+        unprocessed_packet_batches.push(heavier_packet.clone());
+        unprocessed_packet_batches.push(lesser_packet.clone());
+        assert_eq!(
+            unprocessed_packet_batches.pop_max().unwrap(),
+            heavier_packet
+        );
+
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(1);
+        unprocessed_packet_batches.push(heavier_packet);
+
+        // Buffer is now at capacity, pushing the smaller weighted
+        // packet should immediately pop it
+        assert_eq!(
+            unprocessed_packet_batches
+                .push(lesser_packet.clone())
+                .unwrap(),
+            lesser_packet
+        );
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_byte_capacity() {
+        let packets: Vec<_> = (1..=4).map(packet_with_priority).collect();
+        let packet_size = packets[0].immutable_section().buffered_size();
+        assert!(packet_size > packets[0].immutable_section().original_packet().meta.size);
+
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::with_capacity(10).with_byte_capacity(2 * packet_size);
+        assert_eq!(unprocessed_packet_batches.buffered_bytes(), 0);
+        assert!(unprocessed_packet_batches
+            .push(packets[1].clone())
+            .is_none());
+        assert!(unprocessed_packet_batches
+            .push(packets[2].clone())
+            .is_none());
+        assert_eq!(unprocessed_packet_batches.buffered_bytes(), 2 * packet_size);
+
+        // Over the byte budget, the lowest priority packet makes way, even if it's the newest
+        assert_eq!(
+            unprocessed_packet_batches.push(packets[0].clone()),
+            Some(packets[0].clone())
+        );
+        assert_eq!(
+            unprocessed_packet_batches.push(packets[3].clone()),
+            Some(packets[1].clone())
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert_eq!(unprocessed_packet_batches.buffered_bytes(), 2 * packet_size);
+        assert_eq!(
+            unprocessed_packet_batches.take_byte_evicted_packets_count(),
+            2
+        );
+
+        // Every removal path gives the bytes back
+        assert_eq!(
+            unprocessed_packet_batches.pop_max(),
+            Some(packets[3].clone())
+        );
+        assert_eq!(unprocessed_packet_batches.buffered_bytes(), packet_size);
+        unprocessed_packet_batches.retain(|_| false);
+        assert_eq!(unprocessed_packet_batches.buffered_bytes(), 0);
+        unprocessed_packet_batches.push(packets[2].clone());
+        unprocessed_packet_batches.clear();
+        assert_eq!(unprocessed_packet_batches.buffered_bytes(), 0);
+
+        // Without a byte capacity the bytes are only tracked
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10);
+        unprocessed_packet_batches.insert_batch(packets.into_iter());
+        assert_eq!(unprocessed_packet_batches.len(), 4);
+        assert_eq!(unprocessed_packet_batches.buffered_bytes(), 4 * packet_size);
+        assert_eq!(
+            unprocessed_packet_batches.take_byte_evicted_packets_count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_queue_latency_stats() {
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
+        let packets: Vec<_> = (1..=3).map(packet_with_priority).collect();
+        assert!(unprocessed_packet_batches.queue_latency_stats().is_empty());
+
+        // a packet rejected on arrival was never buffered
+        unprocessed_packet_batches.push(packets[1].clone());
+        unprocessed_packet_batches.push(packets[2].clone());
+        assert!(unprocessed_packet_batches
+            .push(packets[0].clone())
+            .is_some());
+        assert!(unprocessed_packet_batches.queue_latency_stats().is_empty());
+
+        // a buffered packet pushed out by a higher priority one is evicted
+        let packet = packet_with_priority(4);
+        assert_eq!(
+            unprocessed_packet_batches.push(packet.clone()).unwrap(),
+            packets[1]
+        );
+        let stats = unprocessed_packet_batches.queue_latency_stats();
+        assert_eq!(stats.evicted_us.entries(), 1);
+        assert_eq!(stats.popped_us.entries(), 0);
+
+        assert_eq!(unprocessed_packet_batches.pop_max().unwrap(), packet);
+        let message_hash = *packets[2].immutable_section().message_hash();
+        let taken_packets = unprocessed_packet_batches.take_packets_desc();
+        assert_eq!(taken_packets.len(), 1);
+        assert_eq!(taken_packets[0].message_hash(), &message_hash);
+        assert_eq!(
+            unprocessed_packet_batches.remove_popped(&taken_packets[0]),
+            packets[2]
+        );
+        let stats = unprocessed_packet_batches.queue_latency_stats();
+        assert_eq!(stats.evicted_us.entries(), 1);
+        assert_eq!(stats.popped_us.entries(), 2);
+        assert!(unprocessed_packet_batches.is_empty());
+    }
+
+    #[test]
+    fn test_queue_latency_stats_pushed_back_packet() {
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
+        unprocessed_packet_batches.push(packet_with_priority(1));
+        std::thread::sleep(Duration::from_millis(50));
+        let packet = unprocessed_packet_batches.pop_max().unwrap();
+
+        // a packet retried after being popped is only timed from when it was pushed back
+        unprocessed_packet_batches.push(packet.clone());
+        assert_eq!(unprocessed_packet_batches.pop_max().unwrap(), packet);
+        let stats = unprocessed_packet_batches.queue_latency_stats();
+        assert_eq!(stats.popped_us.entries(), 2);
+        assert!(stats.popped_us.maximum().unwrap() >= 25_000);
+        assert!(stats.popped_us.minimum().unwrap() < 25_000);
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_dedup_stats() {
+        let ip_a = IpAddr::from([10, 0, 0, 1]);
+        let ip_b = IpAddr::from([10, 0, 0, 2]);
+        let packet = packet_with_sender_stake(1, Some(ip_a));
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10);
+        assert!(unprocessed_packet_batches.push(packet.clone()).is_none());
+        assert_eq!(unprocessed_packet_batches.dedup_stats().num_duplicates, 0);
+
+        // resubmit the same message twice from one sender and once from another
+        assert!(unprocessed_packet_batches.push(packet.clone()).is_none());
+        assert!(unprocessed_packet_batches.push(packet.clone()).is_none());
+        let mut resubmitted = packet.immutable_section().original_packet().clone();
+        resubmitted.meta.addr = ip_b;
+        assert!(unprocessed_packet_batches
+            .push(DeserializedPacket::new(resubmitted).unwrap())
+            .is_none());
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+
+        let dedup_stats = unprocessed_packet_batches.take_dedup_stats();
+        assert_eq!(dedup_stats.num_duplicates, 3);
+        assert_eq!(dedup_stats.top_sources(1), vec![(ip_a, 2)]);
+        assert_eq!(dedup_stats.top_sources(5), vec![(ip_a, 2), (ip_b, 1)]);
+        assert_eq!(
+            unprocessed_packet_batches.take_dedup_stats(),
+            DedupStats::default()
+        );
+    }
+
+    #[test]
+    fn test_dedup_stats_bounded_sources() {
+        let mut dedup_stats = DedupStats::default();
+        for i in 0..=MAX_DEDUP_STATS_SOURCES as u32 {
+            dedup_stats.record(IpAddr::from(i.to_be_bytes()));
+        }
+        assert_eq!(dedup_stats.num_duplicates, MAX_DEDUP_STATS_SOURCES + 1);
+        assert_eq!(
+            dedup_stats.duplicates_by_source.len(),
+            MAX_DEDUP_STATS_SOURCES
+        );
+
+        let mut merged = DedupStats::default();
+        merged.merge(dedup_stats.clone());
+        merged.merge(dedup_stats);
+        assert_eq!(merged.num_duplicates, 2 * (MAX_DEDUP_STATS_SOURCES + 1));
+        assert_eq!(merged.duplicates_by_source.len(), MAX_DEDUP_STATS_SOURCES);
+        assert_eq!(merged.top_sources(1)[0].1, 2);
+    }
+
+    #[test]
+    fn test_source_rate_limiter() {
+        let ip_a = IpAddr::from([10, 0, 0, 1]);
+        let ip_b = IpAddr::from([10, 0, 0, 2]);
+        let mut source_rate_limiter = SourceRateLimiter::new(2);
+        let start = source_rate_limiter.window_start;
+        assert!(source_rate_limiter.allow(ip_a, start));
+        assert!(source_rate_limiter.allow(ip_a, start));
+        assert!(!source_rate_limiter.allow(ip_a, start));
+        // Sources have budgets of their own
+        assert!(source_rate_limiter.allow(ip_b, start));
+
+        // Half of the previous window still overlaps the sliding window
+        let now = start + SOURCE_RATE_LIMIT_WINDOW * 3 / 2;
+        assert!(source_rate_limiter.allow(ip_a, now));
+        assert!(!source_rate_limiter.allow(ip_a, now));
+        assert!(source_rate_limiter.allow(ip_b, now));
+        assert!(source_rate_limiter.allow(ip_b, now));
+        assert!(!source_rate_limiter.allow(ip_b, now));
+
+        // Idle sources are forgotten
+        let now = start + SOURCE_RATE_LIMIT_WINDOW * 4;
+        assert!(source_rate_limiter.allow(ip_a, now));
+        assert_eq!(source_rate_limiter.counts.len(), 1);
+    }
+
+    #[test]
+    fn test_source_rate_limiter_bounded_sources() {
+        let mut source_rate_limiter = SourceRateLimiter::new(1);
+        let now = source_rate_limiter.window_start;
+        // Start past 0.0.0.0, which is where further sources are counted
+        for i in 1..=MAX_RATE_LIMITED_SOURCES as u32 {
+            assert!(source_rate_limiter.allow(IpAddr::from(i.to_be_bytes()), now));
+        }
+        // Further sources share a budget
+        let first_overflow = MAX_RATE_LIMITED_SOURCES as u32 + 1;
+        assert!(source_rate_limiter.allow(IpAddr::from(first_overflow.to_be_bytes()), now));
+        assert!(!source_rate_limiter.allow(IpAddr::from((first_overflow + 1).to_be_bytes()), now));
+        assert_eq!(
+            source_rate_limiter.counts.len(),
+            MAX_RATE_LIMITED_SOURCES + 1
+        );
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_source_rate_limit() {
+        let ip_a = IpAddr::from([10, 0, 0, 1]);
+        let ip_b = IpAddr::from([10, 0, 0, 2]);
+        let packets = [ip_a, ip_a, ip_b, ip_a]
+            .into_iter()
+            .map(|ip| packet_with_sender_stake(1, Some(ip)));
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::with_capacity(10).with_source_rate_limit(2);
+        assert_eq!(unprocessed_packet_batches.insert_batch(packets), 0);
+        assert_eq!(unprocessed_packet_batches.len(), 3);
+        assert_eq!(
+            unprocessed_packet_batches.take_rate_limited_packets_count(),
+            1
+        );
+        assert_eq!(
+            unprocessed_packet_batches.take_rate_limited_packets_count(),
+            0
+        );
+    }
+
+    fn pop_all_sender_stakes<P: PacketWeightingPolicy>(
+        unprocessed_packet_batches: &mut UnprocessedPacketBatches<P>,
+    ) -> Vec<u64> {
+        std::iter::from_fn(|| unprocessed_packet_batches.pop_max())
+            .map(|packet| packet.immutable_section().sender_stake())
+            .collect()
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_weighting_policy() {
+        let packets: Vec<_> = [2, 1, 3]
+            .into_iter()
+            .map(|sender_stake| packet_with_sender_stake(sender_stake, None))
+            .collect();
+
+        let mut stake_weighted = UnprocessedPacketBatches::with_capacity(3);
+        stake_weighted.insert_batch(packets.clone().into_iter());
+        assert_eq!(pop_all_sender_stakes(&mut stake_weighted), vec![3, 2, 1]);
+
+        let mut arrival_order =
+            UnprocessedPacketBatches::with_capacity_and_policy(3, ArrivalOrderPolicy::default());
+        arrival_order.insert_batch(packets.clone().into_iter());
+        assert_eq!(pop_all_sender_stakes(&mut arrival_order), vec![2, 1, 3]);
+
+        // a full buffer drops the packet that arrived last
+        let mut arrival_order =
+            UnprocessedPacketBatches::with_capacity_and_policy(2, ArrivalOrderPolicy::default());
+        assert_eq!(arrival_order.insert_batch(packets.clone().into_iter()), 1);
+        assert_eq!(pop_all_sender_stakes(&mut arrival_order), vec![2, 1]);
+
+        let mut random = UnprocessedPacketBatches::with_capacity_and_policy(3, RandomPolicy);
+        random.insert_batch(packets.into_iter());
+        let mut sender_stakes = pop_all_sender_stakes(&mut random);
+        sender_stakes.sort_unstable();
+        assert_eq!(sender_stakes, vec![1, 2, 3]);
+
+        // without a compute unit price, fees are equal so the smallest compute budget wins
+        let mut fee_per_compute_unit =
+            UnprocessedPacketBatches::with_capacity_and_policy(3, FeePerComputeUnitPolicy);
+        fee_per_compute_unit.insert_batch(
+            [200_000, 100, 50_000]
+                .into_iter()
+                .map(|compute_unit_limit| packet_with_compute_unit_limit(compute_unit_limit, 0)),
+        );
+        let compute_unit_limits: Vec<_> = std::iter::from_fn(|| fee_per_compute_unit.pop_max())
+            .map(|packet| packet.immutable_section().compute_unit_limit())
+            .collect();
+        assert_eq!(compute_unit_limits, vec![100, 50_000, 200_000]);
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_pop_max_n() {
+        let num_packets = 10;
+        let packets_iter =
+            std::iter::repeat_with(|| packet_with_sender_stake(1, None)).take(num_packets);
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::from_iter(packets_iter.clone(), num_packets);
+
+        // Test with small step size
+        let step_size = 1;
+        for _ in 0..num_packets {
+            assert_eq!(
+                unprocessed_packet_batches
+                    .pop_max_n(step_size)
+                    .unwrap()
+                    .len(),
+                step_size
+            );
+        }
+
+        assert!(unprocessed_packet_batches.is_empty());
+        assert!(unprocessed_packet_batches.pop_max_n(0).is_none());
+        assert!(unprocessed_packet_batches.pop_max_n(1).is_none());
+
+        // Test with step size larger than `num_packets`
+        let step_size = num_packets + 1;
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::from_iter(packets_iter.clone(), num_packets);
+        assert_eq!(
+            unprocessed_packet_batches
+                .pop_max_n(step_size)
+                .unwrap()
+                .len(),
+            num_packets
+        );
+        assert!(unprocessed_packet_batches.is_empty());
+        assert!(unprocessed_packet_batches.pop_max_n(0).is_none());
+
+        // Test with step size equal to `num_packets`
+        let step_size = num_packets;
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::from_iter(packets_iter, num_packets);
+        assert_eq!(
+            unprocessed_packet_batches
+                .pop_max_n(step_size)
+                .unwrap()
+                .len(),
+            step_size
+        );
+        assert!(unprocessed_packet_batches.is_empty());
+        assert!(unprocessed_packet_batches.pop_max_n(0).is_none());
+    }
+
+    fn vote_packet_with_priority(priority: u64) -> DeserializedPacket {
+        let tx = system_transaction::transfer(
+            &Keypair::new(),
+            &solana_sdk::pubkey::new_rand(),
+            1,
+            Hash::new_unique(),
+        );
+        let mut packet = Packet::from_data(None, &tx).unwrap();
+        packet.meta.flags |= PacketFlags::SIMPLE_VOTE_TX;
+        DeserializedPacket::new_with_priority(packet, priority).unwrap()
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_dual_queue() {
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_vote_capacity(2, 1);
+        assert!(unprocessed_packet_batches.is_dual_queue());
+
+        let vote = vote_packet_with_priority(1);
+        unprocessed_packet_batches.push(vote.clone());
+
+        // A flood of higher priority transactions does not evict the vote
+        let txs: Vec<_> = (2..6).map(packet_with_priority).collect();
+        assert_eq!(unprocessed_packet_batches.insert_batch(txs.into_iter()), 2);
+        assert_eq!(unprocessed_packet_batches.len(), 3);
+        assert_eq!(unprocessed_packet_batches.votes_len(), 1);
+
+        // Votes have their own capacity
+        let lesser_vote = vote_packet_with_priority(0);
+        assert_eq!(
+            unprocessed_packet_batches.push(lesser_vote.clone()),
+            Some(lesser_vote)
+        );
+
+        assert_eq!(
+            unprocessed_packet_batches.pop_max_votes_n(2).unwrap(),
+            vec![vote]
+        );
+        assert!(unprocessed_packet_batches.pop_max_votes_n(1).is_none());
+        let txs = unprocessed_packet_batches.pop_max_txs_n(3).unwrap();
+        assert_eq!(
+            txs.iter()
+                .map(|packet| packet.immutable_section().priority())
+                .collect::<Vec<_>>(),
+            vec![5, 4]
+        );
+        assert!(unprocessed_packet_batches.is_empty());
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_take_and_reinsert_packets() {
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_vote_capacity(2, 2);
+        unprocessed_packet_batches.push(packet_with_priority(10));
+        unprocessed_packet_batches.push(vote_packet_with_priority(1));
+
+        let packets = unprocessed_packet_batches.take_packets_desc();
+        assert_eq!(packets.len(), 2);
+        // Votes come first regardless of priority
+        assert!(packets[0].is_simple_vote());
+        assert_eq!(unprocessed_packet_batches.len(), 0);
+        assert_eq!(
+            unprocessed_packet_batches.message_hash_to_transaction.len(),
+            2
+        );
+
+        unprocessed_packet_batches.reinsert_packets(packets);
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert_eq!(unprocessed_packet_batches.votes_len(), 1);
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_save_restore() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("buffer.bin");
+
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(3);
+        let forwarded_packet = packet_with_priority(10);
+        let forwarded_hash = *forwarded_packet.immutable_section().message_hash();
+        unprocessed_packet_batches.push(forwarded_packet);
+        unprocessed_packet_batches.push(packet_with_priority(20));
+        unprocessed_packet_batches
+            .message_hash_to_transaction
+            .get_mut(&forwarded_hash)
+            .unwrap()
+            .forwarded = true;
+        assert_eq!(unprocessed_packet_batches.save_to_file(&path).unwrap(), 2);
+        assert!(path.exists());
+
+        let mut restored_packet_batches = UnprocessedPacketBatches::with_capacity(3);
+        assert_eq!(restored_packet_batches.restore_from_file(&path).unwrap(), 2);
+        assert!(!path.exists());
+        assert_eq!(restored_packet_batches.len(), 2);
+        assert_eq!(
+            restored_packet_batches
+                .message_hash_to_transaction
+                .values()
+                .filter(|deserialized_packet| deserialized_packet.forwarded)
+                .map(|deserialized_packet| *deserialized_packet.immutable_section().message_hash())
+                .collect::<Vec<_>>(),
+            vec![forwarded_hash]
+        );
+
+        // A file that does not deserialize is an error
+        fs::write(&path, [1, 2, 3]).unwrap();
+        assert!(restored_packet_batches.restore_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_single_queue_holds_votes() {
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
+        unprocessed_packet_batches.push(vote_packet_with_priority(1));
+        assert_eq!(unprocessed_packet_batches.votes_len(), 0);
+        assert!(unprocessed_packet_batches.pop_max_votes_n(1).is_none());
+        assert_eq!(
+            unprocessed_packet_batches.pop_max_txs_n(1).unwrap().len(),
+            1
+        );
+    }
+
+    fn transfer_packet_with_priority(to: &Pubkey, priority: u64) -> DeserializedPacket {
+        let tx = system_transaction::transfer(&Keypair::new(), to, 1, Hash::new_unique());
+        let packet = Packet::from_data(None, &tx).unwrap();
+        DeserializedPacket::new_with_priority(packet, priority).unwrap()
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_max_packets_per_lock_set() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let payer_transfer_packet = |to: &Pubkey, priority: u64| {
+            let tx = system_transaction::transfer(&payer, to, 1, Hash::new_unique());
+            let packet = Packet::from_data(None, &tx).unwrap();
+            DeserializedPacket::new_with_priority(packet, priority).unwrap()
+        };
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::with_capacity(10).with_max_packets_per_lock_set(2);
+        let highest = payer_transfer_packet(&to, 5);
+        let lowest = payer_transfer_packet(&to, 3);
+        assert!(unprocessed_packet_batches.push(highest.clone()).is_none());
+        assert!(unprocessed_packet_batches.push(lowest).is_none());
+
+        // Dropped as the buffered packets of the lock set have higher priorities
+        assert!(unprocessed_packet_batches
+            .push(payer_transfer_packet(&to, 1))
+            .is_none());
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        // Evicts the lowest priority packet of the lock set
+        let higher = payer_transfer_packet(&to, 4);
+        assert!(unprocessed_packet_batches.push(higher.clone()).is_none());
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert_eq!(unprocessed_packet_batches.take_coalesced_packets_count(), 2);
+
+        // Packets writing other accounts or paid by others are not limited
+        let other_account = payer_transfer_packet(&Pubkey::new_unique(), 1);
+        let other_payer = transfer_packet_with_priority(&to, 1);
+        assert!(unprocessed_packet_batches
+            .push(other_account.clone())
+            .is_none());
+        assert!(unprocessed_packet_batches
+            .push(other_payer.clone())
+            .is_none());
+        assert_eq!(unprocessed_packet_batches.len(), 4);
+        assert_eq!(unprocessed_packet_batches.take_coalesced_packets_count(), 0);
+
+        assert_eq!(unprocessed_packet_batches.pop_max().unwrap(), highest);
+        assert_eq!(unprocessed_packet_batches.pop_max().unwrap(), higher);
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_transaction_account_lock_limit() {
+        // A transfer locks its payer, recipient and the system program
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10);
+        unprocessed_packet_batches.set_transaction_account_lock_limit(Some(2));
+        assert!(unprocessed_packet_batches
+            .push(transfer_packet_with_priority(&Pubkey::new_unique(), 1))
+            .is_none());
+        assert!(unprocessed_packet_batches.is_empty());
+        assert_eq!(
+            unprocessed_packet_batches.take_too_many_account_locks_packets_count(),
+            1
+        );
+
+        unprocessed_packet_batches.set_transaction_account_lock_limit(Some(3));
+        assert!(unprocessed_packet_batches
+            .push(transfer_packet_with_priority(&Pubkey::new_unique(), 1))
+            .is_none());
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+        assert_eq!(
+            unprocessed_packet_batches.take_too_many_account_locks_packets_count(),
+            0
+        );
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[test]
+    fn test_unprocessed_packet_batches_fault_injection() {
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10)
+            .with_fault_injection(PacketFaultInjectionConfig {
+                drop_rate: 1.0,
+                ..PacketFaultInjectionConfig::default()
+            });
+        let packet = packet_with_priority(1);
+        assert_eq!(
+            unprocessed_packet_batches.push(packet.clone()),
+            Some(packet)
+        );
+        assert!(unprocessed_packet_batches.is_empty());
+
+        // Capacity pressure evicts the lowest priority packets beyond the forced capacity
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10)
+            .with_fault_injection(PacketFaultInjectionConfig {
+                duplicate_rate: 1.0,
+                capacity: Some(2),
+                ..PacketFaultInjectionConfig::default()
+            });
+        let packets: Vec<_> = (1..=3).map(packet_with_priority).collect();
+        assert_eq!(
+            unprocessed_packet_batches.insert_batch(packets.clone().into_iter()),
+            1
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+
+        // Popped packets are delivered again with the next batch
+        assert_eq!(
+            unprocessed_packet_batches.pop_max(),
+            Some(packets[2].clone())
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+        assert_eq!(
+            unprocessed_packet_batches.insert_batch(std::iter::empty()),
+            0
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert_eq!(unprocessed_packet_batches.peek_max().unwrap().priority(), 3);
+        let stats = unprocessed_packet_batches
+            .fault_injection_stats_mut()
+            .unwrap();
+        assert_eq!(stats.dropped_count, 0);
+        assert_eq!(stats.duplicated_count, 1);
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_pop_max_n_nonconflicting() {
+        let shared_account = Pubkey::new_unique();
+        let highest = transfer_packet_with_priority(&shared_account, 3);
+        let conflicting = transfer_packet_with_priority(&shared_account, 2);
+        let independent_account = Pubkey::new_unique();
+        let independent = transfer_packet_with_priority(&independent_account, 1);
+
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::from_iter(
+            vec![highest.clone(), conflicting.clone(), independent.clone()],
+            3,
+        );
+
+        // The second packet writes the same account as the first one and is skipped
+        assert_eq!(
+            unprocessed_packet_batches
+                .pop_max_n_nonconflicting(2, &PacketAccountLocks::default())
+                .unwrap(),
+            vec![highest, independent]
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+
+        // Packets conflicting with already held locks stay in the buffer
+        let mut locked_accounts = PacketAccountLocks::default();
+        locked_accounts.read_locks.insert(shared_account);
+        assert_eq!(
+            unprocessed_packet_batches
+                .pop_max_n_nonconflicting(2, &locked_accounts)
+                .unwrap(),
+            vec![]
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+
+        assert_eq!(
+            unprocessed_packet_batches
+                .pop_max_n_nonconflicting(2, &PacketAccountLocks::default())
+                .unwrap(),
+            vec![conflicting]
+        );
+        assert!(unprocessed_packet_batches
+            .pop_max_n_nonconflicting(2, &PacketAccountLocks::default())
+            .is_none());
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_pop_max_n_unforwarded() {
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::from_iter((0..6).map(packet_with_priority), 10);
+        unprocessed_packet_batches
+            .iter_mut()
+            .filter(|packet| packet.immutable_section().priority() % 2 == 1)
+            .for_each(|packet| packet.forwarded = true);
+
+        let priorities = |packets: Vec<DeserializedPacket>| -> Vec<u64> {
+            packets
+                .iter()
+                .map(|packet| packet.immutable_section().priority())
+                .collect()
+        };
+        assert_eq!(
+            priorities(unprocessed_packet_batches.pop_max_n_unforwarded(2).unwrap()),
+            vec![4, 2]
+        );
+        assert_eq!(
+            priorities(unprocessed_packet_batches.pop_max_n_unforwarded(2).unwrap()),
+            vec![0]
+        );
+        assert!(unprocessed_packet_batches
+            .pop_max_n_unforwarded(2)
+            .unwrap()
+            .is_empty());
+
+        // Forwarded packets stay buffered, in priority order
+        assert_eq!(unprocessed_packet_batches.len(), 3);
+        assert_eq!(
+            unprocessed_packet_batches.message_hash_to_transaction.len(),
+            3
+        );
+        assert_eq!(
+            priorities(unprocessed_packet_batches.pop_max_n(3).unwrap()),
+            vec![5, 3, 1]
+        );
+        assert!(unprocessed_packet_batches
+            .pop_max_n_unforwarded(1)
+            .is_none());
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_take_packets_to_forward() {
+        let packets = (0..6).map(|priority| {
+            let mut packet = packet_with_priority(priority)
+                .immutable_section()
+                .original_packet()
+                .clone();
+            packet.meta.port = priority as u16;
+            if priority == 4 {
+                packet.meta.flags |= PacketFlags::FORWARDED;
+            }
+            DeserializedPacket::new_with_priority(packet, priority).unwrap()
+        });
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::from_iter(packets, 10);
+        unprocessed_packet_batches
+            .iter_mut()
+            .filter(|packet| packet.immutable_section().priority() == 5)
+            .for_each(|packet| packet.forwarded = true);
+        let packet_size = unprocessed_packet_batches
+            .iter()
+            .next()
+            .unwrap()
+            .immutable_section()
+            .original_packet()
+            .meta
+            .size;
+
+        let ports = |packet_batches: Vec<PacketBatch>| -> Vec<u16> {
+            packet_batches
+                .iter()
+                .flat_map(PacketBatch::iter)
+                .map(|packet| packet.meta.port)
+                .collect()
+        };
+        // The packet forwarded by its sender is marked but not returned
+        assert_eq!(
+            ports(unprocessed_packet_batches.take_packets_to_forward(usize::MAX, 2)),
+            vec![3, 2]
+        );
+        // Selection stops at the first packet exceeding the byte limit
+        assert_eq!(
+            ports(unprocessed_packet_batches.take_packets_to_forward(2 * packet_size - 1, 10)),
+            vec![1]
+        );
+        assert_eq!(
+            ports(unprocessed_packet_batches.take_packets_to_forward(usize::MAX, 10)),
+            vec![0]
+        );
+        assert!(unprocessed_packet_batches
+            .take_packets_to_forward(usize::MAX, 10)
+            .is_empty());
+
+        // Forwarded packets stay buffered
+        assert_eq!(unprocessed_packet_batches.len(), 6);
+        assert!(unprocessed_packet_batches
+            .iter()
+            .all(|packet| packet.forwarded));
+
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::from_iter(
+            (0..PACKETS_PER_BATCH as u64 + 1).map(packet_with_priority),
+            PACKETS_PER_BATCH + 1,
+        );
+        let packet_batches =
+            unprocessed_packet_batches.take_packets_to_forward(usize::MAX, usize::MAX);
+        assert_eq!(
+            packet_batches
+                .iter()
+                .map(PacketBatch::len)
+                .collect::<Vec<_>>(),
+            vec![PACKETS_PER_BATCH, 1]
+        );
+    }
+
+    #[test]
+    fn test_packet_account_locks_read_locks_do_not_conflict() {
+        let payer = Keypair::new();
+        let tx = system_transaction::transfer(&payer, &Pubkey::new_unique(), 1, Hash::new_unique());
+        let packet = DeserializedPacket::new(Packet::from_data(None, &tx).unwrap()).unwrap();
+
+        // The system program is only read by the transfer
+        let mut locked_accounts = PacketAccountLocks::default();
+        locked_accounts
+            .read_locks
+            .insert(solana_sdk::system_program::id());
+        assert!(locked_accounts.can_lock(packet.immutable_section()));
+
+        locked_accounts.write_locks.insert(payer.pubkey());
+        assert!(!locked_accounts.can_lock(packet.immutable_section()));
+    }
+
+    #[test]
+    fn test_shared_unprocessed_packet_batches_pop_in_global_order() {
+        let num_shards = 4;
+        let shared_packet_batches = Arc::new(SharedUnprocessedPacketBatches::new(num_shards, 64));
+        assert_eq!(shared_packet_batches.num_shards(), num_shards);
+        assert!(shared_packet_batches.capacity() >= 64);
+        assert!(shared_packet_batches.pop_max_n(1).is_none());
+
+        // Insert from several threads at once
+        let handles: Vec<_> = (0..3)
+            .map(|thread_index| {
+                let shared_packet_batches = shared_packet_batches.clone();
+                std::thread::spawn(move || {
+                    let packets = (0..5).map(|i| packet_with_priority(thread_index * 5 + i));
+                    shared_packet_batches.insert_batch(packets)
+                })
+            })
+            .collect();
+        let num_dropped: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        assert_eq!(num_dropped, 0);
+        assert_eq!(shared_packet_batches.len(), 15);
+
+        let priorities: Vec<u64> = shared_packet_batches
+            .pop_max_n(10)
+            .unwrap()
+            .iter()
+            .map(|packet| packet.immutable_section().priority())
+            .collect();
+        assert_eq!(priorities, (5..15).rev().collect::<Vec<_>>());
+
+        shared_packet_batches.retain(|packet| packet.immutable_section().priority() % 2 == 0);
+        assert_eq!(shared_packet_batches.len(), 3);
+        shared_packet_batches.clear();
+        assert!(shared_packet_batches.is_empty());
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_pop_weighted_random_n() {
+        let mut rng = ChaChaRng::from_seed([7u8; 32]);
+        let low = packet_with_priority(1);
+        let high = packet_with_priority(3);
+        let vote = vote_packet_with_priority(0);
+
+        // packets are drawn in proportion to priority plus one, so the higher priority packet
+        // comes first about two thirds of the time
+        let num_trials = 1000;
+        let num_high_first = (0..num_trials)
+            .filter(|_| {
+                let mut unprocessed_packet_batches =
+                    UnprocessedPacketBatches::from_iter(vec![low.clone(), high.clone()], 2);
+                let packets = unprocessed_packet_batches
+                    .pop_weighted_random_n_with_rng(1, &mut rng)
+                    .unwrap();
+                assert_eq!(unprocessed_packet_batches.len(), 1);
+                assert_eq!(
+                    unprocessed_packet_batches.message_hash_to_transaction.len(),
+                    1
+                );
+                packets == vec![high.clone()]
+            })
+            .count();
+        assert!((550..800).contains(&num_high_first), "{}", num_high_first);
+
+        // votes are popped ahead of other transactions
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_vote_capacity(2, 1);
+        unprocessed_packet_batches.insert_batch(vec![low, high, vote.clone()].into_iter());
+        let packets = unprocessed_packet_batches
+            .pop_weighted_random_n_with_rng(2, &mut rng)
+            .unwrap();
+        assert_eq!(packets[0], vote);
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+        assert_eq!(
+            unprocessed_packet_batches
+                .pop_weighted_random_n(10)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(unprocessed_packet_batches
+            .pop_weighted_random_n(1)
+            .is_none());
+    }
+
+    #[test]
+    fn test_pop_policy_config() {
+        let pop_policy_config = PopPolicyConfig {
+            default_policy: PopPolicy::Greedy,
+            thread_policies: HashMap::from([(2, PopPolicy::WeightedRandom)]),
+        };
+        assert_eq!(pop_policy_config.policy_for(1), PopPolicy::Greedy);
+        assert_eq!(pop_policy_config.policy_for(2), PopPolicy::WeightedRandom);
+
+        assert_eq!("greedy".parse(), Ok(PopPolicy::Greedy));
+        assert_eq!("weighted-random".parse(), Ok(PopPolicy::WeightedRandom));
+        assert!("random".parse::<PopPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_merged_packet_view() {
+        let mut buffers: Vec<_> = (0..3)
+            .map(|_| UnprocessedPacketBatches::with_capacity(10))
+            .collect();
+        assert!(MergedPacketView::new(&buffers).is_empty());
+        for priority in 0..12 {
+            buffers[priority as usize % 3].push(packet_with_priority(priority));
+        }
+        // a buffer in dual-queue mode contributes its votes as well
+        let mut dual_queue_buffer = UnprocessedPacketBatches::with_vote_capacity(10, 10);
+        dual_queue_buffer.push(vote_packet_with_priority(20));
+        dual_queue_buffer.push(packet_with_priority(12));
+
+        let view = MergedPacketView::new(buffers.iter().chain([&dual_queue_buffer]));
+        assert_eq!(view.len(), 14);
+        let priorities: Vec<_> = view.iter().map(|packet| packet.priority()).collect();
+        assert_eq!(
+            priorities,
+            std::iter::once(20).chain((0..13).rev()).collect::<Vec<_>>()
+        );
+        let priorities: Vec<_> = view
+            .peek_top_n(4)
+            .iter()
+            .map(|packet| packet.priority())
+            .collect();
+        assert_eq!(priorities, vec![20, 12, 11, 10]);
+        assert!(view.peek_top_n(0).is_empty());
+        assert_eq!(view.peek_top_n(100).len(), 14);
+
+        // packets are left in place
+        assert_eq!(buffers.iter().map(|buffer| buffer.len()).sum::<usize>(), 12);
+        assert_eq!(dual_queue_buffer.len(), 2);
+    }
+
+    #[test]
+    fn test_shared_unprocessed_packet_batches_peek_top_n() {
+        let shared_packet_batches = SharedUnprocessedPacketBatches::new(4, 64);
+        shared_packet_batches.insert_batch((0..10).map(packet_with_priority));
+        let priorities: Vec<_> = shared_packet_batches
+            .peek_top_n(3)
+            .iter()
+            .map(|packet| packet.priority())
+            .collect();
+        assert_eq!(priorities, vec![9, 8, 7]);
+        assert_eq!(shared_packet_batches.len(), 10);
+    }
+
+    #[test]
+    fn test_priority_aging_config_boost() {
+        let priority_aging_config = PriorityAgingConfig {
+            interval: Duration::from_millis(100),
+            boost_per_interval: 10,
+            max_boost: 25,
+        };
+        assert_eq!(priority_aging_config.boost(Duration::from_millis(99)), 0);
+        assert_eq!(priority_aging_config.boost(Duration::from_millis(100)), 10);
+        assert_eq!(priority_aging_config.boost(Duration::from_millis(250)), 20);
+        assert_eq!(priority_aging_config.boost(Duration::from_secs(10)), 25);
+        assert_eq!(
+            PriorityAgingConfig::default().boost(Duration::from_secs(10)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_age_priorities() {
+        let old_packet = packet_with_priority(1);
+        let new_packet = packet_with_priority(5);
+
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
+        unprocessed_packet_batches.set_priority_aging_config(PriorityAgingConfig {
+            interval: Duration::from_millis(10),
+            boost_per_interval: 10,
+            max_boost: 10,
+        });
+        unprocessed_packet_batches.push(old_packet.clone());
+        std::thread::sleep(Duration::from_millis(20));
+        unprocessed_packet_batches.push(new_packet.clone());
+
+        // Without aging the higher priority packet comes first
+        assert_eq!(
+            unprocessed_packet_batches
+                .packet_priority_queue
+                .peek_max()
+                .unwrap()
+                .message_hash(),
+            new_packet.immutable_section().message_hash()
+        );
+
+        // The older packet has been boosted past the newer one
+        assert!(unprocessed_packet_batches.age_priorities());
+        assert_eq!(unprocessed_packet_batches.pop_max().unwrap(), old_packet);
+        assert_eq!(unprocessed_packet_batches.pop_max().unwrap(), new_packet);
+
+        // Aging runs at most once per interval
+        assert!(!unprocessed_packet_batches.age_priorities());
+    }
+
+    #[test]
+    fn test_fuzz_regression_inputs() {
+        let regressions_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fuzz")
+            .join("regressions")
+            .join("deserialized_packet");
+        let mut num_inputs = 0;
+        for entry in std::fs::read_dir(regressions_dir).unwrap() {
+            let data = std::fs::read(entry.unwrap().path()).unwrap();
+            let size = data.len().min(PACKET_DATA_SIZE);
+            let mut packet = Packet::default();
+            packet.buffer_mut()[..size].copy_from_slice(&data[..size]);
+            packet.meta.size = size;
+            check_packet_deserialization(packet);
+            num_inputs += 1;
+        }
+        assert!(num_inputs > 0);
+    }
+
+    fn packet_with_compute_unit_limit(
+        compute_unit_limit: u32,
+        compute_unit_price: u64,
+    ) -> DeserializedPacket {
+        let payer = Keypair::new();
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit),
+                ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+            ],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        DeserializedPacket::new(Packet::from_data(None, &tx).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_priority_quantiles() {
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(20);
+        assert_eq!(unprocessed_packet_batches.priority_quantiles(), None);
+
+        unprocessed_packet_batches
+            .insert_batch((1..=10).map(|price| packet_with_compute_unit_limit(200_000, price)));
+        // simple votes don't bid for block space
+        unprocessed_packet_batches.push(vote_packet_with_priority(100));
+        assert_eq!(
+            unprocessed_packet_batches.priority_quantiles(),
+            Some(PriorityQuantiles {
+                num_packets: 10,
+                min: 1,
+                median: 5,
+                p90: 9,
+            })
+        );
+
+        // the vote and the eight highest bids
+        unprocessed_packet_batches.pop_max_n(9);
+        assert_eq!(
+            unprocessed_packet_batches.priority_quantiles(),
+            Some(PriorityQuantiles {
+                num_packets: 2,
+                min: 1,
+                median: 1,
+                p90: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_priority_mode() {
+        // requests the maximum compute units at a modest price
+        let large = packet_with_compute_unit_limit(1_400_000, 10);
+        // requests few compute units at a lower price, paying more per compute unit overall
+        let small = packet_with_compute_unit_limit(1_000, 5);
+        assert!(large.immutable_section().priority() > small.immutable_section().priority());
+        assert!(
+            large.immutable_section().fee_per_compute_unit()
+                < small.immutable_section().fee_per_compute_unit()
+        );
+        assert_eq!(
+            get_priority_with_mode(
+                small
+                    .immutable_section()
+                    .transaction()
+                    .unwrap()
+                    .get_message(),
+                PriorityMode::FeePerComputeUnit
+            ),
+            Some(small.immutable_section().fee_per_compute_unit())
+        );
+
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
+        unprocessed_packet_batches.insert_batch([large.clone(), small.clone()].into_iter());
+        assert_eq!(unprocessed_packet_batches.pop_max(), Some(large.clone()));
+
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2)
+            .with_priority_mode(PriorityMode::FeePerComputeUnit);
+        unprocessed_packet_batches.insert_batch([large.clone(), small.clone()].into_iter());
+        assert_eq!(
+            unprocessed_packet_batches.peek_max().unwrap().priority(),
+            small.immutable_section().fee_per_compute_unit()
+        );
+        assert_eq!(unprocessed_packet_batches.pop_max(), Some(small));
+        assert_eq!(unprocessed_packet_batches.pop_max(), Some(large));
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_pop_until_compute_limit() {
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::from_iter(
+            [
+                packet_with_compute_unit_limit(300, 4),
+                packet_with_compute_unit_limit(500, 3),
+                packet_with_compute_unit_limit(100, 2),
+                packet_with_compute_unit_limit(100, 1),
+            ],
+            10,
+        );
+        let compute_unit_limits = |packets: Vec<DeserializedPacket>| -> Vec<u64> {
+            packets
+                .iter()
+                .map(|packet| packet.immutable_section().compute_unit_limit())
+                .collect()
+        };
+
+        // Stops at the first packet that doesn't fit, even if later ones would
+        assert_eq!(
+            compute_unit_limits(
+                unprocessed_packet_batches
+                    .pop_until_compute_limit(700)
+                    .unwrap()
+            ),
+            vec![300]
+        );
+        assert!(unprocessed_packet_batches
+            .pop_until_compute_limit(499)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            compute_unit_limits(
+                unprocessed_packet_batches
+                    .pop_until_compute_limit(u64::MAX)
+                    .unwrap()
+            ),
+            vec![500, 100, 100]
+        );
+        assert!(unprocessed_packet_batches
+            .pop_until_compute_limit(u64::MAX)
+            .is_none());
+    }
+
+    #[test]
+    fn test_deserialized_packet_compute_budget_details() {
+        let packet = packet_with_compute_unit_limit(200_000, 1_500_000);
+        let immutable_section = packet.immutable_section();
+        assert_eq!(immutable_section.compute_unit_limit(), 200_000);
+        assert_eq!(immutable_section.compute_unit_price(), 1_500_000);
+        assert_eq!(immutable_section.priority(), 1_500_000);
+        // one signature plus 200_000 units at 1.5 lamports per unit
+        assert_eq!(
+            immutable_section.estimated_fee(),
+            ESTIMATED_LAMPORTS_PER_SIGNATURE + 300_000
+        );
+
+        let packet = packet_with_priority(7);
+        let immutable_section = packet.immutable_section();
+        assert_eq!(immutable_section.priority(), 7);
+        assert_eq!(immutable_section.compute_unit_price(), 0);
+        assert_eq!(
+            immutable_section.estimated_fee(),
+            ESTIMATED_LAMPORTS_PER_SIGNATURE
+        );
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_recompute_priorities() {
+        // Priorities assigned on deserialization are replaced by the ones computed from the
+        // compute budget instructions
+        let overridden_packet = packet_with_priority(7);
+        let priced_packet = packet_with_compute_unit_limit(200_000, 3);
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10);
+        unprocessed_packet_batches.push(overridden_packet.clone());
+        unprocessed_packet_batches.push(priced_packet.clone());
+        assert_eq!(
+            unprocessed_packet_batches
+                .peek_max()
+                .unwrap()
+                .immutable_section()
+                .message_hash(),
+            overridden_packet.immutable_section().message_hash()
+        );
+
+        assert_eq!(
+            unprocessed_packet_batches.recompute_priorities(&ComputeBudget::default()),
+            0
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert_eq!(unprocessed_packet_batches.pop_max().unwrap(), priced_packet);
+        let popped_packet = unprocessed_packet_batches.pop_max().unwrap();
+        assert_eq!(
+            popped_packet.immutable_section().message_hash(),
+            overridden_packet.immutable_section().message_hash()
+        );
+        assert_eq!(popped_packet.immutable_section().priority(), 0);
+    }
+
+    #[test]
+    fn test_deserialized_packet_simple_vote_compute_budget_details() {
+        let vote_keypair = Keypair::new();
+        let tx = solana_vote_program::vote_transaction::new_vote_transaction(
+            vec![1],
+            Hash::new_unique(),
+            Hash::new_unique(),
+            &Keypair::new(),
+            &vote_keypair,
+            &vote_keypair,
+            None,
+        );
+        let mut packet = Packet::from_data(None, &tx).unwrap();
+        let full_compute_budget_details =
+            get_packet_compute_budget_details(&PacketView::new(packet.data()).unwrap()).unwrap();
+        packet.meta.flags |= PacketFlags::SIMPLE_VOTE_TX;
+        let immutable_section = DeserializedPacket::new(packet).unwrap().immutable_section;
+        assert!(immutable_section.is_simple_vote());
+        assert_eq!(immutable_section.priority(), SIMPLE_VOTE_PRIORITY);
+        assert_eq!(
+            full_compute_budget_details,
+            ComputeBudgetDetails {
+                priority: immutable_section.priority(),
+                compute_unit_limit: immutable_section.compute_unit_limit(),
+                compute_unit_price: immutable_section.compute_unit_price(),
+                estimated_fee: immutable_section.estimated_fee(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialized_packet_flagged_simple_vote_with_compute_budget() {
+        let vote_keypair = Keypair::new();
+        let vote_instruction = solana_vote_program::vote_instruction::vote(
+            &vote_keypair.pubkey(),
+            &vote_keypair.pubkey(),
+            solana_vote_program::vote_state::Vote::new(vec![1], Hash::new_unique()),
+        );
+        let new_flagged_packet = |compute_budget_instruction| {
+            let tx = Transaction::new_signed_with_payer(
+                &[compute_budget_instruction, vote_instruction.clone()],
+                Some(&vote_keypair.pubkey()),
+                &[&vote_keypair],
+                Hash::new_unique(),
+            );
+            let mut packet = Packet::from_data(None, &tx).unwrap();
+            packet.meta.flags |= PacketFlags::SIMPLE_VOTE_TX;
+            packet
+        };
+
+        // A compute unit price is not skipped by the simple vote fast path
+        let packet = new_flagged_packet(ComputeBudgetInstruction::set_compute_unit_price(10));
+        check_packet_deserialization(packet.clone());
+        let immutable_section = DeserializedPacket::new(packet).unwrap().immutable_section;
+        assert!(immutable_section.is_simple_vote());
+        assert_eq!(immutable_section.priority(), 10);
+
+        // Neither is an invalid compute budget instruction
+        let packet = new_flagged_packet(Instruction::new_with_bytes(
+            compute_budget::id(),
+            &[u8::MAX],
+            vec![],
+        ));
+        check_packet_deserialization(packet.clone());
+        assert!(matches!(
+            DeserializedPacket::new(packet),
+            Err(DeserializedPacketError::PrioritizationFailure)
+        ));
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_packet_filter() {
+        let denied_program = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let tx = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(denied_program, &[], vec![])],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        let denied_packet = DeserializedPacket::new(Packet::from_data(None, &tx).unwrap()).unwrap();
+        let allowed_packet = packet_with_priority(1);
+
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10);
+        unprocessed_packet_batches.set_packet_filter(Some(Arc::new(AccountDenylist::new(
+            HashSet::from([denied_program]),
+        ))));
+        assert_eq!(
+            unprocessed_packet_batches
+                .insert_batch(vec![denied_packet, allowed_packet.clone()].into_iter()),
+            0
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+        assert_eq!(unprocessed_packet_batches.pop_max(), Some(allowed_packet));
+        assert_eq!(unprocessed_packet_batches.take_filtered_packets_count(), 1);
+        assert_eq!(unprocessed_packet_batches.take_filtered_packets_count(), 0);
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_drop_notifier() {
+        #[derive(Default)]
+        struct RecordingNotifier(Mutex<Vec<(Signature, TransactionDropReason)>>);
+
+        impl PacketDropNotifier for RecordingNotifier {
+            fn notify_dropped(
+                &self,
+                packet: &ImmutableDeserializedPacket,
+                reason: TransactionDropReason,
+            ) {
+                let signature = packet.packet_view().signature();
+                self.0.lock().unwrap().push((signature, reason));
+            }
         }
-    }
 
-    pub fn capacity(&self) -> usize {
-        self.packet_priority_queue.capacity()
-    }
-}
+        let packets: Vec<_> = (1..=3).map(packet_with_priority).collect();
+        let signature = |index: usize| packets[index].immutable_section().packet_view().signature();
+        let notifier = Arc::new(RecordingNotifier::default());
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(1);
+        unprocessed_packet_batches.set_drop_notifier(Some(notifier.clone()));
 
-pub fn deserialize_packets<'a>(
-    packet_batch: &'a PacketBatch,
-    packet_indexes: &'a [usize],
-) -> impl Iterator<Item = DeserializedPacket> + 'a {
-    packet_indexes.iter().filter_map(move |packet_index| {
-        DeserializedPacket::new(packet_batch[*packet_index].clone()).ok()
-    })
-}
+        // Packets evicted by higher priority ones are reported, as is a packet that doesn't
+        // prioritize over the buffered one
+        assert_eq!(
+            unprocessed_packet_batches.insert_batch(
+                vec![packets[1].clone(), packets[2].clone(), packets[0].clone()].into_iter()
+            ),
+            2
+        );
+        assert_eq!(
+            *notifier.0.lock().unwrap(),
+            vec![
+                (signature(1), TransactionDropReason::Evicted),
+                (signature(0), TransactionDropReason::Evicted),
+            ]
+        );
 
-/// Read the transaction message from packet data
-pub fn packet_message(packet: &Packet) -> Result<&[u8], DeserializedPacketError> {
-    let (sig_len, sig_size) =
-        decode_shortu16_len(packet.data()).map_err(DeserializedPacketError::ShortVecError)?;
-    sig_len
-        .checked_mul(size_of::<Signature>())
-        .and_then(|v| v.checked_add(sig_size))
-        .and_then(|msg_start| packet.data().get(msg_start..))
-        .ok_or(DeserializedPacketError::SignatureOverflowed(sig_size))
-}
+        unprocessed_packet_batches.notify_dropped(
+            packets[2].immutable_section(),
+            TransactionDropReason::BlockhashExpired,
+        );
+        assert_eq!(
+            notifier.0.lock().unwrap().last(),
+            Some(&(signature(2), TransactionDropReason::BlockhashExpired))
+        );
+    }
 
-fn get_priority(message: &SanitizedVersionedMessage) -> Option<u64> {
-    let mut compute_budget = ComputeBudget::default();
-    let prioritization_fee_details = compute_budget
-        .process_instructions(
-            message.program_instructions_iter(),
-            true, // don't reject txs that use request heap size ix
-            true, // use default units per instruction
-            true, // don't reject txs that use set compute unit price ix
-        )
-        .ok()?;
-    Some(prioritization_fee_details.get_priority())
-}
+    #[test]
+    fn test_unprocessed_packet_batches_retain_valid_nonces() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(10_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let nonce_authority = Keypair::new();
+        let nonce_pubkey = Pubkey::new_unique();
+        let durable_nonce = Hash::new_unique();
+        let nonce_state = nonce::state::Versions::new_current(nonce::State::Initialized(
+            nonce::state::Data::new(nonce_authority.pubkey(), durable_nonce, 5000),
+        ));
+        bank.store_account(
+            &nonce_pubkey,
+            &AccountSharedData::new_data(1_000, &nonce_state, &system_program::id()).unwrap(),
+        );
+        let nonced_packet = |nonce_hash| {
+            let tx = system_transaction::nonced_transfer(
+                &Keypair::new(),
+                &Pubkey::new_unique(),
+                1,
+                &nonce_pubkey,
+                &nonce_authority,
+                nonce_hash,
+            );
+            DeserializedPacket::new(Packet::from_data(None, &tx).unwrap()).unwrap()
+        };
+        let valid_packet = nonced_packet(durable_nonce);
+        let stale_packet = nonced_packet(Hash::new_unique());
+        let recent_packet = nonced_packet(bank.last_blockhash());
+        let regular_packet = packet_with_priority(1);
+        assert_eq!(
+            stale_packet.immutable_section().durable_nonce_account(),
+            Some(nonce_pubkey)
+        );
+        assert_eq!(
+            regular_packet.immutable_section().durable_nonce_account(),
+            None
+        );
+        let packets = vec![
+            valid_packet,
+            stale_packet.clone(),
+            recent_packet,
+            regular_packet,
+        ];
 
-pub fn transactions_to_deserialized_packets(
-    transactions: &[Transaction],
-) -> Result<Vec<DeserializedPacket>, DeserializedPacketError> {
-    transactions
-        .iter()
-        .map(|transaction| {
-            let packet = Packet::from_data(None, transaction)?;
-            DeserializedPacket::new(packet)
-        })
-        .collect()
-}
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::from_iter(packets.clone(), 10);
+        assert_eq!(unprocessed_packet_batches.retain_valid_nonces(&bank), 0);
+        assert_eq!(unprocessed_packet_batches.len(), 4);
 
-#[cfg(test)]
-mod tests {
-    use {
-        super::*,
-        solana_sdk::{
-            compute_budget::ComputeBudgetInstruction, message::VersionedMessage, pubkey::Pubkey,
-            signature::Keypair, system_transaction,
-        },
-        std::net::IpAddr,
-    };
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::with_capacity(10).with_nonce_check();
+        unprocessed_packet_batches.insert_batch(packets.into_iter());
+        assert_eq!(unprocessed_packet_batches.retain_valid_nonces(&bank), 1);
+        assert_eq!(unprocessed_packet_batches.len(), 3);
+        assert!(!unprocessed_packet_batches
+            .message_hash_to_transaction
+            .contains_key(stale_packet.immutable_section().message_hash()));
+        assert_eq!(unprocessed_packet_batches.retain_valid_nonces(&bank), 0);
+    }
 
-    fn packet_with_sender_stake(sender_stake: u64, ip: Option<IpAddr>) -> DeserializedPacket {
-        let tx = system_transaction::transfer(
-            &Keypair::new(),
-            &solana_sdk::pubkey::new_rand(),
-            1,
+    fn packet_with_sequence_hint(
+        payer: &Keypair,
+        stream: u64,
+        sequence: u64,
+    ) -> DeserializedPacket {
+        transfer_packet_with_sequence_hint(payer, &Pubkey::new_unique(), stream, sequence)
+    }
+
+    fn transfer_packet_with_sequence_hint(
+        payer: &Keypair,
+        to: &Pubkey,
+        stream: u64,
+        sequence: u64,
+    ) -> DeserializedPacket {
+        let memo = format!("{}{}:{}", SEQUENCE_HINT_MEMO_PREFIX, stream, sequence);
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::transfer(&payer.pubkey(), to, 1),
+                Instruction::new_with_bytes(spl_memo_id_v3(), memo.as_bytes(), vec![]),
+            ],
+            Some(&payer.pubkey()),
+            &[payer],
             Hash::new_unique(),
         );
-        let mut packet = Packet::from_data(None, &tx).unwrap();
-        packet.meta.sender_stake = sender_stake;
-        if let Some(ip) = ip {
-            packet.meta.addr = ip;
-        }
-        DeserializedPacket::new(packet).unwrap()
+        DeserializedPacket::new(Packet::from_data(None, &tx).unwrap()).unwrap()
     }
 
-    fn packet_with_priority(priority: u64) -> DeserializedPacket {
-        let tx = system_transaction::transfer(
-            &Keypair::new(),
-            &solana_sdk::pubkey::new_rand(),
-            1,
+    #[test]
+    fn test_deserialized_packet_lazy_transaction() {
+        let keypair = Keypair::new();
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(3),
+                system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1),
+            ],
+            Some(&keypair.pubkey()),
+            &[&keypair],
             Hash::new_unique(),
         );
-        let packet = Packet::from_data(None, &tx).unwrap();
-        DeserializedPacket::new_with_priority(packet, priority).unwrap()
+        let packet = DeserializedPacket::new(Packet::from_data(None, &tx).unwrap()).unwrap();
+        let immutable_section = packet.immutable_section();
+        assert!(immutable_section.transaction.get().is_none());
+        assert_eq!(immutable_section.fee_payer(), &keypair.pubkey());
+        assert_eq!(
+            immutable_section.recent_blockhash(),
+            &tx.message.recent_blockhash
+        );
+        assert_eq!(immutable_section.message_hash(), &tx.message.hash());
+        assert_eq!(immutable_section.compute_unit_price(), 3);
+        assert!(immutable_section.transaction.get().is_none());
+
+        let transaction = immutable_section.transaction().unwrap();
+        assert_eq!(
+            transaction,
+            &SanitizedVersionedTransaction::try_from(VersionedTransaction::from(tx)).unwrap()
+        );
+        assert_eq!(
+            get_compute_budget_details(transaction.get_message()),
+            Some(ComputeBudgetDetails {
+                priority: immutable_section.priority(),
+                compute_unit_limit: immutable_section.compute_unit_limit(),
+                compute_unit_price: immutable_section.compute_unit_price(),
+                estimated_fee: immutable_section.estimated_fee(),
+            })
+        );
+        assert!(immutable_section.transaction.get().is_some());
     }
 
     #[test]
-    fn test_unprocessed_packet_batches_insert_pop_same_packet() {
-        let packet = packet_with_sender_stake(1, None);
-        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
-        unprocessed_packet_batches.push(packet.clone());
-        unprocessed_packet_batches.push(packet.clone());
+    fn test_get_sequence_hint() {
+        let payer = Keypair::new();
+        let packet = packet_with_sequence_hint(&payer, 7, 42);
+        assert_eq!(
+            get_sequence_hint(
+                packet
+                    .immutable_section()
+                    .transaction()
+                    .unwrap()
+                    .get_message()
+            ),
+            Some(SequenceHint {
+                stream: 7,
+                sequence: 42
+            })
+        );
+        assert_eq!(
+            packet.immutable_section().sequence_hint(),
+            Some(SequenceHint {
+                stream: 7,
+                sequence: 42
+            })
+        );
 
-        // There was only one unique packet, so that one should be the
-        // only packet returned
+        let packet = packet_with_priority(1);
         assert_eq!(
-            unprocessed_packet_batches.pop_max_n(2).unwrap(),
-            vec![packet]
+            get_sequence_hint(
+                packet
+                    .immutable_section()
+                    .transaction()
+                    .unwrap()
+                    .get_message()
+            ),
+            None
+        );
+
+        // a memo that isn't a well formed hint is ignored
+        let tx = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                spl_memo_id_v1(),
+                b"seq:7",
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        let packet = DeserializedPacket::new(Packet::from_data(None, &tx).unwrap()).unwrap();
+        assert_eq!(
+            get_sequence_hint(
+                packet
+                    .immutable_section()
+                    .transaction()
+                    .unwrap()
+                    .get_message()
+            ),
+            None
         );
     }
 
     #[test]
-    fn test_unprocessed_packet_batches_insert_minimum_packet_over_capacity() {
-        let heavier_packet_weight = 2;
-        let heavier_packet = packet_with_priority(heavier_packet_weight);
+    fn test_unprocessed_packet_batches_sequence_hints() {
+        let payer = Keypair::new();
+        let mut unprocessed_packet_batches =
+            UnprocessedPacketBatches::with_capacity(10).with_sequence_hints();
 
-        let lesser_packet_weight = heavier_packet_weight - 1;
-        let lesser_packet = packet_with_priority(lesser_packet_weight);
+        let first = packet_with_sequence_hint(&payer, 0, 1);
+        let other_stream = packet_with_sequence_hint(&payer, 1, 1);
+        let other_payer = packet_with_sequence_hint(&Keypair::new(), 0, 1);
+        unprocessed_packet_batches
+            .insert_batch(vec![first, other_stream.clone(), other_payer.clone()].into_iter());
+        assert_eq!(unprocessed_packet_batches.len(), 3);
+        assert_eq!(
+            unprocessed_packet_batches.take_superseded_packets_count(),
+            0
+        );
 
-        // Test that the heavier packet is actually heavier
-        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(2);
-        unprocessed_packet_batches.push(heavier_packet.clone());
-        unprocessed_packet_batches.push(lesser_packet.clone());
+        // a higher sequence replaces the buffered packet
+        let second = packet_with_sequence_hint(&payer, 0, 2);
+        assert_eq!(unprocessed_packet_batches.push(second.clone()), None);
+        assert_eq!(unprocessed_packet_batches.len(), 3);
+        // while a stale one is dropped
+        let stale = packet_with_sequence_hint(&payer, 0, 1);
+        assert_eq!(unprocessed_packet_batches.push(stale), None);
+        assert_eq!(unprocessed_packet_batches.len(), 3);
         assert_eq!(
-            unprocessed_packet_batches.pop_max().unwrap(),
-            heavier_packet
+            unprocessed_packet_batches.take_superseded_packets_count(),
+            2
+        );
+        assert_eq!(
+            unprocessed_packet_batches.message_hash_to_transaction.len(),
+            unprocessed_packet_batches.len()
         );
 
-        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(1);
-        unprocessed_packet_batches.push(heavier_packet);
+        let mut packets = unprocessed_packet_batches.pop_max_n(3).unwrap();
+        let mut expected = vec![second, other_stream, other_payer];
+        packets.sort_by_key(|packet| *packet.immutable_section().message_hash());
+        expected.sort_by_key(|packet| *packet.immutable_section().message_hash());
+        assert_eq!(packets, expected);
 
-        // Buffer is now at capacity, pushing the smaller weighted
-        // packet should immediately pop it
+        // once the latest packet has left the buffer, any sequence is accepted again
+        let stale = packet_with_sequence_hint(&payer, 0, 1);
+        assert_eq!(unprocessed_packet_batches.push(stale), None);
+        assert_eq!(unprocessed_packet_batches.len(), 1);
         assert_eq!(
-            unprocessed_packet_batches
-                .push(lesser_packet.clone())
-                .unwrap(),
-            lesser_packet
+            unprocessed_packet_batches.take_superseded_packets_count(),
+            0
         );
     }
 
     #[test]
-    fn test_unprocessed_packet_batches_pop_max_n() {
-        let num_packets = 10;
-        let packets_iter =
-            std::iter::repeat_with(|| packet_with_sender_stake(1, None)).take(num_packets);
-        let mut unprocessed_packet_batches =
-            UnprocessedPacketBatches::from_iter(packets_iter.clone(), num_packets);
+    fn test_unprocessed_packet_batches_rejected_packet_supersedes_nothing() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10)
+            .with_sequence_hints()
+            .with_max_packets_per_lock_set(1);
+        let first = packet_with_sequence_hint(&payer, 0, 1);
+        let tx = system_transaction::transfer(&payer, &to, 1, Hash::new_unique());
+        let higher =
+            DeserializedPacket::new_with_priority(Packet::from_data(None, &tx).unwrap(), 5)
+                .unwrap();
+        assert_eq!(unprocessed_packet_batches.push(first.clone()), None);
+        assert_eq!(unprocessed_packet_batches.push(higher), None);
 
-        // Test with small step size
-        let step_size = 1;
-        for _ in 0..num_packets {
-            assert_eq!(
-                unprocessed_packet_batches
-                    .pop_max_n(step_size)
+        // a higher sequence in a lock set of higher priority packets is dropped, and the
+        // packet it would have superseded stays
+        let second = transfer_packet_with_sequence_hint(&payer, &to, 0, 2);
+        assert_eq!(unprocessed_packet_batches.push(second), None);
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert!(unprocessed_packet_batches
+            .message_hash_to_transaction
+            .contains_key(first.immutable_section().message_hash()));
+        assert_eq!(
+            unprocessed_packet_batches.take_superseded_packets_count(),
+            0
+        );
+        assert_eq!(unprocessed_packet_batches.take_coalesced_packets_count(), 1);
+
+        // so it still supersedes a later sequence of its stream
+        let third = packet_with_sequence_hint(&payer, 0, 3);
+        assert_eq!(unprocessed_packet_batches.push(third.clone()), None);
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert!(unprocessed_packet_batches
+            .message_hash_to_transaction
+            .contains_key(third.immutable_section().message_hash()));
+        assert_eq!(
+            unprocessed_packet_batches.take_superseded_packets_count(),
+            1
+        );
+    }
+
+    fn fee_bump_packet(
+        payer: &Keypair,
+        to: &Pubkey,
+        recent_blockhash: Hash,
+        compute_unit_price: u64,
+    ) -> DeserializedPacket {
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                system_instruction::transfer(&payer.pubkey(), to, 1),
+            ],
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        DeserializedPacket::new(Packet::from_data(None, &tx).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_replacement_key() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let recent_blockhash = Hash::new_unique();
+        let key = |packet: &DeserializedPacket| {
+            ReplacementKey::new(
+                packet
+                    .immutable_section()
+                    .transaction()
                     .unwrap()
-                    .len(),
-                step_size
-            );
-        }
+                    .get_message(),
+            )
+        };
 
-        assert!(unprocessed_packet_batches.is_empty());
-        assert!(unprocessed_packet_batches.pop_max_n(0).is_none());
-        assert!(unprocessed_packet_batches.pop_max_n(1).is_none());
+        let packet = fee_bump_packet(&payer, &to, recent_blockhash, 1);
+        let bumped = fee_bump_packet(&payer, &to, recent_blockhash, 2);
+        assert_ne!(
+            packet.immutable_section().message_hash(),
+            bumped.immutable_section().message_hash()
+        );
+        assert_eq!(key(&packet), key(&bumped));
+        assert_eq!(key(&packet).fee_payer, payer.pubkey());
+        assert_eq!(key(&packet).recent_blockhash, recent_blockhash);
 
-        // Test with step size larger than `num_packets`
-        let step_size = num_packets + 1;
-        let mut unprocessed_packet_batches =
-            UnprocessedPacketBatches::from_iter(packets_iter.clone(), num_packets);
+        // any other change to the message yields another key
+        let other_recipient = fee_bump_packet(&payer, &Pubkey::new_unique(), recent_blockhash, 2);
+        assert_ne!(key(&packet), key(&other_recipient));
+        let other_blockhash = fee_bump_packet(&payer, &to, Hash::new_unique(), 2);
+        assert_ne!(key(&packet), key(&other_blockhash));
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_push_with_replacement() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let recent_blockhash = Hash::new_unique();
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10);
+
+        let packet = fee_bump_packet(&payer, &to, recent_blockhash, 10);
+        let unrelated = fee_bump_packet(&Keypair::new(), &to, recent_blockhash, 10);
         assert_eq!(
-            unprocessed_packet_batches
-                .pop_max_n(step_size)
-                .unwrap()
-                .len(),
-            num_packets
+            unprocessed_packet_batches.push_with_replacement(packet.clone()),
+            None
+        );
+        assert_eq!(
+            unprocessed_packet_batches.push_with_replacement(unrelated.clone()),
+            None
         );
-        assert!(unprocessed_packet_batches.is_empty());
-        assert!(unprocessed_packet_batches.pop_max_n(0).is_none());
 
-        // Test with step size equal to `num_packets`
-        let step_size = num_packets;
-        let mut unprocessed_packet_batches =
-            UnprocessedPacketBatches::from_iter(packets_iter, num_packets);
+        // a lower or equal price doesn't replace the buffered packet
+        let underpriced = fee_bump_packet(&payer, &to, recent_blockhash, 10);
         assert_eq!(
-            unprocessed_packet_batches
-                .pop_max_n(step_size)
-                .unwrap()
-                .len(),
-            step_size
+            unprocessed_packet_batches.push_with_replacement(underpriced.clone()),
+            Some(underpriced)
         );
-        assert!(unprocessed_packet_batches.is_empty());
-        assert!(unprocessed_packet_batches.pop_max_n(0).is_none());
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert_eq!(unprocessed_packet_batches.take_replaced_packets_count(), 0);
+
+        // a higher price does
+        let bumped = fee_bump_packet(&payer, &to, recent_blockhash, 20);
+        assert_eq!(
+            unprocessed_packet_batches.push_with_replacement(bumped.clone()),
+            Some(packet)
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+        assert_eq!(unprocessed_packet_batches.take_replaced_packets_count(), 1);
+        assert_eq!(
+            unprocessed_packet_batches.message_hash_to_transaction.len(),
+            unprocessed_packet_batches.len()
+        );
+
+        // packets pushed without replacement are never replaced
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(10);
+        let packet = fee_bump_packet(&payer, &to, recent_blockhash, 10);
+        assert_eq!(unprocessed_packet_batches.push(packet), None);
+        assert_eq!(
+            unprocessed_packet_batches.push_with_replacement(bumped.clone()),
+            None
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 2);
+
+        let packets = unprocessed_packet_batches.pop_max_n(1).unwrap();
+        assert_eq!(packets, vec![bumped]);
+    }
+
+    #[test]
+    fn test_unprocessed_packet_batches_rejected_replacement() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let recent_blockhash = Hash::new_unique();
+        let mut unprocessed_packet_batches = UnprocessedPacketBatches::with_capacity(1);
+        let packet = fee_bump_packet(&payer, &to, recent_blockhash, 10);
+        assert_eq!(
+            unprocessed_packet_batches.push_with_replacement(packet.clone()),
+            None
+        );
+
+        // a replacement over the lock limit is dropped, and the packet it would have replaced
+        // keeps its place
+        unprocessed_packet_batches.set_transaction_account_lock_limit(Some(3));
+        let bumped = fee_bump_packet(&payer, &to, recent_blockhash, 20);
+        assert_eq!(
+            unprocessed_packet_batches.push_with_replacement(bumped),
+            None
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+        assert_eq!(unprocessed_packet_batches.take_replaced_packets_count(), 0);
+        assert_eq!(
+            unprocessed_packet_batches.take_too_many_account_locks_packets_count(),
+            1
+        );
+
+        // while an admitted one takes its place within the capacity
+        unprocessed_packet_batches.set_transaction_account_lock_limit(None);
+        let bumped = fee_bump_packet(&payer, &to, recent_blockhash, 20);
+        assert_eq!(
+            unprocessed_packet_batches.push_with_replacement(bumped.clone()),
+            Some(packet)
+        );
+        assert_eq!(unprocessed_packet_batches.len(), 1);
+        assert_eq!(unprocessed_packet_batches.take_replaced_packets_count(), 1);
+        assert_eq!(
+            unprocessed_packet_batches.pop_max_n(1).unwrap(),
+            vec![bumped]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_packets_par() {
+        let mut packets: Vec<_> = (0..8)
+            .map(|_| {
+                let tx = system_transaction::transfer(
+                    &Keypair::new(),
+                    &solana_sdk::pubkey::new_rand(),
+                    1,
+                    Hash::new_unique(),
+                );
+                Packet::from_data(None, &tx).unwrap()
+            })
+            .collect();
+        // an undeserializable packet is skipped without disturbing the order of the rest
+        packets[3] = Packet::default();
+        let packet_batch = PacketBatch::new(packets);
+        let packet_indexes: Vec<_> = (0..packet_batch.len()).collect();
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+
+        let serial: Vec<_> = deserialize_packets(&packet_batch, &packet_indexes).collect();
+        assert_eq!(serial.len(), 7);
+        for parallel_threshold in [0, packet_indexes.len() + 1] {
+            let mut stats = PacketDeserializerStats::default();
+            let deserialized_packets = deserialize_packets_par(
+                &thread_pool,
+                &packet_batch,
+                &packet_indexes,
+                parallel_threshold,
+                &mut stats,
+            );
+            assert_eq!(deserialized_packets, serial);
+            assert_eq!(
+                stats,
+                PacketDeserializerStats {
+                    deserialization_errors: 1,
+                    ..PacketDeserializerStats::default()
+                }
+            );
+        }
     }
 
     #[test]
@@ -3,18 +3,23 @@
 pub use solana_perf::report_target_features;
 use {
     crate::{
-        accounts_hash_verifier::AccountsHashVerifier,
-        broadcast_stage::BroadcastStageType,
+        accounts_hash_verifier::{AccountsHashVerificationPolicy, AccountsHashVerifier},
+        banking_stage::BankingStageConfig,
+        broadcast_stage::{BroadcastConfig, BroadcastStageType},
         cache_block_meta_service::{CacheBlockMetaSender, CacheBlockMetaService},
         cluster_info_vote_listener::VoteTracker,
         completed_data_sets_service::CompletedDataSetsService,
         consensus::{reconcile_blockstore_roots_with_tower, Tower},
+        forward_pacer::ForwardPacingConfig,
+        ledger_cleanup_service::SlotPurgeListeners,
         ledger_metric_report_service::LedgerMetricReportService,
         poh_timing_report_service::PohTimingReportService,
         rewards_recorder_service::{RewardsRecorderSender, RewardsRecorderService},
         sample_performance_service::SamplePerformanceService,
         serve_repair::ServeRepair,
+        serve_repair_admission::{ServeRepairAdmission, ServeRepairAdmissionConfig},
         serve_repair_service::ServeRepairService,
+        shutdown_coordinator::{ShutdownConfig, ShutdownCoordinator},
         sigverify,
         snapshot_packager_service::SnapshotPackagerService,
         stats_reporter_service::StatsReporterService,
@@ -22,11 +27,16 @@ use {
         tower_storage::TowerStorage,
         tpu::{Tpu, TpuSockets, DEFAULT_TPU_COALESCE_MS},
         tvu::{Tvu, TvuConfig, TvuSockets},
+        unprocessed_packet_batches::{
+            AccountDenylist, PacketFilter, PopPolicyConfig, PriorityAgingConfig,
+        },
     },
     crossbeam_channel::{bounded, unbounded, Receiver},
     rand::{thread_rng, Rng},
-    solana_entry::poh::compute_hash_time_ns,
-    solana_geyser_plugin_manager::geyser_plugin_service::GeyserPluginService,
+    solana_entry::poh::{compute_concurrent_hash_time_ns, compute_hash_time_ns},
+    solana_geyser_plugin_manager::{
+        geyser_plugin_service::GeyserPluginService, ingest_plugin_manager::IngestPluginManager,
+    },
     solana_gossip::{
         cluster_info::{
             ClusterInfo, Node, DEFAULT_CONTACT_DEBUG_INTERVAL_MILLIS,
@@ -41,10 +51,15 @@ use {
         blockstore::{
             Blockstore, BlockstoreError, BlockstoreSignals, CompletedSlotsReceiver, PurgeType,
         },
-        blockstore_options::{BlockstoreOptions, BlockstoreRecoveryMode, LedgerColumnOptions},
+        blockstore_options::{
+            BlockstoreIoOptions, BlockstoreOptions, BlockstoreRecoveryMode, ColumnLayout,
+            LedgerColumnOptions, RocksDbTuningOptions,
+        },
         blockstore_processor::{self, TransactionStatusSender},
         leader_schedule::FixedSchedule,
         leader_schedule_cache::LeaderScheduleCache,
+        ledger_archive::LedgerArchiveConfig,
+        ledger_archive_service::LedgerArchiveService,
     },
     solana_measure::measure::Measure,
     solana_metrics::{datapoint_info, poh_timing_point::PohTimingSender},
@@ -53,6 +68,8 @@ use {
         poh_service::{self, PohService},
     },
     solana_rpc::{
+        buffered_prioritization_fees::BufferedPrioritizationFees,
+        ledger_storage_backend::open_ledger_archive,
         max_slots::MaxSlots,
         optimistically_confirmed_bank_tracker::{
             OptimisticallyConfirmedBank, OptimisticallyConfirmedBankTracker,
@@ -62,8 +79,10 @@ use {
         rpc_pubsub_service::{PubSubConfig, PubSubService},
         rpc_service::JsonRpcService,
         rpc_subscriptions::RpcSubscriptions,
+        stake_snapshot_service::StakeSnapshotService,
         transaction_notifier_interface::TransactionNotifierLock,
         transaction_status_service::TransactionStatusService,
+        vote_latency_service::{VoteLatencies, VoteLatencyService},
     },
     solana_runtime::{
         accounts_background_service::{
@@ -101,6 +120,7 @@ use {
     solana_vote_program::vote_state::VoteState,
     std::{
         collections::{HashMap, HashSet},
+        fmt,
         net::SocketAddr,
         path::{Path, PathBuf},
         sync::{
@@ -128,11 +148,18 @@ pub struct ValidatorConfig {
     pub account_shrink_paths: Option<Vec<PathBuf>>,
     pub rpc_config: JsonRpcConfig,
     pub geyser_plugin_config_files: Option<Vec<PathBuf>>,
+    /// Config files of the plugins screening the transaction packets received on the TPU
+    pub ingest_plugin_config_files: Option<Vec<PathBuf>>,
     pub rpc_addrs: Option<(SocketAddr, SocketAddr)>, // (JsonRpc, JsonRpcPubSub)
     pub pubsub_config: PubSubConfig,
     pub snapshot_config: Option<SnapshotConfig>,
     pub max_ledger_shreds: Option<u64>,
+    /// Bytes the blockstore columns may hold before the oldest rooted slots are purged
+    pub max_ledger_bytes: Option<u64>,
+    /// Whether the slot ranges purged from the blockstore are recorded as tombstones
+    pub ledger_purge_tombstones: bool,
     pub broadcast_stage_type: BroadcastStageType,
+    pub broadcast_config: BroadcastConfig,
     pub turbine_disabled: Option<Arc<AtomicBool>>,
     pub enforce_ulimit_nofile: bool,
     pub fixed_leader_schedule: Option<FixedSchedule>,
@@ -142,6 +169,8 @@ pub struct ValidatorConfig {
     pub repair_validators: Option<HashSet<Pubkey>>, // None = repair from all
     pub gossip_validators: Option<HashSet<Pubkey>>, // None = gossip with all
     pub halt_on_known_validators_accounts_hash_mismatch: bool,
+    /// Which known validators accounts hash mismatches halt the node, if it halts on them
+    pub accounts_hash_verification_policy: AccountsHashVerificationPolicy,
     pub accounts_hash_fault_injection_slots: u64, // 0 = no fault injection
     pub no_rocksdb_compaction: bool,
     pub rocksdb_compaction_interval: Option<u64>,
@@ -157,6 +186,9 @@ pub struct ValidatorConfig {
     pub contact_save_interval: u64,
     pub send_transaction_service_config: send_transaction_service::Config,
     pub no_poh_speed_test: bool,
+    /// Number of threads hashing at once in the multi-threaded variant of the PoH speed check,
+    /// which is skipped if this is 1 or less
+    pub poh_speed_test_threads: usize,
     pub no_os_memory_stats_reporting: bool,
     pub no_os_network_stats_reporting: bool,
     pub poh_pinned_cpu_core: usize,
@@ -173,7 +205,43 @@ pub struct ValidatorConfig {
     pub accounts_shrink_ratio: AccountShrinkThreshold,
     pub wait_to_vote_slot: Option<Slot>,
     pub ledger_column_options: LedgerColumnOptions,
+    pub rocksdb_tuning_options: Option<RocksDbTuningOptions>,
+    /// Whether the blockstore is read and written with direct I/O or mmap
+    pub rocksdb_io_options: BlockstoreIoOptions,
+    /// Number of the latest slots of the blockstore to check for corruption at startup
+    pub blockstore_verify_on_open: Option<u64>,
+    /// Directories outside of the ledger to store blockstore columns in
+    pub blockstore_column_layout: ColumnLayout,
+    /// Archive rooted slots to this location once they are old enough, then delete them from
+    /// the blockstore
+    pub ledger_archive_config: Option<LedgerArchiveConfig>,
     pub runtime_config: RuntimeConfig,
+    pub slot_purge_listeners: SlotPurgeListeners,
+    /// Transactions referencing any of these accounts or programs are dropped before the
+    /// banking stage buffers them
+    pub banking_account_denylist: Option<HashSet<Pubkey>>,
+    /// Transaction packets buffered by the banking stage per second from each source address
+    pub banking_source_packets_per_second: Option<u64>,
+    /// Transaction packets buffered by the banking stage for each fee payer and set of
+    /// writable accounts
+    pub banking_max_packets_per_lock_set: Option<usize>,
+    /// Notify QUIC senders of the transactions the banking stage drops for an expired
+    /// blockhash or to make room for higher priority ones
+    pub banking_notify_dropped_transactions: bool,
+    /// Save the transaction packets buffered by the banking stage to the ledger directory on
+    /// shutdown, and buffer them again on startup
+    pub banking_persist_buffered_packets: bool,
+    /// Pacing of the transactions forwarded to the upcoming leaders
+    pub banking_forward_pacing_config: ForwardPacingConfig,
+    /// Priority boost of the transactions that wait in the banking stage buffer
+    pub banking_priority_aging_config: PriorityAgingConfig,
+    /// How each banking thread picks the buffered transactions it executes next
+    pub banking_pop_policy_config: PopPolicyConfig,
+    /// Bandwidth budgets for serving repair requests, if they are throttled at all. Known
+    /// validators are never throttled.
+    pub serve_repair_admission_config: Option<ServeRepairAdmissionConfig>,
+    /// Deadlines for services to stop when the validator shuts down
+    pub shutdown_config: ShutdownConfig,
 }
 
 impl Default for ValidatorConfig {
@@ -185,14 +253,18 @@ impl Default for ValidatorConfig {
             expected_shred_version: None,
             voting_disabled: false,
             max_ledger_shreds: None,
+            max_ledger_bytes: None,
+            ledger_purge_tombstones: false,
             account_paths: Vec::new(),
             account_shrink_paths: None,
             rpc_config: JsonRpcConfig::default(),
             geyser_plugin_config_files: None,
+            ingest_plugin_config_files: None,
             rpc_addrs: None,
             pubsub_config: PubSubConfig::default(),
             snapshot_config: None,
             broadcast_stage_type: BroadcastStageType::Standard,
+            broadcast_config: BroadcastConfig::default(),
             turbine_disabled: None,
             enforce_ulimit_nofile: true,
             fixed_leader_schedule: None,
@@ -202,6 +274,7 @@ impl Default for ValidatorConfig {
             repair_validators: None,
             gossip_validators: None,
             halt_on_known_validators_accounts_hash_mismatch: false,
+            accounts_hash_verification_policy: AccountsHashVerificationPolicy::default(),
             accounts_hash_fault_injection_slots: 0,
             no_rocksdb_compaction: false,
             rocksdb_compaction_interval: None,
@@ -217,6 +290,7 @@ impl Default for ValidatorConfig {
             contact_save_interval: DEFAULT_CONTACT_SAVE_INTERVAL_MILLIS,
             send_transaction_service_config: send_transaction_service::Config::default(),
             no_poh_speed_test: true,
+            poh_speed_test_threads: 0,
             no_os_memory_stats_reporting: true,
             no_os_network_stats_reporting: true,
             poh_pinned_cpu_core: poh_service::DEFAULT_PINNED_CPU_CORE,
@@ -233,7 +307,23 @@ impl Default for ValidatorConfig {
             accounts_db_config: None,
             wait_to_vote_slot: None,
             ledger_column_options: LedgerColumnOptions::default(),
+            rocksdb_tuning_options: None,
+            rocksdb_io_options: BlockstoreIoOptions::default(),
+            blockstore_verify_on_open: None,
+            blockstore_column_layout: ColumnLayout::default(),
+            ledger_archive_config: None,
             runtime_config: RuntimeConfig::default(),
+            slot_purge_listeners: SlotPurgeListeners::default(),
+            banking_account_denylist: None,
+            banking_source_packets_per_second: None,
+            banking_max_packets_per_lock_set: None,
+            banking_notify_dropped_transactions: false,
+            banking_persist_buffered_packets: false,
+            banking_forward_pacing_config: ForwardPacingConfig::default(),
+            banking_priority_aging_config: PriorityAgingConfig::default(),
+            banking_pop_policy_config: PopPolicyConfig::default(),
+            serve_repair_admission_config: None,
+            shutdown_config: ShutdownConfig::default(),
         }
     }
 }
@@ -324,6 +414,8 @@ pub struct Validator {
     json_rpc_service: Option<JsonRpcService>,
     pubsub_service: Option<PubSubService>,
     rpc_completed_slots_service: JoinHandle<()>,
+    stake_snapshot_service: Option<JoinHandle<()>>,
+    vote_latency_service: Option<JoinHandle<()>>,
     optimistically_confirmed_bank_tracker: Option<OptimisticallyConfirmedBankTracker>,
     transaction_status_service: Option<TransactionStatusService>,
     rewards_recorder_service: Option<RewardsRecorderService>,
@@ -344,10 +436,13 @@ pub struct Validator {
     pub cluster_info: Arc<ClusterInfo>,
     pub bank_forks: Arc<RwLock<BankForks>>,
     pub blockstore: Arc<Blockstore>,
+    pub poh_speed_check_report: Option<PohSpeedCheckReport>,
     geyser_plugin_service: Option<GeyserPluginService>,
     ledger_metric_report_service: LedgerMetricReportService,
+    ledger_archive_service: Option<LedgerArchiveService>,
     accounts_background_service: AccountsBackgroundService,
     accounts_hash_verifier: AccountsHashVerifier,
+    shutdown_config: ShutdownConfig,
 }
 
 // in the distant future, get rid of ::new()/exit() and use Result properly...
@@ -411,6 +506,20 @@ impl Validator {
                 None
             };
 
+        let ingest_plugin_manager =
+            config
+                .ingest_plugin_config_files
+                .as_ref()
+                .map(|ingest_plugin_config_files| {
+                    match IngestPluginManager::new(ingest_plugin_config_files) {
+                        Ok(ingest_plugin_manager) => Arc::new(ingest_plugin_manager),
+                        Err(err) => {
+                            error!("Failed to load the ingest plugin: {:?}", err);
+                            abort();
+                        }
+                    }
+                });
+
         if config.voting_disabled {
             warn!("voting disabled");
             authorized_voter_keypairs.write().unwrap().clear();
@@ -475,6 +584,32 @@ impl Validator {
                 .register_exit(Box::new(move || exit.store(true, Ordering::Relaxed)));
         }
 
+        let stake_snapshot_service = config
+            .rpc_addrs
+            .and(config.rpc_config.stake_snapshot_dir.as_ref())
+            .map(|stake_snapshot_dir| {
+                let (bank_notification_sender, bank_notification_receiver) = unbounded();
+                bank_notification_senders.push(bank_notification_sender);
+                StakeSnapshotService::spawn(
+                    bank_notification_receiver,
+                    stake_snapshot_dir.clone(),
+                    exit.clone(),
+                )
+            });
+
+        let vote_latencies = Arc::new(VoteLatencies::default());
+        let vote_latency_service = (config.rpc_addrs.is_some()
+            && config.rpc_config.enable_rpc_vote_latencies)
+            .then(|| {
+                let (bank_notification_sender, bank_notification_receiver) = unbounded();
+                bank_notification_senders.push(bank_notification_sender);
+                VoteLatencyService::spawn(
+                    bank_notification_receiver,
+                    vote_latencies.clone(),
+                    exit.clone(),
+                )
+            });
+
         let accounts_update_notifier = geyser_plugin_service
             .as_ref()
             .and_then(|geyser_plugin_service| geyser_plugin_service.get_accounts_update_notifier());
@@ -487,6 +622,13 @@ impl Validator {
             .as_ref()
             .and_then(|geyser_plugin_service| geyser_plugin_service.get_block_metadata_notifier());
 
+        if let Some(slot_purge_notifier) = geyser_plugin_service
+            .as_ref()
+            .and_then(|geyser_plugin_service| geyser_plugin_service.get_slot_purge_notifier())
+        {
+            config.slot_purge_listeners.register(slot_purge_notifier);
+        }
+
         info!(
             "Geyser plugin: accounts_update_notifier: {} transaction_notifier: {}",
             accounts_update_notifier.is_some(),
@@ -523,6 +665,7 @@ impl Validator {
             blockstore_process_options,
             blockstore_root_scan,
             pruned_banks_receiver,
+            poh_speed_check_report,
         ) = load_blockstore(
             config,
             ledger_path,
@@ -629,8 +772,11 @@ impl Validator {
                 pending_snapshot_package,
                 &exit,
                 &cluster_info,
+                bank_forks.clone(),
                 config.known_validators.clone(),
-                config.halt_on_known_validators_accounts_hash_mismatch,
+                config
+                    .halt_on_known_validators_accounts_hash_mismatch
+                    .then(|| config.accounts_hash_verification_policy.clone()),
                 config.accounts_hash_fault_injection_slots,
                 config.snapshot_config.clone(),
             );
@@ -714,6 +860,7 @@ impl Validator {
         ));
 
         let max_slots = Arc::new(MaxSlots::default());
+        let buffered_prioritization_fees = Arc::new(BufferedPrioritizationFees::default());
         let (completed_data_sets_sender, completed_data_sets_receiver) =
             bounded(MAX_COMPLETED_DATA_SETS_IN_CHANNEL);
         let completed_data_sets_service = CompletedDataSetsService::new(
@@ -788,7 +935,9 @@ impl Validator {
                 config.send_transaction_service_config.clone(),
                 max_slots.clone(),
                 leader_schedule_cache.clone(),
-                max_complete_transaction_status_slot,
+                max_complete_transaction_status_slot.clone(),
+                buffered_prioritization_fees.clone(),
+                vote_latencies,
             )
             .unwrap_or_else(|s| {
                 error!("Failed to create JSON RPC Service: {}", s);
@@ -862,12 +1011,23 @@ impl Validator {
             &exit,
         );
         let serve_repair = Arc::new(RwLock::new(ServeRepair::new(cluster_info.clone())));
+        let serve_repair_admission = config.serve_repair_admission_config.clone().map(
+            |mut serve_repair_admission_config| {
+                if let Some(known_validators) = &config.known_validators {
+                    serve_repair_admission_config
+                        .whitelist
+                        .extend(known_validators.iter().copied());
+                }
+                ServeRepairAdmission::new(serve_repair_admission_config, Some(bank_forks.clone()))
+            },
+        );
         let serve_repair_service = ServeRepairService::new(
             &serve_repair,
             Some(blockstore.clone()),
             node.sockets.serve_repair,
             socket_addr_space,
             stats_reporter_sender,
+            serve_repair_admission,
             &exit,
         );
 
@@ -887,6 +1047,16 @@ impl Validator {
         let ledger_metric_report_service =
             LedgerMetricReportService::new(blockstore.clone(), &exit);
 
+        let ledger_archive_service = blockstore.ledger_archive().map(|ledger_archive| {
+            LedgerArchiveService::new(
+                ledger_archive.clone(),
+                blockstore.clone(),
+                block_commitment_cache.clone(),
+                max_complete_transaction_status_slot,
+                exit.clone(),
+            )
+        });
+
         let wait_for_vote_to_start_leader =
             !waited_for_supermajority && !config.no_wait_for_vote_to_start_leader;
 
@@ -958,11 +1128,13 @@ impl Validator {
             cluster_confirmed_slot_receiver,
             TvuConfig {
                 max_ledger_shreds: config.max_ledger_shreds,
+                max_ledger_bytes: config.max_ledger_bytes,
                 shred_version: node.info.shred_version,
                 repair_validators: config.repair_validators.clone(),
                 rocksdb_compaction_interval: config.rocksdb_compaction_interval,
                 rocksdb_max_compaction_jitter: config.rocksdb_compaction_interval,
                 wait_for_vote_to_start_leader,
+                slot_purge_listeners: config.slot_purge_listeners.clone(),
             },
             &max_slots,
             &cost_model,
@@ -983,6 +1155,7 @@ impl Validator {
                 vote: node.sockets.tpu_vote,
                 broadcast: node.sockets.broadcast,
                 transactions_quic: node.sockets.tpu_quic,
+                vote_quic: node.sockets.tpu_vote_quic,
             },
             &rpc_subscriptions,
             transaction_status_sender,
@@ -990,6 +1163,7 @@ impl Validator {
             &config.broadcast_stage_type,
             &exit,
             node.info.shred_version,
+            &config.broadcast_config,
             vote_tracker,
             bank_forks.clone(),
             verified_vote_sender,
@@ -1001,6 +1175,23 @@ impl Validator {
             cluster_confirmed_slot_sender,
             &cost_model,
             &identity_keypair,
+            BankingStageConfig {
+                forward_pacing_config: config.banking_forward_pacing_config.clone(),
+                priority_aging_config: config.banking_priority_aging_config.clone(),
+                pop_policy_config: config.banking_pop_policy_config.clone(),
+                packet_filter: config.banking_account_denylist.clone().map(|denied_keys| {
+                    Arc::new(AccountDenylist::new(denied_keys)) as Arc<dyn PacketFilter>
+                }),
+                source_packets_per_second: config.banking_source_packets_per_second,
+                max_packets_per_lock_set: config.banking_max_packets_per_lock_set,
+                packet_buffer_snapshot_dir: config
+                    .banking_persist_buffered_packets
+                    .then(|| ledger_path.join("banking_stage_buffer")),
+                ..BankingStageConfig::default()
+            },
+            config.banking_notify_dropped_transactions,
+            buffered_prioritization_fees,
+            ingest_plugin_manager,
         );
 
         datapoint_info!("validator-new", ("id", id.to_string(), String));
@@ -1013,6 +1204,8 @@ impl Validator {
             json_rpc_service,
             pubsub_service,
             rpc_completed_slots_service,
+            stake_snapshot_service,
+            vote_latency_service,
             optimistically_confirmed_bank_tracker,
             transaction_status_service,
             rewards_recorder_service,
@@ -1031,10 +1224,13 @@ impl Validator {
             cluster_info,
             bank_forks,
             blockstore,
+            poh_speed_check_report,
             geyser_plugin_service,
             ledger_metric_report_service,
+            ledger_archive_service,
             accounts_background_service,
             accounts_hash_verifier,
+            shutdown_config: config.shutdown_config.clone(),
         }
     }
 
@@ -1076,99 +1272,135 @@ impl Validator {
         );
     }
 
+    /// Stops the services in dependency order, each within its shutdown deadline. Services
+    /// that miss their deadline are left running so that shutdown can't hang.
     pub fn join(self) {
         drop(self.bank_forks);
         drop(self.cluster_info);
 
-        self.poh_service.join().expect("poh_service");
+        let mut shutdown = ShutdownCoordinator::new(self.shutdown_config);
+        let poh_service = self.poh_service;
+        shutdown.join("poh_service", move || poh_service.join());
         drop(self.poh_recorder);
 
         if let Some(json_rpc_service) = self.json_rpc_service {
-            json_rpc_service.join().expect("rpc_service");
+            shutdown.join("rpc_service", move || json_rpc_service.join());
         }
 
         if let Some(pubsub_service) = self.pubsub_service {
-            pubsub_service.join().expect("pubsub_service");
+            shutdown.join("pubsub_service", move || pubsub_service.join());
         }
 
-        self.rpc_completed_slots_service
-            .join()
-            .expect("rpc_completed_slots_service");
+        let rpc_completed_slots_service = self.rpc_completed_slots_service;
+        shutdown.join("rpc_completed_slots_service", move || {
+            rpc_completed_slots_service.join()
+        });
+
+        if let Some(stake_snapshot_service) = self.stake_snapshot_service {
+            shutdown.join("stake_snapshot_service", move || {
+                stake_snapshot_service.join()
+            });
+        }
+
+        if let Some(vote_latency_service) = self.vote_latency_service {
+            shutdown.join("vote_latency_service", move || vote_latency_service.join());
+        }
 
         if let Some(optimistically_confirmed_bank_tracker) =
             self.optimistically_confirmed_bank_tracker
         {
-            optimistically_confirmed_bank_tracker
-                .join()
-                .expect("optimistically_confirmed_bank_tracker");
+            shutdown.join("optimistically_confirmed_bank_tracker", move || {
+                optimistically_confirmed_bank_tracker.join()
+            });
         }
 
         if let Some(transaction_status_service) = self.transaction_status_service {
-            transaction_status_service
-                .join()
-                .expect("transaction_status_service");
+            shutdown.join("transaction_status_service", move || {
+                transaction_status_service.join()
+            });
         }
 
         if let Some(rewards_recorder_service) = self.rewards_recorder_service {
-            rewards_recorder_service
-                .join()
-                .expect("rewards_recorder_service");
+            shutdown.join("rewards_recorder_service", move || {
+                rewards_recorder_service.join()
+            });
         }
 
         if let Some(cache_block_meta_service) = self.cache_block_meta_service {
-            cache_block_meta_service
-                .join()
-                .expect("cache_block_meta_service");
+            shutdown.join("cache_block_meta_service", move || {
+                cache_block_meta_service.join()
+            });
         }
 
         if let Some(system_monitor_service) = self.system_monitor_service {
-            system_monitor_service
-                .join()
-                .expect("system_monitor_service");
+            shutdown.join("system_monitor_service", move || {
+                system_monitor_service.join()
+            });
         }
 
         if let Some(sample_performance_service) = self.sample_performance_service {
-            sample_performance_service
-                .join()
-                .expect("sample_performance_service");
+            shutdown.join("sample_performance_service", move || {
+                sample_performance_service.join()
+            });
         }
 
         if let Some(s) = self.snapshot_packager_service {
-            s.join().expect("snapshot_packager_service");
+            shutdown.join("snapshot_packager_service", move || s.join());
         }
 
-        self.gossip_service.join().expect("gossip_service");
-        self.serve_repair_service
-            .join()
-            .expect("serve_repair_service");
-        self.stats_reporter_service
-            .join()
-            .expect("stats_reporter_service");
-        self.ledger_metric_report_service
-            .join()
-            .expect("ledger_metric_report_service");
-        self.accounts_background_service
-            .join()
-            .expect("accounts_background_service");
-        self.accounts_hash_verifier
-            .join()
-            .expect("accounts_hash_verifier");
-        self.tpu.join().expect("tpu");
-        self.tvu.join().expect("tvu");
-        self.completed_data_sets_service
-            .join()
-            .expect("completed_data_sets_service");
+        let gossip_service = self.gossip_service;
+        shutdown.join("gossip_service", move || gossip_service.join());
+        let serve_repair_service = self.serve_repair_service;
+        shutdown.join("serve_repair_service", move || serve_repair_service.join());
+        let stats_reporter_service = self.stats_reporter_service;
+        shutdown.join("stats_reporter_service", move || {
+            stats_reporter_service.join()
+        });
+        let ledger_metric_report_service = self.ledger_metric_report_service;
+        shutdown.join("ledger_metric_report_service", move || {
+            ledger_metric_report_service.join()
+        });
+        if let Some(ledger_archive_service) = self.ledger_archive_service {
+            shutdown.join("ledger_archive_service", move || {
+                ledger_archive_service.join()
+            });
+        }
+        let accounts_background_service = self.accounts_background_service;
+        shutdown.join("accounts_background_service", move || {
+            accounts_background_service.join()
+        });
+        let accounts_hash_verifier = self.accounts_hash_verifier;
+        shutdown.join("accounts_hash_verifier", move || {
+            accounts_hash_verifier.join()
+        });
+        let tpu = self.tpu;
+        shutdown.join("tpu", move || tpu.join());
+        let tvu = self.tvu;
+        shutdown.join("tvu", move || tvu.join());
+        let completed_data_sets_service = self.completed_data_sets_service;
+        shutdown.join("completed_data_sets_service", move || {
+            completed_data_sets_service.join()
+        });
         if let Some(ip_echo_server) = self.ip_echo_server {
             ip_echo_server.shutdown_background();
         }
 
         if let Some(geyser_plugin_service) = self.geyser_plugin_service {
-            geyser_plugin_service.join().expect("geyser_plugin_service");
+            shutdown.join("geyser_plugin_service", move || {
+                geyser_plugin_service.join()
+            });
         }
 
-        self.poh_timing_report_service
-            .join()
-            .expect("poh_timing_report_service");
+        let poh_timing_report_service = self.poh_timing_report_service;
+        shutdown.join("poh_timing_report_service", move || {
+            poh_timing_report_service.join()
+        });
+
+        let report = shutdown.finish();
+        let panicked_services: Vec<_> = report.panicked_services().collect();
+        if !panicked_services.is_empty() {
+            panic!("services panicked during shutdown: {:?}", panicked_services);
+        }
     }
 }
 
@@ -1181,33 +1413,131 @@ fn active_vote_account_exists_in_bank(bank: &Arc<Bank>, vote_account: &Pubkey) -
     false
 }
 
-fn check_poh_speed(genesis_config: &GenesisConfig, maybe_hash_samples: Option<u64>) {
-    if let Some(hashes_per_tick) = genesis_config.hashes_per_tick() {
-        let ticks_per_slot = genesis_config.ticks_per_slot();
-        let hashes_per_slot = hashes_per_tick * ticks_per_slot;
+/// Speed of the local PoH hasher, measured with one or more threads hashing at once
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PohSpeedMeasurement {
+    pub num_threads: usize,
+    pub ns_per_slot: u64,
+    /// Time to spare per slot as a percentage of the cluster target slot time, negative if the
+    /// hasher is slower than the target
+    pub headroom_percent: f64,
+    pub passed: bool,
+}
+
+impl PohSpeedMeasurement {
+    fn new(num_threads: usize, ns_per_slot: u64, target_ns_per_slot: u64) -> Self {
+        let headroom_percent =
+            (target_ns_per_slot as f64 - ns_per_slot as f64) * 100.0 / target_ns_per_slot as f64;
+        Self {
+            num_threads,
+            ns_per_slot,
+            headroom_percent,
+            passed: ns_per_slot < target_ns_per_slot,
+        }
+    }
+}
+
+/// `PohSpeedCheckReport` is the outcome of the startup PoH speed check, surfaced to the node
+/// operator over the admin RPC channel.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PohSpeedCheckReport {
+    pub hashes_per_tick: u64,
+    pub ticks_per_slot: u64,
+    pub target_ns_per_slot: u64,
+    pub single_threaded: PohSpeedMeasurement,
+    /// `None` if the check was configured to hash on a single thread only
+    pub multi_threaded: Option<PohSpeedMeasurement>,
+}
 
-        let hash_samples = maybe_hash_samples.unwrap_or(hashes_per_slot);
-        let hash_time_ns = compute_hash_time_ns(hash_samples);
+impl PohSpeedCheckReport {
+    /// PoH is generated on a single thread, so only that measurement has to keep up with the
+    /// cluster. The multi-threaded measurement shows whether it still would with every core busy.
+    pub fn passed(&self) -> bool {
+        self.single_threaded.passed
+    }
+}
 
-        let my_ns_per_slot = (hash_time_ns * hashes_per_slot) / hash_samples;
-        debug!("computed: ns_per_slot: {}", my_ns_per_slot);
-        let target_ns_per_slot = genesis_config.ns_per_slot() as u64;
+impl fmt::Display for PohSpeedCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Result: {}", if self.passed() { "PASS" } else { "FAIL" })?;
+        writeln!(f, "Hashes per tick: {}", self.hashes_per_tick)?;
+        writeln!(f, "Ticks per slot: {}", self.ticks_per_slot)?;
+        writeln!(f, "Target ns per slot: {}", self.target_ns_per_slot)?;
+        for measurement in std::iter::once(&self.single_threaded).chain(&self.multi_threaded) {
+            writeln!(
+                f,
+                "{} thread(s): {} ns per slot, {:.1}% headroom, {}",
+                measurement.num_threads,
+                measurement.ns_per_slot,
+                measurement.headroom_percent,
+                if measurement.passed { "pass" } else { "fail" },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn check_poh_speed(
+    genesis_config: &GenesisConfig,
+    maybe_hash_samples: Option<u64>,
+    num_threads: usize,
+) -> Option<PohSpeedCheckReport> {
+    let hashes_per_tick = genesis_config.hashes_per_tick()?;
+    let ticks_per_slot = genesis_config.ticks_per_slot();
+    let hashes_per_slot = hashes_per_tick * ticks_per_slot;
+    let hash_samples = maybe_hash_samples.unwrap_or(hashes_per_slot);
+    let target_ns_per_slot = genesis_config.ns_per_slot() as u64;
+    debug!(
+        "cluster ns_per_hash: {}ns ns_per_slot: {}",
+        target_ns_per_slot / hashes_per_slot,
+        target_ns_per_slot
+    );
+
+    let measure = |num_threads: usize| {
+        let hash_time_ns = if num_threads > 1 {
+            compute_concurrent_hash_time_ns(hash_samples, num_threads)
+        } else {
+            compute_hash_time_ns(hash_samples)
+        };
+        let ns_per_slot = (hash_time_ns * hashes_per_slot) / hash_samples;
         debug!(
-            "cluster ns_per_hash: {}ns ns_per_slot: {}",
-            target_ns_per_slot / hashes_per_slot,
-            target_ns_per_slot
+            "computed: {} threads ns_per_slot: {}",
+            num_threads, ns_per_slot
         );
-        if my_ns_per_slot < target_ns_per_slot {
-            let extra_ns = target_ns_per_slot - my_ns_per_slot;
-            info!("PoH speed check: Will sleep {}ns per slot.", extra_ns);
-        } else {
-            error!(
-                "PoH is slower than cluster target tick rate! mine: {} cluster: {}. If you wish to continue, try --no-poh-speed-test",
-                my_ns_per_slot, target_ns_per_slot,
-            );
-            abort();
-        }
+        PohSpeedMeasurement::new(num_threads, ns_per_slot, target_ns_per_slot)
+    };
+    let report = PohSpeedCheckReport {
+        hashes_per_tick,
+        ticks_per_slot,
+        target_ns_per_slot,
+        single_threaded: measure(1),
+        multi_threaded: (num_threads > 1).then(|| measure(num_threads)),
+    };
+
+    if !report.passed() {
+        error!(
+            "PoH is slower than cluster target tick rate! mine: {} cluster: {}. If you wish to continue, try --no-poh-speed-test",
+            report.single_threaded.ns_per_slot, target_ns_per_slot,
+        );
+        abort();
     }
+    info!(
+        "PoH speed check: Will sleep {}ns per slot ({:.1}% headroom).",
+        target_ns_per_slot - report.single_threaded.ns_per_slot,
+        report.single_threaded.headroom_percent,
+    );
+    if let Some(multi_threaded) = report
+        .multi_threaded
+        .filter(|measurement| !measurement.passed)
+    {
+        warn!(
+            "PoH is slower than cluster target tick rate with {} threads hashing at once! mine: {} cluster: {}",
+            multi_threaded.num_threads, multi_threaded.ns_per_slot, target_ns_per_slot,
+        );
+    }
+    Some(report)
 }
 
 fn post_process_restored_tower(
@@ -1320,6 +1650,7 @@ fn load_blockstore(
     blockstore_processor::ProcessOptions,
     BlockstoreRootScan,
     DroppedSlotsReceiver,
+    Option<PohSpeedCheckReport>,
 ) {
     info!("loading ledger from {:?}...", ledger_path);
     *start_progress.write().unwrap() = ValidatorStartProgress::LoadingLedger;
@@ -1343,8 +1674,21 @@ fn load_blockstore(
         }
     }
 
-    if !config.no_poh_speed_test {
-        check_poh_speed(&genesis_config, None);
+    let poh_speed_check_report = if config.no_poh_speed_test {
+        None
+    } else {
+        check_poh_speed(&genesis_config, None, config.poh_speed_test_threads)
+    };
+
+    if let Some(mismatched_path) = Blockstore::find_mismatched_shred_storage(
+        ledger_path,
+        &config.ledger_column_options.shred_storage_type,
+    ) {
+        warn!(
+            "Ignoring the blockstore at {:?}, which has another shred storage type. \
+             Run `solana-ledger-tool migrate-shred-storage` to convert it.",
+            mismatched_path
+        );
     }
 
     let BlockstoreSignals {
@@ -1358,12 +1702,27 @@ fn load_blockstore(
             recovery_mode: config.wal_recovery_mode.clone(),
             column_options: config.ledger_column_options.clone(),
             enforce_ulimit_nofile: config.enforce_ulimit_nofile,
+            rocksdb_tuning_options: config.rocksdb_tuning_options.clone(),
+            verify_on_open: config.blockstore_verify_on_open,
+            column_layout: config.blockstore_column_layout.clone(),
+            io_options: config.rocksdb_io_options,
             ..BlockstoreOptions::default()
         },
     )
-    .expect("Failed to open ledger database");
+    .unwrap_or_else(|err| {
+        error!("Failed to open ledger database: {:?}", err);
+        abort();
+    });
     blockstore.set_no_compaction(config.no_rocksdb_compaction);
+    blockstore.set_record_slot_tombstones(config.ledger_purge_tombstones);
     blockstore.shred_timing_point_sender = poh_timing_point_sender;
+    if let Some(ledger_archive_config) = &config.ledger_archive_config {
+        let ledger_archive = open_ledger_archive(ledger_archive_config).unwrap_or_else(|err| {
+            error!("Failed to open ledger archive: {}", err);
+            abort();
+        });
+        blockstore.set_ledger_archive(Arc::new(ledger_archive));
+    }
 
     let blockstore = Arc::new(blockstore);
     let blockstore_root_scan = BlockstoreRootScan::new(config, &blockstore, exit);
@@ -1476,6 +1835,7 @@ fn load_blockstore(
         process_options,
         blockstore_root_scan,
         pruned_banks_receiver,
+        poh_speed_check_report,
     )
 }
 
@@ -2325,7 +2685,37 @@ mod tests {
             poh_config,
             ..GenesisConfig::default()
         };
-        check_poh_speed(&genesis_config, Some(10_000));
+        check_poh_speed(&genesis_config, Some(10_000), 1);
+    }
+
+    #[test]
+    fn test_poh_speed_report() {
+        let poh_config = PohConfig {
+            // make PoH rate really slow so the check passes
+            target_tick_duration: Duration::from_secs(1),
+            hashes_per_tick: Some(10),
+            ..PohConfig::default()
+        };
+        let genesis_config = GenesisConfig {
+            poh_config,
+            ..GenesisConfig::default()
+        };
+        let report = check_poh_speed(&genesis_config, Some(10_000), 2).unwrap();
+        assert!(report.passed());
+        assert_eq!(report.hashes_per_tick, 10);
+        assert_eq!(
+            report.target_ns_per_slot,
+            genesis_config.ns_per_slot() as u64
+        );
+        assert_eq!(report.single_threaded.num_threads, 1);
+        let multi_threaded = report.multi_threaded.unwrap();
+        assert_eq!(multi_threaded.num_threads, 2);
+        assert!(multi_threaded.passed);
+        assert!(multi_threaded.headroom_percent > 0.0);
+        assert!(report.to_string().starts_with("Result: PASS"));
+
+        let report = check_poh_speed(&genesis_config, Some(10_000), 1).unwrap();
+        assert_eq!(report.multi_threaded, None);
     }
 
     #[test]
@@ -2339,6 +2729,6 @@ mod tests {
             poh_config,
             ..GenesisConfig::default()
         };
-        check_poh_speed(&genesis_config, Some(10_000));
+        assert_eq!(check_poh_speed(&genesis_config, Some(10_000), 1), None);
     }
 }
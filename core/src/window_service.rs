@@ -15,6 +15,7 @@ use {
     solana_gossip::cluster_info::ClusterInfo,
     solana_ledger::{
         blockstore::{self, Blockstore, BlockstoreInsertionMetrics, MAX_DATA_SHREDS_PER_SLOT},
+        blockstore_meta::DuplicateProofSource,
         leader_schedule_cache::LeaderScheduleCache,
         shred::{Nonce, Shred, ShredType},
     },
@@ -218,10 +219,17 @@ fn run_check_duplicate(
                 blockstore.is_shred_duplicate(shred.id(), shred.payload().clone())
             {
                 cluster_info.push_duplicate_shred(&shred, &existing_shred_payload)?;
+                let shred_payload = shred.into_payload();
+                blockstore.store_duplicate_proof(
+                    shred_slot,
+                    existing_shred_payload.clone(),
+                    shred_payload.clone(),
+                    DuplicateProofSource::Local,
+                )?;
                 blockstore.store_duplicate_slot(
                     shred_slot,
                     existing_shred_payload,
-                    shred.into_payload(),
+                    shred_payload,
                 )?;
 
                 duplicate_slots_sender.send(shred_slot)?;
@@ -295,7 +303,7 @@ fn run_insert<F>(
     outstanding_requests: &RwLock<OutstandingShredRepairs>,
 ) -> Result<()>
 where
-    F: Fn(Shred),
+    F: Fn(Shred) + Sync,
 {
     ws_metrics.run_insert_count += 1;
     let mut shred_receiver_elapsed = Measure::start("shred_receiver_elapsed");
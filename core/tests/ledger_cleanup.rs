@@ -6,7 +6,7 @@ mod tests {
     use {
         crossbeam_channel::unbounded,
         log::*,
-        solana_core::ledger_cleanup_service::LedgerCleanupService,
+        solana_core::ledger_cleanup_service::{LedgerCleanupService, SlotPurgeListeners},
         solana_ledger::{
             blockstore::{make_many_slot_shreds, Blockstore},
             blockstore_options::{
@@ -398,9 +398,11 @@ mod tests {
                 receiver,
                 blockstore.clone(),
                 max_ledger_shreds,
+                None,
                 &exit,
                 compaction_interval,
                 None,
+                SlotPurgeListeners::default(),
             ))
         } else {
             None
@@ -688,9 +690,11 @@ mod tests {
             &receiver,
             &blockstore,
             max_ledger_shreds,
+            None,
             &mut last_purge_slot,
             10,
             &highest_compact_slot,
+            &SlotPurgeListeners::default(),
         )
         .unwrap();
 
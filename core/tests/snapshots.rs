@@ -964,8 +964,9 @@ mod tests {
             Some(pending_snapshot_package),
             &exit,
             &cluster_info,
+            bank_forks.clone(),
+            None,
             None,
-            false,
             0,
             Some(snapshot_test_config.snapshot_config.clone()),
         );
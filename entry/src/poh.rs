@@ -2,7 +2,10 @@
 use {
     log::*,
     solana_sdk::hash::{hash, hashv, Hash},
-    std::time::{Duration, Instant},
+    std::{
+        thread,
+        time::{Duration, Instant},
+    },
 };
 
 pub struct Poh {
@@ -114,6 +117,27 @@ impl Poh {
 
 pub fn compute_hash_time_ns(hashes_sample_size: u64) -> u64 {
     info!("Running {} hashes...", hashes_sample_size);
+    hash_time_ns(hashes_sample_size)
+}
+
+/// Runs `hashes_sample_size` hashes on each of `num_threads` threads at once and returns the
+/// time taken by the slowest thread, which reflects the hash rate while every core is busy
+pub fn compute_concurrent_hash_time_ns(hashes_sample_size: u64, num_threads: usize) -> u64 {
+    info!(
+        "Running {} hashes on {} threads...",
+        hashes_sample_size, num_threads
+    );
+    let threads: Vec<_> = (0..num_threads)
+        .map(|_| thread::spawn(move || hash_time_ns(hashes_sample_size)))
+        .collect();
+    threads
+        .into_iter()
+        .map(|thread| thread.join().unwrap())
+        .max()
+        .unwrap_or_default()
+}
+
+fn hash_time_ns(hashes_sample_size: u64) -> u64 {
     let mut v = Hash::default();
     let start = Instant::now();
     for _ in 0..hashes_sample_size {
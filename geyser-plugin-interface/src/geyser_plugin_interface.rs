@@ -3,8 +3,11 @@
 /// In addition, the dynamic library must export a "C" function _create_plugin which
 /// creates the implementation of the plugin.
 use {
-    solana_sdk::{clock::UnixTimestamp, signature::Signature, transaction::SanitizedTransaction},
-    solana_transaction_status::{Reward, TransactionStatusMeta},
+    solana_sdk::{
+        clock::UnixTimestamp, signature::Signature, transaction::SanitizedTransaction,
+        transaction_context::TransactionReturnData,
+    },
+    solana_transaction_status::{InnerInstructions, Reward, TransactionStatusMeta},
     std::{any::Any, error, io},
     thiserror::Error,
 };
@@ -70,6 +73,41 @@ pub enum ReplicaTransactionInfoVersions<'a> {
     V0_0_1(&'a ReplicaTransactionInfo<'a>),
 }
 
+/// Information about a transaction replayed by the validator, including what its execution
+/// recorded
+#[derive(Clone, Debug)]
+pub struct ReplicaTransactionInfoV2<'a> {
+    /// The first signature of the transaction, used for identifying the transaction.
+    pub signature: &'a Signature,
+
+    /// Indicates if the transaction is a simple vote transaction.
+    pub is_vote: bool,
+
+    /// The sanitized transaction.
+    pub transaction: &'a SanitizedTransaction,
+
+    /// Metadata of the transaction status.
+    pub transaction_status_meta: &'a TransactionStatusMeta,
+
+    /// The instructions invoked by each instruction of the transaction, for the instructions
+    /// that invoked any.
+    pub inner_instructions: &'a [InnerInstructions],
+
+    /// The messages logged by the transaction.
+    pub log_messages: &'a [String],
+
+    /// The data returned by the last program that set any.
+    pub return_data: Option<&'a TransactionReturnData>,
+}
+
+/// A wrapper to future-proof ReplicaTransactionInfoV2 handling.
+/// If there were a change to the structure of ReplicaTransactionInfoV2,
+/// there would be new enum entry for the newer version, forcing
+/// plugin implementations to handle the change.
+pub enum ReplicaTransactionInfoV2Versions<'a> {
+    V0_0_1(&'a ReplicaTransactionInfoV2<'a>),
+}
+
 #[derive(Clone, Debug)]
 pub struct ReplicaBlockInfo<'a> {
     pub slot: u64,
@@ -200,12 +238,57 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         Ok(())
     }
 
+    /// Called when a transaction is replayed at a slot, with the inner instructions, logs and
+    /// return data recorded by its execution. The validator calls this method in place of
+    /// `notify_transaction`; by default it forwards the transaction to `notify_transaction`, so
+    /// that plugins which only implement the latter keep being notified.
+    fn notify_transaction_v2(
+        &mut self,
+        transaction: ReplicaTransactionInfoV2Versions,
+        slot: u64,
+    ) -> Result<()> {
+        match transaction {
+            ReplicaTransactionInfoV2Versions::V0_0_1(transaction_info) => self.notify_transaction(
+                ReplicaTransactionInfoVersions::V0_0_1(&ReplicaTransactionInfo {
+                    signature: transaction_info.signature,
+                    is_vote: transaction_info.is_vote,
+                    transaction: transaction_info.transaction,
+                    transaction_status_meta: transaction_info.transaction_status_meta,
+                }),
+                slot,
+            ),
+        }
+    }
+
     /// Called when block's metadata is updated.
     #[allow(unused_variables)]
     fn notify_block_metadata(&mut self, blockinfo: ReplicaBlockInfoVersions) -> Result<()> {
         Ok(())
     }
 
+    /// Called when the ledger cleanup service is about to purge slots
+    /// `first_slot..=last_slot`. Returning false asks the validator to hold off
+    /// on the purge, e.g. while the plugin finishes copying the data out. The
+    /// purge is retried on later roots and goes ahead anyway once the
+    /// validator's grace window has elapsed.
+    #[allow(unused_variables)]
+    fn approve_slots_purge(&mut self, first_slot: u64, last_slot: u64) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// Called after slots `first_slot..=last_slot` have been purged from the ledger.
+    #[allow(unused_variables)]
+    fn notify_slots_purged(&mut self, first_slot: u64, last_slot: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check if the plugin is interested in ledger purge notifications
+    /// Default is false -- if the plugin is not interested in
+    /// ledger purges, please return false.
+    fn slot_purge_notifications_enabled(&self) -> bool {
+        false
+    }
+
     /// Check if the plugin is interested in account data
     /// Default is true -- if the plugin is not interested in
     /// account data, please return false.
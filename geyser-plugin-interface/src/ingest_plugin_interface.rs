@@ -0,0 +1,68 @@
+/// The interface for ingest plugins. Unlike Geyser plugins, which are notified of what the
+/// validator has processed, an ingest plugin decides which transaction packets the validator
+/// processes at all: every packet received on the TPU is screened by the loaded plugins
+/// before its signatures are verified. A plugin must implement the IngestPlugin trait, and
+/// the dynamic library must export a "C" function _create_ingest_plugin which creates the
+/// implementation of the plugin.
+use {
+    crate::geyser_plugin_interface::Result,
+    std::{any::Any, net::IpAddr},
+};
+
+/// Information about a packet received on the TPU
+#[derive(Clone, Debug)]
+pub struct IngestPacketInfo<'a> {
+    /// The serialized transaction. Its signatures are not verified yet.
+    pub data: &'a [u8],
+
+    /// The address the packet was received from
+    pub addr: IpAddr,
+
+    /// The port the packet was received from
+    pub port: u16,
+
+    /// The stake of the node the packet was received from, zero if unstaked
+    pub sender_stake: u64,
+
+    /// Indicates if the packet was forwarded by another validator
+    pub is_forwarded: bool,
+}
+
+/// A wrapper to future-proof IngestPacketInfo handling.
+/// If there were a change to the structure of IngestPacketInfo,
+/// there would be new enum entry for the newer version, forcing
+/// plugin implementations to handle the change.
+pub enum IngestPacketInfoVersions<'a> {
+    V0_0_1(&'a IngestPacketInfo<'a>),
+}
+
+/// Decision of a plugin on a packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketAdmission {
+    /// The packet goes on to sigverify, unless another plugin rejects it
+    Accept,
+    /// The packet is discarded
+    Reject,
+}
+
+/// Defines an ingest plugin, to screen the packets received by the validator.
+/// Packets are screened on the hot path of the TPU, so admission decisions must be cheap.
+pub trait IngestPlugin: Any + Send + Sync + std::fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    /// The callback called when a plugin is loaded by the system,
+    /// used for doing whatever initialization is required by the plugin.
+    /// The _config_file contains the name of the config file. The config must
+    /// be in JSON format and include a field "libpath" indicating the full path
+    /// name of the shared library implementing this interface.
+    fn on_load(&mut self, _config_file: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// The callback called right before a plugin is unloaded by the system
+    /// Used for doing cleanup before unload.
+    fn on_unload(&mut self) {}
+
+    /// Called for every packet received on the TPU that was not discarded already.
+    fn admit_packet(&self, packet: IngestPacketInfoVersions) -> PacketAdmission;
+}
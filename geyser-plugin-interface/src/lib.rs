@@ -1 +1,2 @@
 pub mod geyser_plugin_interface;
+pub mod ingest_plugin_interface;
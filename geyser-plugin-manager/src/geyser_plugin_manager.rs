@@ -72,4 +72,14 @@ impl GeyserPluginManager {
         }
         false
     }
+
+    /// Check if there is any plugin interested in ledger purges
+    pub fn slot_purge_notifications_enabled(&self) -> bool {
+        for plugin in &self.plugins {
+            if plugin.slot_purge_notifications_enabled() {
+                return true;
+            }
+        }
+        false
+    }
 }
@@ -3,8 +3,9 @@ use {
         accounts_update_notifier::AccountsUpdateNotifierImpl,
         block_metadata_notifier::BlockMetadataNotifierImpl,
         block_metadata_notifier_interface::BlockMetadataNotifierLock,
-        geyser_plugin_manager::GeyserPluginManager, slot_status_notifier::SlotStatusNotifierImpl,
-        slot_status_observer::SlotStatusObserver, transaction_notifier::TransactionNotifierImpl,
+        geyser_plugin_manager::GeyserPluginManager, slot_purge_notifier::SlotPurgeNotifierImpl,
+        slot_status_notifier::SlotStatusNotifierImpl, slot_status_observer::SlotStatusObserver,
+        transaction_notifier::TransactionNotifierImpl,
     },
     crossbeam_channel::Receiver,
     log::*,
@@ -51,6 +52,7 @@ pub struct GeyserPluginService {
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
     transaction_notifier: Option<TransactionNotifierLock>,
     block_metadata_notifier: Option<BlockMetadataNotifierLock>,
+    slot_purge_notifier: Option<Arc<SlotPurgeNotifierImpl>>,
 }
 
 impl GeyserPluginService {
@@ -83,6 +85,7 @@ impl GeyserPluginService {
         let account_data_notifications_enabled =
             plugin_manager.account_data_notifications_enabled();
         let transaction_notifications_enabled = plugin_manager.transaction_notifications_enabled();
+        let slot_purge_notifications_enabled = plugin_manager.slot_purge_notifications_enabled();
 
         let plugin_manager = Arc::new(RwLock::new(plugin_manager));
 
@@ -122,6 +125,12 @@ impl GeyserPluginService {
             (None, None)
         };
 
+        let slot_purge_notifier = if slot_purge_notifications_enabled {
+            Some(Arc::new(SlotPurgeNotifierImpl::new(plugin_manager.clone())))
+        } else {
+            None
+        };
+
         info!("Started GeyserPluginService");
         Ok(GeyserPluginService {
             slot_status_observer,
@@ -129,6 +138,7 @@ impl GeyserPluginService {
             accounts_update_notifier,
             transaction_notifier,
             block_metadata_notifier,
+            slot_purge_notifier,
         })
     }
 
@@ -136,52 +146,7 @@ impl GeyserPluginService {
         plugin_manager: &mut GeyserPluginManager,
         geyser_plugin_config_file: &Path,
     ) -> Result<(), GeyserPluginServiceError> {
-        let mut file = match File::open(geyser_plugin_config_file) {
-            Ok(file) => file,
-            Err(err) => {
-                return Err(GeyserPluginServiceError::CannotOpenConfigFile(format!(
-                    "Failed to open the plugin config file {:?}, error: {:?}",
-                    geyser_plugin_config_file, err
-                )));
-            }
-        };
-
-        let mut contents = String::new();
-        if let Err(err) = file.read_to_string(&mut contents) {
-            return Err(GeyserPluginServiceError::CannotReadConfigFile(format!(
-                "Failed to read the plugin config file {:?}, error: {:?}",
-                geyser_plugin_config_file, err
-            )));
-        }
-
-        let result: serde_json::Value = match json5::from_str(&contents) {
-            Ok(value) => value,
-            Err(err) => {
-                return Err(GeyserPluginServiceError::InvalidConfigFileFormat(format!(
-                    "The config file {:?} is not in a valid Json5 format, error: {:?}",
-                    geyser_plugin_config_file, err
-                )));
-            }
-        };
-
-        let libpath = result["libpath"]
-            .as_str()
-            .ok_or(GeyserPluginServiceError::LibPathNotSet)?;
-        let mut libpath = PathBuf::from(libpath);
-        if libpath.is_relative() {
-            let config_dir = geyser_plugin_config_file.parent().ok_or_else(|| {
-                GeyserPluginServiceError::CannotOpenConfigFile(format!(
-                    "Failed to resolve parent of {:?}",
-                    geyser_plugin_config_file,
-                ))
-            })?;
-            libpath = config_dir.join(libpath);
-        }
-
-        let config_file = geyser_plugin_config_file
-            .as_os_str()
-            .to_str()
-            .ok_or(GeyserPluginServiceError::InvalidPluginPath)?;
+        let (libpath, config_file) = resolve_plugin_config(geyser_plugin_config_file)?;
 
         unsafe {
             let result = plugin_manager.load_plugin(libpath.to_str().unwrap(), config_file);
@@ -208,6 +173,10 @@ impl GeyserPluginService {
         self.block_metadata_notifier.clone()
     }
 
+    pub fn get_slot_purge_notifier(&self) -> Option<Arc<SlotPurgeNotifierImpl>> {
+        self.slot_purge_notifier.clone()
+    }
+
     pub fn join(self) -> thread::Result<()> {
         if let Some(mut slot_status_observer) = self.slot_status_observer {
             slot_status_observer.join()?;
@@ -216,3 +185,59 @@ impl GeyserPluginService {
         Ok(())
     }
 }
+
+/// Reads the plugin config file, returning the path of the plugin library it points to, which
+/// is relative to the config file unless absolute, and the path of the config file as passed
+/// to the plugin
+pub(crate) fn resolve_plugin_config(
+    geyser_plugin_config_file: &Path,
+) -> Result<(PathBuf, &str), GeyserPluginServiceError> {
+    let mut file = match File::open(geyser_plugin_config_file) {
+        Ok(file) => file,
+        Err(err) => {
+            return Err(GeyserPluginServiceError::CannotOpenConfigFile(format!(
+                "Failed to open the plugin config file {:?}, error: {:?}",
+                geyser_plugin_config_file, err
+            )));
+        }
+    };
+
+    let mut contents = String::new();
+    if let Err(err) = file.read_to_string(&mut contents) {
+        return Err(GeyserPluginServiceError::CannotReadConfigFile(format!(
+            "Failed to read the plugin config file {:?}, error: {:?}",
+            geyser_plugin_config_file, err
+        )));
+    }
+
+    let result: serde_json::Value = match json5::from_str(&contents) {
+        Ok(value) => value,
+        Err(err) => {
+            return Err(GeyserPluginServiceError::InvalidConfigFileFormat(format!(
+                "The config file {:?} is not in a valid Json5 format, error: {:?}",
+                geyser_plugin_config_file, err
+            )));
+        }
+    };
+
+    let libpath = result["libpath"]
+        .as_str()
+        .ok_or(GeyserPluginServiceError::LibPathNotSet)?;
+    let mut libpath = PathBuf::from(libpath);
+    if libpath.is_relative() {
+        let config_dir = geyser_plugin_config_file.parent().ok_or_else(|| {
+            GeyserPluginServiceError::CannotOpenConfigFile(format!(
+                "Failed to resolve parent of {:?}",
+                geyser_plugin_config_file,
+            ))
+        })?;
+        libpath = config_dir.join(libpath);
+    }
+
+    let config_file = geyser_plugin_config_file
+        .as_os_str()
+        .to_str()
+        .ok_or(GeyserPluginServiceError::InvalidPluginPath)?;
+
+    Ok((libpath, config_file))
+}
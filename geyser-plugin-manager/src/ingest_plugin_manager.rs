@@ -0,0 +1,103 @@
+/// Managing the ingest plugins
+use {
+    crate::geyser_plugin_service::{resolve_plugin_config, GeyserPluginServiceError},
+    libloading::{Library, Symbol},
+    log::*,
+    solana_geyser_plugin_interface::ingest_plugin_interface::{
+        IngestPacketInfo, IngestPacketInfoVersions, IngestPlugin, PacketAdmission,
+    },
+    solana_sdk::packet::Packet,
+    std::{error::Error, path::PathBuf},
+};
+
+#[derive(Default, Debug)]
+pub struct IngestPluginManager {
+    plugins: Vec<Box<dyn IngestPlugin>>,
+    libs: Vec<Library>,
+}
+
+impl IngestPluginManager {
+    /// Loads the plugins described by `ingest_plugin_config_files`, which have the same
+    /// format as Geyser plugin config files. The libraries must export a `C` function
+    /// `_create_ingest_plugin` returning an implementation of `IngestPlugin`.
+    pub fn new(ingest_plugin_config_files: &[PathBuf]) -> Result<Self, GeyserPluginServiceError> {
+        info!(
+            "Loading ingest plugins from config files: {:?}",
+            ingest_plugin_config_files
+        );
+        let mut plugin_manager = Self::default();
+        for ingest_plugin_config_file in ingest_plugin_config_files {
+            let (libpath, config_file) = resolve_plugin_config(ingest_plugin_config_file)?;
+            unsafe {
+                if let Err(err) = plugin_manager.load_plugin(libpath.to_str().unwrap(), config_file)
+                {
+                    let msg = format!(
+                        "Failed to load the ingest plugin library: {:?}, error: {:?}",
+                        libpath, err
+                    );
+                    return Err(GeyserPluginServiceError::PluginLoadError(msg));
+                }
+            }
+        }
+        Ok(plugin_manager)
+    }
+
+    /// # Safety
+    ///
+    /// This function loads the dynamically linked library specified in the path. The library
+    /// must do necessary initializations.
+    pub unsafe fn load_plugin(
+        &mut self,
+        libpath: &str,
+        config_file: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        type PluginConstructor = unsafe fn() -> *mut dyn IngestPlugin;
+        let lib = Library::new(libpath)?;
+        let constructor: Symbol<PluginConstructor> = lib.get(b"_create_ingest_plugin")?;
+        let plugin_raw = constructor();
+        let mut plugin = Box::from_raw(plugin_raw);
+        plugin.on_load(config_file)?;
+        info!("Loaded ingest plugin {:?}", plugin.name());
+        self.plugins.push(plugin);
+        self.libs.push(lib);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Returns whether every plugin accepts `packet`
+    pub fn admit_packet(&self, packet: &Packet) -> bool {
+        let packet_info = IngestPacketInfo {
+            data: packet.data(),
+            addr: packet.meta.addr,
+            port: packet.meta.port,
+            sender_stake: packet.meta.sender_stake,
+            is_forwarded: packet.meta.forwarded(),
+        };
+        self.plugins.iter().all(|plugin| {
+            plugin.admit_packet(IngestPacketInfoVersions::V0_0_1(&packet_info))
+                == PacketAdmission::Accept
+        })
+    }
+
+    /// Unload all plugins and loaded plugin libraries, making sure to fire
+    /// their `on_unload()` methods so they can do any necessary cleanup.
+    pub fn unload(&mut self) {
+        for mut plugin in self.plugins.drain(..) {
+            info!("Unloading ingest plugin for {:?}", plugin.name());
+            plugin.on_unload();
+        }
+
+        for lib in self.libs.drain(..) {
+            drop(lib);
+        }
+    }
+}
+
+impl Drop for IngestPluginManager {
+    fn drop(&mut self) {
+        self.unload();
+    }
+}
@@ -3,6 +3,8 @@ pub mod block_metadata_notifier;
 pub mod block_metadata_notifier_interface;
 pub mod geyser_plugin_manager;
 pub mod geyser_plugin_service;
+pub mod ingest_plugin_manager;
+pub mod slot_purge_notifier;
 pub mod slot_status_notifier;
 pub mod slot_status_observer;
 pub mod transaction_notifier;
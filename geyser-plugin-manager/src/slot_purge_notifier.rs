@@ -0,0 +1,83 @@
+use {
+    crate::geyser_plugin_manager::GeyserPluginManager,
+    log::*,
+    solana_measure::measure::Measure,
+    solana_metrics::*,
+    solana_sdk::clock::Slot,
+    std::sync::{Arc, RwLock},
+};
+
+/// Relays ledger purges to the plugins interested in them, and collects
+/// their approval before the purge happens
+#[derive(Debug)]
+pub struct SlotPurgeNotifierImpl {
+    plugin_manager: Arc<RwLock<GeyserPluginManager>>,
+}
+
+impl SlotPurgeNotifierImpl {
+    pub fn new(plugin_manager: Arc<RwLock<GeyserPluginManager>>) -> Self {
+        Self { plugin_manager }
+    }
+
+    /// Returns false if any plugin asked to hold off on purging slots
+    /// `first_slot..=last_slot`. Every plugin is asked, so each of them learns
+    /// about the pending purge.
+    pub fn approve_slots_purge(&self, first_slot: Slot, last_slot: Slot) -> bool {
+        let mut plugin_manager = self.plugin_manager.write().unwrap();
+        let mut approved = true;
+        for plugin in plugin_manager.plugins.iter_mut() {
+            if !plugin.slot_purge_notifications_enabled() {
+                continue;
+            }
+            let mut measure = Measure::start("geyser-plugin-approve-slots-purge");
+            match plugin.approve_slots_purge(first_slot, last_slot) {
+                Err(err) => {
+                    error!(
+                        "Failed to request approval to purge slots {}..={}, error: {} to plugin {}",
+                        first_slot,
+                        last_slot,
+                        err,
+                        plugin.name()
+                    )
+                }
+                Ok(plugin_approved) => {
+                    if !plugin_approved {
+                        info!(
+                            "Plugin {} held off the purge of slots {}..={}",
+                            plugin.name(),
+                            first_slot,
+                            last_slot
+                        );
+                    }
+                    approved &= plugin_approved;
+                }
+            }
+            measure.stop();
+            inc_new_counter_debug!(
+                "geyser-plugin-approve-slots-purge-us",
+                measure.as_us() as usize,
+                1000,
+                1000
+            );
+        }
+        approved
+    }
+
+    pub fn notify_slots_purged(&self, first_slot: Slot, last_slot: Slot) {
+        let mut plugin_manager = self.plugin_manager.write().unwrap();
+        for plugin in plugin_manager.plugins.iter_mut() {
+            if !plugin.slot_purge_notifications_enabled() {
+                continue;
+            }
+            if let Err(err) = plugin.notify_slots_purged(first_slot, last_slot) {
+                error!(
+                    "Failed to notify purge of slots {}..={}, error: {} to plugin {}",
+                    first_slot,
+                    last_slot,
+                    err,
+                    plugin.name()
+                )
+            }
+        }
+    }
+}
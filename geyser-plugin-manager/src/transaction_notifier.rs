@@ -3,7 +3,7 @@ use {
     crate::geyser_plugin_manager::GeyserPluginManager,
     log::*,
     solana_geyser_plugin_interface::geyser_plugin_interface::{
-        ReplicaTransactionInfo, ReplicaTransactionInfoVersions,
+        ReplicaTransactionInfoV2, ReplicaTransactionInfoV2Versions,
     },
     solana_measure::measure::Measure,
     solana_metrics::*,
@@ -15,7 +15,7 @@ use {
 
 /// This implementation of TransactionNotifier is passed to the rpc's TransactionStatusService
 /// at the validator startup. TransactionStatusService invokes the notify_transaction method
-/// for new transactions. The implementation in turn invokes the notify_transaction of each
+/// for new transactions. The implementation in turn invokes the notify_transaction_v2 of each
 /// plugin enabled with transaction notification managed by the GeyserPluginManager.
 pub(crate) struct TransactionNotifierImpl {
     plugin_manager: Arc<RwLock<GeyserPluginManager>>,
@@ -43,8 +43,8 @@ impl TransactionNotifier for TransactionNotifierImpl {
             if !plugin.transaction_notifications_enabled() {
                 continue;
             }
-            match plugin.notify_transaction(
-                ReplicaTransactionInfoVersions::V0_0_1(&transaction_log_info),
+            match plugin.notify_transaction_v2(
+                ReplicaTransactionInfoV2Versions::V0_0_1(&transaction_log_info),
                 slot,
             ) {
                 Err(err) => {
@@ -81,12 +81,21 @@ impl TransactionNotifierImpl {
         signature: &'a Signature,
         transaction_status_meta: &'a TransactionStatusMeta,
         transaction: &'a SanitizedTransaction,
-    ) -> ReplicaTransactionInfo<'a> {
-        ReplicaTransactionInfo {
+    ) -> ReplicaTransactionInfoV2<'a> {
+        ReplicaTransactionInfoV2 {
             signature,
             is_vote: transaction.is_simple_vote_transaction(),
             transaction,
             transaction_status_meta,
+            inner_instructions: transaction_status_meta
+                .inner_instructions
+                .as_deref()
+                .unwrap_or_default(),
+            log_messages: transaction_status_meta
+                .log_messages
+                .as_deref()
+                .unwrap_or_default(),
+            return_data: transaction_status_meta.return_data.as_ref(),
         }
     }
 }
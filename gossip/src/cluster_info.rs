@@ -2719,6 +2719,7 @@ pub struct Sockets {
     pub serve_repair: UdpSocket,
     pub ancestor_hashes_requests: UdpSocket,
     pub tpu_quic: UdpSocket,
+    pub tpu_vote_quic: UdpSocket,
 }
 
 #[derive(Debug)]
@@ -2742,7 +2743,8 @@ impl Node {
         let tvu = UdpSocket::bind("127.0.0.1:0").unwrap();
         let tvu_forwards = UdpSocket::bind("127.0.0.1:0").unwrap();
         let tpu_forwards = UdpSocket::bind("127.0.0.1:0").unwrap();
-        let tpu_vote = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let ((_tpu_vote_port, tpu_vote), (_tpu_vote_quic_port, tpu_vote_quic)) =
+            bind_two_consecutive_in_range(IpAddr::V4(Ipv4Addr::LOCALHOST), (1024, 65535)).unwrap();
         let repair = UdpSocket::bind("127.0.0.1:0").unwrap();
         let rpc_port = find_available_port_in_range(bind_ip_addr, (1024, 65535)).unwrap();
         let rpc_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), rpc_port);
@@ -2786,6 +2788,7 @@ impl Node {
                 serve_repair,
                 ancestor_hashes_requests,
                 tpu_quic,
+                tpu_vote_quic,
             },
         }
     }
@@ -2823,7 +2826,8 @@ impl Node {
         let ((tpu_port, tpu), (_tpu_quic_port, tpu_quic)) =
             bind_two_consecutive_in_range(bind_ip_addr, port_range).unwrap();
         let (tpu_forwards_port, tpu_forwards) = Self::bind(bind_ip_addr, port_range);
-        let (tpu_vote_port, tpu_vote) = Self::bind(bind_ip_addr, port_range);
+        let ((tpu_vote_port, tpu_vote), (_tpu_vote_quic_port, tpu_vote_quic)) =
+            bind_two_consecutive_in_range(bind_ip_addr, port_range).unwrap();
         let (_, retransmit_socket) = Self::bind(bind_ip_addr, port_range);
         let (repair_port, repair) = Self::bind(bind_ip_addr, port_range);
         let (serve_repair_port, serve_repair) = Self::bind(bind_ip_addr, port_range);
@@ -2866,6 +2870,7 @@ impl Node {
                 serve_repair,
                 ancestor_hashes_requests,
                 tpu_quic,
+                tpu_vote_quic,
             },
         }
     }
@@ -2900,6 +2905,14 @@ impl Node {
         let (tpu_vote_port, tpu_vote_sockets) =
             multi_bind_in_range(bind_ip_addr, port_range, 1).expect("tpu_vote multi_bind");
 
+        let (_tpu_vote_port_quic, tpu_vote_quic) = Self::bind(
+            bind_ip_addr,
+            (
+                tpu_vote_port + QUIC_PORT_OFFSET,
+                tpu_vote_port + QUIC_PORT_OFFSET + 1,
+            ),
+        );
+
         let (_, retransmit_sockets) =
             multi_bind_in_range(bind_ip_addr, port_range, 8).expect("retransmit multi_bind");
 
@@ -2944,6 +2957,7 @@ impl Node {
                 ip_echo: Some(ip_echo),
                 ancestor_hashes_requests,
                 tpu_quic,
+                tpu_vote_quic,
             },
         }
     }
@@ -1,6 +1,6 @@
 #![allow(clippy::integer_arithmetic)]
 use {
-    crate::{bigtable::*, ledger_path::*},
+    crate::{bigtable::*, ledger_path::*, transaction_graph::*},
     chrono::{DateTime, Utc},
     clap::{
         crate_description, crate_name, value_t, value_t_or_exit, values_t_or_exit, App,
@@ -23,10 +23,17 @@ use {
     solana_ledger::{
         ancestor_iterator::AncestorIterator,
         bank_forks_utils,
-        blockstore::{create_new_ledger, Blockstore, PurgeType},
-        blockstore_db::{self, Database},
+        blockstore::{
+            create_new_ledger, Blockstore, PurgeType, BLOCKSTORE_DIRECTORY_ROCKS_FIFO,
+            BLOCKSTORE_DIRECTORY_ROCKS_LEVEL,
+        },
+        blockstore_db::DEFAULT_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES,
+        blockstore_db::{self, BlockstoreError, Database},
+        blockstore_lock::BlockstoreLock,
+        blockstore_meta::SlotPurgeReason,
         blockstore_options::{
-            AccessType, BlockstoreOptions, BlockstoreRecoveryMode, LedgerColumnOptions,
+            AccessType, BlockstoreOptions, BlockstoreRecoveryMode, BlockstoreRocksFifoOptions,
+            ColumnLayout, LedgerColumnOptions, ShredStorageType,
         },
         blockstore_processor::{BlockstoreProcessorError, ProcessOptions},
         shred::Shred,
@@ -76,7 +83,7 @@ use {
         collections::{BTreeMap, BTreeSet, HashMap, HashSet},
         ffi::OsStr,
         fs::File,
-        io::{self, stdout, BufRead, BufReader, Write},
+        io::{self, stdout, BufRead, BufReader, BufWriter, Write},
         path::{Path, PathBuf},
         process::{exit, Command, Stdio},
         str::FromStr,
@@ -90,6 +97,7 @@ use {
 
 mod bigtable;
 mod ledger_path;
+mod transaction_graph;
 
 #[derive(PartialEq, Eq)]
 enum LedgerOutputMethod {
@@ -700,6 +708,7 @@ fn analyze_storage(database: &Database) {
     analyze_column::<SlotMeta>(database, "SlotMeta");
     analyze_column::<Orphans>(database, "Orphans");
     analyze_column::<DeadSlots>(database, "DeadSlots");
+    analyze_column::<DeadSlotCauses>(database, "DeadSlotCauses");
     analyze_column::<DuplicateSlots>(database, "DuplicateSlots");
     analyze_column::<ErasureMeta>(database, "ErasureMeta");
     analyze_column::<BankHash>(database, "BankHash");
@@ -717,6 +726,8 @@ fn analyze_storage(database: &Database) {
     analyze_column::<BlockHeight>(database, "BlockHeight");
     analyze_column::<ProgramCosts>(database, "ProgramCosts");
     analyze_column::<OptimisticSlots>(database, "OptimisticSlots");
+    analyze_column::<BlockCost>(database, "BlockCost");
+    analyze_column::<DuplicateProofs>(database, "DuplicateProofs");
 }
 
 fn open_blockstore(
@@ -954,9 +965,13 @@ fn main() {
 
     const DEFAULT_ROOT_COUNT: &str = "1";
     const DEFAULT_LATEST_OPTIMISTIC_SLOTS_COUNT: &str = "1";
+    const DEFAULT_BLOCK_COSTS_COUNT: &str = "20";
+    const DEFAULT_SLOT_TOMBSTONES_COUNT: &str = "20";
     const DEFAULT_MAX_SLOTS_ROOT_REPAIR: &str = "2000";
     solana_logger::setup_with_default("solana=info");
 
+    let default_rocks_fifo_shred_storage_size =
+        &DEFAULT_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES.to_string();
     let starting_slot_arg = Arg::with_name("starting_slot")
         .long("starting-slot")
         .value_name("SLOT")
@@ -1167,13 +1182,11 @@ fn main() {
                 .value_name("MODE")
                 .takes_value(true)
                 .global(true)
-                .possible_values(&[
-                    "tolerate_corrupted_tail_records",
-                    "absolute_consistency",
-                    "point_in_time",
-                    "skip_any_corrupted_record"])
+                .validator(is_parsable::<BlockstoreRecoveryMode>)
                 .help(
-                    "Mode to recovery the ledger db write ahead log"
+                    "Mode to recovery the ledger db write ahead log \
+                     [possible values: tolerate_corrupted_tail_records, absolute_consistency, \
+                     point_in_time, skip_any_corrupted_record]"
                 ),
         )
         .arg(
@@ -1271,6 +1284,13 @@ fn main() {
             .arg(&starting_slot_arg)
             .about("Print all the duplicate slots in the ledger")
         )
+        .subcommand(
+            SubCommand::with_name("dead-slots-report")
+            .about("Print the dead and duplicate slots in a range of the ledger, with the \
+                    causes recorded when they were marked")
+            .arg(&starting_slot_arg)
+            .arg(&ending_slot_arg)
+        )
         .subcommand(
             SubCommand::with_name("set-dead-slot")
             .about("Mark one or more slots dead")
@@ -1285,6 +1305,113 @@ fn main() {
                     .help("Slots to mark dead"),
             )
         )
+        .subcommand(
+            SubCommand::with_name("migrate-shred-storage")
+            .about("Convert a ledger stored with another shred storage type to the given one")
+            .arg(
+                Arg::with_name("shred_storage")
+                    .long("shred-storage")
+                    .value_name("ROCKSDB_COMPACTION_STYLE")
+                    .takes_value(true)
+                    .possible_values(&["level", "fifo"])
+                    .required(true)
+                    .help("The shred storage type to convert the ledger to, as given to the \
+                           validator's --rocksdb-shred-compaction"),
+            )
+            .arg(
+                Arg::with_name("fifo_shred_storage_size")
+                    .long("fifo-shred-storage-size")
+                    .value_name("SHRED_STORAGE_SIZE_BYTES")
+                    .takes_value(true)
+                    .validator(is_parsable::<u64>)
+                    .default_value(default_rocks_fifo_shred_storage_size)
+                    .help("The shred storage size in bytes when converting to fifo, as given \
+                           to the validator's --rocksdb-fifo-shred-storage-size"),
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("move-columns")
+            .about("Move column families of the ledger to other directories, or back into the \
+                    ledger")
+            .arg(
+                Arg::with_name("column_path")
+                    .long("column-path")
+                    .value_name("COLUMN=DIR")
+                    .takes_value(true)
+                    .multiple(true)
+                    .validator(|value| {
+                        ColumnLayout::from_column_paths([value.as_str()])
+                            .map(|_| ())
+                            .map_err(|err| err.to_string())
+                    })
+                    .help("Directory to store the COLUMN column family in, as given to the \
+                           validator's --ledger-column-path. Columns without one are moved \
+                           back into the ledger"),
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("unlock")
+            .about("Remove the lock of a ledger whose owner is gone")
+            .arg(
+                Arg::with_name("force")
+                    .long("force")
+                    .takes_value(false)
+                    .help("Remove the locks of the ledger, including RocksDB's, whoever owns \
+                           them. Use this to restart a validator that crashed on shared \
+                           storage, once it is known to be down; unlocking a ledger in use \
+                           can corrupt it"),
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("export-slots")
+            .about("Write the shreds of a range of slots to a file that `import-slots` can \
+                    insert into another ledger")
+            .arg(&starting_slot_arg)
+            .arg(&ending_slot_arg)
+            .arg(
+                Arg::with_name("output_file")
+                    .index(1)
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("File to write the slots to"),
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("import-slots")
+            .about("Insert the slots of a file written by `export-slots` into the ledger, \
+                    keeping the shreds the ledger already has")
+            .arg(
+                Arg::with_name("input_file")
+                    .index(1)
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("File to read the slots from"),
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("rebuild-tx-index")
+            .about("Index the transactions of rooted slots by address, so that history queries \
+                    cover slots replayed without --enable-rpc-transaction-history")
+            .arg(&starting_slot_arg)
+            .arg(&ending_slot_arg)
+        )
+        .subcommand(
+            SubCommand::with_name("create-checkpoint")
+            .about("Copy the blockstore and genesis of the ledger to a new ledger directory, \
+                    hard linking its files where possible. Use \
+                    `solana-validator create-ledger-checkpoint` for the ledger of a running \
+                    validator")
+            .arg(
+                Arg::with_name("checkpoint_path")
+                    .index(1)
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Ledger directory to create the checkpoint in"),
+            )
+        )
         .subcommand(
             SubCommand::with_name("remove-dead-slot")
             .about("Remove the dead flag for a slot")
@@ -1719,6 +1846,34 @@ fn main() {
                     .takes_value(false)
                     .help("Limit purging to dead slots only")
             )
+            .arg(
+                Arg::with_name("record_tombstones")
+                    .long("record-tombstones")
+                    .required(false)
+                    .takes_value(false)
+                    .help("Record the purged slots in the ledger, for `slot-tombstones` \
+                           to tell why they are missing")
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("slot-tombstones")
+                .about("Output the records of the slots purged from the ledger, written when \
+                        the validator runs with --ledger-purge-tombstones")
+                .arg(
+                    Arg::with_name("slot")
+                        .index(1)
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .help("Only output the record of the purge that removed this slot"),
+                )
+                .arg(
+                    Arg::with_name("num_records")
+                        .long("num-records")
+                        .value_name("NUM")
+                        .takes_value(true)
+                        .default_value(DEFAULT_SLOT_TOMBSTONES_COUNT)
+                        .help("Number of the latest records in the output"),
+                )
         )
         .subcommand(
             SubCommand::with_name("list-roots")
@@ -1771,6 +1926,26 @@ fn main() {
                         .help("Number of slots in the output"),
                 )
         )
+        .subcommand(
+            SubCommand::with_name("block-costs")
+                .about("Output the compute units, cost, and fees of up to the most recent \
+                        <num-slots> blocks frozen by the validator, without replaying them")
+                .arg(
+                    Arg::with_name("num_slots")
+                        .long("num-slots")
+                        .value_name("NUM")
+                        .takes_value(true)
+                        .default_value(DEFAULT_BLOCK_COSTS_COUNT)
+                        .required(false)
+                        .help("Number of slots in the output"),
+                )
+                .arg(
+                    Arg::with_name("programs")
+                        .long("programs")
+                        .takes_value(false)
+                        .help("Also output the compute units consumed by each program"),
+                )
+        )
         .subcommand(
             SubCommand::with_name("repair-roots")
                 .about("Traverses the AncestorIterator backward from a last known root \
@@ -1818,6 +1993,36 @@ fn main() {
                     .help("Slots that their blocks are computed for cost, default to all slots in ledger"),
             )
         )
+        .subcommand(
+            SubCommand::with_name("transaction-graph")
+            .about("Export the account dependency graph among the transactions of each \
+                    full slot in a range, along with how parallel the slots could have been \
+                    executed compared to the entries they were recorded in")
+            .arg(&starting_slot_arg)
+            .arg(&ending_slot_arg)
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .value_name("FORMAT")
+                    .takes_value(true)
+                    .possible_values(&["json", "dot"])
+                    .default_value("json")
+                    .help("Output format of the graph"),
+            )
+            .arg(
+                Arg::with_name("include_votes")
+                    .long("include-votes")
+                    .takes_value(false)
+                    .help("Include vote transactions in the graph"),
+            )
+            .arg(
+                Arg::with_name("graph_filename")
+                    .index(1)
+                    .value_name("FILENAME")
+                    .takes_value(true)
+                    .help("Output file, the graph is written to stdout if not specified"),
+            )
+        )
         .get_matches();
 
     info!("{} {}", crate_name!(), solana_version::version!());
@@ -1832,9 +2037,7 @@ fn main() {
             .ok()
             .map(PathBuf::from);
 
-    let wal_recovery_mode = matches
-        .value_of("wal_recovery_mode")
-        .map(BlockstoreRecoveryMode::from);
+    let wal_recovery_mode = value_t!(matches, "wal_recovery_mode", BlockstoreRecoveryMode).ok();
     let verbose_level = matches.occurrences_of("verbose");
 
     if let ("bigtable", Some(arg_matches)) = matches.subcommand() {
@@ -2088,6 +2291,67 @@ fn main() {
                     println!("{}", slot);
                 }
             }
+            ("dead-slots-report", Some(arg_matches)) => {
+                let blockstore =
+                    open_blockstore(&ledger_path, AccessType::Secondary, wal_recovery_mode);
+                let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+                let ending_slot = value_t!(arg_matches, "ending_slot", Slot).unwrap_or(Slot::MAX);
+                let dead_slots = blockstore
+                    .dead_slots_iterator(starting_slot)
+                    .unwrap()
+                    .take_while(|slot| *slot <= ending_slot);
+                let duplicate_slots = blockstore
+                    .duplicate_slots_iterator(starting_slot)
+                    .unwrap()
+                    .take_while(|slot| *slot <= ending_slot);
+                let slots: BTreeSet<Slot> = dead_slots.chain(duplicate_slots).collect();
+
+                println!(
+                    "{:>20} {:>9} {:>9} {:>32}  Cause",
+                    "Slot", "Dead", "Duplicate", "Marked"
+                );
+                for slot in slots {
+                    let is_dead = blockstore.is_dead(slot);
+                    let (cause, marked) = match blockstore.get_dead_slot_cause(slot) {
+                        Ok(Some(meta)) => {
+                            let timestamp = meta.timestamp();
+                            let t = UNIX_EPOCH + Duration::from_millis(timestamp);
+                            let datetime: DateTime<Utc> = t.into();
+                            (meta.cause().to_string(), datetime.to_rfc3339())
+                        }
+                        Ok(None) if is_dead => ("unknown".to_string(), "-".to_string()),
+                        Ok(None) => ("-".to_string(), "-".to_string()),
+                        Err(err) => (format!("failed to read: {:?}", err), "-".to_string()),
+                    };
+                    let duplicate_proof = blockstore.get_duplicate_slot(slot);
+                    let is_duplicate = duplicate_proof.is_some();
+                    let duplicate_cause = duplicate_proof.map(|proof| {
+                        let describe =
+                            |payload: Vec<u8>| match Shred::new_from_serialized_shred(payload) {
+                                Ok(shred) => {
+                                    format!("{:?} shred {}", shred.shred_type(), shred.index())
+                                }
+                                Err(err) => format!("invalid shred: {:?}", err),
+                            };
+                        format!(
+                            "conflicting shreds: {} / {}",
+                            describe(proof.shred1),
+                            describe(proof.shred2)
+                        )
+                    });
+                    let cause = match duplicate_cause {
+                        Some(duplicate_cause) if is_dead => {
+                            format!("{}; {}", cause, duplicate_cause)
+                        }
+                        Some(duplicate_cause) => duplicate_cause,
+                        None => cause,
+                    };
+                    println!(
+                        "{:>20} {:>9} {:>9} {:>32}  {}",
+                        slot, is_dead, is_duplicate, marked, cause
+                    );
+                }
+            }
             ("set-dead-slot", Some(arg_matches)) => {
                 let slots = values_t_or_exit!(arg_matches, "slots", Slot);
                 let blockstore =
@@ -2099,6 +2363,204 @@ fn main() {
                     }
                 }
             }
+            ("migrate-shred-storage", Some(arg_matches)) => {
+                let shred_storage_type = match arg_matches.value_of("shred_storage").unwrap() {
+                    "fifo" => {
+                        ShredStorageType::RocksFifo(BlockstoreRocksFifoOptions::new_for_total_size(
+                            value_t_or_exit!(arg_matches, "fifo_shred_storage_size", u64),
+                        ))
+                    }
+                    _ => ShredStorageType::RocksLevel,
+                };
+                let mut last_cf_name = "";
+                let result = Blockstore::migrate_shred_storage(
+                    &ledger_path,
+                    BlockstoreOptions {
+                        recovery_mode: wal_recovery_mode,
+                        column_options: LedgerColumnOptions {
+                            shred_storage_type,
+                            ..LedgerColumnOptions::default()
+                        },
+                        ..BlockstoreOptions::default()
+                    },
+                    |cf_name, copied| {
+                        if cf_name != last_cf_name {
+                            if !last_cf_name.is_empty() {
+                                println!();
+                            }
+                            last_cf_name = cf_name;
+                        }
+                        print!("\rCopying {}: {} entries", cf_name, copied);
+                        let _ = stdout().flush();
+                    },
+                );
+                if !last_cf_name.is_empty() {
+                    println!();
+                }
+                match result {
+                    Ok(Some(copied)) => println!("Migrated {} entries", copied),
+                    Ok(None) => println!("No ledger of another shred storage type to migrate"),
+                    Err(err) => {
+                        eprintln!("Failed to migrate the shred storage: {:?}", err);
+                        exit(1);
+                    }
+                }
+            }
+            ("move-columns", Some(arg_matches)) => {
+                let column_layout = arg_matches
+                    .values_of("column_path")
+                    .map(ColumnLayout::from_column_paths)
+                    .transpose()
+                    .unwrap()
+                    .unwrap_or_default();
+                for shred_storage_type in [
+                    ShredStorageType::RocksLevel,
+                    ShredStorageType::RocksFifo(BlockstoreRocksFifoOptions::default()),
+                ] {
+                    let blockstore_path =
+                        ledger_path.join(Blockstore::blockstore_directory(&shred_storage_type));
+                    if !blockstore_path.exists() {
+                        continue;
+                    }
+                    let mut last_cf_name = "";
+                    let result = Blockstore::relocate_columns(
+                        &ledger_path,
+                        BlockstoreOptions {
+                            recovery_mode: wal_recovery_mode.clone(),
+                            enforce_ulimit_nofile: true,
+                            column_options: LedgerColumnOptions {
+                                shred_storage_type,
+                                ..LedgerColumnOptions::default()
+                            },
+                            column_layout: column_layout.clone(),
+                            ..BlockstoreOptions::default()
+                        },
+                        |cf_name, copied| {
+                            if cf_name != last_cf_name {
+                                if !last_cf_name.is_empty() {
+                                    println!();
+                                }
+                                last_cf_name = cf_name;
+                            }
+                            print!("\rMoving {}: {} entries", cf_name, copied);
+                            let _ = stdout().flush();
+                        },
+                    );
+                    if !last_cf_name.is_empty() {
+                        println!();
+                    }
+                    match result {
+                        Ok(copied) => println!("Moved {} entries of {:?}", copied, blockstore_path),
+                        Err(err) => {
+                            eprintln!(
+                                "Failed to move the columns of {:?}: {:?}",
+                                blockstore_path, err
+                            );
+                            exit(1);
+                        }
+                    }
+                }
+            }
+            ("unlock", Some(arg_matches)) => {
+                let force = arg_matches.is_present("force");
+                for blockstore_directory in [
+                    BLOCKSTORE_DIRECTORY_ROCKS_LEVEL,
+                    BLOCKSTORE_DIRECTORY_ROCKS_FIFO,
+                ] {
+                    let blockstore_path = ledger_path.join(blockstore_directory);
+                    if !blockstore_path.exists() {
+                        continue;
+                    }
+                    match BlockstoreLock::unlock(&blockstore_path, force) {
+                        Ok(Some(owner)) => {
+                            println!("Unlocked {:?}, locked by {}", blockstore_path, owner)
+                        }
+                        Ok(None) => println!("Unlocked {:?}", blockstore_path),
+                        Err(BlockstoreError::BlockstoreLocked(_, owner)) => {
+                            eprintln!(
+                                "{:?} is in use by {}. Pass --force if it is known to be down",
+                                blockstore_path,
+                                owner.map_or_else(
+                                    || "another process".to_string(),
+                                    |owner| owner.to_string()
+                                ),
+                            );
+                            exit(1);
+                        }
+                        Err(err) => {
+                            eprintln!("Failed to unlock {:?}: {:?}", blockstore_path, err);
+                            exit(1);
+                        }
+                    }
+                }
+            }
+            ("export-slots", Some(arg_matches)) => {
+                let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+                let ending_slot = value_t!(arg_matches, "ending_slot", Slot).unwrap_or(Slot::MAX);
+                let output_file = value_t_or_exit!(arg_matches, "output_file", String);
+                let blockstore =
+                    open_blockstore(&ledger_path, AccessType::Secondary, wal_recovery_mode);
+                let result = File::create(&output_file)
+                    .map_err(BlockstoreError::from)
+                    .and_then(|file| {
+                        blockstore.export_slots(starting_slot..=ending_slot, BufWriter::new(file))
+                    });
+                match result {
+                    Ok(stats) => println!("Exported {} to {}", stats, output_file),
+                    Err(err) => {
+                        eprintln!("Failed to export slots to {}: {:?}", output_file, err);
+                        exit(1);
+                    }
+                }
+            }
+            ("import-slots", Some(arg_matches)) => {
+                let input_file = value_t_or_exit!(arg_matches, "input_file", String);
+                let blockstore =
+                    open_blockstore(&ledger_path, AccessType::Primary, wal_recovery_mode);
+                let result = File::open(&input_file)
+                    .map_err(BlockstoreError::from)
+                    .and_then(|file| blockstore.import_slots(BufReader::new(file)));
+                match result {
+                    Ok(stats) => println!("Imported {} from {}", stats, input_file),
+                    Err(err) => {
+                        eprintln!("Failed to import slots from {}: {:?}", input_file, err);
+                        exit(1);
+                    }
+                }
+            }
+            ("rebuild-tx-index", Some(arg_matches)) => {
+                let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+                let ending_slot = value_t!(arg_matches, "ending_slot", Slot).unwrap_or(Slot::MAX);
+                let blockstore =
+                    open_blockstore(&ledger_path, AccessType::Primary, wal_recovery_mode);
+                let exit = AtomicBool::new(false);
+                match blockstore.rebuild_address_signatures(starting_slot..=ending_slot, &exit) {
+                    Ok(stats) => println!("Rebuilt the address signatures of {}", stats),
+                    Err(err) => {
+                        eprintln!("Failed to rebuild the address signatures: {:?}", err);
+                        exit(1);
+                    }
+                }
+            }
+            ("create-checkpoint", Some(arg_matches)) => {
+                let checkpoint_path =
+                    PathBuf::from(value_t_or_exit!(arg_matches, "checkpoint_path", String));
+                let blockstore = open_blockstore(
+                    &ledger_path,
+                    AccessType::PrimaryForMaintenance,
+                    wal_recovery_mode,
+                );
+                match blockstore.create_checkpoint(&checkpoint_path) {
+                    Ok(()) => println!("Created a checkpoint in {:?}", checkpoint_path),
+                    Err(err) => {
+                        eprintln!(
+                            "Failed to create a checkpoint in {:?}: {:?}",
+                            checkpoint_path, err
+                        );
+                        exit(1);
+                    }
+                }
+            }
             ("remove-dead-slot", Some(arg_matches)) => {
                 let slots = values_t_or_exit!(arg_matches, "slots", Slot);
                 let blockstore =
@@ -3336,7 +3798,8 @@ fn main() {
                 } else {
                     AccessType::PrimaryForMaintenance
                 };
-                let blockstore = open_blockstore(&ledger_path, access_type, wal_recovery_mode);
+                let mut blockstore = open_blockstore(&ledger_path, access_type, wal_recovery_mode);
+                blockstore.set_record_slot_tombstones(arg_matches.is_present("record_tombstones"));
 
                 let end_slot = match end_slot {
                     Some(end_slot) => end_slot,
@@ -3378,6 +3841,14 @@ fn main() {
                     } else {
                         blockstore.purge_and_compact_slots(start_slot, end_slot);
                     }
+                    if let Err(err) =
+                        blockstore.record_slot_purge(start_slot, end_slot, SlotPurgeReason::Manual)
+                    {
+                        warn!(
+                            "Failed to record the purge of slots {} to {}: {:?}",
+                            start_slot, end_slot, err
+                        );
+                    }
                 };
                 if !dead_slots_only {
                     let slots_iter = &(start_slot..=end_slot).chunks(batch_size);
@@ -3406,6 +3877,34 @@ fn main() {
                     }
                 }
             }
+            ("slot-tombstones", Some(arg_matches)) => {
+                let blockstore =
+                    open_blockstore(&ledger_path, AccessType::Secondary, wal_recovery_mode);
+                let tombstones = match value_t!(arg_matches, "slot", Slot) {
+                    Ok(slot) => blockstore
+                        .get_slot_tombstone(slot)
+                        .map(|tombstone| tombstone.into_iter().collect()),
+                    Err(_) => {
+                        let num_records = value_t_or_exit!(arg_matches, "num_records", usize);
+                        blockstore.get_recent_slot_tombstones(num_records)
+                    }
+                }
+                .expect("Failed to get slot tombstones");
+                if tombstones.is_empty() {
+                    println!("No purged slots recorded");
+                }
+                for tombstone in tombstones {
+                    let datetime: DateTime<Utc> =
+                        (UNIX_EPOCH + Duration::from_millis(tombstone.timestamp as u64)).into();
+                    println!(
+                        "Slots {} to {} purged at {}: {}",
+                        tombstone.first_slot,
+                        tombstone.last_slot,
+                        datetime.to_rfc3339(),
+                        tombstone.reason,
+                    );
+                }
+            }
             ("list-roots", Some(arg_matches)) => {
                 let blockstore =
                     open_blockstore(&ledger_path, AccessType::Secondary, wal_recovery_mode);
@@ -3489,6 +3988,34 @@ fn main() {
                     println!("{:>20} {:>44} {:>32}", slot, &hash_str, &time_str);
                 }
             }
+            ("block-costs", Some(arg_matches)) => {
+                let blockstore =
+                    open_blockstore(&ledger_path, AccessType::Secondary, wal_recovery_mode);
+                let num_slots = value_t_or_exit!(arg_matches, "num_slots", usize);
+                let show_programs = arg_matches.is_present("programs");
+                let block_costs = blockstore
+                    .get_recent_block_costs(num_slots)
+                    .expect("Failed to get block costs");
+                println!(
+                    "{:>20} {:>16} {:>16} {:>12} {:>16}",
+                    "Slot", "Compute Units", "Block Cost", "Utilization", "Fees"
+                );
+                for (slot, block_cost) in block_costs.iter().rev() {
+                    println!(
+                        "{:>20} {:>16} {:>16} {:>11.2}% {:>16}",
+                        slot,
+                        block_cost.compute_units,
+                        block_cost.block_cost,
+                        block_cost.utilization() * 100.0,
+                        block_cost.fees,
+                    );
+                    if show_programs {
+                        for (program_id, units) in &block_cost.program_compute_units {
+                            println!("{:>64} {:>16}", program_id.to_string(), units);
+                        }
+                    }
+                }
+            }
             ("repair-roots", Some(arg_matches)) => {
                 let blockstore =
                     open_blockstore(&ledger_path, AccessType::Primary, wal_recovery_mode);
@@ -3624,6 +4151,64 @@ fn main() {
                     }
                 }
             }
+            ("transaction-graph", Some(arg_matches)) => {
+                let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+                let ending_slot = value_t!(arg_matches, "ending_slot", Slot).unwrap_or(Slot::MAX);
+                let include_votes = arg_matches.is_present("include_votes");
+                let output_file = value_t!(arg_matches, "graph_filename", String).ok();
+                let blockstore =
+                    open_blockstore(&ledger_path, AccessType::Secondary, wal_recovery_mode);
+
+                let slots: Vec<_> = blockstore
+                    .slot_meta_iterator(starting_slot)
+                    .unwrap_or_else(|err| {
+                        eprintln!("Failed to load slot metas: {:?}", err);
+                        exit(1);
+                    })
+                    .take_while(|(slot, _)| *slot <= ending_slot)
+                    .filter(|(_, slot_meta)| slot_meta.is_full())
+                    .map(|(slot, _)| slot)
+                    .collect();
+                let graphs: Vec<_> = slots
+                    .into_iter()
+                    .filter_map(|slot| {
+                        build_slot_transaction_graph(&blockstore, slot, include_votes)
+                            .map_err(|err| eprintln!("{}", err))
+                            .ok()
+                    })
+                    .collect();
+
+                let output = match arg_matches.value_of("format") {
+                    Some("dot") => graphs_to_dot(&graphs),
+                    _ => serde_json::to_string_pretty(&graphs).unwrap(),
+                };
+                match output_file {
+                    None => println!("{}", output),
+                    Some(output_file) => {
+                        if let Err(err) = File::create(&output_file)
+                            .and_then(|mut file| file.write_all(output.as_bytes()))
+                        {
+                            eprintln!("Unable to write {}: {}", output_file, err);
+                            exit(1);
+                        }
+                        for graph in &graphs {
+                            println!(
+                                "Slot: {}, Transactions: {}, Dependencies: {}, \
+                                 Critical path: {}, Entries: {}, \
+                                 Theoretical parallelism: {:.2}, Achieved parallelism: {:.2}",
+                                graph.slot,
+                                graph.transactions.len(),
+                                graph.edges.len(),
+                                graph.critical_path_length,
+                                graph.num_entries,
+                                graph.theoretical_parallelism(),
+                                graph.achieved_parallelism(),
+                            );
+                        }
+                        println!("Wrote {}", output_file);
+                    }
+                }
+            }
             ("", _) => {
                 eprintln!("{}", matches.usage());
                 exit(1);
@@ -0,0 +1,324 @@
+//! The `transaction-graph` subcommand exports the account dependency graph among the
+//! transactions of each slot in a range. A transaction depends on an earlier one in the same
+//! slot if both lock an account and at least one of them writes it. The longest chain of
+//! dependencies bounds how parallel the slot could have been executed, which is compared to
+//! the number of entries the leader actually recorded the transactions in.
+use {
+    log::*,
+    serde::Serialize,
+    solana_ledger::blockstore::Blockstore,
+    solana_sdk::{
+        clock::Slot,
+        message::v0::LoadedAddresses,
+        pubkey::Pubkey,
+        transaction::{
+            MessageHash, SanitizedTransaction, SimpleAddressLoader, VersionedTransaction,
+        },
+    },
+    std::{collections::HashMap, fmt::Write},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyKind {
+    /// Both transactions write the account
+    WriteWrite,
+    /// The later transaction reads an account the earlier one writes
+    ReadAfterWrite,
+    /// The later transaction writes an account the earlier one reads
+    WriteAfterRead,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionNode {
+    pub signature: String,
+    /// Index of the entry the transaction was recorded in
+    pub entry: usize,
+    pub is_vote: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyEdge {
+    /// Indexes of the transactions in the slot
+    pub from: usize,
+    pub to: usize,
+    pub account: String,
+    pub kind: DependencyKind,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotTransactionGraph {
+    pub slot: Slot,
+    pub transactions: Vec<TransactionNode>,
+    pub edges: Vec<DependencyEdge>,
+    /// Number of entries holding at least one of the transactions
+    pub num_entries: usize,
+    /// Number of transactions on the longest chain of dependencies
+    pub critical_path_length: usize,
+    /// Transactions that were skipped because their accounts couldn't be resolved
+    pub num_unresolved_transactions: usize,
+    #[serde(skip)]
+    accounts: HashMap<Pubkey, AccountAccess>,
+    #[serde(skip)]
+    depths: Vec<usize>,
+}
+
+// Accesses of an account by the transactions added so far
+#[derive(Default)]
+struct AccountAccess {
+    last_writer: Option<usize>,
+    // Transactions reading the account since it was last written
+    readers: Vec<usize>,
+}
+
+impl SlotTransactionGraph {
+    pub fn new(slot: Slot) -> Self {
+        Self {
+            slot,
+            ..Self::default()
+        }
+    }
+
+    /// Transactions per step if every transaction ran as soon as its dependencies did
+    pub fn theoretical_parallelism(&self) -> f64 {
+        self.transactions.len() as f64 / self.critical_path_length.max(1) as f64
+    }
+
+    /// Transactions per entry as recorded by the leader
+    pub fn achieved_parallelism(&self) -> f64 {
+        self.transactions.len() as f64 / self.num_entries.max(1) as f64
+    }
+
+    /// Adds a transaction following all the transactions added so far. Only the nearest
+    /// conflicting transactions are linked, earlier ones are reachable through them.
+    pub fn add_transaction(
+        &mut self,
+        node: TransactionNode,
+        writable: &[&Pubkey],
+        readonly: &[&Pubkey],
+    ) {
+        let index = self.transactions.len();
+        if self
+            .transactions
+            .last()
+            .map(|last| last.entry != node.entry)
+            .unwrap_or(true)
+        {
+            self.num_entries += 1;
+        }
+        self.transactions.push(node);
+
+        let num_edges = self.edges.len();
+        for account in writable {
+            let access = self.accounts.entry(**account).or_default();
+            if access.readers.is_empty() {
+                if let Some(last_writer) = access.last_writer {
+                    self.edges.push(DependencyEdge {
+                        from: last_writer,
+                        to: index,
+                        account: account.to_string(),
+                        kind: DependencyKind::WriteWrite,
+                    });
+                }
+            }
+            for reader in access.readers.drain(..) {
+                self.edges.push(DependencyEdge {
+                    from: reader,
+                    to: index,
+                    account: account.to_string(),
+                    kind: DependencyKind::WriteAfterRead,
+                });
+            }
+            access.last_writer = Some(index);
+        }
+        for account in readonly {
+            let access = self.accounts.entry(**account).or_default();
+            if let Some(last_writer) = access.last_writer {
+                self.edges.push(DependencyEdge {
+                    from: last_writer,
+                    to: index,
+                    account: account.to_string(),
+                    kind: DependencyKind::ReadAfterWrite,
+                });
+            }
+            access.readers.push(index);
+        }
+
+        let depth = 1 + self.edges[num_edges..]
+            .iter()
+            .map(|edge| self.depths[edge.from])
+            .max()
+            .unwrap_or(0);
+        self.depths.push(depth);
+        self.critical_path_length = self.critical_path_length.max(depth);
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "  subgraph cluster_{} {{", self.slot);
+        let _ = writeln!(dot, "    label=\"Slot {}\";", self.slot);
+        for (index, node) in self.transactions.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    \"{}_{}\" [label=\"{}\\nentry {}\"{}];",
+                self.slot,
+                index,
+                &node.signature[..node.signature.len().min(8)],
+                node.entry,
+                if node.is_vote { ",color=gray" } else { "" },
+            );
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                dot,
+                "    \"{}_{}\" -> \"{}_{}\" [label=\"{}\"{}];",
+                self.slot,
+                edge.from,
+                self.slot,
+                edge.to,
+                &edge.account[..edge.account.len().min(8)],
+                match edge.kind {
+                    DependencyKind::WriteWrite => "",
+                    DependencyKind::ReadAfterWrite => ",style=dashed",
+                    DependencyKind::WriteAfterRead => ",style=dotted",
+                },
+            );
+        }
+        dot.push_str("  }\n");
+        dot
+    }
+}
+
+// Resolves the accounts of the transaction, loading the addresses of its lookup tables from
+// the transaction status recorded for it, if any
+fn sanitize_transaction(
+    blockstore: &Blockstore,
+    slot: Slot,
+    transaction: VersionedTransaction,
+) -> Option<SanitizedTransaction> {
+    let loaded_addresses = match transaction.message.address_table_lookups() {
+        Some(lookups) if !lookups.is_empty() => {
+            blockstore
+                .read_transaction_status((transaction.signatures[0], slot))
+                .ok()
+                .flatten()?
+                .loaded_addresses
+        }
+        _ => LoadedAddresses::default(),
+    };
+    SanitizedTransaction::try_create(
+        transaction,
+        MessageHash::Compute,
+        None,
+        SimpleAddressLoader::Enabled(loaded_addresses),
+        true, // require_static_program_ids
+    )
+    .map_err(|err| warn!("Failed to sanitize transaction in slot {}: {:?}", slot, err))
+    .ok()
+}
+
+pub fn build_slot_transaction_graph(
+    blockstore: &Blockstore,
+    slot: Slot,
+    include_votes: bool,
+) -> Result<SlotTransactionGraph, String> {
+    let entries = blockstore
+        .get_slot_entries(slot, 0)
+        .map_err(|err| format!("Failed to load entries for slot {}: {:?}", slot, err))?;
+    let mut graph = SlotTransactionGraph::new(slot);
+    for (entry_index, entry) in entries.into_iter().enumerate() {
+        for transaction in entry.transactions {
+            let signature = transaction.signatures[0].to_string();
+            let transaction = match sanitize_transaction(blockstore, slot, transaction) {
+                Some(transaction) => transaction,
+                None => {
+                    graph.num_unresolved_transactions += 1;
+                    continue;
+                }
+            };
+            if !include_votes && transaction.is_simple_vote_transaction() {
+                continue;
+            }
+            let account_locks = transaction.get_account_locks_unchecked();
+            graph.add_transaction(
+                TransactionNode {
+                    signature,
+                    entry: entry_index,
+                    is_vote: transaction.is_simple_vote_transaction(),
+                },
+                &account_locks.writable,
+                &account_locks.readonly,
+            );
+        }
+    }
+    Ok(graph)
+}
+
+pub fn graphs_to_dot(graphs: &[SlotTransactionGraph]) -> String {
+    let mut dot = String::from("digraph {\n");
+    for graph in graphs {
+        dot.push_str(&graph.to_dot());
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(entry: usize) -> TransactionNode {
+        TransactionNode {
+            signature: Pubkey::new_unique().to_string(),
+            entry,
+            is_vote: false,
+        }
+    }
+
+    #[test]
+    fn test_slot_transaction_graph() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let c = Pubkey::new_unique();
+        let mut graph = SlotTransactionGraph::new(1);
+        graph.add_transaction(node(0), &[&a], &[]);
+        graph.add_transaction(node(0), &[&b], &[]);
+        // Reads of the same account don't conflict with each other
+        graph.add_transaction(node(1), &[], &[&a, &c]);
+        graph.add_transaction(node(1), &[], &[&a]);
+        graph.add_transaction(node(2), &[&a], &[&b]);
+
+        let edges: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to, edge.kind))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (0, 2, DependencyKind::ReadAfterWrite),
+                (0, 3, DependencyKind::ReadAfterWrite),
+                (2, 4, DependencyKind::WriteAfterRead),
+                (3, 4, DependencyKind::WriteAfterRead),
+                (1, 4, DependencyKind::ReadAfterWrite),
+            ]
+        );
+        assert_eq!(graph.critical_path_length, 3);
+        assert_eq!(graph.num_entries, 3);
+        assert!((graph.theoretical_parallelism() - 5.0 / 3.0).abs() < f64::EPSILON);
+        assert!((graph.achieved_parallelism() - 5.0 / 3.0).abs() < f64::EPSILON);
+
+        graph.add_transaction(node(3), &[&a], &[]);
+        assert_eq!(
+            graph
+                .edges
+                .last()
+                .map(|edge| (edge.from, edge.to, edge.kind)),
+            Some((4, 5, DependencyKind::WriteWrite))
+        );
+        assert_eq!(graph.critical_path_length, 4);
+    }
+}
@@ -155,3 +155,49 @@ fn bench_insert_data_shred_big(bench: &mut Bencher) {
     });
     Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
 }
+
+// Insert the shreds of `num_slots` new slots at once, interleaved the way turbine delivers them,
+// to compare how the insertion scales with the slots in flight
+fn bench_insert_shreds_slots(bench: &mut Bencher, num_slots: u64) {
+    let ledger_path = get_tmp_ledger_path!();
+    let blockstore =
+        Blockstore::open(&ledger_path).expect("Expected to be able to open database ledger");
+    let num_entries = 8 * 1024 / num_slots;
+    let entries = create_ticks(num_entries, 0, Hash::default());
+    let mut first_slot = 1;
+    bench.iter(|| {
+        let slot_shreds: Vec<_> = (first_slot..first_slot + num_slots)
+            .map(|slot| entries_to_test_shreds(&entries, slot, slot - 1, true, 0))
+            .collect();
+        let num_shreds = slot_shreds.iter().map(Vec::len).max().unwrap();
+        let shreds: Vec<_> = (0..num_shreds)
+            .flat_map(|index| {
+                slot_shreds
+                    .iter()
+                    .filter_map(move |shreds| shreds.get(index))
+            })
+            .cloned()
+            .collect();
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        first_slot += num_slots;
+    });
+    Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
+}
+
+#[bench]
+#[ignore]
+fn bench_insert_shreds_one_slot(bench: &mut Bencher) {
+    bench_insert_shreds_slots(bench, 1);
+}
+
+#[bench]
+#[ignore]
+fn bench_insert_shreds_four_slots(bench: &mut Bencher) {
+    bench_insert_shreds_slots(bench, 4);
+}
+
+#[bench]
+#[ignore]
+fn bench_insert_shreds_eight_slots(bench: &mut Bencher) {
+    bench_insert_shreds_slots(bench, 8);
+}
@@ -213,6 +213,11 @@ fn bank_forks_from_snapshot(
     }
 
     deserialized_bank.set_compute_budget(process_options.runtime_config.compute_budget);
+    deserialized_bank.set_transaction_account_lock_limit(
+        process_options
+            .runtime_config
+            .transaction_account_lock_limit,
+    );
 
     let full_snapshot_hash = FullSnapshotHash {
         hash: (
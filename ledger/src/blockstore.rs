@@ -5,14 +5,16 @@ use {
     crate::{
         ancestor_iterator::AncestorIterator,
         blockstore_db::{
-            columns as cf, Column, Database, IteratorDirection, IteratorMode, LedgerColumn, Result,
-            WriteBatch,
+            columns as cf, Column, Database, IteratorDirection, IteratorMode, LedgerColumn,
+            LedgerSnapshot, Result, WriteBatch,
         },
         blockstore_meta::*,
         blockstore_options::{
-            AccessType, BlockstoreOptions, LedgerColumnOptions, ShredStorageType,
+            AccessType, BlockstoreOptions, BlockstoreRocksFifoOptions, ColumnLayout,
+            LedgerColumnOptions, ShredStorageType,
         },
         leader_schedule_cache::LeaderScheduleCache,
+        ledger_archive::LedgerArchive,
         next_slots_iterator::NextSlotsIterator,
         shred::{self, max_ticks_per_n_shreds, ErasureSetId, Shred, ShredId, ShredType, Shredder},
         slot_stats::{ShredSource, SlotsStats},
@@ -21,7 +23,7 @@ use {
     crossbeam_channel::{bounded, Receiver, Sender, TrySendError},
     log::*,
     rayon::{
-        iter::{IntoParallelRefIterator, ParallelIterator},
+        iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
         ThreadPool,
     },
     rocksdb::DBRawIterator,
@@ -36,7 +38,7 @@ use {
     solana_sdk::{
         clock::{Slot, UnixTimestamp, DEFAULT_TICKS_PER_SECOND, MS_PER_TICK},
         genesis_config::{GenesisConfig, DEFAULT_GENESIS_ARCHIVE, DEFAULT_GENESIS_FILE},
-        hash::Hash,
+        hash::{hashv, Hash},
         pubkey::Pubkey,
         signature::{Keypair, Signature, Signer},
         timing::timestamp,
@@ -52,11 +54,12 @@ use {
         borrow::Cow,
         cell::RefCell,
         cmp,
-        collections::{hash_map::Entry as HashMapEntry, BTreeSet, HashMap, HashSet},
+        collections::{hash_map::Entry as HashMapEntry, BTreeMap, BTreeSet, HashMap, HashSet},
         convert::TryInto,
         fmt::Write,
         fs,
         io::{Error as IoError, ErrorKind},
+        ops::RangeInclusive,
         path::{Path, PathBuf},
         rc::Rc,
         sync::{
@@ -68,19 +71,34 @@ use {
     thiserror::Error,
     trees::{Tree, TreeWalk},
 };
+pub mod blockstore_export;
+pub mod blockstore_integrity;
 pub mod blockstore_purge;
+pub mod blockstore_tx_index;
 pub use {
     crate::{
         blockstore_db::BlockstoreError,
         blockstore_meta::{OptimisticSlotMetaVersioned, SlotMeta},
     },
+    blockstore_export::SlotExportStats,
+    blockstore_integrity::{BlockstoreIntegrityIssue, BlockstoreIntegrityReport},
     blockstore_purge::PurgeType,
+    blockstore_tx_index::AddressSignaturesRebuildStats,
     rocksdb::properties as RocksProperties,
 };
 
 pub const BLOCKSTORE_DIRECTORY_ROCKS_LEVEL: &str = "rocksdb";
 pub const BLOCKSTORE_DIRECTORY_ROCKS_FIFO: &str = "rocksdb_fifo";
 
+// Entries per write batch when migrating a blockstore to another shred storage type
+const SHRED_STORAGE_MIGRATION_BATCH_SIZE: usize = 4096;
+// Entries per write batch when moving columns to other directories
+const COLUMN_RELOCATION_BATCH_SIZE: usize = 4096;
+
+// Threads inserting the shreds of different slots at once. Turbine and repair rarely have
+// shreds of more than a few slots in flight, so more threads would mostly sit idle.
+const MAX_INSERT_SHREDS_THREADS: usize = 4;
+
 // get_max_thread_count to match number of threads in the old code.
 // see: https://github.com/solana-labs/solana/pull/24853
 lazy_static! {
@@ -94,6 +112,11 @@ lazy_static! {
         .thread_name(|ix| format!("blockstore_{}", ix))
         .build()
         .unwrap();
+    static ref INSERT_SHREDS_THREAD_POOL: ThreadPool = rayon::ThreadPoolBuilder::new()
+        .num_threads(get_max_thread_count().min(MAX_INSERT_SHREDS_THREADS))
+        .thread_name(|ix| format!("blockstore_insert_{}", ix))
+        .build()
+        .unwrap();
 }
 
 pub const MAX_REPLAY_WAKE_UP_SIGNALS: usize = 1;
@@ -148,6 +171,7 @@ pub struct Blockstore {
     db: Arc<Database>,
     meta_cf: LedgerColumn<cf::SlotMeta>,
     dead_slots_cf: LedgerColumn<cf::DeadSlots>,
+    dead_slot_causes_cf: LedgerColumn<cf::DeadSlotCauses>,
     duplicate_slots_cf: LedgerColumn<cf::DuplicateSlots>,
     erasure_meta_cf: LedgerColumn<cf::ErasureMeta>,
     orphans_cf: LedgerColumn<cf::Orphans>,
@@ -166,6 +190,9 @@ pub struct Blockstore {
     program_costs_cf: LedgerColumn<cf::ProgramCosts>,
     bank_hash_cf: LedgerColumn<cf::BankHash>,
     optimistic_slots_cf: LedgerColumn<cf::OptimisticSlots>,
+    block_cost_cf: LedgerColumn<cf::BlockCost>,
+    slot_tombstones_cf: LedgerColumn<cf::SlotTombstones>,
+    duplicate_proofs_cf: LedgerColumn<cf::DuplicateProofs>,
     last_root: RwLock<Slot>,
     insert_shreds_lock: Mutex<()>,
     new_shreds_signals: Mutex<Vec<Sender<bool>>>,
@@ -174,6 +201,8 @@ pub struct Blockstore {
     pub lowest_cleanup_slot: RwLock<Slot>,
     no_compaction: bool,
     pub slots_stats: SlotsStats,
+    ledger_archive: Option<Arc<LedgerArchive>>,
+    record_slot_tombstones: bool,
 }
 
 pub struct IndexMetaWorkingSetEntry {
@@ -198,9 +227,26 @@ pub struct SlotMetaWorkingSetEntry {
     did_insert_occur: bool,
 }
 
+/// The outcome of inserting the shreds of one slot, see `Blockstore::insert_slot_shreds`
+struct SlotInsertion<'a> {
+    slot: Slot,
+    /// The shreds, erasure metas and index of the slot, to which its `SlotMeta`
+    /// is added once chained
+    write_batch: WriteBatch<'a>,
+    /// The new and the persisted `SlotMeta` of the slot, if a data shred was inserted
+    slot_meta: Option<(SlotMeta, Option<SlotMeta>)>,
+    completed_data_sets: Vec<CompletedDataSetInfo>,
+    /// Indices of the inserted shreds in the input of `insert_shreds_handle_duplicate`
+    inserted_indices: Vec<usize>,
+    recovered_data_shreds: Vec<Shred>,
+    metrics: BlockstoreInsertionMetrics,
+}
+
 #[derive(Default)]
 pub struct BlockstoreInsertionMetrics {
     pub num_shreds: usize,
+    /// Slots of the shreds, each inserted on its own thread
+    pub num_slots: usize,
     pub insert_lock_elapsed: u64,
     pub insert_shreds_elapsed: u64,
     pub shred_recovery_elapsed: u64,
@@ -317,8 +363,39 @@ impl BlockstoreInsertionMetrics {
                 self.num_coding_shreds_inserted,
                 i64
             ),
+            ("num_slots", self.num_slots, i64),
         );
     }
+
+    /// Adds the counts and times of the insertion of the shreds of one slot
+    fn accumulate(&mut self, other: &BlockstoreInsertionMetrics) {
+        self.num_shreds += other.num_shreds;
+        self.num_slots += other.num_slots;
+        self.insert_lock_elapsed += other.insert_lock_elapsed;
+        self.insert_shreds_elapsed += other.insert_shreds_elapsed;
+        self.shred_recovery_elapsed += other.shred_recovery_elapsed;
+        self.chaining_elapsed += other.chaining_elapsed;
+        self.commit_working_sets_elapsed += other.commit_working_sets_elapsed;
+        self.write_batch_elapsed += other.write_batch_elapsed;
+        self.total_elapsed += other.total_elapsed;
+        self.num_inserted += other.num_inserted;
+        self.num_repair += other.num_repair;
+        self.num_recovered += other.num_recovered;
+        self.num_recovered_blockstore_error += other.num_recovered_blockstore_error;
+        self.num_recovered_inserted += other.num_recovered_inserted;
+        self.num_recovered_failed_sig += other.num_recovered_failed_sig;
+        self.num_recovered_failed_invalid += other.num_recovered_failed_invalid;
+        self.num_recovered_exists += other.num_recovered_exists;
+        self.index_meta_time += other.index_meta_time;
+        self.num_data_shreds_exists += other.num_data_shreds_exists;
+        self.num_data_shreds_invalid += other.num_data_shreds_invalid;
+        self.num_data_shreds_blockstore_error += other.num_data_shreds_blockstore_error;
+        self.num_coding_shreds_exists += other.num_coding_shreds_exists;
+        self.num_coding_shreds_invalid += other.num_coding_shreds_invalid;
+        self.num_coding_shreds_invalid_erasure_config +=
+            other.num_coding_shreds_invalid_erasure_config;
+        self.num_coding_shreds_inserted += other.num_coding_shreds_inserted;
+    }
 }
 
 impl Blockstore {
@@ -339,6 +416,173 @@ impl Blockstore {
         }
     }
 
+    /// Returns the directory under `ledger_path` of a blockstore stored with another shred
+    /// storage type than `shred_storage_type`, which opening the ledger with
+    /// `shred_storage_type` would ignore.
+    pub fn find_mismatched_shred_storage(
+        ledger_path: &Path,
+        shred_storage_type: &ShredStorageType,
+    ) -> Option<PathBuf> {
+        let other_directory = match shred_storage_type {
+            ShredStorageType::RocksLevel => BLOCKSTORE_DIRECTORY_ROCKS_FIFO,
+            ShredStorageType::RocksFifo(_) => BLOCKSTORE_DIRECTORY_ROCKS_LEVEL,
+        };
+        let path = ledger_path.join(other_directory);
+        Self::blockstore_exists(&path).then(|| path)
+    }
+
+    fn blockstore_exists(blockstore_path: &Path) -> bool {
+        blockstore_path.join("CURRENT").exists()
+    }
+
+    /// Converts the blockstore under `ledger_path` stored with another shred storage type
+    /// than the one of `options` into a blockstore of that type, then destroys the original.
+    /// `progress` is called with the name of the column being copied and the number of its
+    /// entries copied so far. Returns the number of entries copied, or None if there is no
+    /// blockstore to convert. Fails if a non-empty blockstore of the requested type exists
+    /// already, as the two can't be merged.
+    pub fn migrate_shred_storage(
+        ledger_path: &Path,
+        options: BlockstoreOptions,
+        progress: impl FnMut(&'static str, u64),
+    ) -> Result<Option<u64>> {
+        let shred_storage_type = options.column_options.shred_storage_type.clone();
+        let source_path =
+            match Self::find_mismatched_shred_storage(ledger_path, &shred_storage_type) {
+                Some(source_path) => source_path,
+                None => return Ok(None),
+            };
+        let target_path = ledger_path.join(Self::blockstore_directory(&shred_storage_type));
+        let source_shred_storage_type = match shred_storage_type {
+            ShredStorageType::RocksLevel => {
+                ShredStorageType::RocksFifo(BlockstoreRocksFifoOptions::default())
+            }
+            ShredStorageType::RocksFifo(_) => ShredStorageType::RocksLevel,
+        };
+        adjust_ulimit_nofile(options.enforce_ulimit_nofile)?;
+
+        // Automatic compactions are disabled on both sides so that FIFO compaction can't
+        // drop shreds before they are copied
+        let source = Database::open(
+            &source_path,
+            BlockstoreOptions {
+                access_type: AccessType::PrimaryForMaintenance,
+                column_options: LedgerColumnOptions {
+                    shred_storage_type: source_shred_storage_type,
+                    ..options.column_options.clone()
+                },
+                column_layout: ColumnLayout::default(),
+                ..options.clone()
+            },
+        )?;
+        // Relocated columns stay where they are unless another layout is requested
+        let column_layout = if options.column_layout.is_empty() {
+            source.column_layout().clone()
+        } else {
+            options.column_layout.clone()
+        };
+        let target = Database::open(
+            &target_path,
+            BlockstoreOptions {
+                access_type: AccessType::PrimaryForMaintenance,
+                column_layout,
+                ..options
+            },
+        )?;
+        if target
+            .iter::<cf::SlotMeta>(IteratorMode::Start)?
+            .next()
+            .is_some()
+        {
+            return Err(BlockstoreError::ShredStorageMigrationConflict(target_path));
+        }
+
+        info!(
+            "Migrating blockstore from {:?} to {:?}",
+            source_path, target_path
+        );
+        let mut measure = Measure::start("migrate_shred_storage");
+        let copied = source.copy_into(&target, SHRED_STORAGE_MIGRATION_BATCH_SIZE, progress)?;
+        drop(source);
+        drop(target);
+        Database::destroy(&source_path)?;
+        fs::remove_dir_all(&source_path)?;
+        measure.stop();
+        info!(
+            "Migrated {} entries from {:?} to {:?} in {}ms",
+            copied,
+            source_path,
+            target_path,
+            measure.as_ms()
+        );
+        Ok(Some(copied))
+    }
+
+    /// Moves the columns of the blockstore under `ledger_path` to the directories assigned to
+    /// them by `options.column_layout`, columns assigned to none moving back into the
+    /// blockstore directory. `progress` is called with the name of the column being moved
+    /// and the number of its entries copied so far. Returns the number of entries copied.
+    pub fn relocate_columns(
+        ledger_path: &Path,
+        options: BlockstoreOptions,
+        progress: impl FnMut(&'static str, u64),
+    ) -> Result<u64> {
+        let blockstore_path = ledger_path.join(Self::blockstore_directory(
+            &options.column_options.shred_storage_type,
+        ));
+        adjust_ulimit_nofile(options.enforce_ulimit_nofile)?;
+
+        info!(
+            "Moving columns of {:?} to {:?}",
+            blockstore_path,
+            options.column_layout.iter().collect::<Vec<_>>()
+        );
+        let mut measure = Measure::start("relocate_columns");
+        let copied = Database::relocate_columns(
+            &blockstore_path,
+            options,
+            COLUMN_RELOCATION_BATCH_SIZE,
+            progress,
+        )?;
+        measure.stop();
+        info!(
+            "Moved {} entries of {:?} in {}ms",
+            copied,
+            blockstore_path,
+            measure.as_ms()
+        );
+        Ok(copied)
+    }
+
+    /// Creates a consistent copy of the blockstore in the ledger directory `checkpoint_path`
+    /// while it remains in use, as described by [`Database::create_checkpoint`]. The genesis
+    /// of the ledger is copied along so that the checkpoint can be opened as a ledger.
+    pub fn create_checkpoint(&self, checkpoint_path: &Path) -> Result<()> {
+        let blockstore_directory = self.db.path().file_name().unwrap_or_default();
+        fs::create_dir_all(checkpoint_path)?;
+        info!(
+            "Creating a checkpoint of {:?} in {:?}",
+            self.db.path(),
+            checkpoint_path
+        );
+        let mut measure = Measure::start("create_checkpoint");
+        self.db
+            .create_checkpoint(&checkpoint_path.join(blockstore_directory))?;
+        for genesis_file in [DEFAULT_GENESIS_ARCHIVE, DEFAULT_GENESIS_FILE] {
+            let genesis_path = self.ledger_path.join(genesis_file);
+            if genesis_path.exists() {
+                fs::copy(&genesis_path, checkpoint_path.join(genesis_file))?;
+            }
+        }
+        measure.stop();
+        info!(
+            "Created a checkpoint of {:?} in {}ms",
+            self.db.path(),
+            measure.as_ms()
+        );
+        Ok(())
+    }
+
     /// Opens a Ledger in directory, provides "infinite" window of shreds
     pub fn open(ledger_path: &Path) -> Result<Blockstore> {
         Self::do_open(ledger_path, BlockstoreOptions::default())
@@ -349,12 +593,16 @@ impl Blockstore {
     }
 
     fn do_open(ledger_path: &Path, options: BlockstoreOptions) -> Result<Blockstore> {
-        fs::create_dir_all(&ledger_path)?;
+        let read_only = options.access_type == AccessType::ReadOnly;
+        if !read_only {
+            fs::create_dir_all(&ledger_path)?;
+        }
         let blockstore_path = ledger_path.join(Self::blockstore_directory(
             &options.column_options.shred_storage_type,
         ));
 
         adjust_ulimit_nofile(options.enforce_ulimit_nofile)?;
+        let verify_on_open = options.verify_on_open;
 
         // Open the database
         let mut measure = Measure::start("open");
@@ -366,6 +614,7 @@ impl Blockstore {
 
         // Create the dead slots column family
         let dead_slots_cf = db.column();
+        let dead_slot_causes_cf = db.column();
         let duplicate_slots_cf = db.column();
         let erasure_meta_cf = db.column();
 
@@ -388,6 +637,9 @@ impl Blockstore {
         let program_costs_cf = db.column();
         let bank_hash_cf = db.column();
         let optimistic_slots_cf = db.column();
+        let block_cost_cf = db.column();
+        let slot_tombstones_cf = db.column();
+        let duplicate_proofs_cf = db.column();
 
         let db = Arc::new(db);
 
@@ -422,6 +674,7 @@ impl Blockstore {
             db,
             meta_cf,
             dead_slots_cf,
+            dead_slot_causes_cf,
             duplicate_slots_cf,
             erasure_meta_cf,
             orphans_cf,
@@ -440,6 +693,9 @@ impl Blockstore {
             program_costs_cf,
             bank_hash_cf,
             optimistic_slots_cf,
+            block_cost_cf,
+            slot_tombstones_cf,
+            duplicate_proofs_cf,
             new_shreds_signals: Mutex::default(),
             completed_slots_senders: Mutex::default(),
             shred_timing_point_sender: None,
@@ -448,10 +704,15 @@ impl Blockstore {
             lowest_cleanup_slot: RwLock::<Slot>::default(),
             no_compaction: false,
             slots_stats: SlotsStats::default(),
+            ledger_archive: None,
+            record_slot_tombstones: false,
         };
-        if initialize_transaction_status_index {
+        if initialize_transaction_status_index && !read_only {
             blockstore.initialize_transaction_status_index()?;
         }
+        if let Some(num_slots) = verify_on_open {
+            blockstore.verify_latest_slots(num_slots)?;
+        }
         Ok(blockstore)
     }
 
@@ -532,6 +793,22 @@ impl Blockstore {
         self.no_compaction = no_compaction;
     }
 
+    /// Sets the archive that holds the rooted slots deleted from this blockstore by the
+    /// `LedgerArchiveService`, for RPC to read them from.
+    pub fn set_ledger_archive(&mut self, ledger_archive: Arc<LedgerArchive>) {
+        self.ledger_archive = Some(ledger_archive);
+    }
+
+    pub fn ledger_archive(&self) -> Option<&Arc<LedgerArchive>> {
+        self.ledger_archive.as_ref()
+    }
+
+    /// Sets whether `record_slot_purge` records the purged slot ranges in the
+    /// SlotTombstones column. Default: false.
+    pub fn set_record_slot_tombstones(&mut self, record_slot_tombstones: bool) {
+        self.record_slot_tombstones = record_slot_tombstones;
+    }
+
     /// Deletes the blockstore at the specified path.
     ///
     /// Note that if the `ledger_path` has multiple rocksdb instances, this
@@ -611,11 +888,7 @@ impl Blockstore {
         slot: Slot,
         index: u64,
     ) -> Result<impl Iterator<Item = ((u64, u64), Box<[u8]>)> + '_> {
-        let slot_iterator = self.db.iter::<cf::ShredData>(IteratorMode::From(
-            (slot, index),
-            IteratorDirection::Forward,
-        ))?;
-        Ok(slot_iterator.take_while(move |((shred_slot, _), _)| *shred_slot == slot))
+        self.db.slot_iter::<cf::ShredData>((slot, index))
     }
 
     pub fn slot_coding_iterator(
@@ -623,11 +896,7 @@ impl Blockstore {
         slot: Slot,
         index: u64,
     ) -> Result<impl Iterator<Item = ((u64, u64), Box<[u8]>)> + '_> {
-        let slot_iterator = self.db.iter::<cf::ShredCode>(IteratorMode::From(
-            (slot, index),
-            IteratorDirection::Forward,
-        ))?;
-        Ok(slot_iterator.take_while(move |((shred_slot, _), _)| *shred_slot == slot))
+        self.db.slot_iter::<cf::ShredCode>((slot, index))
     }
 
     pub fn rooted_slot_iterator(&self, slot: Slot) -> Result<impl Iterator<Item = u64> + '_> {
@@ -637,6 +906,17 @@ impl Blockstore {
         Ok(slot_iterator.map(move |(rooted_slot, _)| rooted_slot))
     }
 
+    /// Iterates over the rooted slots at or below `slot`, highest first
+    pub fn rooted_slot_iterator_reverse(
+        &self,
+        slot: Slot,
+    ) -> Result<impl Iterator<Item = u64> + '_> {
+        let slot_iterator = self
+            .db
+            .iter::<cf::Root>(IteratorMode::From(slot, IteratorDirection::Reverse))?;
+        Ok(slot_iterator.map(move |(rooted_slot, _)| rooted_slot))
+    }
+
     fn get_recovery_data_shreds<'a>(
         index: &'a Index,
         slot: Slot,
@@ -746,6 +1026,7 @@ impl Blockstore {
     pub fn submit_rocksdb_cf_metrics_for_all_cfs(&self) {
         self.meta_cf.submit_rocksdb_cf_metrics();
         self.dead_slots_cf.submit_rocksdb_cf_metrics();
+        self.dead_slot_causes_cf.submit_rocksdb_cf_metrics();
         self.duplicate_slots_cf.submit_rocksdb_cf_metrics();
         self.erasure_meta_cf.submit_rocksdb_cf_metrics();
         self.orphans_cf.submit_rocksdb_cf_metrics();
@@ -763,6 +1044,27 @@ impl Blockstore {
         self.program_costs_cf.submit_rocksdb_cf_metrics();
         self.bank_hash_cf.submit_rocksdb_cf_metrics();
         self.optimistic_slots_cf.submit_rocksdb_cf_metrics();
+        self.block_cost_cf.submit_rocksdb_cf_metrics();
+        self.slot_tombstones_cf.submit_rocksdb_cf_metrics();
+        self.duplicate_proofs_cf.submit_rocksdb_cf_metrics();
+        self.db.submit_write_batch_latency();
+    }
+
+    /// Returns how often RocksDB read/write performance samples are collected
+    pub fn rocks_perf_sample_interval(&self) -> usize {
+        self.db.perf_sample_interval()
+    }
+
+    /// Sets how often RocksDB read/write performance samples are collected, in place of the
+    /// `rocks_perf_sample_interval` the blockstore was opened with: once for every
+    /// `sample_interval` operations on a column, 0 disabling sampling. The latency of every
+    /// sample is recorded; their PerfContext is reported at most once per second per column.
+    pub fn set_rocks_perf_sample_interval(&self, sample_interval: usize) {
+        info!(
+            "Setting the RocksDB perf sample interval to {}",
+            sample_interval
+        );
+        self.db.set_perf_sample_interval(sample_interval);
     }
 
     fn try_shred_recovery(
@@ -845,6 +1147,14 @@ impl Blockstore {
     ///     pair to the `cf::Index` column family for each index_working_set_entry
     ///     which insert did occur in this function call.
     ///
+    /// The shreds of each slot are inserted, and their data shreds recovered, on
+    /// their own thread of a small pool, into a write batch of the slot, see
+    /// `insert_slot_shreds`.  Chaining then runs over all the slots, and the
+    /// write batches are written concurrently; each is atomic, but not the
+    /// insertion as a whole.  When slots were chained, the chaining updates are
+    /// written first and the slots one at a time from parents to children
+    /// instead, so that a slot which misses its update gets chained again.
+    ///
     /// Arguments:
     ///  - `shreds`: the shreds to be inserted.
     ///  - `is_repaired`: a boolean vector aligned with `shreds` where each
@@ -873,7 +1183,7 @@ impl Blockstore {
         metrics: &mut BlockstoreInsertionMetrics,
     ) -> Result<(Vec<CompletedDataSetInfo>, Vec<usize>)>
     where
-        F: Fn(Shred),
+        F: Fn(Shred) + Sync,
     {
         assert_eq!(shreds.len(), is_repaired.len());
         let mut total_start = Measure::start("Total elapsed");
@@ -882,20 +1192,152 @@ impl Blockstore {
         start.stop();
         metrics.insert_lock_elapsed += start.as_us();
 
-        let db = &*self.db;
-        let mut write_batch = db.batch()?;
+        metrics.num_shreds += shreds.len();
+        let mut slot_shreds: BTreeMap<Slot, Vec<(usize, Shred, bool)>> = BTreeMap::new();
+        for (i, (shred, is_repaired)) in shreds.into_iter().zip(is_repaired).enumerate() {
+            slot_shreds
+                .entry(shred.slot())
+                .or_default()
+                .push((i, shred, is_repaired));
+        }
+        metrics.num_slots += slot_shreds.len();
+
+        let mut start = Measure::start("Shred insertion");
+        let insert_slot_shreds = |(slot, shreds): (Slot, Vec<(usize, Shred, bool)>)| {
+            self.insert_slot_shreds(slot, shreds, leader_schedule, is_trusted, handle_duplicate)
+        };
+        let slot_insertions: Vec<SlotInsertion> = if slot_shreds.len() > 1 {
+            INSERT_SHREDS_THREAD_POOL.install(|| {
+                slot_shreds
+                    .into_par_iter()
+                    .map(insert_slot_shreds)
+                    .collect::<Result<_>>()
+            })?
+        } else {
+            slot_shreds
+                .into_iter()
+                .map(insert_slot_shreds)
+                .collect::<Result<_>>()?
+        };
+        start.stop();
+        metrics.insert_shreds_elapsed += start.as_us();
+
+        let mut write_batches = BTreeMap::new();
+        let mut slot_meta_working_set = HashMap::new();
+        let mut newly_completed_data_sets = vec![];
+        let mut inserted_indices = vec![];
+        let mut recovered_data_shreds = vec![];
+        for slot_insertion in slot_insertions {
+            let slot = slot_insertion.slot;
+            if let Some((new_slot_meta, old_slot_meta)) = slot_insertion.slot_meta {
+                let mut entry = SlotMetaWorkingSetEntry::new(
+                    Rc::new(RefCell::new(new_slot_meta)),
+                    old_slot_meta,
+                );
+                entry.did_insert_occur = true;
+                slot_meta_working_set.insert(slot, entry);
+            }
+            write_batches.insert(slot, slot_insertion.write_batch);
+            newly_completed_data_sets.extend(slot_insertion.completed_data_sets);
+            inserted_indices.extend(slot_insertion.inserted_indices);
+            recovered_data_shreds.extend(slot_insertion.recovered_data_shreds);
+            metrics.accumulate(&slot_insertion.metrics);
+        }
+        inserted_indices.sort_unstable();
+        if !recovered_data_shreds.is_empty() {
+            if let Some(retransmit_sender) = retransmit_sender {
+                let _ = retransmit_sender.send(recovered_data_shreds);
+            }
+        }
+
+        let mut start = Measure::start("Chaining");
+        // Handle chaining for the members of the slot_meta_working_set that were inserted into,
+        // drop the others
+        let mut chaining_write_batch = self.db.batch()?;
+        let is_chained = handle_chaining(
+            &self.db,
+            &mut chaining_write_batch,
+            &mut slot_meta_working_set,
+        )?;
+        start.stop();
+        metrics.chaining_elapsed += start.as_us();
+
+        let mut start = Measure::start("Commit Working Sets");
+        let (should_signal, newly_completed_slots) = commit_slot_meta_working_set(
+            &slot_meta_working_set,
+            &self.completed_slots_senders.lock().unwrap(),
+            &mut write_batches,
+        )?;
+        start.stop();
+        metrics.commit_working_sets_elapsed += start.as_us();
+
+        let mut start = Measure::start("Write Batch");
+        if is_chained {
+            // The links between slots are written first, then the slots from parents to
+            // children, so that the slots not written if the writes are cut short get chained
+            // again when their shreds are inserted again
+            self.db.write(chaining_write_batch)?;
+            for write_batch in write_batches.into_values() {
+                self.db.write(write_batch)?;
+            }
+        } else if write_batches.len() > 1 {
+            INSERT_SHREDS_THREAD_POOL.install(|| {
+                write_batches
+                    .into_par_iter()
+                    .try_for_each(|(_, write_batch)| self.db.write(write_batch))
+            })?;
+        } else {
+            for write_batch in write_batches.into_values() {
+                self.db.write(write_batch)?;
+            }
+        }
+        start.stop();
+        metrics.write_batch_elapsed += start.as_us();
+
+        send_signals(
+            &self.new_shreds_signals.lock().unwrap(),
+            &self.completed_slots_senders.lock().unwrap(),
+            should_signal,
+            newly_completed_slots,
+        );
+
+        total_start.stop();
+
+        metrics.total_elapsed += total_start.as_us();
+
+        Ok((newly_completed_data_sets, inserted_indices))
+    }
+
+    /// Inserts the `shreds` of `slot`, given with their index in the input of
+    /// `insert_shreds_handle_duplicate` and whether they were repaired, then recovers the data
+    /// shreds of the slot they complete erasure sets for.
+    ///
+    /// Only the state of `slot` is read and written, so that the slots of an insertion can be
+    /// inserted in parallel. Everything but the `SlotMeta` of the slot, which chaining may
+    /// still update, is put into the returned write batch.
+    fn insert_slot_shreds<F>(
+        &self,
+        slot: Slot,
+        shreds: Vec<(usize, Shred, bool)>,
+        leader_schedule: Option<&LeaderScheduleCache>,
+        is_trusted: bool,
+        handle_duplicate: &F,
+    ) -> Result<SlotInsertion>
+    where
+        F: Fn(Shred),
+    {
+        let mut write_batch = self.db.batch()?;
+        let mut metrics = BlockstoreInsertionMetrics::default();
 
         let mut just_inserted_shreds = HashMap::with_capacity(shreds.len());
         let mut erasure_metas = HashMap::new();
         let mut slot_meta_working_set = HashMap::new();
         let mut index_working_set = HashMap::new();
 
-        metrics.num_shreds += shreds.len();
-        let mut start = Measure::start("Shred insertion");
         let mut index_meta_time = 0;
         let mut newly_completed_data_sets: Vec<CompletedDataSetInfo> = vec![];
         let mut inserted_indices = Vec::new();
-        for (i, (shred, is_repaired)) in shreds.into_iter().zip(is_repaired).enumerate() {
+        for (i, shred, is_repaired) in shreds {
             let shred_source = if is_repaired {
                 ShredSource::Repaired
             } else {
@@ -942,25 +1384,26 @@ impl Blockstore {
                         handle_duplicate,
                         is_trusted,
                         shred_source,
-                        metrics,
+                        &mut metrics,
                     );
                 }
             };
         }
-        start.stop();
 
-        metrics.insert_shreds_elapsed += start.as_us();
         let mut start = Measure::start("Shred recovery");
+        let mut recovered_data_shreds = vec![];
         if let Some(leader_schedule_cache) = leader_schedule {
-            let recovered_data_shreds = Self::try_shred_recovery(
-                db,
+            let recovered_shreds = Self::try_shred_recovery(
+                &self.db,
                 &erasure_metas,
                 &mut index_working_set,
                 &just_inserted_shreds,
             );
 
-            metrics.num_recovered += recovered_data_shreds.len();
-            let recovered_data_shreds: Vec<_> = recovered_data_shreds
+            metrics.num_recovered += recovered_shreds.len();
+            // Always collect recovered-shreds so that the insert code below is
+            // executed even if retransmit-sender is None.
+            recovered_data_shreds = recovered_shreds
                 .into_iter()
                 .filter_map(|shred| {
                     let leader =
@@ -1002,32 +1445,11 @@ impl Blockstore {
                         }
                     }
                 })
-                // Always collect recovered-shreds so that above insert code is
-                // executed even if retransmit-sender is None.
                 .collect();
-            if !recovered_data_shreds.is_empty() {
-                if let Some(retransmit_sender) = retransmit_sender {
-                    let _ = retransmit_sender.send(recovered_data_shreds);
-                }
-            }
         }
         start.stop();
         metrics.shred_recovery_elapsed += start.as_us();
 
-        let mut start = Measure::start("Shred recovery");
-        // Handle chaining for the members of the slot_meta_working_set that were inserted into,
-        // drop the others
-        handle_chaining(&self.db, &mut write_batch, &mut slot_meta_working_set)?;
-        start.stop();
-        metrics.chaining_elapsed += start.as_us();
-
-        let mut start = Measure::start("Commit Working Sets");
-        let (should_signal, newly_completed_slots) = commit_slot_meta_working_set(
-            &slot_meta_working_set,
-            &self.completed_slots_senders.lock().unwrap(),
-            &mut write_batch,
-        )?;
-
         for (erasure_set, erasure_meta) in erasure_metas {
             write_batch.put::<cf::ErasureMeta>(erasure_set.store_key(), &erasure_meta)?;
         }
@@ -1037,27 +1459,25 @@ impl Blockstore {
                 write_batch.put::<cf::Index>(slot, &index_working_set_entry.index)?;
             }
         }
-        start.stop();
-        metrics.commit_working_sets_elapsed += start.as_us();
-
-        let mut start = Measure::start("Write Batch");
-        self.db.write(write_batch)?;
-        start.stop();
-        metrics.write_batch_elapsed += start.as_us();
-
-        send_signals(
-            &self.new_shreds_signals.lock().unwrap(),
-            &self.completed_slots_senders.lock().unwrap(),
-            should_signal,
-            newly_completed_slots,
-        );
-
-        total_start.stop();
-
-        metrics.total_elapsed += total_start.as_us();
         metrics.index_meta_time += index_meta_time;
 
-        Ok((newly_completed_data_sets, inserted_indices))
+        let slot_meta = slot_meta_working_set
+            .remove(&slot)
+            .filter(|entry| entry.did_insert_occur)
+            .map(|entry| {
+                let new_slot_meta = RefCell::borrow(&*entry.new_slot_meta).clone();
+                (new_slot_meta, entry.old_slot_meta)
+            });
+
+        Ok(SlotInsertion {
+            slot,
+            write_batch,
+            slot_meta,
+            completed_data_sets: newly_completed_data_sets,
+            inserted_indices,
+            recovered_data_shreds,
+            metrics,
+        })
     }
 
     pub fn add_new_shred_signal(&self, s: Sender<bool>) {
@@ -1285,10 +1705,10 @@ impl Blockstore {
     /// - `shred`: the shred to be inserted
     /// - `erasure_metas`: the in-memory hash-map that maintains the dirty
     ///     copy of the erasure meta.  It will later be written to
-    ///     `cf::ErasureMeta` in insert_shreds_handle_duplicate().
+    ///     `cf::ErasureMeta` in insert_slot_shreds().
     /// - `index_working_set`: the in-memory hash-map that maintains the
     ///     dirty copy of the index meta.  It will later be written to
-    ///     `cf::Index` in insert_shreds_handle_duplicate().
+    ///     `cf::Index` in insert_slot_shreds().
     /// - `slot_meta_working_set`: the in-memory hash-map that maintains
     ///     the dirty copy of the index meta.  It will later be written to
     ///     `cf::SlotMeta` in insert_shreds_handle_duplicate().
@@ -1358,6 +1778,15 @@ impl Blockstore {
                 // and wait for replay to dump and repair the correct version.
                 warn!("Received *last* shred index {} less than previous shred index {}, and slot {} is not full, marking slot dead", shred_index, slot_meta.received, slot);
                 write_batch.put::<cf::DeadSlots>(slot, &true).unwrap();
+                write_batch
+                    .put::<cf::DeadSlotCauses>(
+                        slot,
+                        &DeadSlotMetaVersioned::new(
+                            DeadSlotCause::ConflictingLastShred,
+                            timestamp(),
+                        ),
+                    )
+                    .unwrap();
             }
 
             if !self.should_insert_data_shred(
@@ -1897,10 +2326,7 @@ impl Blockstore {
         end_index: u64,
         max_missing: usize,
     ) -> Vec<u64> {
-        if let Ok(mut db_iterator) = self
-            .db
-            .raw_iterator_cf(self.db.cf_handle::<cf::ShredData>())
-        {
+        if let Ok(mut db_iterator) = self.db.raw_iterator_cf::<cf::ShredData>() {
             Self::find_missing_indexes::<cf::ShredData>(
                 &mut db_iterator,
                 slot,
@@ -1924,6 +2350,50 @@ impl Blockstore {
         self.blocktime_cf.put(slot, &timestamp)
     }
 
+    /// Returns the highest rooted slot at or below `slot` that has a block time, along with that
+    /// block time
+    fn get_rooted_block_time_at_or_below(
+        &self,
+        slot: Slot,
+    ) -> Result<Option<(Slot, UnixTimestamp)>> {
+        for rooted_slot in self.rooted_slot_iterator_reverse(slot)? {
+            if let Some(block_time) = self.blocktime_cf.get(rooted_slot)? {
+                return Ok(Some((rooted_slot, block_time)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the lowest slot in `slot_range` from which on every rooted block was produced at or
+    /// after `timestamp`, or None if the range holds no such slot.
+    ///
+    /// Rooted block times do not decrease, so the range is binary searched, judging each slot by
+    /// the block time of the closest root at or below it.
+    pub fn get_first_slot_at_or_after_block_time(
+        &self,
+        timestamp: UnixTimestamp,
+        slot_range: RangeInclusive<Slot>,
+    ) -> Result<Option<Slot>> {
+        datapoint_info!(
+            "blockstore-rpc-api",
+            ("method", "get_first_slot_at_or_after_block_time", String)
+        );
+        let (mut low, mut high) = (*slot_range.start(), slot_range.end().saturating_add(1));
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let is_at_or_after = self
+                .get_rooted_block_time_at_or_below(mid)?
+                .map(|(_, block_time)| block_time >= timestamp)
+                .unwrap_or(false);
+            if is_at_or_after {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(slot_range.contains(&low).then(|| low))
+    }
+
     pub fn get_block_height(&self, slot: Slot) -> Result<Option<u64>> {
         datapoint_info!("blockstore-rpc-api", ("method", "get_block_height", String));
         let _lock = self.check_lowest_cleanup_slot(slot)?;
@@ -1963,13 +2433,15 @@ impl Blockstore {
         Err(BlockstoreError::SlotNotRooted)
     }
 
+    /// Reads the block at `slot`. The entries, transaction statuses and rewards are all read
+    /// from one snapshot of the ledger, so a concurrent purge or insert can't tear the block.
     pub fn get_complete_block(
         &self,
         slot: Slot,
         require_previous_blockhash: bool,
     ) -> Result<VersionedConfirmedBlock> {
-        let slot_meta_cf = self.db.column::<cf::SlotMeta>();
-        let slot_meta = match slot_meta_cf.get(slot)? {
+        let snapshot = self.db.snapshot();
+        let slot_meta = match snapshot.get::<cf::SlotMeta>(slot)? {
             Some(slot_meta) => slot_meta,
             None => {
                 info!("SlotMeta not found for slot {}", slot);
@@ -1977,7 +2449,7 @@ impl Blockstore {
             }
         };
         if slot_meta.is_full() {
-            let slot_entries = self.get_slot_entries(slot, 0)?;
+            let slot_entries = Self::get_slot_entries_from_snapshot(&snapshot, slot, &slot_meta)?;
             if !slot_entries.is_empty() {
                 let blockhash = slot_entries
                     .last()
@@ -2005,8 +2477,13 @@ impl Blockstore {
                 let parent_slot_entries = slot_meta
                     .parent_slot
                     .and_then(|parent_slot| {
-                        self.get_slot_entries(parent_slot, /*shred_start_index:*/ 0)
-                            .ok()
+                        let parent_slot_meta = snapshot.get::<cf::SlotMeta>(parent_slot).ok()??;
+                        Self::get_slot_entries_from_snapshot(
+                            &snapshot,
+                            parent_slot,
+                            &parent_slot_meta,
+                        )
+                        .ok()
                     })
                     .unwrap_or_default();
                 if parent_slot_entries.is_empty() && require_previous_blockhash {
@@ -2018,17 +2495,16 @@ impl Blockstore {
                     Hash::default()
                 };
 
-                let rewards = self
-                    .rewards_cf
-                    .get_protobuf_or_bincode::<StoredExtendedRewards>(slot)?
+                let rewards = snapshot
+                    .get_protobuf_or_bincode::<cf::Rewards, StoredExtendedRewards>(slot)?
                     .unwrap_or_default()
                     .into();
 
                 // The Blocktime and BlockHeight column families are updated asynchronously; they
                 // may not be written by the time the complete slot entries are available. In this
                 // case, these fields will be `None`.
-                let block_time = self.blocktime_cf.get(slot)?;
-                let block_height = self.block_height_cf.get(slot)?;
+                let block_time = snapshot.get::<cf::Blocktime>(slot)?;
+                let block_height = snapshot.get::<cf::BlockHeight>(slot)?;
 
                 let block = VersionedConfirmedBlock {
                     previous_blockhash: previous_blockhash.to_string(),
@@ -2036,8 +2512,11 @@ impl Blockstore {
                     // If the slot is full it should have parent_slot populated
                     // from shreds received.
                     parent_slot: slot_meta.parent_slot.unwrap(),
-                    transactions: self
-                        .map_transactions_to_statuses(slot, slot_transaction_iterator)?,
+                    transactions: Self::map_transactions_to_statuses_from_snapshot(
+                        &snapshot,
+                        slot,
+                        slot_transaction_iterator,
+                    )?,
                     rewards,
                     block_time,
                     block_height,
@@ -2066,6 +2545,23 @@ impl Blockstore {
             .collect()
     }
 
+    fn map_transactions_to_statuses_from_snapshot(
+        snapshot: &LedgerSnapshot,
+        slot: Slot,
+        iterator: impl Iterator<Item = VersionedTransaction>,
+    ) -> Result<Vec<VersionedTransactionWithStatusMeta>> {
+        iterator
+            .map(|transaction| {
+                let signature = transaction.signatures[0];
+                Ok(VersionedTransactionWithStatusMeta {
+                    transaction,
+                    meta: Self::read_transaction_status_from_snapshot(snapshot, (signature, slot))?
+                        .ok_or(BlockstoreError::MissingTransactionMetadata)?,
+                })
+            })
+            .collect()
+    }
+
     /// Initializes the TransactionStatusIndex column family with two records, `0` and `1`,
     /// which are used as the primary index for entries in the TransactionStatus and
     /// AddressSignatures columns. At any given time, one primary index is active (ie. new records
@@ -2174,6 +2670,26 @@ impl Blockstore {
         }
     }
 
+    fn read_transaction_status_from_snapshot(
+        snapshot: &LedgerSnapshot,
+        index: (Signature, Slot),
+    ) -> Result<Option<TransactionStatusMeta>> {
+        let (signature, slot) = index;
+        let result = snapshot
+            .get_protobuf_or_bincode::<cf::TransactionStatus, StoredTransactionStatusMeta>((
+                0, signature, slot,
+            ))?;
+        if result.is_none() {
+            Ok(snapshot
+                .get_protobuf_or_bincode::<cf::TransactionStatus, StoredTransactionStatusMeta>((
+                    1, signature, slot,
+                ))?
+                .and_then(|meta| meta.try_into().ok()))
+        } else {
+            Ok(result.and_then(|meta| meta.try_into().ok()))
+        }
+    }
+
     pub fn write_transaction_status(
         &self,
         slot: Slot,
@@ -2512,11 +3028,32 @@ impl Blockstore {
         until: Option<Signature>,
         limit: usize,
     ) -> Result<SignatureInfosForAddress> {
-        datapoint_info!(
-            "blockstore-rpc-api",
-            ("method", "get_confirmed_signatures_for_address2", String)
-        );
-        let last_root = self.last_root();
+        self.get_confirmed_signatures_for_address_in_slot_range(
+            address,
+            highest_slot,
+            before,
+            until,
+            0..=highest_slot,
+            limit,
+        )
+    }
+
+    /// Like [`Self::get_confirmed_signatures_for_address2`], but only returns the signatures of
+    /// transactions in `slot_range`, ending the scan once it drops below the range
+    pub fn get_confirmed_signatures_for_address_in_slot_range(
+        &self,
+        address: Pubkey,
+        highest_slot: Slot, // highest_confirmed_root or highest_confirmed_slot
+        before: Option<Signature>,
+        until: Option<Signature>,
+        slot_range: RangeInclusive<Slot>,
+        limit: usize,
+    ) -> Result<SignatureInfosForAddress> {
+        datapoint_info!(
+            "blockstore-rpc-api",
+            ("method", "get_confirmed_signatures_for_address2", String)
+        );
+        let last_root = self.last_root();
         let confirmed_unrooted_slots: Vec<_> = AncestorIterator::new_inclusive(highest_slot, self)
             .take_while(|&slot| slot > last_root)
             .collect();
@@ -2525,7 +3062,7 @@ impl Blockstore {
         // `before` signature if present.  Also generate a HashSet of signatures that should
         // be excluded from the results.
         let mut get_before_slot_timer = Measure::start("get_before_slot_timer");
-        let (slot, mut before_excluded_signatures) = match before {
+        let (slot, before_excluded_signatures) = match before {
             None => (highest_slot, None),
             Some(before) => {
                 let transaction_status =
@@ -2571,6 +3108,21 @@ impl Blockstore {
         };
         get_until_slot_timer.stop();
 
+        // Narrow the listing to `slot_range`. All signatures in slots below the `before` slot are
+        // older than `before`, so none of them need to be excluded.
+        let (slot, mut before_excluded_signatures) = if slot > *slot_range.end() {
+            (*slot_range.end(), None)
+        } else {
+            (slot, before_excluded_signatures)
+        };
+        let lowest_slot = lowest_slot.max(*slot_range.start());
+        if slot < lowest_slot {
+            return Ok(SignatureInfosForAddress {
+                infos: vec![],
+                found_before: true,
+            });
+        }
+
         // Fetch the list of signatures that affect the given address
         let first_available_block = self.get_first_available_block()?;
         let mut address_signatures = vec![];
@@ -2776,6 +3328,27 @@ impl Blockstore {
         self.program_costs_cf.delete(*key)
     }
 
+    pub fn read_block_cost(&self, slot: Slot) -> Result<Option<BlockCost>> {
+        self.block_cost_cf.get(slot)
+    }
+
+    pub fn write_block_cost(&self, slot: Slot, block_cost: &BlockCost) -> Result<()> {
+        self.block_cost_cf.put(slot, block_cost)
+    }
+
+    /// Returns the cost of up to `num` of the most recent slots, newest first
+    pub fn get_recent_block_costs(&self, num: usize) -> Result<Vec<(Slot, BlockCost)>> {
+        Ok(self
+            .db
+            .iter::<cf::BlockCost>(IteratorMode::End)?
+            .take(num)
+            .map(|(slot, data)| {
+                let block_cost = deserialize(&data).unwrap();
+                (slot, block_cost)
+            })
+            .collect())
+    }
+
     /// Returns the entry vector for the slot starting with `shred_start_index`
     pub fn get_slot_entries(&self, slot: Slot, shred_start_index: u64) -> Result<Vec<Entry>> {
         self.get_slot_entries_with_shred_info(slot, shred_start_index, false)
@@ -2820,6 +3393,37 @@ impl Blockstore {
         Ok((entries, num_shreds, slot_meta.is_full()))
     }
 
+    /// Returns the entries of all completed data blocks of `slot` as of `snapshot`
+    fn get_slot_entries_from_snapshot(
+        snapshot: &LedgerSnapshot,
+        slot: Slot,
+        slot_meta: &SlotMeta,
+    ) -> Result<Vec<Entry>> {
+        if snapshot.get::<cf::DeadSlots>(slot)? == Some(true) {
+            return Err(BlockstoreError::DeadSlot);
+        }
+        let completed_ranges = Self::get_completed_data_ranges(
+            0,
+            &slot_meta.completed_data_indexes,
+            slot_meta.consumed as u32,
+        );
+        let entries: Result<Vec<Vec<Entry>>> = PAR_THREAD_POOL.install(|| {
+            completed_ranges
+                .par_iter()
+                .map(|(start_index, end_index)| {
+                    Self::deshred_data_block(
+                        slot,
+                        *start_index,
+                        *end_index,
+                        Some(slot_meta),
+                        |index| snapshot.get_bytes::<cf::ShredData>((slot, index)),
+                    )
+                })
+                .collect()
+        });
+        Ok(entries?.into_iter().flatten().collect())
+    }
+
     fn get_completed_ranges(
         &self,
         slot: Slot,
@@ -2871,48 +3475,56 @@ impl Blockstore {
         slot_meta: Option<&SlotMeta>,
     ) -> Result<Vec<Entry>> {
         let data_shred_cf = self.db.column::<cf::ShredData>();
+        Self::deshred_data_block(slot, start_index, end_index, slot_meta, |index| {
+            data_shred_cf.get_bytes((slot, index))
+        })
+    }
 
+    /// Reconstructs the entries of the data block spanning shreds [start_index, end_index],
+    /// fetching each serialized data shred by index with `get_shred_bytes`
+    fn deshred_data_block(
+        slot: Slot,
+        start_index: u32,
+        end_index: u32,
+        slot_meta: Option<&SlotMeta>,
+        get_shred_bytes: impl Fn(u64) -> Result<Option<Vec<u8>>>,
+    ) -> Result<Vec<Entry>> {
         // Short circuit on first error
         let data_shreds: Result<Vec<Shred>> = (start_index..=end_index)
             .map(|i| {
-                data_shred_cf
-                    .get_bytes((slot, u64::from(i)))
-                    .and_then(|serialized_shred| {
-                        if serialized_shred.is_none() {
-                            if let Some(slot_meta) = slot_meta {
-                                panic!(
-                                    "Shred with
+                get_shred_bytes(u64::from(i)).and_then(|serialized_shred| {
+                    if serialized_shred.is_none() {
+                        if let Some(slot_meta) = slot_meta {
+                            panic!(
+                                "Shred with
                                     slot: {},
                                     index: {},
                                     consumed: {},
                                     completed_indexes: {:?}
                                     must exist if shred index was included in a range: {} {}",
-                                    slot,
-                                    i,
-                                    slot_meta.consumed,
-                                    slot_meta.completed_data_indexes,
-                                    start_index,
-                                    end_index
-                                );
-                            } else {
-                                return Err(BlockstoreError::InvalidShredData(Box::new(
-                                    bincode::ErrorKind::Custom(format!(
-                                        "Missing shred for slot {}, index {}",
-                                        slot, i
-                                    )),
-                                )));
-                            }
+                                slot,
+                                i,
+                                slot_meta.consumed,
+                                slot_meta.completed_data_indexes,
+                                start_index,
+                                end_index
+                            );
+                        } else {
+                            return Err(BlockstoreError::InvalidShredData(Box::new(
+                                bincode::ErrorKind::Custom(format!(
+                                    "Missing shred for slot {}, index {}",
+                                    slot, i
+                                )),
+                            )));
                         }
+                    }
 
-                        Shred::new_from_serialized_shred(serialized_shred.unwrap()).map_err(|err| {
-                            BlockstoreError::InvalidShredData(Box::new(bincode::ErrorKind::Custom(
-                                format!(
-                                    "Could not reconstruct shred from shred payload: {:?}",
-                                    err
-                                ),
-                            )))
-                        })
+                    Shred::new_from_serialized_shred(serialized_shred.unwrap()).map_err(|err| {
+                        BlockstoreError::InvalidShredData(Box::new(bincode::ErrorKind::Custom(
+                            format!("Could not reconstruct shred from shred payload: {:?}", err),
+                        )))
                     })
+                })
             })
             .collect();
 
@@ -3034,6 +3646,49 @@ impl Blockstore {
         self.optimistic_slots_cf.put(slot, &slot_data)
     }
 
+    /// Records that slots `first_slot..=last_slot` were purged for `reason`, if
+    /// enabled with `set_record_slot_tombstones`
+    pub fn record_slot_purge(
+        &self,
+        first_slot: Slot,
+        last_slot: Slot,
+        reason: SlotPurgeReason,
+    ) -> Result<()> {
+        if !self.record_slot_tombstones {
+            return Ok(());
+        }
+        let tombstone = SlotTombstone {
+            first_slot,
+            last_slot,
+            reason,
+            timestamp: timestamp().try_into().unwrap(),
+        };
+        self.slot_tombstones_cf.put(last_slot, &tombstone)
+    }
+
+    /// Returns the record of the purge that removed `slot`, if any
+    pub fn get_slot_tombstone(&self, slot: Slot) -> Result<Option<SlotTombstone>> {
+        // Tombstones are keyed by the last slot of their range, so the first one
+        // ending at or after `slot` is the only one that may cover it
+        Ok(self
+            .db
+            .iter::<cf::SlotTombstones>(IteratorMode::From(slot, IteratorDirection::Forward))?
+            .next()
+            .map(|(_, data)| deserialize::<SlotTombstone>(&data).unwrap())
+            .filter(|tombstone| tombstone.first_slot <= slot))
+    }
+
+    /// Returns up to `num` of the latest slot tombstones, the most recent
+    /// slots first
+    pub fn get_recent_slot_tombstones(&self, num: usize) -> Result<Vec<SlotTombstone>> {
+        Ok(self
+            .db
+            .iter::<cf::SlotTombstones>(IteratorMode::End)?
+            .take(num)
+            .map(|(_, data)| deserialize(&data).unwrap())
+            .collect())
+    }
+
     pub fn get_latest_optimistic_slots(
         &self,
         num: usize,
@@ -3097,8 +3752,25 @@ impl Blockstore {
         self.dead_slots_cf.put(slot, &true)
     }
 
+    /// Marks `slot` dead, recording `cause` so that it can be diagnosed later
+    pub fn set_dead_slot_with_cause(&self, slot: Slot, cause: DeadSlotCause) -> Result<()> {
+        let mut write_batch = self.db.batch()?;
+        write_batch.put::<cf::DeadSlots>(slot, &true)?;
+        write_batch
+            .put::<cf::DeadSlotCauses>(slot, &DeadSlotMetaVersioned::new(cause, timestamp()))?;
+        self.db.write(write_batch)
+    }
+
+    /// Returns the cause recorded when `slot` was marked dead, if any
+    pub fn get_dead_slot_cause(&self, slot: Slot) -> Result<Option<DeadSlotMetaVersioned>> {
+        self.dead_slot_causes_cf.get(slot)
+    }
+
     pub fn remove_dead_slot(&self, slot: Slot) -> Result<()> {
-        self.dead_slots_cf.delete(slot)
+        let mut write_batch = self.db.batch()?;
+        write_batch.delete::<cf::DeadSlots>(slot)?;
+        write_batch.delete::<cf::DeadSlotCauses>(slot)?;
+        self.db.write(write_batch)
     }
 
     pub fn store_duplicate_if_not_existing(
@@ -3125,6 +3797,47 @@ impl Blockstore {
             .expect("fetch from DuplicateSlots column family failed")
     }
 
+    /// Stores the proof that the leader of `slot` produced both `shred1` and `shred2`, unless
+    /// the same proof is stored already, and returns whether it was stored.
+    ///
+    /// Unlike `store_duplicate_slot`, every distinct proof of a slot is kept. The shreds aren't
+    /// checked here; callers verify that they conflict and are signed by the slot leader.
+    pub fn store_duplicate_proof(
+        &self,
+        slot: Slot,
+        shred1: Vec<u8>,
+        shred2: Vec<u8>,
+        source: DuplicateProofSource,
+    ) -> Result<bool> {
+        // The same pair of shreds may be observed in either order, or from several nodes
+        let (shred1, shred2) = if shred1 <= shred2 {
+            (shred1, shred2)
+        } else {
+            (shred2, shred1)
+        };
+        let key = (slot, hashv(&[&shred1, &shred2]));
+        if self.duplicate_proofs_cf.get_bytes(key)?.is_some() {
+            return Ok(false);
+        }
+        let duplicate_proof = DuplicateProof {
+            shred1,
+            shred2,
+            source,
+            timestamp: timestamp().try_into().unwrap(),
+        };
+        self.duplicate_proofs_cf.put(key, &duplicate_proof)?;
+        Ok(true)
+    }
+
+    /// Returns the proofs of duplicate blocks stored for `slot`
+    pub fn get_duplicate_proofs(&self, slot: Slot) -> Result<Vec<DuplicateProof>> {
+        Ok(self
+            .db
+            .slot_iter::<cf::DuplicateProofs>((slot, Hash::default()))?
+            .map(|(_, data)| deserialize(&data).unwrap())
+            .collect())
+    }
+
     // `new_shred` is assumed to have slot and index equal to the given slot and index.
     // Returns the existing shred if `new_shred` is not equal to the existing shred at the
     // given slot and index as this implies the leader generated two different shreds with
@@ -3225,6 +3938,15 @@ impl Blockstore {
         shred_code_cf.get_int_property(RocksProperties::TOTAL_SST_FILES_SIZE)
     }
 
+    /// Returns the bytes held by each column of the blockstore, including the transaction
+    /// statuses and address signatures, as measured by RocksDB.
+    ///
+    /// Unlike `storage_size`, this counts recently inserted data still in memory, but not the
+    /// write-ahead log nor the other files of the blockstore directories.
+    pub fn column_storage_sizes(&self) -> Result<Vec<(&'static str, u64)>> {
+        self.db.column_storage_sizes()
+    }
+
     /// Returns whether the blockstore has primary (read and write) access
     pub fn is_primary_access(&self) -> bool {
         self.db.is_primary_access()
@@ -3469,16 +4191,17 @@ fn send_signals(
 }
 
 /// For each slot in the slot_meta_working_set which has any change, include
-/// corresponding updates to cf::SlotMeta via the specified `write_batch`.
-/// The `write_batch` will later be atomically committed to the blockstore.
+/// corresponding updates to cf::SlotMeta via the write batch of the slot in
+/// `write_batches`.  The write batches will later be committed to the blockstore,
+/// each atomically.
 ///
 /// Arguments:
 /// - `slot_meta_working_set`: a map that maintains slot-id to its `SlotMeta`
 ///   mapping.
 /// - `completed_slot_senders`: the units which are responsible for sending
 ///   signals for completed slots.
-/// - `write_batch`: the write batch which includes all the updates of the
-///   the current write and ensures their atomicity.
+/// - `write_batches`: the write batch of each slot inserted into, which includes
+///   all the updates of the slot and ensures their atomicity.
 ///
 /// On success, the function returns an Ok result with <should_signal,
 /// newly_completed_slots> pair where:
@@ -3488,7 +4211,7 @@ fn send_signals(
 fn commit_slot_meta_working_set(
     slot_meta_working_set: &HashMap<u64, SlotMetaWorkingSetEntry>,
     completed_slots_senders: &[Sender<Vec<u64>>],
-    write_batch: &mut WriteBatch,
+    write_batches: &mut BTreeMap<Slot, WriteBatch>,
 ) -> Result<(bool, Vec<u64>)> {
     let mut should_signal = false;
     let mut newly_completed_slots = vec![];
@@ -3506,7 +4229,10 @@ fn commit_slot_meta_working_set(
         // Check if the working copy of the metadata has changed
         if Some(meta) != meta_backup.as_ref() {
             should_signal = should_signal || slot_has_updates(meta, meta_backup);
-            write_batch.put::<cf::SlotMeta>(*slot, meta)?;
+            write_batches
+                .get_mut(slot)
+                .expect("Slot inserted into must have a write batch")
+                .put::<cf::SlotMeta>(*slot, meta)?;
         }
     }
 
@@ -3592,17 +4318,22 @@ fn find_slot_meta_in_cached_state<'a>(
 ///   the current write and ensures their atomicity.
 /// - `working_set`: a slot-id to SlotMetaWorkingSetEntry map.  This function
 ///   will remove all entries which insertion did not actually occur.
+///
+/// Returns whether any slot was chained, i.e. whether slots other than the
+/// one inserted into may have been updated.
 fn handle_chaining(
     db: &Database,
     write_batch: &mut WriteBatch,
     working_set: &mut HashMap<u64, SlotMetaWorkingSetEntry>,
-) -> Result<()> {
+) -> Result<bool> {
     // Handle chaining for all the SlotMetas that were inserted into
     working_set.retain(|_, entry| entry.did_insert_occur);
     let mut new_chained_slots = HashMap::new();
+    let mut is_chained = false;
     let working_set_slots: Vec<_> = working_set.keys().collect();
     for slot in working_set_slots {
-        handle_chaining_for_slot(db, write_batch, working_set, &mut new_chained_slots, *slot)?;
+        is_chained |=
+            handle_chaining_for_slot(db, write_batch, working_set, &mut new_chained_slots, *slot)?;
     }
 
     // Write all the newly changed slots in new_chained_slots to the write_batch
@@ -3610,7 +4341,7 @@ fn handle_chaining(
         let meta: &SlotMeta = &RefCell::borrow(&*meta);
         write_batch.put::<cf::SlotMeta>(*slot, meta)?;
     }
-    Ok(())
+    Ok(is_chained)
 }
 
 /// A helper function of handle_chaining which handles the chaining based
@@ -3641,19 +4372,22 @@ fn handle_chaining(
 /// `new_chained_slots`: an output parameter which includes all the slots
 ///   which connectivity have been updated.
 /// `slot`: the slot which we want to handle its chaining effect.
+///
+/// Returns whether the slot was chained to its parent or its children.
 fn handle_chaining_for_slot(
     db: &Database,
     write_batch: &mut WriteBatch,
     working_set: &HashMap<u64, SlotMetaWorkingSetEntry>,
     new_chained_slots: &mut HashMap<u64, Rc<RefCell<SlotMeta>>>,
     slot: Slot,
-) -> Result<()> {
+) -> Result<bool> {
     let slot_meta_entry = working_set
         .get(&slot)
         .expect("Slot must exist in the working_set hashmap");
 
     let meta = &slot_meta_entry.new_slot_meta;
     let meta_backup = &slot_meta_entry.old_slot_meta;
+    let mut is_chained = false;
 
     {
         let mut meta_mut = meta.borrow_mut();
@@ -3670,6 +4404,7 @@ fn handle_chaining_for_slot(
             // In both cases we need to run the chaining logic b/c the parent on the slot was
             // previously unknown.
             if meta_backup.is_none() || was_orphan_slot {
+                is_chained = true;
                 let prev_slot_meta =
                     find_slot_meta_else_create(db, working_set, new_chained_slots, prev_slot)?;
 
@@ -3687,6 +4422,7 @@ fn handle_chaining_for_slot(
 
         // At this point this slot has received a parent, so it's no longer an orphan
         if was_orphan_slot {
+            is_chained = true;
             write_batch.delete::<cf::Orphans>(slot)?;
         }
     }
@@ -3700,6 +4436,7 @@ fn handle_chaining_for_slot(
             && RefCell::borrow(&*meta).is_connected;
 
     if should_propagate_is_connected {
+        is_chained = true;
         // slot_function returns a boolean indicating whether to explore the children
         // of the input slot
         let slot_function = |slot: &mut SlotMeta| {
@@ -3720,7 +4457,7 @@ fn handle_chaining_for_slot(
         )?;
     }
 
-    Ok(())
+    Ok(is_chained)
 }
 
 /// Traverse all the direct and indirect children slots and apply the specified
@@ -3782,7 +4519,11 @@ fn chain_new_slot_to_prev_slot(
     current_slot: Slot,
     current_slot_meta: &mut SlotMeta,
 ) {
-    prev_slot_meta.next_slots.push(current_slot);
+    // The slot may be chained again if its own update was not written, see
+    // `insert_shreds_handle_duplicate`
+    if !prev_slot_meta.next_slots.contains(&current_slot) {
+        prev_slot_meta.next_slots.push(current_slot);
+    }
     current_slot_meta.is_connected = prev_slot_meta.is_connected && prev_slot_meta.is_full();
 }
 
@@ -3828,6 +4569,9 @@ pub fn create_new_ledger(
             recovery_mode: None,
             enforce_ulimit_nofile: false,
             column_options: column_options.clone(),
+            rocksdb_tuning_options: None,
+            verify_on_open: None,
+            column_layout: ColumnLayout::default(),
         },
     )?;
     let ticks_per_slot = genesis_config.ticks_per_slot;
@@ -4316,7 +5060,11 @@ pub mod tests {
     use {
         super::*,
         crate::{
-            blockstore_options::BlockstoreRocksFifoOptions,
+            blockstore_db::ColumnName,
+            blockstore_options::{
+                BlockstoreColumnFilterOptions, BlockstorePrefixExtractor,
+                BlockstoreRocksFifoOptions, RocksDbTuningOptions,
+            },
             genesis_utils::{create_genesis_config, GenesisConfigInfo},
             leader_schedule::{FixedSchedule, LeaderSchedule},
             shred::{max_ticks_per_n_shreds, ShredFlags},
@@ -4370,46 +5118,568 @@ pub mod tests {
         let (ledger_path, _blockhash) = create_new_tmp_ledger_auto_delete!(&genesis_config);
         let blockstore = Blockstore::open(ledger_path.path()).unwrap(); //FINDME
 
-        let ticks = create_ticks(genesis_config.ticks_per_slot, 0, genesis_config.hash());
-        let entries = blockstore.get_slot_entries(0, 0).unwrap();
+        let ticks = create_ticks(genesis_config.ticks_per_slot, 0, genesis_config.hash());
+        let entries = blockstore.get_slot_entries(0, 0).unwrap();
+
+        assert_eq!(ticks, entries);
+        assert!(Path::new(ledger_path.path())
+            .join(Blockstore::blockstore_directory(
+                &ShredStorageType::RocksLevel,
+            ))
+            .exists());
+    }
+
+    #[test]
+    fn test_create_new_ledger_with_options_fifo() {
+        solana_logger::setup();
+        let mint_total = 1_000_000_000_000;
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(mint_total);
+        let (ledger_path, _blockhash) = create_new_tmp_ledger_fifo_auto_delete!(&genesis_config);
+        let blockstore = Blockstore::open_with_options(
+            ledger_path.path(),
+            BlockstoreOptions {
+                column_options: LedgerColumnOptions {
+                    shred_storage_type: ShredStorageType::RocksFifo(
+                        BlockstoreRocksFifoOptions::default(),
+                    ),
+                    ..LedgerColumnOptions::default()
+                },
+                ..BlockstoreOptions::default()
+            },
+        )
+        .unwrap();
+
+        let ticks = create_ticks(genesis_config.ticks_per_slot, 0, genesis_config.hash());
+        let entries = blockstore.get_slot_entries(0, 0).unwrap();
+
+        assert_eq!(ticks, entries);
+        assert!(Path::new(ledger_path.path())
+            .join(Blockstore::blockstore_directory(
+                &ShredStorageType::RocksFifo(BlockstoreRocksFifoOptions::default())
+            ))
+            .exists());
+    }
+
+    #[test]
+    fn test_open_fifo_below_minimum_size() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let fifo_options = |clamp_to_minimum_size| BlockstoreOptions {
+            column_options: LedgerColumnOptions {
+                shred_storage_type: ShredStorageType::RocksFifo(BlockstoreRocksFifoOptions {
+                    shred_data_cf_size: 0,
+                    clamp_to_minimum_size,
+                    ..BlockstoreRocksFifoOptions::default()
+                }),
+                ..LedgerColumnOptions::default()
+            },
+            ..BlockstoreOptions::default()
+        };
+
+        assert_matches!(
+            Blockstore::open_with_options(ledger_path.path(), fifo_options(false)),
+            Err(BlockstoreError::InvalidFifoOptions(_))
+        );
+        let blockstore =
+            Blockstore::open_with_options(ledger_path.path(), fifo_options(true)).unwrap();
+        let (shreds, _) = make_slot_entries(0, 0, 1);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        assert!(blockstore.meta(0).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_open_with_column_filter_options() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let column_filter_options = [
+            (
+                cf::ShredData::NAME,
+                BlockstoreColumnFilterOptions {
+                    bloom_filter_bits_per_key: Some(10),
+                    prefix_extractor: Some(BlockstorePrefixExtractor::Slot),
+                },
+            ),
+            (
+                cf::TransactionStatus::NAME,
+                BlockstoreColumnFilterOptions {
+                    bloom_filter_bits_per_key: Some(10),
+                    prefix_extractor: None,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let blockstore = Blockstore::open_with_options(
+            ledger_path.path(),
+            BlockstoreOptions {
+                column_options: LedgerColumnOptions {
+                    column_filter_options,
+                    ..LedgerColumnOptions::default()
+                },
+                ..BlockstoreOptions::default()
+            },
+        )
+        .unwrap();
+
+        // Range scans across slots must still see every slot with a prefix extractor set
+        let num_slots = 4;
+        for slot in 0..num_slots {
+            let (shreds, _) = make_slot_entries(slot, slot.saturating_sub(1), 1);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        assert!(blockstore
+            .slot_data_iterator(2, 0)
+            .unwrap()
+            .all(|((slot, _), _)| slot == 2));
+        let all_slots: HashSet<Slot> = blockstore
+            .db
+            .iter::<cf::ShredData>(IteratorMode::Start)
+            .unwrap()
+            .map(|((slot, _), _)| slot)
+            .collect();
+        assert_eq!(all_slots, (0..num_slots).collect());
+    }
+
+    #[test]
+    fn test_slot_iterator_with_prefix_longer_than_slot() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        // Every shred has a prefix of its own, so scans of a slot can't be bounded by prefix
+        let blockstore = Blockstore::open_with_options(
+            ledger_path.path(),
+            BlockstoreOptions {
+                column_options: LedgerColumnOptions {
+                    column_filter_options: [(
+                        cf::ShredData::NAME,
+                        BlockstoreColumnFilterOptions {
+                            bloom_filter_bits_per_key: Some(10),
+                            prefix_extractor: Some(BlockstorePrefixExtractor::FixedLength(16)),
+                        },
+                    )]
+                    .into_iter()
+                    .collect(),
+                    ..LedgerColumnOptions::default()
+                },
+                ..BlockstoreOptions::default()
+            },
+        )
+        .unwrap();
+
+        let (shreds, _) = make_slot_entries(1, 0, 100);
+        let num_shreds = shreds.len();
+        assert!(num_shreds > 1);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let (shreds, _) = make_slot_entries(2, 1, 100);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        assert_eq!(
+            blockstore.slot_data_iterator(1, 0).unwrap().count(),
+            num_shreds
+        );
+    }
+
+    #[test]
+    fn test_open_with_rocksdb_tuning_options() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open_with_options(
+            ledger_path.path(),
+            BlockstoreOptions {
+                // The shared block cache has to be set alongside the bloom filters
+                column_options: LedgerColumnOptions {
+                    column_filter_options: [(
+                        cf::ShredData::NAME,
+                        BlockstoreColumnFilterOptions {
+                            bloom_filter_bits_per_key: Some(10),
+                            prefix_extractor: None,
+                        },
+                    )]
+                    .into_iter()
+                    .collect(),
+                    ..LedgerColumnOptions::default()
+                },
+                rocksdb_tuning_options: Some(RocksDbTuningOptions {
+                    write_buffer_size: Some(1024 * 1024),
+                    max_write_buffer_number: Some(2),
+                    block_cache_size: Some(8 * 1024 * 1024),
+                    max_background_jobs: Some(2),
+                }),
+                ..BlockstoreOptions::default()
+            },
+        )
+        .unwrap();
+
+        let (shreds, _) = make_slot_entries(0, 0, 10);
+        let num_shreds = shreds.len();
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        assert_eq!(
+            blockstore.get_data_shreds_for_slot(0, 0).unwrap().len(),
+            num_shreds
+        );
+        drop(blockstore);
+
+        // Tuning options aren't persisted, so the ledger reopens with the defaults
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        assert_eq!(
+            blockstore.get_data_shreds_for_slot(0, 0).unwrap().len(),
+            num_shreds
+        );
+    }
+
+    #[test]
+    fn test_open_locked_blockstore() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        assert_matches!(
+            Blockstore::open(ledger_path.path()),
+            Err(BlockstoreError::BlockstoreLocked(_, Some(owner)))
+                if owner.pid == std::process::id()
+        );
+        // Secondary access doesn't contend for the lock
+        Blockstore::open_with_options(
+            ledger_path.path(),
+            BlockstoreOptions {
+                access_type: AccessType::Secondary,
+                ..BlockstoreOptions::default()
+            },
+        )
+        .unwrap();
+
+        drop(blockstore);
+        Blockstore::open(ledger_path.path()).unwrap();
+    }
+
+    #[test]
+    fn test_set_rocks_perf_sample_interval() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let access_orphans = || {
+            for slot in 0..10 {
+                blockstore.orphans_cf.put(slot, &true).unwrap();
+                assert_eq!(blockstore.orphans_cf.get(slot).unwrap(), Some(true));
+            }
+        };
+        assert_eq!(blockstore.rocks_perf_sample_interval(), 0);
+        access_orphans();
+        assert_eq!(blockstore.orphans_cf.latency().num_samples(), (0, 0));
+
+        blockstore.set_rocks_perf_sample_interval(1);
+        assert_eq!(blockstore.rocks_perf_sample_interval(), 1);
+        access_orphans();
+        let (num_reads, num_writes) = blockstore.orphans_cf.latency().num_samples();
+        assert!(num_reads > 0);
+        assert!(num_writes > 0);
+        // Columns opened anew share the latencies of the column
+        assert_eq!(
+            blockstore
+                .db
+                .column::<cf::Orphans>()
+                .latency()
+                .num_samples(),
+            (num_reads, num_writes)
+        );
+        // Reporting the latencies clears them
+        blockstore.submit_rocksdb_cf_metrics_for_all_cfs();
+        assert_eq!(blockstore.orphans_cf.latency().num_samples(), (0, 0));
+
+        blockstore.set_rocks_perf_sample_interval(0);
+        access_orphans();
+        assert_eq!(blockstore.orphans_cf.latency().num_samples(), (0, 0));
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let read_only_options = || BlockstoreOptions {
+            access_type: AccessType::ReadOnly,
+            ..BlockstoreOptions::default()
+        };
+        // There is no database to take a snapshot of yet
+        assert!(Blockstore::open_with_options(ledger_path.path(), read_only_options()).is_err());
+
+        let (shreds, _) = make_slot_entries(0, 0, 1);
+        let num_shreds = shreds.len() as u64;
+        {
+            let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+
+        let blockstore =
+            Blockstore::open_with_options(ledger_path.path(), read_only_options()).unwrap();
+        assert!(!blockstore.is_primary_access());
+        assert_eq!(blockstore.meta(0).unwrap().unwrap().received, num_shreds);
+        assert!(blockstore.set_roots([0].iter()).is_err());
+        assert!(!blockstore.is_root(0));
+    }
+
+    #[test]
+    fn test_migrate_shred_storage() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let fifo_options = || BlockstoreOptions {
+            column_options: LedgerColumnOptions {
+                shred_storage_type: ShredStorageType::RocksFifo(
+                    BlockstoreRocksFifoOptions::default(),
+                ),
+                ..LedgerColumnOptions::default()
+            },
+            ..BlockstoreOptions::default()
+        };
+        let num_slots = 4;
+        let mut expected_shreds = vec![];
+        {
+            let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+            for slot in 0..num_slots {
+                let (shreds, _) = make_slot_entries(slot, slot.saturating_sub(1), 1);
+                expected_shreds.extend(shreds.iter().map(|shred| shred.payload().clone()));
+                blockstore.insert_shreds(shreds, None, false).unwrap();
+            }
+            blockstore.set_roots([0, 1].iter()).unwrap();
+        }
+        let level_path = ledger_path.path().join(BLOCKSTORE_DIRECTORY_ROCKS_LEVEL);
+        let fifo_path = ledger_path.path().join(BLOCKSTORE_DIRECTORY_ROCKS_FIFO);
+        assert_eq!(
+            Blockstore::find_mismatched_shred_storage(
+                ledger_path.path(),
+                &fifo_options().column_options.shred_storage_type
+            ),
+            Some(level_path.clone())
+        );
+        assert_eq!(
+            Blockstore::find_mismatched_shred_storage(
+                ledger_path.path(),
+                &ShredStorageType::RocksLevel
+            ),
+            None
+        );
+
+        let mut progress = HashMap::new();
+        let copied = Blockstore::migrate_shred_storage(
+            ledger_path.path(),
+            fifo_options(),
+            |cf_name, copied| {
+                progress.insert(cf_name, copied);
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(copied, progress.values().sum::<u64>());
+        assert!(progress[cf::ShredData::NAME] >= expected_shreds.len() as u64);
+        assert!(!level_path.exists());
+        assert!(fifo_path.exists());
+
+        {
+            let blockstore =
+                Blockstore::open_with_options(ledger_path.path(), fifo_options()).unwrap();
+            let shreds: Vec<_> = blockstore
+                .db
+                .iter::<cf::ShredData>(IteratorMode::Start)
+                .unwrap()
+                .map(|(_, payload)| payload.to_vec())
+                .collect();
+            assert_eq!(shreds, expected_shreds);
+            assert!(blockstore.is_root(1));
+            assert!(!blockstore.is_root(2));
+        }
+
+        // Nothing left to migrate
+        assert_eq!(
+            Blockstore::migrate_shred_storage(ledger_path.path(), fifo_options(), |_, _| ())
+                .unwrap(),
+            None
+        );
+
+        // A non-empty blockstore of the requested type can't be merged into
+        {
+            let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+            let (shreds, _) = make_slot_entries(0, 0, 1);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        assert_matches!(
+            Blockstore::migrate_shred_storage(
+                ledger_path.path(),
+                BlockstoreOptions::default(),
+                |_, _| ()
+            ),
+            Err(BlockstoreError::ShredStorageMigrationConflict(_))
+        );
+    }
+
+    #[test]
+    fn test_column_layout() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let shred_dir = tempfile::TempDir::new().unwrap();
+        let mut column_layout = ColumnLayout::default();
+        column_layout.set_column_path(cf::ShredData::NAME, shred_dir.path());
+        column_layout.set_column_path(cf::ShredCode::NAME, shred_dir.path());
+        let layout_options = |column_layout: &ColumnLayout| BlockstoreOptions {
+            column_layout: column_layout.clone(),
+            ..BlockstoreOptions::default()
+        };
+
+        let (shreds, _) = make_slot_entries(0, 0, 1);
+        let expected_shreds: Vec<_> = shreds.iter().map(|shred| shred.payload().clone()).collect();
+        {
+            let blockstore =
+                Blockstore::open_with_options(ledger_path.path(), layout_options(&column_layout))
+                    .unwrap();
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        assert!(Blockstore::blockstore_exists(
+            &shred_dir.path().join(BLOCKSTORE_DIRECTORY_ROCKS_LEVEL)
+        ));
+
+        // The recorded layout is used when none is requested
+        {
+            let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+            assert_eq!(blockstore.db.column_layout(), &column_layout);
+            let shreds: Vec<_> = blockstore
+                .db
+                .iter::<cf::ShredData>(IteratorMode::Start)
+                .unwrap()
+                .map(|(_, payload)| payload.to_vec())
+                .collect();
+            assert_eq!(shreds, expected_shreds);
+            assert_eq!(
+                blockstore.meta(0).unwrap().unwrap().received,
+                expected_shreds.len() as u64
+            );
+        }
+
+        // The layout of an existing blockstore can only be changed by moving its columns
+        let other_dir = tempfile::TempDir::new().unwrap();
+        let mut other_layout = ColumnLayout::default();
+        other_layout.set_column_path(cf::ShredData::NAME, other_dir.path());
+        assert_matches!(
+            Blockstore::open_with_options(ledger_path.path(), layout_options(&other_layout)),
+            Err(BlockstoreError::InvalidColumnLayout(_))
+        );
 
-        assert_eq!(ticks, entries);
-        assert!(Path::new(ledger_path.path())
-            .join(Blockstore::blockstore_directory(
-                &ShredStorageType::RocksLevel,
-            ))
+        // Metadata columns stay in the blockstore directory
+        for cf_name in [cf::SlotMeta::NAME, cf::Root::NAME, "no_such_column"] {
+            let mut invalid_layout = ColumnLayout::default();
+            invalid_layout.set_column_path(cf_name, other_dir.path());
+            assert_matches!(
+                Blockstore::open_with_options(ledger_path.path(), layout_options(&invalid_layout)),
+                Err(BlockstoreError::InvalidColumnLayout(_))
+            );
+        }
+
+        Blockstore::destroy(ledger_path.path()).unwrap();
+        assert!(!shred_dir
+            .path()
+            .join(BLOCKSTORE_DIRECTORY_ROCKS_LEVEL)
             .exists());
     }
 
     #[test]
-    fn test_create_new_ledger_with_options_fifo() {
-        solana_logger::setup();
-        let mint_total = 1_000_000_000_000;
-        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(mint_total);
-        let (ledger_path, _blockhash) = create_new_tmp_ledger_fifo_auto_delete!(&genesis_config);
+    fn test_create_checkpoint() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let shred_dir = tempfile::TempDir::new().unwrap();
+        let checkpoint_dir = tempfile::TempDir::new().unwrap();
+        let checkpoint_path = checkpoint_dir.path().join("ledger");
+        let mut column_layout = ColumnLayout::default();
+        column_layout.set_column_path(cf::ShredData::NAME, shred_dir.path());
         let blockstore = Blockstore::open_with_options(
             ledger_path.path(),
             BlockstoreOptions {
-                column_options: LedgerColumnOptions {
-                    shred_storage_type: ShredStorageType::RocksFifo(
-                        BlockstoreRocksFifoOptions::default(),
-                    ),
-                    ..LedgerColumnOptions::default()
-                },
+                column_layout,
                 ..BlockstoreOptions::default()
             },
         )
         .unwrap();
+        let data_shreds = |blockstore: &Blockstore, slot| {
+            blockstore
+                .slot_data_iterator(slot, 0)
+                .unwrap()
+                .map(|(_, payload)| payload.to_vec())
+                .collect::<Vec<_>>()
+        };
 
-        let ticks = create_ticks(genesis_config.ticks_per_slot, 0, genesis_config.hash());
-        let entries = blockstore.get_slot_entries(0, 0).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 2, 1);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        blockstore.create_checkpoint(&checkpoint_path).unwrap();
+        // Writes after the checkpoint are not part of it
+        let (shreds, _) = make_many_slot_entries(2, 1, 1);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        // An existing checkpoint is never overwritten
+        assert_matches!(
+            blockstore.create_checkpoint(&checkpoint_path),
+            Err(BlockstoreError::Io(_))
+        );
 
-        assert_eq!(ticks, entries);
-        assert!(Path::new(ledger_path.path())
-            .join(Blockstore::blockstore_directory(
-                &ShredStorageType::RocksFifo(BlockstoreRocksFifoOptions::default())
-            ))
-            .exists());
+        let checkpoint = Blockstore::open(&checkpoint_path).unwrap();
+        assert_eq!(
+            checkpoint
+                .db
+                .column_layout()
+                .column_path(cf::ShredData::NAME),
+            Some(checkpoint_path.join("columns-0").as_path())
+        );
+        for slot in 0..2 {
+            assert!(!data_shreds(&checkpoint, slot).is_empty());
+            assert_eq!(
+                data_shreds(&checkpoint, slot),
+                data_shreds(&blockstore, slot)
+            );
+            assert_eq!(
+                checkpoint.meta(slot).unwrap(),
+                blockstore.meta(slot).unwrap()
+            );
+        }
+        assert!(checkpoint.meta(2).unwrap().is_none());
+        assert!(data_shreds(&checkpoint, 2).is_empty());
+    }
+
+    #[test]
+    fn test_relocate_columns() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let column_dir = tempfile::TempDir::new().unwrap();
+        let num_slots = 4;
+        let mut expected_shreds = vec![];
+        {
+            let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+            for slot in 0..num_slots {
+                let (shreds, _) = make_slot_entries(slot, slot.saturating_sub(1), 1);
+                expected_shreds.extend(shreds.iter().map(|shred| shred.payload().clone()));
+                blockstore.insert_shreds(shreds, None, false).unwrap();
+            }
+            blockstore.set_roots([0, 1].iter()).unwrap();
+        }
+        let assert_blockstore_intact = |column_layout: &ColumnLayout| {
+            let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+            assert_eq!(blockstore.db.column_layout(), column_layout);
+            let shreds: Vec<_> = blockstore
+                .db
+                .iter::<cf::ShredData>(IteratorMode::Start)
+                .unwrap()
+                .map(|(_, payload)| payload.to_vec())
+                .collect();
+            assert_eq!(shreds, expected_shreds);
+            assert!(blockstore.is_root(1));
+            assert!(!blockstore.is_root(2));
+        };
+
+        let mut column_layout = ColumnLayout::default();
+        column_layout.set_column_path(cf::ShredData::NAME, column_dir.path());
+        let mut progress = HashMap::new();
+        let copied = Blockstore::relocate_columns(
+            ledger_path.path(),
+            BlockstoreOptions {
+                column_layout: column_layout.clone(),
+                ..BlockstoreOptions::default()
+            },
+            |cf_name, copied| {
+                progress.insert(cf_name, copied);
+            },
+        )
+        .unwrap();
+        assert_eq!(progress.len(), 1);
+        assert_eq!(copied, progress[cf::ShredData::NAME]);
+        assert!(copied >= expected_shreds.len() as u64);
+        let relocated_path = column_dir.path().join(BLOCKSTORE_DIRECTORY_ROCKS_LEVEL);
+        assert!(Blockstore::blockstore_exists(&relocated_path));
+        assert_blockstore_intact(&column_layout);
+
+        // Moving the column back removes the directory that held it
+        Blockstore::relocate_columns(ledger_path.path(), BlockstoreOptions::default(), |_, _| ())
+            .unwrap();
+        assert!(!relocated_path.exists());
+        assert_blockstore_intact(&ColumnLayout::default());
     }
 
     #[test]
@@ -5224,6 +6494,64 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_insert_shreds_multiple_slots() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        let num_slots = 4;
+        let slot_entries: Vec<_> = (0..num_slots)
+            .map(|slot| make_slot_entries(slot, slot.saturating_sub(1), 20))
+            .collect();
+        let num_shreds = slot_entries[0].0.len();
+        assert!(num_shreds > 2);
+        // Interleave the shreds of the slots within each range of shred indices
+        let interleave = |indices: std::ops::Range<usize>| -> Vec<Shred> {
+            indices
+                .flat_map(|index| slot_entries.iter().map(move |(shreds, _)| &shreds[index]))
+                .cloned()
+                .collect()
+        };
+
+        // The first shreds chain the slots to each other
+        let (_, inserted_indices) = blockstore
+            .insert_shreds(interleave(0..1), None, false)
+            .unwrap();
+        assert_eq!(
+            inserted_indices,
+            (0..num_slots as usize).collect::<Vec<_>>()
+        );
+        for slot in 0..num_slots {
+            let meta = blockstore.meta(slot).unwrap().unwrap();
+            assert_eq!(meta.consumed, 1);
+            if slot + 1 < num_slots {
+                assert_eq!(meta.next_slots, vec![slot + 1]);
+            }
+        }
+
+        // The slots are already chained, so each is written on its own
+        let shreds = interleave(1..num_shreds - 1);
+        let num_inserted = shreds.len();
+        let (_, inserted_indices) = blockstore.insert_shreds(shreds, None, false).unwrap();
+        assert_eq!(inserted_indices, (0..num_inserted).collect::<Vec<_>>());
+        for slot in 0..num_slots {
+            let meta = blockstore.meta(slot).unwrap().unwrap();
+            assert_eq!(meta.consumed, num_shreds as u64 - 1);
+            assert!(!meta.is_full());
+        }
+
+        // Completing the slots connects them
+        blockstore
+            .insert_shreds(interleave(num_shreds - 1..num_shreds), None, false)
+            .unwrap();
+        for (slot, (_, entries)) in (0..num_slots).zip(&slot_entries) {
+            let meta = blockstore.meta(slot).unwrap().unwrap();
+            assert!(meta.is_full());
+            assert!(meta.is_connected);
+            assert_eq!(&blockstore.get_slot_entries(slot, 0).unwrap(), entries);
+        }
+    }
+
     #[test]
     pub fn test_handle_chaining_missing_slots() {
         let ledger_path = get_tmp_ledger_path_auto_delete!();
@@ -6172,6 +7500,60 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_rooted_slot_iterator_reverse() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        blockstore
+            .set_roots(vec![0, 2, 4, 7, 12, 15].iter())
+            .unwrap();
+
+        let roots: Vec<_> = blockstore
+            .rooted_slot_iterator_reverse(12)
+            .unwrap()
+            .collect();
+        assert_eq!(roots, vec![12, 7, 4, 2, 0]);
+        let roots: Vec<_> = blockstore
+            .rooted_slot_iterator_reverse(11)
+            .unwrap()
+            .take(2)
+            .collect();
+        assert_eq!(roots, vec![7, 4]);
+        let roots: Vec<_> = blockstore
+            .rooted_slot_iterator_reverse(Slot::MAX)
+            .unwrap()
+            .take(1)
+            .collect();
+        assert_eq!(roots, vec![15]);
+    }
+
+    #[test]
+    fn test_ledger_snapshot() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        blockstore.set_roots(vec![1, 2, 3].iter()).unwrap();
+        blockstore.cache_block_time(2, 42).unwrap();
+
+        let snapshot = blockstore.db.snapshot();
+        blockstore.cache_block_time(2, 43).unwrap();
+        blockstore.cache_block_time(3, 44).unwrap();
+        blockstore.set_roots(vec![4].iter()).unwrap();
+        blockstore.run_purge(0, 2, PurgeType::PrimaryIndex).unwrap();
+
+        // the latest state is visible outside of the snapshot
+        assert_eq!(blockstore.get_block_time(2).unwrap(), None);
+        assert_eq!(blockstore.get_block_time(3).unwrap(), Some(44));
+        // while reads through the snapshot see the ledger as of when it was taken
+        assert_eq!(snapshot.get::<cf::Blocktime>(2).unwrap(), Some(42));
+        assert_eq!(snapshot.get::<cf::Blocktime>(3).unwrap(), None);
+        let roots: Vec<_> = snapshot
+            .iter::<cf::Root>(IteratorMode::Start)
+            .unwrap()
+            .map(|(slot, _)| slot)
+            .collect();
+        assert_eq!(roots, vec![1, 2, 3]);
+    }
+
     #[test]
     fn test_is_skipped() {
         let ledger_path = get_tmp_ledger_path_auto_delete!();
@@ -8144,6 +9526,49 @@ pub mod tests {
             .infos;
         assert!(results2.len() < results.len());
 
+        // Restrict the listing to a slot range
+        let results = blockstore
+            .get_confirmed_signatures_for_address_in_slot_range(
+                address0,
+                highest_confirmed_slot,
+                None,
+                None,
+                4..=6,
+                usize::MAX,
+            )
+            .unwrap()
+            .infos;
+        assert_eq!(results.len(), 6);
+        assert!(results.iter().all(|info| (4..=6).contains(&info.slot)));
+
+        // A `before` signature above the range starts the listing at the top of the range
+        let results2 = blockstore
+            .get_confirmed_signatures_for_address_in_slot_range(
+                address0,
+                highest_confirmed_slot,
+                Some(all0[0].signature),
+                None,
+                4..=6,
+                usize::MAX,
+            )
+            .unwrap()
+            .infos;
+        assert_eq!(results2, results);
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let sig_infos = blockstore
+            .get_confirmed_signatures_for_address_in_slot_range(
+                address0,
+                highest_confirmed_slot,
+                None,
+                None,
+                6..=5,
+                usize::MAX,
+            )
+            .unwrap();
+        assert!(sig_infos.found_before);
+        assert!(sig_infos.infos.is_empty());
+
         // Remove signature
         blockstore
             .address_signatures_cf
@@ -8162,6 +9587,44 @@ pub mod tests {
         assert!(sig_infos.infos.is_empty());
     }
 
+    #[test]
+    fn test_get_first_slot_at_or_after_block_time() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        blockstore.set_roots(vec![0, 1, 2, 4, 5, 8].iter()).unwrap();
+        for (slot, block_time) in [(0, 100), (1, 100), (2, 110), (4, 120), (8, 150)] {
+            blockstore.cache_block_time(slot, block_time).unwrap();
+        }
+
+        let first_slot = |timestamp| {
+            blockstore
+                .get_first_slot_at_or_after_block_time(timestamp, 0..=10)
+                .unwrap()
+        };
+        assert_eq!(first_slot(0), Some(0));
+        assert_eq!(first_slot(100), Some(0));
+        assert_eq!(first_slot(105), Some(2));
+        assert_eq!(first_slot(110), Some(2));
+        // Root 5 has no block time, so it is judged by root 4
+        assert_eq!(first_slot(130), Some(8));
+        assert_eq!(first_slot(150), Some(8));
+        assert_eq!(first_slot(151), None);
+
+        assert_eq!(
+            blockstore
+                .get_first_slot_at_or_after_block_time(100, 3..=10)
+                .unwrap(),
+            Some(3)
+        );
+        assert_eq!(
+            blockstore
+                .get_first_slot_at_or_after_block_time(130, 0..=7)
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     #[allow(clippy::same_item_push)]
     fn test_get_last_hash() {
@@ -8274,6 +9737,98 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_write_get_block_costs() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        let program_id = Pubkey::new_unique();
+        let block_costs: Vec<_> = (1..=5)
+            .map(|slot| {
+                (
+                    slot,
+                    BlockCost {
+                        compute_units: slot * 1_000,
+                        program_compute_units: vec![(program_id, slot * 1_000)],
+                        block_cost: 0,
+                        block_cost_limit: 10_000,
+                        fees: slot * 5_000,
+                    },
+                )
+            })
+            .collect();
+        for (slot, block_cost) in &block_costs {
+            blockstore.write_block_cost(*slot, block_cost).unwrap();
+        }
+        assert_eq!(
+            blockstore.read_block_cost(3).unwrap(),
+            Some(block_costs[2].1.clone())
+        );
+        assert_eq!(
+            blockstore
+                .read_block_cost(3)
+                .unwrap()
+                .unwrap()
+                .utilization(),
+            0.3
+        );
+        assert_eq!(blockstore.read_block_cost(6).unwrap(), None);
+        assert_eq!(
+            blockstore.get_recent_block_costs(2).unwrap(),
+            vec![block_costs[4].clone(), block_costs[3].clone()]
+        );
+
+        blockstore.purge_slots(0, 3, PurgeType::Exact);
+        assert_eq!(blockstore.read_block_cost(3).unwrap(), None);
+        assert_eq!(blockstore.get_recent_block_costs(5).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_record_slot_purge() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let mut blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        // Nothing is recorded until enabled
+        blockstore
+            .record_slot_purge(0, 9, SlotPurgeReason::Manual)
+            .unwrap();
+        assert_eq!(blockstore.get_slot_tombstone(5).unwrap(), None);
+
+        blockstore.set_record_slot_tombstones(true);
+        blockstore
+            .record_slot_purge(0, 9, SlotPurgeReason::Archived)
+            .unwrap();
+        blockstore
+            .record_slot_purge(20, 29, SlotPurgeReason::LedgerCleanup)
+            .unwrap();
+
+        let tombstone = blockstore.get_slot_tombstone(5).unwrap().unwrap();
+        assert_eq!(
+            (tombstone.first_slot, tombstone.last_slot, tombstone.reason),
+            (0, 9, SlotPurgeReason::Archived)
+        );
+        assert_eq!(
+            blockstore.get_slot_tombstone(20).unwrap().unwrap().reason,
+            SlotPurgeReason::LedgerCleanup
+        );
+        // Slots between and after the purged ranges have no tombstone
+        assert_eq!(blockstore.get_slot_tombstone(15).unwrap(), None);
+        assert_eq!(blockstore.get_slot_tombstone(30).unwrap(), None);
+
+        let tombstones = blockstore.get_recent_slot_tombstones(5).unwrap();
+        assert_eq!(
+            tombstones
+                .iter()
+                .map(|tombstone| tombstone.last_slot)
+                .collect::<Vec<_>>(),
+            vec![29, 9]
+        );
+
+        // Tombstones outlive the slots they describe
+        blockstore.purge_slots(0, 29, PurgeType::Exact);
+        assert!(blockstore.get_slot_tombstone(5).unwrap().is_some());
+    }
+
     #[test]
     fn test_lowest_slot() {
         let ledger_path = get_tmp_ledger_path_auto_delete!();
@@ -8583,6 +10138,83 @@ pub mod tests {
         assert_eq!(duplicate_proof.shred2, *duplicate_shred.payload());
     }
 
+    #[test]
+    fn test_store_duplicate_proofs() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        let (shred1, shred2, shred3) = (vec![1u8; 8], vec![2u8; 8], vec![3u8; 8]);
+        let node = Pubkey::new_unique();
+        assert!(blockstore.get_duplicate_proofs(5).unwrap().is_empty());
+        assert!(blockstore
+            .store_duplicate_proof(
+                5,
+                shred1.clone(),
+                shred2.clone(),
+                DuplicateProofSource::Local
+            )
+            .unwrap());
+        // The same proof is stored once, whatever the order of its shreds
+        assert!(!blockstore
+            .store_duplicate_proof(
+                5,
+                shred2.clone(),
+                shred1.clone(),
+                DuplicateProofSource::Gossip(node),
+            )
+            .unwrap());
+        assert!(blockstore
+            .store_duplicate_proof(
+                5,
+                shred1.clone(),
+                shred3.clone(),
+                DuplicateProofSource::Gossip(node),
+            )
+            .unwrap());
+        assert!(blockstore
+            .store_duplicate_proof(6, shred1, shred3, DuplicateProofSource::Local)
+            .unwrap());
+
+        let proofs = blockstore.get_duplicate_proofs(5).unwrap();
+        assert_eq!(proofs.len(), 2);
+        assert!(proofs.iter().any(|proof| {
+            proof.shred1 == vec![1u8; 8]
+                && proof.shred2 == shred2
+                && proof.source == DuplicateProofSource::Local
+        }));
+        assert!(proofs
+            .iter()
+            .any(|proof| proof.source == DuplicateProofSource::Gossip(node)));
+        assert_eq!(blockstore.get_duplicate_proofs(6).unwrap().len(), 1);
+
+        blockstore.purge_slots(0, 5, PurgeType::Exact);
+        assert!(blockstore.get_duplicate_proofs(5).unwrap().is_empty());
+        assert_eq!(blockstore.get_duplicate_proofs(6).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_dead_slot_cause() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        blockstore.set_dead_slot(1).unwrap();
+        assert!(blockstore.is_dead(1));
+        assert!(blockstore.get_dead_slot_cause(1).unwrap().is_none());
+
+        let cause = DeadSlotCause::TransactionFailed("insufficient funds".to_string());
+        blockstore
+            .set_dead_slot_with_cause(2, cause.clone())
+            .unwrap();
+        assert!(blockstore.is_dead(2));
+        let meta = blockstore.get_dead_slot_cause(2).unwrap().unwrap();
+        assert_eq!(meta.cause(), &cause);
+        assert!(meta.timestamp() > 0);
+
+        blockstore.remove_dead_slot(2).unwrap();
+        assert!(!blockstore.is_dead(2));
+        assert!(blockstore.get_dead_slot_cause(2).unwrap().is_none());
+    }
+
     #[test]
     fn test_clear_unconfirmed_slot() {
         let ledger_path = get_tmp_ledger_path_auto_delete!();
@@ -8607,11 +10239,17 @@ pub mod tests {
             .get_data_shred(unconfirmed_slot, 1)
             .unwrap()
             .is_none());
-        blockstore.set_dead_slot(unconfirmed_slot).unwrap();
+        blockstore
+            .set_dead_slot_with_cause(unconfirmed_slot, DeadSlotCause::InvalidEntryHash)
+            .unwrap();
 
         // Purge the slot
         blockstore.clear_unconfirmed_slot(unconfirmed_slot);
         assert!(!blockstore.is_dead(unconfirmed_slot));
+        assert!(blockstore
+            .get_dead_slot_cause(unconfirmed_slot)
+            .unwrap()
+            .is_none());
         assert_eq!(
             blockstore
                 .meta(unconfirmed_slot)
@@ -9041,6 +10679,14 @@ pub mod tests {
             .insert_shreds(shreds.clone(), None, false)
             .unwrap();
         assert!(blockstore.is_dead(slot));
+        assert_eq!(
+            blockstore
+                .get_dead_slot_cause(slot)
+                .unwrap()
+                .unwrap()
+                .cause(),
+            &DeadSlotCause::ConflictingLastShred
+        );
         // All the shreds other than the two last index shreds because those two
         // are marked as last, but less than the first received index == 10.
         // The others will be inserted even after the slot is marked dead on attempted
@@ -0,0 +1,277 @@
+//! Export of slot ranges of the blockstore to a portable file, and import of such files into
+//! another blockstore, e.g. to fill in slots missing from a ledger with those of a peer or to
+//! share the slots reproducing a bug.
+//!
+//! An export starts with `SLOT_EXPORT_MAGIC` and the little-endian `u32` version of the
+//! format, followed by a zstd stream of bincode-encoded records: one per exported slot, then
+//! an end record so that a truncated export is detected on import.
+use {
+    super::*,
+    bincode::Options,
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt,
+        io::{Read, Write},
+        ops::RangeInclusive,
+    },
+};
+
+/// First bytes of a slot export
+pub const SLOT_EXPORT_MAGIC: &[u8; 8] = b"SOLSLOTS";
+
+/// Version of the export format written by this crate
+pub const SLOT_EXPORT_VERSION: u32 = 1;
+
+const SLOT_EXPORT_COMPRESSION_LEVEL: i32 = 3;
+
+// Upper bound on the size of one record, so that a corrupted length can't exhaust memory
+const MAX_SLOT_EXPORT_RECORD_SIZE: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, Serialize)]
+enum SlotExportRecord {
+    Slot(ExportedSlot),
+    End { num_slots: u64 },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ExportedSlot {
+    slot: Slot,
+    is_root: bool,
+    is_dead: bool,
+    data_shreds: Vec<Vec<u8>>,
+    code_shreds: Vec<Vec<u8>>,
+}
+
+/// What `Blockstore::export_slots` wrote, or `Blockstore::import_slots` read
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlotExportStats {
+    pub num_slots: u64,
+    pub num_roots: u64,
+    pub num_data_shreds: u64,
+    pub num_code_shreds: u64,
+    pub first_slot: Option<Slot>,
+    pub last_slot: Option<Slot>,
+}
+
+impl SlotExportStats {
+    fn add(&mut self, exported_slot: &ExportedSlot) {
+        self.num_slots += 1;
+        self.num_roots += u64::from(exported_slot.is_root);
+        self.num_data_shreds += exported_slot.data_shreds.len() as u64;
+        self.num_code_shreds += exported_slot.code_shreds.len() as u64;
+        self.first_slot.get_or_insert(exported_slot.slot);
+        self.last_slot = Some(exported_slot.slot);
+    }
+}
+
+impl fmt::Display for SlotExportStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} slots", self.num_slots)?;
+        if let (Some(first_slot), Some(last_slot)) = (self.first_slot, self.last_slot) {
+            write!(f, " ({}..={})", first_slot, last_slot)?;
+        }
+        write!(
+            f,
+            ", {} roots, {} data shreds, {} coding shreds",
+            self.num_roots, self.num_data_shreds, self.num_code_shreds
+        )
+    }
+}
+
+fn record_options() -> impl Options {
+    bincode::options()
+        .with_limit(MAX_SLOT_EXPORT_RECORD_SIZE)
+        .with_fixint_encoding()
+}
+
+fn invalid_export(error: impl fmt::Display) -> BlockstoreError {
+    BlockstoreError::InvalidSlotExport(error.to_string())
+}
+
+impl Blockstore {
+    /// Writes the shreds of the slots of `slots` present in the blockstore to `writer`, along
+    /// with whether each slot is rooted or dead. Slots without shreds are skipped.
+    pub fn export_slots(
+        &self,
+        slots: RangeInclusive<Slot>,
+        mut writer: impl Write,
+    ) -> Result<SlotExportStats> {
+        writer.write_all(SLOT_EXPORT_MAGIC)?;
+        writer.write_all(&SLOT_EXPORT_VERSION.to_le_bytes())?;
+        let mut encoder = zstd::stream::Encoder::new(writer, SLOT_EXPORT_COMPRESSION_LEVEL)?;
+
+        let mut stats = SlotExportStats::default();
+        for (slot, _) in self
+            .slot_meta_iterator(*slots.start())?
+            .take_while(|(slot, _)| slot <= slots.end())
+        {
+            let exported_slot = ExportedSlot {
+                slot,
+                is_root: self.is_root(slot),
+                is_dead: self.is_dead(slot),
+                data_shreds: self
+                    .slot_data_iterator(slot, 0)?
+                    .map(|(_, payload)| payload.into_vec())
+                    .collect(),
+                code_shreds: self
+                    .slot_coding_iterator(slot, 0)?
+                    .map(|(_, payload)| payload.into_vec())
+                    .collect(),
+            };
+            if exported_slot.data_shreds.is_empty() && exported_slot.code_shreds.is_empty() {
+                continue;
+            }
+            stats.add(&exported_slot);
+            record_options()
+                .serialize_into(&mut encoder, &SlotExportRecord::Slot(exported_slot))?;
+        }
+        record_options().serialize_into(
+            &mut encoder,
+            &SlotExportRecord::End {
+                num_slots: stats.num_slots,
+            },
+        )?;
+        encoder.finish()?.flush()?;
+        Ok(stats)
+    }
+
+    /// Inserts the slots of an export written by `export_slots` from `reader`. Shreds already
+    /// in the blockstore are kept, so an export can fill in the slots missing from a ledger.
+    /// Slots rooted or dead in the export are marked as such, once all of them are inserted.
+    pub fn import_slots(&self, mut reader: impl Read) -> Result<SlotExportStats> {
+        let mut magic = [0u8; SLOT_EXPORT_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != SLOT_EXPORT_MAGIC {
+            return Err(invalid_export("not a slot export"));
+        }
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != SLOT_EXPORT_VERSION {
+            return Err(invalid_export(format!(
+                "unsupported export version {}",
+                version
+            )));
+        }
+        let mut decoder = zstd::stream::Decoder::new(reader)?;
+
+        let mut stats = SlotExportStats::default();
+        let mut roots = vec![];
+        let mut dead_slots = vec![];
+        loop {
+            let record: SlotExportRecord = record_options()
+                .deserialize_from(&mut decoder)
+                .map_err(|err| invalid_export(format!("truncated or corrupted: {}", err)))?;
+            let exported_slot = match record {
+                SlotExportRecord::Slot(exported_slot) => exported_slot,
+                SlotExportRecord::End { num_slots } if num_slots == stats.num_slots => break,
+                SlotExportRecord::End { num_slots } => {
+                    return Err(invalid_export(format!(
+                        "{} slots were exported but {} were read",
+                        num_slots, stats.num_slots
+                    )))
+                }
+            };
+            stats.add(&exported_slot);
+            let slot = exported_slot.slot;
+            let shreds = exported_slot
+                .data_shreds
+                .into_iter()
+                .chain(exported_slot.code_shreds)
+                .map(|payload| {
+                    let shred = Shred::new_from_serialized_shred(payload).map_err(|err| {
+                        invalid_export(format!("invalid shred in slot {}: {:?}", slot, err))
+                    })?;
+                    if shred.slot() != slot {
+                        return Err(invalid_export(format!(
+                            "shred of slot {} exported with slot {}",
+                            shred.slot(),
+                            slot
+                        )));
+                    }
+                    Ok(shred)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            self.insert_shreds(shreds, None, false)?;
+            if exported_slot.is_root {
+                roots.push(slot);
+            }
+            if exported_slot.is_dead {
+                dead_slots.push(slot);
+            }
+        }
+        for slot in dead_slots {
+            self.set_dead_slot(slot)?;
+        }
+        self.set_roots(roots.iter())?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::get_tmp_ledger_path_auto_delete, assert_matches::assert_matches};
+
+    #[test]
+    fn test_export_import_slots() {
+        let source_path = get_tmp_ledger_path_auto_delete!();
+        let source = Blockstore::open(source_path.path()).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 6, 4);
+        source.insert_shreds(shreds, None, false).unwrap();
+        source.set_roots([1, 2, 3].iter()).unwrap();
+        source.set_dead_slot(4).unwrap();
+
+        let mut export = vec![];
+        let stats = source.export_slots(2..=4, &mut export).unwrap();
+        assert_eq!(stats.num_slots, 3);
+        assert_eq!(stats.num_roots, 2);
+        assert_eq!((stats.first_slot, stats.last_slot), (Some(2), Some(4)));
+
+        let target_path = get_tmp_ledger_path_auto_delete!();
+        let target = Blockstore::open(target_path.path()).unwrap();
+        assert_eq!(target.import_slots(&export[..]).unwrap(), stats);
+        for slot in 2..=4 {
+            assert_eq!(
+                target.get_data_shreds_for_slot(slot, 0).unwrap(),
+                source.get_data_shreds_for_slot(slot, 0).unwrap()
+            );
+            assert!(target.meta(slot).unwrap().unwrap().is_full());
+        }
+        assert!(target.is_root(2) && target.is_root(3) && !target.is_root(4));
+        assert!(target.is_dead(4));
+        assert!(target.meta(1).unwrap().is_none());
+        assert!(target.meta(5).unwrap().is_none());
+
+        // Importing again leaves the blockstore as is
+        let meta = target.meta(3).unwrap();
+        assert_eq!(target.import_slots(&export[..]).unwrap(), stats);
+        assert_eq!(target.meta(3).unwrap(), meta);
+    }
+
+    #[test]
+    fn test_import_invalid_export() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let (shreds, _) = make_many_slot_entries(0, 2, 4);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let mut export = vec![];
+        blockstore.export_slots(0..=1, &mut export).unwrap();
+
+        let target_path = get_tmp_ledger_path_auto_delete!();
+        let target = Blockstore::open(target_path.path()).unwrap();
+        assert_matches!(
+            target.import_slots(&export[1..]),
+            Err(BlockstoreError::InvalidSlotExport(_))
+        );
+        let mut other_version = export.clone();
+        other_version[SLOT_EXPORT_MAGIC.len()] += 1;
+        assert_matches!(
+            target.import_slots(&other_version[..]),
+            Err(BlockstoreError::InvalidSlotExport(_))
+        );
+        assert_matches!(
+            target.import_slots(&export[..export.len() / 2]),
+            Err(BlockstoreError::InvalidSlotExport(_))
+        );
+    }
+}
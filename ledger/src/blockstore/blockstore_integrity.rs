@@ -0,0 +1,396 @@
+use {
+    super::*,
+    crate::blockstore_db::ColumnName,
+    std::{fmt, ops::RangeInclusive},
+};
+
+/// A problem found in the blockstore by `Blockstore::verify_integrity`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockstoreIntegrityIssue {
+    /// RocksDB failed to read the column, such as on a block checksum mismatch
+    UnreadableColumn {
+        slot: Slot,
+        column: &'static str,
+        error: String,
+    },
+    /// A shred that fails to deserialize or sanitize
+    InvalidShred {
+        slot: Slot,
+        column: &'static str,
+        index: u64,
+        error: String,
+    },
+    /// A shred stored under the key of another one
+    MisplacedShred {
+        slot: Slot,
+        column: &'static str,
+        index: u64,
+    },
+    /// A stored shred missing from the index of its slot
+    UnindexedShred {
+        slot: Slot,
+        column: &'static str,
+        index: u64,
+    },
+    /// A shred in the index of its slot that isn't stored
+    MissingShred {
+        slot: Slot,
+        column: &'static str,
+        index: u64,
+    },
+    /// Slot meta disagreeing with the data shreds stored for the slot
+    InconsistentSlotMeta { slot: Slot, error: String },
+    /// A full slot whose entries can't be read back
+    InvalidEntries { slot: Slot, error: String },
+}
+
+impl BlockstoreIntegrityIssue {
+    pub fn slot(&self) -> Slot {
+        match self {
+            Self::UnreadableColumn { slot, .. }
+            | Self::InvalidShred { slot, .. }
+            | Self::MisplacedShred { slot, .. }
+            | Self::UnindexedShred { slot, .. }
+            | Self::MissingShred { slot, .. }
+            | Self::InconsistentSlotMeta { slot, .. }
+            | Self::InvalidEntries { slot, .. } => *slot,
+        }
+    }
+}
+
+impl fmt::Display for BlockstoreIntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnreadableColumn {
+                slot,
+                column,
+                error,
+            } => write!(f, "slot {}: failed to read {}: {}", slot, column, error),
+            Self::InvalidShred {
+                slot,
+                column,
+                index,
+                error,
+            } => write!(
+                f,
+                "slot {}: invalid shred {} in {}: {}",
+                slot, index, column, error
+            ),
+            Self::MisplacedShred {
+                slot,
+                column,
+                index,
+            } => write!(
+                f,
+                "slot {}: shred stored as {} in {} belongs elsewhere",
+                slot, index, column
+            ),
+            Self::UnindexedShred {
+                slot,
+                column,
+                index,
+            } => write!(
+                f,
+                "slot {}: shred {} in {} is missing from the index",
+                slot, index, column
+            ),
+            Self::MissingShred {
+                slot,
+                column,
+                index,
+            } => write!(
+                f,
+                "slot {}: indexed shred {} is missing from {}",
+                slot, index, column
+            ),
+            Self::InconsistentSlotMeta { slot, error } => {
+                write!(f, "slot {}: inconsistent slot meta: {}", slot, error)
+            }
+            Self::InvalidEntries { slot, error } => {
+                write!(f, "slot {}: invalid entries: {}", slot, error)
+            }
+        }
+    }
+}
+
+/// The outcome of `Blockstore::verify_integrity` over a range of slots
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockstoreIntegrityReport {
+    pub first_slot: Slot,
+    pub last_slot: Slot,
+    /// Number of slots in the range with a slot meta
+    pub num_slots: usize,
+    pub num_data_shreds: usize,
+    pub num_coding_shreds: usize,
+    pub issues: Vec<BlockstoreIntegrityIssue>,
+}
+
+impl BlockstoreIntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the slots with issues, in order
+    pub fn corrupted_slots(&self) -> BTreeSet<Slot> {
+        self.issues.iter().map(|issue| issue.slot()).collect()
+    }
+}
+
+impl fmt::Display for BlockstoreIntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "verified slots {}..={}: {} slots, {} data shreds, {} coding shreds, {} issues",
+            self.first_slot,
+            self.last_slot,
+            self.num_slots,
+            self.num_data_shreds,
+            self.num_coding_shreds,
+            self.issues.len()
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl Blockstore {
+    /// Checks the slots in `slots` for corruption: RocksDB verifies the block checksums of the
+    /// shred columns as they are read, every shred must deserialize, sanitize, and agree with its
+    /// key, the index and the slot meta of each slot must agree with the shreds stored, and the
+    /// entries of full slots that aren't dead must be readable.
+    ///
+    /// Errors are only returned when the check itself can't be run; corruption is collected in
+    /// the report. Slots whose shreds were evicted by FIFO compaction are reported with missing
+    /// shreds, so the range should be kept to recent slots when using it.
+    pub fn verify_integrity(
+        &self,
+        slots: RangeInclusive<Slot>,
+    ) -> Result<BlockstoreIntegrityReport> {
+        let mut report = BlockstoreIntegrityReport {
+            first_slot: *slots.start(),
+            last_slot: *slots.end(),
+            ..BlockstoreIntegrityReport::default()
+        };
+        for (slot, slot_meta) in self.slot_meta_iterator(*slots.start())? {
+            if slot > *slots.end() {
+                break;
+            }
+            report.num_slots += 1;
+            let num_issues = report.issues.len();
+            let index = self.get_index(slot)?.unwrap_or_else(|| Index::new(slot));
+            let data_indexes = self.verify_shreds::<cf::ShredData>(
+                slot,
+                ShredType::Data,
+                index.data(),
+                &mut report.issues,
+            )?;
+            let coding_indexes = self.verify_shreds::<cf::ShredCode>(
+                slot,
+                ShredType::Code,
+                index.coding(),
+                &mut report.issues,
+            )?;
+            report.num_data_shreds += data_indexes.len();
+            report.num_coding_shreds += coding_indexes.len();
+            if let Err(error) = Self::verify_slot_meta(&slot_meta, &data_indexes) {
+                report
+                    .issues
+                    .push(BlockstoreIntegrityIssue::InconsistentSlotMeta { slot, error });
+            }
+            // Only read back entries from shreds that passed the checks above
+            if report.issues.len() == num_issues && slot_meta.is_full() && !self.is_dead(slot) {
+                if let Err(err) = self.get_slot_entries(slot, 0) {
+                    report
+                        .issues
+                        .push(BlockstoreIntegrityIssue::InvalidEntries {
+                            slot,
+                            error: format!("{:?}", err),
+                        });
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Verifies the latest `num_slots` slots, failing if any is corrupted
+    pub(crate) fn verify_latest_slots(&self, num_slots: u64) -> Result<()> {
+        let last_slot = match self.meta_cf.iter(IteratorMode::End)?.next() {
+            Some((slot, _)) => slot,
+            None => return Ok(()),
+        };
+        let first_slot = last_slot.saturating_sub(num_slots.saturating_sub(1));
+        let mut measure = Measure::start("verify_integrity");
+        let report = self.verify_integrity(first_slot..=last_slot)?;
+        measure.stop();
+        if report.is_ok() {
+            info!("Blockstore integrity check passed, {}, {}", report, measure);
+            Ok(())
+        } else {
+            error!("Blockstore integrity check failed, {}", report);
+            Err(BlockstoreError::IntegrityCheckFailed(Box::new(report)))
+        }
+    }
+
+    /// Checks the shreds of `slot` stored in column `C` against their keys and `shred_index`,
+    /// returning the indexes of the shreds found
+    fn verify_shreds<C>(
+        &self,
+        slot: Slot,
+        shred_type: ShredType,
+        shred_index: &ShredIndex,
+        issues: &mut Vec<BlockstoreIntegrityIssue>,
+    ) -> Result<BTreeSet<u64>>
+    where
+        C: Column<Index = (Slot, u64)> + ColumnName,
+    {
+        let mut iter = self.db.raw_iterator_cf::<C>()?;
+        iter.seek(&C::key((slot, 0)));
+        let mut indexes = BTreeSet::new();
+        while let (Some(key), Some(payload)) = (iter.key(), iter.value()) {
+            let (shred_slot, index) = C::index(key);
+            if shred_slot != slot {
+                break;
+            }
+            indexes.insert(index);
+            match Shred::new_from_serialized_shred(payload.to_vec())
+                .and_then(|shred| shred.sanitize().map(|()| shred))
+            {
+                Ok(shred) => {
+                    if shred.slot() != slot
+                        || u64::from(shred.index()) != index
+                        || shred.shred_type() != shred_type
+                    {
+                        issues.push(BlockstoreIntegrityIssue::MisplacedShred {
+                            slot,
+                            column: C::NAME,
+                            index,
+                        });
+                    }
+                }
+                Err(err) => issues.push(BlockstoreIntegrityIssue::InvalidShred {
+                    slot,
+                    column: C::NAME,
+                    index,
+                    error: err.to_string(),
+                }),
+            }
+            if !shred_index.contains(index) {
+                issues.push(BlockstoreIntegrityIssue::UnindexedShred {
+                    slot,
+                    column: C::NAME,
+                    index,
+                });
+            }
+            iter.next();
+        }
+        if let Err(err) = iter.status() {
+            issues.push(BlockstoreIntegrityIssue::UnreadableColumn {
+                slot,
+                column: C::NAME,
+                error: err.to_string(),
+            });
+        }
+        issues.extend(
+            shred_index
+                .range(..)
+                .filter(|index| !indexes.contains(index))
+                .map(|index| BlockstoreIntegrityIssue::MissingShred {
+                    slot,
+                    column: C::NAME,
+                    index: *index,
+                }),
+        );
+        Ok(indexes)
+    }
+
+    fn verify_slot_meta(
+        slot_meta: &SlotMeta,
+        data_indexes: &BTreeSet<u64>,
+    ) -> std::result::Result<(), String> {
+        let received = data_indexes.iter().next_back().map_or(0, |index| index + 1);
+        if slot_meta.received != received {
+            return Err(format!(
+                "received {}, expected {} from the data shreds stored",
+                slot_meta.received, received
+            ));
+        }
+        let consumed = (0..).find(|index| !data_indexes.contains(index)).unwrap();
+        if slot_meta.consumed != consumed {
+            return Err(format!(
+                "consumed {}, expected {} from the data shreds stored",
+                slot_meta.consumed, consumed
+            ));
+        }
+        if let Some(last_index) = slot_meta.last_index {
+            if received > last_index + 1 {
+                return Err(format!(
+                    "last index {}, but data shred {} is stored",
+                    last_index,
+                    received - 1
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use {super::*, crate::get_tmp_ledger_path_auto_delete, assert_matches::assert_matches};
+
+    #[test]
+    fn test_verify_integrity() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let num_slots = 4;
+        let mut num_shreds = 0;
+        for slot in 1..=num_slots {
+            let (shreds, _) = make_slot_entries(slot, slot - 1, 10);
+            num_shreds += shreds.len();
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+
+        let report = blockstore.verify_integrity(0..=num_slots).unwrap();
+        assert!(report.is_ok(), "{}", report);
+        // Slot 0 has a slot meta as the parent of slot 1
+        assert_eq!(report.num_slots, num_slots as usize + 1);
+        assert_eq!(report.num_data_shreds, num_shreds);
+        blockstore.verify_latest_slots(num_slots).unwrap();
+
+        // Corrupt a shred of slot 2 and drop one of slot 3
+        blockstore
+            .data_shred_cf
+            .put_bytes((2, 0), &[0xff; 16])
+            .unwrap();
+        blockstore.data_shred_cf.delete((3, 0)).unwrap();
+
+        let report = blockstore.verify_integrity(0..=num_slots).unwrap();
+        assert_eq!(report.corrupted_slots(), BTreeSet::from([2, 3]));
+        assert_matches!(
+            report.issues[0],
+            BlockstoreIntegrityIssue::InvalidShred {
+                slot: 2,
+                index: 0,
+                ..
+            }
+        );
+        assert!(report
+            .issues
+            .contains(&BlockstoreIntegrityIssue::MissingShred {
+                slot: 3,
+                column: cf::ShredData::NAME,
+                index: 0,
+            }));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            BlockstoreIntegrityIssue::InconsistentSlotMeta { slot: 3, .. }
+        )));
+        assert!(blockstore.verify_integrity(4..=num_slots).unwrap().is_ok());
+        assert_matches!(
+            blockstore.verify_latest_slots(num_slots),
+            Err(BlockstoreError::IntegrityCheckFailed(_))
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use {super::*, solana_sdk::message::AccountKeys, std::time::Instant};
+use {
+    super::*, crate::blockstore_db::ColumnName, solana_sdk::message::AccountKeys,
+    std::time::Instant,
+};
 
 #[derive(Default)]
 pub struct PurgeStats {
@@ -180,6 +183,10 @@ impl Blockstore {
                 .db
                 .delete_range_cf::<cf::DeadSlots>(&mut write_batch, from_slot, to_slot)
                 .is_ok()
+            & self
+                .db
+                .delete_range_cf::<cf::DeadSlotCauses>(&mut write_batch, from_slot, to_slot)
+                .is_ok()
             & self
                 .db
                 .delete_range_cf::<cf::DuplicateSlots>(&mut write_batch, from_slot, to_slot)
@@ -215,6 +222,14 @@ impl Blockstore {
             & self
                 .db
                 .delete_range_cf::<cf::OptimisticSlots>(&mut write_batch, from_slot, to_slot)
+                .is_ok()
+            & self
+                .db
+                .delete_range_cf::<cf::BlockCost>(&mut write_batch, from_slot, to_slot)
+                .is_ok()
+            & self
+                .db
+                .delete_range_cf::<cf::DuplicateProofs>(&mut write_batch, from_slot, to_slot)
                 .is_ok();
         let mut w_active_transaction_status_index =
             self.active_transaction_status_index.write().unwrap();
@@ -285,6 +300,10 @@ impl Blockstore {
                 .dead_slots_cf
                 .compact_range(from_slot, to_slot)
                 .unwrap_or(false)
+            && self
+                .dead_slot_causes_cf
+                .compact_range(from_slot, to_slot)
+                .unwrap_or(false)
             && self
                 .duplicate_slots_cf
                 .compact_range(from_slot, to_slot)
@@ -336,6 +355,14 @@ impl Blockstore {
             && self
                 .optimistic_slots_cf
                 .compact_range(from_slot, to_slot)
+                .unwrap_or(false)
+            && self
+                .block_cost_cf
+                .compact_range(from_slot, to_slot)
+                .unwrap_or(false)
+            && self
+                .duplicate_proofs_cf
+                .compact_range(from_slot, to_slot)
                 .unwrap_or(false);
         compact_timer.stop();
         if !result {
@@ -348,6 +375,57 @@ impl Blockstore {
         Ok(result)
     }
 
+    /// Compacts the keys of `column` from `start_slot` to `end_slot`, so operators can reclaim
+    /// a lagging column, e.g. after a large purge, without waiting for RocksDB to pick it up.
+    /// Unlike `compact_storage`, this runs even if compaction is disabled. Only columns keyed
+    /// by slot are supported.
+    pub fn compact_range(&self, column: &str, start_slot: Slot, end_slot: Slot) -> Result<()> {
+        info!(
+            "compact_range: {} from {} to {}",
+            column, start_slot, end_slot
+        );
+        let mut compact_timer = Measure::start("compact_range");
+        match column {
+            cf::SlotMeta::NAME => self.meta_cf.compact_range(start_slot, end_slot),
+            cf::Root::NAME => self
+                .db
+                .column::<cf::Root>()
+                .compact_range(start_slot, end_slot),
+            cf::ShredData::NAME => self.data_shred_cf.compact_range(start_slot, end_slot),
+            cf::ShredCode::NAME => self.code_shred_cf.compact_range(start_slot, end_slot),
+            cf::DeadSlots::NAME => self.dead_slots_cf.compact_range(start_slot, end_slot),
+            cf::DeadSlotCauses::NAME => {
+                self.dead_slot_causes_cf.compact_range(start_slot, end_slot)
+            }
+            cf::DuplicateSlots::NAME => self.duplicate_slots_cf.compact_range(start_slot, end_slot),
+            cf::ErasureMeta::NAME => self.erasure_meta_cf.compact_range(start_slot, end_slot),
+            cf::Orphans::NAME => self.orphans_cf.compact_range(start_slot, end_slot),
+            cf::BankHash::NAME => self.bank_hash_cf.compact_range(start_slot, end_slot),
+            cf::Index::NAME => self.index_cf.compact_range(start_slot, end_slot),
+            cf::Rewards::NAME => self.rewards_cf.compact_range(start_slot, end_slot),
+            cf::Blocktime::NAME => self.blocktime_cf.compact_range(start_slot, end_slot),
+            cf::PerfSamples::NAME => self.perf_samples_cf.compact_range(start_slot, end_slot),
+            cf::BlockHeight::NAME => self.block_height_cf.compact_range(start_slot, end_slot),
+            cf::OptimisticSlots::NAME => {
+                self.optimistic_slots_cf.compact_range(start_slot, end_slot)
+            }
+            cf::BlockCost::NAME => self.block_cost_cf.compact_range(start_slot, end_slot),
+            cf::DuplicateProofs::NAME => {
+                self.duplicate_proofs_cf.compact_range(start_slot, end_slot)
+            }
+            _ => return Err(BlockstoreError::UnsupportedColumn(column.to_string())),
+        }?;
+        compact_timer.stop();
+        datapoint_info!(
+            "blockstore-compact-range",
+            ("column", column.to_string(), String),
+            ("start_slot", start_slot, i64),
+            ("end_slot", end_slot, i64),
+            ("compact_range_us", compact_timer.as_us(), i64),
+        );
+        Ok(())
+    }
+
     /// Purges special columns (using a non-Slot primary-index) exactly, by
     /// deserializing each slot being purged and iterating through all
     /// transactions to determine the keys of individual records.
@@ -439,6 +517,7 @@ pub mod tests {
         crate::{
             blockstore::tests::make_slot_entries_with_transactions, get_tmp_ledger_path_auto_delete,
         },
+        assert_matches::assert_matches,
         bincode::serialize,
         solana_entry::entry::next_entry_mut,
         solana_sdk::{
@@ -474,6 +553,45 @@ pub mod tests {
             });
     }
 
+    #[test]
+    fn test_compact_range() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        let (shreds, _) = make_many_slot_entries(0, 10, 5);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        // Flush the shreds to SST files, so that there is data on disk for the purge to remove
+        blockstore
+            .compact_range(cf::ShredData::NAME, 0, 10)
+            .unwrap();
+        let storage_size = blockstore.total_data_shred_storage_size().unwrap();
+        assert!(storage_size > 0);
+
+        // Purged shreds keep taking up space until they are compacted away
+        blockstore.purge_slots(0, 4, PurgeType::Exact);
+        assert_eq!(
+            blockstore.total_data_shred_storage_size().unwrap(),
+            storage_size
+        );
+        blockstore.compact_range(cf::ShredData::NAME, 0, 5).unwrap();
+        let compacted_storage_size = blockstore.total_data_shred_storage_size().unwrap();
+        assert!(compacted_storage_size > 0);
+        assert!(compacted_storage_size < storage_size);
+
+        blockstore.compact_range(cf::SlotMeta::NAME, 0, 5).unwrap();
+        assert!(blockstore.meta(4).unwrap().is_none());
+        assert!(blockstore.meta(5).unwrap().is_some());
+
+        assert_matches!(
+            blockstore.compact_range(cf::TransactionStatus::NAME, 0, 5),
+            Err(BlockstoreError::UnsupportedColumn(_))
+        );
+        assert_matches!(
+            blockstore.compact_range("not_a_column", 0, 5),
+            Err(BlockstoreError::UnsupportedColumn(_))
+        );
+    }
+
     #[test]
     fn test_purge_front_of_ledger() {
         let ledger_path = get_tmp_ledger_path_auto_delete!();
@@ -0,0 +1,206 @@
+//! Rebuild of the address signatures index for rooted slots whose transactions were not indexed,
+//! e.g. because they were replayed before `--enable-rpc-transaction-history` was set, so that
+//! history queries by address cover them without downloading the ledger again.
+use {
+    super::*,
+    std::{fmt, ops::RangeInclusive},
+};
+
+/// What `Blockstore::rebuild_address_signatures` did
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AddressSignaturesRebuildStats {
+    pub num_slots: u64,
+    pub num_transactions: u64,
+    /// Address signatures written
+    pub num_address_signatures: u64,
+    /// Address signatures left as they were, since already indexed
+    pub num_existing_address_signatures: u64,
+    /// Transactions loading addresses from lookup tables without a transaction status recording
+    /// those addresses, for which only the static account keys were indexed
+    pub num_unresolved_lookups: u64,
+    pub last_slot: Option<Slot>,
+}
+
+impl fmt::Display for AddressSignaturesRebuildStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} slots, {} transactions, {} address signatures written, {} already present",
+            self.num_slots,
+            self.num_transactions,
+            self.num_address_signatures,
+            self.num_existing_address_signatures
+        )?;
+        if let Some(last_slot) = self.last_slot {
+            write!(f, ", up to slot {}", last_slot)?;
+        }
+        if self.num_unresolved_lookups > 0 {
+            write!(
+                f,
+                ", {} transactions with unresolved address lookups",
+                self.num_unresolved_lookups
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Blockstore {
+    /// Indexes the transactions of the rooted slots of `slots` by the addresses they reference,
+    /// skipping the address signatures already present.
+    ///
+    /// The account keys of a transaction are those of its message, plus the addresses it loaded
+    /// from lookup tables if its transaction status is in the blockstore. Whether a key is
+    /// writable follows `VersionedMessage::is_maybe_writable`.
+    ///
+    /// Each slot is written at once, so the rebuild can run while the blockstore is in use, and
+    /// stops before the next slot once `exit` is set; `last_slot` then tells where to resume.
+    pub fn rebuild_address_signatures(
+        &self,
+        slots: RangeInclusive<Slot>,
+        exit: &AtomicBool,
+    ) -> Result<AddressSignaturesRebuildStats> {
+        let rooted_slots: Vec<Slot> = self
+            .rooted_slot_iterator(*slots.start())?
+            .take_while(|slot| slot <= slots.end())
+            .collect();
+
+        let mut stats = AddressSignaturesRebuildStats::default();
+        for slot in rooted_slots {
+            if exit.load(Ordering::Relaxed) {
+                break;
+            }
+            self.rebuild_slot_address_signatures(slot, &mut stats)?;
+            stats.num_slots += 1;
+            stats.last_slot = Some(slot);
+        }
+        Ok(stats)
+    }
+
+    fn rebuild_slot_address_signatures(
+        &self,
+        slot: Slot,
+        stats: &mut AddressSignaturesRebuildStats,
+    ) -> Result<()> {
+        let transactions = self
+            .get_slot_entries(slot, 0)?
+            .into_iter()
+            .flat_map(|entry| entry.transactions);
+
+        // Hold the transaction status index like `write_transaction_status`, so that the primary
+        // index can't be switched and purged under the rebuild
+        let w_active_transaction_status_index =
+            self.active_transaction_status_index.write().unwrap();
+        let primary_index =
+            self.get_primary_index_to_write(slot, &w_active_transaction_status_index)?;
+        let mut write_batch = self.db.batch()?;
+        for transaction in transactions {
+            let signature = match transaction.signatures.first() {
+                Some(signature) => *signature,
+                None => continue,
+            };
+            stats.num_transactions += 1;
+            let message = &transaction.message;
+            let mut addresses: Vec<(Pubkey, bool)> = message
+                .static_account_keys()
+                .iter()
+                .enumerate()
+                .map(|(index, address)| (*address, message.is_maybe_writable(index)))
+                .collect();
+            if message
+                .address_table_lookups()
+                .map_or(false, |lookups| !lookups.is_empty())
+            {
+                match self.read_transaction_status((signature, slot))? {
+                    Some(status) => {
+                        let loaded_addresses = status.loaded_addresses;
+                        let writable = loaded_addresses.writable.into_iter();
+                        let readonly = loaded_addresses.readonly.into_iter();
+                        addresses.extend(writable.map(|address| (address, true)));
+                        addresses.extend(readonly.map(|address| (address, false)));
+                    }
+                    None => stats.num_unresolved_lookups += 1,
+                }
+            }
+
+            for (address, writeable) in addresses {
+                let mut is_indexed = false;
+                for index in 0..=1 {
+                    is_indexed |= self
+                        .address_signatures_cf
+                        .get((index, address, slot, signature))?
+                        .is_some();
+                }
+                if is_indexed {
+                    stats.num_existing_address_signatures += 1;
+                    continue;
+                }
+                write_batch.put::<cf::AddressSignatures>(
+                    (primary_index, address, slot, signature),
+                    &AddressSignatureMeta { writeable },
+                )?;
+                stats.num_address_signatures += 1;
+            }
+        }
+        self.db.write(write_batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            blockstore::tests::make_slot_entries_with_transactions, get_tmp_ledger_path_auto_delete,
+        },
+    };
+
+    #[test]
+    fn test_rebuild_address_signatures() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let exit = AtomicBool::new(false);
+
+        let mut transactions = vec![];
+        for slot in 1..=3 {
+            let entries = make_slot_entries_with_transactions(2);
+            transactions.extend(
+                entries
+                    .iter()
+                    .flat_map(|entry| entry.transactions.iter().cloned())
+                    .map(|transaction| (slot, transaction)),
+            );
+            let shreds = entries_to_test_shreds(&entries, slot, slot - 1, true, 0);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        blockstore.set_roots([1, 2].iter()).unwrap();
+
+        let stats = blockstore.rebuild_address_signatures(0..=3, &exit).unwrap();
+        // Slot 3 isn't rooted; each transaction has three account keys
+        assert_eq!(stats.num_slots, 2);
+        assert_eq!(stats.num_transactions, 4);
+        assert_eq!(stats.num_address_signatures, 12);
+        assert_eq!(stats.last_slot, Some(2));
+
+        for (slot, transaction) in &transactions {
+            let payer = transaction.message.static_account_keys()[0];
+            let signatures = blockstore
+                .get_confirmed_signatures_for_address(payer, 0, 3)
+                .unwrap();
+            if *slot <= 2 {
+                assert_eq!(signatures, vec![transaction.signatures[0]]);
+            } else {
+                assert!(signatures.is_empty());
+            }
+        }
+
+        // A second rebuild finds everything indexed
+        let stats = blockstore.rebuild_address_signatures(0..=3, &exit).unwrap();
+        assert_eq!(stats.num_address_signatures, 0);
+        assert_eq!(stats.num_existing_address_signatures, 12);
+
+        exit.store(true, Ordering::Relaxed);
+        let stats = blockstore.rebuild_address_signatures(0..=3, &exit).unwrap();
+        assert_eq!(stats, AddressSignaturesRebuildStats::default());
+    }
+}
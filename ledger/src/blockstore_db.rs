@@ -1,13 +1,18 @@
 pub use rocksdb::Direction as IteratorDirection;
 use {
     crate::{
+        blockstore::BlockstoreIntegrityReport,
+        blockstore_lock::{is_rocksdb_lock_error, BlockstoreLock, BlockstoreLockOwner},
         blockstore_meta,
         blockstore_metrics::{
             maybe_enable_rocksdb_perf, report_rocksdb_read_perf, report_rocksdb_write_perf,
-            BlockstoreRocksDbColumnFamilyMetrics, ColumnMetrics, PerfSamplingStatus,
+            BlockstoreRocksDbColumnFamilyMetrics, ColumnMetrics, PerfSampleInterval,
+            PerfSamplingStatus, RocksDbColumnLatency,
         },
         blockstore_options::{
-            AccessType, BlockstoreOptions, LedgerColumnOptions, ShredStorageType,
+            AccessType, BlockstoreIoOptions, BlockstoreOptions, BlockstorePrefixExtractor,
+            BlockstoreRocksFifoOptions, ColumnLayout, LedgerColumnOptions, ParseColumnLayoutError,
+            RocksDbTuningOptions, ShredStorageType,
         },
     },
     bincode::{deserialize, serialize},
@@ -16,16 +21,19 @@ use {
     prost::Message,
     rocksdb::{
         self,
+        checkpoint::Checkpoint,
         compaction_filter::CompactionFilter,
         compaction_filter_factory::{CompactionFilterContext, CompactionFilterFactory},
-        properties as RocksProperties, ColumnFamily, ColumnFamilyDescriptor, CompactionDecision,
-        DBCompactionStyle, DBIterator, DBRawIterator, FifoCompactOptions,
-        IteratorMode as RocksIteratorMode, Options, WriteBatch as RWriteBatch, DB,
+        properties as RocksProperties, BlockBasedOptions, Cache, ColumnFamily,
+        ColumnFamilyDescriptor, CompactionDecision, DBCompactionStyle, DBIterator, DBRawIterator,
+        FifoCompactOptions, IteratorMode as RocksIteratorMode, Options, ReadOptions,
+        SliceTransform, WriteBatch as RWriteBatch, DB,
     },
     serde::{de::DeserializeOwned, Serialize},
     solana_runtime::hardened_unpack::UnpackError,
     solana_sdk::{
         clock::{Slot, UnixTimestamp},
+        hash::{Hash, HASH_BYTES},
         pubkey::Pubkey,
         signature::Signature,
     },
@@ -34,8 +42,9 @@ use {
         collections::{HashMap, HashSet},
         ffi::{CStr, CString},
         fs,
+        io::{self, Write},
         marker::PhantomData,
-        path::Path,
+        path::{Path, PathBuf},
         sync::{
             atomic::{AtomicU64, Ordering},
             Arc,
@@ -54,10 +63,21 @@ pub const DEFAULT_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES: u64 = 250 * 1024 * 1024 *
 const MAX_WRITE_BUFFER_SIZE: u64 = 256 * 1024 * 1024; // 256MB
 const FIFO_WRITE_BUFFER_SIZE: u64 = 2 * MAX_WRITE_BUFFER_SIZE;
 
+// The minimum size of a shred column family when `ShredStorageType::RocksFifo`
+// is used.  FIFO compaction deletes the oldest file once the column family
+// exceeds its size minus the write buffer, so a column family smaller than
+// two write buffers would delete each file as soon as it is flushed.
+pub const MIN_ROCKS_FIFO_CF_SIZE_BYTES: u64 = 2 * FIFO_WRITE_BUFFER_SIZE;
+
+// Readahead of compactions when they bypass the page cache, as RocksDB recommends for direct I/O
+const COMPACTION_READAHEAD_SIZE: usize = 2 * 1024 * 1024;
+
 // Column family for metadata about a leader slot
 const META_CF: &str = "meta";
 // Column family for slots that have been marked as dead
 const DEAD_SLOTS_CF: &str = "dead_slots";
+// Column family for the causes of slots being marked as dead
+const DEAD_SLOT_CAUSES_CF: &str = "dead_slot_causes";
 // Column family for storing proof that there were multiple
 // versions of a slot
 const DUPLICATE_SLOTS_CF: &str = "duplicate_slots";
@@ -97,10 +117,19 @@ const BLOCK_HEIGHT_CF: &str = "block_height";
 const PROGRAM_COSTS_CF: &str = "program_costs";
 /// Column family for optimistic slots
 const OPTIMISTIC_SLOTS_CF: &str = "optimistic_slots";
+/// Column family for the aggregated cost of blocks
+const BLOCK_COST_CF: &str = "block_cost";
+/// Column family for the records of purged slot ranges
+const SLOT_TOMBSTONES_CF: &str = "slot_tombstones";
+/// Column family for the proofs of duplicate blocks
+const DUPLICATE_PROOFS_CF: &str = "duplicate_proofs";
 
 // 1 day is chosen for the same reasoning of DEFAULT_COMPACTION_SLOT_INTERVAL
 const PERIODIC_COMPACTION_SECONDS: u64 = 60 * 60 * 24;
 
+/// File in the blockstore directory recording the directories its columns were relocated to
+const COLUMN_LAYOUT_FILE: &str = "solana-column-layout";
+
 #[derive(Error, Debug)]
 pub enum BlockstoreError {
     ShredForIndexExists,
@@ -123,6 +152,14 @@ pub enum BlockstoreError {
     SlotUnavailable,
     UnsupportedTransactionVersion,
     MissingTransactionMetadata,
+    UnsupportedColumn(String),
+    ShredStorageMigrationConflict(PathBuf),
+    BlockstoreLocked(PathBuf, Option<BlockstoreLockOwner>),
+    IntegrityCheckFailed(Box<BlockstoreIntegrityReport>),
+    InvalidColumnLayout(String),
+    InvalidFifoOptions(String),
+    InvalidSlotExport(String),
+    InvalidIoOptions(String),
 }
 pub type Result<T> = std::result::Result<T, BlockstoreError>;
 
@@ -151,6 +188,10 @@ pub mod columns {
     /// The dead slots column
     pub struct DeadSlots;
 
+    #[derive(Debug)]
+    /// The dead slot causes column
+    pub struct DeadSlotCauses;
+
     #[derive(Debug)]
     /// The duplicate slots column
     pub struct DuplicateSlots;
@@ -219,6 +260,21 @@ pub mod columns {
     /// The optimistic slot column
     pub struct OptimisticSlots;
 
+    #[derive(Debug)]
+    /// The block cost column
+    pub struct BlockCost;
+
+    #[derive(Debug)]
+    /// The slot tombstones column, keyed by the last slot of each purged range
+    pub struct SlotTombstones;
+
+    #[derive(Debug)]
+    /// The duplicate proofs column
+    ///
+    /// index type: (Slot, Hash) - the slot and the hash of the conflicting shreds
+    /// value type: [`blockstore_meta::DuplicateProof`]
+    pub struct DuplicateProofs;
+
     // When adding a new column ...
     // - Add struct below and implement `Column` and `ColumnName` traits
     // - Add descriptor in Rocks::cf_descriptors() and name in Rocks::columns()
@@ -255,40 +311,80 @@ struct Rocks {
     access_type: AccessType,
     oldest_slot: OldestSlot,
     column_options: LedgerColumnOptions,
+    perf_sample_interval: PerfSampleInterval,
     write_batch_perf_status: PerfSamplingStatus,
+    write_batch_latency: RocksDbColumnLatency,
+    column_latencies: HashMap<&'static str, RocksDbColumnLatency>,
+    // Released after the database is closed, as fields are dropped in order
+    _lock: Option<BlockstoreLock>,
 }
 
 impl Rocks {
-    fn open(path: &Path, options: BlockstoreOptions) -> Result<Rocks> {
+    fn open(
+        path: &Path,
+        options: &BlockstoreOptions,
+        perf_sample_interval: PerfSampleInterval,
+    ) -> Result<Rocks> {
+        let resolved_options;
+        let options = match &options.column_options.shred_storage_type {
+            ShredStorageType::RocksFifo(fifo_options) => {
+                let mut options = options.clone();
+                options.column_options.shred_storage_type =
+                    ShredStorageType::RocksFifo(resolve_fifo_options(fifo_options)?);
+                resolved_options = options;
+                &resolved_options
+            }
+            ShredStorageType::RocksLevel => options,
+        };
         let access_type = options.access_type.clone();
         let recovery_mode = options.recovery_mode.clone();
 
-        fs::create_dir_all(&path)?;
+        if access_type != AccessType::ReadOnly {
+            fs::create_dir_all(&path)?;
+        }
 
         // Use default database options
         if should_disable_auto_compactions(&access_type) {
             info!("Disabling rocksdb's automatic compactions...");
         }
-        let mut db_options = get_db_options(&access_type);
+        let tuning_options = options.rocksdb_tuning_options.clone().unwrap_or_default();
+        validate_io_options(&options.io_options)?;
+        let mut db_options = get_db_options(&access_type, &tuning_options, &options.io_options);
         if let Some(recovery_mode) = recovery_mode {
             db_options.set_wal_recovery_mode(recovery_mode.into());
         }
+        let block_cache = tuning_options
+            .block_cache_size
+            .map(Cache::new_lru_cache)
+            .transpose()?;
         let oldest_slot = OldestSlot::default();
         let column_options = options.column_options.clone();
+        let column_latencies = Self::columns()
+            .into_iter()
+            .map(|cf_name| (cf_name, RocksDbColumnLatency::default()))
+            .collect();
 
         // Open the database
         let db = match access_type {
-            AccessType::Primary | AccessType::PrimaryForMaintenance => Rocks {
-                db: DB::open_cf_descriptors(
-                    &db_options,
-                    path,
-                    Self::cf_descriptors(&options, &oldest_slot),
-                )?,
-                access_type: access_type.clone(),
-                oldest_slot,
-                column_options,
-                write_batch_perf_status: PerfSamplingStatus::default(),
-            },
+            AccessType::Primary | AccessType::PrimaryForMaintenance => {
+                let lock = BlockstoreLock::acquire(path)?;
+                Rocks {
+                    db: DB::open_cf_descriptors(
+                        &db_options,
+                        path,
+                        Self::cf_descriptors(options, &oldest_slot, block_cache.as_ref()),
+                    )
+                    .map_err(|err| Self::diagnose_open_error(path, err))?,
+                    access_type: access_type.clone(),
+                    oldest_slot,
+                    column_options,
+                    perf_sample_interval,
+                    write_batch_perf_status: PerfSamplingStatus::default(),
+                    write_batch_latency: RocksDbColumnLatency::default(),
+                    column_latencies,
+                    _lock: Some(lock),
+                }
+            }
             AccessType::Secondary => {
                 let secondary_path = path.join("solana-secondary");
 
@@ -303,12 +399,31 @@ impl Rocks {
                         &db_options,
                         path,
                         &secondary_path,
-                        Self::cf_descriptors(&options, &oldest_slot),
+                        Self::cf_descriptors(options, &oldest_slot, block_cache.as_ref()),
                     )?,
                     access_type: access_type.clone(),
                     oldest_slot,
                     column_options,
+                    perf_sample_interval,
+                    write_batch_perf_status: PerfSamplingStatus::default(),
+                    write_batch_latency: RocksDbColumnLatency::default(),
+                    column_latencies,
+                    _lock: None,
+                }
+            }
+            AccessType::ReadOnly => {
+                info!("Opening Rocks with read only access at: {:?}", path);
+
+                Rocks {
+                    db: DB::open_cf_for_read_only(&db_options, path, Self::columns(), false)?,
+                    access_type: access_type.clone(),
+                    oldest_slot,
+                    column_options,
+                    perf_sample_interval,
                     write_batch_perf_status: PerfSamplingStatus::default(),
+                    write_batch_latency: RocksDbColumnLatency::default(),
+                    column_latencies,
+                    _lock: None,
                 }
             }
         };
@@ -373,35 +488,60 @@ impl Rocks {
         Ok(db)
     }
 
+    /// Explains a failure to take RocksDB's lock, which is held by a process that doesn't take
+    /// the blockstore lock, or whose lock outlived it on shared storage
+    fn diagnose_open_error(path: &Path, err: rocksdb::Error) -> BlockstoreError {
+        if !is_rocksdb_lock_error(&err) {
+            return err.into();
+        }
+        error!(
+            "The blockstore at {:?} is locked by RocksDB ({}), but by no process that can be \
+             identified. It may be in use by an older version of solana-validator or \
+             solana-ledger-tool. If its last owner crashed on shared storage, run \
+             `solana-ledger-tool unlock --force` once it is known to be down",
+            path, err
+        );
+        BlockstoreError::BlockstoreLocked(path.to_path_buf(), None)
+    }
+
     fn cf_descriptors(
         options: &BlockstoreOptions,
         oldest_slot: &OldestSlot,
+        block_cache: Option<&Cache>,
     ) -> Vec<ColumnFamilyDescriptor> {
         use columns::*;
 
         let (cf_descriptor_shred_data, cf_descriptor_shred_code) =
-            new_cf_descriptor_pair_shreds::<ShredData, ShredCode>(options, oldest_slot);
+            new_cf_descriptor_pair_shreds::<ShredData, ShredCode>(
+                options,
+                oldest_slot,
+                block_cache,
+            );
         vec![
-            new_cf_descriptor::<SlotMeta>(options, oldest_slot),
-            new_cf_descriptor::<DeadSlots>(options, oldest_slot),
-            new_cf_descriptor::<DuplicateSlots>(options, oldest_slot),
-            new_cf_descriptor::<ErasureMeta>(options, oldest_slot),
-            new_cf_descriptor::<Orphans>(options, oldest_slot),
-            new_cf_descriptor::<BankHash>(options, oldest_slot),
-            new_cf_descriptor::<Root>(options, oldest_slot),
-            new_cf_descriptor::<Index>(options, oldest_slot),
+            new_cf_descriptor::<SlotMeta>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<DeadSlots>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<DeadSlotCauses>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<DuplicateSlots>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<ErasureMeta>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<Orphans>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<BankHash>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<Root>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<Index>(options, oldest_slot, block_cache),
             cf_descriptor_shred_data,
             cf_descriptor_shred_code,
-            new_cf_descriptor::<TransactionStatus>(options, oldest_slot),
-            new_cf_descriptor::<AddressSignatures>(options, oldest_slot),
-            new_cf_descriptor::<TransactionMemos>(options, oldest_slot),
-            new_cf_descriptor::<TransactionStatusIndex>(options, oldest_slot),
-            new_cf_descriptor::<Rewards>(options, oldest_slot),
-            new_cf_descriptor::<Blocktime>(options, oldest_slot),
-            new_cf_descriptor::<PerfSamples>(options, oldest_slot),
-            new_cf_descriptor::<BlockHeight>(options, oldest_slot),
-            new_cf_descriptor::<ProgramCosts>(options, oldest_slot),
-            new_cf_descriptor::<OptimisticSlots>(options, oldest_slot),
+            new_cf_descriptor::<TransactionStatus>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<AddressSignatures>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<TransactionMemos>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<TransactionStatusIndex>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<Rewards>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<Blocktime>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<PerfSamples>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<BlockHeight>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<ProgramCosts>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<OptimisticSlots>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<BlockCost>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<SlotTombstones>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<DuplicateProofs>(options, oldest_slot, block_cache),
         ]
     }
 
@@ -411,6 +551,7 @@ impl Rocks {
         vec![
             ErasureMeta::NAME,
             DeadSlots::NAME,
+            DeadSlotCauses::NAME,
             DuplicateSlots::NAME,
             Index::NAME,
             Orphans::NAME,
@@ -429,6 +570,9 @@ impl Rocks {
             BlockHeight::NAME,
             ProgramCosts::NAME,
             OptimisticSlots::NAME,
+            BlockCost::NAME,
+            SlotTombstones::NAME,
+            DuplicateProofs::NAME,
         ]
     }
 
@@ -460,6 +604,18 @@ impl Rocks {
     }
 
     fn iterator_cf<C>(&self, cf: &ColumnFamily, iterator_mode: IteratorMode<C::Index>) -> DBIterator
+    where
+        C: Column,
+    {
+        self.iterator_cf_opt::<C>(cf, iterator_mode, total_order_read_options())
+    }
+
+    fn iterator_cf_opt<C>(
+        &self,
+        cf: &ColumnFamily,
+        iterator_mode: IteratorMode<C::Index>,
+        read_options: ReadOptions,
+    ) -> DBIterator
     where
         C: Column,
     {
@@ -472,11 +628,33 @@ impl Rocks {
             IteratorMode::Start => RocksIteratorMode::Start,
             IteratorMode::End => RocksIteratorMode::End,
         };
-        self.db.iterator_cf(cf, iterator_mode)
+        self.db.iterator_cf_opt(cf, read_options, iterator_mode)
     }
 
     fn raw_iterator_cf(&self, cf: &ColumnFamily) -> DBRawIterator {
-        self.db.raw_iterator_cf(cf)
+        self.db.raw_iterator_cf_opt(cf, total_order_read_options())
+    }
+
+    /// Read options for a scan of column `C` that stays within the slot its keys start with.
+    /// Prefix seeks can only be used if the prefix extractor of the column groups keys by no
+    /// more than their slot, otherwise the scan would stop short of the end of the slot.
+    fn slot_read_options<C>(&self) -> ReadOptions
+    where
+        C: Column + ColumnName,
+    {
+        let prefix_len = self
+            .column_options
+            .get_column_filter_options(C::NAME)
+            .and_then(|filter_options| filter_options.prefix_extractor.as_ref())
+            .map(BlockstorePrefixExtractor::prefix_len);
+        match prefix_len {
+            Some(prefix_len) if prefix_len <= std::mem::size_of::<Slot>() => {
+                let mut read_options = ReadOptions::default();
+                read_options.set_prefix_same_as_start(true);
+                read_options
+            }
+            _ => total_order_read_options(),
+        }
     }
 
     fn batch(&self) -> RWriteBatch {
@@ -484,16 +662,17 @@ impl Rocks {
     }
 
     fn write(&self, batch: RWriteBatch) -> Result<()> {
-        let op_start_instant = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+        let perf_sample = maybe_enable_rocksdb_perf(
+            self.perf_sample_interval.get(),
             &self.write_batch_perf_status,
         );
         let result = self.db.write(batch);
-        if let Some(op_start_instant) = op_start_instant {
+        if let Some(perf_sample) = perf_sample {
             report_rocksdb_write_perf(
                 "write_batch", // We use write_batch as cf_name for write batch.
                 "write_batch", // op_name
-                &op_start_instant.elapsed(),
+                perf_sample,
+                &self.write_batch_latency,
                 &self.column_options,
             );
         }
@@ -508,6 +687,74 @@ impl Rocks {
             || self.access_type == AccessType::PrimaryForMaintenance
     }
 
+    fn column_latency(&self, cf_name: &str) -> &RocksDbColumnLatency {
+        &self.column_latencies[cf_name]
+    }
+
+    /// Copies the entries of column family `cf_name` into `target`, in write batches of
+    /// `batch_size` entries, calling `progress` with the number of entries copied so far
+    /// after each batch. Returns the number of entries copied.
+    fn copy_cf(
+        &self,
+        target: &Rocks,
+        cf_name: &'static str,
+        batch_size: usize,
+        progress: &mut impl FnMut(&'static str, u64),
+    ) -> Result<u64> {
+        let source_cf = self.cf_handle(cf_name);
+        let target_cf = target.cf_handle(cf_name);
+        let mut iter = self.raw_iterator_cf(source_cf);
+        iter.seek_to_first();
+        let mut copied = 0;
+        let mut batch = target.batch();
+        let mut batch_len = 0;
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            batch.put_cf(target_cf, key, value);
+            batch_len += 1;
+            if batch_len == batch_size {
+                target.write(std::mem::take(&mut batch))?;
+                copied += batch_len as u64;
+                batch_len = 0;
+                progress(cf_name, copied);
+            }
+            iter.next();
+        }
+        iter.status()?;
+        if batch_len > 0 {
+            target.write(batch)?;
+            copied += batch_len as u64;
+            progress(cf_name, copied);
+        }
+        Ok(copied)
+    }
+
+    /// Creates a RocksDB checkpoint of the database at `path`, which must not exist
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        Checkpoint::new(&self.db)?.create_checkpoint(path)?;
+        Ok(())
+    }
+
+    /// Deletes every entry of column family `cf_name` and compacts it to reclaim the space
+    fn clear_cf(&self, cf_name: &str) -> Result<()> {
+        let cf = self.cf_handle(cf_name);
+        let mut iter = self.raw_iterator_cf(cf);
+        iter.seek_to_first();
+        let first_key = iter.key().map(<[u8]>::to_vec);
+        iter.seek_to_last();
+        let last_key = iter.key().map(<[u8]>::to_vec);
+        iter.status()?;
+        drop(iter);
+        if let (Some(first_key), Some(last_key)) = (first_key, last_key) {
+            let mut batch = self.batch();
+            // The end of a range deletion is exclusive
+            batch.delete_range_cf(cf, &first_key, &last_key);
+            batch.delete_cf(cf, &last_key);
+            self.write(batch)?;
+            self.db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+        }
+        Ok(())
+    }
+
     /// Retrieves the specified RocksDB integer property of the current
     /// column family.
     ///
@@ -862,6 +1109,14 @@ impl TypedColumn for columns::DeadSlots {
     type Type = bool;
 }
 
+impl SlotColumn for columns::DeadSlotCauses {}
+impl ColumnName for columns::DeadSlotCauses {
+    const NAME: &'static str = DEAD_SLOT_CAUSES_CF;
+}
+impl TypedColumn for columns::DeadSlotCauses {
+    type Type = blockstore_meta::DeadSlotMetaVersioned;
+}
+
 impl SlotColumn for columns::DuplicateSlots {}
 impl ColumnName for columns::DuplicateSlots {
     const NAME: &'static str = DUPLICATE_SLOTS_CF;
@@ -943,11 +1198,65 @@ impl TypedColumn for columns::OptimisticSlots {
     type Type = blockstore_meta::OptimisticSlotMetaVersioned;
 }
 
+impl SlotColumn for columns::BlockCost {}
+impl ColumnName for columns::BlockCost {
+    const NAME: &'static str = BLOCK_COST_CF;
+}
+impl TypedColumn for columns::BlockCost {
+    type Type = blockstore_meta::BlockCost;
+}
+
+impl SlotColumn for columns::SlotTombstones {}
+impl ColumnName for columns::SlotTombstones {
+    const NAME: &'static str = SLOT_TOMBSTONES_CF;
+}
+impl TypedColumn for columns::SlotTombstones {
+    type Type = blockstore_meta::SlotTombstone;
+}
+
+impl Column for columns::DuplicateProofs {
+    type Index = (Slot, Hash);
+
+    fn key((slot, hash): (Slot, Hash)) -> Vec<u8> {
+        let mut key = vec![0; 8 + HASH_BYTES];
+        BigEndian::write_u64(&mut key[..8], slot);
+        key[8..].copy_from_slice(hash.as_ref());
+        key
+    }
+
+    fn index(key: &[u8]) -> (Slot, Hash) {
+        let slot = BigEndian::read_u64(&key[..8]);
+        let hash = Hash::new(&key[8..8 + HASH_BYTES]);
+        (slot, hash)
+    }
+
+    fn primary_index(index: Self::Index) -> Slot {
+        index.0
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn as_index(slot: Slot) -> Self::Index {
+        (slot, Hash::default())
+    }
+}
+impl ColumnName for columns::DuplicateProofs {
+    const NAME: &'static str = DUPLICATE_PROOFS_CF;
+}
+impl TypedColumn for columns::DuplicateProofs {
+    type Type = blockstore_meta::DuplicateProof;
+}
+
 #[derive(Debug)]
 pub struct Database {
     backend: Arc<Rocks>,
+    // Backends of the directories holding relocated columns, with their paths
+    relocated_backends: Vec<(PathBuf, Arc<Rocks>)>,
+    // Index into relocated_backends of the backend holding each relocated column
+    relocated_columns: HashMap<&'static str, usize>,
+    column_layout: ColumnLayout,
     path: Arc<Path>,
     column_options: Arc<LedgerColumnOptions>,
+    perf_sample_interval: PerfSampleInterval,
 }
 
 #[derive(Debug)]
@@ -1015,37 +1324,223 @@ impl<C: Column + ColumnName + ColumnMetrics> LedgerColumn<C> {
                 .unwrap_or(BLOCKSTORE_METRICS_ERROR),
         };
         C::report_cf_metrics(cf_rocksdb_metrics, &self.column_options);
+        self.latency().report(C::NAME, &self.column_options);
     }
 }
 
 pub struct WriteBatch<'a> {
-    write_batch: RWriteBatch,
-    map: HashMap<&'static str, &'a ColumnFamily>,
+    // One batch per backend, the batch of the main backend first
+    write_batches: Vec<RWriteBatch>,
+    map: HashMap<&'static str, (usize, &'a ColumnFamily)>,
 }
 
+// The batches of the slots of a shred insertion are built on a thread pool. The column family
+// handles are only passed back to RocksDB, which allows using them from any thread.
+unsafe impl Send for WriteBatch<'_> {}
+
 impl Database {
     pub fn open(path: &Path, options: BlockstoreOptions) -> Result<Self> {
+        let column_layout = resolve_column_layout(path, &options.column_layout)?;
         let column_options = Arc::new(options.column_options.clone());
-        let backend = Arc::new(Rocks::open(path, options)?);
+        let perf_sample_interval =
+            PerfSampleInterval::new(options.column_options.rocks_perf_sample_interval);
+        let backend = Arc::new(Rocks::open(path, &options, perf_sample_interval.clone())?);
+        let column_dirs: Vec<_> = column_layout.paths().into_iter().collect();
+        let mut relocated_backends = Vec::with_capacity(column_dirs.len());
+        for column_dir in &column_dirs {
+            let relocated_path = relocated_path(path, column_dir);
+            info!("Opening relocated columns at {:?}", relocated_path);
+            let relocated_backend = Arc::new(Rocks::open(
+                &relocated_path,
+                &options,
+                perf_sample_interval.clone(),
+            )?);
+            relocated_backends.push((relocated_path, relocated_backend));
+        }
+        let relocated_columns = Rocks::columns()
+            .into_iter()
+            .filter_map(|cf_name| {
+                let column_dir = column_layout.column_path(cf_name)?;
+                let index = column_dirs.iter().position(|dir| *dir == column_dir)?;
+                Some((cf_name, index))
+            })
+            .collect();
+        if backend.is_primary_access() && column_layout != read_column_layout(path)? {
+            write_column_layout(path, &column_layout)?;
+        }
 
         Ok(Database {
             backend,
+            relocated_backends,
+            relocated_columns,
+            column_layout,
             path: Arc::from(path),
             column_options,
+            perf_sample_interval,
         })
     }
 
+    /// The name of the column family called `name`, if the blockstore has one
+    pub fn column_name(name: &str) -> Option<&'static str> {
+        Rocks::columns().into_iter().find(|column| *column == name)
+    }
+
     pub fn destroy(path: &Path) -> Result<()> {
+        // A layout that can't be read is taken as empty so that the blockstore itself can
+        // still be destroyed
+        let column_layout = read_column_layout(path).unwrap_or_default();
+        for column_dir in column_layout.paths() {
+            let relocated_path = relocated_path(path, column_dir);
+            if relocated_path.exists() {
+                Rocks::destroy(&relocated_path)?;
+                fs::remove_dir_all(&relocated_path)?;
+            }
+        }
         Rocks::destroy(path)?;
+        write_column_layout(path, &ColumnLayout::default())?;
 
         Ok(())
     }
 
+    /// Moves the column families of the blockstore at `path` to the directories assigned to
+    /// them by `options.column_layout`, columns assigned to none moving back into `path`.
+    /// Each moved column is copied in write batches of `batch_size` entries, calling
+    /// `progress` after each batch as `copy_into` does, before the new layout is recorded and
+    /// the original copies are deleted. Directories left without columns are destroyed.
+    /// Returns the number of entries copied.
+    pub fn relocate_columns(
+        path: &Path,
+        options: BlockstoreOptions,
+        batch_size: usize,
+        mut progress: impl FnMut(&'static str, u64),
+    ) -> Result<u64> {
+        assert!(batch_size > 0);
+        let target_layout = options.column_layout.clone();
+        validate_column_layout(path, &target_layout)?;
+        // The source is opened with the layout it was recorded with
+        let options = BlockstoreOptions {
+            access_type: AccessType::PrimaryForMaintenance,
+            column_layout: ColumnLayout::default(),
+            ..options
+        };
+        let source = Self::open(path, options.clone())?;
+
+        let mut target_backends: HashMap<PathBuf, Arc<Rocks>> = HashMap::new();
+        let mut moved_columns = vec![];
+        let mut total_copied = 0;
+        for cf_name in Rocks::columns() {
+            let source_dir = source.column_layout.column_path(cf_name);
+            let target_dir = target_layout.column_path(cf_name);
+            if source_dir == target_dir {
+                continue;
+            }
+            let target_backend = match target_dir {
+                None => Arc::clone(&source.backend),
+                Some(target_dir) => {
+                    let target_path = relocated_path(path, target_dir);
+                    let open_backend = source
+                        .relocated_backends
+                        .iter()
+                        .find(|(relocated_path, _)| *relocated_path == target_path)
+                        .map(|(_, backend)| backend)
+                        .or_else(|| target_backends.get(&target_path))
+                        .cloned();
+                    match open_backend {
+                        Some(backend) => backend,
+                        None => {
+                            let backend = Arc::new(Rocks::open(
+                                &target_path,
+                                &options,
+                                source.perf_sample_interval.clone(),
+                            )?);
+                            target_backends.insert(target_path, Arc::clone(&backend));
+                            backend
+                        }
+                    }
+                }
+            };
+            info!(
+                "Moving column {} from {:?} to {:?}",
+                cf_name, source_dir, target_dir
+            );
+            // Entries left behind by an interrupted relocation would otherwise be resurrected
+            target_backend.clear_cf(cf_name)?;
+            total_copied += source.backend_for(cf_name).copy_cf(
+                &target_backend,
+                cf_name,
+                batch_size,
+                &mut progress,
+            )?;
+            moved_columns.push(cf_name);
+        }
+
+        write_column_layout(path, &target_layout)?;
+        for cf_name in moved_columns {
+            source.backend_for(cf_name).clear_cf(cf_name)?;
+        }
+        let target_paths: HashSet<_> = target_layout
+            .paths()
+            .into_iter()
+            .map(|column_dir| relocated_path(path, column_dir))
+            .collect();
+        let unused_paths: Vec<_> = source
+            .relocated_backends
+            .iter()
+            .map(|(relocated_path, _)| relocated_path.clone())
+            .filter(|relocated_path| !target_paths.contains(relocated_path))
+            .collect();
+        drop(source);
+        drop(target_backends);
+        for unused_path in unused_paths {
+            info!(
+                "Removing {:?}, which no longer holds any column",
+                unused_path
+            );
+            Rocks::destroy(&unused_path)?;
+            fs::remove_dir_all(&unused_path)?;
+        }
+        Ok(total_copied)
+    }
+
+    // Position of the backend holding `cf_name`, the main backend being at 0 and the
+    // relocated backends following it
+    fn backend_index(&self, cf_name: &str) -> usize {
+        self.relocated_columns
+            .get(cf_name)
+            .map_or(0, |index| index + 1)
+    }
+
+    fn backend_for(&self, cf_name: &str) -> &Arc<Rocks> {
+        match self.relocated_columns.get(cf_name) {
+            Some(index) => &self.relocated_backends[*index].1,
+            None => &self.backend,
+        }
+    }
+
+    fn backends(&self) -> impl Iterator<Item = &Arc<Rocks>> {
+        std::iter::once(&self.backend).chain(
+            self.relocated_backends
+                .iter()
+                .map(|(_, relocated_backend)| relocated_backend),
+        )
+    }
+
+    pub fn column_layout(&self) -> &ColumnLayout {
+        &self.column_layout
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn get<C>(&self, key: C::Index) -> Result<Option<C::Type>>
     where
         C: TypedColumn + ColumnName,
     {
-        if let Some(serialized_value) = self.backend.get_cf(self.cf_handle::<C>(), &C::key(key))? {
+        if let Some(serialized_value) = self
+            .backend_for(C::NAME)
+            .get_cf(self.cf_handle::<C>(), &C::key(key))?
+        {
             let value = deserialize(&serialized_value)?;
 
             Ok(Some(value))
@@ -1062,16 +1557,41 @@ impl Database {
         C: Column + ColumnName,
     {
         let cf = self.cf_handle::<C>();
-        let iter = self.backend.iterator_cf::<C>(cf, iterator_mode);
+        let iter = self
+            .backend_for(C::NAME)
+            .iterator_cf::<C>(cf, iterator_mode);
         Ok(iter.map(|(key, value)| (C::index(&key), value)))
     }
 
+    /// Iterates forward from `index` over the entries of its slot. Only for columns whose
+    /// keys start with the big-endian slot, which lets the scan use the prefix bloom filters
+    /// of the column.
+    pub fn slot_iter<C>(
+        &self,
+        index: C::Index,
+    ) -> Result<impl Iterator<Item = (C::Index, Box<[u8]>)> + '_>
+    where
+        C: Column + ColumnName,
+        C::Index: Copy,
+    {
+        let slot = C::slot(index);
+        let backend = self.backend_for(C::NAME);
+        let iter = backend.iterator_cf_opt::<C>(
+            self.cf_handle::<C>(),
+            IteratorMode::From(index, IteratorDirection::Forward),
+            backend.slot_read_options::<C>(),
+        );
+        Ok(iter
+            .map(|(key, value)| (C::index(&key), value))
+            .take_while(move |(index, _)| C::slot(*index) == slot))
+    }
+
     #[inline]
     pub fn cf_handle<C: ColumnName>(&self) -> &ColumnFamily
     where
         C: Column + ColumnName,
     {
-        self.backend.cf_handle(C::NAME)
+        self.backend_for(C::NAME).cf_handle(C::NAME)
     }
 
     pub fn column<C>(&self) -> LedgerColumn<C>
@@ -1079,7 +1599,7 @@ impl Database {
         C: Column + ColumnName + ColumnMetrics,
     {
         LedgerColumn {
-            backend: Arc::clone(&self.backend),
+            backend: Arc::clone(self.backend_for(C::NAME)),
             column: PhantomData,
             column_options: Arc::clone(&self.column_options),
             read_perf_status: PerfSamplingStatus::default(),
@@ -1088,26 +1608,121 @@ impl Database {
     }
 
     #[inline]
-    pub fn raw_iterator_cf(&self, cf: &ColumnFamily) -> Result<DBRawIterator> {
-        Ok(self.backend.raw_iterator_cf(cf))
+    pub fn raw_iterator_cf<C>(&self) -> Result<DBRawIterator>
+    where
+        C: Column + ColumnName,
+    {
+        let backend = self.backend_for(C::NAME);
+        Ok(backend.raw_iterator_cf(backend.cf_handle(C::NAME)))
     }
 
     pub fn batch(&self) -> Result<WriteBatch> {
-        let write_batch = self.backend.batch();
+        let write_batches = self.backends().map(|backend| backend.batch()).collect();
         let map = Rocks::columns()
             .into_iter()
-            .map(|desc| (desc, self.backend.cf_handle(desc)))
+            .map(|desc| {
+                let cf = self.backend_for(desc).cf_handle(desc);
+                (desc, (self.backend_index(desc), cf))
+            })
             .collect();
 
-        Ok(WriteBatch { write_batch, map })
+        Ok(WriteBatch { write_batches, map })
     }
 
+    /// Writes `batch`. A batch spanning relocated columns is written to each of their
+    /// backends separately, so it is only atomic per directory. Relocated columns hold the
+    /// data the columns of the main backend refer to and are written first, so that a crash
+    /// part way leaves unreferenced entries behind rather than dangling references.
     pub fn write(&self, batch: WriteBatch) -> Result<()> {
-        self.backend.write(batch.write_batch)
+        let mut write_batches = batch.write_batches.into_iter();
+        let write_batch = write_batches.next().unwrap();
+        for ((_, relocated_backend), relocated_batch) in
+            self.relocated_backends.iter().zip(write_batches)
+        {
+            if !relocated_batch.is_empty() {
+                relocated_backend.write(relocated_batch)?;
+            }
+        }
+        self.backend.write(write_batch)
+    }
+
+    /// Returns the bytes held by each column, in its SST files and in the memtables not yet
+    /// flushed to them
+    pub fn column_storage_sizes(&self) -> Result<Vec<(&'static str, u64)>> {
+        Rocks::columns()
+            .into_iter()
+            .map(|name| {
+                let backend = self.backend_for(name);
+                let cf = backend.cf_handle(name);
+                let size = backend
+                    .get_int_property_cf(cf, RocksProperties::TOTAL_SST_FILES_SIZE)?
+                    + backend.get_int_property_cf(cf, RocksProperties::SIZE_ALL_MEM_TABLES)?;
+                Ok((name, size as u64))
+            })
+            .collect()
     }
 
     pub fn storage_size(&self) -> Result<u64> {
-        Ok(fs_extra::dir::get_size(&self.path)?)
+        let mut storage_size = fs_extra::dir::get_size(&self.path)?;
+        for (relocated_path, _) in &self.relocated_backends {
+            storage_size += fs_extra::dir::get_size(relocated_path)?;
+        }
+        Ok(storage_size)
+    }
+
+    /// Creates a consistent copy of the database at `path`, which must not exist, without
+    /// stopping writes to it. SST files are hard linked where `path` is on the same file system,
+    /// and copied otherwise. The columns of each directory of the column layout are checkpointed
+    /// to a `columns-<n>` directory next to `path`, and the copy records that layout.
+    ///
+    /// Each directory is checkpointed separately, so a write batch spanning directories may
+    /// be only partially included in the copy.
+    pub fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        if path.exists() {
+            return Err(BlockstoreError::Io(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("checkpoint path {:?} already exists", path),
+            )));
+        }
+        let parent = path.parent().unwrap_or_else(|| Path::new("."));
+        let column_dirs: Vec<_> = (0..self.relocated_backends.len())
+            .map(|index| parent.join(format!("columns-{}", index)))
+            .collect();
+        for ((_, relocated_backend), column_dir) in self.relocated_backends.iter().zip(&column_dirs)
+        {
+            fs::create_dir_all(column_dir)?;
+            relocated_backend.create_checkpoint(&relocated_path(path, column_dir))?;
+        }
+        self.backend.create_checkpoint(path)?;
+
+        let mut column_layout = ColumnLayout::default();
+        for (cf_name, index) in &self.relocated_columns {
+            column_layout.set_column_path(*cf_name, &column_dirs[*index]);
+        }
+        write_column_layout(path, &column_layout)
+    }
+
+    /// Copies the entries of every column family into `target`, in write batches of
+    /// `batch_size` entries. `progress` is called after each batch with the name of the
+    /// column family being copied and the number of its entries copied so far.
+    /// Returns the total number of entries copied.
+    pub fn copy_into(
+        &self,
+        target: &Database,
+        batch_size: usize,
+        mut progress: impl FnMut(&'static str, u64),
+    ) -> Result<u64> {
+        assert!(batch_size > 0);
+        let mut total_copied = 0;
+        for cf_name in Rocks::columns() {
+            total_copied += self.backend_for(cf_name).copy_cf(
+                target.backend_for(cf_name),
+                cf_name,
+                batch_size,
+                &mut progress,
+            )?;
+        }
+        Ok(total_copied)
     }
 
     // Adds a range to delete to the given write batch
@@ -1115,18 +1730,124 @@ impl Database {
     where
         C: Column + ColumnName,
     {
-        let cf = self.cf_handle::<C>();
         let from_index = C::as_index(from);
         let to_index = C::as_index(to);
-        batch.delete_range_cf::<C>(cf, from_index, to_index)
+        batch.delete_range_cf::<C>(from_index, to_index)
     }
 
     pub fn is_primary_access(&self) -> bool {
         self.backend.is_primary_access()
     }
 
+    pub fn perf_sample_interval(&self) -> usize {
+        self.perf_sample_interval.get()
+    }
+
+    /// Changes how often the RocksDB operations of every column are sampled for perf metrics,
+    /// overriding `LedgerColumnOptions::rocks_perf_sample_interval`. 0 disables sampling.
+    pub fn set_perf_sample_interval(&self, sample_interval: usize) {
+        self.perf_sample_interval.set(sample_interval);
+    }
+
+    /// Reports the latencies of the sampled write batches since the last report
+    pub fn submit_write_batch_latency(&self) {
+        for backend in self.backends() {
+            backend
+                .write_batch_latency
+                .report("write_batch", &self.column_options);
+        }
+    }
+
     pub fn set_oldest_slot(&self, oldest_slot: Slot) {
-        self.backend.oldest_slot.set(oldest_slot);
+        for backend in self.backends() {
+            backend.oldest_slot.set(oldest_slot);
+        }
+    }
+
+    /// Pins the current state of the database, so that a series of reads observe a consistent
+    /// view even while other threads insert or purge data. Relocated columns are pinned
+    /// separately, right after the columns of the blockstore directory.
+    pub fn snapshot(&self) -> LedgerSnapshot<'_> {
+        LedgerSnapshot {
+            snapshots: self
+                .backends()
+                .map(|backend| (backend.as_ref(), backend.db.snapshot()))
+                .collect(),
+            relocated_columns: &self.relocated_columns,
+        }
+    }
+}
+
+/// A point-in-time view of the database. Reads through a `LedgerSnapshot` see the database as
+/// it was when the snapshot was taken; later writes, deletes and purges are not visible.
+pub struct LedgerSnapshot<'a> {
+    // One snapshot per backend, the snapshot of the main backend first
+    snapshots: Vec<(&'a Rocks, rocksdb::Snapshot<'a>)>,
+    relocated_columns: &'a HashMap<&'static str, usize>,
+}
+
+impl<'a> LedgerSnapshot<'a> {
+    fn snapshot_for(&self, cf_name: &str) -> &(&'a Rocks, rocksdb::Snapshot<'a>) {
+        let index = self
+            .relocated_columns
+            .get(cf_name)
+            .map_or(0, |index| index + 1);
+        &self.snapshots[index]
+    }
+
+    fn read_options(snapshot: &rocksdb::Snapshot) -> ReadOptions {
+        let mut read_options = total_order_read_options();
+        read_options.set_snapshot(snapshot);
+        read_options
+    }
+
+    pub fn get_bytes<C>(&self, key: C::Index) -> Result<Option<Vec<u8>>>
+    where
+        C: Column + ColumnName,
+    {
+        let (backend, snapshot) = self.snapshot_for(C::NAME);
+        let cf = backend.cf_handle(C::NAME);
+        Ok(snapshot.get_cf(cf, &C::key(key))?)
+    }
+
+    pub fn get<C>(&self, key: C::Index) -> Result<Option<C::Type>>
+    where
+        C: TypedColumn + ColumnName,
+    {
+        if let Some(serialized_value) = self.get_bytes::<C>(key)? {
+            Ok(Some(deserialize(&serialized_value)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get_protobuf_or_bincode<C, T>(&self, key: C::Index) -> Result<Option<C::Type>>
+    where
+        C: ProtobufColumn + ColumnName,
+        T: DeserializeOwned + Into<C::Type>,
+    {
+        if let Some(serialized_value) = self.get_bytes::<C>(key)? {
+            let value = match C::Type::decode(&serialized_value[..]) {
+                Ok(value) => value,
+                Err(_) => deserialize::<T>(&serialized_value)?.into(),
+            };
+            Ok(Some(value))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn iter<C>(
+        &self,
+        iterator_mode: IteratorMode<C::Index>,
+    ) -> Result<impl Iterator<Item = (C::Index, Box<[u8]>)> + '_>
+    where
+        C: Column + ColumnName,
+    {
+        let (backend, snapshot) = self.snapshot_for(C::NAME);
+        let cf = backend.cf_handle(C::NAME);
+        let iter = backend.iterator_cf_opt::<C>(cf, iterator_mode, Self::read_options(snapshot));
+        Ok(iter.map(|(key, value)| (C::index(&key), value)))
     }
 }
 
@@ -1136,12 +1857,12 @@ where
 {
     pub fn get_bytes(&self, key: C::Index) -> Result<Option<Vec<u8>>> {
         let is_perf_enabled = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+            self.backend.perf_sample_interval.get(),
             &self.read_perf_status,
         );
         let result = self.backend.get_cf(self.handle(), &C::key(key));
-        if let Some(op_start_instant) = is_perf_enabled {
-            report_rocksdb_read_perf(C::NAME, &op_start_instant.elapsed(), &self.column_options);
+        if let Some(perf_sample) = is_perf_enabled {
+            report_rocksdb_read_perf(C::NAME, perf_sample, self.latency(), &self.column_options);
         }
         result
     }
@@ -1205,6 +1926,12 @@ where
         self.backend.cf_handle(C::NAME)
     }
 
+    /// Latencies of the sampled operations on the column since they were last reported,
+    /// shared by all the `LedgerColumn`s of the column
+    pub fn latency(&self) -> &RocksDbColumnLatency {
+        self.backend.column_latency(C::NAME)
+    }
+
     #[cfg(test)]
     pub fn is_empty(&self) -> Result<bool> {
         let mut iter = self.backend.raw_iterator_cf(self.handle());
@@ -1214,15 +1941,16 @@ where
 
     pub fn put_bytes(&self, key: C::Index, value: &[u8]) -> Result<()> {
         let is_perf_enabled = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+            self.backend.perf_sample_interval.get(),
             &self.write_perf_status,
         );
         let result = self.backend.put_cf(self.handle(), &C::key(key), value);
-        if let Some(op_start_instant) = is_perf_enabled {
+        if let Some(perf_sample) = is_perf_enabled {
             report_rocksdb_write_perf(
                 C::NAME,
                 "put",
-                &op_start_instant.elapsed(),
+                perf_sample,
+                self.latency(),
                 &self.column_options,
             );
         }
@@ -1246,7 +1974,7 @@ where
     pub fn get(&self, key: C::Index) -> Result<Option<C::Type>> {
         let mut result = Ok(None);
         let is_perf_enabled = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+            self.backend.perf_sample_interval.get(),
             &self.read_perf_status,
         );
         if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
@@ -1255,15 +1983,15 @@ where
             result = Ok(Some(value))
         }
 
-        if let Some(op_start_instant) = is_perf_enabled {
-            report_rocksdb_read_perf(C::NAME, &op_start_instant.elapsed(), &self.column_options);
+        if let Some(perf_sample) = is_perf_enabled {
+            report_rocksdb_read_perf(C::NAME, perf_sample, self.latency(), &self.column_options);
         }
         result
     }
 
     pub fn put(&self, key: C::Index, value: &C::Type) -> Result<()> {
         let is_perf_enabled = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+            self.backend.perf_sample_interval.get(),
             &self.write_perf_status,
         );
         let serialized_value = serialize(value)?;
@@ -1272,11 +2000,12 @@ where
             .backend
             .put_cf(self.handle(), &C::key(key), &serialized_value);
 
-        if let Some(op_start_instant) = is_perf_enabled {
+        if let Some(perf_sample) = is_perf_enabled {
             report_rocksdb_write_perf(
                 C::NAME,
                 "put",
-                &op_start_instant.elapsed(),
+                perf_sample,
+                self.latency(),
                 &self.column_options,
             );
         }
@@ -1285,15 +2014,16 @@ where
 
     pub fn delete(&self, key: C::Index) -> Result<()> {
         let is_perf_enabled = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+            self.backend.perf_sample_interval.get(),
             &self.write_perf_status,
         );
         let result = self.backend.delete_cf(self.handle(), &C::key(key));
-        if let Some(op_start_instant) = is_perf_enabled {
+        if let Some(perf_sample) = is_perf_enabled {
             report_rocksdb_write_perf(
                 C::NAME,
                 "delete",
-                &op_start_instant.elapsed(),
+                perf_sample,
+                self.latency(),
                 &self.column_options,
             );
         }
@@ -1310,12 +2040,12 @@ where
         key: C::Index,
     ) -> Result<Option<C::Type>> {
         let is_perf_enabled = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+            self.backend.perf_sample_interval.get(),
             &self.read_perf_status,
         );
         let result = self.backend.get_cf(self.handle(), &C::key(key));
-        if let Some(op_start_instant) = is_perf_enabled {
-            report_rocksdb_read_perf(C::NAME, &op_start_instant.elapsed(), &self.column_options);
+        if let Some(perf_sample) = is_perf_enabled {
+            report_rocksdb_read_perf(C::NAME, perf_sample, self.latency(), &self.column_options);
         }
 
         if let Some(serialized_value) = result? {
@@ -1331,12 +2061,12 @@ where
 
     pub fn get_protobuf(&self, key: C::Index) -> Result<Option<C::Type>> {
         let is_perf_enabled = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+            self.backend.perf_sample_interval.get(),
             &self.read_perf_status,
         );
         let result = self.backend.get_cf(self.handle(), &C::key(key));
-        if let Some(op_start_instant) = is_perf_enabled {
-            report_rocksdb_read_perf(C::NAME, &op_start_instant.elapsed(), &self.column_options);
+        if let Some(perf_sample) = is_perf_enabled {
+            report_rocksdb_read_perf(C::NAME, perf_sample, self.latency(), &self.column_options);
         }
 
         if let Some(serialized_value) = result? {
@@ -1351,15 +2081,16 @@ where
         value.encode(&mut buf)?;
 
         let is_perf_enabled = maybe_enable_rocksdb_perf(
-            self.column_options.rocks_perf_sample_interval,
+            self.backend.perf_sample_interval.get(),
             &self.write_perf_status,
         );
         let result = self.backend.put_cf(self.handle(), &C::key(key), &buf);
-        if let Some(op_start_instant) = is_perf_enabled {
+        if let Some(perf_sample) = is_perf_enabled {
             report_rocksdb_write_perf(
                 C::NAME,
                 "put",
-                &op_start_instant.elapsed(),
+                perf_sample,
+                self.latency(),
                 &self.column_options,
             );
         }
@@ -1370,13 +2101,14 @@ where
 
 impl<'a> WriteBatch<'a> {
     pub fn put_bytes<C: Column + ColumnName>(&mut self, key: C::Index, bytes: &[u8]) -> Result<()> {
-        self.write_batch
-            .put_cf(self.get_cf::<C>(), &C::key(key), bytes);
+        let (write_batch, cf) = self.get_cf::<C>();
+        write_batch.put_cf(cf, &C::key(key), bytes);
         Ok(())
     }
 
     pub fn delete<C: Column + ColumnName>(&mut self, key: C::Index) -> Result<()> {
-        self.write_batch.delete_cf(self.get_cf::<C>(), &C::key(key));
+        let (write_batch, cf) = self.get_cf::<C>();
+        write_batch.delete_cf(cf, &C::key(key));
         Ok(())
     }
 
@@ -1386,24 +2118,24 @@ impl<'a> WriteBatch<'a> {
         value: &C::Type,
     ) -> Result<()> {
         let serialized_value = serialize(&value)?;
-        self.write_batch
-            .put_cf(self.get_cf::<C>(), &C::key(key), &serialized_value);
+        let (write_batch, cf) = self.get_cf::<C>();
+        write_batch.put_cf(cf, &C::key(key), &serialized_value);
         Ok(())
     }
 
     #[inline]
-    fn get_cf<C: Column + ColumnName>(&self) -> &'a ColumnFamily {
-        self.map[C::NAME]
+    fn get_cf<C: Column + ColumnName>(&mut self) -> (&mut RWriteBatch, &'a ColumnFamily) {
+        let (index, cf) = self.map[C::NAME];
+        (&mut self.write_batches[index], cf)
     }
 
-    pub fn delete_range_cf<C: Column>(
+    pub fn delete_range_cf<C: Column + ColumnName>(
         &mut self,
-        cf: &ColumnFamily,
         from: C::Index,
         to: C::Index,
     ) -> Result<()> {
-        self.write_batch
-            .delete_range_cf(cf, C::key(from), C::key(to));
+        let (write_batch, cf) = self.get_cf::<C>();
+        write_batch.delete_range_cf(cf, C::key(from), C::key(to));
         Ok(())
     }
 }
@@ -1464,23 +2196,32 @@ impl<C: Column + ColumnName> CompactionFilterFactory for PurgedSlotFilterFactory
 fn new_cf_descriptor<C: 'static + Column + ColumnName>(
     options: &BlockstoreOptions,
     oldest_slot: &OldestSlot,
+    block_cache: Option<&Cache>,
 ) -> ColumnFamilyDescriptor {
-    ColumnFamilyDescriptor::new(C::NAME, get_cf_options::<C>(options, oldest_slot))
+    ColumnFamilyDescriptor::new(
+        C::NAME,
+        get_cf_options::<C>(options, oldest_slot, block_cache),
+    )
 }
 
 fn get_cf_options<C: 'static + Column + ColumnName>(
     options: &BlockstoreOptions,
     oldest_slot: &OldestSlot,
+    block_cache: Option<&Cache>,
 ) -> Options {
+    let tuning_options = options.rocksdb_tuning_options.as_ref();
+    let write_buffer_size = tuning_options
+        .and_then(|tuning_options| tuning_options.write_buffer_size)
+        .unwrap_or(MAX_WRITE_BUFFER_SIZE);
     let mut cf_options = Options::default();
     // 256 * 8 = 2GB. 6 of these columns should take at most 12GB of RAM
-    cf_options.set_max_write_buffer_number(8);
-    cf_options.set_write_buffer_size(MAX_WRITE_BUFFER_SIZE as usize);
+    cf_options.set_max_write_buffer_number(max_write_buffer_number(tuning_options));
+    cf_options.set_write_buffer_size(write_buffer_size as usize);
     let file_num_compaction_trigger = 4;
     // Recommend that this be around the size of level 0. Level 0 estimated size in stable state is
     // write_buffer_size * min_write_buffer_number_to_merge * level0_file_num_compaction_trigger
     // Source: https://docs.rs/rocksdb/0.6.0/rocksdb/struct.Options.html#method.set_level_zero_file_num_compaction_trigger
-    let total_size_base = MAX_WRITE_BUFFER_SIZE * file_num_compaction_trigger;
+    let total_size_base = write_buffer_size * file_num_compaction_trigger;
     let file_size_base = total_size_base / 10;
     cf_options.set_level_zero_file_num_compaction_trigger(file_num_compaction_trigger as i32);
     cf_options.set_max_bytes_for_level_base(total_size_base);
@@ -1499,14 +2240,21 @@ fn get_cf_options<C: 'static + Column + ColumnName>(
         });
     }
 
-    process_cf_options_advanced::<C>(&mut cf_options, &options.column_options);
+    process_cf_options_advanced::<C>(&mut cf_options, &options.column_options, block_cache);
 
     cf_options
 }
 
+fn max_write_buffer_number(tuning_options: Option<&RocksDbTuningOptions>) -> i32 {
+    tuning_options
+        .and_then(|tuning_options| tuning_options.max_write_buffer_number)
+        .unwrap_or(8)
+}
+
 fn process_cf_options_advanced<C: 'static + Column + ColumnName>(
     cf_options: &mut Options,
     column_options: &LedgerColumnOptions,
+    block_cache: Option<&Cache>,
 ) {
     if should_enable_compression::<C>() {
         cf_options.set_compression_type(
@@ -1515,6 +2263,35 @@ fn process_cf_options_advanced<C: 'static + Column + ColumnName>(
                 .to_rocksdb_compression_type(),
         );
     }
+
+    let filter_options = column_options.get_column_filter_options(C::NAME);
+    let bloom_filter_bits_per_key =
+        filter_options.and_then(|filter_options| filter_options.bloom_filter_bits_per_key);
+    if bloom_filter_bits_per_key.is_some() || block_cache.is_some() {
+        let mut block_based_options = BlockBasedOptions::default();
+        if let Some(bits_per_key) = bloom_filter_bits_per_key {
+            block_based_options.set_bloom_filter(bits_per_key, false);
+        }
+        if let Some(block_cache) = block_cache {
+            block_based_options.set_block_cache(block_cache);
+        }
+        cf_options.set_block_based_table_factory(&block_based_options);
+    }
+    if let Some(filter_options) = filter_options {
+        if let Some(prefix_extractor) = &filter_options.prefix_extractor {
+            cf_options.set_prefix_extractor(SliceTransform::create_fixed_prefix(
+                prefix_extractor.prefix_len(),
+            ));
+        }
+    }
+}
+
+// Scans that may cross prefixes walk the key space in total order, so they are
+// unaffected by any prefix extractor configured on the column family.
+fn total_order_read_options() -> ReadOptions {
+    let mut read_options = ReadOptions::default();
+    read_options.set_total_order_seek(true);
+    read_options
 }
 
 /// Creates and returns the column family descriptors for both data shreds and
@@ -1528,27 +2305,29 @@ fn new_cf_descriptor_pair_shreds<
 >(
     options: &BlockstoreOptions,
     oldest_slot: &OldestSlot,
+    block_cache: Option<&Cache>,
 ) -> (ColumnFamilyDescriptor, ColumnFamilyDescriptor) {
     match &options.column_options.shred_storage_type {
         ShredStorageType::RocksLevel => (
-            new_cf_descriptor::<D>(options, oldest_slot),
-            new_cf_descriptor::<C>(options, oldest_slot),
+            new_cf_descriptor::<D>(options, oldest_slot, block_cache),
+            new_cf_descriptor::<C>(options, oldest_slot, block_cache),
         ),
         ShredStorageType::RocksFifo(fifo_options) => (
-            new_cf_descriptor_fifo::<D>(&fifo_options.shred_data_cf_size, &options.column_options),
-            new_cf_descriptor_fifo::<C>(&fifo_options.shred_code_cf_size, &options.column_options),
+            new_cf_descriptor_fifo::<D>(&fifo_options.shred_data_cf_size, options, block_cache),
+            new_cf_descriptor_fifo::<C>(&fifo_options.shred_code_cf_size, options, block_cache),
         ),
     }
 }
 
 fn new_cf_descriptor_fifo<C: 'static + Column + ColumnName>(
     max_cf_size: &u64,
-    column_options: &LedgerColumnOptions,
+    options: &BlockstoreOptions,
+    block_cache: Option<&Cache>,
 ) -> ColumnFamilyDescriptor {
     if *max_cf_size > FIFO_WRITE_BUFFER_SIZE {
         ColumnFamilyDescriptor::new(
             C::NAME,
-            get_cf_options_fifo::<C>(max_cf_size, column_options),
+            get_cf_options_fifo::<C>(max_cf_size, options, block_cache),
         )
     } else {
         panic!(
@@ -1571,11 +2350,14 @@ fn new_cf_descriptor_fifo<C: 'static + Column + ColumnName>(
 /// maintain the size limit.
 fn get_cf_options_fifo<C: 'static + Column + ColumnName>(
     max_cf_size: &u64,
-    column_options: &LedgerColumnOptions,
+    blockstore_options: &BlockstoreOptions,
+    block_cache: Option<&Cache>,
 ) -> Options {
     let mut options = Options::default();
 
-    options.set_max_write_buffer_number(8);
+    options.set_max_write_buffer_number(max_write_buffer_number(
+        blockstore_options.rocksdb_tuning_options.as_ref(),
+    ));
     options.set_write_buffer_size(FIFO_WRITE_BUFFER_SIZE as usize);
     // FIFO always has its files in L0 so we only have one level.
     options.set_num_levels(1);
@@ -1597,20 +2379,33 @@ fn get_cf_options_fifo<C: 'static + Column + ColumnName>(
     options.set_compaction_style(DBCompactionStyle::Fifo);
     options.set_fifo_compaction_options(&fifo_compact_options);
 
-    process_cf_options_advanced::<C>(&mut options, column_options);
+    process_cf_options_advanced::<C>(
+        &mut options,
+        &blockstore_options.column_options,
+        block_cache,
+    );
 
     options
 }
 
-fn get_db_options(access_type: &AccessType) -> Options {
+fn get_db_options(
+    access_type: &AccessType,
+    tuning_options: &RocksDbTuningOptions,
+    io_options: &BlockstoreIoOptions,
+) -> Options {
     let mut options = Options::default();
 
     // Create missing items to support a clean start
     options.create_if_missing(true);
     options.create_missing_column_families(true);
 
-    // Per the docs, a good value for this is the number of cores on the machine
-    options.increase_parallelism(num_cpus::get() as i32);
+    // Per the docs, a good value for this is the number of cores on the machine. This also
+    // sizes the background thread pool for that many concurrent jobs.
+    options.increase_parallelism(
+        tuning_options
+            .max_background_jobs
+            .unwrap_or(num_cpus::get() as i32),
+    );
 
     let mut env = rocksdb::Env::default().unwrap();
     // While a compaction is ongoing, all the background threads
@@ -1632,9 +2427,37 @@ fn get_db_options(access_type: &AccessType) -> Options {
     // See https://github.com/facebook/rocksdb/wiki/Secondary-instance
     options.set_max_open_files(-1);
 
+    options.set_use_direct_reads(io_options.use_direct_reads);
+    options.set_use_direct_io_for_flush_and_compaction(
+        io_options.use_direct_io_for_flush_and_compaction,
+    );
+    if io_options.use_direct_io_for_flush_and_compaction {
+        // Without the page cache reading ahead, compactions read their inputs in small chunks
+        // unless given a readahead of their own
+        options.set_compaction_readahead_size(COMPACTION_READAHEAD_SIZE);
+    }
+    options.set_allow_mmap_reads(io_options.allow_mmap_reads);
+    options.set_allow_mmap_writes(io_options.allow_mmap_writes);
+
     options
 }
 
+// RocksDB refuses to open with direct I/O and mmap for the same accesses; say which flags clash
+// rather than surfacing its error.
+fn validate_io_options(io_options: &BlockstoreIoOptions) -> Result<()> {
+    if io_options.use_direct_reads && io_options.allow_mmap_reads {
+        return Err(BlockstoreError::InvalidIoOptions(
+            "direct reads can't be used with mmap reads".to_string(),
+        ));
+    }
+    if io_options.use_direct_io_for_flush_and_compaction && io_options.allow_mmap_writes {
+        return Err(BlockstoreError::InvalidIoOptions(
+            "direct I/O for flush and compaction can't be used with mmap writes".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 // Returns whether automatic compactions should be disabled based upon access type
 fn should_disable_auto_compactions(access_type: &AccessType) -> bool {
     // Leave automatic compactions enabled (do not disable) in Primary mode;
@@ -1649,6 +2472,8 @@ fn should_exclude_from_compaction(cf_name: &str) -> bool {
         columns::TransactionStatusIndex::NAME,
         columns::ProgramCosts::NAME,
         columns::TransactionMemos::NAME,
+        // Records the slots purged, so it must outlive them
+        columns::SlotTombstones::NAME,
     ]
     .into_iter()
     .collect();
@@ -1656,6 +2481,139 @@ fn should_exclude_from_compaction(cf_name: &str) -> bool {
     no_compaction_cfs.get(cf_name).is_some()
 }
 
+// Returns true if the column family may be stored outside of the blockstore directory. Only
+// columns of slot data can be; the metadata tying them together stays in the blockstore.
+fn is_relocatable_column(cf_name: &str) -> bool {
+    use columns::*;
+
+    [
+        ShredData::NAME,
+        ShredCode::NAME,
+        TransactionStatus::NAME,
+        AddressSignatures::NAME,
+        TransactionMemos::NAME,
+        Rewards::NAME,
+        Blocktime::NAME,
+        PerfSamples::NAME,
+        BlockHeight::NAME,
+        ProgramCosts::NAME,
+    ]
+    .contains(&cf_name)
+}
+
+// The directory of the RocksDB instance holding the columns of the blockstore at
+// `blockstore_path` that are relocated to `column_dir`
+fn relocated_path(blockstore_path: &Path, column_dir: &Path) -> PathBuf {
+    column_dir.join(blockstore_path.file_name().unwrap_or_default())
+}
+
+fn read_column_layout(blockstore_path: &Path) -> Result<ColumnLayout> {
+    match fs::read_to_string(blockstore_path.join(COLUMN_LAYOUT_FILE)) {
+        Ok(contents) => contents.parse().map_err(|err: ParseColumnLayoutError| {
+            BlockstoreError::InvalidColumnLayout(err.to_string())
+        }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(ColumnLayout::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn write_column_layout(blockstore_path: &Path, column_layout: &ColumnLayout) -> Result<()> {
+    let layout_path = blockstore_path.join(COLUMN_LAYOUT_FILE);
+    if column_layout.is_empty() {
+        return match fs::remove_file(&layout_path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        };
+    }
+    // Replaced in one rename so that a crash can't leave a partial layout behind
+    let tmp_path = layout_path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(column_layout.to_string().as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, &layout_path)?;
+    Ok(())
+}
+
+fn validate_column_layout(blockstore_path: &Path, column_layout: &ColumnLayout) -> Result<()> {
+    for (cf_name, column_dir) in column_layout.iter() {
+        if !Rocks::columns().contains(&cf_name) {
+            return Err(BlockstoreError::InvalidColumnLayout(format!(
+                "unknown column {}",
+                cf_name
+            )));
+        }
+        if !is_relocatable_column(cf_name) {
+            return Err(BlockstoreError::InvalidColumnLayout(format!(
+                "column {} can't be moved out of the blockstore directory",
+                cf_name
+            )));
+        }
+        if relocated_path(blockstore_path, column_dir) == blockstore_path {
+            return Err(BlockstoreError::InvalidColumnLayout(format!(
+                "column {} is assigned to the ledger directory {:?}",
+                cf_name, column_dir
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Returns the layout to open the blockstore at `blockstore_path` with: the recorded one, unless
+// the blockstore is yet to be created with the `requested` one. Changing the layout of an
+// existing blockstore takes moving its columns, so a mismatch is an error.
+fn resolve_column_layout(blockstore_path: &Path, requested: &ColumnLayout) -> Result<ColumnLayout> {
+    validate_column_layout(blockstore_path, requested)?;
+    let recorded = read_column_layout(blockstore_path)?;
+    if requested.is_empty() || *requested == recorded {
+        Ok(recorded)
+    } else if !blockstore_path.join("CURRENT").exists() {
+        Ok(requested.clone())
+    } else {
+        Err(BlockstoreError::InvalidColumnLayout(format!(
+            "the blockstore at {:?} has column layout {:?} rather than {:?}, run \
+             `solana-ledger-tool move-columns` to change it",
+            blockstore_path,
+            recorded.iter().collect::<Vec<_>>(),
+            requested.iter().collect::<Vec<_>>(),
+        )))
+    }
+}
+
+// Returns `fifo_options` with every shred column family size at least
+// MIN_ROCKS_FIFO_CF_SIZE_BYTES, raising smaller sizes to it if
+// `clamp_to_minimum_size` is set and failing otherwise.
+fn resolve_fifo_options(
+    fifo_options: &BlockstoreRocksFifoOptions,
+) -> Result<BlockstoreRocksFifoOptions> {
+    let resolve_size = |cf_name: &str, cf_size: u64| {
+        if cf_size >= MIN_ROCKS_FIFO_CF_SIZE_BYTES {
+            Ok(cf_size)
+        } else if fifo_options.clamp_to_minimum_size {
+            warn!(
+                "Raising the FIFO size of {} from {} to the minimum of {} bytes",
+                cf_name, cf_size, MIN_ROCKS_FIFO_CF_SIZE_BYTES
+            );
+            Ok(MIN_ROCKS_FIFO_CF_SIZE_BYTES)
+        } else {
+            Err(BlockstoreError::InvalidFifoOptions(format!(
+                "the FIFO size of {} is {} bytes, less than the minimum of {} bytes",
+                cf_name, cf_size, MIN_ROCKS_FIFO_CF_SIZE_BYTES
+            )))
+        }
+    };
+    Ok(BlockstoreRocksFifoOptions {
+        shred_data_cf_size: resolve_size(
+            columns::ShredData::NAME,
+            fifo_options.shred_data_cf_size,
+        )?,
+        shred_code_cf_size: resolve_size(
+            columns::ShredCode::NAME,
+            fifo_options.shred_code_cf_size,
+        )?,
+        ..fifo_options.clone()
+    })
+}
+
 // Returns true if the column family enables compression.
 fn should_enable_compression<C: 'static + Column + ColumnName>() -> bool {
     C::NAME == columns::TransactionStatus::NAME
@@ -1722,7 +2680,7 @@ pub mod tests {
         // should update both lists.
         assert_eq!(
             Rocks::columns().len(),
-            Rocks::cf_descriptors(&options, &oldest_slot).len()
+            Rocks::cf_descriptors(&options, &oldest_slot, None).len()
         );
     }
 
@@ -1733,6 +2691,7 @@ pub mod tests {
             &AccessType::PrimaryForMaintenance
         ));
         assert!(should_disable_auto_compactions(&AccessType::Secondary));
+        assert!(should_disable_auto_compactions(&AccessType::ReadOnly));
     }
 
     #[test]
@@ -1747,4 +2706,62 @@ pub mod tests {
         ));
         assert!(!should_exclude_from_compaction("something else"));
     }
+
+    #[test]
+    fn test_validate_io_options() {
+        assert!(validate_io_options(&BlockstoreIoOptions::default()).is_ok());
+        assert!(validate_io_options(&BlockstoreIoOptions {
+            use_direct_reads: true,
+            use_direct_io_for_flush_and_compaction: true,
+            ..BlockstoreIoOptions::default()
+        })
+        .is_ok());
+        assert!(validate_io_options(&BlockstoreIoOptions {
+            use_direct_reads: true,
+            allow_mmap_writes: true,
+            ..BlockstoreIoOptions::default()
+        })
+        .is_ok());
+        assert!(matches!(
+            validate_io_options(&BlockstoreIoOptions {
+                use_direct_reads: true,
+                allow_mmap_reads: true,
+                ..BlockstoreIoOptions::default()
+            }),
+            Err(BlockstoreError::InvalidIoOptions(_))
+        ));
+        assert!(matches!(
+            validate_io_options(&BlockstoreIoOptions {
+                use_direct_io_for_flush_and_compaction: true,
+                allow_mmap_writes: true,
+                ..BlockstoreIoOptions::default()
+            }),
+            Err(BlockstoreError::InvalidIoOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_fifo_options() {
+        let fifo_options = BlockstoreRocksFifoOptions::default();
+        let resolved = resolve_fifo_options(&fifo_options).unwrap();
+        assert_eq!(resolved.shred_data_cf_size, fifo_options.shred_data_cf_size);
+        assert_eq!(resolved.shred_code_cf_size, fifo_options.shred_code_cf_size);
+
+        let fifo_options = BlockstoreRocksFifoOptions {
+            shred_code_cf_size: FIFO_WRITE_BUFFER_SIZE,
+            ..BlockstoreRocksFifoOptions::default()
+        };
+        assert!(matches!(
+            resolve_fifo_options(&fifo_options),
+            Err(BlockstoreError::InvalidFifoOptions(_))
+        ));
+
+        let fifo_options = BlockstoreRocksFifoOptions {
+            clamp_to_minimum_size: true,
+            ..fifo_options
+        };
+        let resolved = resolve_fifo_options(&fifo_options).unwrap();
+        assert_eq!(resolved.shred_data_cf_size, fifo_options.shred_data_cf_size);
+        assert_eq!(resolved.shred_code_cf_size, MIN_ROCKS_FIFO_CF_SIZE_BYTES);
+    }
 }
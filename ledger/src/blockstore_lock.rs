@@ -0,0 +1,435 @@
+//! An advisory lock guarding Primary access to a blockstore directory across processes.
+//!
+//! RocksDB locks its own `LOCK` file, but only reports a failure to take it as an opaque IO
+//! error, and on shared storage the lock of a crashed process may outlive it. The advisory
+//! lock file records the pid and host of its owner, along with a heartbeat it refreshes, so
+//! opening a locked blockstore can say who holds it, and the lock of an owner that is gone can
+//! be taken over. RocksDB's lock still guarantees exclusive access once the advisory one is
+//! taken, including against processes that predate it.
+use {
+    crate::blockstore_db::{BlockstoreError, Result},
+    crossbeam_channel::{bounded, RecvTimeoutError, Sender},
+    gethostname::gethostname,
+    solana_sdk::timing::timestamp,
+    std::{
+        fmt,
+        fs::{self, OpenOptions},
+        io::{self, Write},
+        path::{Path, PathBuf},
+        str::FromStr,
+        thread::{Builder, JoinHandle},
+        time::{Duration, SystemTime},
+    },
+};
+
+/// Name of the advisory lock file, in the blockstore directory
+pub const BLOCKSTORE_LOCK_FILE: &str = "solana-blockstore.lock";
+
+/// Name of the lock file RocksDB keeps in the blockstore directory
+pub const ROCKSDB_LOCK_FILE: &str = "LOCK";
+
+/// How often the owner of the lock refreshes its heartbeat
+pub const BLOCKSTORE_LOCK_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long the heartbeat of an owner on another host may go without a refresh before its
+/// lock is considered stale
+pub const BLOCKSTORE_LOCK_STALE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The process holding a blockstore lock, as recorded in the lock file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockstoreLockOwner {
+    pub pid: u32,
+    pub hostname: String,
+    /// Last time the owner refreshed the lock, in milliseconds since the epoch
+    pub heartbeat: u64,
+}
+
+impl BlockstoreLockOwner {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            hostname: current_hostname(),
+            heartbeat: timestamp(),
+        }
+    }
+
+    fn to_file_contents(&self) -> String {
+        format!(
+            "pid={}\nhostname={}\nheartbeat={}\n",
+            self.pid, self.hostname, self.heartbeat
+        )
+    }
+
+    fn is_same_process(&self, other: &Self) -> bool {
+        self.pid == other.pid && self.hostname == other.hostname
+    }
+
+    fn heartbeat_age(&self, now: u64) -> Duration {
+        Duration::from_millis(now.saturating_sub(self.heartbeat))
+    }
+
+    /// Returns whether the owner is known to be gone. An owner on this host is gone if its
+    /// process has exited, or if it has this process' pid but stopped refreshing the lock, as
+    /// happens when the pid of a crashed process is reused in a restarted container. An owner
+    /// on another host is gone once its heartbeat is older than `BLOCKSTORE_LOCK_STALE_TIMEOUT`.
+    pub fn is_stale(&self) -> bool {
+        if self.hostname != current_hostname() || self.pid == std::process::id() {
+            self.heartbeat_age(timestamp()) > BLOCKSTORE_LOCK_STALE_TIMEOUT
+        } else {
+            !is_process_alive(self.pid)
+        }
+    }
+}
+
+impl fmt::Display for BlockstoreLockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pid {} on host {}, last heartbeat {}s ago",
+            self.pid,
+            self.hostname,
+            self.heartbeat_age(timestamp()).as_secs()
+        )
+    }
+}
+
+impl FromStr for BlockstoreLockOwner {
+    type Err = String;
+
+    fn from_str(contents: &str) -> std::result::Result<Self, Self::Err> {
+        let (mut pid, mut hostname, mut heartbeat) = (None, None, None);
+        for line in contents.lines() {
+            match line.split_once('=') {
+                Some(("pid", value)) => pid = value.parse().ok(),
+                Some(("hostname", value)) => hostname = Some(value.to_string()),
+                Some(("heartbeat", value)) => heartbeat = value.parse().ok(),
+                _ => (),
+            }
+        }
+        match (pid, hostname, heartbeat) {
+            (Some(pid), Some(hostname), Some(heartbeat)) => Ok(Self {
+                pid,
+                hostname,
+                heartbeat,
+            }),
+            _ => Err(format!("invalid blockstore lock file: {:?}", contents)),
+        }
+    }
+}
+
+/// The advisory lock of a blockstore directory, released when dropped
+#[derive(Debug)]
+pub struct BlockstoreLock {
+    lock_path: PathBuf,
+    owner: BlockstoreLockOwner,
+    heartbeat_sender: Option<Sender<()>>,
+    heartbeat_thread: Option<JoinHandle<()>>,
+}
+
+impl BlockstoreLock {
+    /// Takes the lock of the blockstore directory at `path`, taking it over from an owner that
+    /// is gone, and refreshes its heartbeat until dropped
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let lock_path = path.join(BLOCKSTORE_LOCK_FILE);
+        let owner = BlockstoreLockOwner::current();
+        // A second attempt is made after removing the lock of an owner that is gone
+        for _ in 0..2 {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(mut file) => {
+                    file.write_all(owner.to_file_contents().as_bytes())?;
+                    file.sync_all()?;
+                    return Ok(Self::new(lock_path, owner));
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err.into()),
+            }
+            match read_lock_owner(&lock_path)? {
+                LockFile::Owned(current_owner) if !current_owner.is_stale() => {
+                    error!(
+                        "The blockstore at {:?} is in use by {}. Stop that process first; if it \
+                         has crashed on another host, run `solana-ledger-tool unlock --force` \
+                         once it is known to be down",
+                        path, current_owner
+                    );
+                    return Err(BlockstoreError::BlockstoreLocked(
+                        path.to_path_buf(),
+                        Some(current_owner),
+                    ));
+                }
+                LockFile::Owned(current_owner) => {
+                    warn!(
+                        "Taking over the blockstore lock at {:?} from {}, which is gone",
+                        path, current_owner
+                    );
+                }
+                LockFile::Invalid(age) if age <= BLOCKSTORE_LOCK_STALE_TIMEOUT => {
+                    // Possibly being written by its owner right now
+                    error!("The blockstore at {:?} is in use by another process", path);
+                    return Err(BlockstoreError::BlockstoreLocked(path.to_path_buf(), None));
+                }
+                LockFile::Invalid(_) => {
+                    warn!("Removing the invalid blockstore lock at {:?}", lock_path);
+                }
+                LockFile::Missing => continue,
+            }
+            remove_file_if_exists(&lock_path)?;
+        }
+        Err(BlockstoreError::BlockstoreLocked(path.to_path_buf(), None))
+    }
+
+    fn new(lock_path: PathBuf, owner: BlockstoreLockOwner) -> Self {
+        let (heartbeat_sender, heartbeat_receiver) = bounded::<()>(0);
+        let heartbeat_thread = {
+            let lock_path = lock_path.clone();
+            let mut owner = owner.clone();
+            Builder::new()
+                .name("solana-blockstore-lock".to_string())
+                .spawn(move || loop {
+                    match heartbeat_receiver.recv_timeout(BLOCKSTORE_LOCK_HEARTBEAT_INTERVAL) {
+                        Err(RecvTimeoutError::Timeout) => (),
+                        Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                    owner.heartbeat = timestamp();
+                    if let Err(err) = refresh_lock(&lock_path, &owner) {
+                        error!(
+                            "Stopped refreshing the blockstore lock at {:?}: {}",
+                            lock_path, err
+                        );
+                        break;
+                    }
+                })
+                .unwrap()
+        };
+        Self {
+            lock_path,
+            owner,
+            heartbeat_sender: Some(heartbeat_sender),
+            heartbeat_thread: Some(heartbeat_thread),
+        }
+    }
+
+    /// Returns the owner of the lock of the blockstore directory at `path`, if it's locked
+    pub fn owner(path: &Path) -> Result<Option<BlockstoreLockOwner>> {
+        match read_lock_owner(&path.join(BLOCKSTORE_LOCK_FILE))? {
+            LockFile::Owned(owner) => Ok(Some(owner)),
+            LockFile::Invalid(_) | LockFile::Missing => Ok(None),
+        }
+    }
+
+    /// Removes the lock of the blockstore directory at `path` if its owner is gone, returning
+    /// that owner. With `force`, the lock is removed whoever owns it, along with the lock RocksDB
+    /// keeps, so a validator that crashed on shared storage, where neither lock may be seen to be
+    /// stale, can be restarted.
+    ///
+    /// Forcing the removal of the locks of a running process lets another one open the
+    /// blockstore alongside it and corrupt it.
+    pub fn unlock(path: &Path, force: bool) -> Result<Option<BlockstoreLockOwner>> {
+        let lock_path = path.join(BLOCKSTORE_LOCK_FILE);
+        let owner = match read_lock_owner(&lock_path)? {
+            LockFile::Owned(owner) if !force && !owner.is_stale() => {
+                return Err(BlockstoreError::BlockstoreLocked(
+                    path.to_path_buf(),
+                    Some(owner),
+                ));
+            }
+            LockFile::Invalid(age) if !force && age <= BLOCKSTORE_LOCK_STALE_TIMEOUT => {
+                return Err(BlockstoreError::BlockstoreLocked(path.to_path_buf(), None));
+            }
+            LockFile::Owned(owner) => Some(owner),
+            LockFile::Invalid(_) | LockFile::Missing => None,
+        };
+        remove_file_if_exists(&lock_path)?;
+        if force {
+            remove_file_if_exists(&path.join(ROCKSDB_LOCK_FILE))?;
+        }
+        Ok(owner)
+    }
+}
+
+impl Drop for BlockstoreLock {
+    fn drop(&mut self) {
+        drop(self.heartbeat_sender.take());
+        if let Some(heartbeat_thread) = self.heartbeat_thread.take() {
+            let _ = heartbeat_thread.join();
+        }
+        // Leave the lock alone if it was forcibly taken from this process
+        match read_lock_owner(&self.lock_path) {
+            Ok(LockFile::Owned(owner)) if owner.is_same_process(&self.owner) => {
+                if let Err(err) = fs::remove_file(&self.lock_path) {
+                    warn!(
+                        "Failed to remove the blockstore lock at {:?}: {}",
+                        self.lock_path, err
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Returns whether RocksDB failed to open a blockstore because another process holds its lock
+pub(crate) fn is_rocksdb_lock_error(err: &rocksdb::Error) -> bool {
+    let err = err.to_string();
+    err.contains("lock file") || err.contains("lock hold by current process")
+}
+
+enum LockFile {
+    Owned(BlockstoreLockOwner),
+    /// Lock file that can't be parsed, with the time since it was last modified
+    Invalid(Duration),
+    Missing,
+}
+
+fn read_lock_owner(lock_path: &Path) -> Result<LockFile> {
+    let contents = match fs::read_to_string(lock_path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(LockFile::Missing),
+        Err(err) => return Err(err.into()),
+    };
+    if let Ok(owner) = contents.parse() {
+        return Ok(LockFile::Owned(owner));
+    }
+    let modified = match fs::metadata(lock_path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(LockFile::Missing),
+        Err(err) => return Err(err.into()),
+    };
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    Ok(LockFile::Invalid(age))
+}
+
+/// Rewrites the lock file with the refreshed heartbeat of `owner`, unless another process has
+/// taken the lock over
+fn refresh_lock(lock_path: &Path, owner: &BlockstoreLockOwner) -> io::Result<()> {
+    match read_lock_owner(lock_path) {
+        Ok(LockFile::Owned(current_owner)) if current_owner.is_same_process(owner) => (),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "the lock was taken over by another process",
+            ))
+        }
+    }
+    // Write the new contents aside, so readers never see a partially written lock file
+    let tmp_path = lock_path.with_extension(format!("tmp-{}", owner.pid));
+    fs::write(&tmp_path, owner.to_file_contents())?;
+    fs::rename(&tmp_path, lock_path)
+}
+
+fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+fn current_hostname() -> String {
+    gethostname().to_string_lossy().into_owned()
+}
+
+#[cfg(unix)]
+fn is_process_alive(pid: u32) -> bool {
+    // Signal 0 only checks whether the process exists; EPERM means it does, but belongs to
+    // another user
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+    || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_process_alive(_pid: u32) -> bool {
+    // Without a way to tell, the owner is assumed to be running
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, assert_matches::assert_matches, tempfile::TempDir};
+
+    fn write_lock_file(path: &Path, owner: &BlockstoreLockOwner) {
+        fs::write(path.join(BLOCKSTORE_LOCK_FILE), owner.to_file_contents()).unwrap();
+    }
+
+    #[test]
+    fn test_parse_blockstore_lock_owner() {
+        let owner = BlockstoreLockOwner {
+            pid: 42,
+            hostname: "validator-1".to_string(),
+            heartbeat: 1_234,
+        };
+        assert_eq!(owner.to_file_contents().parse(), Ok(owner));
+        assert!("pid=42\n".parse::<BlockstoreLockOwner>().is_err());
+        assert!("".parse::<BlockstoreLockOwner>().is_err());
+    }
+
+    #[test]
+    fn test_blockstore_lock_exclusive() {
+        let dir = TempDir::new().unwrap();
+        let lock = BlockstoreLock::acquire(dir.path()).unwrap();
+        let owner = BlockstoreLock::owner(dir.path()).unwrap().unwrap();
+        assert!(owner.is_same_process(&BlockstoreLockOwner::current()));
+        assert!(!owner.is_stale());
+
+        assert_matches!(
+            BlockstoreLock::acquire(dir.path()),
+            Err(BlockstoreError::BlockstoreLocked(_, Some(_)))
+        );
+        assert_matches!(
+            BlockstoreLock::unlock(dir.path(), false),
+            Err(BlockstoreError::BlockstoreLocked(_, Some(_)))
+        );
+
+        drop(lock);
+        assert_eq!(BlockstoreLock::owner(dir.path()).unwrap(), None);
+        let _lock = BlockstoreLock::acquire(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn test_blockstore_lock_takeover() {
+        let dir = TempDir::new().unwrap();
+        // A previous incarnation of this process that stopped refreshing the lock
+        let crashed_owner = BlockstoreLockOwner {
+            heartbeat: timestamp() - 2 * BLOCKSTORE_LOCK_STALE_TIMEOUT.as_millis() as u64,
+            ..BlockstoreLockOwner::current()
+        };
+        assert!(crashed_owner.is_stale());
+        write_lock_file(dir.path(), &crashed_owner);
+        let lock = BlockstoreLock::acquire(dir.path()).unwrap();
+        assert!(!BlockstoreLock::owner(dir.path())
+            .unwrap()
+            .unwrap()
+            .is_stale());
+        drop(lock);
+
+        // An owner on another host with a recent heartbeat holds on to the lock
+        let remote_owner = BlockstoreLockOwner {
+            pid: 1,
+            hostname: format!("{}-other", current_hostname()),
+            heartbeat: timestamp(),
+        };
+        assert!(!remote_owner.is_stale());
+        write_lock_file(dir.path(), &remote_owner);
+        assert_matches!(
+            BlockstoreLock::acquire(dir.path()),
+            Err(BlockstoreError::BlockstoreLocked(_, Some(owner))) if owner == remote_owner
+        );
+
+        // Until it's forcibly removed
+        assert_matches!(
+            BlockstoreLock::unlock(dir.path(), false),
+            Err(BlockstoreError::BlockstoreLocked(_, Some(_)))
+        );
+        fs::write(dir.path().join(ROCKSDB_LOCK_FILE), "").unwrap();
+        assert_eq!(
+            BlockstoreLock::unlock(dir.path(), true).unwrap(),
+            Some(remote_owner)
+        );
+        assert!(!dir.path().join(ROCKSDB_LOCK_FILE).exists());
+        let _lock = BlockstoreLock::acquire(dir.path()).unwrap();
+    }
+}
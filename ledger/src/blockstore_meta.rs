@@ -4,9 +4,11 @@ use {
     solana_sdk::{
         clock::{Slot, UnixTimestamp},
         hash::Hash,
+        pubkey::Pubkey,
     },
     std::{
         collections::BTreeSet,
+        fmt,
         ops::{Range, RangeBounds},
     },
 };
@@ -307,6 +309,28 @@ impl DuplicateSlotProof {
     }
 }
 
+/// Where a proof of a duplicate block was observed
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum DuplicateProofSource {
+    /// This node received both shreds
+    Local,
+    /// The node with this pubkey pushed the proof over gossip
+    Gossip(Pubkey),
+}
+
+/// Proof that the leader of a slot produced two conflicting shreds, the DuplicateProofs
+/// column family
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DuplicateProof {
+    #[serde(with = "serde_bytes")]
+    pub shred1: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub shred2: Vec<u8>,
+    pub source: DuplicateProofSource,
+    /// When the proof was stored, in milliseconds since the UNIX epoch
+    pub timestamp: UnixTimestamp,
+}
+
 #[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct TransactionStatusIndexMeta {
     pub max_slot: Slot,
@@ -330,6 +354,65 @@ pub struct ProgramCost {
     pub cost: u64,
 }
 
+/// The aggregated cost of the transactions of a block, recorded once the
+/// block is frozen
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BlockCost {
+    /// Compute units consumed by the transactions of the block, as replayed
+    pub compute_units: u64,
+    /// Compute units consumed by each program invoked in the block, as
+    /// replayed, costliest first
+    pub program_compute_units: Vec<(Pubkey, u64)>,
+    /// Cost of the block according to the cost model, as tracked while
+    /// packing it; only known for blocks produced by this node
+    pub block_cost: u64,
+    /// Limit on the cost of a block according to the cost model
+    pub block_cost_limit: u64,
+    /// Transaction fees collected by the leader of the block, before burning
+    pub fees: u64,
+}
+
+impl BlockCost {
+    /// Share of the block cost limit used by the block
+    pub fn utilization(&self) -> f64 {
+        if self.block_cost_limit == 0 {
+            return 0.0;
+        }
+        self.compute_units.max(self.block_cost) as f64 / self.block_cost_limit as f64
+    }
+}
+
+/// Why a range of slots was purged from the blockstore
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SlotPurgeReason {
+    /// The ledger cleanup kept the ledger under its size limit
+    LedgerCleanup,
+    /// The slots were moved to the ledger archive
+    Archived,
+    /// An operator purged the slots
+    Manual,
+}
+
+impl fmt::Display for SlotPurgeReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SlotPurgeReason::LedgerCleanup => write!(f, "ledger cleanup"),
+            SlotPurgeReason::Archived => write!(f, "archived"),
+            SlotPurgeReason::Manual => write!(f, "manual purge"),
+        }
+    }
+}
+
+/// Record of a range of slots purged from the blockstore, the SlotTombstones column family
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SlotTombstone {
+    pub first_slot: Slot,
+    pub last_slot: Slot,
+    pub reason: SlotPurgeReason,
+    /// When the slots were purged, in milliseconds since the UNIX epoch
+    pub timestamp: UnixTimestamp,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct OptimisticSlotMetaV0 {
     pub hash: Hash,
@@ -358,6 +441,65 @@ impl OptimisticSlotMetaVersioned {
         }
     }
 }
+
+/// Why a slot was marked dead
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum DeadSlotCause {
+    /// A last shred was received with an index lower than the shreds received before it
+    ConflictingLastShred,
+    /// The ticks of the slot are invalid: too few or too many, with the wrong hash count, or
+    /// followed by a trailing entry
+    InvalidTicks(String),
+    /// The entry hashes of the slot don't verify against the hash of its parent
+    InvalidEntryHash,
+    /// A transaction of the slot failed to execute
+    TransactionFailed(String),
+    /// Any other error replaying the slot
+    ReplayFailed(String),
+}
+
+impl fmt::Display for DeadSlotCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeadSlotCause::ConflictingLastShred => write!(f, "conflicting last shred"),
+            DeadSlotCause::InvalidTicks(err) => write!(f, "invalid ticks: {}", err),
+            DeadSlotCause::InvalidEntryHash => write!(f, "invalid entry hash"),
+            DeadSlotCause::TransactionFailed(err) => write!(f, "transaction failed: {}", err),
+            DeadSlotCause::ReplayFailed(err) => write!(f, "replay failed: {}", err),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DeadSlotMetaV0 {
+    pub cause: DeadSlotCause,
+    /// Milliseconds since the epoch at which the slot was marked dead
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub enum DeadSlotMetaVersioned {
+    V0(DeadSlotMetaV0),
+}
+
+impl DeadSlotMetaVersioned {
+    pub fn new(cause: DeadSlotCause, timestamp: u64) -> Self {
+        DeadSlotMetaVersioned::V0(DeadSlotMetaV0 { cause, timestamp })
+    }
+
+    pub fn cause(&self) -> &DeadSlotCause {
+        match self {
+            DeadSlotMetaVersioned::V0(meta) => &meta.cause,
+        }
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            DeadSlotMetaVersioned::V0(meta) => meta.timestamp,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use {
@@ -3,6 +3,7 @@ use {
         blockstore_db::columns,
         blockstore_options::{LedgerColumnOptions, ShredStorageType},
     },
+    histogram::Histogram,
     rocksdb::{
         perf::{set_perf_stats, PerfMetric, PerfStatsLevel},
         PerfContext,
@@ -11,10 +12,10 @@ use {
     solana_sdk::timing::timestamp,
     std::{
         cell::RefCell,
-        fmt::Debug,
+        fmt::{self, Debug},
         sync::{
             atomic::{AtomicU64, AtomicUsize, Ordering},
-            Arc,
+            Arc, Mutex,
         },
         time::{Duration, Instant},
     },
@@ -242,34 +243,137 @@ thread_local! {static PER_THREAD_ROCKS_PERF_CONTEXT: RefCell<PerfContext> = RefC
 // The minimum time duration between two RocksDB perf samples of the same operation.
 const PERF_SAMPLING_MIN_DURATION: Duration = Duration::from_secs(1);
 
-/// The function enables RocksDB PerfContext once for every `sample_interval`.
+/// The number of RocksDB operations between two perf samples, shared by all the
+/// column families of a blockstore so that sampling can be turned up while
+/// diagnosing I/O stalls and back down afterwards. 0 disables sampling.
+#[derive(Clone, Debug, Default)]
+pub struct PerfSampleInterval(Arc<AtomicUsize>);
+
+impl PerfSampleInterval {
+    pub fn new(sample_interval: usize) -> Self {
+        Self(Arc::new(AtomicUsize::new(sample_interval)))
+    }
+
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, sample_interval: usize) {
+        self.0.store(sample_interval, Ordering::Relaxed);
+    }
+}
+
+/// Latencies of the sampled RocksDB operations of a column family, in
+/// microseconds, since they were last reported.
+pub struct RocksDbColumnLatency {
+    read_us: Mutex<Histogram>,
+    write_us: Mutex<Histogram>,
+}
+
+impl Default for RocksDbColumnLatency {
+    fn default() -> Self {
+        Self {
+            read_us: Mutex::new(Histogram::new()),
+            write_us: Mutex::new(Histogram::new()),
+        }
+    }
+}
+
+impl Debug for RocksDbColumnLatency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RocksDbColumnLatency")
+            .field("read_count", &self.read_us.lock().unwrap().entries())
+            .field("write_count", &self.write_us.lock().unwrap().entries())
+            .finish()
+    }
+}
+
+impl RocksDbColumnLatency {
+    fn record(histogram: &Mutex<Histogram>, op_duration: &Duration) {
+        let _ = histogram
+            .lock()
+            .unwrap()
+            .increment(op_duration.as_micros() as u64);
+    }
+
+    /// The number of sampled reads and writes since the last report
+    pub fn num_samples(&self) -> (u64, u64) {
+        (
+            self.read_us.lock().unwrap().entries(),
+            self.write_us.lock().unwrap().entries(),
+        )
+    }
+
+    /// Reports the latencies recorded since the last report, then clears them
+    pub fn report(&self, cf_name: &'static str, column_options: &LedgerColumnOptions) {
+        for (op, histogram) in [("read", &self.read_us), ("write", &self.write_us)] {
+            let mut histogram = histogram.lock().unwrap();
+            if histogram.entries() == 0 {
+                continue;
+            }
+            datapoint_info!(
+                "blockstore_rocksdb_latency",
+                "op" => op,
+                "cf_name" => cf_name,
+                "storage" => column_options.get_storage_type_string(),
+                "compression" => column_options.get_compression_type_string(),
+                ("count", histogram.entries() as i64, i64),
+                ("us_50pct", histogram.percentile(50.0).unwrap_or(0) as i64, i64),
+                ("us_90pct", histogram.percentile(90.0).unwrap_or(0) as i64, i64),
+                ("us_99pct", histogram.percentile(99.0).unwrap_or(0) as i64, i64),
+                ("us_max", histogram.maximum().unwrap_or(0) as i64, i64),
+            );
+            histogram.clear();
+        }
+    }
+}
+
+/// A sampled RocksDB operation
+pub(crate) struct RocksDbPerfSample {
+    start: Instant,
+    // Whether the PerfContext of the operation is collected on top of its latency
+    perf_context: bool,
+}
+
+/// The function samples one in every `sample_interval` RocksDB operations,
+/// recording their latency, and enables RocksDB PerfContext for the samples
+/// at most once per `PERF_SAMPLING_MIN_DURATION`.
 ///
 /// PerfContext is a thread-local struct defined in RocksDB for collecting
 /// per-thread read / write performance metrics.
 ///
-/// When this function enables PerfContext, the function will return true,
-/// and the PerfContext of the ubsequent RocksDB operation will be collected.
+/// When this function enables PerfContext, the PerfContext of the subsequent
+/// RocksDB operation will be collected.
 pub(crate) fn maybe_enable_rocksdb_perf(
     sample_interval: usize,
     perf_status: &PerfSamplingStatus,
-) -> Option<Instant> {
-    if perf_status.should_sample(sample_interval) {
+) -> Option<RocksDbPerfSample> {
+    let perf_context = perf_status.sample(sample_interval)?;
+    if perf_context {
         set_perf_stats(PerfStatsLevel::EnableTime);
         PER_THREAD_ROCKS_PERF_CONTEXT.with(|perf_context| {
             perf_context.borrow_mut().reset();
         });
-        return Some(Instant::now());
     }
-    None
+    Some(RocksDbPerfSample {
+        start: Instant::now(),
+        perf_context,
+    })
 }
 
-/// Reports the collected PerfContext and disables the PerfContext after
-/// reporting.
+/// Records the latency of the sampled operation, then reports the collected
+/// PerfContext if any and disables the PerfContext after reporting.
 pub(crate) fn report_rocksdb_read_perf(
     cf_name: &'static str,
-    total_op_duration: &Duration,
+    perf_sample: RocksDbPerfSample,
+    latency: &RocksDbColumnLatency,
     column_options: &LedgerColumnOptions,
 ) {
+    let total_op_duration = perf_sample.start.elapsed();
+    RocksDbColumnLatency::record(&latency.read_us, &total_op_duration);
+    if !perf_sample.perf_context {
+        return;
+    }
     PER_THREAD_ROCKS_PERF_CONTEXT.with(|perf_context_cell| {
         set_perf_stats(PerfStatsLevel::Disable);
         let perf_context = perf_context_cell.borrow();
@@ -438,14 +542,20 @@ pub(crate) fn report_rocksdb_read_perf(
         );
     });
 }
-/// Reports the collected PerfContext and disables the PerfContext after
-/// reporting.
+/// Records the latency of the sampled operation, then reports the collected
+/// PerfContext if any and disables the PerfContext after reporting.
 pub(crate) fn report_rocksdb_write_perf(
     cf_name: &'static str,
     op_name: &'static str,
-    total_op_duration: &Duration,
+    perf_sample: RocksDbPerfSample,
+    latency: &RocksDbColumnLatency,
     column_options: &LedgerColumnOptions,
 ) {
+    let total_op_duration = perf_sample.start.elapsed();
+    RocksDbColumnLatency::record(&latency.write_us, &total_op_duration);
+    if !perf_sample.perf_context {
+        return;
+    }
     PER_THREAD_ROCKS_PERF_CONTEXT.with(|perf_context_cell| {
         set_perf_stats(PerfStatsLevel::Disable);
         let perf_context = perf_context_cell.borrow();
@@ -526,36 +636,40 @@ pub struct PerfSamplingStatus {
 }
 
 impl PerfSamplingStatus {
-    fn should_sample(&self, sample_count_interval: usize) -> bool {
+    // Returns None if the operation isn't sampled, or else whether its
+    // PerfContext is to be collected.
+    fn sample(&self, sample_count_interval: usize) -> Option<bool> {
         if sample_count_interval == 0 {
-            return false;
+            return None;
         }
 
         // Rate-limiting based on the number of samples.
         if self.op_count.fetch_add(1, Ordering::Relaxed) < sample_count_interval {
-            return false;
+            return None;
         }
         self.op_count.store(0, Ordering::Relaxed);
 
-        // Rate-limiting based on the time duration.
+        // PerfContext collection is further rate-limited based on the time duration.
         let current_time_ms = timestamp();
         let old_time_ms = self.last_sample_time_ms.load(Ordering::Relaxed);
         if old_time_ms + (PERF_SAMPLING_MIN_DURATION.as_millis() as u64) > current_time_ms {
-            return false;
+            return Some(false);
         }
 
         // If the `last_sample_time_ms` has a different value than `old_time_ms`,
         // it means some other thread has performed the sampling and updated
         // the last sample time.  In this case, the current thread will skip
         // the current sample.
-        self.last_sample_time_ms
-            .compare_exchange_weak(
-                old_time_ms,
-                current_time_ms,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            )
-            .is_ok()
+        Some(
+            self.last_sample_time_ms
+                .compare_exchange_weak(
+                    old_time_ms,
+                    current_time_ms,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_ok(),
+        )
     }
 }
 
@@ -735,6 +849,19 @@ impl ColumnMetrics for columns::DeadSlots {
     }
 }
 
+impl ColumnMetrics for columns::DeadSlotCauses {
+    fn report_cf_metrics(
+        cf_metrics: BlockstoreRocksDbColumnFamilyMetrics,
+        column_options: &Arc<LedgerColumnOptions>,
+    ) {
+        cf_metrics.report_metrics(rocksdb_metric_header!(
+            "blockstore_rocksdb_cfs",
+            "dead_slot_causes",
+            column_options
+        ));
+    }
+}
+
 impl ColumnMetrics for columns::DuplicateSlots {
     fn report_cf_metrics(
         cf_metrics: BlockstoreRocksDbColumnFamilyMetrics,
@@ -787,6 +914,45 @@ impl ColumnMetrics for columns::OptimisticSlots {
     }
 }
 
+impl ColumnMetrics for columns::BlockCost {
+    fn report_cf_metrics(
+        cf_metrics: BlockstoreRocksDbColumnFamilyMetrics,
+        column_options: &Arc<LedgerColumnOptions>,
+    ) {
+        cf_metrics.report_metrics(rocksdb_metric_header!(
+            "blockstore_rocksdb_cfs",
+            "block_cost",
+            column_options
+        ));
+    }
+}
+
+impl ColumnMetrics for columns::SlotTombstones {
+    fn report_cf_metrics(
+        cf_metrics: BlockstoreRocksDbColumnFamilyMetrics,
+        column_options: &Arc<LedgerColumnOptions>,
+    ) {
+        cf_metrics.report_metrics(rocksdb_metric_header!(
+            "blockstore_rocksdb_cfs",
+            "slot_tombstones",
+            column_options
+        ));
+    }
+}
+
+impl ColumnMetrics for columns::DuplicateProofs {
+    fn report_cf_metrics(
+        cf_metrics: BlockstoreRocksDbColumnFamilyMetrics,
+        column_options: &Arc<LedgerColumnOptions>,
+    ) {
+        cf_metrics.report_metrics(rocksdb_metric_header!(
+            "blockstore_rocksdb_cfs",
+            "duplicate_proofs",
+            column_options
+        ));
+    }
+}
+
 impl ColumnMetrics for columns::Root {
     fn report_cf_metrics(
         cf_metrics: BlockstoreRocksDbColumnFamilyMetrics,
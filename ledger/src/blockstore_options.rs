@@ -1,5 +1,15 @@
-use rocksdb::{DBCompressionType as RocksCompressionType, DBRecoveryMode};
+use {
+    rocksdb::{DBCompressionType as RocksCompressionType, DBRecoveryMode},
+    std::{
+        collections::{BTreeMap, BTreeSet, HashMap},
+        fmt,
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+    thiserror::Error,
+};
 
+#[derive(Clone)]
 pub struct BlockstoreOptions {
     // The access type of blockstore. Default: Primary
     pub access_type: AccessType,
@@ -8,6 +18,17 @@ pub struct BlockstoreOptions {
     // Whether to allow unlimited number of open files. Default: true.
     pub enforce_ulimit_nofile: bool,
     pub column_options: LedgerColumnOptions,
+    // RocksDB tunables overriding the built-in defaults. Default: None.
+    pub rocksdb_tuning_options: Option<RocksDbTuningOptions>,
+    // Number of the latest slots to check with `Blockstore::verify_integrity` when opening,
+    // failing to open if any is corrupted. Default: None.
+    pub verify_on_open: Option<u64>,
+    // Directories holding column families outside of the blockstore directory. Default: empty,
+    // which opens the blockstore with the layout it was created or last migrated with.
+    pub column_layout: ColumnLayout,
+    // How RocksDB reads and writes the blockstore files. Default: through the page cache,
+    // without mmap.
+    pub io_options: BlockstoreIoOptions,
 }
 
 impl Default for BlockstoreOptions {
@@ -18,10 +39,128 @@ impl Default for BlockstoreOptions {
             recovery_mode: None,
             enforce_ulimit_nofile: true,
             column_options: LedgerColumnOptions::default(),
+            rocksdb_tuning_options: None,
+            verify_on_open: None,
+            column_layout: ColumnLayout::default(),
+            io_options: BlockstoreIoOptions::default(),
         }
     }
 }
 
+/// RocksDB tunables, so the ledger store can be fit to the memory and disks of the machine.
+/// Each unset field keeps the built-in default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RocksDbTuningOptions {
+    /// Size of a memtable of the column families using level compaction, in bytes.
+    /// Default: 256 MB. Column families using FIFO compaction keep theirs, which their size
+    /// limit is checked against.
+    pub write_buffer_size: Option<u64>,
+    /// Maximum number of memtables of each column family, including the one being written to
+    /// and the ones being flushed. Default: 8.
+    pub max_write_buffer_number: Option<i32>,
+    /// Capacity of an LRU block cache shared by all column families, in bytes. Default: each
+    /// column family has its own 8 MB block cache.
+    pub block_cache_size: Option<usize>,
+    /// Maximum number of concurrent background compactions and flushes. Default: the number of
+    /// cores of the machine.
+    pub max_background_jobs: Option<i32>,
+}
+
+/// How RocksDB accesses the blockstore files. Direct I/O keeps ledger reads and compactions out
+/// of the page cache, so they neither evict nor depend on what other processes cache, such as
+/// snapshots being served. Direct I/O and mmap can't both be used for the same accesses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockstoreIoOptions {
+    /// Read SST files with direct I/O. Default: false.
+    pub use_direct_reads: bool,
+    /// Read and write the files of flushes and compactions with direct I/O. Default: false.
+    pub use_direct_io_for_flush_and_compaction: bool,
+    /// Read SST files through mmap. Default: false.
+    pub allow_mmap_reads: bool,
+    /// Write SST files through mmap. Default: false.
+    pub allow_mmap_writes: bool,
+}
+
+/// Assignment of column families to directories outside of the blockstore directory, so that
+/// each column can be put on storage suited to its access pattern, such as shreds on large
+/// disks and transaction statuses on fast ones. Each directory holds a RocksDB instance of its
+/// own, named after the blockstore directory. Columns that are not assigned stay in the
+/// blockstore directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnLayout {
+    column_paths: BTreeMap<String, PathBuf>,
+}
+
+impl ColumnLayout {
+    /// Assigns `column` to `path`, replacing any previous assignment
+    pub fn set_column_path(&mut self, column: impl Into<String>, path: impl Into<PathBuf>) {
+        self.column_paths.insert(column.into(), path.into());
+    }
+
+    /// The directory `column` is assigned to, if any
+    pub fn column_path(&self, column: &str) -> Option<&Path> {
+        self.column_paths.get(column).map(PathBuf::as_path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.column_paths.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.column_paths
+            .iter()
+            .map(|(column, path)| (column.as_str(), path.as_path()))
+    }
+
+    /// The distinct directories columns are assigned to
+    pub fn paths(&self) -> BTreeSet<&Path> {
+        self.column_paths.values().map(PathBuf::as_path).collect()
+    }
+
+    /// Parses `COLUMN=DIRECTORY` assignments, such as those given on the command line
+    pub fn from_column_paths<'a>(
+        column_paths: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, ParseColumnLayoutError> {
+        let mut column_layout = ColumnLayout::default();
+        for column_path in column_paths {
+            match column_path.split_once('=') {
+                Some((column, path)) if !column.trim().is_empty() && !path.trim().is_empty() => {
+                    column_layout.set_column_path(column.trim(), path.trim());
+                }
+                _ => return Err(ParseColumnLayoutError(column_path.to_string())),
+            }
+        }
+        Ok(column_layout)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid column path {0:?}, expected COLUMN=DIRECTORY")]
+pub struct ParseColumnLayoutError(String);
+
+impl FromStr for ColumnLayout {
+    type Err = ParseColumnLayoutError;
+
+    /// Parses `COLUMN=DIRECTORY` assignments, one per line
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Self::from_column_paths(
+            string
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty()),
+        )
+    }
+}
+
+impl fmt::Display for ColumnLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (column, path) in self.iter() {
+            writeln!(f, "{}={}", column, path.display())?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AccessType {
     /// Primary (read/write) access; only one process can have Primary access.
@@ -32,6 +171,11 @@ pub enum AccessType {
     /// Additionally, Secondary access can be obtained while another process
     /// already has Primary access.
     Secondary,
+    /// Read only access to a snapshot of the database as of when it was
+    /// opened. Unlike Secondary access, no WAL is replayed into a separate
+    /// directory and later writes by a Primary process are never caught up
+    /// with; the database must not be opened with Primary access meanwhile.
+    ReadOnly,
 }
 
 #[derive(Debug, Clone)]
@@ -42,20 +186,60 @@ pub enum BlockstoreRecoveryMode {
     SkipAnyCorruptedRecord,
 }
 
-impl From<&str> for BlockstoreRecoveryMode {
-    fn from(string: &str) -> Self {
-        match string {
-            "tolerate_corrupted_tail_records" => {
-                BlockstoreRecoveryMode::TolerateCorruptedTailRecords
+impl BlockstoreRecoveryMode {
+    /// Canonical names of the recovery modes, as accepted by `--wal-recovery-mode`
+    pub const NAMES: [&'static str; 4] = [
+        "tolerate_corrupted_tail_records",
+        "absolute_consistency",
+        "point_in_time",
+        "skip_any_corrupted_record",
+    ];
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid recovery mode {0:?}, expected one of: {}", BlockstoreRecoveryMode::NAMES.join(", "))]
+pub struct ParseBlockstoreRecoveryModeError(String);
+
+impl FromStr for BlockstoreRecoveryMode {
+    type Err = ParseBlockstoreRecoveryModeError;
+
+    /// Parses the canonical name of a recovery mode. For forward compatibility with other
+    /// spellings, case, dashes and underscores are ignored, and the RocksDB names of the modes
+    /// (such as `kPointInTimeRecovery`) and their numeric `WALRecoveryMode` values are accepted.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let normalized: String = string
+            .trim()
+            .chars()
+            .filter(|c| *c != '_' && *c != '-')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        let normalized = normalized
+            .strip_prefix('k')
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&normalized);
+        match normalized {
+            "toleratecorruptedtailrecords" | "0" => {
+                Ok(BlockstoreRecoveryMode::TolerateCorruptedTailRecords)
+            }
+            "absoluteconsistency" | "1" => Ok(BlockstoreRecoveryMode::AbsoluteConsistency),
+            "pointintime" | "pointintimerecovery" | "2" => Ok(BlockstoreRecoveryMode::PointInTime),
+            "skipanycorruptedrecord" | "skipanycorruptedrecords" | "3" => {
+                Ok(BlockstoreRecoveryMode::SkipAnyCorruptedRecord)
             }
-            "absolute_consistency" => BlockstoreRecoveryMode::AbsoluteConsistency,
-            "point_in_time" => BlockstoreRecoveryMode::PointInTime,
-            "skip_any_corrupted_record" => BlockstoreRecoveryMode::SkipAnyCorruptedRecord,
-            bad_mode => panic!("Invalid recovery mode: {}", bad_mode),
+            _ => Err(ParseBlockstoreRecoveryModeError(string.to_string())),
         }
     }
 }
 
+/// Deprecated: panics on an invalid recovery mode, use `str::parse()` instead
+impl From<&str> for BlockstoreRecoveryMode {
+    fn from(string: &str) -> Self {
+        string
+            .parse()
+            .unwrap_or_else(|err| panic!("Invalid recovery mode: {}", err))
+    }
+}
+
 impl From<BlockstoreRecoveryMode> for DBRecoveryMode {
     fn from(brm: BlockstoreRecoveryMode) -> Self {
         match brm {
@@ -86,7 +270,12 @@ pub struct LedgerColumnOptions {
     // Control how often RocksDB read/write performance samples are collected.
     // If the value is greater than 0, then RocksDB read/write perf sample
     // will be collected once for every `rocks_perf_sample_interval` ops.
+    // This is the initial value, see Blockstore::set_rocks_perf_sample_interval.
     pub rocks_perf_sample_interval: usize,
+
+    // Bloom filter and prefix extractor settings keyed by column family name.
+    // Column families without an entry keep the RocksDB defaults.
+    pub column_filter_options: HashMap<&'static str, BlockstoreColumnFilterOptions>,
 }
 
 impl Default for LedgerColumnOptions {
@@ -95,6 +284,7 @@ impl Default for LedgerColumnOptions {
             shred_storage_type: ShredStorageType::RocksLevel,
             compression_type: BlockstoreCompressionType::default(),
             rocks_perf_sample_interval: 0,
+            column_filter_options: HashMap::default(),
         }
     }
 }
@@ -115,6 +305,63 @@ impl LedgerColumnOptions {
             BlockstoreCompressionType::Zlib => "Zlib",
         }
     }
+
+    pub fn get_column_filter_options(
+        &self,
+        cf_name: &str,
+    ) -> Option<&BlockstoreColumnFilterOptions> {
+        self.column_filter_options.get(cf_name)
+    }
+}
+
+/// Read-path filter settings of a single column family.  Point-lookup heavy
+/// columns (e.g. TransactionStatus) benefit from whole-key bloom filters while
+/// range-scan heavy columns (e.g. ShredData) benefit from a prefix extractor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockstoreColumnFilterOptions {
+    // Bits per key of the bloom filter built for each SST file.
+    // None leaves the column family without a bloom filter.
+    pub bloom_filter_bits_per_key: Option<i32>,
+
+    // How keys are grouped into prefixes for prefix bloom filters.
+    // None disables prefix filtering.
+    pub prefix_extractor: Option<BlockstorePrefixExtractor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockstorePrefixExtractor {
+    // Groups keys by their leading big-endian slot.  Only valid for columns
+    // whose keys start with the slot, such as ShredData and ShredCode.
+    Slot,
+    // Groups keys by their first `n` bytes.
+    FixedLength(usize),
+}
+
+impl BlockstorePrefixExtractor {
+    pub fn prefix_len(&self) -> usize {
+        match self {
+            Self::Slot => std::mem::size_of::<u64>(),
+            Self::FixedLength(len) => *len,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid prefix extractor {0:?}, expected \"slot\" or a prefix length")]
+pub struct ParsePrefixExtractorError(String);
+
+impl FromStr for BlockstorePrefixExtractor {
+    type Err = ParsePrefixExtractorError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "slot" => Ok(Self::Slot),
+            _ => match string.parse() {
+                Ok(len) if len > 0 => Ok(Self::FixedLength(len)),
+                _ => Err(ParsePrefixExtractorError(string.to_string())),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -140,19 +387,22 @@ pub struct BlockstoreRocksFifoOptions {
     // ledger store storage size if the RPC service is enabled, or 50% if RPC
     // service is not enabled.
     //
-    // Note that this number must be greater than FIFO_WRITE_BUFFER_SIZE
-    // otherwise we won't be able to write any file.  If not, the blockstore
-    // will panic.
+    // Note that this number must be at least MIN_ROCKS_FIFO_CF_SIZE_BYTES,
+    // otherwise the column family can't hold a single file.  If not, opening
+    // the blockstore fails unless `clamp_to_minimum_size` is set.
     pub shred_data_cf_size: u64,
     // The maximum storage size for storing coding shreds in column family
     // [`cf::CodeShred`].  Typically, coding shreds contribute around 20% of the
     // ledger store storage size if the RPC service is enabled, or 40% if RPC
     // service is not enabled.
     //
-    // Note that this number must be greater than FIFO_WRITE_BUFFER_SIZE
-    // otherwise we won't be able to write any file.  If not, the blockstore
-    // will panic.
+    // Note that this number must be at least MIN_ROCKS_FIFO_CF_SIZE_BYTES,
+    // otherwise the column family can't hold a single file.  If not, opening
+    // the blockstore fails unless `clamp_to_minimum_size` is set.
     pub shred_code_cf_size: u64,
+    // Whether to raise the sizes above that are too small to the minimum
+    // rather than failing to open the blockstore.
+    pub clamp_to_minimum_size: bool,
 }
 
 // Maximum size of cf::DataShred.  Used when `shred_storage_type`
@@ -166,6 +416,19 @@ const DEFAULT_FIFO_COMPACTION_DATA_CF_SIZE: u64 = 125 * 1024 * 1024 * 1024;
 // used by coding shreds.
 const DEFAULT_FIFO_COMPACTION_CODING_CF_SIZE: u64 = 100 * 1024 * 1024 * 1024;
 
+impl BlockstoreRocksFifoOptions {
+    /// Splits `total_bytes` of shred storage between cf::ShredData and cf::ShredCode in
+    /// the ratio the two typically contribute to the ledger store, 25% to 20%, i.e. 5:4.
+    pub fn new_for_total_size(total_bytes: u64) -> Self {
+        let shred_data_cf_size = (u128::from(total_bytes) * 5 / 9) as u64;
+        Self {
+            shred_data_cf_size,
+            shred_code_cf_size: total_bytes - shred_data_cf_size,
+            ..Self::default()
+        }
+    }
+}
+
 impl Default for BlockstoreRocksFifoOptions {
     fn default() -> Self {
         Self {
@@ -173,6 +436,7 @@ impl Default for BlockstoreRocksFifoOptions {
             shred_data_cf_size: DEFAULT_FIFO_COMPACTION_DATA_CF_SIZE,
             // Maximum size of cf::ShredCode.
             shred_code_cf_size: DEFAULT_FIFO_COMPACTION_CODING_CF_SIZE,
+            clamp_to_minimum_size: false,
         }
     }
 }
@@ -201,3 +465,107 @@ impl BlockstoreCompressionType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_blockstore_recovery_mode() {
+        for name in BlockstoreRecoveryMode::NAMES {
+            assert!(name.parse::<BlockstoreRecoveryMode>().is_ok());
+        }
+        for alias in [
+            "point-in-time",
+            "POINT_IN_TIME",
+            "PointInTime",
+            "kPointInTimeRecovery",
+            "2",
+        ] {
+            assert!(matches!(
+                alias.parse(),
+                Ok(BlockstoreRecoveryMode::PointInTime)
+            ));
+        }
+        assert!(matches!(
+            "skip_any_corrupted_records".parse(),
+            Ok(BlockstoreRecoveryMode::SkipAnyCorruptedRecord)
+        ));
+        for bad_mode in ["", "k", "4", "point_in_space"] {
+            assert_eq!(
+                bad_mode.parse::<BlockstoreRecoveryMode>().unwrap_err(),
+                ParseBlockstoreRecoveryModeError(bad_mode.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_column_layout() {
+        let column_layout: ColumnLayout = "data_shred=/mnt/hdd\n\ntransaction_status = /mnt/nvme\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            column_layout.column_path("data_shred"),
+            Some(Path::new("/mnt/hdd"))
+        );
+        assert_eq!(
+            column_layout.column_path("transaction_status"),
+            Some(Path::new("/mnt/nvme"))
+        );
+        assert_eq!(column_layout.column_path("code_shred"), None);
+        assert_eq!(column_layout.paths().len(), 2);
+        assert_eq!(
+            ColumnLayout::from_column_paths([
+                "data_shred=/mnt/hdd",
+                "transaction_status=/mnt/nvme"
+            ]),
+            Ok(column_layout.clone())
+        );
+        assert_eq!(column_layout.to_string().parse(), Ok(column_layout));
+        assert!("".parse::<ColumnLayout>().unwrap().is_empty());
+
+        for bad_layout in ["data_shred", "data_shred=", "=/mnt/hdd"] {
+            assert_eq!(
+                bad_layout.parse::<ColumnLayout>().unwrap_err(),
+                ParseColumnLayoutError(bad_layout.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_rocks_fifo_options_new_for_total_size() {
+        let fifo_options = BlockstoreRocksFifoOptions::new_for_total_size(900);
+        assert_eq!(fifo_options.shred_data_cf_size, 500);
+        assert_eq!(fifo_options.shred_code_cf_size, 400);
+        assert!(!fifo_options.clamp_to_minimum_size);
+
+        // Rounding leaves the remainder to coding shreds, without losing any bytes
+        let fifo_options = BlockstoreRocksFifoOptions::new_for_total_size(1000);
+        assert_eq!(fifo_options.shred_data_cf_size, 555);
+        assert_eq!(fifo_options.shred_code_cf_size, 445);
+
+        let fifo_options = BlockstoreRocksFifoOptions::new_for_total_size(u64::MAX);
+        assert_eq!(
+            fifo_options.shred_data_cf_size + fifo_options.shred_code_cf_size,
+            u64::MAX
+        );
+        assert_eq!(
+            BlockstoreRocksFifoOptions::new_for_total_size(0).shred_data_cf_size,
+            0
+        );
+    }
+
+    #[test]
+    fn test_parse_prefix_extractor() {
+        assert_eq!("slot".parse(), Ok(BlockstorePrefixExtractor::Slot));
+        assert_eq!("12".parse(), Ok(BlockstorePrefixExtractor::FixedLength(12)));
+        for bad_prefix_extractor in ["", "0", "-1", "Slot"] {
+            assert_eq!(
+                bad_prefix_extractor
+                    .parse::<BlockstorePrefixExtractor>()
+                    .unwrap_err(),
+                ParsePrefixExtractorError(bad_prefix_extractor.to_string())
+            );
+        }
+    }
+}
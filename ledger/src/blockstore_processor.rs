@@ -1,7 +1,10 @@
 use {
     crate::{
-        block_error::BlockError, blockstore::Blockstore, blockstore_db::BlockstoreError,
-        blockstore_meta::SlotMeta, leader_schedule_cache::LeaderScheduleCache,
+        block_error::BlockError,
+        blockstore::Blockstore,
+        blockstore_db::BlockstoreError,
+        blockstore_meta::{DeadSlotCause, SlotMeta},
+        leader_schedule_cache::LeaderScheduleCache,
     },
     chrono_humanize::{Accuracy, HumanTime, Tense},
     crossbeam_channel::Sender,
@@ -583,6 +586,27 @@ pub enum BlockstoreProcessorError {
     RootBankWithMismatchedCapitalization(Slot),
 }
 
+impl From<&BlockstoreProcessorError> for DeadSlotCause {
+    fn from(err: &BlockstoreProcessorError) -> Self {
+        match err {
+            BlockstoreProcessorError::InvalidBlock(BlockError::InvalidEntryHash) => {
+                DeadSlotCause::InvalidEntryHash
+            }
+            BlockstoreProcessorError::InvalidBlock(
+                err @ (BlockError::InvalidLastTick
+                | BlockError::TooFewTicks
+                | BlockError::TooManyTicks
+                | BlockError::InvalidTickHashCount
+                | BlockError::TrailingEntry),
+            ) => DeadSlotCause::InvalidTicks(err.to_string()),
+            BlockstoreProcessorError::InvalidTransaction(err) => {
+                DeadSlotCause::TransactionFailed(err.to_string())
+            }
+            err => DeadSlotCause::ReplayFailed(err.to_string()),
+        }
+    }
+}
+
 /// Callback for accessing bank state while processing the blockstore
 pub type ProcessCallback = Arc<dyn Fn(&Bank) + Sync + Send>;
 
@@ -656,6 +680,7 @@ pub(crate) fn process_blockstore_for_bank_0(
         accounts_update_notifier,
     );
     bank0.set_compute_budget(opts.runtime_config.compute_budget);
+    bank0.set_transaction_account_lock_limit(opts.runtime_config.transaction_account_lock_limit);
     let bank_forks = Arc::new(RwLock::new(BankForks::new(bank0)));
 
     info!("processing ledger for slot 0...");
@@ -1438,7 +1463,7 @@ fn process_single_slot(
         warn!("slot {} failed to verify: {}", slot, err);
         if blockstore.is_primary_access() {
             blockstore
-                .set_dead_slot(slot)
+                .set_dead_slot_with_cause(slot, DeadSlotCause::from(&err))
                 .expect("Failed to mark slot as dead in blockstore");
         } else {
             info!(
@@ -1648,7 +1673,7 @@ pub mod tests {
                 // just pass the original session if it is a Primary variant
                 test_process_blockstore(genesis_config, blockstore, opts)
             }
-            AccessType::Secondary => {
+            AccessType::Secondary | AccessType::ReadOnly => {
                 let secondary_blockstore = Blockstore::open_with_options(
                     blockstore.ledger_path(),
                     BlockstoreOptions {
@@ -1672,6 +1697,11 @@ pub mod tests {
         do_test_process_blockstore_with_missing_hashes(AccessType::Secondary);
     }
 
+    #[test]
+    fn test_process_blockstore_with_missing_hashes_read_only_access() {
+        do_test_process_blockstore_with_missing_hashes(AccessType::ReadOnly);
+    }
+
     // Intentionally make slot 1 faulty and ensure that processing sees it as dead
     fn do_test_process_blockstore_with_missing_hashes(blockstore_access_type: AccessType) {
         solana_logger::setup();
@@ -1717,9 +1747,9 @@ pub mod tests {
 
         let dead_slots: Vec<Slot> = blockstore.dead_slots_iterator(0).unwrap().collect();
         match blockstore_access_type {
-            // Secondary access is immutable so even though a dead slot
-            // will be identified, it won't actually be marked dead.
-            AccessType::Secondary => {
+            // Secondary and ReadOnly access are immutable so even though a
+            // dead slot will be identified, it won't actually be marked dead.
+            AccessType::Secondary | AccessType::ReadOnly => {
                 assert_eq!(dead_slots.len(), 0);
             }
             AccessType::Primary | AccessType::PrimaryForMaintenance => {
@@ -2092,6 +2122,32 @@ pub mod tests {
         verify_fork_infos(&bank_forks);
     }
 
+    #[test]
+    fn test_dead_slot_cause_from_error() {
+        assert_eq!(
+            DeadSlotCause::from(&BlockstoreProcessorError::InvalidBlock(
+                BlockError::InvalidEntryHash
+            )),
+            DeadSlotCause::InvalidEntryHash
+        );
+        assert_eq!(
+            DeadSlotCause::from(&BlockstoreProcessorError::InvalidBlock(
+                BlockError::TooFewTicks
+            )),
+            DeadSlotCause::InvalidTicks(BlockError::TooFewTicks.to_string())
+        );
+        assert_eq!(
+            DeadSlotCause::from(&BlockstoreProcessorError::InvalidTransaction(
+                TransactionError::AccountNotFound
+            )),
+            DeadSlotCause::TransactionFailed(TransactionError::AccountNotFound.to_string())
+        );
+        assert!(matches!(
+            DeadSlotCause::from(&BlockstoreProcessorError::FailedToLoadMeta),
+            DeadSlotCause::ReplayFailed(_)
+        ));
+    }
+
     #[test]
     fn test_process_blockstore_with_dead_slot() {
         solana_logger::setup();
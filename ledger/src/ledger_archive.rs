@@ -0,0 +1,403 @@
+//! An archival tier for rooted slots that have aged out of the local blockstore.
+//!
+//! Each archived slot is stored as one object, holding the slot's `SlotMeta`, the payloads of
+//! its data shreds and the block as RPC serves it, so that archived blocks can be returned by
+//! `getBlock` without replaying the shreds. Objects are kept in an `ArchiveStorage`: a local
+//! directory, which may be a mounted bucket, or a client of an object store provided outside of
+//! this crate.
+use {
+    crate::{
+        blockstore::Blockstore,
+        blockstore_db::{BlockstoreError, Result as BlockstoreResult},
+        blockstore_meta::SlotMeta,
+    },
+    async_trait::async_trait,
+    prost::Message,
+    serde::{Deserialize, Serialize},
+    solana_sdk::clock::Slot,
+    solana_storage_proto::convert::generated,
+    solana_transaction_status::ConfirmedBlock,
+    std::{
+        convert::TryFrom,
+        io,
+        path::{Path, PathBuf},
+    },
+    thiserror::Error,
+};
+
+/// Version of the archive format written by this crate
+pub const SLOT_ARCHIVE_VERSION: u32 = 1;
+
+/// Rooted slots are archived by default once they are this many slots behind the latest root,
+/// roughly one day
+pub const DEFAULT_LEDGER_ARCHIVE_MIN_AGE_SLOTS: u64 = 216_000;
+
+const SLOTS_DIR: &str = "slots";
+
+#[derive(Error, Debug)]
+pub enum LedgerArchiveError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("storage error: {0}")]
+    Storage(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("object store responded with status {status} for {key}")]
+    ObjectStore { status: u16, key: String },
+
+    #[error("blockstore error: {0:?}")]
+    Blockstore(#[from] BlockstoreError),
+
+    #[error("invalid archive of slot {0}: {1}")]
+    InvalidArchive(Slot, String),
+
+    #[error("invalid archive url: {0}")]
+    InvalidUrl(String),
+
+    #[error("environment variable {0} is not set")]
+    MissingCredentials(&'static str),
+}
+
+pub type Result<T> = std::result::Result<T, LedgerArchiveError>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LedgerArchiveConfig {
+    /// Where archived slots are stored: a local directory, `s3://bucket[/prefix]` or
+    /// `gs://bucket[/prefix]`
+    pub url: String,
+    /// Endpoint of the object store, overriding the default of the url's scheme
+    pub endpoint: Option<String>,
+    /// Region the object store is signed for, overriding the default of the url's scheme
+    pub region: Option<String>,
+    /// Rooted slots are archived, then deleted locally, once they are this many slots behind
+    /// the latest root
+    pub min_age_slots: u64,
+}
+
+impl Default for LedgerArchiveConfig {
+    fn default() -> Self {
+        Self {
+            url: String::default(),
+            endpoint: None,
+            region: None,
+            min_age_slots: DEFAULT_LEDGER_ARCHIVE_MIN_AGE_SLOTS,
+        }
+    }
+}
+
+/// A rooted slot, as archived
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SlotArchive {
+    pub version: u32,
+    pub slot: Slot,
+    pub slot_meta: SlotMeta,
+    /// Payloads of the slot's data shreds, in index order
+    pub data_shreds: Vec<Vec<u8>>,
+    /// The block, as a protobuf-encoded `generated::ConfirmedBlock`
+    pub block: Vec<u8>,
+}
+
+impl SlotArchive {
+    /// Packages rooted `slot` from `blockstore`
+    pub fn from_blockstore(blockstore: &Blockstore, slot: Slot) -> BlockstoreResult<Self> {
+        let slot_meta = blockstore
+            .meta(slot)?
+            .ok_or(BlockstoreError::SlotUnavailable)?;
+        let block = blockstore.get_rooted_block(slot, true)?;
+        let data_shreds = blockstore
+            .slot_data_iterator(slot, 0)?
+            .map(|(_, payload)| payload.into_vec())
+            .collect();
+        Ok(Self {
+            version: SLOT_ARCHIVE_VERSION,
+            slot,
+            slot_meta,
+            data_shreds,
+            block: generated::ConfirmedBlock::from(block).encode_to_vec(),
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    pub fn deserialize(slot: Slot, data: &[u8]) -> Result<Self> {
+        let archive: Self = bincode::deserialize(data)
+            .map_err(|err| LedgerArchiveError::InvalidArchive(slot, err.to_string()))?;
+        if archive.version != SLOT_ARCHIVE_VERSION {
+            return Err(LedgerArchiveError::InvalidArchive(
+                slot,
+                format!("unsupported version {}", archive.version),
+            ));
+        }
+        if archive.slot != slot {
+            return Err(LedgerArchiveError::InvalidArchive(
+                slot,
+                format!("archive holds slot {}", archive.slot),
+            ));
+        }
+        Ok(archive)
+    }
+
+    pub fn confirmed_block(&self) -> Result<ConfirmedBlock> {
+        let block = generated::ConfirmedBlock::decode(&self.block[..])
+            .map_err(|err| LedgerArchiveError::InvalidArchive(self.slot, err.to_string()))?;
+        ConfirmedBlock::try_from(block)
+            .map_err(|err| LedgerArchiveError::InvalidArchive(self.slot, err.to_string()))
+    }
+}
+
+/// Where archived slots are kept. Keys are `/`-separated paths.
+#[async_trait]
+pub trait ArchiveStorage: std::fmt::Debug + Send + Sync {
+    /// Stores `data` under `key`, replacing any previous object
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()>;
+
+    /// Fetches the object stored under `key`, or `None` if there is none
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Lists, in ascending order, up to `limit` names in `dir` that sort after `start_after`.
+    /// An empty list means there are no more names.
+    async fn list(&self, dir: &str, start_after: &str, limit: usize) -> Result<Vec<String>>;
+}
+
+/// Keeps archived slots in a local directory
+#[derive(Debug)]
+pub struct FilesystemStorage {
+    root: PathBuf,
+}
+
+impl FilesystemStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+#[async_trait]
+impl ArchiveStorage for FilesystemStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Write to a temporary file first so readers never see a partial archive
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, data).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.root.join(key)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn list(&self, dir: &str, start_after: &str, limit: usize) -> Result<Vec<String>> {
+        let mut entries = match tokio::fs::read_dir(self.root.join(dir)).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+        let mut names = vec![];
+        while let Some(entry) = entries.next_entry().await? {
+            if let Some(name) = entry.file_name().to_str() {
+                if name > start_after {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort_unstable();
+        names.truncate(limit);
+        Ok(names)
+    }
+}
+
+fn slot_key(slot: Slot) -> String {
+    format!("{}/{:016x}", SLOTS_DIR, slot)
+}
+
+fn parse_slot_key(name: &str) -> Option<Slot> {
+    (name.len() == 16)
+        .then(|| Slot::from_str_radix(name, 16).ok())
+        .flatten()
+}
+
+/// Archives rooted slots and serves them back once they are deleted locally
+#[derive(Debug)]
+pub struct LedgerArchive {
+    storage: Box<dyn ArchiveStorage>,
+    min_age_slots: u64,
+}
+
+impl LedgerArchive {
+    pub fn new_with_storage(storage: Box<dyn ArchiveStorage>, min_age_slots: u64) -> Self {
+        Self {
+            storage,
+            min_age_slots,
+        }
+    }
+
+    /// Creates an archive in the local directory `path`
+    pub fn open(path: &Path, min_age_slots: u64) -> Self {
+        Self::new_with_storage(
+            Box::new(FilesystemStorage::new(path.to_path_buf())),
+            min_age_slots,
+        )
+    }
+
+    pub fn min_age_slots(&self) -> u64 {
+        self.min_age_slots
+    }
+
+    /// Uploads rooted `slot` of `blockstore`. The slot is not deleted locally.
+    pub async fn archive_slot(&self, blockstore: &Blockstore, slot: Slot) -> Result<()> {
+        let archive = SlotArchive::from_blockstore(blockstore, slot)?;
+        self.storage.put(&slot_key(slot), archive.serialize()).await
+    }
+
+    /// Fetches the archive of `slot`, or `None` if the slot was never archived
+    pub async fn get_slot(&self, slot: Slot) -> Result<Option<SlotArchive>> {
+        self.storage
+            .get(&slot_key(slot))
+            .await?
+            .map(|data| SlotArchive::deserialize(slot, &data))
+            .transpose()
+    }
+
+    /// Fetches the block of archived `slot`, or `None` if the slot was never archived
+    pub async fn get_block(&self, slot: Slot) -> Result<Option<ConfirmedBlock>> {
+        self.get_slot(slot)
+            .await?
+            .map(|archive| archive.confirmed_block())
+            .transpose()
+    }
+
+    /// Returns up to `limit` archived slots, in ascending order, starting at `start_slot`
+    pub async fn get_slots(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
+        let mut slots = vec![];
+        let mut start_after = start_slot
+            .checked_sub(1)
+            .map(slot_key)
+            .map(|key| key[SLOTS_DIR.len() + 1..].to_string())
+            .unwrap_or_default();
+        while slots.len() < limit {
+            let names = self
+                .storage
+                .list(SLOTS_DIR, &start_after, limit - slots.len())
+                .await?;
+            match names.last() {
+                Some(last) => start_after = last.clone(),
+                None => break,
+            }
+            slots.extend(names.iter().filter_map(|name| parse_slot_key(name)));
+        }
+        Ok(slots)
+    }
+
+    /// Returns the lowest archived slot
+    pub async fn get_first_slot(&self) -> Result<Option<Slot>> {
+        Ok(self.get_slots(0, 1).await?.first().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{blockstore::make_slot_entries, get_tmp_ledger_path_auto_delete},
+    };
+
+    #[test]
+    fn test_archive_slot() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let archive_dir = tempfile::TempDir::new().unwrap();
+        let ledger_archive = LedgerArchive::open(archive_dir.path(), 0);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let mut blockhash = None;
+        for slot in 0..3 {
+            let (shreds, entries) = make_slot_entries(slot, slot.saturating_sub(1), 4);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+            blockhash = entries.last().map(|entry| entry.hash);
+        }
+        blockstore.set_roots([0, 1, 2].iter()).unwrap();
+
+        runtime
+            .block_on(ledger_archive.archive_slot(&blockstore, 2))
+            .unwrap();
+        assert!(runtime
+            .block_on(ledger_archive.get_slot(1))
+            .unwrap()
+            .is_none());
+
+        let archive = runtime
+            .block_on(ledger_archive.get_slot(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(archive.slot_meta, blockstore.meta(2).unwrap().unwrap());
+        assert_eq!(
+            archive.data_shreds,
+            blockstore
+                .slot_data_iterator(2, 0)
+                .unwrap()
+                .map(|(_, payload)| payload.into_vec())
+                .collect::<Vec<_>>()
+        );
+        let block = runtime
+            .block_on(ledger_archive.get_block(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            block,
+            ConfirmedBlock::from(blockstore.get_rooted_block(2, true).unwrap())
+        );
+        assert_eq!(block.blockhash, blockhash.unwrap().to_string());
+    }
+
+    #[test]
+    fn test_get_slots() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let archive_dir = tempfile::TempDir::new().unwrap();
+        let ledger_archive = LedgerArchive::open(archive_dir.path(), 0);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        assert_eq!(
+            runtime.block_on(ledger_archive.get_first_slot()).unwrap(),
+            None
+        );
+        for slot in 0..5 {
+            let (shreds, _) = make_slot_entries(slot, slot.saturating_sub(1), 1);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+        }
+        blockstore.set_roots([0, 1, 2, 3, 4].iter()).unwrap();
+        for slot in [1, 3, 4] {
+            runtime
+                .block_on(ledger_archive.archive_slot(&blockstore, slot))
+                .unwrap();
+        }
+        // Leftovers of interrupted writes are not slots
+        std::fs::write(archive_dir.path().join("slots/0000000000000002.tmp"), []).unwrap();
+
+        assert_eq!(
+            runtime.block_on(ledger_archive.get_first_slot()).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            runtime.block_on(ledger_archive.get_slots(0, 10)).unwrap(),
+            vec![1, 3, 4]
+        );
+        assert_eq!(
+            runtime.block_on(ledger_archive.get_slots(2, 2)).unwrap(),
+            vec![3, 4]
+        );
+        assert_eq!(
+            runtime.block_on(ledger_archive.get_slots(5, 10)).unwrap(),
+            Vec::<Slot>::new()
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! The `ledger_archive_service` moves rooted slots that are older than the configured age from
+//! the blockstore to its ledger archive.
+use {
+    crate::{
+        blockstore::{Blockstore, PurgeType},
+        blockstore_meta::SlotPurgeReason,
+        ledger_archive::{LedgerArchive, Result},
+    },
+    solana_measure::measure::Measure,
+    solana_runtime::commitment::BlockCommitmentCache,
+    solana_sdk::clock::Slot,
+    std::{
+        cmp::{max, min},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, RwLock,
+        },
+        thread::{self, sleep, Builder, JoinHandle},
+        time::Duration,
+    },
+    tokio::runtime::Runtime,
+};
+
+/// Maximum number of slots archived before the archived slots are deleted locally
+const MAX_SLOTS_PER_ROUND: usize = 256;
+
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(1);
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct LedgerArchiveService {
+    thread: JoinHandle<()>,
+}
+
+impl LedgerArchiveService {
+    pub fn new(
+        ledger_archive: Arc<LedgerArchive>,
+        blockstore: Arc<Blockstore>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        max_complete_transaction_status_slot: Arc<AtomicU64>,
+        exit: Arc<AtomicBool>,
+    ) -> Self {
+        info!(
+            "Starting ledger archive service, archiving slots {} slots behind the root",
+            ledger_archive.min_age_slots()
+        );
+        let thread = Builder::new()
+            .name("solana-ledger-archive".to_string())
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                Self::run(
+                    runtime,
+                    ledger_archive,
+                    blockstore,
+                    block_commitment_cache,
+                    max_complete_transaction_status_slot,
+                    exit,
+                )
+            })
+            .unwrap();
+
+        Self { thread }
+    }
+
+    fn run(
+        runtime: Runtime,
+        ledger_archive: Arc<LedgerArchive>,
+        blockstore: Arc<Blockstore>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        max_complete_transaction_status_slot: Arc<AtomicU64>,
+        exit: Arc<AtomicBool>,
+    ) {
+        let mut last_archived_slot = None;
+        while !exit.load(Ordering::Relaxed) {
+            // Like the BigTable upload, only slots with complete transaction-status metadata
+            // can be archived
+            let highest_complete_root = min(
+                max_complete_transaction_status_slot.load(Ordering::SeqCst),
+                block_commitment_cache.read().unwrap().root(),
+            );
+            let end_slot = highest_complete_root.saturating_sub(ledger_archive.min_age_slots());
+
+            match runtime.block_on(Self::archive_slots(
+                &ledger_archive,
+                &blockstore,
+                last_archived_slot,
+                end_slot,
+                &exit,
+            )) {
+                Ok(None) => sleep(ARCHIVE_INTERVAL),
+                Ok(Some(slot)) => last_archived_slot = Some(slot),
+                Err(err) => {
+                    warn!("ledger archive: failed to archive slots: {}", err);
+                    sleep(RETRY_INTERVAL);
+                }
+            }
+        }
+    }
+
+    /// Archives up to `MAX_SLOTS_PER_ROUND` rooted slots newer than `last_archived_slot` and no
+    /// newer than `end_slot`, then deletes every slot older than the newest of them. That slot
+    /// itself is kept locally until the next round, as the block of its child can't be built
+    /// without it. Returns the newest slot archived, if any.
+    pub async fn archive_slots(
+        ledger_archive: &LedgerArchive,
+        blockstore: &Blockstore,
+        last_archived_slot: Option<Slot>,
+        end_slot: Slot,
+        exit: &AtomicBool,
+    ) -> Result<Option<Slot>> {
+        let start_slot = max(
+            blockstore.get_first_available_block()?,
+            last_archived_slot.map_or(0, |slot| slot.saturating_add(1)),
+        );
+        if start_slot > end_slot {
+            return Ok(None);
+        }
+        let slots: Vec<Slot> = blockstore
+            .rooted_slot_iterator(start_slot)?
+            .take_while(|slot| *slot <= end_slot)
+            .take(MAX_SLOTS_PER_ROUND)
+            .collect();
+
+        let mut archive_time = Measure::start("archive_time");
+        let mut num_archived = 0;
+        for slot in &slots {
+            if exit.load(Ordering::Relaxed) {
+                break;
+            }
+            ledger_archive.archive_slot(blockstore, *slot).await?;
+            num_archived += 1;
+        }
+        archive_time.stop();
+        let last_archived_slot = match num_archived {
+            0 => return Ok(None),
+            _ => slots[num_archived - 1],
+        };
+        if last_archived_slot == 0 {
+            return Ok(Some(last_archived_slot));
+        }
+
+        let mut purge_time = Measure::start("purge_time");
+        let lowest_cleanup_slot = last_archived_slot - 1;
+        {
+            // Stop serving the archived slots from the blockstore before purging them, the same
+            // way LedgerCleanupService does
+            let mut blockstore_lowest_cleanup_slot =
+                blockstore.lowest_cleanup_slot.write().unwrap();
+            *blockstore_lowest_cleanup_slot =
+                max(*blockstore_lowest_cleanup_slot, lowest_cleanup_slot);
+        }
+        blockstore.purge_slots(0, lowest_cleanup_slot, PurgeType::CompactionFilter);
+        blockstore.set_max_expired_slot(lowest_cleanup_slot);
+        if let Err(err) =
+            blockstore.record_slot_purge(0, lowest_cleanup_slot, SlotPurgeReason::Archived)
+        {
+            warn!(
+                "failed to record purge of slots up to {}: {:?}",
+                lowest_cleanup_slot, err
+            );
+        }
+        purge_time.stop();
+
+        datapoint_info!(
+            "ledger-archive",
+            ("first_slot", slots[0], i64),
+            ("last_slot", last_archived_slot, i64),
+            ("num_slots", num_archived, i64),
+            ("archive_time_us", archive_time.as_us(), i64),
+            ("purge_time_us", purge_time.as_us(), i64),
+        );
+        Ok(Some(last_archived_slot))
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            blockstore::make_many_slot_entries, blockstore_db::BlockstoreError,
+            get_tmp_ledger_path_auto_delete,
+        },
+        assert_matches::assert_matches,
+    };
+
+    #[test]
+    fn test_archive_slots() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+        let archive_dir = tempfile::TempDir::new().unwrap();
+        let ledger_archive = LedgerArchive::open(archive_dir.path(), 0);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let exit = AtomicBool::new(false);
+
+        let (shreds, _) = make_many_slot_entries(1, 10, 4);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        blockstore
+            .set_roots((1..=10).collect::<Vec<_>>().iter())
+            .unwrap();
+
+        // Slot 1 is the first root, whose block can't be complete; slots 2 to 6 are archived
+        let last_archived_slot = runtime
+            .block_on(LedgerArchiveService::archive_slots(
+                &ledger_archive,
+                &blockstore,
+                None,
+                6,
+                &exit,
+            ))
+            .unwrap();
+        assert_eq!(last_archived_slot, Some(6));
+        assert_eq!(blockstore.lowest_cleanup_slot(), 5);
+        assert_eq!(blockstore.get_first_available_block().unwrap(), 7);
+        for slot in 2..=5 {
+            assert!(blockstore.meta(slot).unwrap().is_none());
+            assert_matches!(
+                blockstore.get_rooted_block(slot, true),
+                Err(BlockstoreError::SlotCleanedUp)
+            );
+        }
+        for slot in 2..=6 {
+            assert!(runtime
+                .block_on(ledger_archive.get_block(slot))
+                .unwrap()
+                .is_some());
+        }
+        assert!(runtime
+            .block_on(ledger_archive.get_block(7))
+            .unwrap()
+            .is_none());
+
+        // Slot 6 is kept locally, but nothing is left to archive up to it
+        assert_eq!(
+            runtime
+                .block_on(LedgerArchiveService::archive_slots(
+                    &ledger_archive,
+                    &blockstore,
+                    last_archived_slot,
+                    6,
+                    &exit,
+                ))
+                .unwrap(),
+            None
+        );
+
+        // The next round starts after it, and deletes it
+        assert_eq!(
+            runtime
+                .block_on(LedgerArchiveService::archive_slots(
+                    &ledger_archive,
+                    &blockstore,
+                    last_archived_slot,
+                    8,
+                    &exit,
+                ))
+                .unwrap(),
+            Some(8)
+        );
+        assert_eq!(blockstore.lowest_cleanup_slot(), 7);
+        assert_eq!(blockstore.get_first_available_block().unwrap(), 9);
+        assert!(runtime
+            .block_on(ledger_archive.get_block(7))
+            .unwrap()
+            .is_some());
+    }
+}
@@ -12,6 +12,7 @@ pub mod block_error;
 pub mod blockstore;
 pub mod ancestor_iterator;
 pub mod blockstore_db;
+pub mod blockstore_lock;
 pub mod blockstore_meta;
 #[macro_use]
 pub mod blockstore_metrics;
@@ -22,6 +23,8 @@ pub mod genesis_utils;
 pub mod leader_schedule;
 pub mod leader_schedule_cache;
 pub mod leader_schedule_utils;
+pub mod ledger_archive;
+pub mod ledger_archive_service;
 pub mod next_slots_iterator;
 pub mod rooted_slot_iterator;
 pub mod shred;
@@ -163,13 +163,33 @@ impl Shredder {
         is_last_in_slot: bool,
         next_code_index: u32,
         process_stats: &mut ProcessShredsStats,
+    ) -> Result<Vec<Shred>, Error> {
+        Self::data_shreds_to_coding_shreds_with_thread_pool(
+            &PAR_THREAD_POOL,
+            keypair,
+            data_shreds,
+            is_last_in_slot,
+            next_code_index,
+            process_stats,
+        )
+    }
+
+    /// Same as `data_shreds_to_coding_shreds`, running the erasure coding on `thread_pool`
+    /// rather than the shared shredder thread pool
+    pub fn data_shreds_to_coding_shreds_with_thread_pool(
+        thread_pool: &ThreadPool,
+        keypair: &Keypair,
+        data_shreds: &[Shred],
+        is_last_in_slot: bool,
+        next_code_index: u32,
+        process_stats: &mut ProcessShredsStats,
     ) -> Result<Vec<Shred>, Error> {
         if data_shreds.is_empty() {
             return Ok(Vec::default());
         }
         let mut gen_coding_time = Measure::start("gen_coding_shreds");
         // 1) Generate coding shreds
-        let mut coding_shreds: Vec<_> = PAR_THREAD_POOL.install(|| {
+        let mut coding_shreds: Vec<_> = thread_pool.install(|| {
             data_shreds
                 .par_chunks(MAX_DATA_SHREDS_PER_FEC_BLOCK as usize)
                 .enumerate()
@@ -199,7 +219,7 @@ impl Shredder {
 
         let mut sign_coding_time = Measure::start("sign_coding_shreds");
         // 2) Sign coding shreds
-        PAR_THREAD_POOL.install(|| {
+        thread_pool.install(|| {
             coding_shreds.par_iter_mut().for_each(|coding_shred| {
                 coding_shred.sign(keypair);
             })
@@ -211,6 +231,17 @@ impl Shredder {
         Ok(coding_shreds)
     }
 
+    /// Returns the number of coding shreds generated for an FEC set of `num_data` data shreds
+    pub fn num_coding_shreds(num_data: usize, is_last_in_slot: bool) -> usize {
+        if is_last_in_slot {
+            (2 * MAX_DATA_SHREDS_PER_FEC_BLOCK as usize)
+                .saturating_sub(num_data)
+                .max(num_data)
+        } else {
+            num_data
+        }
+    }
+
     /// Generates coding shreds for the data shreds in the current FEC set
     pub fn generate_coding_shreds(
         data: &[Shred],
@@ -231,13 +262,7 @@ impl Shredder {
             && shred.version() == version
             && shred.fec_set_index() == fec_set_index));
         let num_data = data.len();
-        let num_coding = if is_last_in_slot {
-            (2 * MAX_DATA_SHREDS_PER_FEC_BLOCK as usize)
-                .saturating_sub(num_data)
-                .max(num_data)
-        } else {
-            num_data
-        };
+        let num_coding = Self::num_coding_shreds(num_data, is_last_in_slot);
         let data = data.iter().map(Shred::erasure_shard_as_slice);
         let data: Vec<_> = data.collect::<Result<_, _>>().unwrap();
         let mut parity = vec![vec![0u8; data[0].len()]; num_coding];
@@ -15,11 +15,15 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         account_shrink_paths: config.account_shrink_paths.clone(),
         rpc_config: config.rpc_config.clone(),
         geyser_plugin_config_files: config.geyser_plugin_config_files.clone(),
+        ingest_plugin_config_files: config.ingest_plugin_config_files.clone(),
         rpc_addrs: config.rpc_addrs,
         pubsub_config: config.pubsub_config.clone(),
         snapshot_config: config.snapshot_config.clone(),
         max_ledger_shreds: config.max_ledger_shreds,
+        max_ledger_bytes: config.max_ledger_bytes,
+        ledger_purge_tombstones: config.ledger_purge_tombstones,
         broadcast_stage_type: config.broadcast_stage_type.clone(),
+        broadcast_config: config.broadcast_config.clone(),
         turbine_disabled: config.turbine_disabled.clone(),
         enforce_ulimit_nofile: config.enforce_ulimit_nofile,
         fixed_leader_schedule: config.fixed_leader_schedule.clone(),
@@ -30,6 +34,7 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         gossip_validators: config.gossip_validators.clone(),
         halt_on_known_validators_accounts_hash_mismatch: config
             .halt_on_known_validators_accounts_hash_mismatch,
+        accounts_hash_verification_policy: config.accounts_hash_verification_policy.clone(),
         accounts_hash_fault_injection_slots: config.accounts_hash_fault_injection_slots,
         no_rocksdb_compaction: config.no_rocksdb_compaction,
         rocksdb_compaction_interval: config.rocksdb_compaction_interval,
@@ -45,6 +50,7 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         contact_save_interval: config.contact_save_interval,
         send_transaction_service_config: config.send_transaction_service_config.clone(),
         no_poh_speed_test: config.no_poh_speed_test,
+        poh_speed_test_threads: config.poh_speed_test_threads,
         no_os_memory_stats_reporting: config.no_os_memory_stats_reporting,
         no_os_network_stats_reporting: config.no_os_network_stats_reporting,
         poh_pinned_cpu_core: config.poh_pinned_cpu_core,
@@ -61,7 +67,23 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         accounts_db_config: config.accounts_db_config.clone(),
         wait_to_vote_slot: config.wait_to_vote_slot,
         ledger_column_options: config.ledger_column_options.clone(),
+        rocksdb_tuning_options: config.rocksdb_tuning_options.clone(),
+        rocksdb_io_options: config.rocksdb_io_options,
+        blockstore_verify_on_open: config.blockstore_verify_on_open,
+        blockstore_column_layout: config.blockstore_column_layout.clone(),
+        ledger_archive_config: config.ledger_archive_config.clone(),
         runtime_config: config.runtime_config.clone(),
+        slot_purge_listeners: config.slot_purge_listeners.clone(),
+        banking_account_denylist: config.banking_account_denylist.clone(),
+        banking_source_packets_per_second: config.banking_source_packets_per_second,
+        banking_max_packets_per_lock_set: config.banking_max_packets_per_lock_set,
+        banking_notify_dropped_transactions: config.banking_notify_dropped_transactions,
+        banking_persist_buffered_packets: config.banking_persist_buffered_packets,
+        banking_forward_pacing_config: config.banking_forward_pacing_config.clone(),
+        banking_priority_aging_config: config.banking_priority_aging_config.clone(),
+        banking_pop_policy_config: config.banking_pop_policy_config.clone(),
+        serve_repair_admission_config: config.serve_repair_admission_config.clone(),
+        shutdown_config: config.shutdown_config.clone(),
     }
 }
 
@@ -0,0 +1,426 @@
+//! The `block_time_backfill_service` estimates the block times missing from the ledger, so
+//! `getBlockTime` can answer for old slots whose block time was never recorded. Slots are dated
+//! with the median timestamp of the votes on them that landed in the following blocks, and slots
+//! without timestamped votes are interpolated between the closest known block times.
+use {
+    crate::ledger_storage_backend::{LedgerStorageBackend, LedgerStorageError},
+    solana_ledger::blockstore::Blockstore,
+    solana_runtime::vote_parser,
+    solana_sdk::clock::{Slot, UnixTimestamp},
+    solana_transaction_status::{ConfirmedBlock, TransactionWithStatusMeta},
+    std::{
+        collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex, RwLock,
+        },
+        thread::{self, Builder, JoinHandle},
+        time::Duration,
+    },
+    tokio::runtime::Runtime,
+};
+
+/// Votes on a slot are looked for in the blocks of up to this many slots after it
+pub const MAX_VOTE_TIMESTAMP_DELAY_SLOTS: Slot = 32;
+
+/// Slots of long-term storage waiting to be estimated are dropped beyond this many
+const MAX_PENDING_STORAGE_SLOTS: usize = 1_024;
+
+const BLOCK_TIME_BACKFILL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Block times estimated for the slots whose block time is missing from the ledger
+#[derive(Debug, Default)]
+pub struct EstimatedBlockTimes {
+    block_times: RwLock<BTreeMap<Slot, UnixTimestamp>>,
+    /// Slots served from long-term storage without a block time, to be estimated next
+    pending_storage_slots: Mutex<BTreeSet<Slot>>,
+}
+
+impl EstimatedBlockTimes {
+    pub fn get(&self, slot: Slot) -> Option<UnixTimestamp> {
+        self.block_times.read().unwrap().get(&slot).copied()
+    }
+
+    /// Asks for the block time of `slot`, which long-term storage does not have, to be estimated
+    pub fn request_storage_estimate(&self, slot: Slot) {
+        let mut pending_storage_slots = self.pending_storage_slots.lock().unwrap();
+        if pending_storage_slots.len() < MAX_PENDING_STORAGE_SLOTS {
+            pending_storage_slots.insert(slot);
+        }
+    }
+
+    fn take_pending_storage_slots(&self) -> BTreeSet<Slot> {
+        std::mem::take(&mut *self.pending_storage_slots.lock().unwrap())
+    }
+
+    pub(crate) fn insert(&self, block_times: Vec<(Slot, UnixTimestamp)>) {
+        if !block_times.is_empty() {
+            self.block_times.write().unwrap().extend(block_times);
+        }
+    }
+}
+
+/// Estimates the missing block times of consecutive blocks, which are added in slot order
+#[derive(Debug, Default)]
+struct BlockTimeEstimator {
+    /// Blocks whose votes may still land, with their block time if it is known
+    window: VecDeque<(Slot, Option<UnixTimestamp>)>,
+    /// Timestamps of the votes on the blocks of the window that miss a block time
+    vote_timestamps: HashMap<Slot, Vec<UnixTimestamp>>,
+    /// Slots without a block time nor timestamped votes, waiting for the next known block time
+    unresolved_slots: Vec<Slot>,
+    last_known_block_time: Option<(Slot, UnixTimestamp)>,
+}
+
+impl BlockTimeEstimator {
+    /// Whether a block has to be read to estimate the block times of the window
+    fn needs_votes(&self) -> bool {
+        !self.vote_timestamps.is_empty()
+    }
+
+    /// Adds a block whose block time is known and whose votes are not needed. Returns the block
+    /// times estimated as a result.
+    fn add_block_time(
+        &mut self,
+        slot: Slot,
+        block_time: UnixTimestamp,
+    ) -> Vec<(Slot, UnixTimestamp)> {
+        self.window.push_back((slot, Some(block_time)));
+        self.resolve_window(slot)
+    }
+
+    /// Adds a block, collecting the timestamps of the votes it includes. Returns the block times
+    /// estimated as a result.
+    fn add_block(&mut self, slot: Slot, block: &ConfirmedBlock) -> Vec<(Slot, UnixTimestamp)> {
+        for transaction in &block.transactions {
+            if let TransactionWithStatusMeta::Complete(transaction) = transaction {
+                if transaction.meta.status.is_err() {
+                    continue;
+                }
+            }
+            let vote = transaction
+                .get_transaction()
+                .into_legacy_transaction()
+                .and_then(|transaction| vote_parser::parse_vote_transaction(&transaction));
+            if let Some((_vote_pubkey, vote, _switch_proof_hash, _signature)) = vote {
+                if let (Some(voted_slot), Some(timestamp)) =
+                    (vote.last_voted_slot(), vote.timestamp())
+                {
+                    if let Some(vote_timestamps) = self.vote_timestamps.get_mut(&voted_slot) {
+                        vote_timestamps.push(timestamp);
+                    }
+                }
+            }
+        }
+        if block.block_time.is_none() {
+            self.vote_timestamps.insert(slot, vec![]);
+        }
+        self.window.push_back((slot, block.block_time));
+        self.resolve_window(slot)
+    }
+
+    /// Estimates the block times that can be, as no more blocks follow
+    fn finish(&mut self) -> Vec<(Slot, UnixTimestamp)> {
+        self.resolve_window(Slot::MAX)
+    }
+
+    fn resolve_window(&mut self, highest_slot: Slot) -> Vec<(Slot, UnixTimestamp)> {
+        let mut estimates = vec![];
+        while let Some((slot, block_time)) = self.window.front().copied() {
+            if slot.saturating_add(MAX_VOTE_TIMESTAMP_DELAY_SLOTS) > highest_slot {
+                break;
+            }
+            self.window.pop_front();
+            let block_time = match block_time {
+                Some(block_time) => block_time,
+                None => {
+                    let vote_timestamps = self.vote_timestamps.remove(&slot).unwrap_or_default();
+                    match median(vote_timestamps) {
+                        Some(block_time) => {
+                            estimates.push((slot, block_time));
+                            block_time
+                        }
+                        None => {
+                            self.unresolved_slots.push(slot);
+                            continue;
+                        }
+                    }
+                }
+            };
+            if let Some(last_known_block_time) = self.last_known_block_time {
+                estimates.extend(self.unresolved_slots.drain(..).map(|unresolved_slot| {
+                    (
+                        unresolved_slot,
+                        interpolate(last_known_block_time, (slot, block_time), unresolved_slot),
+                    )
+                }));
+            } else {
+                // Slots before the first known block time cannot be estimated
+                self.unresolved_slots.clear();
+            }
+            self.last_known_block_time = Some((slot, block_time));
+        }
+        estimates.sort_unstable();
+        estimates
+    }
+}
+
+fn median(mut timestamps: Vec<UnixTimestamp>) -> Option<UnixTimestamp> {
+    timestamps.sort_unstable();
+    timestamps.get(timestamps.len() / 2).copied()
+}
+
+fn interpolate(
+    (low_slot, low_block_time): (Slot, UnixTimestamp),
+    (high_slot, high_block_time): (Slot, UnixTimestamp),
+    slot: Slot,
+) -> UnixTimestamp {
+    let elapsed_slots = slot.saturating_sub(low_slot) as i128;
+    let total_slots = high_slot.saturating_sub(low_slot).max(1) as i128;
+    let total_time = i128::from(high_block_time) - i128::from(low_block_time);
+    low_block_time + (total_time * elapsed_slots / total_slots) as UnixTimestamp
+}
+
+pub struct BlockTimeBackfillService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl BlockTimeBackfillService {
+    pub fn new(
+        blockstore: Arc<Blockstore>,
+        ledger_storage_backend: Option<Arc<dyn LedgerStorageBackend>>,
+        runtime: Arc<Runtime>,
+        estimated_block_times: Arc<EstimatedBlockTimes>,
+        exit: Arc<AtomicBool>,
+    ) -> Self {
+        info!("Starting BlockTimeBackfillService");
+        let thread_hdl = Builder::new()
+            .name("solana-block-time-backfill".to_string())
+            .spawn(move || {
+                let mut estimator = BlockTimeEstimator::default();
+                let mut next_slot = None;
+                while !exit.load(Ordering::Relaxed) {
+                    next_slot = Some(Self::backfill_blockstore(
+                        &blockstore,
+                        &estimated_block_times,
+                        &mut estimator,
+                        next_slot,
+                        &exit,
+                    ));
+                    if let Some(ledger_storage_backend) = &ledger_storage_backend {
+                        Self::backfill_storage_slots(
+                            ledger_storage_backend.as_ref(),
+                            &runtime,
+                            &estimated_block_times,
+                        );
+                    }
+                    thread::sleep(BLOCK_TIME_BACKFILL_INTERVAL);
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    /// Walks the roots of the blockstore from `next_slot`, or from the first available block,
+    /// to the highest root. Returns the slot to resume from.
+    fn backfill_blockstore(
+        blockstore: &Blockstore,
+        estimated_block_times: &EstimatedBlockTimes,
+        estimator: &mut BlockTimeEstimator,
+        next_slot: Option<Slot>,
+        exit: &AtomicBool,
+    ) -> Slot {
+        let first_available_block = blockstore.get_first_available_block().unwrap_or_default();
+        let mut next_slot = match next_slot {
+            Some(next_slot) if next_slot >= first_available_block => next_slot,
+            // The blockstore was cleaned up past the slots being estimated
+            _ => {
+                *estimator = BlockTimeEstimator::default();
+                first_available_block
+            }
+        };
+        let max_root = blockstore.max_root();
+        let rooted_slots = match blockstore.rooted_slot_iterator(next_slot) {
+            Ok(rooted_slots) => rooted_slots,
+            Err(err) => {
+                warn!(
+                    "Failed to iterate rooted slots from {}: {:?}",
+                    next_slot, err
+                );
+                return next_slot;
+            }
+        };
+        for slot in rooted_slots.take_while(|slot| *slot <= max_root) {
+            if exit.load(Ordering::Relaxed) {
+                break;
+            }
+            let block_time = blockstore.get_block_time(slot).ok().flatten();
+            let estimates = match block_time {
+                Some(block_time) if !estimator.needs_votes() => {
+                    estimator.add_block_time(slot, block_time)
+                }
+                _ => match blockstore.get_rooted_block(slot, false) {
+                    Ok(block) => estimator.add_block(slot, &ConfirmedBlock::from(block)),
+                    Err(err) => {
+                        debug!("Failed to read block {}: {:?}", slot, err);
+                        vec![]
+                    }
+                },
+            };
+            estimated_block_times.insert(estimates);
+            next_slot = slot + 1;
+        }
+        next_slot
+    }
+
+    /// Estimates the block times of the slots requested from long-term storage, from the blocks
+    /// around each of them
+    fn backfill_storage_slots(
+        ledger_storage_backend: &dyn LedgerStorageBackend,
+        runtime: &Runtime,
+        estimated_block_times: &EstimatedBlockTimes,
+    ) {
+        for slot in estimated_block_times.take_pending_storage_slots() {
+            if estimated_block_times.get(slot).is_some() {
+                continue;
+            }
+            let start_slot = slot.saturating_sub(MAX_VOTE_TIMESTAMP_DELAY_SLOTS);
+            let limit = 3 * MAX_VOTE_TIMESTAMP_DELAY_SLOTS as usize;
+            let estimates = runtime.block_on(async {
+                let mut estimator = BlockTimeEstimator::default();
+                let mut estimates = vec![];
+                for block_slot in ledger_storage_backend
+                    .get_confirmed_blocks(start_slot, limit)
+                    .await?
+                {
+                    let block = ledger_storage_backend
+                        .get_confirmed_block(block_slot)
+                        .await?;
+                    estimates.extend(estimator.add_block(block_slot, &block));
+                }
+                estimates.extend(estimator.finish());
+                Ok::<_, LedgerStorageError>(estimates)
+            });
+            match estimates {
+                Ok(estimates) => estimated_block_times.insert(estimates),
+                Err(err) => warn!(
+                    "Failed to estimate the block time of slot {} from {}: {:?}",
+                    slot,
+                    ledger_storage_backend.name(),
+                    err
+                ),
+            }
+        }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            hash::Hash,
+            signature::{Keypair, Signer},
+            transaction::Transaction,
+        },
+        solana_vote_program::{vote_instruction, vote_state::Vote},
+    };
+
+    fn vote_transaction(voted_slot: Slot, timestamp: UnixTimestamp) -> TransactionWithStatusMeta {
+        let keypair = Keypair::new();
+        let mut vote = Vote::new(vec![voted_slot], Hash::default());
+        vote.timestamp = Some(timestamp);
+        let instruction = vote_instruction::vote(&keypair.pubkey(), &keypair.pubkey(), vote);
+        TransactionWithStatusMeta::MissingMetadata(Transaction::new_with_payer(
+            &[instruction],
+            Some(&keypair.pubkey()),
+        ))
+    }
+
+    fn block(
+        block_time: Option<UnixTimestamp>,
+        transactions: Vec<TransactionWithStatusMeta>,
+    ) -> ConfirmedBlock {
+        ConfirmedBlock {
+            previous_blockhash: String::default(),
+            blockhash: String::default(),
+            parent_slot: 0,
+            transactions,
+            rewards: vec![],
+            block_time,
+            block_height: None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(interpolate((10, 100), (20, 110), 10), 100);
+        assert_eq!(interpolate((10, 100), (20, 110), 15), 105);
+        assert_eq!(interpolate((10, 100), (20, 110), 19), 109);
+        assert_eq!(interpolate((10, 100), (13, 100), 12), 100);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![5]), Some(5));
+        assert_eq!(median(vec![9, 1, 5]), Some(5));
+    }
+
+    #[test]
+    fn test_block_time_estimator() {
+        let mut estimator = BlockTimeEstimator::default();
+        let mut estimates = vec![];
+        estimates.extend(estimator.add_block_time(1, 1_000));
+        assert!(!estimator.needs_votes());
+        // Slot 2 is dated by the votes on it, slot 3 has none
+        estimates.extend(estimator.add_block(2, &block(None, vec![])));
+        assert!(estimator.needs_votes());
+        estimates.extend(estimator.add_block(3, &block(None, vec![])));
+        estimates.extend(estimator.add_block(
+            4,
+            &block(
+                None,
+                vec![
+                    vote_transaction(2, 1_010),
+                    vote_transaction(2, 1_012),
+                    vote_transaction(2, 1_500),
+                ],
+            ),
+        ));
+        estimates.extend(estimator.add_block(5, &block(Some(1_020), vec![])));
+        // Nothing is resolved until the votes on the blocks had time to land
+        assert!(estimates.is_empty());
+
+        let highest_slot = 5 + MAX_VOTE_TIMESTAMP_DELAY_SLOTS;
+        for slot in 6..=highest_slot {
+            estimates.extend(estimator.add_block_time(slot, 1_020 + slot as UnixTimestamp));
+        }
+        estimates.extend(estimator.finish());
+        assert_eq!(estimates, vec![(2, 1_012), (3, 1_014), (4, 1_017)]);
+        assert!(!estimator.needs_votes());
+    }
+
+    #[test]
+    fn test_estimated_block_times() {
+        let estimated_block_times = EstimatedBlockTimes::default();
+        assert_eq!(estimated_block_times.get(1), None);
+        estimated_block_times.insert(vec![(1, 100), (2, 101)]);
+        assert_eq!(estimated_block_times.get(2), Some(101));
+
+        for slot in 0..MAX_PENDING_STORAGE_SLOTS as Slot + 10 {
+            estimated_block_times.request_storage_estimate(slot);
+        }
+        assert_eq!(
+            estimated_block_times.take_pending_storage_slots().len(),
+            MAX_PENDING_STORAGE_SLOTS
+        );
+        assert!(estimated_block_times
+            .take_pending_storage_slots()
+            .is_empty());
+    }
+}
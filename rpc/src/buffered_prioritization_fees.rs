@@ -0,0 +1,113 @@
+//! The `buffered_prioritization_fees` module gathers the compute unit prices offered by the
+//! packets held in the banking stage buffers of the node, so clients can gauge how congested
+//! the queue of the leader is in real time rather than only from the fees of landed
+//! transactions.
+use {
+    solana_client::rpc_response::RpcBufferedPrioritizationFees,
+    std::{
+        collections::HashMap,
+        sync::RwLock,
+        time::{Duration, Instant},
+    },
+};
+
+/// Fees published longer ago than this are ignored, as the thread that published them has
+/// stopped updating them
+pub const MAX_BUFFERED_FEES_AGE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+pub struct BufferedPrioritizationFees {
+    /// Latest fees published by each banking thread, by thread id
+    threads: RwLock<HashMap<u32, (Instant, RpcBufferedPrioritizationFees)>>,
+}
+
+impl BufferedPrioritizationFees {
+    /// Replaces the fees published by banking thread `id`
+    pub fn publish(&self, id: u32, fees: RpcBufferedPrioritizationFees) {
+        self.threads
+            .write()
+            .unwrap()
+            .insert(id, (Instant::now(), fees));
+    }
+
+    /// Combines the fees published by every banking thread. The median and 90th percentile
+    /// prices are approximated by weighting the ones of each thread by the number of packets
+    /// it buffers.
+    pub fn get(&self) -> RpcBufferedPrioritizationFees {
+        let threads = self.threads.read().unwrap();
+        let fees: Vec<_> = threads
+            .values()
+            .filter(|(published, fees)| {
+                published.elapsed() <= MAX_BUFFERED_FEES_AGE && fees.num_packets > 0
+            })
+            .map(|(_, fees)| fees)
+            .collect();
+        let num_packets: usize = fees.iter().map(|fees| fees.num_packets).sum();
+        if num_packets == 0 {
+            return RpcBufferedPrioritizationFees::default();
+        }
+        let weighted_mean = |price: fn(&RpcBufferedPrioritizationFees) -> u64| {
+            let weighted_sum: u128 = fees
+                .iter()
+                .map(|fees| u128::from(price(fees)) * fees.num_packets as u128)
+                .sum();
+            (weighted_sum / num_packets as u128) as u64
+        };
+        RpcBufferedPrioritizationFees {
+            num_packets,
+            min_compute_unit_price: fees
+                .iter()
+                .map(|fees| fees.min_compute_unit_price)
+                .min()
+                .unwrap_or_default(),
+            median_compute_unit_price: weighted_mean(|fees| fees.median_compute_unit_price),
+            p90_compute_unit_price: weighted_mean(|fees| fees.p90_compute_unit_price),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(num_packets: usize, min: u64, median: u64, p90: u64) -> RpcBufferedPrioritizationFees {
+        RpcBufferedPrioritizationFees {
+            num_packets,
+            min_compute_unit_price: min,
+            median_compute_unit_price: median,
+            p90_compute_unit_price: p90,
+        }
+    }
+
+    #[test]
+    fn test_buffered_prioritization_fees() {
+        let buffered_prioritization_fees = BufferedPrioritizationFees::default();
+        assert_eq!(
+            buffered_prioritization_fees.get(),
+            RpcBufferedPrioritizationFees::default()
+        );
+
+        buffered_prioritization_fees.publish(2, fees(30, 10, 100, 1_000));
+        buffered_prioritization_fees.publish(3, fees(10, 5, 500, 2_000));
+        buffered_prioritization_fees.publish(4, fees(0, 0, 0, 0));
+        assert_eq!(buffered_prioritization_fees.get(), fees(40, 5, 200, 1_250));
+
+        // a thread replaces its own fees
+        buffered_prioritization_fees.publish(3, fees(0, 0, 0, 0));
+        assert_eq!(buffered_prioritization_fees.get(), fees(30, 10, 100, 1_000));
+
+        // stale fees are ignored
+        buffered_prioritization_fees
+            .threads
+            .write()
+            .unwrap()
+            .insert(
+                5,
+                (
+                    Instant::now() - MAX_BUFFERED_FEES_AGE - Duration::from_secs(1),
+                    fees(30, 1, 1, 1),
+                ),
+            );
+        assert_eq!(buffered_prioritization_fees.get(), fees(30, 10, 100, 1_000));
+    }
+}
@@ -0,0 +1,242 @@
+//! The `ledger_storage_backend` module abstracts the long-term storage RPC falls back to for
+//! blocks and transactions that are no longer in the local blockstore. Backends are provided for
+//! BigTable and for the ledger archive, which keeps slots in a local directory or in an
+//! S3-compatible object store.
+use {
+    crate::object_store::ObjectStore,
+    async_trait::async_trait,
+    solana_ledger::ledger_archive::{
+        ArchiveStorage, FilesystemStorage, LedgerArchive, LedgerArchiveConfig, LedgerArchiveError,
+    },
+    solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature},
+    solana_transaction_status::{
+        ConfirmedBlock, ConfirmedTransactionStatusWithSignature,
+        ConfirmedTransactionWithStatusMeta, TransactionStatus,
+    },
+    std::path::PathBuf,
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum LedgerStorageError {
+    #[error("Block not found: {0}")]
+    BlockNotFound(Slot),
+
+    #[error("Signature not found")]
+    SignatureNotFound,
+
+    #[error("{0}")]
+    BigTable(solana_storage_bigtable::Error),
+
+    #[error("Ledger archive: {0}")]
+    LedgerArchive(#[from] LedgerArchiveError),
+}
+
+impl From<solana_storage_bigtable::Error> for LedgerStorageError {
+    fn from(err: solana_storage_bigtable::Error) -> Self {
+        match err {
+            solana_storage_bigtable::Error::BlockNotFound(slot) => Self::BlockNotFound(slot),
+            solana_storage_bigtable::Error::SignatureNotFound => Self::SignatureNotFound,
+            err => Self::BigTable(err),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, LedgerStorageError>;
+
+/// Long-term storage of the ledger history
+#[async_trait]
+pub trait LedgerStorageBackend: Send + Sync {
+    /// Name of the backend, for logs and errors
+    fn name(&self) -> &'static str;
+
+    /// Returns the lowest slot in storage
+    async fn get_first_available_block(&self) -> Result<Option<Slot>>;
+
+    /// Returns up to `limit` confirmed slots, in ascending order, starting at `start_slot`
+    async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>>;
+
+    /// Fetches the block of confirmed `slot`
+    async fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock>;
+
+    /// Returns the status of the transaction with `signature`
+    async fn get_signature_status(&self, signature: &Signature) -> Result<TransactionStatus>;
+
+    /// Fetches the confirmed transaction with `signature`
+    async fn get_confirmed_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<ConfirmedTransactionWithStatusMeta>>;
+
+    /// Returns up to `limit` signatures of transactions involving `address`, newest first,
+    /// starting before `before_signature` and ending at `until_signature`, along with the
+    /// transactions' index in their block
+    async fn get_confirmed_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before_signature: Option<&Signature>,
+        until_signature: Option<&Signature>,
+        limit: usize,
+    ) -> Result<Vec<(ConfirmedTransactionStatusWithSignature, u32)>>;
+}
+
+#[async_trait]
+impl LedgerStorageBackend for solana_storage_bigtable::LedgerStorage {
+    fn name(&self) -> &'static str {
+        "BigTable"
+    }
+
+    async fn get_first_available_block(&self) -> Result<Option<Slot>> {
+        Ok(self.get_first_available_block().await?)
+    }
+
+    async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
+        Ok(self.get_confirmed_blocks(start_slot, limit).await?)
+    }
+
+    async fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
+        Ok(self.get_confirmed_block(slot).await?)
+    }
+
+    async fn get_signature_status(&self, signature: &Signature) -> Result<TransactionStatus> {
+        Ok(self.get_signature_status(signature).await?)
+    }
+
+    async fn get_confirmed_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<ConfirmedTransactionWithStatusMeta>> {
+        Ok(self.get_confirmed_transaction(signature).await?)
+    }
+
+    async fn get_confirmed_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before_signature: Option<&Signature>,
+        until_signature: Option<&Signature>,
+        limit: usize,
+    ) -> Result<Vec<(ConfirmedTransactionStatusWithSignature, u32)>> {
+        Ok(self
+            .get_confirmed_signatures_for_address(address, before_signature, until_signature, limit)
+            .await?)
+    }
+}
+
+/// Opens the ledger archive of `config`, kept in an object store for `s3://` and `gs://` urls,
+/// and in a local directory otherwise
+pub fn open_ledger_archive(
+    config: &LedgerArchiveConfig,
+) -> std::result::Result<LedgerArchive, LedgerArchiveError> {
+    let storage: Box<dyn ArchiveStorage> = match config.url.split_once("://") {
+        None => Box::new(FilesystemStorage::new(PathBuf::from(&config.url))),
+        Some(("file", path)) => Box::new(FilesystemStorage::new(PathBuf::from(path))),
+        Some(_) => Box::new(ObjectStore::from_config(config)?),
+    };
+    Ok(LedgerArchive::new_with_storage(
+        storage,
+        config.min_age_slots,
+    ))
+}
+
+/// The ledger archive stores blocks by slot only. Without a signature index, transactions are
+/// never found in it.
+#[async_trait]
+impl LedgerStorageBackend for LedgerArchive {
+    fn name(&self) -> &'static str {
+        "Ledger archive"
+    }
+
+    async fn get_first_available_block(&self) -> Result<Option<Slot>> {
+        Ok(self.get_first_slot().await?)
+    }
+
+    async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
+        Ok(self.get_slots(start_slot, limit).await?)
+    }
+
+    async fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
+        self.get_block(slot)
+            .await?
+            .ok_or(LedgerStorageError::BlockNotFound(slot))
+    }
+
+    async fn get_signature_status(&self, _signature: &Signature) -> Result<TransactionStatus> {
+        Err(LedgerStorageError::SignatureNotFound)
+    }
+
+    async fn get_confirmed_transaction(
+        &self,
+        _signature: &Signature,
+    ) -> Result<Option<ConfirmedTransactionWithStatusMeta>> {
+        Ok(None)
+    }
+
+    async fn get_confirmed_signatures_for_address(
+        &self,
+        _address: &Pubkey,
+        _before_signature: Option<&Signature>,
+        _until_signature: Option<&Signature>,
+        _limit: usize,
+    ) -> Result<Vec<(ConfirmedTransactionStatusWithSignature, u32)>> {
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_ledger::get_tmp_ledger_path_auto_delete};
+
+    #[test]
+    fn test_bigtable_error_conversion() {
+        assert!(matches!(
+            LedgerStorageError::from(solana_storage_bigtable::Error::BlockNotFound(42)),
+            LedgerStorageError::BlockNotFound(42)
+        ));
+        assert!(matches!(
+            LedgerStorageError::from(solana_storage_bigtable::Error::SignatureNotFound),
+            LedgerStorageError::SignatureNotFound
+        ));
+        assert!(matches!(
+            LedgerStorageError::from(
+                solana_storage_bigtable::Error::UnsupportedTransactionEncoding
+            ),
+            LedgerStorageError::BigTable(_)
+        ));
+    }
+
+    #[test]
+    fn test_ledger_archive_backend() {
+        let archive_dir = get_tmp_ledger_path_auto_delete!();
+        let backend: Box<dyn LedgerStorageBackend> = Box::new(
+            open_ledger_archive(&LedgerArchiveConfig {
+                url: format!("file://{}", archive_dir.path().display()),
+                ..LedgerArchiveConfig::default()
+            })
+            .unwrap(),
+        );
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        assert_eq!(
+            runtime
+                .block_on(backend.get_first_available_block())
+                .unwrap(),
+            None
+        );
+        assert!(runtime
+            .block_on(backend.get_confirmed_blocks(0, 10))
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            runtime.block_on(backend.get_confirmed_block(42)),
+            Err(LedgerStorageError::BlockNotFound(42))
+        ));
+        assert!(matches!(
+            runtime.block_on(backend.get_signature_status(&Signature::default())),
+            Err(LedgerStorageError::SignatureNotFound)
+        ));
+        assert!(runtime
+            .block_on(backend.get_confirmed_transaction(&Signature::default()))
+            .unwrap()
+            .is_none());
+    }
+}
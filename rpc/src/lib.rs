@@ -1,9 +1,14 @@
 #![allow(clippy::integer_arithmetic)]
+pub mod block_time_backfill_service;
+pub mod buffered_prioritization_fees;
 mod cluster_tpu_info;
+pub mod ledger_storage_backend;
 pub mod max_slots;
+pub mod object_store;
 pub mod optimistically_confirmed_bank_tracker;
 pub mod parsed_token_accounts;
 pub mod rpc;
+pub mod rpc_auth;
 pub mod rpc_completed_slots_service;
 pub mod rpc_health;
 pub mod rpc_pubsub;
@@ -11,8 +16,12 @@ pub mod rpc_pubsub_service;
 pub mod rpc_service;
 pub mod rpc_subscription_tracker;
 pub mod rpc_subscriptions;
+pub mod rpc_upstreams;
+pub mod slot_time_oracle;
+pub mod stake_snapshot_service;
 pub mod transaction_notifier_interface;
 pub mod transaction_status_service;
+pub mod vote_latency_service;
 
 #[macro_use]
 extern crate log;
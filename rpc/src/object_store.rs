@@ -0,0 +1,454 @@
+//! The `object_store` module keeps the ledger archive in an S3-compatible object store, such as
+//! S3 itself or GCS with HMAC keys, addressed path-style and authenticated with AWS Signature
+//! Version 4.
+use {
+    async_trait::async_trait,
+    chrono::Utc,
+    hmac::{Hmac, Mac},
+    quick_xml::{events::Event, Reader},
+    sha2::{Digest, Sha256},
+    solana_ledger::ledger_archive::{
+        ArchiveStorage, LedgerArchiveConfig, LedgerArchiveError, Result,
+    },
+};
+
+/// Environment variables holding the HMAC credentials of the object store
+pub const LEDGER_ARCHIVE_ACCESS_KEY_ID_ENV: &str = "AWS_ACCESS_KEY_ID";
+pub const LEDGER_ARCHIVE_SECRET_ACCESS_KEY_ENV: &str = "AWS_SECRET_ACCESS_KEY";
+
+const DEFAULT_S3_REGION: &str = "us-east-1";
+const GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+const GCS_REGION: &str = "auto";
+/// Most keys a ListObjectsV2 request returns
+const MAX_LIST_KEYS: usize = 1000;
+
+#[derive(Clone)]
+pub struct ObjectStoreCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl ObjectStoreCredentials {
+    pub fn from_env() -> Result<Self> {
+        let var =
+            |name| std::env::var(name).map_err(|_| LedgerArchiveError::MissingCredentials(name));
+        Ok(Self {
+            access_key_id: var(LEDGER_ARCHIVE_ACCESS_KEY_ID_ENV)?,
+            secret_access_key: var(LEDGER_ARCHIVE_SECRET_ACCESS_KEY_ENV)?,
+        })
+    }
+}
+
+impl std::fmt::Debug for ObjectStoreCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObjectStoreCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct ObjectStore {
+    client: reqwest::Client,
+    endpoint: String,
+    host: String,
+    region: String,
+    bucket: String,
+    prefix: String,
+    credentials: ObjectStoreCredentials,
+}
+
+impl ObjectStore {
+    pub fn new(
+        endpoint: String,
+        region: String,
+        bucket: String,
+        prefix: String,
+        credentials: ObjectStoreCredentials,
+    ) -> Result<Self> {
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let host = endpoint
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .filter(|rest| !rest.is_empty() && !rest.contains('/'))
+            .ok_or_else(|| LedgerArchiveError::InvalidUrl(endpoint.clone()))?
+            .to_string();
+        Ok(Self {
+            client: reqwest::Client::new(),
+            endpoint,
+            host,
+            region,
+            bucket,
+            prefix,
+            credentials,
+        })
+    }
+
+    /// Connects to the object store of `config.url`, `s3://bucket[/prefix]` or
+    /// `gs://bucket[/prefix]`, with the credentials in the environment
+    pub fn from_config(config: &LedgerArchiveConfig) -> Result<Self> {
+        let invalid_url = || LedgerArchiveError::InvalidUrl(config.url.clone());
+        let (scheme, location) = config.url.split_once("://").ok_or_else(invalid_url)?;
+        let (default_endpoint, default_region) = match scheme {
+            "s3" => (None, DEFAULT_S3_REGION),
+            "gs" => (Some(GCS_ENDPOINT.to_string()), GCS_REGION),
+            _ => return Err(invalid_url()),
+        };
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty() {
+            return Err(invalid_url());
+        }
+        let region = config
+            .region
+            .clone()
+            .unwrap_or_else(|| default_region.to_string());
+        let endpoint = config
+            .endpoint
+            .clone()
+            .or(default_endpoint)
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        Self::new(
+            endpoint,
+            region,
+            bucket.to_string(),
+            prefix.trim_matches('/').to_string(),
+            ObjectStoreCredentials::from_env()?,
+        )
+    }
+
+    /// Name of `key` in the bucket, below the prefix
+    fn object_name(&self, key: &str) -> String {
+        self.prefix
+            .split('/')
+            .chain(key.split('/'))
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn path(&self, key: &str) -> String {
+        let mut path = format!("/{}", uri_encode(&self.bucket));
+        for segment in self.object_name(key).split('/') {
+            if !segment.is_empty() {
+                path.push('/');
+                path.push_str(&uri_encode(segment));
+            }
+        }
+        path
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: &[u8],
+    ) -> reqwest::RequestBuilder {
+        let mut query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", uri_encode(name), uri_encode(value)))
+            .collect::<Vec<_>>();
+        query.sort_unstable();
+        let query = query.join("&");
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(body));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, query, self.host, payload_hash, amz_date, signed_headers, payload_hash,
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(
+                format!("AWS4{}", self.credentials.secret_access_key).as_bytes(),
+                date.as_bytes(),
+            ),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        let url = if query.is_empty() {
+            format!("{}{}", self.endpoint, path)
+        } else {
+            format!("{}{}?{}", self.endpoint, path, query)
+        };
+        self.client
+            .request(method, url)
+            .header("host", &self.host)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.credentials.access_key_id, scope, signed_headers, signature,
+                ),
+            )
+    }
+
+    /// Lists up to `max_keys` keys below `prefix` that sort after `start_after`, or that
+    /// follow the previous page if `continuation_token` is set
+    async fn list_page(
+        &self,
+        prefix: &str,
+        start_after: &str,
+        continuation_token: Option<&str>,
+        max_keys: usize,
+    ) -> Result<ListPage> {
+        let max_keys = max_keys.min(MAX_LIST_KEYS).to_string();
+        let mut query = vec![
+            ("list-type", "2"),
+            ("max-keys", max_keys.as_str()),
+            ("prefix", prefix),
+            ("start-after", start_after),
+        ];
+        if let Some(continuation_token) = continuation_token {
+            query.push(("continuation-token", continuation_token));
+        }
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/{}", uri_encode(&self.bucket)),
+                &query,
+                &[],
+            )
+            .send()
+            .await
+            .map_err(storage_error)?;
+        if !response.status().is_success() {
+            return Err(LedgerArchiveError::ObjectStore {
+                status: response.status().as_u16(),
+                key: prefix.to_string(),
+            });
+        }
+        parse_list_page(&response.text().await.map_err(storage_error)?)
+    }
+}
+
+#[async_trait]
+impl ArchiveStorage for ObjectStore {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::PUT, &self.path(key), &[], &data)
+            .body(data)
+            .send()
+            .await
+            .map_err(storage_error)?;
+        if !response.status().is_success() {
+            return Err(LedgerArchiveError::ObjectStore {
+                status: response.status().as_u16(),
+                key: key.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = self
+            .request(reqwest::Method::GET, &self.path(key), &[], &[])
+            .send()
+            .await
+            .map_err(storage_error)?;
+        match response.status() {
+            status if status.is_success() => Ok(Some(
+                response.bytes().await.map_err(storage_error)?.to_vec(),
+            )),
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            status => Err(LedgerArchiveError::ObjectStore {
+                status: status.as_u16(),
+                key: key.to_string(),
+            }),
+        }
+    }
+
+    async fn list(&self, dir: &str, start_after: &str, limit: usize) -> Result<Vec<String>> {
+        let prefix = format!("{}/", self.object_name(dir));
+        let start_after = format!("{}{}", prefix, start_after);
+        let mut names = vec![];
+        let mut continuation_token = None;
+        while names.len() < limit {
+            let page = self
+                .list_page(
+                    &prefix,
+                    &start_after,
+                    continuation_token.as_deref(),
+                    limit - names.len(),
+                )
+                .await?;
+            names.extend(
+                page.keys
+                    .iter()
+                    .filter_map(|key| key.strip_prefix(&prefix).map(String::from)),
+            );
+            continuation_token = match page.next_continuation_token {
+                Some(next_continuation_token) => Some(next_continuation_token),
+                None => break,
+            };
+        }
+        Ok(names)
+    }
+}
+
+/// One page of a ListObjectsV2 response
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ListPage {
+    keys: Vec<String>,
+    /// Set if the listing continues on another page
+    next_continuation_token: Option<String>,
+}
+
+fn parse_list_page(body: &str) -> Result<ListPage> {
+    let mut reader = Reader::from_str(body);
+    reader.trim_text(true);
+    let mut buf = vec![];
+    let mut element = vec![];
+    let mut is_truncated = false;
+    let mut page = ListPage::default();
+    loop {
+        match reader.read_event(&mut buf).map_err(storage_error)? {
+            Event::Start(start) => element = start.name().to_vec(),
+            Event::End(_) => element.clear(),
+            Event::Text(text) => {
+                let text = text.unescape_and_decode(&reader).map_err(storage_error)?;
+                match element.as_slice() {
+                    b"Key" => page.keys.push(text),
+                    b"IsTruncated" => is_truncated = text == "true",
+                    b"NextContinuationToken" => page.next_continuation_token = Some(text),
+                    _ => (),
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    if !is_truncated {
+        page.next_continuation_token = None;
+    }
+    Ok(page)
+}
+
+fn storage_error(err: impl std::error::Error + Send + Sync + 'static) -> LedgerArchiveError {
+    LedgerArchiveError::Storage(Box::new(err))
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_store_from_config() {
+        let config = |url: &str| LedgerArchiveConfig {
+            url: url.to_string(),
+            ..LedgerArchiveConfig::default()
+        };
+        assert!(matches!(
+            ObjectStore::from_config(&config("ftp://host/archive")),
+            Err(LedgerArchiveError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            ObjectStore::from_config(&config("s3://")),
+            Err(LedgerArchiveError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            ObjectStore::from_config(&config("/mnt/archive")),
+            Err(LedgerArchiveError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_object_store_path() {
+        let object_store = ObjectStore::new(
+            GCS_ENDPOINT.to_string(),
+            GCS_REGION.to_string(),
+            "ledger".to_string(),
+            "mainnet/archive".to_string(),
+            ObjectStoreCredentials {
+                access_key_id: String::default(),
+                secret_access_key: String::default(),
+            },
+        )
+        .unwrap();
+        assert_eq!(object_store.host, "storage.googleapis.com");
+        assert_eq!(
+            object_store.path("slots/000000000000002a"),
+            "/ledger/mainnet/archive/slots/000000000000002a"
+        );
+        assert_eq!(uri_encode("a b+c"), "a%20b%2Bc");
+        assert_eq!(
+            object_store.object_name("slots"),
+            "mainnet/archive/slots".to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_list_page() {
+        assert_eq!(
+            parse_list_page(
+                "<ListBucketResult><IsTruncated>true</IsTruncated>\
+                 <Contents><Key>slots/000000000000002a</Key></Contents>\
+                 <Contents><Key>slots/a&amp;b</Key></Contents>\
+                 <NextContinuationToken>1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=\
+                 </NextContinuationToken></ListBucketResult>"
+            )
+            .unwrap(),
+            ListPage {
+                keys: vec![
+                    "slots/000000000000002a".to_string(),
+                    "slots/a&b".to_string()
+                ],
+                next_continuation_token: Some(
+                    "1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=".to_string()
+                ),
+            }
+        );
+        // The last page has no continuation
+        assert_eq!(
+            parse_list_page(
+                "<ListBucketResult><IsTruncated>false</IsTruncated>\
+                 <Contents><Key>slots/000000000000002b</Key></Contents></ListBucketResult>"
+            )
+            .unwrap(),
+            ListPage {
+                keys: vec!["slots/000000000000002b".to_string()],
+                next_continuation_token: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
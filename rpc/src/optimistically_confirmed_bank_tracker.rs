@@ -257,6 +257,8 @@ impl OptimisticallyConfirmedBankTracker {
                         },
                     });
 
+                    subscriptions.notify_bank_frozen(bank.clone());
+
                     Self::notify_slot_status(
                         bank_notification_subscribers,
                         BankNotification::Frozen(bank.clone()),
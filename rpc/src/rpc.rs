@@ -2,8 +2,17 @@
 
 use {
     crate::{
-        max_slots::MaxSlots, optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
-        parsed_token_accounts::*, rpc_health::*,
+        block_time_backfill_service::EstimatedBlockTimes,
+        buffered_prioritization_fees::BufferedPrioritizationFees,
+        ledger_storage_backend::{LedgerStorageBackend, LedgerStorageError},
+        max_slots::MaxSlots,
+        optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
+        parsed_token_accounts::*,
+        rpc_auth::{RpcAccessControl, RpcMethodAllowlist},
+        rpc_health::*,
+        rpc_upstreams::RpcUpstreams,
+        slot_time_oracle::{self, SlotTimeOracle},
+        vote_latency_service::VoteLatencies,
     },
     bincode::{config::Options, serialize},
     crossbeam_channel::{unbounded, Receiver, Sender},
@@ -21,11 +30,13 @@ use {
         rpc_deprecated_config::*,
         rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
         rpc_request::{
-            TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE,
+            RpcRequest, TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE,
             MAX_GET_CONFIRMED_BLOCKS_RANGE, MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT,
-            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE, MAX_GET_PROGRAM_ACCOUNT_FILTERS,
-            MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_GET_SLOT_LEADERS, MAX_MULTIPLE_ACCOUNTS,
-            NUM_LARGEST_ACCOUNTS,
+            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE,
+            MAX_GET_PROGRAM_ACCOUNTS_PAGE_LIMIT, MAX_GET_PROGRAM_ACCOUNT_FILTERS,
+            MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_GET_SLOT_LEADERS,
+            MAX_GET_SLOT_TIME_ESTIMATES_SLOTS, MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT,
+            MAX_MULTIPLE_ACCOUNTS, MAX_SIMULATE_BUNDLE_TRANSACTIONS, NUM_LARGEST_ACCOUNTS,
         },
         rpc_response::{Response as RpcResponse, *},
     },
@@ -37,6 +48,7 @@ use {
         blockstore_db::BlockstoreError,
         get_tmp_ledger_path,
         leader_schedule_cache::LeaderScheduleCache,
+        ledger_archive::LedgerArchiveConfig,
     },
     solana_metrics::inc_new_counter_info,
     solana_perf::packet::PACKET_DATA_SIZE,
@@ -51,16 +63,18 @@ use {
         non_circulating_supply::calculate_non_circulating_supply,
         snapshot_config::SnapshotConfig,
         snapshot_utils,
+        stake_snapshot::{self, EpochStakeSnapshot},
+        vote_account::{VoteAccount, VoteAccountsHashMap},
     },
     solana_sdk::{
         account::{AccountSharedData, ReadableAccount},
         account_utils::StateMut,
-        clock::{Slot, UnixTimestamp, MAX_RECENT_BLOCKHASHES},
+        clock::{Epoch, Slot, UnixTimestamp, MAX_RECENT_BLOCKHASHES},
         commitment_config::{CommitmentConfig, CommitmentLevel},
         epoch_info::EpochInfo,
         epoch_schedule::EpochSchedule,
         exit::Exit,
-        feature_set::{self, nonce_must_be_writable},
+        feature_set::{self, nonce_must_be_writable, FeatureSet},
         fee_calculator::FeeCalculator,
         hash::Hash,
         message::{Message, SanitizedMessage},
@@ -74,12 +88,12 @@ use {
             self, AddressLoader, MessageHash, SanitizedTransaction, TransactionError,
             VersionedTransaction,
         },
+        transaction_context::TransactionAccount,
     },
     solana_send_transaction_service::{
         send_transaction_service::{SendTransactionService, TransactionInfo, DEFAULT_TPU_USE_QUIC},
         tpu_info::NullTpuInfo,
     },
-    solana_storage_bigtable::Error as StorageError,
     solana_streamer::socket::SocketAddrSpace,
     solana_transaction_status::{
         BlockEncodingOptions, ConfirmedBlock, ConfirmedTransactionStatusWithSignature,
@@ -96,15 +110,17 @@ use {
     std::{
         any::type_name,
         cmp::{max, min},
-        collections::{HashMap, HashSet},
+        collections::{BTreeMap, HashMap, HashSet},
         convert::TryFrom,
         net::SocketAddr,
+        ops::{Range, RangeInclusive},
+        path::PathBuf,
         str::FromStr,
         sync::{
             atomic::{AtomicBool, AtomicU64, Ordering},
             Arc, Mutex, RwLock,
         },
-        time::Duration,
+        time::{Duration, SystemTime, UNIX_EPOCH},
     },
 };
 
@@ -112,10 +128,17 @@ type RpcCustomResult<T> = std::result::Result<T, RpcCustomError>;
 
 pub const MAX_REQUEST_PAYLOAD_SIZE: usize = 50 * (1 << 10); // 50kB
 pub const PERFORMANCE_SAMPLES_LIMIT: usize = 720;
+// Performance samples, one per minute, averaged into slot time estimates
+const SLOT_TIME_PERF_SAMPLES: usize = 10;
+// Rooted slots inspected to estimate the leader skip rate
+const SKIP_RATE_SAMPLE_SLOTS: u64 = 1_000;
 
 // Limit the length of the `epoch_credits` array for each validator in a `get_vote_accounts`
 // response
 const MAX_RPC_EPOCH_CREDITS_HISTORY: usize = 5;
+// Changed ranges of account data separated by fewer unchanged bytes than this are returned as
+// one range by `simulateTransaction`, as each range adds some overhead to the response
+const ACCOUNT_DIFF_MERGE_GAP: usize = 16;
 
 fn new_response<T>(bank: &Bank, value: T) -> RpcResponse<T> {
     RpcResponse {
@@ -151,6 +174,9 @@ pub struct JsonRpcConfig {
     pub faucet_addr: Option<SocketAddr>,
     pub health_check_slot_distance: u64,
     pub rpc_bigtable_config: Option<RpcBigtableConfig>,
+    /// Serve history deleted from the blockstore out of this ledger archive, when BigTable is not
+    /// configured
+    pub rpc_ledger_archive_config: Option<LedgerArchiveConfig>,
     pub max_multiple_accounts: Option<usize>,
     pub account_indexes: AccountSecondaryIndexes,
     pub rpc_threads: usize,
@@ -158,6 +184,18 @@ pub struct JsonRpcConfig {
     pub full_api: bool,
     pub obsolete_v1_7_api: bool,
     pub rpc_scan_and_fix_roots: bool,
+    pub stake_snapshot_dir: Option<PathBuf>,
+    pub enable_rpc_vote_latencies: bool,
+    /// Compress JSON RPC responses larger than this many bytes when the client advertises a
+    /// supported encoding in its Accept-Encoding header
+    pub rpc_response_compression_threshold: Option<usize>,
+    /// Require a bearer token to call methods that are not public
+    pub rpc_access_control: Option<Arc<RpcAccessControl>>,
+    /// Estimate the block times missing from the ledger, which `getBlockTime` returns on request
+    pub enable_rpc_block_time_backfill: bool,
+    /// Forward `getBlock` and `getTransaction` requests for history that is no longer available
+    /// locally to these archive RPC nodes
+    pub rpc_upstream_urls: Vec<String>,
 }
 
 impl JsonRpcConfig {
@@ -200,12 +238,18 @@ pub struct JsonRpcRequestProcessor {
     cluster_info: Arc<ClusterInfo>,
     genesis_hash: Hash,
     transaction_sender: Arc<Mutex<Sender<TransactionInfo>>>,
-    bigtable_ledger_storage: Option<solana_storage_bigtable::LedgerStorage>,
+    ledger_storage_backend: Option<Arc<dyn LedgerStorageBackend>>,
+    rpc_upstreams: Option<Arc<RpcUpstreams>>,
     optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
     largest_accounts_cache: Arc<RwLock<LargestAccountsCache>>,
     max_slots: Arc<MaxSlots>,
     leader_schedule_cache: Arc<LeaderScheduleCache>,
     max_complete_transaction_status_slot: Arc<AtomicU64>,
+    buffered_prioritization_fees: Arc<BufferedPrioritizationFees>,
+    vote_latencies: Arc<VoteLatencies>,
+    estimated_block_times: Arc<EstimatedBlockTimes>,
+    /// Methods callable by the request, when access control is enabled
+    allowed_methods: Option<Arc<RpcMethodAllowlist>>,
 }
 impl Metadata for JsonRpcRequestProcessor {}
 
@@ -227,6 +271,43 @@ impl JsonRpcRequestProcessor {
         Ok(bank)
     }
 
+    /// Returns the bank to simulate transactions against: the bank selected by `simulation_bank`
+    /// if any, otherwise the bank of `config.commitment`
+    fn get_simulation_bank(
+        &self,
+        simulation_bank: Option<RpcSimulationBank>,
+        config: RpcContextConfig,
+    ) -> Result<Arc<Bank>> {
+        let commitment = match simulation_bank {
+            None => return self.get_bank_with_config(config),
+            Some(RpcSimulationBank::Processed) => CommitmentConfig::processed(),
+            Some(RpcSimulationBank::Confirmed) => CommitmentConfig::confirmed(),
+            Some(RpcSimulationBank::Finalized) => CommitmentConfig::finalized(),
+            Some(RpcSimulationBank::Slot(slot)) => {
+                let bank = self
+                    .bank_forks
+                    .read()
+                    .unwrap()
+                    .get(slot)
+                    .filter(|bank| bank.is_frozen())
+                    .ok_or(RpcCustomError::SimulationBankNotAvailable { slot })?;
+                if let Some(min_context_slot) = config.min_context_slot {
+                    if slot < min_context_slot {
+                        return Err(RpcCustomError::MinContextSlotNotReached {
+                            context_slot: slot,
+                        }
+                        .into());
+                    }
+                }
+                return Ok(bank);
+            }
+        };
+        self.get_bank_with_config(RpcContextConfig {
+            commitment: Some(commitment),
+            ..config
+        })
+    }
+
     #[allow(deprecated)]
     fn bank(&self, commitment: Option<CommitmentConfig>) -> Arc<Bank> {
         debug!("RPC commitment_config: {:?}", commitment);
@@ -305,12 +386,16 @@ impl JsonRpcRequestProcessor {
         health: Arc<RpcHealth>,
         cluster_info: Arc<ClusterInfo>,
         genesis_hash: Hash,
-        bigtable_ledger_storage: Option<solana_storage_bigtable::LedgerStorage>,
+        ledger_storage_backend: Option<Arc<dyn LedgerStorageBackend>>,
+        rpc_upstreams: Option<Arc<RpcUpstreams>>,
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
         largest_accounts_cache: Arc<RwLock<LargestAccountsCache>>,
         max_slots: Arc<MaxSlots>,
         leader_schedule_cache: Arc<LeaderScheduleCache>,
         max_complete_transaction_status_slot: Arc<AtomicU64>,
+        buffered_prioritization_fees: Arc<BufferedPrioritizationFees>,
+        vote_latencies: Arc<VoteLatencies>,
+        estimated_block_times: Arc<EstimatedBlockTimes>,
     ) -> (Self, Receiver<TransactionInfo>) {
         let (sender, receiver) = unbounded();
         (
@@ -325,17 +410,34 @@ impl JsonRpcRequestProcessor {
                 cluster_info,
                 genesis_hash,
                 transaction_sender: Arc::new(Mutex::new(sender)),
-                bigtable_ledger_storage,
+                ledger_storage_backend,
+                rpc_upstreams,
                 optimistically_confirmed_bank,
                 largest_accounts_cache,
                 max_slots,
                 leader_schedule_cache,
                 max_complete_transaction_status_slot,
+                buffered_prioritization_fees,
+                vote_latencies,
+                estimated_block_times,
+                allowed_methods: None,
             },
             receiver,
         )
     }
 
+    pub(crate) fn with_allowed_methods(mut self, allowed_methods: Arc<RpcMethodAllowlist>) -> Self {
+        self.allowed_methods = Some(allowed_methods);
+        self
+    }
+
+    pub(crate) fn is_method_allowed(&self, method: &str) -> bool {
+        self.allowed_methods
+            .as_ref()
+            .map(|allowed_methods| allowed_methods.allows(method))
+            .unwrap_or(true)
+    }
+
     // Useful for unit testing
     pub fn new_from_bank(bank: &Arc<Bank>, socket_addr_space: SocketAddrSpace) -> Self {
         let genesis_hash = bank.hash();
@@ -377,7 +479,8 @@ impl JsonRpcRequestProcessor {
             cluster_info,
             genesis_hash,
             transaction_sender: Arc::new(Mutex::new(sender)),
-            bigtable_ledger_storage: None,
+            ledger_storage_backend: None,
+            rpc_upstreams: None,
             optimistically_confirmed_bank: Arc::new(RwLock::new(OptimisticallyConfirmedBank {
                 bank: bank.clone(),
             })),
@@ -385,6 +488,10 @@ impl JsonRpcRequestProcessor {
             max_slots: Arc::new(MaxSlots::default()),
             leader_schedule_cache: Arc::new(LeaderScheduleCache::new_from_bank(bank)),
             max_complete_transaction_status_slot: Arc::new(AtomicU64::default()),
+            buffered_prioritization_fees: Arc::new(BufferedPrioritizationFees::default()),
+            vote_latencies: Arc::new(VoteLatencies::default()),
+            estimated_block_times: Arc::new(EstimatedBlockTimes::default()),
+            allowed_methods: None,
         }
     }
 
@@ -473,27 +580,75 @@ impl JsonRpcRequestProcessor {
                 self.get_filtered_program_accounts(&bank, program_id, filters)?
             }
         };
-        let accounts = if is_known_spl_token_id(program_id)
-            && encoding == UiAccountEncoding::JsonParsed
-        {
-            get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
-        } else {
-            keyed_accounts
-                .into_iter()
-                .map(|(pubkey, account)| {
-                    Ok(RpcKeyedAccount {
-                        pubkey: pubkey.to_string(),
-                        account: encode_account(&account, &pubkey, encoding, data_slice_config)?,
-                    })
-                })
-                .collect::<Result<Vec<_>>>()?
-        };
+        let accounts = encode_program_accounts(
+            &bank,
+            program_id,
+            keyed_accounts,
+            encoding,
+            data_slice_config,
+        )?;
         Ok(match with_context {
             true => OptionalContext::Context(new_response(&bank, accounts)),
             false => OptionalContext::NoContext(accounts),
         })
     }
 
+    /// Returns up to `limit` accounts of `program_id` that pass `filters`, in pubkey order,
+    /// starting after pubkey `cursor`. Unlike `get_program_accounts()`, only one page of
+    /// accounts is loaded and the scan stops as soon as the page is full. Secondary indexes are
+    /// not used, as they do not keep pubkeys in order.
+    pub fn get_program_accounts_paginated(
+        &self,
+        program_id: &Pubkey,
+        config: Option<RpcAccountInfoConfig>,
+        mut filters: Vec<RpcFilterType>,
+        limit: usize,
+        cursor: Option<Pubkey>,
+    ) -> Result<RpcResponse<RpcKeyedAccountsPage>> {
+        let RpcAccountInfoConfig {
+            encoding,
+            data_slice: data_slice_config,
+            commitment,
+            min_context_slot,
+        } = config.unwrap_or_default();
+        let bank = self.get_bank_with_config(RpcContextConfig {
+            commitment,
+            min_context_slot,
+        })?;
+        let encoding = encoding.unwrap_or(UiAccountEncoding::Binary);
+        check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
+        optimize_filters(&mut filters);
+        let keyed_accounts = bank
+            .get_filtered_program_accounts_after(
+                program_id,
+                |account| {
+                    filters
+                        .iter()
+                        .all(|filter_type| filter_type.allows(account))
+                },
+                cursor.as_ref(),
+                limit,
+            )
+            .map_err(|e| RpcCustomError::ScanError {
+                message: e.to_string(),
+            })?;
+        // A full page may be followed by more accounts. If not, the next page is empty.
+        let cursor = (keyed_accounts.len() >= limit)
+            .then(|| keyed_accounts.last().map(|(pubkey, _)| pubkey.to_string()))
+            .flatten();
+        let accounts = encode_program_accounts(
+            &bank,
+            program_id,
+            keyed_accounts,
+            encoding,
+            data_slice_config,
+        )?;
+        Ok(new_response(
+            &bank,
+            RpcKeyedAccountsPage { accounts, cursor },
+        ))
+    }
+
     pub async fn get_inflation_reward(
         &self,
         addresses: Vec<Pubkey>,
@@ -515,7 +670,7 @@ impl JsonRpcRequestProcessor {
         // Rewards for this epoch are found in the first confirmed block of the next epoch
         let first_slot_in_epoch = epoch_schedule.get_first_slot_in_epoch(epoch.saturating_add(1));
         if first_slot_in_epoch < first_available_block {
-            if self.bigtable_ledger_storage.is_some() {
+            if self.ledger_storage_backend.is_some() {
                 return Err(RpcCustomError::LongTermStorageSlotSkipped {
                     slot: first_slot_in_epoch,
                 }
@@ -735,6 +890,203 @@ impl JsonRpcRequestProcessor {
         self.max_slots.shred_insert.load(Ordering::Relaxed)
     }
 
+    fn get_health_detailed(&self) -> RpcHealthDetailed {
+        let health_check_slot_distance = self.config.health_check_slot_distance;
+        let bank = self.bank(Some(CommitmentConfig::processed()));
+
+        let max_shred_insert_slot = self.get_max_shred_insert_slot();
+        let slots_behind = max_shred_insert_slot.saturating_sub(bank.slot());
+        let replay = RpcReplayHealth {
+            status: slot_distance_state(slots_behind, health_check_slot_distance),
+            processed_slot: bank.slot(),
+            max_shred_insert_slot,
+            slots_behind,
+        };
+
+        let snapshot = match &self.snapshot_config {
+            Some(snapshot_config)
+                if snapshot_config.full_snapshot_archive_interval_slots != Slot::MAX =>
+            {
+                let full_snapshot_slot = snapshot_utils::get_highest_full_snapshot_archive_slot(
+                    &snapshot_config.full_snapshot_archives_dir,
+                );
+                let incremental_snapshot_slot = full_snapshot_slot.and_then(|full_snapshot_slot| {
+                    snapshot_utils::get_highest_incremental_snapshot_archive_slot(
+                        &snapshot_config.incremental_snapshot_archives_dir,
+                        full_snapshot_slot,
+                    )
+                });
+                let slots_behind_root =
+                    incremental_snapshot_slot
+                        .or(full_snapshot_slot)
+                        .map(|snapshot_slot| {
+                            self.bank_forks
+                                .read()
+                                .unwrap()
+                                .root()
+                                .saturating_sub(snapshot_slot)
+                        });
+                // A snapshot is expected at least every interval, so allow for one to be skipped
+                // before degrading
+                let snapshot_interval_slots = snapshot_config
+                    .full_snapshot_archive_interval_slots
+                    .min(snapshot_config.incremental_snapshot_archive_interval_slots);
+                RpcSnapshotHealth {
+                    status: slots_behind_root
+                        .map(|slots_behind_root| {
+                            slot_distance_state(
+                                slots_behind_root,
+                                snapshot_interval_slots.saturating_mul(4),
+                            )
+                        })
+                        .unwrap_or(RpcHealthState::Degraded),
+                    full_snapshot_slot,
+                    incremental_snapshot_slot,
+                    slots_behind_root,
+                }
+            }
+            // Snapshots are disabled, so they cannot fall behind
+            _ => RpcSnapshotHealth {
+                status: RpcHealthState::Ok,
+                full_snapshot_slot: None,
+                incremental_snapshot_slot: None,
+                slots_behind_root: None,
+            },
+        };
+
+        let health_status = self.health.check();
+        let accounts_hash = RpcAccountsHashHealth {
+            status: health_status.into(),
+            slots_behind_known_validators: match health_status {
+                RpcHealthStatus::Behind { num_slots } => Some(num_slots),
+                RpcHealthStatus::Ok | RpcHealthStatus::Unknown => None,
+            },
+        };
+
+        // A node without peers may be the only node of its cluster, so it is not failed
+        let num_peers = self.cluster_info.tvu_peers().len();
+        let gossip = RpcGossipHealth {
+            status: if num_peers == 0 {
+                RpcHealthState::Degraded
+            } else {
+                RpcHealthState::Ok
+            },
+            num_peers,
+        };
+
+        let identity = self.cluster_info.id();
+        let last_vote_slot = bank
+            .vote_accounts()
+            .values()
+            .filter_map(|(_stake, account)| match account.vote_state().as_ref() {
+                Ok(vote_state) if vote_state.node_pubkey == identity => {
+                    vote_state.last_voted_slot()
+                }
+                _ => None,
+            })
+            .max();
+        // Nodes that do not vote are healthy as far as voting goes
+        let voting = match last_vote_slot {
+            Some(last_vote_slot) => {
+                let slots_behind = bank.slot().saturating_sub(last_vote_slot);
+                RpcVotingHealth {
+                    status: slot_distance_state(slots_behind, DELINQUENT_VALIDATOR_SLOT_DISTANCE),
+                    last_vote_slot: Some(last_vote_slot),
+                    slots_behind: Some(slots_behind),
+                }
+            }
+            None => RpcVotingHealth {
+                status: RpcHealthState::Ok,
+                last_vote_slot: None,
+                slots_behind: None,
+            },
+        };
+
+        RpcHealthDetailed {
+            status: [
+                replay.status,
+                snapshot.status,
+                accounts_hash.status,
+                gossip.status,
+                voting.status,
+            ]
+            .into_iter()
+            .max()
+            .unwrap(),
+            replay,
+            snapshot,
+            accounts_hash,
+            gossip,
+            voting,
+        }
+    }
+
+    fn get_buffered_prioritization_fees(&self) -> RpcBufferedPrioritizationFees {
+        self.buffered_prioritization_fees.get()
+    }
+
+    fn get_slot_time_estimates(
+        &self,
+        slots: Vec<Slot>,
+        config: RpcContextConfig,
+    ) -> Result<RpcResponse<RpcSlotTimeEstimates>> {
+        let bank = self.get_bank_with_config(config)?;
+        let processed_slot = self.bank(Some(CommitmentConfig::processed())).slot();
+        let root = self.bank(Some(CommitmentConfig::finalized())).slot();
+
+        let perf_samples = self
+            .blockstore
+            .get_recent_perf_samples(SLOT_TIME_PERF_SAMPLES)
+            .map_err(|err| {
+                warn!("get_slot_time_estimates failed: {:?}", err);
+                Error::invalid_request()
+            })?;
+        let skip_rate_start_slot = root
+            .saturating_sub(SKIP_RATE_SAMPLE_SLOTS)
+            .max(self.blockstore.lowest_slot());
+        let skip_rate = if skip_rate_start_slot < root {
+            let num_rooted_slots = self
+                .blockstore
+                .rooted_slot_iterator(skip_rate_start_slot)
+                .map_err(|err| {
+                    warn!("get_slot_time_estimates failed: {:?}", err);
+                    Error::invalid_request()
+                })?
+                .take_while(|slot| *slot <= root)
+                .count();
+            slot_time_oracle::skip_rate(num_rooted_slots as u64, root - skip_rate_start_slot + 1)
+        } else {
+            0.0
+        };
+        let oracle = SlotTimeOracle::from_perf_samples(&perf_samples, skip_rate);
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let slots = slots
+            .into_iter()
+            .map(|slot| {
+                let eta_ms = oracle.eta_ms(processed_slot, slot);
+                RpcSlotTimeEstimate {
+                    slot,
+                    eta_ms,
+                    estimated_time: (now_ms.saturating_add(eta_ms) / 1000) as UnixTimestamp,
+                }
+            })
+            .collect();
+        Ok(new_response(
+            &bank,
+            RpcSlotTimeEstimates {
+                ms_per_slot: oracle.ms_per_slot() as u64,
+                skip_rate: oracle.skip_rate(),
+                confirmation_eta_ms: oracle
+                    .confirmation_eta_ms(processed_slot.saturating_sub(bank.slot())),
+                slots,
+            },
+        ))
+    }
+
     fn get_slot_leader(&self, config: RpcContextConfig) -> Result<String> {
         let bank = self.get_bank_with_config(config)?;
         Ok(bank.collector_id().to_string())
@@ -1029,11 +1381,11 @@ impl JsonRpcRequestProcessor {
         Ok(())
     }
 
-    fn check_bigtable_result<T>(
+    fn check_long_term_storage_result<T>(
         &self,
-        result: &std::result::Result<T, solana_storage_bigtable::Error>,
+        result: &std::result::Result<T, LedgerStorageError>,
     ) -> Result<()> {
-        if let Err(solana_storage_bigtable::Error::BlockNotFound(slot)) = result {
+        if let Err(LedgerStorageError::BlockNotFound(slot)) = result {
             return Err(RpcCustomError::LongTermStorageSlotSkipped { slot: *slot }.into());
         }
         Ok(())
@@ -1090,12 +1442,35 @@ impl JsonRpcRequestProcessor {
                     }
                     Ok(encoded_block)
                 };
+                if let Err(BlockstoreError::SlotCleanedUp) = result {
+                    if let Some(ledger_archive) = self.blockstore.ledger_archive() {
+                        match ledger_archive.get_block(slot).await {
+                            Ok(Some(block)) => return encode_block(block).map(Some),
+                            Ok(None) => (),
+                            Err(err) => {
+                                warn!("ledger archive: get_block({}) failed: {}", slot, err)
+                            }
+                        }
+                    }
+                }
                 if result.is_err() {
-                    if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                        let bigtable_result =
-                            bigtable_ledger_storage.get_confirmed_block(slot).await;
-                        self.check_bigtable_result(&bigtable_result)?;
-                        return bigtable_result.ok().map(encode_block).transpose();
+                    if let Some(ledger_storage_backend) = &self.ledger_storage_backend {
+                        let storage_result = ledger_storage_backend.get_confirmed_block(slot).await;
+                        // Upstreams may still have the block
+                        if storage_result.is_ok() || self.rpc_upstreams.is_none() {
+                            self.check_long_term_storage_result(&storage_result)?;
+                            return storage_result.ok().map(encode_block).transpose();
+                        }
+                    }
+                }
+                if let Err(BlockstoreError::SlotCleanedUp) = result {
+                    if let Some(rpc_upstreams) = &self.rpc_upstreams {
+                        if let Some(block) = rpc_upstreams
+                            .forward(RpcRequest::GetBlock, serde_json::json!([slot, config]))
+                            .await
+                        {
+                            return Ok(Some(block));
+                        }
                     }
                 }
                 self.check_slot_cleaned_up(&result, slot)?;
@@ -1181,21 +1556,21 @@ impl JsonRpcRequestProcessor {
             .unwrap_or_default();
         if start_slot < lowest_blockstore_slot {
             // If the starting slot is lower than what's available in blockstore assume the entire
-            // [start_slot..end_slot] can be fetched from BigTable. This range should not ever run
-            // into unfinalized confirmed blocks due to MAX_GET_CONFIRMED_BLOCKS_RANGE
-            if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                return bigtable_ledger_storage
+            // [start_slot..end_slot] can be fetched from long-term storage. This range should not
+            // ever run into unfinalized confirmed blocks due to MAX_GET_CONFIRMED_BLOCKS_RANGE
+            if let Some(ledger_storage_backend) = &self.ledger_storage_backend {
+                return ledger_storage_backend
                     .get_confirmed_blocks(start_slot, (end_slot - start_slot) as usize + 1) // increment limit by 1 to ensure returned range is inclusive of both start_slot and end_slot
                     .await
-                    .map(|mut bigtable_blocks| {
-                        bigtable_blocks.retain(|&slot| slot <= end_slot);
-                        bigtable_blocks
+                    .map(|mut storage_blocks| {
+                        storage_blocks.retain(|&slot| slot <= end_slot);
+                        storage_blocks
                     })
                     .map_err(|_| {
-                        Error::invalid_params(
-                            "BigTable query failed (maybe timeout due to too large range?)"
-                                .to_string(),
-                        )
+                        Error::invalid_params(format!(
+                            "{} query failed (maybe timeout due to too large range?)",
+                            ledger_storage_backend.name()
+                        ))
                     });
             }
         }
@@ -1249,10 +1624,10 @@ impl JsonRpcRequestProcessor {
 
         if start_slot < lowest_blockstore_slot {
             // If the starting slot is lower than what's available in blockstore assume the entire
-            // range can be fetched from BigTable. This range should not ever run into unfinalized
-            // confirmed blocks due to MAX_GET_CONFIRMED_BLOCKS_RANGE
-            if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                return Ok(bigtable_ledger_storage
+            // range can be fetched from long-term storage. This range should not ever run into
+            // unfinalized confirmed blocks due to MAX_GET_CONFIRMED_BLOCKS_RANGE
+            if let Some(ledger_storage_backend) = &self.ledger_storage_backend {
+                return Ok(ledger_storage_backend
                     .get_confirmed_blocks(start_slot, limit)
                     .await
                     .unwrap_or_default());
@@ -1293,6 +1668,62 @@ impl JsonRpcRequestProcessor {
         Ok(blocks)
     }
 
+    /// Lists up to `limit` blocks at or below `start_slot`, highest first. No block above the
+    /// watermark of `commitment` is returned, so a `start_slot` of `u64::MAX` yields the most
+    /// recent blocks. Blocks that are no longer in the blockstore are not fetched from long-term
+    /// storage.
+    pub fn get_blocks_with_limit_descending(
+        &self,
+        start_slot: Slot,
+        limit: usize,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<Vec<Slot>> {
+        let commitment = commitment.unwrap_or_default();
+        check_is_at_least_confirmed(commitment)?;
+
+        if limit > MAX_GET_CONFIRMED_BLOCKS_RANGE as usize {
+            return Err(Error::invalid_params(format!(
+                "Limit too large; max {}",
+                MAX_GET_CONFIRMED_BLOCKS_RANGE
+            )));
+        }
+
+        let highest_confirmed_root = self
+            .block_commitment_cache
+            .read()
+            .unwrap()
+            .highest_confirmed_root();
+
+        // Maybe start with confirmed blocks
+        let mut blocks = vec![];
+        if commitment.is_confirmed() {
+            let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()));
+            blocks = confirmed_bank
+                .status_cache_ancestors()
+                .into_iter()
+                .filter(|&slot| slot > highest_confirmed_root && slot <= start_slot)
+                .collect();
+            blocks.sort_unstable_by(|a, b| b.cmp(a));
+            blocks.truncate(limit);
+        }
+
+        // Finalized blocks
+        let lowest_blockstore_slot = self
+            .blockstore
+            .get_first_available_block()
+            .unwrap_or_default();
+        let remaining = limit - blocks.len();
+        blocks.extend(
+            self.blockstore
+                .rooted_slot_iterator_reverse(min(start_slot, highest_confirmed_root))
+                .map_err(|_| Error::internal_error())?
+                .take_while(|&slot| slot >= lowest_blockstore_slot)
+                .take(remaining),
+        );
+
+        Ok(blocks)
+    }
+
     pub async fn get_block_time(&self, slot: Slot) -> Result<Option<UnixTimestamp>> {
         if slot == 0 {
             return Ok(Some(self.genesis_creation_time()));
@@ -1307,12 +1738,16 @@ impl JsonRpcRequestProcessor {
             let result = self.blockstore.get_block_time(slot);
             self.check_blockstore_root(&result, slot)?;
             if result.is_err() || matches!(result, Ok(None)) {
-                if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                    let bigtable_result = bigtable_ledger_storage.get_confirmed_block(slot).await;
-                    self.check_bigtable_result(&bigtable_result)?;
-                    return Ok(bigtable_result
+                if let Some(ledger_storage_backend) = &self.ledger_storage_backend {
+                    let storage_result = ledger_storage_backend.get_confirmed_block(slot).await;
+                    self.check_long_term_storage_result(&storage_result)?;
+                    let block_time = storage_result
                         .ok()
-                        .and_then(|confirmed_block| confirmed_block.block_time));
+                        .map(|confirmed_block| confirmed_block.block_time);
+                    if block_time == Some(None) && self.config.enable_rpc_block_time_backfill {
+                        self.estimated_block_times.request_storage_estimate(slot);
+                    }
+                    return Ok(block_time.flatten());
                 }
             }
             self.check_slot_cleaned_up(&result, slot)?;
@@ -1327,6 +1762,28 @@ impl JsonRpcRequestProcessor {
         }
     }
 
+    /// Returns the block time of `slot`, or the estimate of the block time backfill service if
+    /// it was not recorded
+    pub async fn get_block_time_with_estimate(
+        &self,
+        slot: Slot,
+    ) -> Result<Option<RpcEstimatedBlockTime>> {
+        let result = self.get_block_time(slot).await;
+        if let Ok(Some(block_time)) = result {
+            return Ok(Some(RpcEstimatedBlockTime {
+                block_time,
+                estimated: false,
+            }));
+        }
+        match self.estimated_block_times.get(slot) {
+            Some(block_time) => Ok(Some(RpcEstimatedBlockTime {
+                block_time,
+                estimated: true,
+            })),
+            None => result.map(|_| None),
+        }
+    }
+
     pub fn get_signature_confirmation_status(
         &self,
         signature: Signature,
@@ -1400,8 +1857,8 @@ impl JsonRpcRequestProcessor {
                     })
                 {
                     Some(status)
-                } else if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                    bigtable_ledger_storage
+                } else if let Some(ledger_storage_backend) = &self.ledger_storage_backend {
+                    ledger_storage_backend
                         .get_signature_status(&signature)
                         .await
                         .map(Some)
@@ -1464,6 +1921,7 @@ impl JsonRpcRequestProcessor {
             .unwrap_or_default();
         let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Json);
         let max_supported_transaction_version = config.max_supported_transaction_version;
+        let decode_instruction_error = config.decode_instruction_error.unwrap_or_default();
         let commitment = config.commitment.unwrap_or_default();
         check_is_at_least_confirmed(commitment)?;
 
@@ -1479,7 +1937,18 @@ impl JsonRpcRequestProcessor {
 
             let encode_transaction =
                 |confirmed_tx_with_meta: ConfirmedTransactionWithStatusMeta| -> Result<EncodedConfirmedTransactionWithStatusMeta> {
-                    Ok(confirmed_tx_with_meta.encode(encoding, max_supported_transaction_version).map_err(RpcCustomError::from)?)
+                    let instruction_error_detail = if decode_instruction_error {
+                        confirmed_tx_with_meta.tx_with_meta.instruction_error_detail()
+                    } else {
+                        None
+                    };
+                    let mut encoded_transaction = confirmed_tx_with_meta
+                        .encode(encoding, max_supported_transaction_version)
+                        .map_err(RpcCustomError::from)?;
+                    if let Some(meta) = encoded_transaction.transaction.meta.as_mut() {
+                        meta.instruction_error_detail = instruction_error_detail;
+                    }
+                    Ok(encoded_transaction)
                 };
 
             match confirmed_transaction.unwrap_or(None) {
@@ -1509,13 +1978,32 @@ impl JsonRpcRequestProcessor {
                     }
                 }
                 None => {
-                    if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                        return bigtable_ledger_storage
+                    if let Some(ledger_storage_backend) = &self.ledger_storage_backend {
+                        let storage_result = ledger_storage_backend
                             .get_confirmed_transaction(&signature)
                             .await
-                            .unwrap_or(None)
-                            .map(encode_transaction)
-                            .transpose();
+                            .unwrap_or(None);
+                        if storage_result.is_some() || self.rpc_upstreams.is_none() {
+                            return storage_result.map(encode_transaction).transpose();
+                        }
+                    }
+                    // The transaction may be older than the blockstore. Signatures do not tell
+                    // their slot, so any signature missing locally is forwarded; `RpcUpstreams`
+                    // caps how many such lookups run at once.
+                    if self
+                        .blockstore
+                        .get_first_available_block()
+                        .unwrap_or_default()
+                        > 0
+                    {
+                        if let Some(rpc_upstreams) = &self.rpc_upstreams {
+                            return Ok(rpc_upstreams
+                                .forward(
+                                    RpcRequest::GetTransaction,
+                                    serde_json::json!([signature.to_string(), config]),
+                                )
+                                .await);
+                        }
                     }
                 }
             }
@@ -1532,7 +2020,7 @@ impl JsonRpcRequestProcessor {
         end_slot: Slot,
     ) -> Vec<Signature> {
         if self.config.enable_rpc_transaction_history {
-            // TODO: Add bigtable_ledger_storage support as a part of
+            // TODO: Add long-term storage support as a part of
             // https://github.com/solana-labs/solana/pull/10928
             let end_slot = min(
                 end_slot,
@@ -1549,11 +2037,101 @@ impl JsonRpcRequestProcessor {
         }
     }
 
+    /// Returns the Blockstore slots, up to `highest_slot`, that hold the blocks produced in
+    /// `[after_time, before_time)`, or None if there are none
+    fn get_slot_range_for_block_times(
+        &self,
+        highest_slot: Slot,
+        before_time: Option<UnixTimestamp>,
+        after_time: Option<UnixTimestamp>,
+    ) -> Result<Option<RangeInclusive<Slot>>> {
+        let first_slot_at_or_after = |timestamp, slot_range| {
+            self.blockstore
+                .get_first_slot_at_or_after_block_time(timestamp, slot_range)
+                .map_err(|err| Error::invalid_params(format!("{}", err)))
+        };
+
+        let lowest_slot = match after_time {
+            None => 0,
+            Some(after_time) => match first_slot_at_or_after(after_time, 0..=highest_slot)? {
+                None => return Ok(None),
+                Some(slot) => slot,
+            },
+        };
+        let highest_slot = match before_time {
+            None => highest_slot,
+            Some(before_time) => {
+                match first_slot_at_or_after(before_time, lowest_slot..=highest_slot)? {
+                    None => highest_slot,
+                    Some(slot) if slot == lowest_slot => return Ok(None),
+                    Some(slot) => slot - 1,
+                }
+            }
+        };
+        Ok(Some(lowest_slot..=highest_slot))
+    }
+
+    /// Returns the first signature of the earliest block in long-term storage that was produced
+    /// at or after `before_time`. Listing signatures before it skips all newer transactions
+    /// without paging through them.
+    async fn get_storage_signature_at_block_time(
+        ledger_storage_backend: &Arc<dyn LedgerStorageBackend>,
+        before_time: UnixTimestamp,
+        highest_slot: Slot,
+    ) -> Option<Signature> {
+        let first_block_at_or_after = |slot| async move {
+            ledger_storage_backend
+                .get_confirmed_blocks(slot, 1)
+                .await
+                .ok()
+                .map(|slots| slots.first().copied())
+        };
+
+        // Rooted block times do not decrease, so binary search for the first stored block
+        // produced at or after `before_time`
+        let mut low = ledger_storage_backend
+            .get_first_available_block()
+            .await
+            .ok()??;
+        let mut high = highest_slot.saturating_add(1);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let is_at_or_after = match first_block_at_or_after(mid).await? {
+                None => true,
+                Some(slot) => ledger_storage_backend
+                    .get_confirmed_block(slot)
+                    .await
+                    .ok()?
+                    .block_time
+                    .map(|block_time| block_time >= before_time)
+                    .unwrap_or(false),
+            };
+            if is_at_or_after {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        let slot = first_block_at_or_after(low).await??;
+        let block = ledger_storage_backend
+            .get_confirmed_block(slot)
+            .await
+            .ok()?;
+        block
+            .transactions
+            .first()
+            .map(|transaction| *transaction.transaction_signature())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_signatures_for_address(
         &self,
         address: Pubkey,
         before: Option<Signature>,
         until: Option<Signature>,
+        before_time: Option<UnixTimestamp>,
+        after_time: Option<UnixTimestamp>,
         mut limit: usize,
         config: RpcContextConfig,
     ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
@@ -1580,13 +2158,33 @@ impl JsonRpcRequestProcessor {
                 highest_confirmed_root
             };
 
+            let is_in_time_range = |block_time: Option<UnixTimestamp>| match block_time {
+                None => before_time.is_none() && after_time.is_none(),
+                Some(block_time) => {
+                    before_time.map_or(true, |before_time| block_time < before_time)
+                        && after_time.map_or(true, |after_time| block_time >= after_time)
+                }
+            };
+
+            // Bound the Blockstore scan to the slots of the blocks produced in the requested time
+            // range
             let SignatureInfosForAddress {
                 infos: mut results,
                 found_before,
-            } = self
-                .blockstore
-                .get_confirmed_signatures_for_address2(address, highest_slot, before, until, limit)
-                .map_err(|err| Error::invalid_params(format!("{}", err)))?;
+            } = match self.get_slot_range_for_block_times(highest_slot, before_time, after_time)? {
+                Some(slot_range) => self
+                    .blockstore
+                    .get_confirmed_signatures_for_address_in_slot_range(
+                        address,
+                        highest_slot,
+                        before,
+                        until,
+                        slot_range,
+                        limit,
+                    )
+                    .map_err(|err| Error::invalid_params(format!("{}", err)))?,
+                None => SignatureInfosForAddress::default(),
+            };
 
             let map_results = |results: Vec<ConfirmedTransactionStatusWithSignature>| {
                 results
@@ -1612,23 +2210,23 @@ impl JsonRpcRequestProcessor {
             };
 
             if results.len() < limit {
-                if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                    let mut bigtable_before = before;
+                if let Some(ledger_storage_backend) = &self.ledger_storage_backend {
+                    let mut storage_before = before;
                     if !results.is_empty() {
                         limit -= results.len();
-                        bigtable_before = results.last().map(|x| x.signature);
+                        storage_before = results.last().map(|x| x.signature);
                     }
 
                     // If the oldest address-signature found in Blockstore has not yet been
                     // uploaded to long-term storage, modify the storage query to return all latest
                     // signatures to prevent erroring on RowNotFound. This can race with upload.
-                    if found_before && bigtable_before.is_some() {
-                        match bigtable_ledger_storage
-                            .get_signature_status(&bigtable_before.unwrap())
+                    if found_before && storage_before.is_some() {
+                        match ledger_storage_backend
+                            .get_signature_status(&storage_before.unwrap())
                             .await
                         {
-                            Err(StorageError::SignatureNotFound) => {
-                                bigtable_before = None;
+                            Err(LedgerStorageError::SignatureNotFound) => {
+                                storage_before = None;
                             }
                             Err(err) => {
                                 warn!("{:?}", err);
@@ -1638,26 +2236,37 @@ impl JsonRpcRequestProcessor {
                         }
                     }
 
-                    let bigtable_results = bigtable_ledger_storage
+                    // Start listing long-term storage at `before_time` rather than at its tip
+                    if let (None, Some(before_time)) = (storage_before, before_time) {
+                        storage_before = Self::get_storage_signature_at_block_time(
+                            ledger_storage_backend,
+                            before_time,
+                            highest_slot,
+                        )
+                        .await;
+                    }
+
+                    let storage_results = ledger_storage_backend
                         .get_confirmed_signatures_for_address(
                             &address,
-                            bigtable_before.as_ref(),
+                            storage_before.as_ref(),
                             until.as_ref(),
                             limit,
                         )
                         .await;
-                    match bigtable_results {
-                        Ok(bigtable_results) => {
+                    match storage_results {
+                        Ok(storage_results) => {
                             let results_set: HashSet<_> =
                                 results.iter().map(|result| result.signature).collect();
-                            for (bigtable_result, _) in bigtable_results {
+                            for (storage_result, _) in storage_results {
                                 // In the upload race condition, latest address-signatures in
                                 // long-term storage may include original `before` signature...
-                                if before != Some(bigtable_result.signature)
+                                if before != Some(storage_result.signature)
                                     // ...or earlier Blockstore signatures
-                                    && !results_set.contains(&bigtable_result.signature)
+                                    && !results_set.contains(&storage_result.signature)
+                                    && is_in_time_range(storage_result.block_time)
                                 {
-                                    results.push(bigtable_result);
+                                    results.push(storage_result);
                                 }
                             }
                         }
@@ -1680,15 +2289,15 @@ impl JsonRpcRequestProcessor {
             .get_first_available_block()
             .unwrap_or_default();
 
-        if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-            let bigtable_slot = bigtable_ledger_storage
+        if let Some(ledger_storage_backend) = &self.ledger_storage_backend {
+            let storage_slot = ledger_storage_backend
                 .get_first_available_block()
                 .await
                 .unwrap_or(None)
                 .unwrap_or(slot);
 
-            if bigtable_slot < slot {
-                return bigtable_slot;
+            if storage_slot < slot {
+                return storage_slot;
             }
         }
         slot
@@ -1756,15 +2365,7 @@ impl JsonRpcRequestProcessor {
             activating,
             deactivating,
         } = delegation.stake_activating_and_deactivating(epoch, Some(&stake_history));
-        let stake_activation_state = if deactivating > 0 {
-            StakeActivationState::Deactivating
-        } else if activating > 0 {
-            StakeActivationState::Activating
-        } else if effective > 0 {
-            StakeActivationState::Active
-        } else {
-            StakeActivationState::Inactive
-        };
+        let stake_activation_state = stake_activation_state(effective, activating, deactivating);
         let inactive_stake = match stake_activation_state {
             StakeActivationState::Activating => activating,
             StakeActivationState::Active => 0,
@@ -1778,18 +2379,174 @@ impl JsonRpcRequestProcessor {
         })
     }
 
-    pub fn get_token_account_balance(
+    pub fn get_stake_snapshot(&self, epoch: Epoch) -> Result<Option<RpcStakeSnapshot>> {
+        let stake_snapshot_dir = self
+            .config
+            .stake_snapshot_dir
+            .as_ref()
+            .ok_or(RpcCustomError::StakeSnapshotNotAvailable)?;
+        let snapshot =
+            stake_snapshot::read_stake_snapshot(stake_snapshot_dir, epoch).map_err(|err| {
+                warn!("get_stake_snapshot failed: {:?}", err);
+                Error::internal_error()
+            })?;
+        Ok(snapshot.map(rpc_stake_snapshot))
+    }
+
+    pub fn get_stake_minimum_delegation_history(
         &self,
-        pubkey: &Pubkey,
-        commitment: Option<CommitmentConfig>,
-    ) -> Result<RpcResponse<UiTokenAmount>> {
-        let bank = self.bank(commitment);
-        let account = bank.get_account(pubkey).ok_or_else(|| {
-            Error::invalid_params("Invalid param: could not find account".to_string())
-        })?;
+        config: RpcContextConfig,
+    ) -> Result<Vec<RpcEpochStakeHistoryEntry>> {
+        let bank = self.get_bank_with_config(config)?;
+        let stake_history_account = bank
+            .get_account(&stake_history::id())
+            .ok_or_else(Error::internal_error)?;
+        let stake_history =
+            solana_sdk::account::from_account::<StakeHistory, _>(&stake_history_account)
+                .ok_or_else(Error::internal_error)?;
 
-        if !is_known_spl_token_id(account.owner()) {
-            return Err(Error::invalid_params(
+        // The minimum delegation is raised by a feature, from the epoch it is activated in
+        let raise_minimum_delegation_epoch = bank
+            .feature_set
+            .activated_slot(&feature_set::stake_raise_minimum_delegation_to_1_sol::id())
+            .map(|slot| bank.epoch_schedule().get_epoch(slot));
+        let minimum_delegation = |epoch: Epoch| {
+            let mut epoch_feature_set = FeatureSet::default();
+            if raise_minimum_delegation_epoch
+                .map_or(false, |activation_epoch| activation_epoch <= epoch)
+            {
+                epoch_feature_set.activate(
+                    &feature_set::stake_raise_minimum_delegation_to_1_sol::id(),
+                    0,
+                );
+            }
+            solana_stake_program::get_minimum_delegation(&epoch_feature_set)
+        };
+
+        Ok(stake_history
+            .iter()
+            .map(|(epoch, entry)| RpcEpochStakeHistoryEntry {
+                epoch: *epoch,
+                minimum_delegation: minimum_delegation(*epoch),
+                effective: entry.effective,
+                activating: entry.activating,
+                deactivating: entry.deactivating,
+            })
+            .collect())
+    }
+
+    pub fn get_epoch_stake_breakdown(
+        &self,
+        config: RpcEpochConfig,
+    ) -> Result<Option<RpcEpochStakeBreakdown>> {
+        let bank = self.get_bank_with_config(RpcContextConfig {
+            commitment: config.commitment,
+            min_context_slot: config.min_context_slot,
+        })?;
+        let epoch = config.epoch.unwrap_or_else(|| bank.epoch());
+        let vote_accounts = match bank.epoch_vote_accounts(epoch) {
+            Some(vote_accounts) => vote_accounts,
+            None => return Ok(None),
+        };
+        let previous_vote_accounts = epoch
+            .checked_sub(1)
+            .and_then(|previous_epoch| bank.epoch_vote_accounts(previous_epoch));
+        let stake_delta =
+            |stake: u64, previous_stake: u64| (stake as i64).saturating_sub(previous_stake as i64);
+        let node_pubkey = |vote_account: &VoteAccount| {
+            vote_account
+                .vote_state()
+                .as_ref()
+                .ok()
+                .map(|vote_state| vote_state.node_pubkey.to_string())
+        };
+
+        let mut validators: BTreeMap<Pubkey, RpcValidatorStakeDelta> = vote_accounts
+            .iter()
+            .filter(|(_, (activated_stake, _))| *activated_stake > 0)
+            .map(|(vote_pubkey, (activated_stake, vote_account))| {
+                let previous_stake = previous_vote_accounts.map(|previous_vote_accounts| {
+                    previous_vote_accounts
+                        .get(vote_pubkey)
+                        .map(|(previous_stake, _)| *previous_stake)
+                        .unwrap_or_default()
+                });
+                (
+                    *vote_pubkey,
+                    RpcValidatorStakeDelta {
+                        vote_pubkey: vote_pubkey.to_string(),
+                        node_pubkey: node_pubkey(vote_account),
+                        activated_stake: *activated_stake,
+                        stake_delta: previous_stake
+                            .map(|previous_stake| stake_delta(*activated_stake, previous_stake)),
+                    },
+                )
+            })
+            .collect();
+        // Validators whose stake was fully deactivated since the previous epoch
+        for (vote_pubkey, (previous_stake, vote_account)) in
+            previous_vote_accounts.into_iter().flatten()
+        {
+            if *previous_stake > 0 && !validators.contains_key(vote_pubkey) {
+                validators.insert(
+                    *vote_pubkey,
+                    RpcValidatorStakeDelta {
+                        vote_pubkey: vote_pubkey.to_string(),
+                        node_pubkey: node_pubkey(vote_account),
+                        activated_stake: 0,
+                        stake_delta: Some(stake_delta(0, *previous_stake)),
+                    },
+                );
+            }
+        }
+
+        let total_stake = |vote_accounts: &VoteAccountsHashMap| {
+            vote_accounts.values().map(|(stake, _)| *stake).sum::<u64>()
+        };
+        let total_stake_delta = previous_vote_accounts.map(|previous_vote_accounts| {
+            stake_delta(
+                total_stake(vote_accounts),
+                total_stake(previous_vote_accounts),
+            )
+        });
+        Ok(Some(RpcEpochStakeBreakdown {
+            epoch,
+            total_stake: total_stake(vote_accounts),
+            total_stake_delta,
+            validators: validators.into_values().collect(),
+        }))
+    }
+
+    pub fn get_vote_latencies(
+        &self,
+        vote_pubkeys: Option<Vec<String>>,
+    ) -> Result<RpcVoteLatencies> {
+        if !self.config.enable_rpc_vote_latencies {
+            return Err(RpcCustomError::VoteLatenciesNotAvailable.into());
+        }
+        let vote_pubkeys = vote_pubkeys
+            .map(|vote_pubkeys| {
+                vote_pubkeys
+                    .iter()
+                    .map(|vote_pubkey| verify_pubkey(vote_pubkey))
+                    .collect::<Result<HashSet<_>>>()
+            })
+            .transpose()?;
+        Ok(self.vote_latencies.get(vote_pubkeys.as_ref()))
+    }
+
+    pub fn get_token_account_balance(
+        &self,
+        pubkey: &Pubkey,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<RpcResponse<UiTokenAmount>> {
+        let bank = self.bank(commitment);
+        let account = bank.get_account(pubkey).ok_or_else(|| {
+            Error::invalid_params("Invalid param: could not find account".to_string())
+        })?;
+
+        if !is_known_spl_token_id(account.owner()) {
+            return Err(Error::invalid_params(
                 "Invalid param: not a Token account".to_string(),
             ));
         }
@@ -1830,36 +2587,89 @@ impl JsonRpcRequestProcessor {
         commitment: Option<CommitmentConfig>,
     ) -> Result<RpcResponse<Vec<RpcTokenAccountBalance>>> {
         let bank = self.bank(commitment);
-        let (mint_owner, decimals) = get_mint_owner_and_decimals(&bank, mint)?;
+        let (balances, decimals) = self.get_token_account_balances(&bank, mint)?;
+        let token_balances = balances
+            .into_iter()
+            .take(NUM_LARGEST_ACCOUNTS)
+            .map(|(amount, address)| new_token_account_balance(amount, &address, decimals))
+            .collect();
+        Ok(new_response(&bank, token_balances))
+    }
+
+    /// Returns up to `limit` accounts of `mint`, in decreasing balance order, starting after the
+    /// account with balance and address `cursor`
+    pub fn get_token_largest_accounts_paginated(
+        &self,
+        mint: &Pubkey,
+        commitment: Option<CommitmentConfig>,
+        limit: usize,
+        cursor: Option<(u64, Pubkey)>,
+    ) -> Result<RpcResponse<RpcTokenAccountBalancesPage>> {
+        let bank = self.bank(commitment);
+        let (balances, decimals) = self.get_token_account_balances(&bank, mint)?;
+        let start = cursor
+            .map(|(cursor_amount, cursor_address)| {
+                balances.partition_point(|(amount, address)| {
+                    *amount > cursor_amount
+                        || (*amount == cursor_amount && *address <= cursor_address)
+                })
+            })
+            .unwrap_or(0);
+        let end = balances.len().min(start.saturating_add(limit));
+        let page = &balances[start..end];
+        // Unlike by-owner pages, the accounts after the page are known, so the last page is
+        // never followed by an empty one
+        let cursor = (end < balances.len())
+            .then(|| page.last())
+            .flatten()
+            .map(|(amount, address)| format!("{}:{}", amount, address));
+        let accounts = page
+            .iter()
+            .map(|(amount, address)| new_token_account_balance(*amount, address, decimals))
+            .collect();
+        Ok(new_response(
+            &bank,
+            RpcTokenAccountBalancesPage { accounts, cursor },
+        ))
+    }
+
+    pub fn get_token_account_count_by_mint(
+        &self,
+        mint: &Pubkey,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<RpcResponse<usize>> {
+        let bank = self.bank(commitment);
+        let (balances, _decimals) = self.get_token_account_balances(&bank, mint)?;
+        Ok(new_response(&bank, balances.len()))
+    }
+
+    /// Returns the balances and addresses of the accounts of `mint`, in decreasing balance order
+    /// and increasing address order among equal balances, along with the decimals of `mint`
+    fn get_token_account_balances(
+        &self,
+        bank: &Arc<Bank>,
+        mint: &Pubkey,
+    ) -> Result<(Vec<(u64, Pubkey)>, u8)> {
+        let (mint_owner, decimals) = get_mint_owner_and_decimals(bank, mint)?;
         if !is_known_spl_token_id(&mint_owner) {
             return Err(Error::invalid_params(
                 "Invalid param: not a Token mint".to_string(),
             ));
         }
-        let mut token_balances: Vec<RpcTokenAccountBalance> = self
-            .get_filtered_spl_token_accounts_by_mint(&bank, &mint_owner, mint, vec![])?
+        let mut balances: Vec<(u64, Pubkey)> = self
+            .get_filtered_spl_token_accounts_by_mint(bank, &mint_owner, mint, vec![])?
             .into_iter()
             .map(|(address, account)| {
                 let amount = StateWithExtensions::<TokenAccount>::unpack(account.data())
                     .map(|account| account.base.amount)
                     .unwrap_or(0);
-                let amount = token_amount_to_ui_amount(amount, decimals);
-                RpcTokenAccountBalance {
-                    address: address.to_string(),
-                    amount,
-                }
+                (amount, address)
             })
             .collect();
-        token_balances.sort_by(|a, b| {
-            a.amount
-                .amount
-                .parse::<u64>()
-                .unwrap()
-                .cmp(&b.amount.amount.parse::<u64>().unwrap())
-                .reverse()
+        balances.sort_unstable_by(|(a_amount, a_address), (b_amount, b_address)| {
+            b_amount.cmp(a_amount).then(a_address.cmp(b_address))
         });
-        token_balances.truncate(NUM_LARGEST_ACCOUNTS);
-        Ok(new_response(&bank, token_balances))
+        Ok((balances, decimals))
     }
 
     pub fn get_token_accounts_by_owner(
@@ -1898,22 +2708,89 @@ impl JsonRpcRequestProcessor {
             owner,
             filters,
         )?;
-        let accounts = if encoding == UiAccountEncoding::JsonParsed {
-            get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
-        } else {
-            keyed_accounts
-                .into_iter()
-                .map(|(pubkey, account)| {
-                    Ok(RpcKeyedAccount {
-                        pubkey: pubkey.to_string(),
-                        account: encode_account(&account, &pubkey, encoding, data_slice_config)?,
-                    })
-                })
-                .collect::<Result<Vec<_>>>()?
-        };
+        let accounts = encode_token_accounts(&bank, keyed_accounts, encoding, data_slice_config)?;
         Ok(new_response(&bank, accounts))
     }
 
+    /// Returns up to `limit` token accounts of `owner`, in pubkey order, starting after pubkey
+    /// `cursor`. Only the accounts of `owner` are scanned if the by-owner secondary index is
+    /// enabled.
+    pub fn get_token_accounts_by_owner_paginated(
+        &self,
+        owner: &Pubkey,
+        token_account_filter: TokenAccountsFilter,
+        config: Option<RpcAccountInfoConfig>,
+        limit: usize,
+        cursor: Option<Pubkey>,
+    ) -> Result<RpcResponse<RpcKeyedAccountsPage>> {
+        let RpcAccountInfoConfig {
+            encoding,
+            data_slice: data_slice_config,
+            commitment,
+            min_context_slot,
+        } = config.unwrap_or_default();
+        let bank = self.get_bank_with_config(RpcContextConfig {
+            commitment,
+            min_context_slot,
+        })?;
+        let encoding = encoding.unwrap_or(UiAccountEncoding::Binary);
+        check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
+        let (token_program_id, mint) = get_token_program_id_and_mint(&bank, token_account_filter)?;
+
+        let mut filters = vec![];
+        if let Some(mint) = mint {
+            // Optional filter on Mint address
+            filters.push(RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Bytes(mint.to_bytes().into()),
+                encoding: None,
+            }));
+        }
+
+        let keyed_accounts = self.get_filtered_spl_token_accounts_by_owner_after(
+            &bank,
+            &token_program_id,
+            owner,
+            filters,
+            cursor.as_ref(),
+            limit,
+        )?;
+        // A full page may be followed by more accounts. If not, the next page is empty.
+        let cursor = (keyed_accounts.len() >= limit)
+            .then(|| keyed_accounts.last().map(|(pubkey, _)| pubkey.to_string()))
+            .flatten();
+        let accounts = encode_token_accounts(&bank, keyed_accounts, encoding, data_slice_config)?;
+        Ok(new_response(
+            &bank,
+            RpcKeyedAccountsPage { accounts, cursor },
+        ))
+    }
+
+    pub fn get_token_account_count_by_owner(
+        &self,
+        owner: &Pubkey,
+        token_account_filter: TokenAccountsFilter,
+        config: RpcContextConfig,
+    ) -> Result<RpcResponse<usize>> {
+        let bank = self.get_bank_with_config(config)?;
+        let (token_program_id, mint) = get_token_program_id_and_mint(&bank, token_account_filter)?;
+
+        let mut filters = vec![];
+        if let Some(mint) = mint {
+            // Optional filter on Mint address
+            filters.push(RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Bytes(mint.to_bytes().into()),
+                encoding: None,
+            }));
+        }
+
+        let count = self
+            .get_filtered_spl_token_accounts_by_owner(&bank, &token_program_id, owner, filters)?
+            .len();
+        Ok(new_response(&bank, count))
+    }
+
     pub fn get_token_accounts_by_delegate(
         &self,
         delegate: &Pubkey,
@@ -1956,19 +2833,7 @@ impl JsonRpcRequestProcessor {
             filters.push(RpcFilterType::TokenAccountState);
             self.get_filtered_program_accounts(&bank, &token_program_id, filters)?
         };
-        let accounts = if encoding == UiAccountEncoding::JsonParsed {
-            get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
-        } else {
-            keyed_accounts
-                .into_iter()
-                .map(|(pubkey, account)| {
-                    Ok(RpcKeyedAccount {
-                        pubkey: pubkey.to_string(),
-                        account: encode_account(&account, &pubkey, encoding, data_slice_config)?,
-                    })
-                })
-                .collect::<Result<Vec<_>>>()?
-        };
+        let accounts = encode_token_accounts(&bank, keyed_accounts, encoding, data_slice_config)?;
         Ok(new_response(&bank, accounts))
     }
 
@@ -1989,6 +2854,7 @@ impl JsonRpcRequestProcessor {
             .config
             .account_indexes
             .contains(&AccountIndex::ProgramId)
+            || bank.account_index_ready(&AccountIndex::ProgramId)
         {
             if !self.config.account_indexes.include_key(program_id) {
                 return Err(RpcCustomError::KeyExcludedFromSecondaryIndex {
@@ -2048,6 +2914,7 @@ impl JsonRpcRequestProcessor {
             .config
             .account_indexes
             .contains(&AccountIndex::SplTokenOwner)
+            || bank.account_index_ready(&AccountIndex::SplTokenOwner)
         {
             if !self.config.account_indexes.include_key(owner_key) {
                 return Err(RpcCustomError::KeyExcludedFromSecondaryIndex {
@@ -2074,6 +2941,59 @@ impl JsonRpcRequestProcessor {
         }
     }
 
+    /// Get up to `limit` spl-token accounts by owner address, in pubkey order, starting after
+    /// pubkey `after`
+    fn get_filtered_spl_token_accounts_by_owner_after(
+        &self,
+        bank: &Arc<Bank>,
+        program_id: &Pubkey,
+        owner_key: &Pubkey,
+        mut filters: Vec<RpcFilterType>,
+        after: Option<&Pubkey>,
+        limit: usize,
+    ) -> RpcCustomResult<Vec<(Pubkey, AccountSharedData)>> {
+        // Redundant with the by-owner accounts index, see `get_filtered_spl_token_accounts_by_owner()`
+        //
+        // Filter on Token Account state
+        filters.push(RpcFilterType::TokenAccountState);
+        // Filter on Owner address
+        filters.push(RpcFilterType::Memcmp(Memcmp {
+            offset: SPL_TOKEN_ACCOUNT_OWNER_OFFSET,
+            bytes: MemcmpEncodedBytes::Bytes(owner_key.to_bytes().into()),
+            encoding: None,
+        }));
+        let filter_closure = |account: &AccountSharedData| {
+            account.owner() == program_id
+                && filters
+                    .iter()
+                    .all(|filter_type| filter_type.allows(account))
+        };
+
+        if self
+            .config
+            .account_indexes
+            .contains(&AccountIndex::SplTokenOwner)
+            || bank.account_index_ready(&AccountIndex::SplTokenOwner)
+        {
+            if !self.config.account_indexes.include_key(owner_key) {
+                return Err(RpcCustomError::KeyExcludedFromSecondaryIndex {
+                    index_key: owner_key.to_string(),
+                });
+            }
+            bank.get_filtered_indexed_accounts_after(
+                &IndexKey::SplTokenOwner(*owner_key),
+                filter_closure,
+                after,
+                limit,
+            )
+        } else {
+            bank.get_filtered_program_accounts_after(program_id, filter_closure, after, limit)
+        }
+        .map_err(|e| RpcCustomError::ScanError {
+            message: e.to_string(),
+        })
+    }
+
     /// Get an iterator of spl-token accounts by mint address
     fn get_filtered_spl_token_accounts_by_mint(
         &self,
@@ -2099,6 +3019,7 @@ impl JsonRpcRequestProcessor {
             .config
             .account_indexes
             .contains(&AccountIndex::SplTokenMint)
+            || bank.account_index_ready(&AccountIndex::SplTokenMint)
         {
             if !self.config.account_indexes.include_key(mint_key) {
                 return Err(RpcCustomError::KeyExcludedFromSecondaryIndex {
@@ -2161,7 +3082,7 @@ impl JsonRpcRequestProcessor {
     }
 }
 
-fn optimize_filters(filters: &mut [RpcFilterType]) {
+pub(crate) fn optimize_filters(filters: &mut [RpcFilterType]) {
     filters.iter_mut().for_each(|filter_type| {
         if let RpcFilterType::Memcmp(compare) = filter_type {
             use MemcmpEncodedBytes::*;
@@ -2195,7 +3116,7 @@ fn verify_transaction(
     Ok(())
 }
 
-fn verify_filter(input: &RpcFilterType) -> Result<()> {
+pub(crate) fn verify_filter(input: &RpcFilterType) -> Result<()> {
     input
         .verify()
         .map_err(|e| Error::invalid_params(format!("Invalid param: {:?}", e)))
@@ -2234,6 +3155,55 @@ fn verify_token_account_filter(
     }
 }
 
+/// How the accounts matching a token accounts query are returned
+#[derive(Debug, PartialEq, Eq)]
+enum TokenAccountsQuery {
+    All,
+    Page {
+        limit: usize,
+        cursor: Option<String>,
+    },
+    Count,
+}
+
+fn verify_token_accounts_page_config(
+    page_config: RpcTokenAccountsPageConfig,
+) -> Result<TokenAccountsQuery> {
+    let RpcTokenAccountsPageConfig {
+        limit,
+        cursor,
+        count_only,
+    } = page_config;
+    if count_only.unwrap_or_default() {
+        if limit.is_some() || cursor.is_some() {
+            return Err(Error::invalid_params(
+                "countOnly cannot be combined with limit or cursor".to_string(),
+            ));
+        }
+        return Ok(TokenAccountsQuery::Count);
+    }
+    if limit.is_none() && cursor.is_none() {
+        return Ok(TokenAccountsQuery::All);
+    }
+    let limit = limit.unwrap_or(MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT);
+    if limit == 0 || limit > MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT {
+        return Err(Error::invalid_params(format!(
+            "Invalid limit; max {}",
+            MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT
+        )));
+    }
+    Ok(TokenAccountsQuery::Page { limit, cursor })
+}
+
+/// Parses a `getTokenLargestAccounts` cursor, made of the balance and address of the last account
+/// of the previous page
+fn verify_token_balance_cursor(input: &str) -> Result<(u64, Pubkey)> {
+    input
+        .split_once(':')
+        .and_then(|(amount, address)| Some((amount.parse().ok()?, address.parse().ok()?)))
+        .ok_or_else(|| Error::invalid_params(format!("Invalid param: cursor {}", input)))
+}
+
 fn verify_and_parse_signatures_for_address_params(
     address: String,
     before: Option<String>,
@@ -2309,6 +3279,184 @@ fn get_encoded_account(
     }
 }
 
+fn encode_program_accounts(
+    bank: &Arc<Bank>,
+    program_id: &Pubkey,
+    keyed_accounts: Vec<(Pubkey, AccountSharedData)>,
+    encoding: UiAccountEncoding,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Result<Vec<RpcKeyedAccount>> {
+    if is_known_spl_token_id(program_id) && encoding == UiAccountEncoding::JsonParsed {
+        Ok(get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect())
+    } else {
+        keyed_accounts
+            .into_iter()
+            .map(|(pubkey, account)| {
+                Ok(RpcKeyedAccount {
+                    pubkey: pubkey.to_string(),
+                    account: encode_account(&account, &pubkey, encoding, data_slice)?,
+                })
+            })
+            .collect()
+    }
+}
+
+type SimulatedAccounts = (
+    Option<Vec<Option<UiAccount>>>,
+    Option<Vec<Option<RpcAccountDiff>>>,
+);
+
+/// Returns the state of, or the changes to, the accounts requested by `config_accounts` after a
+/// simulated transaction. `pre_account` returns the state of an account before the transaction.
+fn simulated_accounts(
+    config_accounts: &RpcSimulateTransactionAccountsConfig,
+    result: &transaction::Result<()>,
+    post_simulation_accounts: &[TransactionAccount],
+    pre_account: impl Fn(&Pubkey) -> AccountSharedData,
+) -> Result<SimulatedAccounts> {
+    let accounts_encoding = config_accounts
+        .encoding
+        .unwrap_or(UiAccountEncoding::Base64);
+
+    if accounts_encoding == UiAccountEncoding::Binary
+        || accounts_encoding == UiAccountEncoding::Base58
+    {
+        return Err(Error::invalid_params("base58 encoding not supported"));
+    }
+
+    if result.is_err() {
+        let accounts = Some(vec![None; config_accounts.addresses.len()]);
+        return Ok(if config_accounts.diff {
+            (None, accounts)
+        } else {
+            (accounts, None)
+        });
+    }
+
+    let addresses = config_accounts
+        .addresses
+        .iter()
+        .map(|address_str| verify_pubkey(address_str))
+        .collect::<Result<Vec<_>>>()?;
+    let post_simulation_account = |address: &Pubkey| {
+        post_simulation_accounts
+            .iter()
+            .find(|(key, _account)| key == address)
+            .map(|(_pubkey, account)| account)
+    };
+    if config_accounts.diff {
+        let account_diffs = addresses
+            .iter()
+            .map(|address| {
+                post_simulation_account(address)
+                    .map(|post_account| diff_account(&pre_account(address), post_account))
+            })
+            .collect();
+        Ok((None, Some(account_diffs)))
+    } else {
+        let accounts = addresses
+            .iter()
+            .map(|address| {
+                post_simulation_account(address)
+                    .map(|account| encode_account(account, address, accounts_encoding, None))
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok((Some(accounts), None))
+    }
+}
+
+/// Returns the writable accounts of a simulated transaction whose state was changed by it, none
+/// if it failed. `pre_account` returns the state of an account before the transaction.
+fn account_state_diffs(
+    writable_accounts: &HashSet<Pubkey>,
+    result: &transaction::Result<()>,
+    post_simulation_accounts: &[TransactionAccount],
+    pre_account: impl Fn(&Pubkey) -> Option<AccountSharedData>,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Vec<RpcAccountStateDiff> {
+    if result.is_err() {
+        return vec![];
+    }
+    let encode = |pubkey: &Pubkey, account: &AccountSharedData| {
+        (account.lamports() > 0).then(|| {
+            UiAccount::encode(pubkey, account, UiAccountEncoding::Base64, None, data_slice)
+        })
+    };
+    post_simulation_accounts
+        .iter()
+        .filter(|(pubkey, _)| writable_accounts.contains(pubkey))
+        .filter_map(|(pubkey, post_account)| {
+            let pre_account = pre_account(pubkey).unwrap_or_default();
+            // The rent epoch of a loaded account may be advanced by the simulation, which is
+            // not a change made by the transaction
+            let unchanged = pre_account.lamports() == post_account.lamports()
+                && pre_account.owner() == post_account.owner()
+                && pre_account.executable() == post_account.executable()
+                && pre_account.data() == post_account.data();
+            (!unchanged).then(|| RpcAccountStateDiff {
+                pubkey: pubkey.to_string(),
+                pre: encode(pubkey, &pre_account),
+                post: encode(pubkey, post_account),
+                lamports_delta: (post_account.lamports() as i64)
+                    .saturating_sub(pre_account.lamports() as i64),
+            })
+        })
+        .collect()
+}
+
+/// Returns the accounts of `transaction` that it may write
+fn writable_accounts(transaction: &SanitizedTransaction) -> HashSet<Pubkey> {
+    let message = transaction.message();
+    message
+        .account_keys()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| message.is_writable(*index))
+        .map(|(_, pubkey)| *pubkey)
+        .collect()
+}
+
+/// Describes the state of `post_account` along with the ranges of its data that differ from
+/// the data of `pre_account`
+fn diff_account(
+    pre_account: &AccountSharedData,
+    post_account: &AccountSharedData,
+) -> RpcAccountDiff {
+    RpcAccountDiff {
+        lamports: post_account.lamports(),
+        owner: post_account.owner().to_string(),
+        executable: post_account.executable(),
+        rent_epoch: post_account.rent_epoch(),
+        data_len: post_account.data().len(),
+        data_changes: diff_account_data(pre_account.data(), post_account.data()),
+    }
+}
+
+/// Returns the ranges of `post_data` that differ from `pre_data`, treating bytes past the end
+/// of `pre_data` as zero
+fn diff_account_data(pre_data: &[u8], post_data: &[u8]) -> Vec<RpcAccountDataChange> {
+    let pre_byte = |offset: usize| pre_data.get(offset).copied().unwrap_or_default();
+    let mut changed_ranges: Vec<Range<usize>> = vec![];
+    for (offset, _) in post_data
+        .iter()
+        .enumerate()
+        .filter(|(offset, byte)| **byte != pre_byte(*offset))
+    {
+        match changed_ranges.last_mut() {
+            Some(range) if offset <= range.end + ACCOUNT_DIFF_MERGE_GAP => range.end = offset + 1,
+            _ => changed_ranges.push(offset..offset + 1),
+        }
+    }
+    changed_ranges
+        .into_iter()
+        .map(|range| RpcAccountDataChange {
+            offset: range.start,
+            data: base64::encode(&post_data[range]),
+        })
+        .collect()
+}
+
 fn encode_account<T: ReadableAccount>(
     account: &T,
     pubkey: &Pubkey,
@@ -2445,6 +3593,38 @@ fn get_spl_token_mint_filter(program_id: &Pubkey, filters: &[RpcFilterType]) ->
 
 /// Analyze a passed Pubkey that may be a Token program id or Mint address to determine the program
 /// id and optional Mint
+fn new_token_account_balance(
+    amount: u64,
+    address: &Pubkey,
+    decimals: u8,
+) -> RpcTokenAccountBalance {
+    RpcTokenAccountBalance {
+        address: address.to_string(),
+        amount: token_amount_to_ui_amount(amount, decimals),
+    }
+}
+
+fn encode_token_accounts(
+    bank: &Arc<Bank>,
+    keyed_accounts: Vec<(Pubkey, AccountSharedData)>,
+    encoding: UiAccountEncoding,
+    data_slice_config: Option<UiDataSliceConfig>,
+) -> Result<Vec<RpcKeyedAccount>> {
+    if encoding == UiAccountEncoding::JsonParsed {
+        Ok(get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect())
+    } else {
+        keyed_accounts
+            .into_iter()
+            .map(|(pubkey, account)| {
+                Ok(RpcKeyedAccount {
+                    pubkey: pubkey.to_string(),
+                    account: encode_account(&account, &pubkey, encoding, data_slice_config)?,
+                })
+            })
+            .collect()
+    }
+}
+
 fn get_token_program_id_and_mint(
     bank: &Arc<Bank>,
     token_account_filter: TokenAccountsFilter,
@@ -2471,6 +3651,56 @@ fn get_token_program_id_and_mint(
     }
 }
 
+fn stake_activation_state(
+    effective: u64,
+    activating: u64,
+    deactivating: u64,
+) -> StakeActivationState {
+    if deactivating > 0 {
+        StakeActivationState::Deactivating
+    } else if activating > 0 {
+        StakeActivationState::Activating
+    } else if effective > 0 {
+        StakeActivationState::Active
+    } else {
+        StakeActivationState::Inactive
+    }
+}
+
+fn rpc_stake_snapshot(snapshot: EpochStakeSnapshot) -> RpcStakeSnapshot {
+    RpcStakeSnapshot {
+        epoch: snapshot.epoch,
+        total_activated_stake: snapshot.total_activated_stake,
+        validators: snapshot
+            .validators
+            .into_iter()
+            .map(|validator| RpcValidatorStakeSnapshot {
+                vote_pubkey: validator.vote_pubkey.to_string(),
+                node_pubkey: validator.node_pubkey.map(|pubkey| pubkey.to_string()),
+                activated_stake: validator.activated_stake,
+                delegations: validator
+                    .delegations
+                    .into_iter()
+                    .map(|delegation| RpcStakeDelegationSnapshot {
+                        stake_pubkey: delegation.stake_pubkey.to_string(),
+                        stake: delegation.stake,
+                        activation_epoch: delegation.activation_epoch,
+                        deactivation_epoch: delegation.deactivation_epoch,
+                        state: stake_activation_state(
+                            delegation.effective,
+                            delegation.activating,
+                            delegation.deactivating,
+                        ),
+                        effective: delegation.effective,
+                        activating: delegation.activating,
+                        deactivating: delegation.deactivating,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
 fn _send_transaction(
     meta: JsonRpcRequestProcessor,
     signature: Signature,
@@ -2524,6 +3754,9 @@ pub mod rpc_minimal {
         #[rpc(meta, name = "getHealth")]
         fn get_health(&self, meta: Self::Metadata) -> Result<String>;
 
+        #[rpc(meta, name = "getHealthDetailed")]
+        fn get_health_detailed(&self, meta: Self::Metadata) -> Result<RpcHealthDetailed>;
+
         #[rpc(meta, name = "getIdentity")]
         fn get_identity(&self, meta: Self::Metadata) -> Result<RpcIdentity>;
 
@@ -2614,6 +3847,11 @@ pub mod rpc_minimal {
             }
         }
 
+        fn get_health_detailed(&self, meta: Self::Metadata) -> Result<RpcHealthDetailed> {
+            debug!("get_health_detailed rpc request received");
+            Ok(meta.get_health_detailed())
+        }
+
         fn get_identity(&self, meta: Self::Metadata) -> Result<RpcIdentity> {
             debug!("get_identity rpc request received");
             Ok(RpcIdentity {
@@ -2984,6 +4222,14 @@ pub mod rpc_accounts {
             config: Option<RpcProgramAccountsConfig>,
         ) -> Result<OptionalContext<Vec<RpcKeyedAccount>>>;
 
+        #[rpc(meta, name = "getProgramAccountsPaginated")]
+        fn get_program_accounts_paginated(
+            &self,
+            meta: Self::Metadata,
+            program_id_str: String,
+            config: Option<RpcProgramAccountsPaginatedConfig>,
+        ) -> Result<RpcResponse<RpcKeyedAccountsPage>>;
+
         #[rpc(meta, name = "getBlockCommitment")]
         fn get_block_commitment(
             &self,
@@ -3038,8 +4284,8 @@ pub mod rpc_accounts {
             &self,
             meta: Self::Metadata,
             mint_str: String,
-            commitment: Option<CommitmentConfig>,
-        ) -> Result<RpcResponse<Vec<RpcTokenAccountBalance>>>;
+            config: Option<RpcTokenLargestAccountsConfig>,
+        ) -> Result<RpcResponse<RpcTokenAccountBalances>>;
 
         #[rpc(meta, name = "getTokenAccountsByOwner")]
         fn get_token_accounts_by_owner(
@@ -3047,8 +4293,8 @@ pub mod rpc_accounts {
             meta: Self::Metadata,
             owner_str: String,
             token_account_filter: RpcTokenAccountsFilter,
-            config: Option<RpcAccountInfoConfig>,
-        ) -> Result<RpcResponse<Vec<RpcKeyedAccount>>>;
+            config: Option<RpcTokenAccountsConfig>,
+        ) -> Result<RpcResponse<RpcTokenAccounts>>;
 
         #[rpc(meta, name = "getTokenAccountsByDelegate")]
         fn get_token_accounts_by_delegate(
@@ -3135,6 +4381,50 @@ pub mod rpc_accounts {
             meta.get_program_accounts(&program_id, config, filters, with_context)
         }
 
+        fn get_program_accounts_paginated(
+            &self,
+            meta: Self::Metadata,
+            program_id_str: String,
+            config: Option<RpcProgramAccountsPaginatedConfig>,
+        ) -> Result<RpcResponse<RpcKeyedAccountsPage>> {
+            debug!(
+                "get_program_accounts_paginated rpc request received: {:?}",
+                program_id_str
+            );
+            let program_id = verify_pubkey(&program_id_str)?;
+            let RpcProgramAccountsPaginatedConfig {
+                filters,
+                account_config,
+                limit,
+                cursor,
+            } = config.unwrap_or_default();
+            let filters = filters.unwrap_or_default();
+            if filters.len() > MAX_GET_PROGRAM_ACCOUNT_FILTERS {
+                return Err(Error::invalid_params(format!(
+                    "Too many filters provided; max {}",
+                    MAX_GET_PROGRAM_ACCOUNT_FILTERS
+                )));
+            }
+            for filter in &filters {
+                verify_filter(filter)?;
+            }
+            let limit = limit.unwrap_or(MAX_GET_PROGRAM_ACCOUNTS_PAGE_LIMIT);
+            if limit == 0 || limit > MAX_GET_PROGRAM_ACCOUNTS_PAGE_LIMIT {
+                return Err(Error::invalid_params(format!(
+                    "Invalid limit; max {}",
+                    MAX_GET_PROGRAM_ACCOUNTS_PAGE_LIMIT
+                )));
+            }
+            let cursor = cursor.map(|cursor| verify_pubkey(&cursor)).transpose()?;
+            meta.get_program_accounts_paginated(
+                &program_id,
+                Some(account_config),
+                filters,
+                limit,
+                cursor,
+            )
+        }
+
         fn get_block_commitment(
             &self,
             meta: Self::Metadata,
@@ -3205,14 +4495,46 @@ pub mod rpc_accounts {
             &self,
             meta: Self::Metadata,
             mint_str: String,
-            commitment: Option<CommitmentConfig>,
-        ) -> Result<RpcResponse<Vec<RpcTokenAccountBalance>>> {
+            config: Option<RpcTokenLargestAccountsConfig>,
+        ) -> Result<RpcResponse<RpcTokenAccountBalances>> {
             debug!(
                 "get_token_largest_accounts rpc request received: {:?}",
                 mint_str
             );
             let mint = verify_pubkey(&mint_str)?;
-            meta.get_token_largest_accounts(&mint, commitment)
+            let RpcTokenLargestAccountsConfig {
+                commitment,
+                page_config,
+            } = config.unwrap_or_default();
+            Ok(match verify_token_accounts_page_config(page_config)? {
+                TokenAccountsQuery::All => {
+                    let RpcResponse { context, value } =
+                        meta.get_token_largest_accounts(&mint, commitment)?;
+                    RpcResponse {
+                        context,
+                        value: RpcTokenAccountBalances::Balances(value),
+                    }
+                }
+                TokenAccountsQuery::Page { limit, cursor } => {
+                    let cursor = cursor
+                        .map(|cursor| verify_token_balance_cursor(&cursor))
+                        .transpose()?;
+                    let RpcResponse { context, value } = meta
+                        .get_token_largest_accounts_paginated(&mint, commitment, limit, cursor)?;
+                    RpcResponse {
+                        context,
+                        value: RpcTokenAccountBalances::Page(value),
+                    }
+                }
+                TokenAccountsQuery::Count => {
+                    let RpcResponse { context, value } =
+                        meta.get_token_account_count_by_mint(&mint, commitment)?;
+                    RpcResponse {
+                        context,
+                        value: RpcTokenAccountBalances::Count(RpcAccountsCount { count: value }),
+                    }
+                }
+            })
         }
 
         fn get_token_accounts_by_owner(
@@ -3220,15 +4542,60 @@ pub mod rpc_accounts {
             meta: Self::Metadata,
             owner_str: String,
             token_account_filter: RpcTokenAccountsFilter,
-            config: Option<RpcAccountInfoConfig>,
-        ) -> Result<RpcResponse<Vec<RpcKeyedAccount>>> {
+            config: Option<RpcTokenAccountsConfig>,
+        ) -> Result<RpcResponse<RpcTokenAccounts>> {
             debug!(
                 "get_token_accounts_by_owner rpc request received: {:?}",
                 owner_str
             );
             let owner = verify_pubkey(&owner_str)?;
             let token_account_filter = verify_token_account_filter(token_account_filter)?;
-            meta.get_token_accounts_by_owner(&owner, token_account_filter, config)
+            let RpcTokenAccountsConfig {
+                account_config,
+                page_config,
+            } = config.unwrap_or_default();
+            Ok(match verify_token_accounts_page_config(page_config)? {
+                TokenAccountsQuery::All => {
+                    let RpcResponse { context, value } = meta.get_token_accounts_by_owner(
+                        &owner,
+                        token_account_filter,
+                        Some(account_config),
+                    )?;
+                    RpcResponse {
+                        context,
+                        value: RpcTokenAccounts::Accounts(value),
+                    }
+                }
+                TokenAccountsQuery::Page { limit, cursor } => {
+                    let cursor = cursor.map(|cursor| verify_pubkey(&cursor)).transpose()?;
+                    let RpcResponse { context, value } = meta
+                        .get_token_accounts_by_owner_paginated(
+                            &owner,
+                            token_account_filter,
+                            Some(account_config),
+                            limit,
+                            cursor,
+                        )?;
+                    RpcResponse {
+                        context,
+                        value: RpcTokenAccounts::Page(value),
+                    }
+                }
+                TokenAccountsQuery::Count => {
+                    let RpcResponse { context, value } = meta.get_token_account_count_by_owner(
+                        &owner,
+                        token_account_filter,
+                        RpcContextConfig {
+                            commitment: account_config.commitment,
+                            min_context_slot: account_config.min_context_slot,
+                        },
+                    )?;
+                    RpcResponse {
+                        context,
+                        value: RpcTokenAccounts::Count(RpcAccountsCount { count: value }),
+                    }
+                }
+            })
         }
 
         fn get_token_accounts_by_delegate(
@@ -3275,6 +4642,48 @@ pub mod rpc_full {
             limit: Option<usize>,
         ) -> Result<Vec<RpcPerfSample>>;
 
+        #[rpc(meta, name = "getStakeSnapshot")]
+        fn get_stake_snapshot(
+            &self,
+            meta: Self::Metadata,
+            epoch: Epoch,
+        ) -> Result<Option<RpcStakeSnapshot>>;
+
+        #[rpc(meta, name = "getStakeMinimumDelegationHistory")]
+        fn get_stake_minimum_delegation_history(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcContextConfig>,
+        ) -> Result<Vec<RpcEpochStakeHistoryEntry>>;
+
+        #[rpc(meta, name = "getEpochStakeBreakdown")]
+        fn get_epoch_stake_breakdown(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcEpochConfig>,
+        ) -> Result<Option<RpcEpochStakeBreakdown>>;
+
+        #[rpc(meta, name = "getVoteLatencies")]
+        fn get_vote_latencies(
+            &self,
+            meta: Self::Metadata,
+            vote_pubkeys: Option<Vec<String>>,
+        ) -> Result<RpcVoteLatencies>;
+
+        #[rpc(meta, name = "getSlotTimeEstimates")]
+        fn get_slot_time_estimates(
+            &self,
+            meta: Self::Metadata,
+            slots: Vec<Slot>,
+            config: Option<RpcContextConfig>,
+        ) -> Result<RpcResponse<RpcSlotTimeEstimates>>;
+
+        #[rpc(meta, name = "getBufferedPrioritizationFees")]
+        fn get_buffered_prioritization_fees(
+            &self,
+            meta: Self::Metadata,
+        ) -> Result<RpcBufferedPrioritizationFees>;
+
         #[rpc(meta, name = "getSignatureStatuses")]
         fn get_signature_statuses(
             &self,
@@ -3314,6 +4723,14 @@ pub mod rpc_full {
             config: Option<RpcSimulateTransactionConfig>,
         ) -> Result<RpcResponse<RpcSimulateTransactionResult>>;
 
+        #[rpc(meta, name = "simulateBundle")]
+        fn simulate_bundle(
+            &self,
+            meta: Self::Metadata,
+            data: Vec<String>,
+            config: Option<RpcSimulateTransactionConfig>,
+        ) -> Result<RpcResponse<Vec<RpcSimulateTransactionResult>>>;
+
         #[rpc(meta, name = "minimumLedgerSlot")]
         fn minimum_ledger_slot(&self, meta: Self::Metadata) -> Result<Slot>;
 
@@ -3330,7 +4747,8 @@ pub mod rpc_full {
             &self,
             meta: Self::Metadata,
             slot: Slot,
-        ) -> BoxFuture<Result<Option<UnixTimestamp>>>;
+            config: Option<RpcBlockTimeConfig>,
+        ) -> BoxFuture<Result<Option<RpcBlockTime>>>;
 
         #[rpc(meta, name = "getBlocks")]
         fn get_blocks(
@@ -3347,7 +4765,7 @@ pub mod rpc_full {
             meta: Self::Metadata,
             start_slot: Slot,
             limit: usize,
-            commitment: Option<CommitmentConfig>,
+            config: Option<RpcBlocksWithLimitConfig>,
         ) -> BoxFuture<Result<Vec<Slot>>>;
 
         #[rpc(meta, name = "getTransaction")]
@@ -3430,6 +4848,75 @@ pub mod rpc_full {
                 .collect())
         }
 
+        fn get_stake_snapshot(
+            &self,
+            meta: Self::Metadata,
+            epoch: Epoch,
+        ) -> Result<Option<RpcStakeSnapshot>> {
+            debug!("get_stake_snapshot rpc request received: {:?}", epoch);
+            meta.get_stake_snapshot(epoch)
+        }
+
+        fn get_stake_minimum_delegation_history(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcContextConfig>,
+        ) -> Result<Vec<RpcEpochStakeHistoryEntry>> {
+            debug!("get_stake_minimum_delegation_history rpc request received");
+            meta.get_stake_minimum_delegation_history(config.unwrap_or_default())
+        }
+
+        fn get_epoch_stake_breakdown(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcEpochConfig>,
+        ) -> Result<Option<RpcEpochStakeBreakdown>> {
+            debug!(
+                "get_epoch_stake_breakdown rpc request received: {:?}",
+                config
+            );
+            meta.get_epoch_stake_breakdown(config.unwrap_or_default())
+        }
+
+        fn get_vote_latencies(
+            &self,
+            meta: Self::Metadata,
+            vote_pubkeys: Option<Vec<String>>,
+        ) -> Result<RpcVoteLatencies> {
+            debug!(
+                "get_vote_latencies rpc request received: {:?}",
+                vote_pubkeys
+            );
+            meta.get_vote_latencies(vote_pubkeys)
+        }
+
+        fn get_slot_time_estimates(
+            &self,
+            meta: Self::Metadata,
+            slots: Vec<Slot>,
+            config: Option<RpcContextConfig>,
+        ) -> Result<RpcResponse<RpcSlotTimeEstimates>> {
+            debug!(
+                "get_slot_time_estimates rpc request received: {:?} slots",
+                slots.len()
+            );
+            if slots.len() > MAX_GET_SLOT_TIME_ESTIMATES_SLOTS {
+                return Err(Error::invalid_params(format!(
+                    "Too many inputs provided; max {}",
+                    MAX_GET_SLOT_TIME_ESTIMATES_SLOTS
+                )));
+            }
+            meta.get_slot_time_estimates(slots, config.unwrap_or_default())
+        }
+
+        fn get_buffered_prioritization_fees(
+            &self,
+            meta: Self::Metadata,
+        ) -> Result<RpcBufferedPrioritizationFees> {
+            debug!("get_buffered_prioritization_fees rpc request received");
+            Ok(meta.get_buffered_prioritization_fees())
+        }
+
         fn get_cluster_nodes(&self, meta: Self::Metadata) -> Result<Vec<RpcContactInfo>> {
             debug!("get_cluster_nodes rpc request received");
             let cluster_info = &meta.cluster_info;
@@ -3582,6 +5069,7 @@ pub mod rpc_full {
                 encoding,
                 max_retries,
                 min_context_slot,
+                simulation_bank,
             } = config.unwrap_or_default();
             let tx_encoding = encoding.unwrap_or(UiTransactionEncoding::Base58);
             let binary_encoding = tx_encoding.into_binary_encoding().ok_or_else(|| {
@@ -3595,10 +5083,13 @@ pub mod rpc_full {
 
             let preflight_commitment =
                 preflight_commitment.map(|commitment| CommitmentConfig { commitment });
-            let preflight_bank = &*meta.get_bank_with_config(RpcContextConfig {
-                commitment: preflight_commitment,
-                min_context_slot,
-            })?;
+            let preflight_bank = &*meta.get_simulation_bank(
+                simulation_bank,
+                RpcContextConfig {
+                    commitment: preflight_commitment,
+                    min_context_slot,
+                },
+            )?;
 
             let transaction = sanitize_transaction(unsanitized_tx, preflight_bank)?;
             let signature = *transaction.signature();
@@ -3668,6 +5159,8 @@ pub mod rpc_full {
                             err: Some(err),
                             logs: Some(logs),
                             accounts: None,
+                            account_diffs: None,
+                            account_state_diffs: None,
                             units_consumed: Some(units_consumed),
                             return_data: return_data.map(|return_data| return_data.into()),
                         },
@@ -3700,6 +5193,9 @@ pub mod rpc_full {
                 encoding,
                 accounts: config_accounts,
                 min_context_slot,
+                return_account_diffs,
+                account_diffs_data_slice,
+                simulation_bank,
             } = config.unwrap_or_default();
             let tx_encoding = encoding.unwrap_or(UiTransactionEncoding::Base58);
             let binary_encoding = tx_encoding.into_binary_encoding().ok_or_else(|| {
@@ -3711,10 +5207,13 @@ pub mod rpc_full {
             let (_, mut unsanitized_tx) =
                 decode_and_deserialize::<VersionedTransaction>(data, binary_encoding)?;
 
-            let bank = &*meta.get_bank_with_config(RpcContextConfig {
-                commitment,
-                min_context_slot,
-            })?;
+            let bank = &*meta.get_simulation_bank(
+                simulation_bank,
+                RpcContextConfig {
+                    commitment,
+                    min_context_slot,
+                },
+            )?;
             if replace_recent_blockhash {
                 if sig_verify {
                     return Err(Error::invalid_params(
@@ -3731,6 +5230,7 @@ pub mod rpc_full {
                 verify_transaction(&transaction, &bank.feature_set)?;
             }
             let number_of_accounts = transaction.message().account_keys().len();
+            let writable_accounts = writable_accounts(&transaction);
 
             let TransactionSimulationResult {
                 result,
@@ -3740,58 +5240,189 @@ pub mod rpc_full {
                 return_data,
             } = bank.simulate_transaction(transaction);
 
-            let accounts = if let Some(config_accounts) = config_accounts {
-                let accounts_encoding = config_accounts
-                    .encoding
-                    .unwrap_or(UiAccountEncoding::Base64);
-
-                if accounts_encoding == UiAccountEncoding::Binary
-                    || accounts_encoding == UiAccountEncoding::Base58
-                {
-                    return Err(Error::invalid_params("base58 encoding not supported"));
+            let (accounts, account_diffs) = if let Some(config_accounts) = config_accounts {
+                if config_accounts.addresses.len() > number_of_accounts {
+                    return Err(Error::invalid_params(format!(
+                        "Too many accounts provided; max {}",
+                        number_of_accounts
+                    )));
                 }
+                simulated_accounts(
+                    &config_accounts,
+                    &result,
+                    &post_simulation_accounts,
+                    |address| bank.get_account(address).unwrap_or_default(),
+                )?
+            } else {
+                (None, None)
+            };
+            let account_state_diffs = return_account_diffs.then(|| {
+                account_state_diffs(
+                    &writable_accounts,
+                    &result,
+                    &post_simulation_accounts,
+                    |address| bank.get_account(address),
+                    account_diffs_data_slice,
+                )
+            });
+
+            Ok(new_response(
+                bank,
+                RpcSimulateTransactionResult {
+                    err: result.err(),
+                    logs: Some(logs),
+                    accounts,
+                    account_diffs,
+                    account_state_diffs,
+                    units_consumed: Some(units_consumed),
+                    return_data: return_data.map(|return_data| return_data.into()),
+                },
+            ))
+        }
 
+        fn simulate_bundle(
+            &self,
+            meta: Self::Metadata,
+            data: Vec<String>,
+            config: Option<RpcSimulateTransactionConfig>,
+        ) -> Result<RpcResponse<Vec<RpcSimulateTransactionResult>>> {
+            debug!("simulate_bundle rpc request received");
+            let RpcSimulateTransactionConfig {
+                sig_verify,
+                replace_recent_blockhash,
+                commitment,
+                encoding,
+                accounts: config_accounts,
+                min_context_slot,
+                return_account_diffs,
+                account_diffs_data_slice,
+                simulation_bank,
+            } = config.unwrap_or_default();
+            if data.is_empty() {
+                return Err(Error::invalid_params("No transactions provided"));
+            }
+            if data.len() > MAX_SIMULATE_BUNDLE_TRANSACTIONS {
+                return Err(Error::invalid_params(format!(
+                    "Too many transactions provided; max {}",
+                    MAX_SIMULATE_BUNDLE_TRANSACTIONS
+                )));
+            }
+            if replace_recent_blockhash && sig_verify {
+                return Err(Error::invalid_params(
+                    "sigVerify may not be used with replaceRecentBlockhash",
+                ));
+            }
+            let tx_encoding = encoding.unwrap_or(UiTransactionEncoding::Base58);
+            let binary_encoding = tx_encoding.into_binary_encoding().ok_or_else(|| {
+                Error::invalid_params(format!(
+                    "unsupported encoding: {}. Supported encodings: base58, base64",
+                    tx_encoding
+                ))
+            })?;
+
+            let bank = &*meta.get_simulation_bank(
+                simulation_bank,
+                RpcContextConfig {
+                    commitment,
+                    min_context_slot,
+                },
+            )?;
+            let transactions = data
+                .into_iter()
+                .map(|data| {
+                    let (_, mut unsanitized_tx) =
+                        decode_and_deserialize::<VersionedTransaction>(data, binary_encoding)?;
+                    if replace_recent_blockhash {
+                        unsanitized_tx
+                            .message
+                            .set_recent_blockhash(bank.last_blockhash());
+                    }
+                    let transaction = sanitize_transaction(unsanitized_tx, bank)?;
+                    if sig_verify {
+                        verify_transaction(&transaction, &bank.feature_set)?;
+                    }
+                    Ok(transaction)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if let Some(config_accounts) = &config_accounts {
+                let number_of_accounts = transactions
+                    .iter()
+                    .flat_map(|transaction| transaction.message().account_keys().iter())
+                    .collect::<HashSet<_>>()
+                    .len();
                 if config_accounts.addresses.len() > number_of_accounts {
                     return Err(Error::invalid_params(format!(
                         "Too many accounts provided; max {}",
                         number_of_accounts
                     )));
                 }
+            }
 
-                if result.is_err() {
-                    Some(vec![None; config_accounts.addresses.len()])
-                } else {
-                    Some(
-                        config_accounts
-                            .addresses
-                            .iter()
-                            .map(|address_str| {
-                                let address = verify_pubkey(address_str)?;
-                                post_simulation_accounts
-                                    .iter()
-                                    .find(|(key, _account)| key == &address)
-                                    .map(|(pubkey, account)| {
-                                        encode_account(account, pubkey, accounts_encoding, None)
-                                    })
-                                    .transpose()
-                            })
-                            .collect::<Result<Vec<_>>>()?,
-                    )
-                }
-            } else {
-                None
-            };
+            let writable_accounts = transactions
+                .iter()
+                .map(writable_accounts)
+                .collect::<Vec<_>>();
+
+            // Latest state of the accounts written by the simulated transactions, against which
+            // the account diffs of the following transactions are taken
+            let mut bundle_accounts = HashMap::<Pubkey, AccountSharedData>::new();
+            let results = bank
+                .simulate_transactions(transactions)
+                .into_iter()
+                .zip(writable_accounts)
+                .map(
+                    |(
+                        TransactionSimulationResult {
+                            result,
+                            logs,
+                            post_simulation_accounts,
+                            units_consumed,
+                            return_data,
+                        },
+                        writable_accounts,
+                    )| {
+                        let pre_account = |address: &Pubkey| {
+                            bundle_accounts
+                                .get(address)
+                                .cloned()
+                                .or_else(|| bank.get_account(address))
+                        };
+                        let (accounts, account_diffs) = match &config_accounts {
+                            Some(config_accounts) => simulated_accounts(
+                                config_accounts,
+                                &result,
+                                &post_simulation_accounts,
+                                |address| pre_account(address).unwrap_or_default(),
+                            )?,
+                            None => (None, None),
+                        };
+                        let account_state_diffs = return_account_diffs.then(|| {
+                            account_state_diffs(
+                                &writable_accounts,
+                                &result,
+                                &post_simulation_accounts,
+                                pre_account,
+                                account_diffs_data_slice,
+                            )
+                        });
+                        if result.is_ok() {
+                            bundle_accounts.extend(post_simulation_accounts);
+                        }
+                        Ok(RpcSimulateTransactionResult {
+                            err: result.err(),
+                            logs: Some(logs),
+                            accounts,
+                            account_diffs,
+                            account_state_diffs,
+                            units_consumed: Some(units_consumed),
+                            return_data: return_data.map(|return_data| return_data.into()),
+                        })
+                    },
+                )
+                .collect::<Result<Vec<_>>>()?;
 
-            Ok(new_response(
-                bank,
-                RpcSimulateTransactionResult {
-                    err: result.err(),
-                    logs: Some(logs),
-                    accounts,
-                    units_consumed: Some(units_consumed),
-                    return_data: return_data.map(|return_data| return_data.into()),
-                },
-            ))
+            Ok(new_response(bank, results))
         }
 
         fn minimum_ledger_slot(&self, meta: Self::Metadata) -> Result<Slot> {
@@ -3833,15 +5464,20 @@ pub mod rpc_full {
             meta: Self::Metadata,
             start_slot: Slot,
             limit: usize,
-            commitment: Option<CommitmentConfig>,
+            config: Option<RpcBlocksWithLimitConfig>,
         ) -> BoxFuture<Result<Vec<Slot>>> {
             debug!(
                 "get_blocks_with_limit rpc request received: {}-{}",
                 start_slot, limit,
             );
+            let config = config.unwrap_or_default();
             Box::pin(async move {
-                meta.get_blocks_with_limit(start_slot, limit, commitment)
-                    .await
+                if config.descending.unwrap_or_default() {
+                    meta.get_blocks_with_limit_descending(start_slot, limit, config.commitment)
+                } else {
+                    meta.get_blocks_with_limit(start_slot, limit, config.commitment)
+                        .await
+                }
             })
         }
 
@@ -3849,8 +5485,21 @@ pub mod rpc_full {
             &self,
             meta: Self::Metadata,
             slot: Slot,
-        ) -> BoxFuture<Result<Option<UnixTimestamp>>> {
-            Box::pin(async move { meta.get_block_time(slot).await })
+            config: Option<RpcBlockTimeConfig>,
+        ) -> BoxFuture<Result<Option<RpcBlockTime>>> {
+            let allow_estimated = config
+                .and_then(|config| config.allow_estimated)
+                .unwrap_or_default();
+            Box::pin(async move {
+                if allow_estimated {
+                    Ok(meta
+                        .get_block_time_with_estimate(slot)
+                        .await?
+                        .map(RpcBlockTime::MaybeEstimated))
+                } else {
+                    Ok(meta.get_block_time(slot).await?.map(RpcBlockTime::Recorded))
+                }
+            })
         }
 
         fn get_transaction(
@@ -3879,7 +5528,16 @@ pub mod rpc_full {
                 limit,
                 commitment,
                 min_context_slot,
+                before_time,
+                after_time,
             } = config.unwrap_or_default();
+            if let (Some(before_time), Some(after_time)) = (before_time, after_time) {
+                if after_time >= before_time {
+                    return Box::pin(future::err(Error::invalid_params(
+                        "Invalid time range; afterTime must be earlier than beforeTime",
+                    )));
+                }
+            }
             let verification =
                 verify_and_parse_signatures_for_address_params(address, before, until, limit);
 
@@ -3890,6 +5548,8 @@ pub mod rpc_full {
                         address,
                         before,
                         until,
+                        before_time,
+                        after_time,
                         limit,
                         RpcContextConfig {
                             commitment,
@@ -4214,6 +5874,8 @@ pub mod rpc_deprecated_v1_7 {
                         address,
                         before,
                         until,
+                        None,
+                        None,
                         limit,
                         RpcContextConfig {
                             commitment,
@@ -4547,8 +6209,12 @@ pub mod tests {
         solana_client::{
             rpc_custom_error::{
                 JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+                JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+                JSON_RPC_SERVER_ERROR_SIMULATION_BANK_NOT_AVAILABLE,
+                JSON_RPC_SERVER_ERROR_STAKE_SNAPSHOT_NOT_AVAILABLE,
                 JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE,
                 JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION,
+                JSON_RPC_SERVER_ERROR_VOTE_LATENCIES_NOT_AVAILABLE,
             },
             rpc_filter::{Memcmp, MemcmpEncodedBytes},
         },
@@ -4580,8 +6246,9 @@ pub mod tests {
             },
         },
         solana_transaction_status::{
-            EncodedConfirmedBlock, EncodedTransaction, EncodedTransactionWithStatusMeta,
-            TransactionDetails,
+            parse_instruction_error::UiInstructionErrorDetail, EncodedConfirmedBlock,
+            EncodedTransaction, EncodedTransactionWithStatusMeta, TransactionDetails,
+            TransactionStatusMeta,
         },
         solana_vote_program::{
             vote_instruction,
@@ -4692,11 +6359,15 @@ pub mod tests {
                 cluster_info,
                 Hash::default(),
                 None,
+                None,
                 OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
                 Arc::new(RwLock::new(LargestAccountsCache::new(30))),
                 max_slots.clone(),
                 Arc::new(LeaderScheduleCache::new_from_bank(&bank)),
                 max_complete_transaction_status_slot.clone(),
+                Arc::new(BufferedPrioritizationFees::default()),
+                Arc::new(VoteLatencies::default()),
+                Arc::new(EstimatedBlockTimes::default()),
             )
             .0;
 
@@ -5052,6 +6723,210 @@ pub mod tests {
         assert_eq!(response, expected);
     }
 
+    #[test]
+    fn test_rpc_get_slot_time_estimates() {
+        let rpc = RpcHandler::start();
+        rpc.blockstore
+            .write_perf_sample(
+                0,
+                &PerfSample {
+                    num_slots: 120,
+                    num_transactions: 4,
+                    sample_period_secs: 60,
+                },
+            )
+            .expect("write to blockstore");
+
+        let request = create_test_request("getSlot", Some(json!([CommitmentConfig::processed()])));
+        let processed_slot: Slot = parse_success_result(rpc.handle_request_sync(request));
+        let request = create_test_request(
+            "getSlotTimeEstimates",
+            Some(json!([
+                [processed_slot, processed_slot + 10],
+                CommitmentConfig::processed()
+            ])),
+        );
+        let result: RpcResponse<RpcSlotTimeEstimates> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let estimates = result.value;
+        assert_eq!(estimates.ms_per_slot, 500);
+        assert_eq!(
+            estimates.confirmation_eta_ms,
+            SlotTimeOracle::new(500.0, estimates.skip_rate).confirmation_eta_ms(0)
+        );
+        assert_eq!(estimates.slots.len(), 2);
+        assert_eq!(estimates.slots[0].slot, processed_slot);
+        assert_eq!(estimates.slots[0].eta_ms, 0);
+        assert_eq!(estimates.slots[1].slot, processed_slot + 10);
+        assert_eq!(estimates.slots[1].eta_ms, 5_000);
+        assert!(estimates.slots[1].estimated_time >= estimates.slots[0].estimated_time + 4);
+
+        let request = create_test_request(
+            "getSlotTimeEstimates",
+            Some(json!([vec![0; MAX_GET_SLOT_TIME_ESTIMATES_SLOTS + 1]])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            format!(
+                "Too many inputs provided; max {}",
+                MAX_GET_SLOT_TIME_ESTIMATES_SLOTS
+            ),
+        );
+        assert_eq!(response, expected);
+    }
+
+    #[test]
+    fn test_rpc_get_buffered_prioritization_fees() {
+        let rpc = RpcHandler::start();
+        let request = create_test_request("getBufferedPrioritizationFees", None);
+        let result: RpcBufferedPrioritizationFees =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, RpcBufferedPrioritizationFees::default());
+
+        let fees = RpcBufferedPrioritizationFees {
+            num_packets: 10,
+            min_compute_unit_price: 1,
+            median_compute_unit_price: 5,
+            p90_compute_unit_price: 9,
+        };
+        rpc.meta
+            .buffered_prioritization_fees
+            .publish(2, fees.clone());
+        let request = create_test_request("getBufferedPrioritizationFees", None);
+        let result: RpcBufferedPrioritizationFees =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, fees);
+    }
+
+    #[test]
+    fn test_rpc_get_vote_latencies() {
+        let mut rpc = RpcHandler::start();
+        let request = create_test_request("getVoteLatencies", None);
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, JSON_RPC_SERVER_ERROR_VOTE_LATENCIES_NOT_AVAILABLE);
+
+        rpc.meta.config.enable_rpc_vote_latencies = true;
+        let request = create_test_request("getVoteLatencies", None);
+        let result: RpcVoteLatencies = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, RpcVoteLatencies::default());
+
+        let leader_vote_pubkey = rpc.leader_vote_keypair.pubkey();
+        let other_vote_pubkey = solana_sdk::pubkey::new_rand();
+        rpc.meta.vote_latencies.record(
+            3,
+            vec![
+                (leader_vote_pubkey, vec![1, 2]),
+                (other_vote_pubkey, vec![2]),
+            ],
+        );
+        let request = create_test_request("getVoteLatencies", None);
+        let result: RpcVoteLatencies = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.last_slot, 3);
+        assert_eq!(result.validators.len(), 2);
+
+        let request = create_test_request(
+            "getVoteLatencies",
+            Some(json!([[leader_vote_pubkey.to_string()]])),
+        );
+        let result: RpcVoteLatencies = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.validators.len(), 1);
+        let latency = &result.validators[0];
+        assert_eq!(latency.vote_pubkey, leader_vote_pubkey.to_string());
+        assert_eq!(latency.num_votes, 2);
+        assert_eq!(latency.mean_latency, 1.5);
+        assert_eq!(latency.max_latency, 2);
+        assert_eq!(&latency.latency_counts[..2], &[1, 1]);
+
+        let request = create_test_request("getVoteLatencies", Some(json!([["invalid"]])));
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, ErrorCode::InvalidParams.code());
+    }
+
+    #[test]
+    fn test_rpc_get_stake_snapshot() {
+        let mut rpc = RpcHandler::start();
+        let request = create_test_request("getStakeSnapshot", Some(json!([0])));
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, JSON_RPC_SERVER_ERROR_STAKE_SNAPSHOT_NOT_AVAILABLE);
+
+        let stake_snapshot_dir = rpc.blockstore.ledger_path().join("stake-snapshots");
+        rpc.meta.config.stake_snapshot_dir = Some(stake_snapshot_dir.clone());
+        let request = create_test_request("getStakeSnapshot", Some(json!([0])));
+        let result: Option<RpcStakeSnapshot> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, None);
+
+        let bank = rpc.working_bank();
+        let snapshot = EpochStakeSnapshot::new(bank.epoch_stakes(0).unwrap().stakes());
+        stake_snapshot::write_stake_snapshot(&stake_snapshot_dir, &snapshot).unwrap();
+        let request = create_test_request("getStakeSnapshot", Some(json!([0])));
+        let result: Option<RpcStakeSnapshot> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let result = result.unwrap();
+        assert_eq!(result, rpc_stake_snapshot(snapshot));
+        let leader_vote_pubkey = rpc.leader_vote_keypair.pubkey().to_string();
+        let validator = result
+            .validators
+            .iter()
+            .find(|validator| validator.vote_pubkey == leader_vote_pubkey)
+            .unwrap();
+        assert_eq!(validator.delegations[0].state, StakeActivationState::Active);
+    }
+
+    #[test]
+    fn test_rpc_get_stake_minimum_delegation_history() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        let stake_history = solana_sdk::account::from_account::<StakeHistory, _>(
+            &bank.get_account(&stake_history::id()).unwrap(),
+        )
+        .unwrap();
+
+        let request = create_test_request("getStakeMinimumDelegationHistory", None);
+        let result: Vec<RpcEpochStakeHistoryEntry> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.len(), stake_history.len());
+        let minimum_delegation = solana_stake_program::get_minimum_delegation(&bank.feature_set);
+        for (entry, (epoch, stake_history_entry)) in result.iter().zip(stake_history.iter()) {
+            assert_eq!(entry.epoch, *epoch);
+            assert_eq!(entry.effective, stake_history_entry.effective);
+            assert_eq!(entry.minimum_delegation, minimum_delegation);
+        }
+    }
+
+    #[test]
+    fn test_rpc_get_epoch_stake_breakdown() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+
+        let request = create_test_request("getEpochStakeBreakdown", None);
+        let result: Option<RpcEpochStakeBreakdown> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let result = result.unwrap();
+        assert_eq!(result.epoch, bank.epoch());
+        assert_eq!(result.total_stake, bank.total_epoch_stake());
+        // The stakes of the epoch before the first are unknown
+        assert_eq!(result.total_stake_delta, None);
+        let leader_vote_pubkey = rpc.leader_vote_keypair.pubkey().to_string();
+        let validator = result
+            .validators
+            .iter()
+            .find(|validator| validator.vote_pubkey == leader_vote_pubkey)
+            .unwrap();
+        assert_eq!(
+            validator.activated_stake,
+            bank.epoch_vote_account_stake(&rpc.leader_vote_keypair.pubkey())
+        );
+        assert_eq!(validator.stake_delta, None);
+
+        let request =
+            create_test_request("getEpochStakeBreakdown", Some(json!([{"epoch": 1_000}])));
+        let result: Option<RpcEpochStakeBreakdown> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, None);
+    }
+
     #[test]
     fn test_rpc_get_slot_leader() {
         let rpc = RpcHandler::start();
@@ -5498,6 +7373,73 @@ pub mod tests {
         assert_eq!(response, expected);
     }
 
+    #[test]
+    fn test_rpc_get_program_accounts_paginated() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+
+        let program_id = Pubkey::new_unique();
+        let mut expected_pubkeys = (0..5)
+            .map(|i| {
+                let pubkey = Pubkey::new_unique();
+                let account = AccountSharedData::new(42, i, &program_id);
+                bank.store_account(&pubkey, &account);
+                pubkey
+            })
+            .collect::<Vec<_>>();
+        expected_pubkeys.sort_unstable();
+
+        // Page through the accounts, two at a time
+        let mut pubkeys = vec![];
+        let mut cursor: Option<String> = None;
+        loop {
+            let request = create_test_request(
+                "getProgramAccountsPaginated",
+                Some(json!([program_id.to_string(), {"limit": 2, "cursor": cursor}])),
+            );
+            let result: RpcResponse<RpcKeyedAccountsPage> =
+                parse_success_result(rpc.handle_request_sync(request));
+            assert!(result.value.accounts.len() <= 2);
+            pubkeys.extend(
+                result
+                    .value
+                    .accounts
+                    .iter()
+                    .map(|keyed_account| Pubkey::from_str(&keyed_account.pubkey).unwrap()),
+            );
+            cursor = result.value.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pubkeys, expected_pubkeys);
+
+        // Filters apply before paging
+        let request = create_test_request(
+            "getProgramAccountsPaginated",
+            Some(json!([
+                program_id.to_string(),
+                {"limit": 1, "filters": [{"dataSize": 3}]},
+            ])),
+        );
+        let result: RpcResponse<RpcKeyedAccountsPage> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.value.accounts.len(), 1);
+        let account: AccountSharedData = result.value.accounts[0].account.decode().unwrap();
+        assert_eq!(account.data().len(), 3);
+
+        let request = create_test_request(
+            "getProgramAccountsPaginated",
+            Some(json!([program_id.to_string(), {"limit": 0}])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            format!("Invalid limit; max {}", MAX_GET_PROGRAM_ACCOUNTS_PAGE_LIMIT),
+        );
+        assert_eq!(response, expected);
+    }
+
     #[test]
     fn test_rpc_get_program_accounts() {
         let rpc = RpcHandler::start();
@@ -5903,30 +7845,247 @@ pub mod tests {
             r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}", {{"replaceRecentBlockhash": true}}]}}"#,
             tx_invalid_recent_blockhash,
         );
-        let res = io.handle_request_sync(&req, meta.clone());
-        let expected = json!({
-            "jsonrpc": "2.0",
-            "result": {
-                "context": {"slot": 0, "apiVersion": RpcApiVersion::default()},
-                "value":{
-                    "accounts":null,
-                    "err":null,
-                    "logs":[
-                        "Program 11111111111111111111111111111111 invoke [1]",
-                        "Program 11111111111111111111111111111111 success"
-                    ],
-                    "returnData":null,
-                    "unitsConsumed":0
+        let res = io.handle_request_sync(&req, meta.clone());
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": {
+                "context": {"slot": 0, "apiVersion": RpcApiVersion::default()},
+                "value":{
+                    "accounts":null,
+                    "err":null,
+                    "logs":[
+                        "Program 11111111111111111111111111111111 invoke [1]",
+                        "Program 11111111111111111111111111111111 success"
+                    ],
+                    "returnData":null,
+                    "unitsConsumed":0
+                }
+            },
+            "id": 1,
+        });
+
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_diff_account_data() {
+        let pre_data = vec![1u8; 100];
+        let mut post_data = pre_data.clone();
+        post_data[10] = 2;
+        post_data[20] = 2;
+        post_data[60] = 2;
+        post_data.extend_from_slice(&[0, 0, 3]);
+
+        let data_changes = diff_account_data(&pre_data, &post_data);
+        assert_eq!(
+            data_changes
+                .iter()
+                .map(|data_change| (
+                    data_change.offset,
+                    base64::decode(&data_change.data).unwrap().len()
+                ))
+                .collect::<Vec<_>>(),
+            vec![(10, 11), (60, 1), (102, 1)]
+        );
+        assert!(diff_account_data(&pre_data, &pre_data).is_empty());
+
+        let owner = Pubkey::new_unique();
+        let pre_account = AccountSharedData::create(1, pre_data.clone(), owner, false, 0);
+        let post_account = AccountSharedData::create(2, post_data.clone(), owner, false, 0);
+        let account_diff = diff_account(&pre_account, &post_account);
+        assert_eq!(account_diff.lamports, 2);
+        assert_eq!(account_diff.owner, owner.to_string());
+        assert_eq!(account_diff.apply(&pre_data), Some(post_data.clone()));
+
+        // Data past the new length is dropped rather than reported as changed
+        let truncated_diff = diff_account(&post_account, &pre_account);
+        assert_eq!(truncated_diff.data_len, pre_data.len());
+        assert_eq!(truncated_diff.apply(&post_data), Some(pre_data));
+    }
+
+    #[test]
+    fn test_rpc_simulate_transaction_simulation_bank() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        let tx = system_transaction::transfer(
+            &rpc.mint_keypair,
+            &solana_sdk::pubkey::new_rand(),
+            bank.get_minimum_balance_for_rent_exemption(0),
+            bank.confirmed_last_blockhash(),
+        );
+        let tx_serialized_encoded = bs58::encode(serialize(&tx).unwrap()).into_string();
+
+        // The bank of a slot must be frozen to be simulated against
+        let request = create_test_request(
+            "simulateTransaction",
+            Some(json!([tx_serialized_encoded, {"simulationBank": {"slot": 0}}])),
+        );
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, JSON_RPC_SERVER_ERROR_SIMULATION_BANK_NOT_AVAILABLE);
+
+        bank.freeze();
+        let request = create_test_request(
+            "simulateTransaction",
+            Some(json!([tx_serialized_encoded, {"simulationBank": {"slot": 0}}])),
+        );
+        let response: RpcResponse<RpcSimulateTransactionResult> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(response.context.slot, 0);
+        assert!(response.value.err.is_none());
+
+        let request = create_test_request(
+            "simulateTransaction",
+            Some(json!([tx_serialized_encoded, {"simulationBank": "processed"}])),
+        );
+        let response: RpcResponse<RpcSimulateTransactionResult> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert!(response.value.err.is_none());
+
+        // A slot older than the minimum context slot is rejected rather than replaced
+        let request = create_test_request(
+            "simulateTransaction",
+            Some(json!([
+                tx_serialized_encoded,
+                {"simulationBank": {"slot": 0}, "minContextSlot": 1}
+            ])),
+        );
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED);
+
+        let request = create_test_request(
+            "simulateTransaction",
+            Some(json!([tx_serialized_encoded, {"simulationBank": {"slot": 100}}])),
+        );
+        let (code, _) = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(code, JSON_RPC_SERVER_ERROR_SIMULATION_BANK_NOT_AVAILABLE);
+    }
+
+    #[test]
+    fn test_rpc_simulate_transaction_account_state_diffs() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        let rent_exempt_amount = bank.get_minimum_balance_for_rent_exemption(0);
+        let recent_blockhash = bank.confirmed_last_blockhash();
+        let mint_pubkey = rpc.mint_keypair.pubkey();
+        let mint_balance = bank.get_balance(&mint_pubkey);
+
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let tx = system_transaction::transfer(
+            &rpc.mint_keypair,
+            &bob_pubkey,
+            rent_exempt_amount,
+            recent_blockhash,
+        );
+        let tx_serialized_encoded = bs58::encode(serialize(&tx).unwrap()).into_string();
+        let fee = bank
+            .get_fee_for_message(&SanitizedMessage::try_from(tx.message).unwrap())
+            .unwrap();
+
+        // Simulation bank must be frozen
+        bank.freeze();
+
+        let request = create_test_request(
+            "simulateTransaction",
+            Some(json!([
+                tx_serialized_encoded,
+                {
+                    "returnAccountDiffs": true,
+                    "accountDiffsDataSlice": {"offset": 0, "length": 0}
+                }
+            ])),
+        );
+        let response: RpcResponse<RpcSimulateTransactionResult> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let account_state_diffs = response.value.account_state_diffs.unwrap();
+        assert_eq!(account_state_diffs.len(), 2);
+
+        let mint_diff = &account_state_diffs[0];
+        assert_eq!(mint_diff.pubkey, mint_pubkey.to_string());
+        assert_eq!(mint_diff.pre.as_ref().unwrap().lamports, mint_balance);
+        assert_eq!(
+            mint_diff.post.as_ref().unwrap().lamports,
+            mint_balance - rent_exempt_amount - fee
+        );
+        assert_eq!(
+            mint_diff.lamports_delta,
+            -((rent_exempt_amount + fee) as i64)
+        );
+
+        let bob_diff = &account_state_diffs[1];
+        assert_eq!(bob_diff.pubkey, bob_pubkey.to_string());
+        assert_eq!(bob_diff.pre, None);
+        assert_eq!(bob_diff.post.as_ref().unwrap().lamports, rent_exempt_amount);
+        assert_eq!(bob_diff.lamports_delta, rent_exempt_amount as i64);
+
+        // Not reported unless requested
+        let request =
+            create_test_request("simulateTransaction", Some(json!([tx_serialized_encoded])));
+        let response: RpcResponse<RpcSimulateTransactionResult> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert!(response.value.account_state_diffs.is_none());
+    }
+
+    #[test]
+    fn test_rpc_simulate_bundle() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        let rent_exempt_amount = bank.get_minimum_balance_for_rent_exemption(0);
+        let recent_blockhash = bank.confirmed_last_blockhash();
+
+        let bob = Keypair::new();
+        let carol_pubkey = solana_sdk::pubkey::new_rand();
+        let fund_bob = system_transaction::transfer(
+            &rpc.mint_keypair,
+            &bob.pubkey(),
+            3 * rent_exempt_amount,
+            recent_blockhash,
+        );
+        let pay_carol =
+            system_transaction::transfer(&bob, &carol_pubkey, rent_exempt_amount, recent_blockhash);
+        let fund_bob_encoded = bs58::encode(serialize(&fund_bob).unwrap()).into_string();
+        let pay_carol_encoded = bs58::encode(serialize(&pay_carol).unwrap()).into_string();
+
+        // Simulation bank must be frozen
+        bank.freeze();
+
+        // Bob can only pay Carol with the funds of the preceding transaction
+        let request = create_test_request(
+            "simulateBundle",
+            Some(json!([
+                [fund_bob_encoded, pay_carol_encoded],
+                {
+                    "sigVerify": true,
+                    "accounts": {"addresses": [carol_pubkey.to_string()]}
                 }
-            },
-            "id": 1,
-        });
+            ])),
+        );
+        let response: RpcResponse<Vec<RpcSimulateTransactionResult>> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(response.value.len(), 2);
+        assert!(response.value.iter().all(|result| result.err.is_none()));
+        assert_eq!(response.value[0].accounts, Some(vec![None]));
+        let carol_account = response.value[1].accounts.as_ref().unwrap()[0]
+            .as_ref()
+            .unwrap();
+        assert_eq!(carol_account.lamports, rent_exempt_amount);
 
-        let expected: Response =
-            serde_json::from_value(expected).expect("expected response deserialization");
-        let result: Response = serde_json::from_str(&res.expect("actual response"))
-            .expect("actual response deserialization");
-        assert_eq!(result, expected);
+        // Simulated alone, Bob has no funds
+        let request = create_test_request("simulateBundle", Some(json!([[pay_carol_encoded]])));
+        let response: RpcResponse<Vec<RpcSimulateTransactionResult>> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert!(response.value[0].err.is_some());
+
+        let request = create_test_request("simulateBundle", Some(json!([[]])));
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            String::from("No transactions provided"),
+        );
+        assert_eq!(response, expected);
     }
 
     #[test]
@@ -6243,11 +8402,15 @@ pub mod tests {
             cluster_info,
             Hash::default(),
             None,
+            None,
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
             Arc::new(RwLock::new(LargestAccountsCache::new(30))),
             Arc::new(MaxSlots::default()),
             Arc::new(LeaderScheduleCache::default()),
             Arc::new(AtomicU64::default()),
+            Arc::new(BufferedPrioritizationFees::default()),
+            Arc::new(VoteLatencies::default()),
+            Arc::new(EstimatedBlockTimes::default()),
         );
         SendTransactionService::new::<NullTpuInfo>(
             tpu_address,
@@ -6454,6 +8617,59 @@ pub mod tests {
         assert_eq!(result, 43);
     }
 
+    #[test]
+    fn test_rpc_get_health_detailed() {
+        let rpc = RpcHandler::start();
+        let slot = rpc.meta.bank(Some(CommitmentConfig::processed())).slot();
+        rpc.max_slots.shred_insert.store(slot, Ordering::Relaxed);
+
+        let request = create_test_request("getHealthDetailed", None);
+        let result: RpcHealthDetailed = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.status, RpcHealthState::Ok);
+        assert_eq!(
+            result.replay,
+            RpcReplayHealth {
+                status: RpcHealthState::Ok,
+                processed_slot: slot,
+                max_shred_insert_slot: slot,
+                slots_behind: 0,
+            }
+        );
+        assert_eq!(result.snapshot.status, RpcHealthState::Ok);
+        assert_eq!(result.snapshot.full_snapshot_slot, None);
+        assert_eq!(result.accounts_hash.status, RpcHealthState::Ok);
+        assert_eq!(
+            result.gossip,
+            RpcGossipHealth {
+                status: RpcHealthState::Ok,
+                num_peers: 1,
+            }
+        );
+        // The identity of the node has no vote account
+        assert_eq!(result.voting.status, RpcHealthState::Ok);
+        assert_eq!(result.voting.last_vote_slot, None);
+
+        // Replay lags behind the slots received, and the health check slot distance is zero
+        rpc.max_slots
+            .shred_insert
+            .store(slot + 1, Ordering::Relaxed);
+        rpc.meta
+            .health
+            .stub_set_health_status(Some(RpcHealthStatus::Behind { num_slots: 42 }));
+        let request = create_test_request("getHealthDetailed", None);
+        let result: RpcHealthDetailed = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.status, RpcHealthState::Failed);
+        assert_eq!(result.replay.status, RpcHealthState::Failed);
+        assert_eq!(result.replay.slots_behind, 1);
+        assert_eq!(
+            result.accounts_hash,
+            RpcAccountsHashHealth {
+                status: RpcHealthState::Failed,
+                slots_behind_known_validators: Some(42),
+            }
+        );
+    }
+
     #[test]
     fn test_rpc_get_version() {
         let rpc = RpcHandler::start();
@@ -6509,11 +8725,15 @@ pub mod tests {
             cluster_info,
             Hash::default(),
             None,
+            None,
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
             Arc::new(RwLock::new(LargestAccountsCache::new(30))),
             Arc::new(MaxSlots::default()),
             Arc::new(LeaderScheduleCache::default()),
             Arc::new(AtomicU64::default()),
+            Arc::new(BufferedPrioritizationFees::default()),
+            Arc::new(VoteLatencies::default()),
+            Arc::new(EstimatedBlockTimes::default()),
         );
         SendTransactionService::new::<NullTpuInfo>(
             tpu_address,
@@ -6619,6 +8839,53 @@ pub mod tests {
         assert_eq!(response, expected);
     }
 
+    #[test]
+    fn test_get_transaction_decode_instruction_error() {
+        let rpc = RpcHandler::start();
+        let signatures = rpc.create_test_transactions_and_populate_blockstore();
+
+        // The second transaction fails transferring more lamports than its payer holds
+        let request = create_test_request(
+            "getTransaction",
+            Some(json!([signatures[1].to_string(), {"decodeInstructionError": true}])),
+        );
+        let result: EncodedConfirmedTransactionWithStatusMeta =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(
+            result.transaction.meta.unwrap().instruction_error_detail,
+            Some(UiInstructionErrorDetail {
+                instruction_index: 0,
+                program_id: system_program::id().to_string(),
+                program: Some("system".to_string()),
+                error: InstructionError::Custom(1),
+                message: Some(
+                    "account does not have enough SOL to perform the operation".to_string()
+                ),
+            })
+        );
+
+        // The detail is only added on request
+        let request =
+            create_test_request("getTransaction", Some(json!([signatures[1].to_string()])));
+        let result: EncodedConfirmedTransactionWithStatusMeta =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(
+            result.transaction.meta.unwrap().instruction_error_detail,
+            None
+        );
+
+        let request = create_test_request(
+            "getTransaction",
+            Some(json!([signatures[0].to_string(), {"decodeInstructionError": true}])),
+        );
+        let result: EncodedConfirmedTransactionWithStatusMeta =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(
+            result.transaction.meta.unwrap().instruction_error_detail,
+            None
+        );
+    }
+
     #[test]
     fn test_get_block() {
         let mut rpc = RpcHandler::start();
@@ -6773,6 +9040,32 @@ pub mod tests {
         assert!(confirmed_block.transactions.is_none());
         assert!(confirmed_block.signatures.is_none());
         assert_eq!(confirmed_block.rewards.unwrap(), vec![]);
+
+        let request = create_test_request(
+            "getBlock",
+            Some(json!([
+                0u64,
+                RpcBlockConfig {
+                    encoding: None,
+                    transaction_details: Some(TransactionDetails::Balances),
+                    rewards: Some(false),
+                    commitment: None,
+                    max_supported_transaction_version: None,
+                },
+            ])),
+        );
+        let result: Option<UiConfirmedBlock> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let confirmed_block = result.unwrap();
+        assert!(confirmed_block.transactions.is_none());
+        assert!(confirmed_block.signatures.is_none());
+        for (i, balances) in confirmed_block.balances.unwrap()[..2].iter().enumerate() {
+            assert_eq!(
+                balances.signature,
+                confirmed_block_signatures[i].to_string()
+            );
+            assert_eq!(balances.pre_balances.len(), balances.account_keys.len());
+        }
     }
 
     #[test]
@@ -6923,6 +9216,58 @@ pub mod tests {
         assert_eq!(result, Vec::<Slot>::new());
     }
 
+    #[test]
+    fn test_get_blocks_with_limit_descending() {
+        let rpc = RpcHandler::start();
+        rpc.add_roots_to_blockstore(vec![0, 1, 3, 4, 8]);
+        rpc.block_commitment_cache
+            .write()
+            .unwrap()
+            .set_highest_confirmed_root(4);
+
+        let descending = json!({"descending": true});
+        let request = create_test_request(
+            "getBlocksWithLimit",
+            Some(json!([0u64, 500_001u64, descending])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            String::from("Limit too large; max 500000"),
+        );
+        assert_eq!(response, expected);
+
+        // finalized blocks stop at the highest confirmed root
+        let request = create_test_request(
+            "getBlocksWithLimit",
+            Some(json!([u64::MAX, 3u64, descending])),
+        );
+        let result: Vec<Slot> = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, vec![4, 3, 1]);
+
+        let request = create_test_request(
+            "getBlocksWithLimit",
+            Some(json!([2u64, 500_000u64, descending])),
+        );
+        let result: Vec<Slot> = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, vec![1, 0]);
+
+        let request = create_test_request(
+            "getBlocksWithLimit",
+            Some(json!([u64::MAX, 0u64, descending])),
+        );
+        let result: Vec<Slot> = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, Vec::<Slot>::new());
+
+        // a plain commitment config still lists blocks in ascending order
+        let request = create_test_request(
+            "getBlocksWithLimit",
+            Some(json!([2u64, 2u64, {"commitment": "finalized"}])),
+        );
+        let result: Vec<Slot> = parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result, vec![3, 4]);
+    }
+
     #[test]
     fn test_get_block_time() {
         let rpc = RpcHandler::start();
@@ -6961,6 +9306,74 @@ pub mod tests {
         assert_eq!(response, expected);
     }
 
+    #[test]
+    fn test_get_signatures_for_address_time_range() {
+        let rpc = RpcHandler::start();
+        rpc.add_roots_to_blockstore(vec![1, 2, 3, 4, 5, 6, 7]);
+        rpc.block_commitment_cache
+            .write()
+            .unwrap()
+            .set_highest_confirmed_root(7);
+
+        let address = solana_sdk::pubkey::new_rand();
+        for slot in 1..=7 {
+            rpc.blockstore
+                .write_transaction_status(
+                    slot,
+                    Signature::new_unique(),
+                    vec![&address],
+                    vec![],
+                    TransactionStatusMeta::default(),
+                )
+                .unwrap();
+        }
+        let block_time = |slot| rpc.blockstore.get_block_time(slot).unwrap().unwrap();
+        let (after_time, before_time) = (block_time(3), block_time(5));
+        let expected_slots: Vec<Slot> = (1..=7)
+            .rev()
+            .filter(|slot| (after_time..before_time).contains(&block_time(*slot)))
+            .collect();
+        assert!(!expected_slots.is_empty());
+
+        let request = create_test_request(
+            "getSignaturesForAddress",
+            Some(json!([
+                address.to_string(),
+                {"beforeTime": before_time, "afterTime": after_time}
+            ])),
+        );
+        let result: Vec<RpcConfirmedTransactionStatusWithSignature> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let slots: Vec<Slot> = result.iter().map(|info| info.slot).collect();
+        assert_eq!(slots, expected_slots);
+
+        // Only the lower bound
+        let request = create_test_request(
+            "getSignaturesForAddress",
+            Some(json!([address.to_string(), {"afterTime": after_time}])),
+        );
+        let result: Vec<RpcConfirmedTransactionStatusWithSignature> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert!(result
+            .iter()
+            .all(|info| info.block_time.unwrap() >= after_time));
+        assert_eq!(result.last().unwrap().slot, *expected_slots.last().unwrap());
+
+        let request = create_test_request(
+            "getSignaturesForAddress",
+            Some(json!([
+                address.to_string(),
+                {"beforeTime": after_time, "afterTime": after_time}
+            ])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            String::from("Invalid time range; afterTime must be earlier than beforeTime"),
+        );
+        assert_eq!(response, expected);
+    }
+
     #[test]
     fn test_get_vote_accounts() {
         let rpc = RpcHandler::start();
@@ -7707,6 +10120,179 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_token_accounts_pagination() {
+        let rpc = RpcHandler::start();
+        let bank = rpc.working_bank();
+        let program_id = inline_spl_token::id();
+        let mint = SplTokenPubkey::new(&[2; 32]);
+        let owner = SplTokenPubkey::new(&[3; 32]);
+
+        let mut mint_data = vec![0; Mint::get_packed_len()];
+        let mint_state = Mint {
+            mint_authority: COption::Some(owner),
+            supply: 500,
+            decimals: 2,
+            is_initialized: true,
+            freeze_authority: COption::Some(owner),
+        };
+        Mint::pack(mint_state, &mut mint_data).unwrap();
+        let mint_account = AccountSharedData::from(Account {
+            lamports: 111,
+            data: mint_data,
+            owner: program_id,
+            ..Account::default()
+        });
+        bank.store_account(&Pubkey::from_str(&mint.to_string()).unwrap(), &mint_account);
+
+        let mut token_accounts: Vec<(u64, Pubkey)> = [10, 40, 20, 40, 30]
+            .into_iter()
+            .map(|amount| {
+                let mut account_data = vec![0; TokenAccount::get_packed_len()];
+                let token_account = TokenAccount {
+                    mint,
+                    owner,
+                    delegate: COption::None,
+                    amount,
+                    state: TokenAccountState::Initialized,
+                    is_native: COption::None,
+                    delegated_amount: 0,
+                    close_authority: COption::None,
+                };
+                TokenAccount::pack(token_account, &mut account_data).unwrap();
+                let token_account = AccountSharedData::from(Account {
+                    lamports: 111,
+                    data: account_data,
+                    owner: program_id,
+                    ..Account::default()
+                });
+                let pubkey = solana_sdk::pubkey::new_rand();
+                bank.store_account(&pubkey, &token_account);
+                (amount, pubkey)
+            })
+            .collect();
+
+        // Page through the accounts of the owner, in pubkey order
+        let mut expected_pubkeys: Vec<_> =
+            token_accounts.iter().map(|(_, pubkey)| *pubkey).collect();
+        expected_pubkeys.sort();
+        let mut pubkeys = vec![];
+        let mut cursor: Option<String> = None;
+        loop {
+            let request = create_test_request(
+                "getTokenAccountsByOwner",
+                Some(json!([
+                    owner.to_string(),
+                    {"mint": mint.to_string()},
+                    {"limit": 2, "cursor": cursor},
+                ])),
+            );
+            let result: RpcResponse<RpcKeyedAccountsPage> =
+                parse_success_result(rpc.handle_request_sync(request));
+            assert!(result.value.accounts.len() <= 2);
+            pubkeys.extend(
+                result
+                    .value
+                    .accounts
+                    .iter()
+                    .map(|keyed_account| Pubkey::from_str(&keyed_account.pubkey).unwrap()),
+            );
+            cursor = result.value.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pubkeys, expected_pubkeys);
+
+        let request = create_test_request(
+            "getTokenAccountsByOwner",
+            Some(json!([
+                owner.to_string(),
+                {"programId": program_id.to_string()},
+                {"countOnly": true},
+            ])),
+        );
+        let result: RpcResponse<RpcAccountsCount> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.value.count, 5);
+
+        let request = create_test_request(
+            "getTokenAccountsByOwner",
+            Some(json!([
+                owner.to_string(),
+                {"mint": mint.to_string()},
+                {"countOnly": true, "limit": 2},
+            ])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            "countOnly cannot be combined with limit or cursor".to_string(),
+        );
+        assert_eq!(response, expected);
+
+        let request = create_test_request(
+            "getTokenAccountsByOwner",
+            Some(json!([
+                owner.to_string(),
+                {"mint": mint.to_string()},
+                {"limit": MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT + 1},
+            ])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            format!("Invalid limit; max {}", MAX_GET_TOKEN_ACCOUNTS_PAGE_LIMIT),
+        );
+        assert_eq!(response, expected);
+
+        // Page through the accounts of the mint, in decreasing balance order
+        token_accounts.sort_by(|(a_amount, a_pubkey), (b_amount, b_pubkey)| {
+            b_amount.cmp(a_amount).then(a_pubkey.cmp(b_pubkey))
+        });
+        let mut balances = vec![];
+        let mut cursor: Option<String> = None;
+        loop {
+            let request = create_test_request(
+                "getTokenLargestAccounts",
+                Some(json!([mint.to_string(), {"limit": 2, "cursor": cursor}])),
+            );
+            let result: RpcResponse<RpcTokenAccountBalancesPage> =
+                parse_success_result(rpc.handle_request_sync(request));
+            assert!(!result.value.accounts.is_empty());
+            balances.extend(result.value.accounts.iter().map(|balance| {
+                (
+                    balance.amount.amount.parse::<u64>().unwrap(),
+                    Pubkey::from_str(&balance.address).unwrap(),
+                )
+            }));
+            cursor = result.value.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(balances, token_accounts);
+
+        let request = create_test_request(
+            "getTokenLargestAccounts",
+            Some(json!([mint.to_string(), {"countOnly": true}])),
+        );
+        let result: RpcResponse<RpcAccountsCount> =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(result.value.count, 5);
+
+        let request = create_test_request(
+            "getTokenLargestAccounts",
+            Some(json!([mint.to_string(), {"cursor": "40"}])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        let expected = (
+            ErrorCode::InvalidParams.code(),
+            "Invalid param: cursor 40".to_string(),
+        );
+        assert_eq!(response, expected);
+    }
+
     #[test]
     fn test_token_parsing() {
         for program_id in solana_account_decoder::parse_token::spl_token_ids() {
@@ -8209,11 +10795,15 @@ pub mod tests {
             cluster_info,
             Hash::default(),
             None,
+            None,
             optimistically_confirmed_bank.clone(),
             Arc::new(RwLock::new(LargestAccountsCache::new(30))),
             Arc::new(MaxSlots::default()),
             Arc::new(LeaderScheduleCache::default()),
             Arc::new(AtomicU64::default()),
+            Arc::new(BufferedPrioritizationFees::default()),
+            Arc::new(VoteLatencies::default()),
+            Arc::new(EstimatedBlockTimes::default()),
         );
 
         let mut io = MetaIoHandler::default();
@@ -0,0 +1,186 @@
+//! The `rpc_auth` module implements bearer token access control for the JSON RPC service.
+
+use {
+    crate::rpc::JsonRpcRequestProcessor,
+    jsonrpc_core::{
+        futures::future::{self, Either, Future},
+        middleware::{Middleware, NoopFuture},
+        Call, Failure, Output,
+    },
+    solana_client::rpc_custom_error::RpcCustomError,
+    std::{
+        collections::{HashMap, HashSet},
+        fmt,
+        sync::{Arc, RwLock},
+    },
+};
+
+/// JSON RPC methods that a client may call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpcMethodAllowlist {
+    All,
+    Methods(HashSet<String>),
+}
+
+impl RpcMethodAllowlist {
+    pub fn none() -> Self {
+        Self::Methods(HashSet::new())
+    }
+
+    pub fn allows(&self, method: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Methods(methods) => methods.contains(method),
+        }
+    }
+}
+
+/// Bearer tokens accepted by the JSON RPC service, and the methods callable with each of them.
+/// Tokens are managed through the admin RPC while the validator runs.
+pub struct RpcAccessControl {
+    /// Methods callable by requests without an Authorization header
+    public_methods: Arc<RpcMethodAllowlist>,
+    tokens: RwLock<HashMap<String, Arc<RpcMethodAllowlist>>>,
+}
+
+impl RpcAccessControl {
+    pub fn new(public_methods: HashSet<String>) -> Self {
+        Self {
+            public_methods: Arc::new(RpcMethodAllowlist::Methods(public_methods)),
+            tokens: RwLock::default(),
+        }
+    }
+
+    /// Accepts `token`, replacing its allowlist if it was already accepted. Returns false in that
+    /// case.
+    pub fn add_token(&self, token: String, allowlist: RpcMethodAllowlist) -> bool {
+        self.tokens
+            .write()
+            .unwrap()
+            .insert(token, Arc::new(allowlist))
+            .is_none()
+    }
+
+    /// Stops accepting `token`. Returns false if it was not accepted.
+    pub fn remove_token(&self, token: &str) -> bool {
+        self.tokens.write().unwrap().remove(token).is_some()
+    }
+
+    /// Returns the methods callable with the credentials of an HTTP Authorization header, or None
+    /// if they are not accepted
+    pub fn allowed_methods(&self, authorization: Option<&str>) -> Option<Arc<RpcMethodAllowlist>> {
+        match authorization {
+            None => Some(self.public_methods.clone()),
+            Some(authorization) => {
+                let token = parse_bearer_token(authorization)?;
+                self.tokens.read().unwrap().get(token).cloned()
+            }
+        }
+    }
+}
+
+// Tokens are secrets, so only count them
+impl fmt::Debug for RpcAccessControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RpcAccessControl")
+            .field("public_methods", &self.public_methods)
+            .field("num_tokens", &self.tokens.read().unwrap().len())
+            .finish()
+    }
+}
+
+fn parse_bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("bearer") {
+        Some(token.trim_start())
+    } else {
+        None
+    }
+}
+
+/// Fails calls to methods outside of the allowlist that the request metadata was authorized with
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RpcAccessControlMiddleware;
+
+impl Middleware<JsonRpcRequestProcessor> for RpcAccessControlMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = future::Ready<Option<Output>>;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let allowed = match &call {
+            Call::MethodCall(method_call) => meta.is_method_allowed(&method_call.method),
+            Call::Notification(notification) => meta.is_method_allowed(&notification.method),
+            Call::Invalid { .. } => true,
+        };
+        if allowed {
+            return Either::Right(next(call, meta));
+        }
+
+        inc_new_counter_info!("rpc-unauthorized-call", 1);
+        Either::Left(future::ready(match call {
+            Call::MethodCall(method_call) => Some(Output::Failure(Failure {
+                jsonrpc: method_call.jsonrpc,
+                error: RpcCustomError::MethodNotAuthorized {
+                    method: method_call.method,
+                }
+                .into(),
+                id: method_call.id,
+            })),
+            _ => None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bearer_token() {
+        assert_eq!(parse_bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(parse_bearer_token("bearer  abc "), Some("abc"));
+        assert_eq!(parse_bearer_token("Basic abc"), None);
+        assert_eq!(parse_bearer_token("Bearer"), None);
+    }
+
+    #[test]
+    fn test_rpc_access_control() {
+        let access_control =
+            RpcAccessControl::new(vec!["getHealth".to_string()].into_iter().collect());
+
+        let public_methods = access_control.allowed_methods(None).unwrap();
+        assert!(public_methods.allows("getHealth"));
+        assert!(!public_methods.allows("getProgramAccounts"));
+        assert!(access_control.allowed_methods(Some("Bearer abc")).is_none());
+
+        assert!(access_control.add_token(
+            "abc".to_string(),
+            RpcMethodAllowlist::Methods(
+                vec!["getProgramAccounts".to_string()].into_iter().collect()
+            )
+        ));
+        let token_methods = access_control.allowed_methods(Some("Bearer abc")).unwrap();
+        assert!(token_methods.allows("getProgramAccounts"));
+        assert!(!token_methods.allows("getHealth"));
+
+        assert!(!access_control.add_token("abc".to_string(), RpcMethodAllowlist::All));
+        assert!(access_control
+            .allowed_methods(Some("Bearer abc"))
+            .unwrap()
+            .allows("getHealth"));
+
+        assert!(access_control.remove_token("abc"));
+        assert!(!access_control.remove_token("abc"));
+        assert!(access_control.allowed_methods(Some("Bearer abc")).is_none());
+        assert!(!format!("{:?}", access_control).contains("abc"));
+    }
+}
@@ -1,4 +1,5 @@
 use {
+    solana_client::rpc_response::RpcHealthState,
     solana_gossip::cluster_info::ClusterInfo,
     solana_sdk::{clock::Slot, pubkey::Pubkey},
     std::{
@@ -17,6 +18,28 @@ pub enum RpcHealthStatus {
     Unknown,
 }
 
+impl From<RpcHealthStatus> for RpcHealthState {
+    fn from(status: RpcHealthStatus) -> Self {
+        match status {
+            RpcHealthStatus::Ok => Self::Ok,
+            RpcHealthStatus::Unknown => Self::Degraded,
+            RpcHealthStatus::Behind { .. } => Self::Failed,
+        }
+    }
+}
+
+/// State of a subsystem lagging `slots_behind` slots, which degrades past half of
+/// `max_slot_distance` and fails past `max_slot_distance`
+pub fn slot_distance_state(slots_behind: u64, max_slot_distance: u64) -> RpcHealthState {
+    if slots_behind > max_slot_distance {
+        RpcHealthState::Failed
+    } else if slots_behind > max_slot_distance / 2 {
+        RpcHealthState::Degraded
+    } else {
+        RpcHealthState::Ok
+    }
+}
+
 pub struct RpcHealth {
     cluster_info: Arc<ClusterInfo>,
     known_validators: Option<HashSet<Pubkey>>,
@@ -142,3 +165,19 @@ impl RpcHealth {
         *self.stub_health_status.write().unwrap() = stub_health_status;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_distance_state() {
+        assert_eq!(slot_distance_state(0, 150), RpcHealthState::Ok);
+        assert_eq!(slot_distance_state(75, 150), RpcHealthState::Ok);
+        assert_eq!(slot_distance_state(76, 150), RpcHealthState::Degraded);
+        assert_eq!(slot_distance_state(150, 150), RpcHealthState::Degraded);
+        assert_eq!(slot_distance_state(151, 150), RpcHealthState::Failed);
+        assert_eq!(slot_distance_state(0, 0), RpcHealthState::Ok);
+        assert_eq!(slot_distance_state(1, 0), RpcHealthState::Failed);
+    }
+}
@@ -2,15 +2,17 @@
 
 use {
     crate::{
-        rpc::check_is_at_least_confirmed,
+        rpc::{check_is_at_least_confirmed, optimize_filters, verify_filter},
         rpc_pubsub_service::PubSubConfig,
         rpc_subscription_tracker::{
             AccountSubscriptionParams, BlockSubscriptionKind, BlockSubscriptionParams,
-            LogsSubscriptionKind, LogsSubscriptionParams, ProgramSubscriptionParams,
-            SignatureSubscriptionParams, SubscriptionControl, SubscriptionId, SubscriptionParams,
-            SubscriptionToken,
+            LogsSubscriptionKind, LogsSubscriptionParams, PrioritizationFeeSubscriptionParams,
+            ProgramSubscriptionParams, SignatureSubscriptionParams, SubscriptionControl,
+            SubscriptionId, SubscriptionParams, SubscriptionToken,
         },
+        rpc_subscriptions::{NotificationReplayer, ReplayRequest},
     },
+    crossbeam_channel::Sender,
     dashmap::DashMap,
     jsonrpc_core::{Error, ErrorCode, Result},
     jsonrpc_derive::rpc,
@@ -18,13 +20,19 @@ use {
     solana_account_decoder::{UiAccount, UiAccountEncoding},
     solana_client::{
         rpc_config::{
-            RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
-            RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+            RpcAccountInfoConfig, RpcAccountSubscribeConfig, RpcBlockSubscribeConfig,
+            RpcBlockSubscribeFilter, RpcPrioritizationFeeSubscribeConfig, RpcProgramAccountsConfig,
+            RpcSignatureSubscribeConfig, RpcSubscribeResumeConfig, RpcTransactionLogsConfig,
             RpcTransactionLogsFilter,
         },
+        rpc_filter::RpcFilterType,
+        rpc_request::{
+            MAX_GET_PROGRAM_ACCOUNT_FILTERS, MAX_LOGS_SUBSCRIBE_MENTIONS,
+            MAX_PRIORITIZATION_FEE_SUBSCRIBE_ACCOUNTS,
+        },
         rpc_response::{
             Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
-            RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
+            RpcPrioritizationFeeUpdate, RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
         },
     },
     solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature},
@@ -60,7 +68,7 @@ pub trait RpcSolPubSub {
         meta: Self::Metadata,
         subscriber: Subscriber<RpcResponse<UiAccount>>,
         pubkey_str: String,
-        config: Option<RpcAccountInfoConfig>,
+        config: Option<RpcAccountSubscribeConfig>,
     );
 
     // Unsubscribe from account notification subscription.
@@ -153,7 +161,12 @@ pub trait RpcSolPubSub {
 
     // Get notification when slot is encountered
     #[pubsub(subscription = "slotNotification", subscribe, name = "slotSubscribe")]
-    fn slot_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<SlotInfo>);
+    fn slot_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<SlotInfo>,
+        config: Option<RpcSubscribeResumeConfig>,
+    );
 
     // Unsubscribe from slot notification subscription.
     #[pubsub(
@@ -231,7 +244,12 @@ pub trait RpcSolPubSub {
 
     // Get notification when a new root is set
     #[pubsub(subscription = "rootNotification", subscribe, name = "rootSubscribe")]
-    fn root_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<Slot>);
+    fn root_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<Slot>,
+        config: Option<RpcSubscribeResumeConfig>,
+    );
 
     // Unsubscribe from slot notification subscription.
     #[pubsub(
@@ -244,6 +262,31 @@ pub trait RpcSolPubSub {
         meta: Option<Self::Metadata>,
         id: PubSubSubscriptionId,
     ) -> Result<bool>;
+
+    // Get notification of the prioritization fees paid in each bank as it is frozen
+    #[pubsub(
+        subscription = "prioritizationFeeNotification",
+        subscribe,
+        name = "prioritizationFeeSubscribe"
+    )]
+    fn prioritization_fee_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<RpcPrioritizationFeeUpdate>,
+        config: Option<RpcPrioritizationFeeSubscribeConfig>,
+    );
+
+    // Unsubscribe from prioritization fee notification subscription.
+    #[pubsub(
+        subscription = "prioritizationFeeNotification",
+        unsubscribe,
+        name = "prioritizationFeeUnsubscribe"
+    )]
+    fn prioritization_fee_unsubscribe(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: PubSubSubscriptionId,
+    ) -> Result<bool>;
 }
 
 pub use internal::RpcSolPubSubInternal;
@@ -260,7 +303,7 @@ mod internal {
         fn account_subscribe(
             &self,
             pubkey_str: String,
-            config: Option<RpcAccountInfoConfig>,
+            config: Option<RpcAccountSubscribeConfig>,
         ) -> Result<SubscriptionId>;
 
         // Unsubscribe from account notification subscription.
@@ -307,7 +350,10 @@ mod internal {
 
         // Get notification when slot is encountered
         #[rpc(name = "slotSubscribe")]
-        fn slot_subscribe(&self) -> Result<SubscriptionId>;
+        fn slot_subscribe(
+            &self,
+            config: Option<RpcSubscribeResumeConfig>,
+        ) -> Result<SubscriptionId>;
 
         // Unsubscribe from slot notification subscription.
         #[rpc(name = "slotUnsubscribe")]
@@ -343,11 +389,25 @@ mod internal {
 
         // Get notification when a new root is set
         #[rpc(name = "rootSubscribe")]
-        fn root_subscribe(&self) -> Result<SubscriptionId>;
+        fn root_subscribe(
+            &self,
+            config: Option<RpcSubscribeResumeConfig>,
+        ) -> Result<SubscriptionId>;
 
         // Unsubscribe from slot notification subscription.
         #[rpc(name = "rootUnsubscribe")]
         fn root_unsubscribe(&self, id: SubscriptionId) -> Result<bool>;
+
+        // Get notification of the prioritization fees paid in each bank as it is frozen
+        #[rpc(name = "prioritizationFeeSubscribe")]
+        fn prioritization_fee_subscribe(
+            &self,
+            config: Option<RpcPrioritizationFeeSubscribeConfig>,
+        ) -> Result<SubscriptionId>;
+
+        // Unsubscribe from prioritization fee notification subscription.
+        #[rpc(name = "prioritizationFeeUnsubscribe")]
+        fn prioritization_fee_unsubscribe(&self, id: SubscriptionId) -> Result<bool>;
     }
 }
 
@@ -355,6 +415,9 @@ pub struct RpcSolPubSubImpl {
     config: PubSubConfig,
     subscription_control: SubscriptionControl,
     current_subscriptions: Arc<DashMap<SubscriptionId, SubscriptionToken>>,
+    replayer: Arc<NotificationReplayer>,
+    /// Resumed subscriptions, replayed by the connection after the response to the subscription
+    replay_sender: Sender<ReplayRequest>,
 }
 
 impl RpcSolPubSubImpl {
@@ -362,11 +425,15 @@ impl RpcSolPubSubImpl {
         config: PubSubConfig,
         subscription_control: SubscriptionControl,
         current_subscriptions: Arc<DashMap<SubscriptionId, SubscriptionToken>>,
+        replayer: Arc<NotificationReplayer>,
+        replay_sender: Sender<ReplayRequest>,
     ) -> Self {
         Self {
             config,
             subscription_control,
             current_subscriptions,
+            replayer,
+            replay_sender,
         }
     }
 
@@ -385,6 +452,37 @@ impl RpcSolPubSubImpl {
         Ok(id)
     }
 
+    /// Subscribes, then queues the replay of the notifications for the slots since `resume_from`
+    fn subscribe_and_replay(
+        &self,
+        params: SubscriptionParams,
+        resume_from: Option<Slot>,
+    ) -> Result<SubscriptionId> {
+        if let Some(resume_from) = resume_from {
+            let first_replayable_slot = self.replayer.first_replayable_slot();
+            if resume_from < first_replayable_slot {
+                return Err(Error {
+                    code: ErrorCode::InvalidParams,
+                    message: format!(
+                        "Invalid Request: resumeFrom slot {} is older than the first replayable slot {}",
+                        resume_from, first_replayable_slot
+                    ),
+                    data: None,
+                });
+            }
+        }
+        let id = self.subscribe(params.clone())?;
+        if let Some(resume_from) = resume_from {
+            // The connection is gone if this fails
+            let _ = self.replay_sender.send(ReplayRequest {
+                params,
+                id,
+                resume_from,
+            });
+        }
+        Ok(id)
+    }
+
     fn unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
         if self.current_subscriptions.remove(&id).is_some() {
             Ok(true)
@@ -406,20 +504,46 @@ fn param<T: FromStr>(param_str: &str, thing: &str) -> Result<T> {
     })
 }
 
+/// Checks the account filters of a subscription and decodes their bytes up front, so they are
+/// cheap to evaluate against every account update
+fn param_filters(filters: Option<Vec<RpcFilterType>>) -> Result<Vec<RpcFilterType>> {
+    let mut filters = filters.unwrap_or_default();
+    if filters.len() > MAX_GET_PROGRAM_ACCOUNT_FILTERS {
+        return Err(Error {
+            code: ErrorCode::InvalidParams,
+            message: format!(
+                "Invalid Request: Too many filters provided; max {}",
+                MAX_GET_PROGRAM_ACCOUNT_FILTERS
+            ),
+            data: None,
+        });
+    }
+    for filter in &filters {
+        verify_filter(filter)?;
+    }
+    optimize_filters(&mut filters);
+    Ok(filters)
+}
+
 impl RpcSolPubSubInternal for RpcSolPubSubImpl {
     fn account_subscribe(
         &self,
         pubkey_str: String,
-        config: Option<RpcAccountInfoConfig>,
+        config: Option<RpcAccountSubscribeConfig>,
     ) -> Result<SubscriptionId> {
-        let RpcAccountInfoConfig {
-            encoding,
-            data_slice,
-            commitment,
-            min_context_slot: _, // ignored
+        let RpcAccountSubscribeConfig {
+            filters,
+            account_config:
+                RpcAccountInfoConfig {
+                    encoding,
+                    data_slice,
+                    commitment,
+                    min_context_slot: _, // ignored
+                },
         } = config.unwrap_or_default();
         let params = AccountSubscriptionParams {
             pubkey: param::<Pubkey>(&pubkey_str, "pubkey")?,
+            filters: param_filters(filters)?,
             commitment: commitment.unwrap_or_default(),
             data_slice,
             encoding: encoding.unwrap_or(UiAccountEncoding::Binary),
@@ -439,7 +563,7 @@ impl RpcSolPubSubInternal for RpcSolPubSubImpl {
         let config = config.unwrap_or_default();
         let params = ProgramSubscriptionParams {
             pubkey: param::<Pubkey>(&pubkey_str, "pubkey")?,
-            filters: config.filters.unwrap_or_default(),
+            filters: param_filters(config.filters)?,
             encoding: config
                 .account_config
                 .encoding
@@ -460,22 +584,34 @@ impl RpcSolPubSubInternal for RpcSolPubSubImpl {
         filter: RpcTransactionLogsFilter,
         config: Option<RpcTransactionLogsConfig>,
     ) -> Result<SubscriptionId> {
+        let config = config.unwrap_or_default();
         let params = LogsSubscriptionParams {
             kind: match filter {
                 RpcTransactionLogsFilter::All => LogsSubscriptionKind::All,
                 RpcTransactionLogsFilter::AllWithVotes => LogsSubscriptionKind::AllWithVotes,
                 RpcTransactionLogsFilter::Mentions(keys) => {
-                    if keys.len() != 1 {
+                    if keys.is_empty() || keys.len() > MAX_LOGS_SUBSCRIBE_MENTIONS {
                         return Err(Error {
                             code: ErrorCode::InvalidParams,
-                            message: "Invalid Request: Only 1 address supported".into(),
+                            message: format!(
+                                "Invalid Request: Between 1 and {} addresses supported",
+                                MAX_LOGS_SUBSCRIBE_MENTIONS
+                            ),
                             data: None,
                         });
                     }
-                    LogsSubscriptionKind::Single(param::<Pubkey>(&keys[0], "mentions")?)
+                    let mut keys = keys
+                        .iter()
+                        .map(|key| param::<Pubkey>(key, "mentions"))
+                        .collect::<Result<Vec<_>>>()?;
+                    // Order the addresses so that equivalent subscriptions are shared
+                    keys.sort_unstable();
+                    keys.dedup();
+                    LogsSubscriptionKind::Mentions(keys)
                 }
             },
-            commitment: config.and_then(|c| c.commitment).unwrap_or_default(),
+            commitment: config.commitment.unwrap_or_default(),
+            log_prefix: config.log_prefix,
         };
         self.subscribe(SubscriptionParams::Logs(params))
     }
@@ -502,8 +638,9 @@ impl RpcSolPubSubInternal for RpcSolPubSubImpl {
         self.unsubscribe(id)
     }
 
-    fn slot_subscribe(&self) -> Result<SubscriptionId> {
-        self.subscribe(SubscriptionParams::Slot)
+    fn slot_subscribe(&self, config: Option<RpcSubscribeResumeConfig>) -> Result<SubscriptionId> {
+        let resume_from = config.and_then(|config| config.resume_from);
+        self.subscribe_and_replay(SubscriptionParams::Slot, resume_from)
     }
 
     fn slot_unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
@@ -540,12 +677,18 @@ impl RpcSolPubSubInternal for RpcSolPubSubImpl {
                         "mentions_account_or_program",
                     )?)
                 }
+                RpcBlockSubscribeFilter::MentionsProgram(key) => {
+                    BlockSubscriptionKind::MentionsProgram(param::<Pubkey>(
+                        &key,
+                        "mentions_program",
+                    )?)
+                }
             },
             transaction_details: config.transaction_details.unwrap_or_default(),
             show_rewards: config.show_rewards.unwrap_or_default(),
             max_supported_transaction_version: config.max_supported_transaction_version,
         };
-        self.subscribe(SubscriptionParams::Block(params))
+        self.subscribe_and_replay(SubscriptionParams::Block(params), config.resume_from)
     }
 
     fn block_unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
@@ -569,13 +712,42 @@ impl RpcSolPubSubInternal for RpcSolPubSubImpl {
         self.unsubscribe(id)
     }
 
-    fn root_subscribe(&self) -> Result<SubscriptionId> {
-        self.subscribe(SubscriptionParams::Root)
+    fn root_subscribe(&self, config: Option<RpcSubscribeResumeConfig>) -> Result<SubscriptionId> {
+        let resume_from = config.and_then(|config| config.resume_from);
+        self.subscribe_and_replay(SubscriptionParams::Root, resume_from)
     }
 
     fn root_unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
         self.unsubscribe(id)
     }
+
+    fn prioritization_fee_subscribe(
+        &self,
+        config: Option<RpcPrioritizationFeeSubscribeConfig>,
+    ) -> Result<SubscriptionId> {
+        let accounts = config.and_then(|c| c.accounts).unwrap_or_default();
+        if accounts.len() > MAX_PRIORITIZATION_FEE_SUBSCRIBE_ACCOUNTS {
+            return Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: format!(
+                    "Invalid Request: Too many accounts provided; max {}",
+                    MAX_PRIORITIZATION_FEE_SUBSCRIBE_ACCOUNTS
+                ),
+                data: None,
+            });
+        }
+        let params = PrioritizationFeeSubscriptionParams {
+            accounts: accounts
+                .iter()
+                .map(|account| param::<Pubkey>(account, "accounts"))
+                .collect::<Result<_>>()?,
+        };
+        self.subscribe(SubscriptionParams::PrioritizationFee(params))
+    }
+
+    fn prioritization_fee_unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
+        self.unsubscribe(id)
+    }
 }
 
 #[cfg(test)]
@@ -589,8 +761,11 @@ mod tests {
         jsonrpc_core::{IoHandler, Response},
         serial_test::serial,
         solana_account_decoder::{parse_account_data::parse_account_data, UiAccountEncoding},
-        solana_client::rpc_response::{
-            ProcessedSignatureResult, ReceivedSignatureResult, RpcSignatureResult, SlotInfo,
+        solana_client::{
+            rpc_filter::{DataSizeRange, Memcmp, MemcmpEncodedBytes},
+            rpc_response::{
+                ProcessedSignatureResult, ReceivedSignatureResult, RpcSignatureResult, SlotInfo,
+            },
         },
         solana_runtime::{
             bank::Bank,
@@ -866,18 +1041,21 @@ mod tests {
 
         rpc.account_subscribe(
             stake_account.pubkey().to_string(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::processed()),
-                encoding: Some(encoding),
-                data_slice: None,
-                min_context_slot: None,
+            Some(RpcAccountSubscribeConfig {
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::processed()),
+                    encoding: Some(encoding),
+                    data_slice: None,
+                    min_context_slot: None,
+                },
+                filters: None,
             }),
         )
         .unwrap();
 
         // Make sure the subscription is processed before continuing.
         let (rpc2, mut receiver2) = rpc_pubsub_service::test_connection(&rpc_subscriptions);
-        rpc2.slot_subscribe().unwrap();
+        rpc2.slot_subscribe(None).unwrap();
         rpc_subscriptions.notify_slot(1, 0, 0);
         receiver2.recv();
 
@@ -994,18 +1172,21 @@ mod tests {
 
         rpc.account_subscribe(
             nonce_account.pubkey().to_string(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::processed()),
-                encoding: Some(UiAccountEncoding::JsonParsed),
-                data_slice: None,
-                min_context_slot: None,
+            Some(RpcAccountSubscribeConfig {
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::processed()),
+                    encoding: Some(UiAccountEncoding::JsonParsed),
+                    data_slice: None,
+                    min_context_slot: None,
+                },
+                filters: None,
             }),
         )
         .unwrap();
 
         // Make sure the subscription is processed before continuing.
         let (rpc2, mut receiver2) = rpc_pubsub_service::test_connection(&rpc_subscriptions);
-        rpc2.slot_subscribe().unwrap();
+        rpc2.slot_subscribe(None).unwrap();
         rpc_subscriptions.notify_slot(1, 0, 0);
         receiver2.recv();
 
@@ -1060,6 +1241,66 @@ mod tests {
         );
     }
 
+    #[test]
+    #[serial]
+    fn test_account_subscribe_filters() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(Bank::new_for_tests(
+            &genesis_config,
+        ))));
+        let max_complete_transaction_status_slot = Arc::new(AtomicU64::default());
+        let subscriptions = Arc::new(RpcSubscriptions::default_with_bank_forks(
+            max_complete_transaction_status_slot,
+            bank_forks,
+        ));
+        let (rpc, _receiver) = rpc_pubsub_service::test_connection(&subscriptions);
+        let account_subscribe = |filters| {
+            rpc.account_subscribe(
+                solana_sdk::pubkey::new_rand().to_string(),
+                Some(RpcAccountSubscribeConfig {
+                    filters: Some(filters),
+                    ..RpcAccountSubscribeConfig::default()
+                }),
+            )
+        };
+        let memcmp = |offset, bytes: &[u8]| {
+            RpcFilterType::Memcmp(Memcmp {
+                offset,
+                bytes: MemcmpEncodedBytes::Base58(bs58::encode(bytes).into_string()),
+                encoding: None,
+            })
+        };
+
+        assert!(account_subscribe(vec![
+            RpcFilterType::DataSizeRange(DataSizeRange {
+                min: Some(8),
+                max: Some(165),
+            }),
+            memcmp(0, &[1]),
+            memcmp(4, &[2, 3]),
+        ])
+        .is_ok());
+        assert!(
+            account_subscribe(vec![RpcFilterType::DataSizeRange(DataSizeRange {
+                min: Some(165),
+                max: Some(8),
+            })])
+            .is_err()
+        );
+        assert!(
+            account_subscribe(vec![memcmp(0, &[1]); MAX_GET_PROGRAM_ACCOUNT_FILTERS + 1]).is_err()
+        );
+        assert!(rpc
+            .program_subscribe(
+                system_program::id().to_string(),
+                Some(RpcProgramAccountsConfig {
+                    filters: Some(vec![memcmp(0, &[0; 129])]),
+                    ..RpcProgramAccountsConfig::default()
+                }),
+            )
+            .is_err());
+    }
+
     #[test]
     #[serial]
     fn test_account_unsubscribe() {
@@ -1132,11 +1373,14 @@ mod tests {
 
         rpc.account_subscribe(
             bob.pubkey().to_string(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::finalized()),
-                encoding: None,
-                data_slice: None,
-                min_context_slot: None,
+            Some(RpcAccountSubscribeConfig {
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::finalized()),
+                    encoding: None,
+                    data_slice: None,
+                    min_context_slot: None,
+                },
+                filters: None,
             }),
         )
         .unwrap();
@@ -1185,11 +1429,14 @@ mod tests {
 
         rpc.account_subscribe(
             bob.pubkey().to_string(),
-            Some(RpcAccountInfoConfig {
-                commitment: Some(CommitmentConfig::finalized()),
-                encoding: None,
-                data_slice: None,
-                min_context_slot: None,
+            Some(RpcAccountSubscribeConfig {
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::finalized()),
+                    encoding: None,
+                    data_slice: None,
+                    min_context_slot: None,
+                },
+                filters: None,
             }),
         )
         .unwrap();
@@ -1251,7 +1498,7 @@ mod tests {
             bank_forks,
         ));
         let (rpc, mut receiver) = rpc_pubsub_service::test_connection(&rpc_subscriptions);
-        rpc.slot_subscribe().unwrap();
+        rpc.slot_subscribe(None).unwrap();
 
         rpc_subscriptions.notify_slot(0, 0, 0);
 
@@ -1283,7 +1530,7 @@ mod tests {
             bank_forks,
         ));
         let (rpc, mut receiver) = rpc_pubsub_service::test_connection(&rpc_subscriptions);
-        let sub_id = rpc.slot_subscribe().unwrap();
+        let sub_id = rpc.slot_subscribe(None).unwrap();
 
         rpc_subscriptions.notify_slot(0, 0, 0);
         let response = receiver.recv();
@@ -6,13 +6,17 @@ use {
         rpc_subscription_tracker::{
             SubscriptionControl, SubscriptionId, SubscriptionParams, SubscriptionToken,
         },
-        rpc_subscriptions::{RpcNotification, RpcSubscriptions},
+        rpc_subscriptions::{
+            NotificationReplayer, ReplayRequest, RpcNotification, RpcSubscriptions,
+        },
     },
     dashmap::{mapref::entry::Entry, DashMap},
     jsonrpc_core::IoHandler,
     soketto::handshake::{server, Server},
     solana_metrics::TokenCounter,
+    solana_sdk::clock::Slot,
     std::{
+        collections::HashMap,
         io,
         net::SocketAddr,
         str,
@@ -81,6 +85,7 @@ impl PubSubService {
         pubsub_addr: SocketAddr,
     ) -> (Trigger, Self) {
         let subscription_control = subscriptions.control().clone();
+        let replayer = subscriptions.replayer().clone();
         info!("rpc_pubsub bound to {:?}", pubsub_addr);
 
         let (trigger, tripwire) = Tripwire::new();
@@ -96,6 +101,7 @@ impl PubSubService {
                     pubsub_addr,
                     pubsub_config,
                     subscription_control,
+                    replayer,
                     tripwire,
                 )) {
                     error!("pubsub service failed: {}", err);
@@ -117,6 +123,9 @@ impl PubSubService {
 
 struct BroadcastHandler {
     current_subscriptions: Arc<DashMap<SubscriptionId, SubscriptionToken>>,
+    /// Last slot replayed for each resumed subscription, whose live notifications up to that
+    /// slot were already sent
+    replayed_slots: HashMap<SubscriptionId, Slot>,
 }
 
 fn count_final(params: &SubscriptionParams) {
@@ -148,15 +157,41 @@ fn count_final(params: &SubscriptionParams) {
         SubscriptionParams::Block(_) => {
             inc_new_counter_info!("rpc-pubsub-final-slot-txs", 1);
         }
+        SubscriptionParams::PrioritizationFee(_) => {
+            inc_new_counter_info!("rpc-pubsub-final-prioritization-fees", 1);
+        }
     }
 }
 
 impl BroadcastHandler {
-    fn handle(&self, notification: RpcNotification) -> Result<Option<Arc<String>>, Error> {
+    fn new(current_subscriptions: Arc<DashMap<SubscriptionId, SubscriptionToken>>) -> Self {
+        Self {
+            current_subscriptions,
+            replayed_slots: HashMap::new(),
+        }
+    }
+
+    fn replayed(&mut self, id: SubscriptionId, last_replayed_slot: Slot) {
+        let current_subscriptions = &self.current_subscriptions;
+        self.replayed_slots
+            .retain(|id, _| current_subscriptions.contains_key(id));
+        self.replayed_slots.insert(id, last_replayed_slot);
+    }
+
+    fn handle(&mut self, notification: RpcNotification) -> Result<Option<Arc<String>>, Error> {
         if let Entry::Occupied(entry) = self
             .current_subscriptions
             .entry(notification.subscription_id)
         {
+            if let (Some(slot), Some(last_replayed_slot)) = (
+                notification.slot,
+                self.replayed_slots.get(&notification.subscription_id),
+            ) {
+                if slot <= *last_replayed_slot {
+                    return Ok(None);
+                }
+            }
+
             count_final(entry.get().params());
 
             let time_since_created = notification.created_at.elapsed();
@@ -172,6 +207,7 @@ impl BroadcastHandler {
 
             if notification.is_final {
                 entry.remove();
+                self.replayed_slots.remove(&notification.subscription_id);
             }
             notification
                 .json
@@ -188,6 +224,9 @@ impl BroadcastHandler {
 pub struct TestBroadcastReceiver {
     handler: BroadcastHandler,
     inner: tokio::sync::broadcast::Receiver<RpcNotification>,
+    replayer: Arc<NotificationReplayer>,
+    replay_receiver: crossbeam_channel::Receiver<ReplayRequest>,
+    replayed: std::collections::VecDeque<String>,
 }
 
 #[cfg(test)]
@@ -205,6 +244,18 @@ impl TestBroadcastReceiver {
         let started = std::time::Instant::now();
 
         loop {
+            // Replayed notifications are sent before the notifications queued in the meantime
+            while let Ok(request) = self.replay_receiver.try_recv() {
+                let replayed = self.replayer.replay(&request);
+                if let Some((last_replayed_slot, _)) = replayed.last() {
+                    self.handler.replayed(request.id, *last_replayed_slot);
+                }
+                self.replayed
+                    .extend(replayed.into_iter().map(|(_, json)| json));
+            }
+            if let Some(json) = self.replayed.pop_front() {
+                return Ok(json);
+            }
             match self.inner.try_recv() {
                 Ok(notification) => {
                     debug!(
@@ -232,6 +283,7 @@ pub fn test_connection(
     subscriptions: &Arc<RpcSubscriptions>,
 ) -> (RpcSolPubSubImpl, TestBroadcastReceiver) {
     let current_subscriptions = Arc::new(DashMap::new());
+    let (replay_sender, replay_receiver) = crossbeam_channel::unbounded();
 
     let rpc_impl = RpcSolPubSubImpl::new(
         PubSubConfig {
@@ -242,13 +294,16 @@ pub fn test_connection(
         },
         subscriptions.control().clone(),
         Arc::clone(&current_subscriptions),
+        subscriptions.replayer().clone(),
+        replay_sender,
     );
-    let broadcast_handler = BroadcastHandler {
-        current_subscriptions,
-    };
+    let broadcast_handler = BroadcastHandler::new(current_subscriptions);
     let receiver = TestBroadcastReceiver {
         inner: subscriptions.control().broadcast_receiver(),
         handler: broadcast_handler,
+        replayer: subscriptions.replayer().clone(),
+        replay_receiver,
+        replayed: std::collections::VecDeque::new(),
     };
     (rpc_impl, receiver)
 }
@@ -268,6 +323,7 @@ enum Error {
 async fn handle_connection(
     socket: TcpStream,
     subscription_control: SubscriptionControl,
+    replayer: Arc<NotificationReplayer>,
     config: PubSubConfig,
     mut tripwire: Tripwire,
 ) -> Result<(), Error> {
@@ -283,17 +339,18 @@ async fn handle_connection(
     let mut broadcast_receiver = subscription_control.broadcast_receiver();
     let mut data = Vec::new();
     let current_subscriptions = Arc::new(DashMap::new());
+    let (replay_sender, replay_receiver) = crossbeam_channel::unbounded();
 
     let mut json_rpc_handler = IoHandler::new();
     let rpc_impl = RpcSolPubSubImpl::new(
         config,
         subscription_control,
         Arc::clone(&current_subscriptions),
+        Arc::clone(&replayer),
+        replay_sender,
     );
     json_rpc_handler.extend_with(rpc_impl.to_delegate());
-    let broadcast_handler = BroadcastHandler {
-        current_subscriptions,
-    };
+    let mut broadcast_handler = BroadcastHandler::new(current_subscriptions);
     loop {
         // Extra block for dropping `receive_future`.
         {
@@ -335,6 +392,25 @@ async fn handle_connection(
         if let Some(response) = json_rpc_handler.handle_request(data_str).await {
             sender.send_text(&response).await?;
         }
+        // Notifications replayed for a resumed subscription follow the response with its id.
+        // Live notifications queued in the meantime are skipped up to the last replayed slot.
+        for request in replay_receiver.try_iter() {
+            let id = request.id;
+            let replayer = Arc::clone(&replayer);
+            let replayed = tokio::task::spawn_blocking(move || replayer.replay(&request))
+                .await
+                .unwrap_or_else(|err| {
+                    warn!("notification replay failed: {}", err);
+                    vec![]
+                });
+            if let Some((last_replayed_slot, _)) = replayed.last() {
+                broadcast_handler.replayed(id, *last_replayed_slot);
+            }
+            for (_, json) in replayed {
+                inc_new_counter_info!("rpc-pubsub-replayed-messages", 1);
+                sender.send_text(&json).await?;
+            }
+        }
         data.clear();
     }
 
@@ -345,6 +421,7 @@ async fn listen(
     listen_address: SocketAddr,
     config: PubSubConfig,
     subscription_control: SubscriptionControl,
+    replayer: Arc<NotificationReplayer>,
     mut tripwire: Tripwire,
 ) -> io::Result<()> {
     let listener = tokio::net::TcpListener::bind(&listen_address).await?;
@@ -355,12 +432,13 @@ async fn listen(
                 Ok((socket, addr)) => {
                     debug!("new client ({:?})", addr);
                     let subscription_control = subscription_control.clone();
+                    let replayer = replayer.clone();
                     let config = config.clone();
                     let tripwire = tripwire.clone();
                     let counter_token = counter.create_token();
                     tokio::spawn(async move {
                         let handle = handle_connection(
-                            socket, subscription_control, config, tripwire
+                            socket, subscription_control, replayer, config, tripwire
                         );
                         match handle.await {
                             Ok(()) => debug!("connection closed ({:?})", addr),
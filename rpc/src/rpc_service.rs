@@ -2,19 +2,27 @@
 
 use {
     crate::{
+        block_time_backfill_service::{BlockTimeBackfillService, EstimatedBlockTimes},
+        buffered_prioritization_fees::BufferedPrioritizationFees,
         cluster_tpu_info::ClusterTpuInfo,
+        ledger_storage_backend::{open_ledger_archive, LedgerStorageBackend},
         max_slots::MaxSlots,
         optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
         rpc::{
             rpc_accounts::*, rpc_bank::*, rpc_deprecated_v1_7::*, rpc_deprecated_v1_9::*,
             rpc_full::*, rpc_minimal::*, rpc_obsolete_v1_7::*, *,
         },
+        rpc_auth::{RpcAccessControl, RpcAccessControlMiddleware, RpcMethodAllowlist},
         rpc_health::*,
+        rpc_upstreams::{RpcUpstreams, RpcUpstreamsHealthService},
+        vote_latency_service::VoteLatencies,
     },
     crossbeam_channel::unbounded,
+    flate2::{write::GzEncoder, Compression},
     jsonrpc_core::{futures::prelude::*, MetaIoHandler},
     jsonrpc_http_server::{
-        hyper, AccessControlAllowOrigin, CloseHandle, DomainsValidation, RequestMiddleware,
+        hyper::{self, body::HttpBody},
+        AccessControlAllowOrigin, CloseHandle, DomainsValidation, RequestMiddleware,
         RequestMiddlewareAction, ServerBuilder,
     },
     regex::Regex,
@@ -41,6 +49,7 @@ use {
     solana_storage_bigtable::CredentialType,
     std::{
         collections::HashSet,
+        io::Write,
         net::SocketAddr,
         path::{Path, PathBuf},
         sync::{
@@ -72,6 +81,104 @@ struct RpcRequestMiddleware {
     snapshot_config: Option<SnapshotConfig>,
     bank_forks: Arc<RwLock<BankForks>>,
     health: Arc<RpcHealth>,
+    response_compression: Option<RpcResponseCompression>,
+    access_control: Option<Arc<RpcAccessControl>>,
+}
+
+/// Handles JSON RPC requests inside the middleware so that their responses can be compressed,
+/// which the HTTP server does not support on its own
+struct RpcResponseCompression {
+    /// Responses smaller than this many bytes are sent uncompressed
+    threshold: usize,
+    io: Arc<MetaIoHandler<JsonRpcRequestProcessor, RpcAccessControlMiddleware>>,
+    meta: JsonRpcRequestProcessor,
+}
+
+/// Returns the methods callable with the credentials of `request`, or None if they are not
+/// accepted
+fn request_allowed_methods(
+    access_control: &RpcAccessControl,
+    request: &hyper::Request<hyper::Body>,
+) -> Option<Arc<RpcMethodAllowlist>> {
+    let authorization = request
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .map(|authorization| authorization.to_str().unwrap_or_default());
+    access_control.allowed_methods(authorization)
+}
+
+/// Restricts the request metadata to the methods callable with the credentials of `request`
+fn authorize_meta(
+    meta: JsonRpcRequestProcessor,
+    access_control: Option<&RpcAccessControl>,
+    request: &hyper::Request<hyper::Body>,
+) -> JsonRpcRequestProcessor {
+    match access_control {
+        Some(access_control) => meta.with_allowed_methods(
+            request_allowed_methods(access_control, request)
+                .unwrap_or_else(|| Arc::new(RpcMethodAllowlist::none())),
+        ),
+        None => meta,
+    }
+}
+
+/// Content codings supported for JSON RPC responses, in order of preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResponseEncoding {
+    Zstd,
+    Gzip,
+}
+
+impl ResponseEncoding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Zstd => "zstd",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    /// Returns the most preferred encoding accepted by an `Accept-Encoding` header value
+    fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let codings: Vec<(String, f32)> = accept_encoding
+            .split(',')
+            .filter_map(|entry| {
+                let mut params = entry.split(';');
+                let coding = params.next()?.trim().to_ascii_lowercase();
+                if coding.is_empty() {
+                    return None;
+                }
+                let quality = params
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map(|quality| quality.trim().parse::<f32>().unwrap_or(0.0))
+                    .unwrap_or(1.0);
+                Some((coding, quality))
+            })
+            .collect();
+        let quality_of = |coding: &str| {
+            codings
+                .iter()
+                .find(|(accepted, _)| accepted == coding)
+                .map(|(_, quality)| *quality)
+        };
+
+        [Self::Zstd, Self::Gzip].into_iter().find(|encoding| {
+            quality_of(encoding.as_str())
+                .or_else(|| quality_of("*"))
+                .map(|quality| quality > 0.0)
+                .unwrap_or(false)
+        })
+    }
+
+    fn compress(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Zstd => zstd::stream::encode_all(data, 0),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
 }
 
 impl RpcRequestMiddleware {
@@ -94,9 +201,21 @@ impl RpcRequestMiddleware {
             snapshot_config,
             bank_forks,
             health,
+            response_compression: None,
+            access_control: None,
         }
     }
 
+    fn with_response_compression(mut self, response_compression: RpcResponseCompression) -> Self {
+        self.response_compression = Some(response_compression);
+        self
+    }
+
+    fn with_access_control(mut self, access_control: Arc<RpcAccessControl>) -> Self {
+        self.access_control = Some(access_control);
+        self
+    }
+
     fn redirect(location: &str) -> hyper::Response<hyper::Body> {
         hyper::Response::builder()
             .status(hyper::StatusCode::SEE_OTHER)
@@ -120,6 +239,29 @@ impl RpcRequestMiddleware {
             .unwrap()
     }
 
+    fn error_response(status: hyper::StatusCode) -> hyper::Response<hyper::Body> {
+        hyper::Response::builder()
+            .status(status)
+            .body(hyper::Body::empty())
+            .unwrap()
+    }
+
+    fn unauthorized() -> hyper::Response<hyper::Body> {
+        hyper::Response::builder()
+            .status(hyper::StatusCode::UNAUTHORIZED)
+            .header(hyper::header::WWW_AUTHENTICATE, "Bearer")
+            .body(hyper::Body::empty())
+            .unwrap()
+    }
+
+    /// Returns false if `request` carries credentials that are not accepted
+    fn is_authorized(&self, request: &hyper::Request<hyper::Body>) -> bool {
+        self.access_control
+            .as_ref()
+            .map(|access_control| request_allowed_methods(access_control, request).is_some())
+            .unwrap_or(true)
+    }
+
     fn is_file_get_path(&self, path: &str) -> bool {
         if path == DEFAULT_GENESIS_DOWNLOAD_PATH {
             return true;
@@ -230,6 +372,91 @@ impl RpcRequestMiddleware {
         }
     }
 
+    /// Returns the encoding to compress the response to `request` with, if it is a JSON RPC call
+    /// from a client that accepts compressed responses
+    fn response_encoding(&self, request: &hyper::Request<hyper::Body>) -> Option<ResponseEncoding> {
+        self.response_compression.as_ref()?;
+        if request.method() != hyper::Method::POST {
+            return None;
+        }
+        request
+            .headers()
+            .get(hyper::header::ACCEPT_ENCODING)
+            .and_then(|accept_encoding| accept_encoding.to_str().ok())
+            .and_then(ResponseEncoding::negotiate)
+    }
+
+    fn process_compressible_request(
+        &self,
+        request: hyper::Request<hyper::Body>,
+        encoding: ResponseEncoding,
+    ) -> RequestMiddlewareAction {
+        let RpcResponseCompression {
+            threshold,
+            io,
+            meta,
+        } = self.response_compression.as_ref().unwrap();
+        let threshold = *threshold;
+        let io = io.clone();
+        let meta = authorize_meta(meta.clone(), self.access_control.as_deref(), &request);
+
+        RequestMiddlewareAction::Respond {
+            should_validate_hosts: true,
+            response: Box::pin(async move {
+                let mut body = request.into_body();
+                let mut payload = Vec::new();
+                while let Some(chunk) = body.data().await {
+                    let chunk = chunk?;
+                    if payload.len() + chunk.len() > MAX_REQUEST_PAYLOAD_SIZE {
+                        return Ok(Self::error_response(hyper::StatusCode::PAYLOAD_TOO_LARGE));
+                    }
+                    payload.extend_from_slice(&chunk);
+                }
+                let payload = match String::from_utf8(payload) {
+                    Ok(payload) => payload,
+                    Err(_) => return Ok(Self::error_response(hyper::StatusCode::BAD_REQUEST)),
+                };
+
+                let response = io
+                    .handle_request(&payload, meta)
+                    .await
+                    .unwrap_or_default()
+                    .into_bytes();
+                let builder = hyper::Response::builder()
+                    .header(
+                        hyper::header::CONTENT_TYPE,
+                        "application/json; charset=utf-8",
+                    )
+                    .header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+                    .header(hyper::header::VARY, "Accept-Encoding");
+                if response.len() < threshold {
+                    return Ok(builder.body(hyper::Body::from(response)).unwrap());
+                }
+
+                // Compress on the blocking pool so that large responses do not stall the event
+                // loop
+                let compressed = tokio::task::spawn_blocking(move || {
+                    encoding.compress(&response).map_err(|err| {
+                        warn!("failed to compress rpc response: {}", err);
+                        response
+                    })
+                })
+                .await;
+                Ok(match compressed {
+                    Ok(Ok(compressed)) => {
+                        inc_new_counter_info!("rpc-compressed-response", 1);
+                        builder
+                            .header(hyper::header::CONTENT_ENCODING, encoding.as_str())
+                            .body(hyper::Body::from(compressed))
+                            .unwrap()
+                    }
+                    Ok(Err(response)) => builder.body(hyper::Body::from(response)).unwrap(),
+                    Err(_) => Self::internal_server_error(),
+                })
+            }),
+        }
+    }
+
     fn health_check(&self) -> &'static str {
         let response = match self.health.check() {
             RpcHealthStatus::Ok => "ok",
@@ -303,6 +530,11 @@ impl RequestMiddleware for RpcRequestMiddleware {
                 .body(hyper::Body::from(self.health_check()))
                 .unwrap()
                 .into()
+        } else if !self.is_authorized(&request) {
+            inc_new_counter_info!("rpc-unauthorized-request", 1);
+            Self::unauthorized().into()
+        } else if let Some(encoding) = self.response_encoding(&request) {
+            self.process_compressible_request(request, encoding)
         } else {
             request.into()
         }
@@ -353,11 +585,15 @@ impl JsonRpcService {
         max_slots: Arc<MaxSlots>,
         leader_schedule_cache: Arc<LeaderScheduleCache>,
         current_transaction_status_slot: Arc<AtomicU64>,
+        buffered_prioritization_fees: Arc<BufferedPrioritizationFees>,
+        vote_latencies: Arc<VoteLatencies>,
     ) -> Result<Self, String> {
         info!("rpc bound to {:?}", rpc_addr);
         info!("rpc configuration: {:?}", config);
         let rpc_threads = 1.max(config.rpc_threads);
         let rpc_niceness_adj = config.rpc_niceness_adj;
+        let response_compression_threshold = config.rpc_response_compression_threshold;
+        let access_control = config.rpc_access_control.clone();
 
         let health = Arc::new(RpcHealth::new(
             cluster_info.clone(),
@@ -437,24 +673,69 @@ impl JsonRpcService {
                 (None, None)
             };
 
+        let ledger_storage_backend: Option<Arc<dyn LedgerStorageBackend>> =
+            if let Some(bigtable_ledger_storage) = bigtable_ledger_storage {
+                Some(Arc::new(bigtable_ledger_storage))
+            } else if let Some(ledger_archive_config) = &config.rpc_ledger_archive_config {
+                match open_ledger_archive(ledger_archive_config) {
+                    Ok(ledger_archive) => {
+                        info!("Ledger archive storage initialized");
+                        Some(Arc::new(ledger_archive))
+                    }
+                    Err(err) => {
+                        error!("Failed to initialize ledger archive storage: {:?}", err);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+        let estimated_block_times = Arc::new(EstimatedBlockTimes::default());
+        let exit_block_time_backfill_service = Arc::new(AtomicBool::new(false));
+        let _block_time_backfill_service = config.enable_rpc_block_time_backfill.then(|| {
+            BlockTimeBackfillService::new(
+                blockstore.clone(),
+                ledger_storage_backend.clone(),
+                runtime.clone(),
+                estimated_block_times.clone(),
+                exit_block_time_backfill_service.clone(),
+            )
+        });
+
+        let rpc_upstreams = (!config.rpc_upstream_urls.is_empty())
+            .then(|| Arc::new(RpcUpstreams::new(config.rpc_upstream_urls.clone())));
+        let exit_rpc_upstreams_health_service = Arc::new(AtomicBool::new(false));
+        let _rpc_upstreams_health_service = rpc_upstreams.as_ref().map(|rpc_upstreams| {
+            RpcUpstreamsHealthService::new(
+                rpc_upstreams.clone(),
+                runtime.clone(),
+                exit_rpc_upstreams_health_service.clone(),
+            )
+        });
+
         let full_api = config.full_api;
         let obsolete_v1_7_api = config.obsolete_v1_7_api;
         let (request_processor, receiver) = JsonRpcRequestProcessor::new(
             config,
             snapshot_config.clone(),
             bank_forks.clone(),
-            block_commitment_cache,
+            block_commitment_cache.clone(),
             blockstore,
             validator_exit.clone(),
             health.clone(),
             cluster_info.clone(),
             genesis_hash,
-            bigtable_ledger_storage,
+            ledger_storage_backend,
+            rpc_upstreams,
             optimistically_confirmed_bank,
             largest_accounts_cache,
             max_slots,
             leader_schedule_cache,
             current_transaction_status_slot,
+            buffered_prioritization_fees,
+            vote_latencies,
+            estimated_block_times,
         );
 
         let leader_info =
@@ -462,6 +743,7 @@ impl JsonRpcService {
         let _send_transaction_service = Arc::new(SendTransactionService::new_with_config(
             tpu_address,
             &bank_forks,
+            Some(block_commitment_cache),
             leader_info,
             receiver,
             send_transaction_service_config,
@@ -478,7 +760,7 @@ impl JsonRpcService {
             .spawn(move || {
                 renice_this_thread(rpc_niceness_adj).unwrap();
 
-                let mut io = MetaIoHandler::default();
+                let mut io = MetaIoHandler::with_middleware(RpcAccessControlMiddleware);
 
                 io.extend_with(rpc_minimal::MinimalImpl.to_delegate());
                 if full_api {
@@ -492,15 +774,28 @@ impl JsonRpcService {
                     io.extend_with(rpc_obsolete_v1_7::ObsoleteV1_7Impl.to_delegate());
                 }
 
-                let request_middleware = RpcRequestMiddleware::new(
+                let mut request_middleware = RpcRequestMiddleware::new(
                     ledger_path,
                     snapshot_config,
                     bank_forks.clone(),
                     health.clone(),
                 );
+                if let Some(threshold) = response_compression_threshold {
+                    request_middleware =
+                        request_middleware.with_response_compression(RpcResponseCompression {
+                            threshold,
+                            io: Arc::new(io.clone()),
+                            meta: request_processor.clone(),
+                        });
+                }
+                if let Some(access_control) = access_control.clone() {
+                    request_middleware = request_middleware.with_access_control(access_control);
+                }
                 let server = ServerBuilder::with_meta_extractor(
                     io,
-                    move |_req: &hyper::Request<hyper::Body>| request_processor.clone(),
+                    move |req: &hyper::Request<hyper::Body>| {
+                        authorize_meta(request_processor.clone(), access_control.as_deref(), req)
+                    },
                 )
                 .event_loop_executor(runtime.handle().clone())
                 .threads(1)
@@ -527,6 +822,8 @@ impl JsonRpcService {
                 close_handle_sender.send(Ok(server.close_handle())).unwrap();
                 server.wait();
                 exit_bigtable_ledger_upload_service.store(true, Ordering::Relaxed);
+                exit_block_time_backfill_service.store(true, Ordering::Relaxed);
+                exit_rpc_upstreams_health_service.store(true, Ordering::Relaxed);
             })
             .unwrap();
 
@@ -560,7 +857,10 @@ mod tests {
     use {
         super::*,
         crate::rpc::create_validator_exit,
-        solana_client::rpc_config::RpcContextConfig,
+        solana_client::{
+            rpc_config::RpcContextConfig,
+            rpc_custom_error::JSON_RPC_SERVER_ERROR_METHOD_NOT_AUTHORIZED,
+        },
         solana_gossip::{
             contact_info::ContactInfo,
             crds::GossipRoute,
@@ -578,7 +878,7 @@ mod tests {
         },
         solana_streamer::socket::SocketAddrSpace,
         std::{
-            io::Write,
+            io::{Read, Write},
             net::{IpAddr, Ipv4Addr},
         },
         tokio::runtime::Runtime,
@@ -633,6 +933,8 @@ mod tests {
             Arc::new(MaxSlots::default()),
             Arc::new(LeaderScheduleCache::default()),
             Arc::new(AtomicU64::default()),
+            Arc::new(BufferedPrioritizationFees::default()),
+            Arc::new(VoteLatencies::default()),
         )
         .unwrap();
         let thread = rpc_service.thread_hdl.thread();
@@ -802,6 +1104,208 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_response_encoding_negotiate() {
+        assert_eq!(
+            ResponseEncoding::negotiate("gzip"),
+            Some(ResponseEncoding::Gzip)
+        );
+        assert_eq!(
+            ResponseEncoding::negotiate("gzip, deflate, br, zstd"),
+            Some(ResponseEncoding::Zstd)
+        );
+        assert_eq!(
+            ResponseEncoding::negotiate("ZSTD;q=0, gzip;q=0.5"),
+            Some(ResponseEncoding::Gzip)
+        );
+        assert_eq!(
+            ResponseEncoding::negotiate("*"),
+            Some(ResponseEncoding::Zstd)
+        );
+        assert_eq!(
+            ResponseEncoding::negotiate("zstd;q=0, *;q=0.1"),
+            Some(ResponseEncoding::Gzip)
+        );
+        assert_eq!(ResponseEncoding::negotiate("zstd;q=0, gzip;q=0, *"), None);
+        assert_eq!(ResponseEncoding::negotiate("identity"), None);
+        assert_eq!(ResponseEncoding::negotiate(""), None);
+    }
+
+    #[test]
+    fn test_process_compressible_request() {
+        let runtime = Runtime::new().unwrap();
+        let bank = Arc::new(Bank::new_for_tests(
+            &create_genesis_config(10_000).genesis_config,
+        ));
+        let meta = JsonRpcRequestProcessor::new_from_bank(&bank, SocketAddrSpace::Unspecified);
+        let mut io = MetaIoHandler::with_middleware(RpcAccessControlMiddleware);
+        io.extend_with(rpc_minimal::MinimalImpl.to_delegate());
+        let io = Arc::new(io);
+        let create_middleware = |threshold| {
+            RpcRequestMiddleware::new(
+                PathBuf::from("/"),
+                None,
+                create_bank_forks(),
+                RpcHealth::stub(),
+            )
+            .with_response_compression(RpcResponseCompression {
+                threshold,
+                io: io.clone(),
+                meta: meta.clone(),
+            })
+        };
+        let create_request = |accept_encoding: &str| {
+            hyper::Request::post("/")
+                .header(hyper::header::ACCEPT_ENCODING, accept_encoding)
+                .body(hyper::Body::from(
+                    r#"{"jsonrpc":"2.0","id":1,"method":"getVersion"}"#,
+                ))
+                .unwrap()
+        };
+        let respond = |rrm: &RpcRequestMiddleware, accept_encoding: &str| {
+            if let RequestMiddlewareAction::Respond { response, .. } =
+                rrm.on_request(create_request(accept_encoding))
+            {
+                let response = runtime.block_on(response).unwrap();
+                assert_eq!(response.status(), 200);
+                let content_encoding = response
+                    .headers()
+                    .get(hyper::header::CONTENT_ENCODING)
+                    .map(|encoding| encoding.to_str().unwrap().to_string());
+                let body = runtime
+                    .block_on(hyper::body::to_bytes(response.into_body()))
+                    .unwrap()
+                    .to_vec();
+                (content_encoding, body)
+            } else {
+                panic!("Unexpected RequestMiddlewareAction variant");
+            }
+        };
+        let assert_version_response = |body: &[u8]| {
+            let response: serde_json::Value = serde_json::from_slice(body).unwrap();
+            assert!(response["result"]["solana-core"].is_string());
+        };
+
+        let rrm = create_middleware(0);
+        let (content_encoding, body) = respond(&rrm, "gzip");
+        assert_eq!(content_encoding.as_deref(), Some("gzip"));
+        let mut decompressed = vec![];
+        flate2::read::GzDecoder::new(body.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_version_response(&decompressed);
+
+        let (content_encoding, body) = respond(&rrm, "gzip, zstd");
+        assert_eq!(content_encoding.as_deref(), Some("zstd"));
+        assert_version_response(&zstd::stream::decode_all(body.as_slice()).unwrap());
+
+        // Responses below the threshold are sent uncompressed
+        let (content_encoding, body) = respond(&create_middleware(usize::MAX), "zstd");
+        assert_eq!(content_encoding, None);
+        assert_version_response(&body);
+
+        // Requests without a supported encoding are left to the HTTP server
+        assert!(matches!(
+            rrm.on_request(create_request("identity")),
+            RequestMiddlewareAction::Proceed { .. }
+        ));
+        let rrm = RpcRequestMiddleware::new(
+            PathBuf::from("/"),
+            None,
+            create_bank_forks(),
+            RpcHealth::stub(),
+        );
+        assert!(matches!(
+            rrm.on_request(create_request("gzip")),
+            RequestMiddlewareAction::Proceed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_access_control() {
+        let runtime = Runtime::new().unwrap();
+        let bank = Arc::new(Bank::new_for_tests(
+            &create_genesis_config(10_000).genesis_config,
+        ));
+        let meta = JsonRpcRequestProcessor::new_from_bank(&bank, SocketAddrSpace::Unspecified);
+        let mut io = MetaIoHandler::with_middleware(RpcAccessControlMiddleware);
+        io.extend_with(rpc_minimal::MinimalImpl.to_delegate());
+        let access_control = Arc::new(RpcAccessControl::new(
+            vec!["getVersion".to_string()].into_iter().collect(),
+        ));
+        access_control.add_token(
+            "secret".to_string(),
+            RpcMethodAllowlist::Methods(vec!["getSlot".to_string()].into_iter().collect()),
+        );
+        // Handle requests in the middleware, as the HTTP server is not running
+        let rrm = RpcRequestMiddleware::new(
+            PathBuf::from("/"),
+            None,
+            create_bank_forks(),
+            RpcHealth::stub(),
+        )
+        .with_response_compression(RpcResponseCompression {
+            threshold: usize::MAX,
+            io: Arc::new(io),
+            meta,
+        })
+        .with_access_control(access_control.clone());
+        let respond = |authorization: Option<&str>, method: &str| {
+            let mut request =
+                hyper::Request::post("/").header(hyper::header::ACCEPT_ENCODING, "gzip");
+            if let Some(authorization) = authorization {
+                request = request.header(hyper::header::AUTHORIZATION, authorization);
+            }
+            let request = request
+                .body(hyper::Body::from(format!(
+                    r#"{{"jsonrpc":"2.0","id":1,"method":"{}"}}"#,
+                    method
+                )))
+                .unwrap();
+            if let RequestMiddlewareAction::Respond { response, .. } = rrm.on_request(request) {
+                let response = runtime.block_on(response).unwrap();
+                if response.status() != hyper::StatusCode::OK {
+                    return (response.status(), serde_json::Value::Null);
+                }
+                let body = runtime
+                    .block_on(hyper::body::to_bytes(response.into_body()))
+                    .unwrap();
+                (
+                    hyper::StatusCode::OK,
+                    serde_json::from_slice(&body).unwrap(),
+                )
+            } else {
+                panic!("Unexpected RequestMiddlewareAction variant");
+            }
+        };
+        let assert_not_authorized = |response: serde_json::Value| {
+            assert_eq!(
+                response["error"]["code"],
+                JSON_RPC_SERVER_ERROR_METHOD_NOT_AUTHORIZED
+            );
+        };
+
+        let (status, response) = respond(None, "getVersion");
+        assert_eq!(status, hyper::StatusCode::OK);
+        assert!(response["result"].is_object());
+        assert_not_authorized(respond(None, "getSlot").1);
+
+        let (status, response) = respond(Some("Bearer secret"), "getSlot");
+        assert_eq!(status, hyper::StatusCode::OK);
+        assert!(response["result"].is_u64());
+        assert_not_authorized(respond(Some("Bearer secret"), "getVersion").1);
+
+        assert_eq!(
+            respond(Some("Bearer wrong"), "getVersion").0,
+            hyper::StatusCode::UNAUTHORIZED
+        );
+        access_control.remove_token("secret");
+        assert_eq!(
+            respond(Some("Bearer secret"), "getSlot").0,
+            hyper::StatusCode::UNAUTHORIZED
+        );
+    }
+
     #[test]
     fn test_health_check_with_no_known_validators() {
         let rm = RpcRequestMiddleware::new(
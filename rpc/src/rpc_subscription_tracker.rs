@@ -44,6 +44,7 @@ pub enum SubscriptionParams {
     Account(AccountSubscriptionParams),
     Block(BlockSubscriptionParams),
     Logs(LogsSubscriptionParams),
+    PrioritizationFee(PrioritizationFeeSubscriptionParams),
     Program(ProgramSubscriptionParams),
     Signature(SignatureSubscriptionParams),
     Slot,
@@ -53,10 +54,11 @@ pub enum SubscriptionParams {
 }
 
 impl SubscriptionParams {
-    fn method(&self) -> &'static str {
+    pub(crate) fn method(&self) -> &'static str {
         match self {
             SubscriptionParams::Account(_) => "accountNotification",
             SubscriptionParams::Logs(_) => "logsNotification",
+            SubscriptionParams::PrioritizationFee(_) => "prioritizationFeeNotification",
             SubscriptionParams::Program(_) => "programNotification",
             SubscriptionParams::Signature(_) => "signatureNotification",
             SubscriptionParams::Slot => "slotNotification",
@@ -74,7 +76,8 @@ impl SubscriptionParams {
             SubscriptionParams::Program(params) => Some(params.commitment),
             SubscriptionParams::Signature(params) => Some(params.commitment),
             SubscriptionParams::Block(params) => Some(params.commitment),
-            SubscriptionParams::Slot
+            SubscriptionParams::PrioritizationFee(_)
+            | SubscriptionParams::Slot
             | SubscriptionParams::SlotsUpdates
             | SubscriptionParams::Root
             | SubscriptionParams::Vote => None,
@@ -88,7 +91,8 @@ impl SubscriptionParams {
             SubscriptionParams::Logs(params) => &params.commitment,
             SubscriptionParams::Program(params) => &params.commitment,
             SubscriptionParams::Signature(params) => &params.commitment,
-            SubscriptionParams::Root
+            SubscriptionParams::PrioritizationFee(_)
+            | SubscriptionParams::Root
             | SubscriptionParams::Slot
             | SubscriptionParams::SlotsUpdates
             | SubscriptionParams::Vote => return false,
@@ -103,7 +107,8 @@ impl SubscriptionParams {
             SubscriptionParams::Logs(params) => &params.commitment,
             SubscriptionParams::Program(params) => &params.commitment,
             SubscriptionParams::Signature(params) => &params.commitment,
-            SubscriptionParams::Root
+            SubscriptionParams::PrioritizationFee(_)
+            | SubscriptionParams::Root
             | SubscriptionParams::Slot
             | SubscriptionParams::SlotsUpdates
             | SubscriptionParams::Vote => return false,
@@ -114,7 +119,8 @@ impl SubscriptionParams {
     fn is_node_progress_watcher(&self) -> bool {
         matches!(
             self,
-            SubscriptionParams::Slot
+            SubscriptionParams::PrioritizationFee(_)
+                | SubscriptionParams::Slot
                 | SubscriptionParams::SlotsUpdates
                 | SubscriptionParams::Root
                 | SubscriptionParams::Vote
@@ -125,6 +131,7 @@ impl SubscriptionParams {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AccountSubscriptionParams {
     pub pubkey: Pubkey,
+    pub filters: Vec<RpcFilterType>,
     pub encoding: UiAccountEncoding,
     pub data_slice: Option<UiDataSliceConfig>,
     pub commitment: CommitmentConfig,
@@ -144,19 +151,29 @@ pub struct BlockSubscriptionParams {
 pub enum BlockSubscriptionKind {
     All,
     MentionsAccountOrProgram(Pubkey),
+    MentionsProgram(Pubkey),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct LogsSubscriptionParams {
     pub kind: LogsSubscriptionKind,
     pub commitment: CommitmentConfig,
+    /// Only notify transactions that output a log message starting with this prefix
+    pub log_prefix: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LogsSubscriptionKind {
     All,
     AllWithVotes,
-    Single(Pubkey),
+    /// Transactions mentioning any of these addresses, sorted and deduplicated
+    Mentions(Vec<Pubkey>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrioritizationFeeSubscriptionParams {
+    /// Accounts whose write lock fees are reported along with the fees of the whole bank
+    pub accounts: Vec<Pubkey>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -291,11 +308,13 @@ impl SubscriptionControl {
     pub fn logs_subscribed(&self, pubkey: Option<&Pubkey>) -> bool {
         self.0.subscriptions.iter().any(|item| {
             if let SubscriptionParams::Logs(params) = item.key() {
-                let subscribed_pubkey = match &params.kind {
-                    LogsSubscriptionKind::All | LogsSubscriptionKind::AllWithVotes => None,
-                    LogsSubscriptionKind::Single(pubkey) => Some(pubkey),
-                };
-                subscribed_pubkey == pubkey
+                match (&params.kind, pubkey) {
+                    (LogsSubscriptionKind::All | LogsSubscriptionKind::AllWithVotes, None) => true,
+                    (LogsSubscriptionKind::Mentions(pubkeys), Some(pubkey)) => {
+                        pubkeys.contains(pubkey)
+                    }
+                    _ => false,
+                }
             } else {
                 false
             }
@@ -350,42 +369,48 @@ pub enum Error {
 struct LogsSubscriptionsIndex {
     all_count: usize,
     all_with_votes_count: usize,
-    single_count: HashMap<Pubkey, usize>,
+    mentions_count: HashMap<Pubkey, usize>,
 
     bank_forks: Arc<RwLock<BankForks>>,
 }
 
 impl LogsSubscriptionsIndex {
     fn add(&mut self, params: &LogsSubscriptionParams) {
-        match params.kind {
+        match &params.kind {
             LogsSubscriptionKind::All => self.all_count += 1,
             LogsSubscriptionKind::AllWithVotes => self.all_with_votes_count += 1,
-            LogsSubscriptionKind::Single(key) => {
-                *self.single_count.entry(key).or_default() += 1;
+            LogsSubscriptionKind::Mentions(keys) => {
+                for key in keys {
+                    *self.mentions_count.entry(*key).or_default() += 1;
+                }
             }
         }
         self.update_config();
     }
 
     fn remove(&mut self, params: &LogsSubscriptionParams) {
-        match params.kind {
+        match &params.kind {
             LogsSubscriptionKind::All => self.all_count -= 1,
             LogsSubscriptionKind::AllWithVotes => self.all_with_votes_count -= 1,
-            LogsSubscriptionKind::Single(key) => match self.single_count.entry(key) {
-                Entry::Occupied(mut entry) => {
-                    *entry.get_mut() -= 1;
-                    if *entry.get() == 0 {
-                        entry.remove();
+            LogsSubscriptionKind::Mentions(keys) => {
+                for key in keys {
+                    match self.mentions_count.entry(*key) {
+                        Entry::Occupied(mut entry) => {
+                            *entry.get_mut() -= 1;
+                            if *entry.get() == 0 {
+                                entry.remove();
+                            }
+                        }
+                        Entry::Vacant(_) => error!("missing entry in mentions_count"),
                     }
                 }
-                Entry::Vacant(_) => error!("missing entry in single_count"),
-            },
+            }
         }
         self.update_config();
     }
 
     fn update_config(&self) {
-        let mentioned_addresses = self.single_count.keys().copied().collect();
+        let mentioned_addresses = self.mentions_count.keys().copied().collect();
         let config = if self.all_with_votes_count > 0 {
             TransactionLogCollectorConfig {
                 filter: TransactionLogCollectorFilter::AllWithVotes,
@@ -431,7 +456,7 @@ impl SubscriptionsTracker {
             logs_subscriptions_index: LogsSubscriptionsIndex {
                 all_count: 0,
                 all_with_votes_count: 0,
-                single_count: HashMap::new(),
+                mentions_count: HashMap::new(),
                 bank_forks,
             },
             by_signature: HashMap::new(),
@@ -716,6 +741,7 @@ mod tests {
 
         let account_params = SubscriptionParams::Account(AccountSubscriptionParams {
             pubkey: Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap(),
+            filters: vec![],
             commitment: CommitmentConfig::finalized(),
             encoding: UiAccountEncoding::Base64Zstd,
             data_slice: None,
@@ -756,6 +782,7 @@ mod tests {
 
         let account_params = SubscriptionParams::Account(AccountSubscriptionParams {
             pubkey: Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap(),
+            filters: vec![],
             commitment: CommitmentConfig::finalized(),
             encoding: UiAccountEncoding::Base64Zstd,
             data_slice: None,
@@ -767,6 +794,7 @@ mod tests {
 
         let account_params2 = SubscriptionParams::Account(AccountSubscriptionParams {
             pubkey: Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap(),
+            filters: vec![],
             commitment: CommitmentConfig::confirmed(),
             encoding: UiAccountEncoding::Base64Zstd,
             data_slice: None,
@@ -7,19 +7,23 @@ use {
         rpc_pubsub_service::PubSubConfig,
         rpc_subscription_tracker::{
             AccountSubscriptionParams, BlockSubscriptionKind, BlockSubscriptionParams,
-            LogsSubscriptionKind, LogsSubscriptionParams, ProgramSubscriptionParams,
-            SignatureSubscriptionParams, SubscriptionControl, SubscriptionId, SubscriptionInfo,
-            SubscriptionParams, SubscriptionsTracker,
+            LogsSubscriptionKind, LogsSubscriptionParams, PrioritizationFeeSubscriptionParams,
+            ProgramSubscriptionParams, SignatureSubscriptionParams, SubscriptionControl,
+            SubscriptionId, SubscriptionInfo, SubscriptionParams, SubscriptionsTracker,
         },
     },
     crossbeam_channel::{Receiver, RecvTimeoutError, SendError, Sender},
     rayon::prelude::*,
     serde::Serialize,
     solana_account_decoder::{parse_token::is_known_spl_token_id, UiAccount, UiAccountEncoding},
-    solana_client::rpc_response::{
-        ProcessedSignatureResult, ReceivedSignatureResult, Response as RpcResponse, RpcBlockUpdate,
-        RpcBlockUpdateError, RpcKeyedAccount, RpcLogsResponse, RpcResponseContext,
-        RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
+    solana_client::{
+        rpc_request::MAX_SUBSCRIPTION_REPLAY_SLOTS,
+        rpc_response::{
+            ProcessedSignatureResult, ReceivedSignatureResult, Response as RpcResponse,
+            RpcAccountPrioritizationFees, RpcBlockUpdate, RpcBlockUpdateError, RpcKeyedAccount,
+            RpcLogsResponse, RpcPrioritizationFeeUpdate, RpcResponseContext, RpcSignatureResult,
+            RpcVote, SlotInfo, SlotUpdate,
+        },
     },
     solana_ledger::{blockstore::Blockstore, get_tmp_ledger_path},
     solana_measure::measure::Measure,
@@ -62,19 +66,63 @@ fn get_transaction_logs(
     bank: &Bank,
     params: &LogsSubscriptionParams,
 ) -> Option<Vec<TransactionLogInfo>> {
-    let pubkey = match &params.kind {
-        LogsSubscriptionKind::All | LogsSubscriptionKind::AllWithVotes => None,
-        LogsSubscriptionKind::Single(pubkey) => Some(pubkey),
+    let mut logs = match &params.kind {
+        LogsSubscriptionKind::All | LogsSubscriptionKind::AllWithVotes => {
+            bank.get_transaction_logs(None)
+        }
+        LogsSubscriptionKind::Mentions(pubkeys) => bank.get_transaction_logs_for_addresses(pubkeys),
     };
-    let mut logs = bank.get_transaction_logs(pubkey);
-    if matches!(params.kind, LogsSubscriptionKind::All) {
-        // Filter out votes if the subscriber doesn't want them
-        if let Some(logs) = &mut logs {
+    if let Some(logs) = &mut logs {
+        if matches!(params.kind, LogsSubscriptionKind::All) {
+            // Filter out votes if the subscriber doesn't want them
             logs.retain(|log| !log.is_vote);
         }
+        if let Some(log_prefix) = &params.log_prefix {
+            logs.retain(|log| {
+                log.log_messages
+                    .iter()
+                    .any(|log_message| log_message.starts_with(log_prefix))
+            });
+        }
     }
     logs
 }
+
+fn get_prioritization_fee_update(
+    bank: &Bank,
+    params: &PrioritizationFeeSubscriptionParams,
+) -> RpcPrioritizationFeeUpdate {
+    let landed_prioritization_fees = bank.read_landed_prioritization_fees().unwrap();
+    let quantiles = landed_prioritization_fees.quantiles().unwrap_or_default();
+    let accounts = (!params.accounts.is_empty()).then(|| {
+        params
+            .accounts
+            .iter()
+            .map(|account| {
+                let quantiles = landed_prioritization_fees
+                    .account_quantiles(account)
+                    .unwrap_or_default();
+                RpcAccountPrioritizationFees {
+                    account: account.to_string(),
+                    num_transactions: quantiles.num_transactions,
+                    min_compute_unit_price: quantiles.min,
+                    median_compute_unit_price: quantiles.median,
+                    p90_compute_unit_price: quantiles.p90,
+                    max_compute_unit_price: quantiles.max,
+                }
+            })
+            .collect()
+    });
+    RpcPrioritizationFeeUpdate {
+        slot: bank.slot(),
+        num_transactions: quantiles.num_transactions,
+        min_compute_unit_price: quantiles.min,
+        median_compute_unit_price: quantiles.median,
+        p90_compute_unit_price: quantiles.p90,
+        max_compute_unit_price: quantiles.max,
+        accounts,
+    }
+}
 #[derive(Debug)]
 pub struct TimestampedNotificationEntry {
     pub entry: NotificationEntry,
@@ -95,6 +143,7 @@ pub enum NotificationEntry {
     SlotUpdate(SlotUpdate),
     Vote((Pubkey, VoteTransaction, Signature)),
     Root(Slot),
+    BankFrozen(Arc<Bank>),
     Bank(CommitmentSlots),
     Gossip(Slot),
     SignaturesReceived((Slot, Vec<Signature>)),
@@ -106,6 +155,7 @@ impl std::fmt::Debug for NotificationEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             NotificationEntry::Root(root) => write!(f, "Root({})", root),
+            NotificationEntry::BankFrozen(bank) => write!(f, "BankFrozen({})", bank.slot()),
             NotificationEntry::Vote(vote) => write!(f, "Vote({:?})", vote),
             NotificationEntry::Slot(slot_info) => write!(f, "Slot({:?})", slot_info),
             NotificationEntry::SlotUpdate(slot_update) => {
@@ -175,6 +225,8 @@ pub struct RpcNotification {
     pub is_final: bool,
     pub json: Weak<String>,
     pub created_at: Instant,
+    /// Slot of slot, root and block notifications, which resumed subscriptions replay
+    pub slot: Option<Slot>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -281,33 +333,57 @@ struct Notification<T> {
     params: NotificationParams<T>,
 }
 
+fn serialize_notification<T>(value: T, method: &'static str, subscription: SubscriptionId) -> String
+where
+    T: serde::Serialize,
+{
+    RPC_NOTIFIER_BUF.with(|buf| {
+        let mut buf = buf.borrow_mut();
+        buf.clear();
+        let notification = Notification {
+            jsonrpc: Some(jsonrpc_core::Version::V2),
+            method,
+            params: NotificationParams {
+                result: value,
+                subscription,
+            },
+        };
+        serde_json::to_writer(Cursor::new(&mut *buf), &notification)
+            .expect("serialization never fails");
+        let buf_str = str::from_utf8(&buf).expect("json is always utf-8");
+        String::from(buf_str)
+    })
+}
+
 impl RpcNotifier {
     fn notify<T>(&self, value: T, subscription: &SubscriptionInfo, is_final: bool)
     where
         T: serde::Serialize,
     {
-        let buf_arc = RPC_NOTIFIER_BUF.with(|buf| {
-            let mut buf = buf.borrow_mut();
-            buf.clear();
-            let notification = Notification {
-                jsonrpc: Some(jsonrpc_core::Version::V2),
-                method: subscription.method(),
-                params: NotificationParams {
-                    result: value,
-                    subscription: subscription.id(),
-                },
-            };
-            serde_json::to_writer(Cursor::new(&mut *buf), &notification)
-                .expect("serialization never fails");
-            let buf_str = str::from_utf8(&buf).expect("json is always utf-8");
-            Arc::new(String::from(buf_str))
-        });
+        self.notify_with_slot(value, None, subscription, is_final)
+    }
+
+    fn notify_with_slot<T>(
+        &self,
+        value: T,
+        slot: Option<Slot>,
+        subscription: &SubscriptionInfo,
+        is_final: bool,
+    ) where
+        T: serde::Serialize,
+    {
+        let buf_arc = Arc::new(serialize_notification(
+            value,
+            subscription.method(),
+            subscription.id(),
+        ));
 
         let notification = RpcNotification {
             subscription_id: subscription.id(),
             json: Arc::downgrade(&buf_arc),
             is_final,
             created_at: Instant::now(),
+            slot,
         };
         // There is an unlikely case where this can fail: if the last subscription is closed
         // just as the notifier generates a notification for it.
@@ -332,12 +408,15 @@ fn filter_block_result_txs(
             .into_iter()
             .filter(|tx| tx.account_keys().iter().any(|key| key == &pk))
             .collect(),
+        BlockSubscriptionKind::MentionsProgram(program_id) => block
+            .transactions
+            .into_iter()
+            .filter(|tx| tx.invokes_program(&program_id))
+            .collect(),
     };
 
-    if block.transactions.is_empty() {
-        if let BlockSubscriptionKind::MentionsAccountOrProgram(_) = params.kind {
-            return Ok(None);
-        }
+    if block.transactions.is_empty() && params.kind != BlockSubscriptionKind::All {
+        return Ok(None);
     }
 
     let block = ConfirmedBlock::from(block)
@@ -377,7 +456,13 @@ fn filter_account_result(
 
     // If last_modified_slot < last_notified_slot this means that we last notified for a fork
     // and should notify that the account state has been reverted.
-    let results: Box<dyn Iterator<Item = UiAccount>> = if last_modified_slot != last_notified_slot {
+    // Updates the filters reject are skipped before the account is encoded.
+    let results: Box<dyn Iterator<Item = UiAccount>> = if last_modified_slot != last_notified_slot
+        && params
+            .filters
+            .iter()
+            .all(|filter_type| filter_type.allows(&account))
+    {
         if is_known_spl_token_id(account.owner())
             && params.encoding == UiAccountEncoding::JsonParsed
         {
@@ -526,12 +611,173 @@ impl PubsubNotificationStats {
     }
 }
 
+/// A resumed subscription, whose notifications for the slots from `resume_from` onwards are to
+/// be replayed
+#[derive(Debug)]
+pub struct ReplayRequest {
+    pub params: SubscriptionParams,
+    pub id: SubscriptionId,
+    pub resume_from: Slot,
+}
+
+/// Rebuilds from the blockstore the notifications that were sent for past slots, so that clients
+/// can resume slot, root and block subscriptions after reconnecting
+pub struct NotificationReplayer {
+    blockstore: Arc<Blockstore>,
+    max_complete_transaction_status_slot: Arc<AtomicU64>,
+    optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
+}
+
+impl NotificationReplayer {
+    /// Oldest slot that notifications can be replayed from
+    pub fn first_replayable_slot(&self) -> Slot {
+        let first_available_block = self
+            .blockstore
+            .get_first_available_block()
+            .unwrap_or_default();
+        self.blockstore
+            .max_root()
+            .saturating_sub(MAX_SUBSCRIPTION_REPLAY_SLOTS)
+            .max(first_available_block)
+    }
+
+    /// Returns the notifications of the subscription of `request` for the slots from
+    /// `resume_from` onwards, along with their slots, oldest first. Subscriptions other than
+    /// slot, root and block subscriptions have none. Loads up to `MAX_SUBSCRIPTION_REPLAY_SLOTS`
+    /// blocks from the blockstore, so it must not run on the async runtime.
+    pub fn replay(&self, request: &ReplayRequest) -> Vec<(Slot, String)> {
+        let ReplayRequest {
+            params,
+            id,
+            resume_from,
+        } = request;
+        let method = params.method();
+        match params {
+            SubscriptionParams::Slot => self
+                .replay_slots(*resume_from)
+                .into_iter()
+                .map(|slot_info| {
+                    (
+                        slot_info.slot,
+                        serialize_notification(slot_info, method, *id),
+                    )
+                })
+                .collect(),
+            SubscriptionParams::Root => self
+                .rooted_slots(*resume_from, self.blockstore.max_root())
+                .into_iter()
+                .map(|root| (root, serialize_notification(root, method, *id)))
+                .collect(),
+            SubscriptionParams::Block(params) => self
+                .replay_blocks(params, *resume_from)
+                .into_iter()
+                .map(|block_update| {
+                    (
+                        block_update.context.slot,
+                        serialize_notification(block_update, method, *id),
+                    )
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn rooted_slots(&self, resume_from: Slot, max_root: Slot) -> Vec<Slot> {
+        match self.blockstore.rooted_slot_iterator(resume_from) {
+            Ok(rooted_slots) => rooted_slots.take_while(|slot| *slot <= max_root).collect(),
+            Err(err) => {
+                warn!("rooted_slot_iterator error: {}", err);
+                vec![]
+            }
+        }
+    }
+
+    fn replay_slots(&self, resume_from: Slot) -> Vec<SlotInfo> {
+        let slot_metas = match self.blockstore.slot_meta_iterator(resume_from) {
+            Ok(slot_metas) => slot_metas,
+            Err(err) => {
+                warn!("slot_meta_iterator error: {}", err);
+                return vec![];
+            }
+        };
+        slot_metas
+            .filter(|(_slot, meta)| meta.is_full())
+            .filter_map(|(slot, meta)| {
+                let parent = meta.parent_slot?;
+                let root = self
+                    .blockstore
+                    .rooted_slot_iterator_reverse(slot.saturating_sub(1))
+                    .ok()?
+                    .next()
+                    .unwrap_or_default();
+                Some(SlotInfo { slot, parent, root })
+            })
+            .collect()
+    }
+
+    fn replay_blocks(
+        &self,
+        params: &BlockSubscriptionParams,
+        resume_from: Slot,
+    ) -> Vec<RpcResponse<RpcBlockUpdate>> {
+        let max_complete_transaction_status_slot = self
+            .max_complete_transaction_status_slot
+            .load(Ordering::SeqCst);
+        let max_root = self.blockstore.max_root();
+        let mut slots = self.rooted_slots(resume_from, max_root);
+        if !params.commitment.is_finalized() {
+            let bank = self
+                .optimistically_confirmed_bank
+                .read()
+                .unwrap()
+                .bank
+                .clone();
+            let mut confirmed_slots: Vec<_> = bank
+                .proper_ancestors_set()
+                .into_iter()
+                .chain(iter::once(bank.slot()))
+                .filter(|slot| *slot > max_root && *slot >= resume_from)
+                .collect();
+            confirmed_slots.sort_unstable();
+            slots.extend(confirmed_slots);
+        }
+
+        slots
+            .into_iter()
+            .take_while(|slot| *slot <= max_complete_transaction_status_slot)
+            .filter_map(|slot| {
+                let block_update_result = self
+                    .blockstore
+                    .get_complete_block(slot, false)
+                    .map_err(|e| {
+                        error!("get_complete_block error: {}", e);
+                        RpcBlockUpdateError::BlockStoreError
+                    })
+                    .and_then(|block| filter_block_result_txs(block, slot, params));
+                let value = match block_update_result {
+                    Ok(block_update) => block_update?,
+                    Err(err) => RpcBlockUpdate {
+                        slot,
+                        block: None,
+                        err: Some(err),
+                    },
+                };
+                Some(RpcResponse::from(RpcNotificationResponse {
+                    context: RpcNotificationContext { slot },
+                    value,
+                }))
+            })
+            .collect()
+    }
+}
+
 pub struct RpcSubscriptions {
     notification_sender: Option<Sender<TimestampedNotificationEntry>>,
     t_cleanup: Option<JoinHandle<()>>,
 
     exit: Arc<AtomicBool>,
     control: SubscriptionControl,
+    replayer: Arc<NotificationReplayer>,
 }
 
 impl Drop for RpcSubscriptions {
@@ -614,6 +860,12 @@ impl RpcSubscriptions {
     ) -> Self {
         let (notification_sender, notification_receiver) = crossbeam_channel::unbounded();
 
+        let replayer = Arc::new(NotificationReplayer {
+            blockstore: blockstore.clone(),
+            max_complete_transaction_status_slot: max_complete_transaction_status_slot.clone(),
+            optimistically_confirmed_bank: optimistically_confirmed_bank.clone(),
+        });
+
         let exit_clone = exit.clone();
         let subscriptions = SubscriptionsTracker::new(bank_forks.clone());
 
@@ -672,6 +924,7 @@ impl RpcSubscriptions {
             t_cleanup,
             exit: exit.clone(),
             control,
+            replayer,
         }
     }
 
@@ -699,6 +952,10 @@ impl RpcSubscriptions {
         &self.control
     }
 
+    pub fn replayer(&self) -> &Arc<NotificationReplayer> {
+        &self.replayer
+    }
+
     /// Notify subscribers of changes to any accounts or new signatures since
     /// the bank's last checkpoint.
     pub fn notify_subscribers(&self, commitment_slots: CommitmentSlots) {
@@ -724,6 +981,10 @@ impl RpcSubscriptions {
         }));
     }
 
+    pub fn notify_bank_frozen(&self, bank: Arc<Bank>) {
+        self.enqueue_notification(NotificationEntry::BankFrozen(bank));
+    }
+
     pub fn notify_signatures_received(&self, slot_signatures: (Slot, Vec<Signature>)) {
         self.enqueue_notification(NotificationEntry::SignaturesReceived(slot_signatures));
     }
@@ -799,7 +1060,12 @@ impl RpcSubscriptions {
                             {
                                 debug!("slot notify: {:?}", slot_info);
                                 inc_new_counter_info!("rpc-subscription-notify-slot", 1);
-                                notifier.notify(&slot_info, sub, false);
+                                notifier.notify_with_slot(
+                                    &slot_info,
+                                    Some(slot_info.slot),
+                                    sub,
+                                    false,
+                                );
                             }
                         }
                         NotificationEntry::SlotUpdate(slot_update) => {
@@ -838,7 +1104,23 @@ impl RpcSubscriptions {
                             {
                                 debug!("root notify: {:?}", root);
                                 inc_new_counter_info!("rpc-subscription-notify-root", 1);
-                                notifier.notify(&root, sub, false);
+                                notifier.notify_with_slot(&root, Some(root), sub, false);
+                            }
+                        }
+                        NotificationEntry::BankFrozen(bank) => {
+                            for sub in subscriptions.node_progress_watchers().values() {
+                                if let SubscriptionParams::PrioritizationFee(params) = sub.params()
+                                {
+                                    inc_new_counter_info!(
+                                        "rpc-subscription-notify-prioritization-fee",
+                                        1
+                                    );
+                                    notifier.notify(
+                                        &get_prioritization_fee_update(&bank, params),
+                                        sub,
+                                        false,
+                                    );
+                                }
                             }
                         }
                         NotificationEntry::Bank(commitment_slots) => {
@@ -1026,11 +1308,12 @@ impl RpcSubscriptions {
                                 match block_update_result {
                                     Ok(block_update) => {
                                         if let Some(block_update) = block_update {
-                                            notifier.notify(
+                                            notifier.notify_with_slot(
                                                 RpcResponse::from(RpcNotificationResponse {
                                                     context: RpcNotificationContext { slot: s },
                                                     value: block_update,
                                                 }),
+                                                Some(s),
                                                 subscription,
                                                 false,
                                             );
@@ -1043,7 +1326,7 @@ impl RpcSubscriptions {
                                     Err(err) => {
                                         // we don't advance `w_last_unnotified_slot` so that
                                         // it'll retry on the next notification trigger
-                                        notifier.notify(
+                                        notifier.notify_with_slot(
                                             RpcResponse::from(RpcNotificationResponse {
                                                 context: RpcNotificationContext { slot: s },
                                                 value: RpcBlockUpdate {
@@ -1052,6 +1335,7 @@ impl RpcSubscriptions {
                                                     err: Some(err),
                                                 },
                                             }),
+                                            Some(s),
                                             subscription,
                                             false,
                                         );
@@ -1243,17 +1527,24 @@ pub(crate) mod tests {
             rpc_pubsub_service,
         },
         serial_test::serial,
-        solana_client::rpc_config::{
-            RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
-            RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
-            RpcTransactionLogsFilter,
+        solana_client::{
+            rpc_config::{
+                RpcAccountInfoConfig, RpcAccountSubscribeConfig, RpcBlockSubscribeConfig,
+                RpcBlockSubscribeFilter, RpcPrioritizationFeeSubscribeConfig,
+                RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcSubscribeResumeConfig,
+                RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+            },
+            rpc_filter::{DataSizeRange, Memcmp, MemcmpEncodedBytes},
+            rpc_request::MAX_PRIORITIZATION_FEE_SUBSCRIBE_ACCOUNTS,
         },
         solana_runtime::{
             commitment::BlockCommitment,
             genesis_utils::{create_genesis_config, GenesisConfigInfo},
         },
         solana_sdk::{
+            account::WritableAccount,
             commitment_config::CommitmentConfig,
+            compute_budget::ComputeBudgetInstruction,
             message::Message,
             signature::{Keypair, Signer},
             stake, system_instruction, system_program, system_transaction,
@@ -1354,11 +1645,14 @@ pub(crate) mod tests {
             let sub_id = rpc
                 .account_subscribe(
                     pubkey.to_string(),
-                    Some(RpcAccountInfoConfig {
-                        commitment: Some(CommitmentConfig::processed()),
-                        encoding: None,
-                        data_slice: None,
-                        min_context_slot: None,
+                    Some(RpcAccountSubscribeConfig {
+                        account_config: RpcAccountInfoConfig {
+                            commitment: Some(CommitmentConfig::processed()),
+                            encoding: None,
+                            data_slice: None,
+                            min_context_slot: None,
+                        },
+                        filters: None,
                     }),
                 )
                 .unwrap();
@@ -1367,6 +1661,7 @@ pub(crate) mod tests {
                 .control
                 .assert_subscribed(&SubscriptionParams::Account(AccountSubscriptionParams {
                     pubkey,
+                    filters: vec![],
                     commitment: CommitmentConfig::processed(),
                     data_slice: None,
                     encoding: UiAccountEncoding::Binary,
@@ -1396,6 +1691,7 @@ pub(crate) mod tests {
                 .control
                 .assert_unsubscribed(&SubscriptionParams::Account(AccountSubscriptionParams {
                     pubkey,
+                    filters: vec![],
                     commitment: CommitmentConfig::processed(),
                     data_slice: None,
                     encoding: UiAccountEncoding::Binary,
@@ -1437,6 +1733,7 @@ pub(crate) mod tests {
             transaction_details: Some(TransactionDetails::Signatures),
             show_rewards: None,
             max_supported_transaction_version: None,
+            resume_from: None,
         };
         let params = BlockSubscriptionParams {
             kind: BlockSubscriptionKind::All,
@@ -1557,6 +1854,7 @@ pub(crate) mod tests {
             transaction_details: Some(TransactionDetails::Signatures),
             show_rewards: None,
             max_supported_transaction_version: None,
+            resume_from: None,
         };
         let params = BlockSubscriptionParams {
             kind: BlockSubscriptionKind::MentionsAccountOrProgram(keypair1.pubkey()),
@@ -1637,6 +1935,74 @@ pub(crate) mod tests {
             .assert_unsubscribed(&SubscriptionParams::Block(params));
     }
 
+    #[test]
+    fn test_filter_block_result_txs_mentions_program() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let max_complete_transaction_status_slot = Arc::new(AtomicU64::new(blockstore.max_root()));
+        let keypair1 = Keypair::new();
+        let keypair2 = Keypair::new();
+        let keypair3 = Keypair::new();
+        populate_blockstore_for_tests(
+            create_test_transaction_entries(
+                vec![&mint_keypair, &keypair1, &keypair2, &keypair3],
+                bank.clone(),
+            )
+            .0,
+            bank,
+            blockstore.clone(),
+            max_complete_transaction_status_slot,
+        );
+        let slot = 0;
+        let block = blockstore.get_complete_block(slot, false).unwrap();
+        assert!(!block.transactions.is_empty());
+        let params = |kind| BlockSubscriptionParams {
+            kind,
+            commitment: CommitmentConfig::confirmed(),
+            encoding: UiTransactionEncoding::Json,
+            transaction_details: TransactionDetails::Signatures,
+            show_rewards: false,
+            max_supported_transaction_version: None,
+        };
+
+        // Every test transaction invokes the system program
+        let update = filter_block_result_txs(
+            block.clone(),
+            slot,
+            &params(BlockSubscriptionKind::MentionsProgram(system_program::id())),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            update.block.unwrap().signatures.unwrap().len(),
+            block.transactions.len()
+        );
+
+        // keypair1 is mentioned by transactions, but never invoked as a program
+        assert!(filter_block_result_txs(
+            block.clone(),
+            slot,
+            &params(BlockSubscriptionKind::MentionsProgram(keypair1.pubkey())),
+        )
+        .unwrap()
+        .is_none());
+        assert!(filter_block_result_txs(
+            block,
+            slot,
+            &params(BlockSubscriptionKind::MentionsAccountOrProgram(
+                keypair1.pubkey()
+            )),
+        )
+        .unwrap()
+        .is_some());
+    }
+
     #[test]
     #[serial]
     fn test_check_finalized_block_subscribe() {
@@ -1671,6 +2037,7 @@ pub(crate) mod tests {
             transaction_details: Some(TransactionDetails::Signatures),
             show_rewards: None,
             max_supported_transaction_version: None,
+            resume_from: None,
         };
         let params = BlockSubscriptionParams {
             kind: BlockSubscriptionKind::All,
@@ -2583,7 +2950,7 @@ pub(crate) mod tests {
             optimistically_confirmed_bank,
         ));
         let (rpc, mut receiver) = rpc_pubsub_service::test_connection(&subscriptions);
-        let sub_id = rpc.slot_subscribe().unwrap();
+        let sub_id = rpc.slot_subscribe(None).unwrap();
 
         subscriptions
             .control
@@ -2629,7 +2996,7 @@ pub(crate) mod tests {
             optimistically_confirmed_bank,
         ));
         let (rpc, mut receiver) = rpc_pubsub_service::test_connection(&subscriptions);
-        let sub_id = rpc.root_subscribe().unwrap();
+        let sub_id = rpc.root_subscribe(None).unwrap();
 
         subscriptions
             .control
@@ -2655,6 +3022,229 @@ pub(crate) mod tests {
             .assert_unsubscribed(&SubscriptionParams::Root);
     }
 
+    #[test]
+    #[serial]
+    fn test_resume_root_subscribe() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let optimistically_confirmed_bank =
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let max_root = MAX_SUBSCRIPTION_REPLAY_SLOTS + 10;
+        let roots: Vec<_> = (1..=max_root).collect();
+        blockstore.set_roots(roots.iter()).unwrap();
+        let max_complete_transaction_status_slot = Arc::new(AtomicU64::default());
+        let subscriptions = Arc::new(RpcSubscriptions::new_for_tests_with_blockstore(
+            &exit,
+            max_complete_transaction_status_slot,
+            blockstore,
+            bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+            optimistically_confirmed_bank,
+        ));
+        let (rpc, mut receiver) = rpc_pubsub_service::test_connection(&subscriptions);
+        assert_eq!(
+            subscriptions.replayer().first_replayable_slot(),
+            max_root - MAX_SUBSCRIPTION_REPLAY_SLOTS
+        );
+
+        // Too old to be replayed
+        assert!(rpc
+            .root_subscribe(Some(RpcSubscribeResumeConfig {
+                resume_from: Some(max_root - MAX_SUBSCRIPTION_REPLAY_SLOTS - 1),
+            }))
+            .is_err());
+        subscriptions
+            .control
+            .assert_unsubscribed(&SubscriptionParams::Root);
+
+        let sub_id = rpc
+            .root_subscribe(Some(RpcSubscribeResumeConfig {
+                resume_from: Some(max_root - 1),
+            }))
+            .unwrap();
+        subscriptions
+            .control
+            .assert_subscribed(&SubscriptionParams::Root);
+
+        // Missed roots are replayed before live notifications, which skip the replayed roots
+        subscriptions.notify_roots(vec![max_root, max_root + 1]);
+        for expected_root in max_root - 1..=max_root + 1 {
+            let response = receiver.recv();
+            let expected = format!(
+                r#"{{"jsonrpc":"2.0","method":"rootNotification","params":{{"result":{},"subscription":0}}}}"#,
+                expected_root
+            );
+            assert_eq!(expected, response);
+        }
+
+        rpc.root_unsubscribe(sub_id).unwrap();
+        subscriptions
+            .control
+            .assert_unsubscribed(&SubscriptionParams::Root);
+    }
+
+    #[test]
+    fn test_filter_account_result() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        let mut account = AccountSharedData::new(1, 10, &system_program::id());
+        account.data_as_mut_slice()[..3].copy_from_slice(&[1, 2, 3]);
+        let count_notifications = |filters| {
+            let params = AccountSubscriptionParams {
+                pubkey: Pubkey::new_unique(),
+                filters,
+                encoding: UiAccountEncoding::Base64,
+                data_slice: None,
+                commitment: CommitmentConfig::processed(),
+            };
+            let (results, slot) =
+                filter_account_result(Some((account.clone(), 1)), &params, 0, bank.clone());
+            assert_eq!(slot, 1);
+            results.count()
+        };
+        let memcmp = |offset, bytes: &[u8]| {
+            RpcFilterType::Memcmp(Memcmp {
+                offset,
+                bytes: MemcmpEncodedBytes::Bytes(bytes.to_vec()),
+                encoding: None,
+            })
+        };
+        let data_size_range = |min, max| RpcFilterType::DataSizeRange(DataSizeRange { min, max });
+
+        assert_eq!(count_notifications(vec![]), 1);
+        assert_eq!(count_notifications(vec![data_size_range(Some(8), None)]), 1);
+        assert_eq!(count_notifications(vec![data_size_range(None, Some(8))]), 0);
+        assert_eq!(
+            count_notifications(vec![
+                data_size_range(Some(8), Some(16)),
+                memcmp(0, &[1]),
+                memcmp(1, &[2, 3]),
+            ]),
+            1
+        );
+        assert_eq!(
+            count_notifications(vec![memcmp(0, &[1]), memcmp(1, &[3])]),
+            0
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_prioritization_fee_subscribe() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let recipient = Pubkey::new_unique();
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(42),
+                system_instruction::transfer(&mint_keypair.pubkey(), &recipient, 1_000_000),
+            ],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            bank.last_blockhash(),
+        );
+        bank.process_transaction(&tx).unwrap();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let bank0 = bank_forks.read().unwrap().get(0).unwrap();
+        let optimistically_confirmed_bank =
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+        let max_complete_transaction_status_slot = Arc::new(AtomicU64::default());
+        let subscriptions = Arc::new(RpcSubscriptions::new_for_tests(
+            &exit,
+            max_complete_transaction_status_slot,
+            bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+            optimistically_confirmed_bank,
+        ));
+        let (rpc, mut receiver) = rpc_pubsub_service::test_connection(&subscriptions);
+
+        assert!(rpc
+            .prioritization_fee_subscribe(Some(RpcPrioritizationFeeSubscribeConfig {
+                accounts: Some(vec!["invalid".to_string()]),
+            }))
+            .is_err());
+        assert!(rpc
+            .prioritization_fee_subscribe(Some(RpcPrioritizationFeeSubscribeConfig {
+                accounts: Some(vec![
+                    recipient.to_string();
+                    MAX_PRIORITIZATION_FEE_SUBSCRIBE_ACCOUNTS + 1
+                ]),
+            }))
+            .is_err());
+
+        let untouched = Pubkey::new_unique();
+        let sub_id = rpc
+            .prioritization_fee_subscribe(Some(RpcPrioritizationFeeSubscribeConfig {
+                accounts: Some(vec![recipient.to_string(), untouched.to_string()]),
+            }))
+            .unwrap();
+        subscriptions
+            .control
+            .assert_subscribed(&SubscriptionParams::PrioritizationFee(
+                PrioritizationFeeSubscriptionParams {
+                    accounts: vec![recipient, untouched],
+                },
+            ));
+
+        subscriptions.notify_bank_frozen(bank0);
+        let response = receiver.recv();
+        let expected = json!({
+           "jsonrpc": "2.0",
+           "method": "prioritizationFeeNotification",
+           "params": {
+               "result": {
+                   "slot": 0,
+                   "numTransactions": 1,
+                   "minComputeUnitPrice": 42,
+                   "medianComputeUnitPrice": 42,
+                   "p90ComputeUnitPrice": 42,
+                   "maxComputeUnitPrice": 42,
+                   "accounts": [
+                       {
+                           "account": recipient.to_string(),
+                           "numTransactions": 1,
+                           "minComputeUnitPrice": 42,
+                           "medianComputeUnitPrice": 42,
+                           "p90ComputeUnitPrice": 42,
+                           "maxComputeUnitPrice": 42,
+                       },
+                       {
+                           "account": untouched.to_string(),
+                           "numTransactions": 0,
+                           "minComputeUnitPrice": 0,
+                           "medianComputeUnitPrice": 0,
+                           "p90ComputeUnitPrice": 0,
+                           "maxComputeUnitPrice": 0,
+                       },
+                   ],
+               },
+               "subscription": 0,
+           }
+        });
+        assert_eq!(
+            expected,
+            serde_json::from_str::<serde_json::Value>(&response).unwrap(),
+        );
+
+        rpc.prioritization_fee_unsubscribe(sub_id).unwrap();
+        subscriptions
+            .control
+            .assert_unsubscribed(&SubscriptionParams::PrioritizationFee(
+                PrioritizationFeeSubscriptionParams {
+                    accounts: vec![recipient, untouched],
+                },
+            ));
+    }
+
     #[test]
     #[serial]
     fn test_gossip_separate_account_notifications() {
@@ -2693,11 +3283,14 @@ pub(crate) mod tests {
         let sub_id0 = rpc0
             .account_subscribe(
                 alice.pubkey().to_string(),
-                Some(RpcAccountInfoConfig {
-                    commitment: Some(CommitmentConfig::confirmed()),
-                    encoding: None,
-                    data_slice: None,
-                    min_context_slot: None,
+                Some(RpcAccountSubscribeConfig {
+                    account_config: RpcAccountInfoConfig {
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        encoding: None,
+                        data_slice: None,
+                        min_context_slot: None,
+                    },
+                    filters: None,
                 }),
             )
             .unwrap();
@@ -2781,11 +3374,14 @@ pub(crate) mod tests {
         let sub_id1 = rpc1
             .account_subscribe(
                 alice.pubkey().to_string(),
-                Some(RpcAccountInfoConfig {
-                    commitment: Some(CommitmentConfig::confirmed()),
-                    encoding: None,
-                    data_slice: None,
-                    min_context_slot: None,
+                Some(RpcAccountSubscribeConfig {
+                    account_config: RpcAccountInfoConfig {
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        encoding: None,
+                        data_slice: None,
+                        min_context_slot: None,
+                    },
+                    filters: None,
                 }),
             )
             .unwrap();
@@ -2881,6 +3477,7 @@ pub(crate) mod tests {
 
         let sub_config = RpcTransactionLogsConfig {
             commitment: Some(CommitmentConfig::processed()),
+            log_prefix: None,
         };
 
         let (rpc_all, mut receiver_all) = rpc_pubsub_service::test_connection(&subscriptions);
@@ -2938,6 +3535,118 @@ pub(crate) mod tests {
         assert!(!subscriptions.control.logs_subscribed(Some(&alice.pubkey())));
     }
 
+    #[test]
+    #[serial]
+    fn test_logs_subscribe_mentions_and_log_prefix() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let blockhash = bank.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+
+        let exit = Arc::new(AtomicBool::new(false));
+        let max_complete_transaction_status_slot = Arc::new(AtomicU64::default());
+        let optimistically_confirmed_bank =
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+        let subscriptions = Arc::new(RpcSubscriptions::new_for_tests(
+            &exit,
+            max_complete_transaction_status_slot,
+            bank_forks.clone(),
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+            optimistically_confirmed_bank,
+        ));
+
+        let (rpc_both, mut receiver_both) = rpc_pubsub_service::test_connection(&subscriptions);
+        let sub_id_for_both = rpc_both
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![
+                    bob.pubkey().to_string(),
+                    alice.pubkey().to_string(),
+                ]),
+                Some(RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::processed()),
+                    log_prefix: None,
+                }),
+            )
+            .unwrap();
+        assert!(subscriptions.control.logs_subscribed(Some(&alice.pubkey())));
+        assert!(subscriptions.control.logs_subscribed(Some(&bob.pubkey())));
+
+        let (rpc_prefix, mut receiver_prefix) = rpc_pubsub_service::test_connection(&subscriptions);
+        let sub_id_for_prefix = rpc_prefix
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![bob.pubkey().to_string()]),
+                Some(RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::processed()),
+                    log_prefix: Some("Program 11111111111111111111111111111111 invoke".into()),
+                }),
+            )
+            .unwrap();
+
+        let (rpc_no_match, mut receiver_no_match) =
+            rpc_pubsub_service::test_connection(&subscriptions);
+        rpc_no_match
+            .logs_subscribe(
+                RpcTransactionLogsFilter::All,
+                Some(RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::processed()),
+                    log_prefix: Some("Program log:".into()),
+                }),
+            )
+            .unwrap();
+
+        let bank = bank_forks.read().unwrap().get(0).unwrap();
+        let tx_alice = system_transaction::create_account(
+            &mint_keypair,
+            &alice,
+            blockhash,
+            1,
+            0,
+            &system_program::id(),
+        );
+        bank.process_transaction_with_logs(&tx_alice).unwrap();
+        let tx_bob = system_transaction::create_account(
+            &mint_keypair,
+            &bob,
+            blockhash,
+            1,
+            0,
+            &system_program::id(),
+        );
+        bank.process_transaction_with_logs(&tx_bob).unwrap();
+
+        subscriptions.notify_subscribers(CommitmentSlots::new_from_slot(0));
+
+        for tx in [&tx_alice, &tx_bob] {
+            let response = receiver_both.recv();
+            assert_eq!(
+                make_logs_result(&tx.signatures[0].to_string(), u64::from(sub_id_for_both)),
+                serde_json::from_str::<serde_json::Value>(&response).unwrap(),
+            );
+        }
+        let response = receiver_prefix.recv();
+        assert_eq!(
+            make_logs_result(
+                &tx_bob.signatures[0].to_string(),
+                u64::from(sub_id_for_prefix)
+            ),
+            serde_json::from_str::<serde_json::Value>(&response).unwrap(),
+        );
+        assert!(receiver_no_match
+            .recv_timeout(Duration::from_millis(300))
+            .is_err());
+
+        assert!(rpc_both
+            .logs_subscribe(RpcTransactionLogsFilter::Mentions(vec![]), None)
+            .is_err());
+    }
+
     #[test]
     fn test_total_subscriptions() {
         let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(100);
@@ -2977,7 +3686,7 @@ pub(crate) mod tests {
         assert_eq!(subscriptions.total(), 4);
 
         let (rpc5, _receiver5) = rpc_pubsub_service::test_connection(&subscriptions);
-        let sub_id5 = rpc5.slot_subscribe().unwrap();
+        let sub_id5 = rpc5.slot_subscribe(None).unwrap();
 
         assert_eq!(subscriptions.total(), 5);
 
@@ -2987,7 +3696,7 @@ pub(crate) mod tests {
         assert_eq!(subscriptions.total(), 6);
 
         let (rpc7, _receiver7) = rpc_pubsub_service::test_connection(&subscriptions);
-        let sub_id7 = rpc7.root_subscribe().unwrap();
+        let sub_id7 = rpc7.root_subscribe(None).unwrap();
 
         assert_eq!(subscriptions.total(), 7);
 
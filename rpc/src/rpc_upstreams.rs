@@ -0,0 +1,283 @@
+//! The `rpc_upstreams` module forwards requests for history that this node no longer has to
+//! upstream archive RPC nodes, and relays their results. Upstreams are tried in order, skipping
+//! those that report unhealthy and those whose circuit breaker tripped after repeated failures.
+//! At most `MAX_CONCURRENT_UPSTREAM_REQUESTS` requests are forwarded at once.
+use {
+    serde::de::DeserializeOwned,
+    serde_json::Value,
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind},
+        nonblocking::rpc_client::RpcClient,
+        rpc_request::{RpcError, RpcRequest},
+    },
+    std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread::{self, Builder, JoinHandle},
+        time::{Duration, Instant},
+    },
+    tokio::{runtime::Runtime, sync::Semaphore},
+};
+
+/// Consecutive failed requests after which an upstream is skipped for `CIRCUIT_OPEN_DURATION`
+pub const MAX_CONSECUTIVE_UPSTREAM_FAILURES: usize = 3;
+
+/// Requests forwarded at once; lookups that find nothing locally are not forwarded beyond this
+pub const MAX_CONCURRENT_UPSTREAM_REQUESTS: usize = 16;
+
+const CIRCUIT_OPEN_DURATION: Duration = Duration::from_secs(30);
+
+const UPSTREAM_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const UPSTREAM_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Stops requests to an upstream after repeated failures. Once `CIRCUIT_OPEN_DURATION` elapsed,
+/// requests are let through again until the next failure, which trips the circuit right away; the
+/// circuit closes again after a success.
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: usize,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn allows_request(&self, now: Instant) -> bool {
+        self.open_until
+            .map(|open_until| now >= open_until)
+            .unwrap_or(true)
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.consecutive_failures >= MAX_CONSECUTIVE_UPSTREAM_FAILURES {
+            self.open_until = Some(now + CIRCUIT_OPEN_DURATION);
+        }
+    }
+}
+
+struct RpcUpstream {
+    url: String,
+    rpc_client: RpcClient,
+    /// Result of the last health check
+    healthy: AtomicBool,
+    circuit_breaker: Mutex<CircuitBreaker>,
+}
+
+impl RpcUpstream {
+    fn is_available(&self, now: Instant) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+            && self.circuit_breaker.lock().unwrap().allows_request(now)
+    }
+}
+
+/// Upstream archive RPC nodes
+pub struct RpcUpstreams {
+    upstreams: Vec<RpcUpstream>,
+    forward_permits: Semaphore,
+}
+
+impl RpcUpstreams {
+    pub fn new(urls: Vec<String>) -> Self {
+        let upstreams = urls
+            .into_iter()
+            .map(|url| RpcUpstream {
+                rpc_client: RpcClient::new_with_timeout(url.clone(), UPSTREAM_REQUEST_TIMEOUT),
+                url,
+                healthy: AtomicBool::new(true),
+                circuit_breaker: Mutex::default(),
+            })
+            .collect();
+        Self {
+            upstreams,
+            forward_permits: Semaphore::new(MAX_CONCURRENT_UPSTREAM_REQUESTS),
+        }
+    }
+
+    /// Sends `request` to the available upstreams in turn, and returns the first non-null result.
+    /// Returns None if no upstream had one, or if `MAX_CONCURRENT_UPSTREAM_REQUESTS` requests are
+    /// already being forwarded.
+    pub async fn forward<T: DeserializeOwned>(
+        &self,
+        request: RpcRequest,
+        params: Value,
+    ) -> Option<T> {
+        let _permit = match self.forward_permits.try_acquire() {
+            Ok(permit) => permit,
+            Err(_) => {
+                inc_new_counter_info!("rpc-upstream-forward-throttled", 1);
+                return None;
+            }
+        };
+        for upstream in &self.upstreams {
+            if !upstream.is_available(Instant::now()) {
+                continue;
+            }
+            inc_new_counter_info!("rpc-upstream-forward", 1);
+            match upstream
+                .rpc_client
+                .send::<Option<T>>(request, params.clone())
+                .await
+            {
+                Ok(result) => {
+                    upstream.circuit_breaker.lock().unwrap().record_success();
+                    if result.is_some() {
+                        return result;
+                    }
+                }
+                // The upstream answered, it just does not have the result either
+                Err(ClientError {
+                    kind: ClientErrorKind::RpcError(RpcError::RpcResponseError { .. }),
+                    ..
+                }) => upstream.circuit_breaker.lock().unwrap().record_success(),
+                Err(err) => {
+                    inc_new_counter_info!("rpc-upstream-forward-failure", 1);
+                    warn!("{} request to {} failed: {}", request, upstream.url, err);
+                    upstream
+                        .circuit_breaker
+                        .lock()
+                        .unwrap()
+                        .record_failure(Instant::now());
+                }
+            }
+        }
+        None
+    }
+
+    /// Asks each upstream for its health, and skips those that are not healthy until the next
+    /// check
+    pub async fn check_health(&self) {
+        for upstream in &self.upstreams {
+            let healthy = match upstream.rpc_client.get_health().await {
+                Ok(()) => true,
+                Err(err) => {
+                    debug!("Upstream {} is unhealthy: {}", upstream.url, err);
+                    false
+                }
+            };
+            if healthy != upstream.healthy.swap(healthy, Ordering::Relaxed) {
+                info!(
+                    "Upstream {} is {}",
+                    upstream.url,
+                    if healthy { "healthy" } else { "unhealthy" }
+                );
+            }
+        }
+    }
+}
+
+pub struct RpcUpstreamsHealthService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl RpcUpstreamsHealthService {
+    pub fn new(
+        rpc_upstreams: Arc<RpcUpstreams>,
+        runtime: Arc<Runtime>,
+        exit: Arc<AtomicBool>,
+    ) -> Self {
+        info!("Starting RpcUpstreamsHealthService");
+        let thread_hdl = Builder::new()
+            .name("solana-rpc-upstreams-health".to_string())
+            .spawn(move || {
+                while !exit.load(Ordering::Relaxed) {
+                    runtime.block_on(rpc_upstreams.check_health());
+                    thread::sleep(UPSTREAM_HEALTH_CHECK_INTERVAL);
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::clock::Slot, solana_transaction_status::UiConfirmedBlock};
+
+    #[test]
+    fn test_circuit_breaker() {
+        let now = Instant::now();
+        let mut circuit_breaker = CircuitBreaker::default();
+        assert!(circuit_breaker.allows_request(now));
+
+        for _ in 1..MAX_CONSECUTIVE_UPSTREAM_FAILURES {
+            circuit_breaker.record_failure(now);
+        }
+        assert!(circuit_breaker.allows_request(now));
+        circuit_breaker.record_success();
+        for _ in 1..MAX_CONSECUTIVE_UPSTREAM_FAILURES {
+            circuit_breaker.record_failure(now);
+        }
+        assert!(circuit_breaker.allows_request(now));
+
+        circuit_breaker.record_failure(now);
+        assert!(!circuit_breaker.allows_request(now));
+        let retry_at = now + CIRCUIT_OPEN_DURATION;
+        assert!(circuit_breaker.allows_request(retry_at));
+
+        // A failure while half-open trips the circuit again right away
+        circuit_breaker.record_failure(retry_at);
+        assert!(!circuit_breaker.allows_request(retry_at));
+        circuit_breaker.record_success();
+        assert!(circuit_breaker.allows_request(retry_at));
+    }
+
+    #[test]
+    fn test_forward_to_unreachable_upstream() {
+        let rpc_upstreams = RpcUpstreams::new(vec!["http://127.0.0.1:1".to_string()]);
+        let runtime = Runtime::new().unwrap();
+        for _ in 0..MAX_CONSECUTIVE_UPSTREAM_FAILURES {
+            assert!(runtime
+                .block_on(rpc_upstreams.forward::<UiConfirmedBlock>(
+                    RpcRequest::GetBlock,
+                    serde_json::json!([42 as Slot]),
+                ))
+                .is_none());
+        }
+        assert!(!rpc_upstreams.upstreams[0].is_available(Instant::now()));
+
+        runtime.block_on(rpc_upstreams.check_health());
+        assert!(!rpc_upstreams.upstreams[0].healthy.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_forward_throttled() {
+        let rpc_upstreams = RpcUpstreams::new(vec!["http://127.0.0.1:1".to_string()]);
+        let runtime = Runtime::new().unwrap();
+        let permits = rpc_upstreams
+            .forward_permits
+            .try_acquire_many(MAX_CONCURRENT_UPSTREAM_REQUESTS as u32)
+            .unwrap();
+        for _ in 0..MAX_CONSECUTIVE_UPSTREAM_FAILURES {
+            assert!(runtime
+                .block_on(rpc_upstreams.forward::<UiConfirmedBlock>(
+                    RpcRequest::GetBlock,
+                    serde_json::json!([42 as Slot]),
+                ))
+                .is_none());
+        }
+        // Nothing was sent, so the upstream did not fail
+        assert!(rpc_upstreams.upstreams[0].is_available(Instant::now()));
+
+        drop(permits);
+        for _ in 0..MAX_CONSECUTIVE_UPSTREAM_FAILURES {
+            assert!(runtime
+                .block_on(rpc_upstreams.forward::<UiConfirmedBlock>(
+                    RpcRequest::GetBlock,
+                    serde_json::json!([42 as Slot]),
+                ))
+                .is_none());
+        }
+        assert!(!rpc_upstreams.upstreams[0].is_available(Instant::now()));
+    }
+}
@@ -0,0 +1,146 @@
+//! The `slot_time_oracle` module estimates when future slots will be reached, and how long a
+//! transaction takes to reach a commitment level, from the recent slot time and leader skip
+//! rate observed by the node.
+use {
+    solana_ledger::blockstore_meta::PerfSample,
+    solana_sdk::clock::{Slot, DEFAULT_MS_PER_SLOT},
+};
+
+/// Weight of the newest performance sample in the slot time moving average
+pub const SLOT_TIME_EWMA_WEIGHT: f64 = 0.3;
+
+/// Skip rates are capped so a streak of skipped slots doesn't yield unbounded estimates
+const MAX_SKIP_RATE: f64 = 0.9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlotTimeOracle {
+    ms_per_slot: f64,
+    skip_rate: f64,
+}
+
+impl SlotTimeOracle {
+    pub fn new(ms_per_slot: f64, skip_rate: f64) -> Self {
+        Self {
+            ms_per_slot,
+            skip_rate: skip_rate.clamp(0.0, MAX_SKIP_RATE),
+        }
+    }
+
+    /// Builds an oracle from an exponentially weighted moving average of the slot times in
+    /// `perf_samples`, which are ordered newest first as returned by
+    /// `Blockstore::get_recent_perf_samples()`. Falls back to `DEFAULT_MS_PER_SLOT` if no
+    /// sample covers any slots.
+    pub fn from_perf_samples(perf_samples: &[(Slot, PerfSample)], skip_rate: f64) -> Self {
+        let ms_per_slot = perf_samples
+            .iter()
+            .rev()
+            .filter(|(_, sample)| sample.num_slots > 0)
+            .map(|(_, sample)| {
+                f64::from(sample.sample_period_secs) * 1000.0 / sample.num_slots as f64
+            })
+            .fold(None, |ewma, ms_per_slot| {
+                Some(match ewma {
+                    Some(ewma) => {
+                        SLOT_TIME_EWMA_WEIGHT * ms_per_slot + (1.0 - SLOT_TIME_EWMA_WEIGHT) * ewma
+                    }
+                    None => ms_per_slot,
+                })
+            })
+            .unwrap_or(DEFAULT_MS_PER_SLOT as f64);
+        Self::new(ms_per_slot, skip_rate)
+    }
+
+    pub fn ms_per_slot(&self) -> f64 {
+        self.ms_per_slot
+    }
+
+    pub fn skip_rate(&self) -> f64 {
+        self.skip_rate
+    }
+
+    /// Estimated milliseconds until `slot` is reached, given that `current_slot` is the
+    /// slot being processed now
+    pub fn eta_ms(&self, current_slot: Slot, slot: Slot) -> u64 {
+        (slot.saturating_sub(current_slot) as f64 * self.ms_per_slot) as u64
+    }
+
+    /// Expected number of slots until a leader produces a block, with each leader slot
+    /// skipped independently at the skip rate
+    pub fn expected_slots_until_block(&self) -> f64 {
+        1.0 / (1.0 - self.skip_rate)
+    }
+
+    /// Estimated milliseconds until a transaction sent now lands in a block and reaches a
+    /// commitment level trailing the processed slot by `commitment_lag_slots`
+    pub fn confirmation_eta_ms(&self, commitment_lag_slots: u64) -> u64 {
+        ((self.expected_slots_until_block() + commitment_lag_slots as f64) * self.ms_per_slot)
+            as u64
+    }
+}
+
+/// Fraction of the `num_slots` slots in a range that were skipped, given that
+/// `num_rooted_slots` of them were rooted
+pub fn skip_rate(num_rooted_slots: u64, num_slots: u64) -> f64 {
+    if num_slots == 0 {
+        return 0.0;
+    }
+    1.0 - num_rooted_slots.min(num_slots) as f64 / num_slots as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perf_sample(num_slots: u64, sample_period_secs: u16) -> PerfSample {
+        PerfSample {
+            num_transactions: 0,
+            num_slots,
+            sample_period_secs,
+        }
+    }
+
+    #[test]
+    fn test_from_perf_samples() {
+        let oracle = SlotTimeOracle::from_perf_samples(&[], 0.0);
+        assert_eq!(oracle.ms_per_slot(), DEFAULT_MS_PER_SLOT as f64);
+
+        // Newest first; the newest sample carries the most weight
+        let perf_samples = [(300, perf_sample(100, 50)), (200, perf_sample(100, 40))];
+        let oracle = SlotTimeOracle::from_perf_samples(&perf_samples, 0.0);
+        assert!((oracle.ms_per_slot() - 430.0).abs() < f64::EPSILON * 1000.0);
+
+        // Samples without slots are ignored
+        let perf_samples = [(300, perf_sample(0, 60)), (200, perf_sample(100, 40))];
+        let oracle = SlotTimeOracle::from_perf_samples(&perf_samples, 0.0);
+        assert_eq!(oracle.ms_per_slot(), 400.0);
+    }
+
+    #[test]
+    fn test_eta_ms() {
+        let oracle = SlotTimeOracle::new(400.0, 0.0);
+        assert_eq!(oracle.eta_ms(100, 90), 0);
+        assert_eq!(oracle.eta_ms(100, 100), 0);
+        assert_eq!(oracle.eta_ms(100, 110), 4_000);
+    }
+
+    #[test]
+    fn test_confirmation_eta_ms() {
+        let oracle = SlotTimeOracle::new(400.0, 0.0);
+        assert_eq!(oracle.confirmation_eta_ms(0), 400);
+        assert_eq!(oracle.confirmation_eta_ms(31), 12_800);
+
+        let oracle = SlotTimeOracle::new(400.0, 0.5);
+        assert_eq!(oracle.confirmation_eta_ms(0), 800);
+
+        // Skip rates are capped
+        assert_eq!(SlotTimeOracle::new(400.0, 1.0).skip_rate(), MAX_SKIP_RATE);
+    }
+
+    #[test]
+    fn test_skip_rate() {
+        assert_eq!(skip_rate(0, 0), 0.0);
+        assert_eq!(skip_rate(10, 10), 0.0);
+        assert_eq!(skip_rate(5, 10), 0.5);
+        assert_eq!(skip_rate(20, 10), 0.0);
+    }
+}
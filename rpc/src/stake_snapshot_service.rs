@@ -0,0 +1,122 @@
+//! The `stake_snapshot_service` exports the stake delegations of every epoch, as of its first
+//! slot, once a bank in that epoch is rooted.
+use {
+    crate::optimistically_confirmed_bank_tracker::{BankNotification, BankNotificationReceiver},
+    crossbeam_channel::RecvTimeoutError,
+    solana_runtime::{
+        bank::Bank,
+        stake_snapshot::{self, EpochStakeSnapshot},
+    },
+    solana_sdk::clock::Epoch,
+    std::{
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::{Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+const STAKE_SNAPSHOT_RECV_TIMEOUT_MS: u64 = 100;
+
+pub struct StakeSnapshotService;
+impl StakeSnapshotService {
+    pub fn spawn(
+        bank_notification_receiver: BankNotificationReceiver,
+        stake_snapshot_dir: PathBuf,
+        exit: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        Builder::new()
+            .name("solana-stake-snapshot-service".to_string())
+            .spawn(move || {
+                let mut last_exported_epoch = None;
+                loop {
+                    if exit.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    match bank_notification_receiver
+                        .recv_timeout(Duration::from_millis(STAKE_SNAPSHOT_RECV_TIMEOUT_MS))
+                    {
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => {
+                            info!("StakeSnapshotService channel disconnected, exiting.");
+                            break;
+                        }
+                        Ok(BankNotification::Root(bank)) => {
+                            if last_exported_epoch < Some(bank.epoch()) {
+                                Self::export(&bank, &stake_snapshot_dir);
+                                last_exported_epoch = Some(bank.epoch());
+                            }
+                        }
+                        Ok(_) => {}
+                    }
+                }
+            })
+            .unwrap()
+    }
+
+    /// Stakes in effect for an epoch are captured in the epoch stakes computed by the first
+    /// bank of that epoch
+    fn epoch_stake_snapshot(bank: &Bank, epoch: Epoch) -> Option<EpochStakeSnapshot> {
+        let epoch_schedule = bank.epoch_schedule();
+        let leader_schedule_epoch =
+            epoch_schedule.get_leader_schedule_epoch(epoch_schedule.get_first_slot_in_epoch(epoch));
+        bank.epoch_stakes(leader_schedule_epoch)
+            .map(|epoch_stakes| epoch_stakes.stakes())
+            .filter(|stakes| stakes.epoch() == epoch)
+            .map(EpochStakeSnapshot::new)
+    }
+
+    fn export(bank: &Bank, stake_snapshot_dir: &Path) {
+        let epoch = bank.epoch();
+        let snapshot = match Self::epoch_stake_snapshot(bank, epoch) {
+            Some(snapshot) => snapshot,
+            None => {
+                warn!("No stakes available to export for epoch {}", epoch);
+                return;
+            }
+        };
+        match stake_snapshot::write_stake_snapshot(stake_snapshot_dir, &snapshot) {
+            Ok(path) => {
+                info!("Exported stake snapshot for epoch {} to {:?}", epoch, path);
+                datapoint_info!(
+                    "stake-snapshot-export",
+                    ("epoch", epoch, i64),
+                    ("num_validators", snapshot.validators.len(), i64),
+                );
+            }
+            Err(err) => warn!(
+                "Failed to export stake snapshot for epoch {}: {}",
+                epoch, err
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_runtime::genesis_utils::{create_genesis_config_with_leader, GenesisConfigInfo},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_epoch_stake_snapshot() {
+        let GenesisConfigInfo { genesis_config, .. } =
+            create_genesis_config_with_leader(100, &Pubkey::new_unique(), 10);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let first_slot_in_epoch_1 = bank0.epoch_schedule().get_first_slot_in_epoch(1);
+        let bank = Bank::new_from_parent(&bank0, &Pubkey::default(), first_slot_in_epoch_1);
+
+        let snapshot = StakeSnapshotService::epoch_stake_snapshot(&bank, 1).unwrap();
+        assert_eq!(snapshot.epoch, 1);
+        assert_eq!(snapshot.validators.len(), 1);
+        assert_eq!(snapshot.validators[0].delegations.len(), 1);
+        assert_eq!(snapshot.total_activated_stake, 10);
+        assert!(StakeSnapshotService::epoch_stake_snapshot(&bank, 2).is_none());
+    }
+}
@@ -0,0 +1,280 @@
+//! The `vote_latency_service` measures how many slots the votes of every validator take to land,
+//! from the slot voted on to the slot of the replayed block that includes the vote, so the vote
+//! performance of validators can be compared without indexing their vote transactions.
+use {
+    crate::optimistically_confirmed_bank_tracker::{BankNotification, BankNotificationReceiver},
+    crossbeam_channel::RecvTimeoutError,
+    solana_client::rpc_response::{RpcVoteLatencies, RpcVoteLatency},
+    solana_runtime::bank::Bank,
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::{
+        collections::{BTreeMap, HashMap, HashSet},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, RwLock,
+        },
+        thread::{Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+const VOTE_LATENCY_RECV_TIMEOUT_MS: u64 = 100;
+
+/// Votes on slots further behind the highest slot a vote landed in are forgotten
+pub const VOTE_LATENCY_WINDOW_SLOTS: Slot = 4_096;
+
+/// Latencies are counted one slot apart up to this many slots, and together beyond
+pub const MAX_VOTE_LATENCY_BUCKETS: usize = 32;
+
+#[derive(Debug, Default)]
+struct VoteLatenciesInner {
+    /// Latency of the landed votes of each vote account, by slot voted on
+    latencies: HashMap<Pubkey, BTreeMap<Slot, Slot>>,
+    highest_landed_slot: Slot,
+}
+
+/// Rolling store of the latencies of the votes landed over the last
+/// `VOTE_LATENCY_WINDOW_SLOTS` slots
+#[derive(Debug, Default)]
+pub struct VoteLatencies {
+    inner: RwLock<VoteLatenciesInner>,
+}
+
+impl VoteLatencies {
+    /// Records the votes landed in `bank`, which are the ones added to the vote states since its
+    /// parent. A vote landing on several forks keeps the latency of the first one replayed.
+    pub fn record_bank(&self, bank: &Bank) {
+        let parent = match bank.parent() {
+            Some(parent) => parent,
+            None => return,
+        };
+        let parent_vote_accounts = parent.vote_accounts();
+        let landed_votes: Vec<_> = bank
+            .vote_accounts()
+            .iter()
+            .filter_map(|(vote_pubkey, (_stake, vote_account))| {
+                // Vote accounts created in this bank have no votes to compare against
+                let (_stake, parent_vote_account) = parent_vote_accounts.get(vote_pubkey)?;
+                let parent_last_voted_slot = parent_vote_account
+                    .vote_state()
+                    .as_ref()
+                    .ok()?
+                    .last_voted_slot();
+                let vote_state = vote_account.vote_state();
+                let voted_slots: Vec<_> = vote_state
+                    .as_ref()
+                    .ok()?
+                    .votes
+                    .iter()
+                    .map(|lockout| lockout.slot)
+                    .filter(|slot| Some(*slot) > parent_last_voted_slot)
+                    .collect();
+                (!voted_slots.is_empty()).then(|| (*vote_pubkey, voted_slots))
+            })
+            .collect();
+        self.record(bank.slot(), landed_votes);
+    }
+
+    pub(crate) fn record(&self, landed_slot: Slot, landed_votes: Vec<(Pubkey, Vec<Slot>)>) {
+        let mut inner = self.inner.write().unwrap();
+        for (vote_pubkey, voted_slots) in landed_votes {
+            let latencies = inner.latencies.entry(vote_pubkey).or_default();
+            for voted_slot in voted_slots {
+                latencies
+                    .entry(voted_slot)
+                    .or_insert_with(|| landed_slot.saturating_sub(voted_slot));
+            }
+        }
+        if landed_slot > inner.highest_landed_slot {
+            inner.highest_landed_slot = landed_slot;
+            let first_slot = landed_slot.saturating_sub(VOTE_LATENCY_WINDOW_SLOTS);
+            inner.latencies.retain(|_, latencies| {
+                *latencies = latencies.split_off(&first_slot);
+                !latencies.is_empty()
+            });
+        }
+    }
+
+    /// Summarizes the latencies of the votes of the given vote accounts, or of every vote account
+    /// if `None`, ordered by vote account
+    pub fn get(&self, vote_pubkeys: Option<&HashSet<Pubkey>>) -> RpcVoteLatencies {
+        let inner = self.inner.read().unwrap();
+        let mut validators: Vec<_> = inner
+            .latencies
+            .iter()
+            .filter(|(vote_pubkey, _)| {
+                vote_pubkeys.map_or(true, |vote_pubkeys| vote_pubkeys.contains(vote_pubkey))
+            })
+            .map(|(vote_pubkey, latencies)| {
+                Self::summarize(vote_pubkey, latencies.values().copied().collect())
+            })
+            .collect();
+        validators.sort_by(|a, b| a.vote_pubkey.cmp(&b.vote_pubkey));
+        RpcVoteLatencies {
+            first_slot: inner
+                .highest_landed_slot
+                .saturating_sub(VOTE_LATENCY_WINDOW_SLOTS),
+            last_slot: inner.highest_landed_slot,
+            validators,
+        }
+    }
+
+    fn summarize(vote_pubkey: &Pubkey, mut latencies: Vec<Slot>) -> RpcVoteLatency {
+        latencies.sort_unstable();
+        let num_votes = latencies.len();
+        let mut latency_counts = vec![0; MAX_VOTE_LATENCY_BUCKETS];
+        for latency in &latencies {
+            let bucket = (*latency as usize).clamp(1, MAX_VOTE_LATENCY_BUCKETS) - 1;
+            latency_counts[bucket] += 1;
+        }
+        RpcVoteLatency {
+            vote_pubkey: vote_pubkey.to_string(),
+            num_votes: num_votes as u64,
+            mean_latency: latencies.iter().sum::<Slot>() as f64 / num_votes.max(1) as f64,
+            median_latency: latencies.get(num_votes / 2).copied().unwrap_or_default(),
+            p90_latency: latencies
+                .get(num_votes * 9 / 10)
+                .copied()
+                .unwrap_or_default(),
+            max_latency: latencies.last().copied().unwrap_or_default(),
+            latency_counts,
+        }
+    }
+}
+
+pub struct VoteLatencyService;
+impl VoteLatencyService {
+    pub fn spawn(
+        bank_notification_receiver: BankNotificationReceiver,
+        vote_latencies: Arc<VoteLatencies>,
+        exit: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        Builder::new()
+            .name("solana-vote-latency-service".to_string())
+            .spawn(move || loop {
+                if exit.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                match bank_notification_receiver
+                    .recv_timeout(Duration::from_millis(VOTE_LATENCY_RECV_TIMEOUT_MS))
+                {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        info!("VoteLatencyService channel disconnected, exiting.");
+                        break;
+                    }
+                    Ok(BankNotification::Frozen(bank)) => vote_latencies.record_bank(&bank),
+                    Ok(_) => {}
+                }
+            })
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_runtime::genesis_utils::{
+            create_genesis_config_with_vote_accounts, GenesisConfigInfo, ValidatorVoteKeypairs,
+        },
+        solana_sdk::signature::Signer,
+        solana_vote_program::vote_transaction,
+    };
+
+    #[test]
+    fn test_vote_latencies_record() {
+        let vote_latencies = VoteLatencies::default();
+        let vote_pubkey = Pubkey::new_unique();
+        let other_vote_pubkey = Pubkey::new_unique();
+        vote_latencies.record(2, vec![(vote_pubkey, vec![1])]);
+        vote_latencies.record(
+            5,
+            vec![(vote_pubkey, vec![2, 3]), (other_vote_pubkey, vec![4])],
+        );
+        // The same vote landing on another fork keeps its first latency
+        vote_latencies.record(6, vec![(vote_pubkey, vec![3])]);
+
+        let latencies = vote_latencies.get(None);
+        assert_eq!(latencies.last_slot, 6);
+        assert_eq!(latencies.validators.len(), 2);
+        let latency = latencies
+            .validators
+            .iter()
+            .find(|latency| latency.vote_pubkey == vote_pubkey.to_string())
+            .unwrap();
+        assert_eq!(latency.num_votes, 3);
+        assert_eq!(latency.mean_latency, 2.0);
+        assert_eq!(latency.median_latency, 2);
+        assert_eq!(latency.p90_latency, 3);
+        assert_eq!(latency.max_latency, 3);
+        assert_eq!(&latency.latency_counts[..4], &[1, 1, 1, 0]);
+
+        let latencies = vote_latencies.get(Some(&HashSet::from([other_vote_pubkey])));
+        assert_eq!(latencies.validators.len(), 1);
+        assert_eq!(latencies.validators[0].num_votes, 1);
+        assert_eq!(latencies.validators[0].latency_counts[0], 1);
+
+        // Votes beyond the window are forgotten
+        vote_latencies.record(VOTE_LATENCY_WINDOW_SLOTS + 4, vec![]);
+        let latencies = vote_latencies.get(None);
+        assert_eq!(latencies.first_slot, 4);
+        assert_eq!(latencies.validators.len(), 1);
+        assert_eq!(
+            latencies.validators[0].vote_pubkey,
+            other_vote_pubkey.to_string()
+        );
+
+        // Latencies beyond the last bucket are counted together
+        let far_slot = VOTE_LATENCY_WINDOW_SLOTS + 4 + MAX_VOTE_LATENCY_BUCKETS as Slot + 10;
+        vote_latencies.record(far_slot, vec![(vote_pubkey, vec![far_slot - 40])]);
+        let latencies = vote_latencies.get(Some(&HashSet::from([vote_pubkey])));
+        assert_eq!(latencies.validators[0].max_latency, 40);
+        assert_eq!(
+            latencies.validators[0].latency_counts[MAX_VOTE_LATENCY_BUCKETS - 1],
+            1
+        );
+    }
+
+    #[test]
+    fn test_vote_latencies_record_bank() {
+        let validator_keypairs = ValidatorVoteKeypairs::new_rand();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+            1_000_000_000,
+            &[&validator_keypairs],
+            vec![100],
+        );
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
+        bank1.freeze();
+        let bank3 = Bank::new_from_parent(&bank1, &Pubkey::default(), 3);
+
+        // Votes on slots 0 and 1 land in slot 3
+        let vote_tx = vote_transaction::new_vote_transaction(
+            vec![0, 1],
+            bank1.hash(),
+            bank3.last_blockhash(),
+            &validator_keypairs.node_keypair,
+            &validator_keypairs.vote_keypair,
+            &validator_keypairs.vote_keypair,
+            None,
+        );
+        bank3.process_transaction(&vote_tx).unwrap();
+
+        let vote_latencies = VoteLatencies::default();
+        vote_latencies.record_bank(&bank3);
+        let latencies = vote_latencies.get(None);
+        assert_eq!(latencies.last_slot, 3);
+        assert_eq!(latencies.validators.len(), 1);
+        let latency = &latencies.validators[0];
+        assert_eq!(
+            latency.vote_pubkey,
+            validator_keypairs.vote_keypair.pubkey().to_string()
+        );
+        assert_eq!(latency.num_votes, 2);
+        assert_eq!(latency.mean_latency, 2.5);
+        assert_eq!(latency.median_latency, 3);
+        assert_eq!(latency.max_latency, 3);
+    }
+}
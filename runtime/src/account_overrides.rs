@@ -1,9 +1,13 @@
-use solana_sdk::{account::AccountSharedData, pubkey::Pubkey, sysvar};
+use {
+    solana_sdk::{account::AccountSharedData, pubkey::Pubkey, sysvar},
+    std::collections::HashMap,
+};
 
 /// Encapsulates overridden accounts, typically used for transaction simulations
 #[derive(Default)]
 pub struct AccountOverrides {
     pub slot_history: Option<AccountSharedData>,
+    pub accounts: HashMap<Pubkey, AccountSharedData>,
 }
 
 impl AccountOverrides {
@@ -14,12 +18,19 @@ impl AccountOverrides {
         self.slot_history = slot_history;
     }
 
+    /// Overrides the state of the account at `pubkey`
+    ///
+    /// Note: no checks are performed on the correctness of the contained data
+    pub fn set_account(&mut self, pubkey: &Pubkey, account: AccountSharedData) {
+        self.accounts.insert(*pubkey, account);
+    }
+
     /// Gets the account if it's found in the list of overrides
     pub fn get(&self, pubkey: &Pubkey) -> Option<&AccountSharedData> {
         if pubkey == &sysvar::slot_history::id() {
             self.slot_history.as_ref()
         } else {
-            None
+            self.accounts.get(pubkey)
         }
     }
 }
@@ -51,7 +51,7 @@ use {
     std::{
         cmp::Reverse,
         collections::{hash_map, BinaryHeap, HashMap, HashSet},
-        ops::RangeBounds,
+        ops::{Bound, RangeBounds},
         path::PathBuf,
         sync::{
             atomic::{AtomicUsize, Ordering},
@@ -62,10 +62,20 @@ use {
 
 pub type PubkeyAccountSlot = (Pubkey, AccountSharedData, Slot);
 
+/// Write-lock activity on an account while transactions were processed against an `Accounts`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, AbiExample)]
+pub struct AccountWriteLockCounts {
+    /// Write locks taken on the account
+    pub write_locks: u64,
+    /// Transactions that failed to lock the account because it was already locked
+    pub conflicts: u64,
+}
+
 #[derive(Debug, Default, AbiExample)]
 pub struct AccountLocks {
     write_locks: HashSet<Pubkey>,
     readonly_locks: HashMap<Pubkey, u64>,
+    write_lock_counts: HashMap<Pubkey, AccountWriteLockCounts>,
 }
 
 impl AccountLocks {
@@ -103,6 +113,14 @@ impl AccountLocks {
     fn unlock_write(&mut self, key: &Pubkey) {
         self.write_locks.remove(key);
     }
+
+    fn record_write_lock(&mut self, key: &Pubkey) {
+        self.write_lock_counts.entry(*key).or_default().write_locks += 1;
+    }
+
+    fn record_conflict(&mut self, key: &Pubkey) {
+        self.write_lock_counts.entry(*key).or_default().conflicts += 1;
+    }
 }
 
 /// This structure handles synchronization for db
@@ -906,6 +924,74 @@ impl Accounts {
         )
     }
 
+    /// Loads, in pubkey order, up to `limit` accounts owned by `program_id` that pass `filter`,
+    /// starting after pubkey `after`. The scan stops as soon as `limit` accounts are found, so
+    /// a program's accounts can be paged through without loading all of them at once.
+    pub fn load_by_program_with_filter_after<F: Fn(&AccountSharedData) -> bool>(
+        &self,
+        ancestors: &Ancestors,
+        bank_id: BankId,
+        program_id: &Pubkey,
+        filter: F,
+        after: Option<&Pubkey>,
+        limit: usize,
+    ) -> ScanResult<Vec<TransactionAccount>> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+        let start_bound = after.map_or(Bound::Unbounded, |after| Bound::Excluded(*after));
+        // A sorted scan, which is aborted, without error, once the page is full
+        let config = ScanConfig::default().recreate_with_abort();
+        self.accounts_db.checked_range_scan_accounts(
+            ancestors,
+            bank_id,
+            (start_bound, Bound::Unbounded),
+            |collector: &mut Vec<TransactionAccount>, some_account_tuple| {
+                Self::load_while_filtering(collector, some_account_tuple, |account| {
+                    account.owner() == program_id && filter(account)
+                });
+                if collector.len() >= limit {
+                    config.abort();
+                }
+            },
+            &config,
+        )
+    }
+
+    /// Like `load_by_program_with_filter_after()`, but only loads the accounts of the secondary
+    /// index entry of `index_key`
+    pub fn load_by_index_key_with_filter_after<F: Fn(&AccountSharedData) -> bool>(
+        &self,
+        ancestors: &Ancestors,
+        bank_id: BankId,
+        index_key: &IndexKey,
+        filter: F,
+        after: Option<&Pubkey>,
+        limit: usize,
+    ) -> ScanResult<Vec<TransactionAccount>> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+        let start_bound = after.map_or(Bound::Unbounded, |after| Bound::Excluded(*after));
+        // A sorted scan, which is aborted, without error, once the page is full
+        let config = ScanConfig::default().recreate_with_abort();
+        self.accounts_db
+            .index_range_scan_accounts(
+                ancestors,
+                bank_id,
+                *index_key,
+                (start_bound, Bound::Unbounded),
+                |collector: &mut Vec<TransactionAccount>, some_account_tuple| {
+                    Self::load_while_filtering(collector, some_account_tuple, &filter);
+                    if collector.len() >= limit {
+                        config.abort();
+                    }
+                },
+                &config,
+            )
+            .map(|result| result.0)
+    }
+
     fn calc_scan_result_size(account: &AccountSharedData) -> usize {
         account.data().len()
             + std::mem::size_of::<AccountSharedData>()
@@ -1053,18 +1139,21 @@ impl Accounts {
         for k in writable_keys.iter() {
             if account_locks.is_locked_write(k) || account_locks.is_locked_readonly(k) {
                 debug!("Writable account in use: {:?}", k);
+                account_locks.record_conflict(k);
                 return Err(TransactionError::AccountInUse);
             }
         }
         for k in readonly_keys.iter() {
             if account_locks.is_locked_write(k) {
                 debug!("Read-only account in use: {:?}", k);
+                account_locks.record_conflict(k);
                 return Err(TransactionError::AccountInUse);
             }
         }
 
         for k in writable_keys {
             account_locks.write_locks.insert(*k);
+            account_locks.record_write_lock(k);
         }
 
         for k in readonly_keys {
@@ -1114,10 +1203,11 @@ impl Accounts {
     pub fn lock_accounts<'a>(
         &self,
         txs: impl Iterator<Item = &'a SanitizedTransaction>,
-        feature_set: &FeatureSet,
+        tx_account_lock_limit: usize,
     ) -> Vec<Result<()>> {
-        let tx_account_locks_results: Vec<Result<_>> =
-            txs.map(|tx| tx.get_account_locks(feature_set)).collect();
+        let tx_account_locks_results: Vec<Result<_>> = txs
+            .map(|tx| tx.get_account_locks(tx_account_lock_limit))
+            .collect();
         self.lock_accounts_inner(tx_account_locks_results)
     }
 
@@ -1127,12 +1217,12 @@ impl Accounts {
         &self,
         txs: impl Iterator<Item = &'a SanitizedTransaction>,
         results: impl Iterator<Item = &'a Result<()>>,
-        feature_set: &FeatureSet,
+        tx_account_lock_limit: usize,
     ) -> Vec<Result<()>> {
         let tx_account_locks_results: Vec<Result<_>> = txs
             .zip(results)
             .map(|(tx, result)| match result {
-                Ok(()) => tx.get_account_locks(feature_set),
+                Ok(()) => tx.get_account_locks(tx_account_lock_limit),
                 Err(err) => Err(err.clone()),
             })
             .collect();
@@ -1187,6 +1277,37 @@ impl Accounts {
         });
     }
 
+    /// Returns up to `max_accounts` of the accounts write-locked most often, along with how
+    /// often transactions conflicted on them, most write locks first
+    pub fn write_lock_heat_map(
+        &self,
+        max_accounts: usize,
+    ) -> Vec<(Pubkey, AccountWriteLockCounts)> {
+        let mut heat_map: Vec<_> = self
+            .account_locks
+            .lock()
+            .unwrap()
+            .write_lock_counts
+            .iter()
+            .map(|(pubkey, counts)| (*pubkey, *counts))
+            .collect();
+        let hottest_first =
+            |(a_pubkey, a_counts): &(Pubkey, AccountWriteLockCounts),
+             (b_pubkey, b_counts): &(Pubkey, AccountWriteLockCounts)| {
+                b_counts
+                    .write_locks
+                    .cmp(&a_counts.write_locks)
+                    .then(b_counts.conflicts.cmp(&a_counts.conflicts))
+                    .then(a_pubkey.cmp(b_pubkey))
+            };
+        if heat_map.len() > max_accounts {
+            heat_map.select_nth_unstable_by(max_accounts, hottest_first);
+            heat_map.truncate(max_accounts);
+        }
+        heat_map.sort_unstable_by(hottest_first);
+        heat_map
+    }
+
     /// Store the accounts into the DB
     // allow(clippy) needed for various gating flags
     #[allow(clippy::too_many_arguments)]
@@ -2192,6 +2313,128 @@ mod tests {
         assert_eq!(loaded, vec![]);
     }
 
+    #[test]
+    fn test_load_by_program_with_filter_after() {
+        let accounts = Accounts::new_with_config_for_tests(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            AccountShrinkThreshold::default(),
+        );
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+
+        let mut expected = vec![];
+        for lamports in 1..=10 {
+            let pubkey = solana_sdk::pubkey::new_rand();
+            let owner = if lamports % 4 == 0 {
+                &other_program_id
+            } else {
+                &program_id
+            };
+            let account = AccountSharedData::new(lamports, 0, owner);
+            accounts.store_slow_uncached(0, &pubkey, &account);
+            if owner == &program_id && lamports != 3 {
+                expected.push((pubkey, account));
+            }
+        }
+        expected.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        let ancestors = vec![(0, 0)].into_iter().collect();
+        let load_page = |after: Option<&Pubkey>, limit| {
+            accounts
+                .load_by_program_with_filter_after(
+                    &ancestors,
+                    0,
+                    &program_id,
+                    |account| account.lamports() != 3,
+                    after,
+                    limit,
+                )
+                .unwrap()
+        };
+
+        let mut loaded = vec![];
+        let mut after = None;
+        loop {
+            let page = load_page(after.as_ref(), 3);
+            assert!(page.len() <= 3);
+            if page.is_empty() {
+                break;
+            }
+            after = page.last().map(|(pubkey, _)| *pubkey);
+            loaded.extend(page);
+        }
+        assert_eq!(loaded, expected);
+        assert_eq!(load_page(None, expected.len() + 1), expected);
+        assert!(load_page(None, 0).is_empty());
+    }
+
+    #[test]
+    fn test_load_by_index_key_with_filter_after() {
+        let accounts = Accounts::new_with_config_for_tests(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes {
+                keys: None,
+                indexes: vec![crate::accounts_index::AccountIndex::ProgramId]
+                    .into_iter()
+                    .collect(),
+            },
+            false,
+            AccountShrinkThreshold::default(),
+        );
+        let program_id = Pubkey::new_unique();
+        let other_program_id = Pubkey::new_unique();
+
+        let mut expected = vec![];
+        for lamports in 1..=10 {
+            let pubkey = solana_sdk::pubkey::new_rand();
+            let owner = if lamports % 4 == 0 {
+                &other_program_id
+            } else {
+                &program_id
+            };
+            let account = AccountSharedData::new(lamports, 0, owner);
+            accounts.store_slow_uncached(0, &pubkey, &account);
+            if owner == &program_id && lamports != 3 {
+                expected.push((pubkey, account));
+            }
+        }
+        expected.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        let ancestors = vec![(0, 0)].into_iter().collect();
+        let index_key = IndexKey::ProgramId(program_id);
+        let load_page = |after: Option<&Pubkey>, limit| {
+            accounts
+                .load_by_index_key_with_filter_after(
+                    &ancestors,
+                    0,
+                    &index_key,
+                    |account| account.lamports() != 3,
+                    after,
+                    limit,
+                )
+                .unwrap()
+        };
+
+        let mut loaded = vec![];
+        let mut after = None;
+        loop {
+            let page = load_page(after.as_ref(), 3);
+            assert!(page.len() <= 3);
+            if page.is_empty() {
+                break;
+            }
+            after = page.last().map(|(pubkey, _)| *pubkey);
+            loaded.extend(page);
+        }
+        assert_eq!(loaded, expected);
+        assert_eq!(load_page(None, expected.len() + 1), expected);
+        assert!(load_page(None, 0).is_empty());
+    }
+
     #[test]
     fn test_load_accounts_executable_with_write_lock() {
         let mut accounts: Vec<TransactionAccount> = Vec::new();
@@ -2491,7 +2734,7 @@ mod tests {
         };
 
         let tx = new_sanitized_tx(&[&keypair], message, Hash::default());
-        let results = accounts.lock_accounts([tx].iter(), &FeatureSet::all_enabled());
+        let results = accounts.lock_accounts([tx].iter(), MAX_TX_ACCOUNT_LOCKS);
         assert_eq!(results[0], Err(TransactionError::AccountLoadedTwice));
     }
 
@@ -2524,12 +2767,12 @@ mod tests {
             };
 
             let txs = vec![new_sanitized_tx(&[&keypair], message, Hash::default())];
-            let results = accounts.lock_accounts(txs.iter(), &FeatureSet::all_enabled());
+            let results = accounts.lock_accounts(txs.iter(), MAX_TX_ACCOUNT_LOCKS);
             assert_eq!(results[0], Ok(()));
             accounts.unlock_accounts(txs.iter(), &results);
         }
 
-        // Allow over MAX_TX_ACCOUNT_LOCKS before feature activation
+        // Allow over MAX_TX_ACCOUNT_LOCKS with a higher limit
         {
             let num_account_keys = MAX_TX_ACCOUNT_LOCKS + 1;
             let mut account_keys: Vec<_> = (0..num_account_keys)
@@ -2546,12 +2789,12 @@ mod tests {
             };
 
             let txs = vec![new_sanitized_tx(&[&keypair], message, Hash::default())];
-            let results = accounts.lock_accounts(txs.iter(), &FeatureSet::default());
+            let results = accounts.lock_accounts(txs.iter(), usize::MAX);
             assert_eq!(results[0], Ok(()));
             accounts.unlock_accounts(txs.iter(), &results);
         }
 
-        // Disallow over MAX_TX_ACCOUNT_LOCKS after feature activation
+        // Disallow over MAX_TX_ACCOUNT_LOCKS
         {
             let num_account_keys = MAX_TX_ACCOUNT_LOCKS + 1;
             let mut account_keys: Vec<_> = (0..num_account_keys)
@@ -2568,11 +2811,81 @@ mod tests {
             };
 
             let txs = vec![new_sanitized_tx(&[&keypair], message, Hash::default())];
-            let results = accounts.lock_accounts(txs.iter(), &FeatureSet::all_enabled());
+            let results = accounts.lock_accounts(txs.iter(), MAX_TX_ACCOUNT_LOCKS);
             assert_eq!(results[0], Err(TransactionError::TooManyAccountLocks));
         }
     }
 
+    #[test]
+    fn test_write_lock_heat_map() {
+        let keypair0 = Keypair::new();
+        let keypair1 = Keypair::new();
+        let keypair2 = Keypair::new();
+        let accounts = Accounts::new_with_config_for_tests(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            AccountShrinkThreshold::default(),
+        );
+        let new_tx = |writable: &Keypair, readonly: Pubkey| {
+            let message = Message::new_with_compiled_instructions(
+                1,
+                0,
+                2,
+                vec![writable.pubkey(), readonly, native_loader::id()],
+                Hash::default(),
+                vec![CompiledInstruction::new(2, &(), vec![0, 1])],
+            );
+            new_sanitized_tx(&[writable], message, Hash::default())
+        };
+        // writes keypair0, then conflicts on it both as a writer and as a reader
+        let tx0 = new_tx(&keypair0, keypair1.pubkey());
+        let tx1 = new_tx(&keypair0, keypair2.pubkey());
+        let tx2 = new_tx(&keypair2, keypair0.pubkey());
+        let txs = vec![tx0, tx1, tx2];
+        let results = accounts.lock_accounts(txs.iter(), MAX_TX_ACCOUNT_LOCKS);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1], Err(TransactionError::AccountInUse));
+        assert_eq!(results[2], Err(TransactionError::AccountInUse));
+        accounts.unlock_accounts(txs.iter(), &results);
+
+        let results = accounts.lock_accounts(txs[1..].iter(), MAX_TX_ACCOUNT_LOCKS);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1], Err(TransactionError::AccountInUse));
+        accounts.unlock_accounts(txs[1..].iter(), &results);
+
+        assert_eq!(
+            accounts.write_lock_heat_map(1),
+            vec![(
+                keypair0.pubkey(),
+                AccountWriteLockCounts {
+                    write_locks: 2,
+                    conflicts: 2,
+                }
+            )]
+        );
+        assert_eq!(
+            accounts.write_lock_heat_map(10),
+            vec![
+                (
+                    keypair0.pubkey(),
+                    AccountWriteLockCounts {
+                        write_locks: 2,
+                        conflicts: 2,
+                    }
+                ),
+                (
+                    keypair2.pubkey(),
+                    AccountWriteLockCounts {
+                        write_locks: 0,
+                        conflicts: 1,
+                    }
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_accounts_locks() {
         let keypair0 = Keypair::new();
@@ -2607,7 +2920,7 @@ mod tests {
             instructions,
         );
         let tx = new_sanitized_tx(&[&keypair0], message, Hash::default());
-        let results0 = accounts.lock_accounts([tx.clone()].iter(), &FeatureSet::all_enabled());
+        let results0 = accounts.lock_accounts([tx.clone()].iter(), MAX_TX_ACCOUNT_LOCKS);
 
         assert!(results0[0].is_ok());
         assert_eq!(
@@ -2642,7 +2955,7 @@ mod tests {
         );
         let tx1 = new_sanitized_tx(&[&keypair1], message, Hash::default());
         let txs = vec![tx0, tx1];
-        let results1 = accounts.lock_accounts(txs.iter(), &FeatureSet::all_enabled());
+        let results1 = accounts.lock_accounts(txs.iter(), MAX_TX_ACCOUNT_LOCKS);
 
         assert!(results1[0].is_ok()); // Read-only account (keypair1) can be referenced multiple times
         assert!(results1[1].is_err()); // Read-only account (keypair1) cannot also be locked as writable
@@ -2669,7 +2982,7 @@ mod tests {
             instructions,
         );
         let tx = new_sanitized_tx(&[&keypair1], message, Hash::default());
-        let results2 = accounts.lock_accounts([tx].iter(), &FeatureSet::all_enabled());
+        let results2 = accounts.lock_accounts([tx].iter(), MAX_TX_ACCOUNT_LOCKS);
         assert!(results2[0].is_ok()); // Now keypair1 account can be locked as writable
 
         // Check that read-only lock with zero references is deleted
@@ -2740,7 +3053,7 @@ mod tests {
                 let txs = vec![writable_tx.clone()];
                 let results = accounts_clone
                     .clone()
-                    .lock_accounts(txs.iter(), &FeatureSet::all_enabled());
+                    .lock_accounts(txs.iter(), MAX_TX_ACCOUNT_LOCKS);
                 for result in results.iter() {
                     if result.is_ok() {
                         counter_clone.clone().fetch_add(1, Ordering::SeqCst);
@@ -2757,7 +3070,7 @@ mod tests {
             let txs = vec![readonly_tx.clone()];
             let results = accounts_arc
                 .clone()
-                .lock_accounts(txs.iter(), &FeatureSet::all_enabled());
+                .lock_accounts(txs.iter(), MAX_TX_ACCOUNT_LOCKS);
             if results[0].is_ok() {
                 let counter_value = counter_clone.clone().load(Ordering::SeqCst);
                 thread::sleep(time::Duration::from_millis(50));
@@ -2803,7 +3116,7 @@ mod tests {
             instructions,
         );
         let tx = new_sanitized_tx(&[&keypair0], message, Hash::default());
-        let results0 = accounts.lock_accounts([tx].iter(), &FeatureSet::all_enabled());
+        let results0 = accounts.lock_accounts([tx].iter(), MAX_TX_ACCOUNT_LOCKS);
 
         assert!(results0[0].is_ok());
         // Instruction program-id account demoted to readonly
@@ -2897,7 +3210,7 @@ mod tests {
         let results = accounts.lock_accounts_with_results(
             txs.iter(),
             qos_results.iter(),
-            &FeatureSet::all_enabled(),
+            MAX_TX_ACCOUNT_LOCKS,
         );
 
         assert!(results[0].is_ok()); // Read-only account (keypair0) can be referenced multiple times
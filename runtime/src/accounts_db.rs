@@ -28,10 +28,10 @@ use {
             PreviousPass,
         },
         accounts_index::{
-            AccountIndexGetResult, AccountSecondaryIndexes, AccountsIndex, AccountsIndexConfig,
-            AccountsIndexRootsStats, IndexKey, IndexValue, IsCached, RefCount, ScanConfig,
-            ScanResult, SlotList, SlotSlice, ZeroLamport, ACCOUNTS_INDEX_CONFIG_FOR_BENCHMARKS,
-            ACCOUNTS_INDEX_CONFIG_FOR_TESTING,
+            AccountIndex, AccountIndexGetResult, AccountSecondaryIndexes, AccountsIndex,
+            AccountsIndexConfig, AccountsIndexRootsStats, IndexKey, IndexValue, IsCached, RefCount,
+            ScanConfig, ScanResult, SlotList, SlotSlice, ZeroLamport,
+            ACCOUNTS_INDEX_CONFIG_FOR_BENCHMARKS, ACCOUNTS_INDEX_CONFIG_FOR_TESTING,
         },
         accounts_index_storage::Startup,
         accounts_update_notifier_interface::AccountsUpdateNotifier,
@@ -48,6 +48,7 @@ use {
         cache_hash_data::CacheHashData,
         contains::Contains,
         expected_rent_collection::{ExpectedRentCollection, SlotInfoInEpoch},
+        mmap_governor::MmapGovernor,
         pubkey_bins::PubkeyBinCalculator24,
         read_only_accounts_cache::ReadOnlyAccountsCache,
         rent_collector::RentCollector,
@@ -1002,6 +1003,71 @@ struct RemoveUnrootedSlotsSynchronization {
 
 type AccountInfoAccountsIndex = AccountsIndex<AccountInfo>;
 
+/// Progress of the backfill of a secondary index enabled on a running node, which scans the
+/// accounts index one bin at a time
+#[derive(Debug)]
+pub struct AccountIndexBackfill {
+    index: AccountIndex,
+    num_bins: usize,
+    bins_scanned: AtomicUsize,
+    accounts_scanned: AtomicU64,
+    started: Instant,
+    /// Time the backfill took, set once it completes
+    elapsed_us: AtomicU64,
+}
+
+impl AccountIndexBackfill {
+    fn new(index: AccountIndex, num_bins: usize) -> Self {
+        Self {
+            index,
+            num_bins,
+            bins_scanned: AtomicUsize::default(),
+            accounts_scanned: AtomicU64::default(),
+            started: Instant::now(),
+            elapsed_us: AtomicU64::default(),
+        }
+    }
+
+    pub fn index(&self) -> &AccountIndex {
+        &self.index
+    }
+
+    pub fn num_bins(&self) -> usize {
+        self.num_bins
+    }
+
+    pub fn bins_scanned(&self) -> usize {
+        self.bins_scanned.load(Ordering::Acquire)
+    }
+
+    pub fn accounts_scanned(&self) -> u64 {
+        self.accounts_scanned.load(Ordering::Relaxed)
+    }
+
+    /// True once every account stored before the index was enabled has been indexed
+    pub fn is_complete(&self) -> bool {
+        self.bins_scanned() == self.num_bins
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        if self.is_complete() {
+            Duration::from_micros(self.elapsed_us.load(Ordering::Relaxed))
+        } else {
+            self.started.elapsed()
+        }
+    }
+
+    fn finish_bin(&self, num_accounts: usize) {
+        self.accounts_scanned
+            .fetch_add(num_accounts as u64, Ordering::Relaxed);
+        if self.bins_scanned.load(Ordering::Relaxed) + 1 == self.num_bins {
+            self.elapsed_us
+                .store(self.started.elapsed().as_micros() as u64, Ordering::Relaxed);
+        }
+        self.bins_scanned.fetch_add(1, Ordering::Release);
+    }
+}
+
 // This structure handles the load/store of the accounts
 #[derive(Debug)]
 pub struct AccountsDb {
@@ -1037,6 +1103,9 @@ pub struct AccountsDb {
     /// Set of shrinkable stores organized by map of slot to append_vec_id
     pub shrink_candidate_slots: Mutex<ShrinkCandidates>,
 
+    /// Frees the maps of storages when they get close to the map limit of the process
+    pub(crate) mmap_governor: MmapGovernor,
+
     /// Legacy shrink slots to support non-cached code-path.
     pub shrink_candidate_slots_v1: Mutex<Vec<Slot>>,
 
@@ -1085,6 +1154,14 @@ pub struct AccountsDb {
 
     pub account_indexes: AccountSecondaryIndexes,
 
+    /// `account_indexes` plus the secondary indexes enabled since startup, if any. Stores hold
+    /// the read lock while they update the indexes, so every store that begins after an index
+    /// is enabled keeps it current.
+    runtime_account_indexes: RwLock<Option<AccountSecondaryIndexes>>,
+
+    /// Backfills of the secondary indexes enabled since startup
+    account_index_backfills: RwLock<HashMap<AccountIndex, Arc<AccountIndexBackfill>>>,
+
     pub caching_enabled: bool,
 
     /// Set of unique keys per slot which is used
@@ -1640,6 +1717,7 @@ impl AccountsDb {
             next_id: AtomicAppendVecId::new(0),
             shrink_candidate_slots_v1: Mutex::new(Vec::new()),
             shrink_candidate_slots: Mutex::new(HashMap::new()),
+            mmap_governor: MmapGovernor::default(),
             write_cache_limit_bytes: None,
             write_version: AtomicU64::new(0),
             paths: vec![],
@@ -1662,6 +1740,8 @@ impl AccountsDb {
             stats: AccountsStats::default(),
             cluster_type: None,
             account_indexes: AccountSecondaryIndexes::default(),
+            runtime_account_indexes: RwLock::default(),
+            account_index_backfills: RwLock::default(),
             caching_enabled: false,
             #[cfg(test)]
             load_delay: u64::default(),
@@ -2003,8 +2083,13 @@ impl AccountsDb {
             }
         }
 
-        self.accounts_index
-            .handle_dead_keys(&dead_keys, &self.account_indexes);
+        let runtime_account_indexes = self.runtime_account_indexes.read().unwrap();
+        self.accounts_index.handle_dead_keys(
+            &dead_keys,
+            runtime_account_indexes
+                .as_ref()
+                .unwrap_or(&self.account_indexes),
+        );
         reclaims
     }
 
@@ -3433,7 +3518,68 @@ impl AccountsDb {
         }
     }
 
+    /// While the governor is under pressure, combines the stores of the rooted slots holding
+    /// the most of them, then drops the recycled stores, which are all mapped. Returns the
+    /// number of stores dropped.
+    fn relieve_mmap_pressure(&self) -> usize {
+        if !self.mmap_governor.is_under_pressure() {
+            return 0;
+        }
+        let mut measure = Measure::start("relieve_mmap_pressure");
+        let mut slots: Vec<(Slot, usize)> = self
+            .storage
+            .map
+            .iter()
+            .map(|slot_stores| {
+                (
+                    *slot_stores.key(),
+                    slot_stores.value().read().unwrap().len(),
+                )
+            })
+            .filter(|(slot, num_stores)| {
+                *num_stores > 1 && self.accounts_index.is_alive_root(*slot)
+            })
+            .collect();
+        slots.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+
+        let mut num_combined_stores = 0;
+        for (slot, num_stores) in slots {
+            if !self.mmap_governor.is_under_pressure() {
+                break;
+            }
+            self.shrink_slot_forced(slot);
+            let num_stores_after = self
+                .storage
+                .get_slot_stores(slot)
+                .map(|slot_stores| slot_stores.read().unwrap().len())
+                .unwrap_or_default();
+            num_combined_stores += num_stores.saturating_sub(num_stores_after);
+        }
+        self.shrink_ancient_slots();
+
+        let recycle_stores = std::mem::take(&mut *self.recycle_stores.write().unwrap());
+        let num_recycled_stores = recycle_stores.entry_count();
+        drop(recycle_stores);
+        measure.stop();
+
+        warn!(
+            "accounts_db under mmap pressure, map headroom: {:?}, combined {} stores, \
+             dropped {} recycled stores",
+            self.mmap_governor.map_headroom(),
+            num_combined_stores,
+            num_recycled_stores,
+        );
+        datapoint_warn!(
+            "accounts_db-mmap_pressure",
+            ("num_combined_stores", num_combined_stores, i64),
+            ("num_recycled_stores", num_recycled_stores, i64),
+            ("elapsed_us", measure.as_us(), i64),
+        );
+        num_combined_stores + num_recycled_stores
+    }
+
     pub fn shrink_candidate_slots(&self) -> usize {
+        self.relieve_mmap_pressure();
         let shrink_candidates_slots =
             std::mem::take(&mut *self.shrink_candidate_slots.lock().unwrap());
         if !shrink_candidates_slots.is_empty() {
@@ -3556,6 +3702,39 @@ impl AccountsDb {
         Ok(collector)
     }
 
+    /// Like `scan_accounts()`, but only over the pubkeys in `range`. Unless
+    /// `config.collect_all_unsorted`, accounts are scanned in pubkey order.
+    pub fn checked_range_scan_accounts<F, A, R>(
+        &self,
+        ancestors: &Ancestors,
+        bank_id: BankId,
+        range: R,
+        scan_func: F,
+        config: &ScanConfig,
+    ) -> ScanResult<A>
+    where
+        F: Fn(&mut A, Option<(&Pubkey, AccountSharedData, Slot)>),
+        A: Default,
+        R: RangeBounds<Pubkey> + std::fmt::Debug,
+    {
+        let mut collector = A::default();
+        self.accounts_index.checked_range_scan_accounts(
+            ancestors,
+            bank_id,
+            range,
+            |pubkey, (account_info, slot)| {
+                let account_slot = self
+                    .get_account_accessor(slot, pubkey, &account_info.storage_location())
+                    .get_loaded_account()
+                    .map(|loaded_account| (pubkey, loaded_account.take_account(), slot));
+                scan_func(&mut collector, account_slot)
+            },
+            config,
+        )?;
+
+        Ok(collector)
+    }
+
     pub fn unchecked_scan_accounts<F, A>(
         &self,
         metric_name: &'static str,
@@ -3624,6 +3803,106 @@ impl AccountsDb {
         collector
     }
 
+    /// Enables secondary index `index` on a running node. Stores keep it current from now on,
+    /// while a background thread backfills it with the accounts stored before. Scans must not
+    /// rely on the index until the backfill completes. Returns `None` if `index` is already
+    /// enabled.
+    pub fn enable_account_index(
+        self: &Arc<Self>,
+        index: AccountIndex,
+    ) -> Option<Arc<AccountIndexBackfill>> {
+        if self.account_indexes.contains(&index) {
+            return None;
+        }
+        {
+            let mut runtime_account_indexes = self.runtime_account_indexes.write().unwrap();
+            if !runtime_account_indexes
+                .get_or_insert_with(|| self.account_indexes.clone())
+                .indexes
+                .insert(index.clone())
+            {
+                return None;
+            }
+        }
+        // Stores that read the indexes before the write lock was taken have completed, so
+        // every account they stored is in the accounts index for the backfill to find
+        let backfill = Arc::new(AccountIndexBackfill::new(
+            index.clone(),
+            self.accounts_index.bins(),
+        ));
+        self.account_index_backfills
+            .write()
+            .unwrap()
+            .insert(index, backfill.clone());
+        let accounts_db = self.clone();
+        let thread_backfill = backfill.clone();
+        Builder::new()
+            .name("solana-account-index-backfill".to_string())
+            .spawn(move || accounts_db.backfill_account_index(&thread_backfill))
+            .unwrap();
+        Some(backfill)
+    }
+
+    fn backfill_account_index(&self, backfill: &AccountIndexBackfill) {
+        info!("backfilling secondary index: {:?}", backfill.index());
+        let account_indexes = AccountSecondaryIndexes {
+            keys: self.account_indexes.keys.clone(),
+            indexes: HashSet::from([backfill.index().clone()]),
+        };
+        for bin in 0..backfill.num_bins() {
+            let items = self.accounts_index.account_maps[bin]
+                .read()
+                .unwrap()
+                .items(&(..));
+            for (pubkey, account_map_entry) in &items {
+                // Every version may still be visible to some bank, and scans filter out the
+                // versions their ancestors don't include
+                let slot_list = account_map_entry.slot_list.read().unwrap().clone();
+                for (slot, account_info) in slot_list {
+                    let mut accessor =
+                        self.get_account_accessor(slot, pubkey, &account_info.storage_location());
+                    if let Some(loaded_account) = accessor.get_loaded_account() {
+                        self.accounts_index.update_secondary_indexes(
+                            pubkey,
+                            &loaded_account,
+                            &account_indexes,
+                        );
+                    }
+                }
+            }
+            backfill.finish_bin(items.len());
+        }
+        info!(
+            "backfilled secondary index: {:?}, accounts: {}, elapsed: {:?}",
+            backfill.index(),
+            backfill.accounts_scanned(),
+            backfill.elapsed()
+        );
+    }
+
+    /// Whether scans can rely on secondary index `index`, which requires it to be enabled at
+    /// startup or to have completed its backfill since
+    pub fn account_index_ready(&self, index: &AccountIndex) -> bool {
+        self.account_indexes.contains(index)
+            || self
+                .account_index_backfills
+                .read()
+                .unwrap()
+                .get(index)
+                .map(|backfill| backfill.is_complete())
+                .unwrap_or_default()
+    }
+
+    /// Backfills of the secondary indexes enabled since startup
+    pub fn account_index_backfills(&self) -> Vec<Arc<AccountIndexBackfill>> {
+        self.account_index_backfills
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
     pub fn index_scan_accounts<F, A>(
         &self,
         ancestors: &Ancestors,
@@ -3666,6 +3945,53 @@ impl AccountsDb {
         Ok((collector, used_index))
     }
 
+    /// Like `index_scan_accounts()`, but only scans the accounts of `range`, in pubkey order
+    pub fn index_range_scan_accounts<F, A, R>(
+        &self,
+        ancestors: &Ancestors,
+        bank_id: BankId,
+        index_key: IndexKey,
+        range: R,
+        scan_func: F,
+        config: &ScanConfig,
+    ) -> ScanResult<(A, bool)>
+    where
+        F: Fn(&mut A, Option<(&Pubkey, AccountSharedData, Slot)>),
+        A: Default,
+        R: RangeBounds<Pubkey> + std::fmt::Debug,
+    {
+        let key = match &index_key {
+            IndexKey::ProgramId(key) => key,
+            IndexKey::SplTokenMint(key) => key,
+            IndexKey::SplTokenOwner(key) => key,
+        };
+        if !self.account_indexes.include_key(key) {
+            // the requested key was not indexed in the secondary index, so do a normal range scan
+            let used_index = false;
+            let scan_result =
+                self.checked_range_scan_accounts(ancestors, bank_id, range, scan_func, config)?;
+            return Ok((scan_result, used_index));
+        }
+
+        let mut collector = A::default();
+        self.accounts_index.index_range_scan_accounts(
+            ancestors,
+            bank_id,
+            index_key,
+            range,
+            |pubkey, (account_info, slot)| {
+                let account_slot = self
+                    .get_account_accessor(slot, pubkey, &account_info.storage_location())
+                    .get_loaded_account()
+                    .map(|loaded_account| (pubkey, loaded_account.take_account(), slot));
+                scan_func(&mut collector, account_slot)
+            },
+            config,
+        )?;
+        let used_index = true;
+        Ok((collector, used_index))
+    }
+
     /// Scan a specific slot through all the account storage in parallel
     pub fn scan_account_storage<R, B>(
         &self,
@@ -5479,6 +5805,7 @@ impl AccountsDb {
             ("total_alive_bytes", total_alive_bytes, i64),
             ("total_alive_ratio", total_alive_ratio, f64),
         );
+        self.mmap_governor.report();
         datapoint_info!(
             "accounts_db-perf-stats",
             (
@@ -6608,6 +6935,10 @@ impl AccountsDb {
         // so, instead we limit how many threads will be created to the same size as the bg thread pool
         let len = std::cmp::min(accounts.len(), infos.len());
         let chunk_size = std::cmp::max(1, len / quarter_thread_count()); // # pubkeys/thread
+        let runtime_account_indexes = self.runtime_account_indexes.read().unwrap();
+        let account_indexes = runtime_account_indexes
+            .as_ref()
+            .unwrap_or(&self.account_indexes);
         let batches = 1 + len / chunk_size;
         (0..batches)
             .into_par_iter()
@@ -6625,7 +6956,7 @@ impl AccountsDb {
                         old_slot,
                         pubkey,
                         pubkey_account.1,
-                        &self.account_indexes,
+                        account_indexes,
                         info,
                         &mut reclaims,
                         previous_slot_entry_was_cached,
@@ -8201,6 +8532,7 @@ pub mod tests {
             accounts_index::{tests::*, AccountSecondaryIndexesIncludeExclude, RefCount},
             append_vec::{test_utils::TempFile, AccountMeta},
             inline_spl_token,
+            mmap_governor::{MmapLimits, DEFAULT_MMAP_PRESSURE_RATIO},
         },
         assert_matches::assert_matches,
         rand::{prelude::SliceRandom, thread_rng, Rng},
@@ -13434,6 +13766,34 @@ pub mod tests {
         assert!(!slot_stores(&db, 1).is_empty());
     }
 
+    #[test]
+    fn test_relieve_mmap_pressure() {
+        let mut db = AccountsDb::new_single_for_tests();
+        let store_dir = TempDir::new().unwrap();
+        db.recycle_stores
+            .write()
+            .unwrap()
+            .add_entry(Arc::new(AccountStorageEntry::new(
+                store_dir.path(),
+                0,
+                1,
+                1000,
+            )));
+
+        // The map limit is unknown
+        db.mmap_governor = MmapGovernor::new(MmapLimits::default(), DEFAULT_MMAP_PRESSURE_RATIO);
+        assert_eq!(db.relieve_mmap_pressure(), 0);
+        assert_eq!(db.recycle_stores.read().unwrap().entry_count(), 1);
+
+        let limits = MmapLimits {
+            max_map_count: Some(0),
+            max_open_files: None,
+        };
+        db.mmap_governor = MmapGovernor::new(limits, DEFAULT_MMAP_PRESSURE_RATIO);
+        assert_eq!(db.relieve_mmap_pressure(), 1);
+        assert_eq!(db.recycle_stores.read().unwrap().entry_count(), 0);
+    }
+
     #[test]
     fn test_recycle_stores_expiration() {
         solana_logger::setup();
@@ -134,7 +134,8 @@ pub enum ScanError {
 
 enum ScanTypes<R: RangeBounds<Pubkey>> {
     Unindexed(Option<R>),
-    Indexed(IndexKey),
+    /// The pubkeys of a secondary index entry, in pubkey order if a range is given
+    Indexed(IndexKey, Option<R>),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -891,32 +892,35 @@ impl<T: IndexValue> AccountsIndex<T> {
                 // Pass "" not to log metrics, so RPC doesn't get spammy
                 self.do_scan_accounts(metric_name, ancestors, func, range, Some(max_root), config);
             }
-            ScanTypes::Indexed(IndexKey::ProgramId(program_id)) => {
+            ScanTypes::Indexed(IndexKey::ProgramId(program_id), range) => {
                 self.do_scan_secondary_index(
                     ancestors,
                     func,
                     &self.program_id_index,
                     &program_id,
+                    range,
                     Some(max_root),
                     config,
                 );
             }
-            ScanTypes::Indexed(IndexKey::SplTokenMint(mint_key)) => {
+            ScanTypes::Indexed(IndexKey::SplTokenMint(mint_key), range) => {
                 self.do_scan_secondary_index(
                     ancestors,
                     func,
                     &self.spl_token_mint_index,
                     &mint_key,
+                    range,
                     Some(max_root),
                     config,
                 );
             }
-            ScanTypes::Indexed(IndexKey::SplTokenOwner(owner_key)) => {
+            ScanTypes::Indexed(IndexKey::SplTokenOwner(owner_key), range) => {
                 self.do_scan_secondary_index(
                     ancestors,
                     func,
                     &self.spl_token_owner_index,
                     &owner_key,
+                    range,
                     Some(max_root),
                     config,
                 );
@@ -1029,6 +1033,7 @@ impl<T: IndexValue> AccountsIndex<T> {
 
     fn do_scan_secondary_index<
         F,
+        R,
         SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send,
     >(
         &self,
@@ -1036,12 +1041,21 @@ impl<T: IndexValue> AccountsIndex<T> {
         mut func: F,
         index: &SecondaryIndex<SecondaryIndexEntryType>,
         index_key: &Pubkey,
+        range: Option<R>,
         max_root: Option<Slot>,
         config: &ScanConfig,
     ) where
         F: FnMut(&Pubkey, (&T, Slot)),
+        R: RangeBounds<Pubkey>,
     {
-        for pubkey in index.get(index_key) {
+        let mut pubkeys = index.get(index_key);
+        if let Some(range) = range {
+            // The index entries are unordered, so sort them for scans to resume where a previous
+            // scan stopped
+            pubkeys.retain(|pubkey| range.contains(pubkey));
+            pubkeys.sort_unstable();
+        }
+        for pubkey in pubkeys {
             // Maybe these reads from the AccountsIndex can be batched every time it
             // grabs the read lock as well...
             if let AccountIndexGetResult::Found(list_r, index) =
@@ -1121,6 +1135,29 @@ impl<T: IndexValue> AccountsIndex<T> {
         )
     }
 
+    /// call func with every pubkey in `range` and index visible from a given set of ancestors
+    pub(crate) fn checked_range_scan_accounts<F, R>(
+        &self,
+        ancestors: &Ancestors,
+        scan_bank_id: BankId,
+        range: R,
+        func: F,
+        config: &ScanConfig,
+    ) -> Result<(), ScanError>
+    where
+        F: FnMut(&Pubkey, (&T, Slot)),
+        R: RangeBounds<Pubkey> + std::fmt::Debug,
+    {
+        self.do_checked_scan_accounts(
+            "",
+            ancestors,
+            scan_bank_id,
+            func,
+            ScanTypes::Unindexed(Some(range)),
+            config,
+        )
+    }
+
     pub(crate) fn unchecked_scan_accounts<F>(
         &self,
         metric_name: &'static str,
@@ -1173,7 +1210,33 @@ impl<T: IndexValue> AccountsIndex<T> {
             ancestors,
             scan_bank_id,
             func,
-            ScanTypes::<Range<Pubkey>>::Indexed(index_key),
+            ScanTypes::<Range<Pubkey>>::Indexed(index_key, None),
+            config,
+        )
+    }
+
+    /// call func with every pubkey of `range` in the secondary index entry of `index_key` and
+    /// its index visible from a given set of ancestors, in pubkey order
+    pub(crate) fn index_range_scan_accounts<F, R>(
+        &self,
+        ancestors: &Ancestors,
+        scan_bank_id: BankId,
+        index_key: IndexKey,
+        range: R,
+        func: F,
+        config: &ScanConfig,
+    ) -> Result<(), ScanError>
+    where
+        F: FnMut(&Pubkey, (&T, Slot)),
+        R: RangeBounds<Pubkey> + std::fmt::Debug,
+    {
+        // Pass "" not to log metrics, so RPC doesn't get spammy
+        self.do_checked_scan_accounts(
+            "",
+            ancestors,
+            scan_bank_id,
+            func,
+            ScanTypes::Indexed(index_key, Some(range)),
             config,
         )
     }
@@ -5,6 +5,7 @@
 //! <https://docs.solana.com/implemented-proposals/persistent-account-storage>
 
 use {
+    crate::mmap_governor,
     log::*,
     memmap2::MmapMut,
     serde::{Deserialize, Serialize},
@@ -169,7 +170,7 @@ impl<'a> Iterator for AppendVecAccountsIter<'a> {
 /// are serialized such that only one thread updates the internal `append_lock` at a time. No
 /// restrictions are placed on reading. That is, one may read items from one thread while another
 /// is appending new items.
-#[derive(Debug, AbiExample)]
+#[derive(Debug)]
 pub struct AppendVec {
     /// The file path where the data is stored.
     path: PathBuf,
@@ -190,8 +191,17 @@ pub struct AppendVec {
     remove_on_drop: bool,
 }
 
+#[cfg(RUSTC_WITH_SPECIALIZATION)]
+impl solana_frozen_abi::abi_example::AbiExample for AppendVec {
+    fn example() -> Self {
+        // The map has to be counted by the mmap governor, which uncounts it on drop
+        AppendVec::new_empty_map(0)
+    }
+}
+
 impl Drop for AppendVec {
     fn drop(&mut self) {
+        mmap_governor::append_vec_unmapped();
         if self.remove_on_drop {
             if let Err(_e) = remove_file(&self.path) {
                 // promote this to panic soon.
@@ -212,6 +222,7 @@ impl AppendVec {
             let _ignored = remove_file(file);
         }
 
+        let _file_handle = mmap_governor::append_vec_file_handle();
         let mut data = OpenOptions::new()
             .read(true)
             .write(true)
@@ -247,6 +258,7 @@ impl AppendVec {
             std::process::exit(1);
         });
 
+        mmap_governor::append_vec_mapped();
         AppendVec {
             path: file.to_path_buf(),
             map,
@@ -273,6 +285,7 @@ impl AppendVec {
             std::process::exit(1);
         });
 
+        mmap_governor::append_vec_mapped();
         AppendVec {
             path: PathBuf::from(String::default()),
             map,
@@ -340,6 +353,7 @@ impl AppendVec {
     }
 
     pub fn new_from_file<P: AsRef<Path>>(path: P, current_len: usize) -> io::Result<(Self, usize)> {
+        let _file_handle = mmap_governor::append_vec_file_handle();
         let data = OpenOptions::new()
             .read(true)
             .write(true)
@@ -358,6 +372,7 @@ impl AppendVec {
             result?
         };
 
+        mmap_governor::append_vec_mapped();
         let new = AppendVec {
             path: path.as_ref().to_path_buf(),
             map,
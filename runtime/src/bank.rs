@@ -39,14 +39,14 @@ use {
     crate::{
         account_overrides::AccountOverrides,
         accounts::{
-            AccountAddressFilter, Accounts, LoadedTransaction, PubkeyAccountSlot,
-            TransactionLoadResult,
+            AccountAddressFilter, AccountWriteLockCounts, Accounts, LoadedTransaction,
+            PubkeyAccountSlot, TransactionLoadResult,
         },
         accounts_db::{
-            AccountShrinkThreshold, AccountsDbConfig, SnapshotStorages,
+            AccountIndexBackfill, AccountShrinkThreshold, AccountsDbConfig, SnapshotStorages,
             ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS, ACCOUNTS_DB_CONFIG_FOR_TESTING,
         },
-        accounts_index::{AccountSecondaryIndexes, IndexKey, ScanConfig, ScanResult},
+        accounts_index::{AccountIndex, AccountSecondaryIndexes, IndexKey, ScanConfig, ScanResult},
         accounts_update_notifier_interface::AccountsUpdateNotifier,
         ancestors::{Ancestors, AncestorsForSerialization},
         blockhash_queue::BlockhashQueue,
@@ -55,6 +55,7 @@ use {
         epoch_stakes::{EpochStakes, NodeVoteAccounts},
         expected_rent_collection::{ExpectedRentCollection, SlotInfoInEpoch},
         inline_spl_associated_token_account, inline_spl_token,
+        landed_prioritization_fees::LandedPrioritizationFees,
         message_processor::MessageProcessor,
         rent_collector::{CollectedInfo, RentCollector},
         stake_account::{self, StakeAccount},
@@ -137,7 +138,7 @@ use {
         timing::years_as_slots,
         transaction::{
             MessageHash, Result, SanitizedTransaction, Transaction, TransactionError,
-            TransactionVerificationMode, VersionedTransaction,
+            TransactionVerificationMode, VersionedTransaction, MAX_TX_ACCOUNT_LOCKS,
         },
         transaction_context::{
             ExecutionRecord, InstructionTrace, TransactionAccount, TransactionContext,
@@ -151,7 +152,7 @@ use {
     std::{
         borrow::Cow,
         cell::RefCell,
-        collections::{HashMap, HashSet},
+        collections::{BTreeSet, HashMap, HashSet},
         convert::{TryFrom, TryInto},
         fmt, mem,
         ops::{Deref, Div, RangeInclusive},
@@ -188,6 +189,9 @@ pub const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
 
 pub const MAX_LEADER_SCHEDULE_STAKES: Epoch = 5;
 
+/// Number of the most write-locked accounts reported for each frozen bank
+pub const WRITE_LOCK_HEAT_MAP_REPORT_SIZE: usize = 10;
+
 pub type Rewrites = RwLock<HashMap<Pubkey, Hash>>;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -833,6 +837,23 @@ impl TransactionLogCollector {
             }),
         }
     }
+
+    /// Returns the logs of the transactions that mention any of `addresses`, in the order the
+    /// transactions were processed, or None if no address is mentioned
+    pub fn get_logs_for_addresses(&self, addresses: &[Pubkey]) -> Option<Vec<TransactionLogInfo>> {
+        let mut mentioned_log_indices = addresses
+            .iter()
+            .filter_map(|address| self.mentioned_address_map.get(address))
+            .peekable();
+        mentioned_log_indices.peek()?;
+        let log_indices: BTreeSet<usize> = mentioned_log_indices.flatten().copied().collect();
+        Some(
+            log_indices
+                .into_iter()
+                .filter_map(|i| self.logs.get(i).cloned())
+                .collect(),
+        )
+    }
 }
 
 pub trait NonceInfo {
@@ -1060,6 +1081,7 @@ impl PartialEq for Bank {
             // TODO: Confirm if all these fields are intentionally ignored!
             builtin_programs: _,
             compute_budget: _,
+            transaction_account_lock_limit: _,
             builtin_feature_transitions: _,
             rewards: _,
             cluster_type: _,
@@ -1074,6 +1096,7 @@ impl PartialEq for Bank {
             freeze_started: _,
             vote_only_bank: _,
             cost_tracker: _,
+            landed_prioritization_fees: _,
             rewrites_skipped_this_slot: _,
             sysvar_cache: _,
             accounts_data_size_initial: _,
@@ -1303,6 +1326,10 @@ pub struct Bank {
 
     compute_budget: Option<ComputeBudget>,
 
+    /// Maximum number of accounts a transaction may lock, overriding the
+    /// feature-gated default when set
+    transaction_account_lock_limit: Option<usize>,
+
     /// Dynamic feature transitions for builtin programs
     #[allow(clippy::rc_buffer)]
     builtin_feature_transitions: Arc<Vec<BuiltinFeatureTransition>>,
@@ -1342,6 +1369,9 @@ pub struct Bank {
 
     cost_tracker: RwLock<CostTracker>,
 
+    /// Compute unit prices of the transactions committed to this bank
+    landed_prioritization_fees: RwLock<LandedPrioritizationFees>,
+
     sysvar_cache: RwLock<SysvarCache>,
 
     /// (Pubkey, account Hash) for each account that would have been rewritten in rent collection for this slot
@@ -1500,6 +1530,7 @@ impl Bank {
             is_delta: AtomicBool::default(),
             builtin_programs: BuiltinPrograms::default(),
             compute_budget: Option::<ComputeBudget>::default(),
+            transaction_account_lock_limit: Option::<usize>::default(),
             builtin_feature_transitions: Arc::<Vec<BuiltinFeatureTransition>>::default(),
             rewards: RwLock::<Vec<(Pubkey, RewardInfo)>>::default(),
             cluster_type: Option::<ClusterType>::default(),
@@ -1515,6 +1546,7 @@ impl Bank {
             freeze_started: AtomicBool::default(),
             vote_only_bank: false,
             cost_tracker: RwLock::<CostTracker>::default(),
+            landed_prioritization_fees: RwLock::<LandedPrioritizationFees>::default(),
             sysvar_cache: RwLock::<SysvarCache>::default(),
             accounts_data_size_initial: 0,
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
@@ -1832,6 +1864,7 @@ impl Bank {
             signature_count: AtomicU64::new(0),
             builtin_programs,
             compute_budget: parent.compute_budget,
+            transaction_account_lock_limit: parent.transaction_account_lock_limit,
             builtin_feature_transitions: parent.builtin_feature_transitions.clone(),
             hard_forks: parent.hard_forks.clone(),
             rewards: RwLock::new(vec![]),
@@ -1858,6 +1891,7 @@ impl Bank {
                     .is_active(&feature_set::cap_accounts_data_len::id())
                     .then(|| MAX_ACCOUNTS_DATA_LEN.saturating_sub(accounts_data_size_initial)),
             )),
+            landed_prioritization_fees: RwLock::<LandedPrioritizationFees>::default(),
             sysvar_cache: RwLock::new(SysvarCache::default()),
             accounts_data_size_initial,
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
@@ -2188,6 +2222,7 @@ impl Bank {
             is_delta: AtomicBool::new(fields.is_delta),
             builtin_programs: new(),
             compute_budget: None,
+            transaction_account_lock_limit: None,
             builtin_feature_transitions: new(),
             rewards: new(),
             cluster_type: Some(genesis_config.cluster_type),
@@ -2209,6 +2244,7 @@ impl Bank {
                     .is_active(&feature_set::cap_accounts_data_len::id())
                     .then(|| MAX_ACCOUNTS_DATA_LEN.saturating_sub(accounts_data_size_initial)),
             )),
+            landed_prioritization_fees: RwLock::<LandedPrioritizationFees>::default(),
             sysvar_cache: RwLock::new(SysvarCache::default()),
             accounts_data_size_initial,
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
@@ -3834,7 +3870,7 @@ impl Bank {
         let lock_results = self
             .rc
             .accounts
-            .lock_accounts(sanitized_txs.iter(), &FeatureSet::all_enabled());
+            .lock_accounts(sanitized_txs.iter(), MAX_TX_ACCOUNT_LOCKS);
         TransactionBatch::new(lock_results, self, Cow::Owned(sanitized_txs))
     }
 
@@ -3857,7 +3893,7 @@ impl Bank {
         let lock_results = self
             .rc
             .accounts
-            .lock_accounts(sanitized_txs.iter(), &FeatureSet::all_enabled());
+            .lock_accounts(sanitized_txs.iter(), MAX_TX_ACCOUNT_LOCKS);
         Ok(TransactionBatch::new(
             lock_results,
             self,
@@ -3870,10 +3906,11 @@ impl Bank {
         &'a self,
         txs: &'b [SanitizedTransaction],
     ) -> TransactionBatch<'a, 'b> {
+        let tx_account_lock_limit = self.get_transaction_account_lock_limit();
         let lock_results = self
             .rc
             .accounts
-            .lock_accounts(txs.iter(), &self.feature_set);
+            .lock_accounts(txs.iter(), tx_account_lock_limit);
         TransactionBatch::new(lock_results, self, Cow::Borrowed(txs))
     }
 
@@ -3885,10 +3922,11 @@ impl Bank {
         transaction_results: impl Iterator<Item = &'b Result<()>>,
     ) -> TransactionBatch<'a, 'b> {
         // this lock_results could be: Ok, AccountInUse, WouldExceedBlockMaxLimit or WouldExceedAccountMaxLimit
+        let tx_account_lock_limit = self.get_transaction_account_lock_limit();
         let lock_results = self.rc.accounts.lock_accounts_with_results(
             transactions.iter(),
             transaction_results,
-            &self.feature_set,
+            tx_account_lock_limit,
         );
         TransactionBatch::new(lock_results, self, Cow::Borrowed(transactions))
     }
@@ -3898,7 +3936,10 @@ impl Bank {
         &'a self,
         transaction: SanitizedTransaction,
     ) -> TransactionBatch<'a, '_> {
-        let lock_result = transaction.get_account_locks(&self.feature_set).map(|_| ());
+        let tx_account_lock_limit = self.get_transaction_account_lock_limit();
+        let lock_result = transaction
+            .get_account_locks(tx_account_lock_limit)
+            .map(|_| ());
         let mut batch =
             TransactionBatch::new(vec![lock_result], self, Cow::Owned(vec![transaction]));
         batch.set_needs_unlock(false);
@@ -3915,15 +3956,62 @@ impl Bank {
         self.simulate_transaction_unchecked(transaction)
     }
 
+    /// Run an ordered list of transactions against a frozen bank without committing the results.
+    /// Each transaction observes the account writes of the successful transactions before it.
+    pub fn simulate_transactions(
+        &self,
+        transactions: Vec<SanitizedTransaction>,
+    ) -> Vec<TransactionSimulationResult> {
+        assert!(self.is_frozen(), "simulation bank must be frozen");
+
+        let mut account_overrides = AccountOverrides::default();
+        transactions
+            .into_iter()
+            .map(|transaction| {
+                let writable_accounts = transaction
+                    .message()
+                    .account_keys()
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| transaction.message().is_writable(*index))
+                    .map(|(_, pubkey)| *pubkey)
+                    .collect::<HashSet<_>>();
+                let simulation_result = self.simulate_transaction_with_account_overrides(
+                    transaction,
+                    &mut account_overrides,
+                );
+                if simulation_result.result.is_ok() {
+                    for (pubkey, account) in &simulation_result.post_simulation_accounts {
+                        if writable_accounts.contains(pubkey) {
+                            account_overrides.set_account(pubkey, account.clone());
+                        }
+                    }
+                }
+                simulation_result
+            })
+            .collect()
+    }
+
     /// Run transactions against a bank without committing the results; does not check if the bank
     /// is frozen, enabling use in single-Bank test frameworks
     pub fn simulate_transaction_unchecked(
         &self,
         transaction: SanitizedTransaction,
+    ) -> TransactionSimulationResult {
+        self.simulate_transaction_with_account_overrides(
+            transaction,
+            &mut AccountOverrides::default(),
+        )
+    }
+
+    fn simulate_transaction_with_account_overrides(
+        &self,
+        transaction: SanitizedTransaction,
+        account_overrides: &mut AccountOverrides,
     ) -> TransactionSimulationResult {
         let account_keys = transaction.message().account_keys();
         let number_of_accounts = account_keys.len();
-        let account_overrides = self.get_account_overrides_for_simulation(&account_keys);
+        self.set_account_overrides_for_simulation(&account_keys, account_overrides);
         let batch = self.prepare_simulation_batch(transaction);
         let mut timings = ExecuteTimings::default();
 
@@ -3941,7 +4029,7 @@ impl Bank {
             true,
             true,
             &mut timings,
-            Some(&account_overrides),
+            Some(account_overrides),
         );
 
         let post_simulation_accounts = loaded_transactions
@@ -3988,8 +4076,11 @@ impl Bank {
         }
     }
 
-    fn get_account_overrides_for_simulation(&self, account_keys: &AccountKeys) -> AccountOverrides {
-        let mut account_overrides = AccountOverrides::default();
+    fn set_account_overrides_for_simulation(
+        &self,
+        account_keys: &AccountKeys,
+        account_overrides: &mut AccountOverrides,
+    ) {
         let slot_history_id = sysvar::slot_history::id();
         if account_keys.iter().any(|pubkey| *pubkey == slot_history_id) {
             let current_account = self.get_account_with_fixed_root(&slot_history_id);
@@ -4006,7 +4097,6 @@ impl Bank {
                 }
             }
         }
-        account_overrides
     }
 
     pub fn unlock_accounts(&self, batch: &mut TransactionBatch) {
@@ -4087,6 +4177,23 @@ impl Bank {
             .collect()
     }
 
+    /// Returns whether each `(message_hash, recent_blockhash)` pair has already been processed
+    /// on this bank's fork. The status cache lock is taken once for the whole batch.
+    pub fn check_messages_already_processed<'a>(
+        &self,
+        messages: impl IntoIterator<Item = (&'a Hash, &'a Hash)>,
+    ) -> Vec<bool> {
+        let rcache = self.src.status_cache.read().unwrap();
+        messages
+            .into_iter()
+            .map(|(message_hash, recent_blockhash)| {
+                rcache
+                    .get_status(message_hash, recent_blockhash, &self.ancestors)
+                    .is_some()
+            })
+            .collect()
+    }
+
     pub fn get_hash_age(&self, hash: &Hash) -> Option<u64> {
         self.blockhash_queue.read().unwrap().get_hash_age(hash)
     }
@@ -4773,7 +4880,11 @@ impl Bank {
         execution_results: &[TransactionExecutionResult],
     ) -> Vec<Result<()>> {
         let hash_queue = self.blockhash_queue.read().unwrap();
+        let support_set_compute_unit_price_ix = self
+            .feature_set
+            .is_active(&add_set_compute_unit_price_ix::id());
         let mut fees = 0;
+        let mut landed_compute_unit_prices = vec![];
 
         let results = txs
             .iter()
@@ -4803,9 +4914,20 @@ impl Bank {
                     lamports_per_signature,
                     &self.fee_structure,
                     self.feature_set.is_active(&tx_wide_compute_cap::id()),
-                    self.feature_set
-                        .is_active(&add_set_compute_unit_price_ix::id()),
+                    support_set_compute_unit_price_ix,
                 );
+                if !tx.is_simple_vote_transaction() {
+                    let compute_unit_price = ComputeBudget::default()
+                        .process_instructions(
+                            tx.message().program_instructions_iter(),
+                            false,
+                            false,
+                            support_set_compute_unit_price_ix,
+                        )
+                        .unwrap_or_default()
+                        .get_priority();
+                    landed_compute_unit_prices.push((tx, compute_unit_price));
+                }
 
                 // In case of instruction error, even though no accounts
                 // were stored we still need to charge the payer the
@@ -4824,6 +4946,21 @@ impl Bank {
             .collect();
 
         self.collector_fees.fetch_add(fees, Relaxed);
+        if !landed_compute_unit_prices.is_empty() {
+            let mut landed_prioritization_fees = self.landed_prioritization_fees.write().unwrap();
+            for (tx, compute_unit_price) in landed_compute_unit_prices {
+                let message = tx.message();
+                landed_prioritization_fees.record(
+                    compute_unit_price,
+                    message
+                        .account_keys()
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| message.is_writable(*i))
+                        .map(|(_, key)| key),
+                );
+            }
+        }
         results
     }
 
@@ -6147,6 +6284,27 @@ impl Bank {
         self.compute_budget = compute_budget;
     }
 
+    pub fn set_transaction_account_lock_limit(
+        &mut self,
+        transaction_account_lock_limit: Option<usize>,
+    ) {
+        self.transaction_account_lock_limit = transaction_account_lock_limit;
+    }
+
+    /// Get the max number of accounts that a transaction may lock in this block
+    pub fn get_transaction_account_lock_limit(&self) -> usize {
+        if let Some(transaction_account_lock_limit) = self.transaction_account_lock_limit {
+            transaction_account_lock_limit
+        } else if self
+            .feature_set
+            .is_active(&feature_set::max_tx_account_locks::id())
+        {
+            MAX_TX_ACCOUNT_LOCKS
+        } else {
+            usize::MAX
+        }
+    }
+
     pub fn hard_forks(&self) -> Arc<RwLock<HardForks>> {
         self.hard_forks.clone()
     }
@@ -6233,6 +6391,25 @@ impl Bank {
         )
     }
 
+    /// Returns, in pubkey order, up to `limit` accounts owned by `program_id` that pass
+    /// `filter`, starting after pubkey `after`
+    pub fn get_filtered_program_accounts_after<F: Fn(&AccountSharedData) -> bool>(
+        &self,
+        program_id: &Pubkey,
+        filter: F,
+        after: Option<&Pubkey>,
+        limit: usize,
+    ) -> ScanResult<Vec<TransactionAccount>> {
+        self.rc.accounts.load_by_program_with_filter_after(
+            &self.ancestors,
+            self.bank_id,
+            program_id,
+            filter,
+            after,
+            limit,
+        )
+    }
+
     pub fn get_filtered_indexed_accounts<F: Fn(&AccountSharedData) -> bool>(
         &self,
         index_key: &IndexKey,
@@ -6250,10 +6427,44 @@ impl Bank {
         )
     }
 
+    /// Returns up to `limit` accounts of the secondary index entry of `index_key` that pass
+    /// `filter`, in pubkey order, starting after pubkey `after`
+    pub fn get_filtered_indexed_accounts_after<F: Fn(&AccountSharedData) -> bool>(
+        &self,
+        index_key: &IndexKey,
+        filter: F,
+        after: Option<&Pubkey>,
+        limit: usize,
+    ) -> ScanResult<Vec<TransactionAccount>> {
+        self.rc.accounts.load_by_index_key_with_filter_after(
+            &self.ancestors,
+            self.bank_id,
+            index_key,
+            filter,
+            after,
+            limit,
+        )
+    }
+
     pub fn account_indexes_include_key(&self, key: &Pubkey) -> bool {
         self.rc.accounts.account_indexes_include_key(key)
     }
 
+    /// Enables secondary index `index` and starts backfilling it in the background, see
+    /// `AccountsDb::enable_account_index()`
+    pub fn enable_account_index(&self, index: AccountIndex) -> Option<Arc<AccountIndexBackfill>> {
+        self.rc.accounts.accounts_db.enable_account_index(index)
+    }
+
+    /// Whether indexed scans can use secondary index `index`
+    pub fn account_index_ready(&self, index: &AccountIndex) -> bool {
+        self.rc.accounts.accounts_db.account_index_ready(index)
+    }
+
+    pub fn account_index_backfills(&self) -> Vec<Arc<AccountIndexBackfill>> {
+        self.rc.accounts.accounts_db.account_index_backfills()
+    }
+
     pub fn get_all_accounts_with_modified_slots(&self) -> ScanResult<Vec<PubkeyAccountSlot>> {
         self.rc.accounts.load_all(&self.ancestors, self.bank_id)
     }
@@ -6277,6 +6488,16 @@ impl Bank {
             .get_logs_for_address(address)
     }
 
+    pub fn get_transaction_logs_for_addresses(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Option<Vec<TransactionLogInfo>> {
+        self.transaction_log_collector
+            .read()
+            .unwrap()
+            .get_logs_for_addresses(addresses)
+    }
+
     pub fn get_all_accounts_modified_since_parent(&self) -> Vec<TransactionAccount> {
         self.rc.accounts.load_by_program_slot(self.slot(), None)
     }
@@ -6705,6 +6926,11 @@ impl Bank {
         self.capitalization.load(Relaxed)
     }
 
+    /// Return the transaction fees collected by this bank's leader, before burning
+    pub fn collector_fees(&self) -> u64 {
+        self.collector_fees.load(Relaxed)
+    }
+
     /// Return this bank's max_tick_height
     pub fn max_tick_height(&self) -> u64 {
         self.max_tick_height
@@ -6986,6 +7212,32 @@ impl Bank {
             .is_active(&feature_set::send_to_tpu_vote_port::id())
     }
 
+    /// Returns up to `max_accounts` of the accounts write-locked most often by this bank's
+    /// transactions, along with how often transactions conflicted on them
+    pub fn write_lock_heat_map(
+        &self,
+        max_accounts: usize,
+    ) -> Vec<(Pubkey, AccountWriteLockCounts)> {
+        self.rc.accounts.write_lock_heat_map(max_accounts)
+    }
+
+    pub fn report_write_lock_heat_map(&self) {
+        for (rank, (pubkey, counts)) in self
+            .write_lock_heat_map(WRITE_LOCK_HEAT_MAP_REPORT_SIZE)
+            .into_iter()
+            .enumerate()
+        {
+            datapoint_info!(
+                "bank-write_lock_heat_map",
+                ("slot", self.slot() as i64, i64),
+                ("rank", rank as i64, i64),
+                ("account", pubkey.to_string(), String),
+                ("write_locks", counts.write_locks as i64, i64),
+                ("conflicts", counts.conflicts as i64, i64),
+            );
+        }
+    }
+
     pub fn read_cost_tracker(&self) -> LockResult<RwLockReadGuard<CostTracker>> {
         self.cost_tracker.read()
     }
@@ -6994,6 +7246,12 @@ impl Bank {
         self.cost_tracker.write()
     }
 
+    pub fn read_landed_prioritization_fees(
+        &self,
+    ) -> LockResult<RwLockReadGuard<LandedPrioritizationFees>> {
+        self.landed_prioritization_fees.read()
+    }
+
     // Check if the wallclock time from bank creation to now has exceeded the allotted
     // time for transaction processing
     pub fn should_bank_still_be_processing_txs(
@@ -7385,6 +7643,7 @@ pub(crate) mod tests {
                 genesis_sysvar_and_builtin_program_lamports, GenesisConfigInfo,
                 ValidatorVoteKeypairs,
             },
+            landed_prioritization_fees::PrioritizationFeeQuantiles,
             status_cache::MAX_CACHE_ENTRIES,
         },
         crossbeam_channel::{bounded, unbounded},
@@ -7418,7 +7677,6 @@ pub(crate) mod tests {
             system_instruction::{self, SystemError, MAX_PERMITTED_DATA_LENGTH},
             system_program,
             timing::duration_as_s,
-            transaction::MAX_TX_ACCOUNT_LOCKS,
             transaction_context::InstructionContext,
         },
         solana_vote_program::{
@@ -9788,6 +10046,44 @@ pub(crate) mod tests {
         assert_eq!(bank.transaction_count(), 2);
     }
 
+    #[test]
+    fn test_simulate_transactions_threads_account_state() {
+        let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(10.));
+        let bank = Bank::new_for_tests(&genesis_config);
+        bank.freeze();
+        let alice = Keypair::new();
+        let bob = solana_sdk::pubkey::new_rand();
+
+        let fund_alice =
+            SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+                &mint_keypair,
+                &alice.pubkey(),
+                sol_to_lamports(2.),
+                genesis_config.hash(),
+            ));
+        let pay_bob = SanitizedTransaction::from_transaction_for_tests(
+            system_transaction::transfer(&alice, &bob, sol_to_lamports(1.), genesis_config.hash()),
+        );
+
+        // Alice has no funds without the first transaction
+        assert!(bank.simulate_transaction(pay_bob.clone()).result.is_err());
+
+        let results = bank.simulate_transactions(vec![fund_alice, pay_bob]);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].result, Ok(()));
+        assert_eq!(results[1].result, Ok(()));
+        let bob_account = results[1]
+            .post_simulation_accounts
+            .iter()
+            .find(|(pubkey, _)| *pubkey == bob)
+            .map(|(_, account)| account.lamports());
+        assert_eq!(bob_account, Some(sol_to_lamports(1.)));
+
+        // Simulation leaves the bank untouched
+        assert_eq!(bank.get_balance(&alice.pubkey()), 0);
+        assert_eq!(bank.get_balance(&bob), 0);
+    }
+
     #[test]
     fn test_one_source_two_tx_one_batch() {
         let (genesis_config, mint_keypair) = create_genesis_config(1);
@@ -10793,6 +11089,46 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_check_messages_already_processed() {
+        let (genesis_config, mint_keypair) = create_genesis_config(2);
+        let root = Arc::new(Bank::new_for_tests(&genesis_config));
+        let parent = Arc::new(new_from_parent(&root));
+
+        let processed_tx = system_transaction::transfer(
+            &mint_keypair,
+            &Keypair::new().pubkey(),
+            1,
+            genesis_config.hash(),
+        );
+        let unprocessed_tx = system_transaction::transfer(
+            &mint_keypair,
+            &Keypair::new().pubkey(),
+            1,
+            genesis_config.hash(),
+        );
+        assert_eq!(parent.process_transaction(&processed_tx), Ok(()));
+        let bank = new_from_parent(&parent);
+
+        let processed_hash = processed_tx.message().hash();
+        let unprocessed_hash = unprocessed_tx.message().hash();
+        assert_eq!(
+            bank.check_messages_already_processed(vec![
+                (&processed_hash, &genesis_config.hash()),
+                (&unprocessed_hash, &genesis_config.hash()),
+            ]),
+            vec![true, false]
+        );
+
+        // A bank on a sibling fork has not seen the transaction
+        let sibling = Bank::new_from_parent(&root, &Pubkey::default(), parent.slot() + 1);
+        assert_eq!(
+            sibling
+                .check_messages_already_processed(vec![(&processed_hash, &genesis_config.hash())]),
+            vec![false]
+        );
+    }
+
     /// Verifies that last ids and accounts are correctly referenced from parent
     #[test]
     fn test_bank_parent_account_spend() {
@@ -11862,6 +12198,52 @@ pub(crate) mod tests {
         assert_eq!(indexed_accounts[0], (address, new_account));
     }
 
+    #[test]
+    fn test_enable_account_index() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(500);
+        let bank = Arc::new(Bank::new_with_config(
+            &genesis_config,
+            AccountSecondaryIndexes::default(),
+            false,
+            AccountShrinkThreshold::default(),
+        ));
+        assert!(!bank.account_index_ready(&AccountIndex::ProgramId));
+
+        let address = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let account = AccountSharedData::new(1, 0, &program_id);
+        bank.store_account(&address, &account);
+
+        let backfill = bank.enable_account_index(AccountIndex::ProgramId).unwrap();
+        assert!(bank.enable_account_index(AccountIndex::ProgramId).is_none());
+        assert_eq!(bank.account_index_backfills().len(), 1);
+
+        // Stored while the backfill may be running
+        let another_address = Pubkey::new_unique();
+        let another_account = AccountSharedData::new(2, 0, &program_id);
+        bank.store_account(&another_address, &another_account);
+
+        while !backfill.is_complete() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(bank.account_index_ready(&AccountIndex::ProgramId));
+        assert_eq!(backfill.bins_scanned(), backfill.num_bins());
+        assert!(backfill.accounts_scanned() >= 2);
+
+        let mut indexed_accounts = bank
+            .get_filtered_indexed_accounts(
+                &IndexKey::ProgramId(program_id),
+                |_| true,
+                &ScanConfig::default(),
+                None,
+            )
+            .unwrap();
+        indexed_accounts.sort_by_key(|(pubkey, _)| *pubkey);
+        let mut expected_accounts = vec![(address, account), (another_address, another_account)];
+        expected_accounts.sort_by_key(|(pubkey, _)| *pubkey);
+        assert_eq!(indexed_accounts, expected_accounts);
+    }
+
     #[test]
     fn test_status_cache_ancestors() {
         solana_logger::setup();
@@ -13425,6 +13807,33 @@ pub(crate) mod tests {
         assert_eq!(result, Err(TransactionError::TooManyAccountLocks));
     }
 
+    #[test]
+    fn test_transaction_account_lock_limit() {
+        let (genesis_config, mint_keypair) = create_genesis_config(500);
+        let mut bank = Bank::new_for_tests(&genesis_config);
+        bank.set_transaction_account_lock_limit(Some(2));
+        let bank = Arc::new(bank);
+        assert_eq!(bank.get_transaction_account_lock_limit(), 2);
+
+        // The limit is inherited by child banks
+        let bank = Bank::new_from_parent(&bank, &Pubkey::default(), 1);
+        assert_eq!(bank.get_transaction_account_lock_limit(), 2);
+
+        // A transfer locks its payer, recipient and the system program
+        let tx = SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &mint_keypair,
+            &solana_sdk::pubkey::new_rand(),
+            1,
+            bank.last_blockhash(),
+        ));
+        let txs = vec![tx];
+        let batch = bank.prepare_sanitized_batch(&txs);
+        assert_eq!(
+            batch.lock_results(),
+            &vec![Err(TransactionError::TooManyAccountLocks)]
+        );
+    }
+
     #[test]
     fn test_program_id_as_payer() {
         solana_logger::setup();
@@ -16993,6 +17402,62 @@ pub(crate) mod tests {
         }
     }
 
+    #[test]
+    fn test_landed_prioritization_fees() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config_with_leader(sol_to_lamports(1.0), &Pubkey::new_unique(), 42);
+        let bank = Bank::new_for_tests(&genesis_config);
+        assert_eq!(
+            bank.read_landed_prioritization_fees().unwrap().quantiles(),
+            None
+        );
+
+        let recipient = Pubkey::new_unique();
+        let tx = system_transaction::transfer(
+            &mint_keypair,
+            &recipient,
+            sol_to_lamports(0.5),
+            bank.last_blockhash(),
+        );
+        bank.process_transaction(&tx).unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(42),
+                system_instruction::transfer(&mint_keypair.pubkey(), &recipient, 2),
+            ],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            bank.last_blockhash(),
+        );
+        bank.process_transaction(&tx).unwrap();
+
+        let landed_prioritization_fees = bank.read_landed_prioritization_fees().unwrap();
+        assert_eq!(
+            landed_prioritization_fees.quantiles(),
+            Some(PrioritizationFeeQuantiles {
+                num_transactions: 2,
+                min: 0,
+                median: 0,
+                p90: 42,
+                max: 42,
+            })
+        );
+        assert_eq!(
+            landed_prioritization_fees
+                .account_quantiles(&recipient)
+                .map(|quantiles| quantiles.num_transactions),
+            Some(2)
+        );
+        // read-only accounts are not tracked
+        assert_eq!(
+            landed_prioritization_fees.account_quantiles(&system_program::id()),
+            None
+        );
+    }
+
     #[test]
     fn test_calculate_fee_secp256k1() {
         let key0 = Pubkey::new_unique();
@@ -17072,6 +17537,43 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_transaction_log_collector_get_logs_for_addresses() {
+        let new_log = |log_message: &str| TransactionLogInfo {
+            signature: Signature::new_unique(),
+            result: Ok(()),
+            is_vote: false,
+            log_messages: vec![log_message.to_string()],
+        };
+        let (address0, address1, address2) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let logs = vec![new_log("first"), new_log("second"), new_log("third")];
+        let mut mentioned_address_map = HashMap::new();
+        mentioned_address_map.insert(address0, vec![0, 2]);
+        mentioned_address_map.insert(address1, vec![1, 2]);
+        let transaction_log_collector = TransactionLogCollector {
+            logs: logs.clone(),
+            mentioned_address_map,
+        };
+
+        assert_eq!(
+            transaction_log_collector.get_logs_for_addresses(&[address1, address0]),
+            Some(logs.clone()),
+        );
+        assert_eq!(
+            transaction_log_collector.get_logs_for_addresses(&[address1, address2]),
+            Some(logs[1..].to_vec()),
+        );
+        assert_eq!(
+            transaction_log_collector.get_logs_for_addresses(&[address2]),
+            None
+        );
+        assert_eq!(transaction_log_collector.get_logs_for_addresses(&[]), None);
+    }
+
     /// Test exceeding the max accounts data size by creating accounts in a loop
     #[test]
     fn test_max_accounts_data_size_exceeded() {
@@ -0,0 +1,114 @@
+//! The `landed_prioritization_fees` module gathers the compute unit prices of the transactions
+//! committed to a bank, over the whole bank and by account they write lock, so fee estimates
+//! can be published as soon as the bank is frozen.
+use {solana_sdk::pubkey::Pubkey, std::collections::HashMap};
+
+/// Distribution of the compute unit prices of the transactions that landed in a bank
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PrioritizationFeeQuantiles {
+    pub num_transactions: usize,
+    pub min: u64,
+    pub median: u64,
+    pub p90: u64,
+    pub max: u64,
+}
+
+impl PrioritizationFeeQuantiles {
+    /// Returns `None` if there are no prices
+    fn from_prices(prices: &[u64]) -> Option<Self> {
+        if prices.is_empty() {
+            return None;
+        }
+        let mut prices = prices.to_vec();
+        prices.sort_unstable();
+        // Nearest rank, so every quantile is a price actually paid
+        let quantile = |percent: usize| prices[(prices.len() * percent + 99) / 100 - 1];
+        Some(Self {
+            num_transactions: prices.len(),
+            min: prices[0],
+            median: quantile(50),
+            p90: quantile(90),
+            max: prices[prices.len() - 1],
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LandedPrioritizationFees {
+    /// Compute unit price of every landed transaction other than simple votes
+    compute_unit_prices: Vec<u64>,
+    /// Compute unit prices of the landed transactions that write lock each account
+    account_compute_unit_prices: HashMap<Pubkey, Vec<u64>>,
+}
+
+impl LandedPrioritizationFees {
+    /// Records a landed transaction paying `compute_unit_price` and write locking
+    /// `writable_accounts`
+    pub fn record<'a>(
+        &mut self,
+        compute_unit_price: u64,
+        writable_accounts: impl IntoIterator<Item = &'a Pubkey>,
+    ) {
+        self.compute_unit_prices.push(compute_unit_price);
+        for account in writable_accounts {
+            self.account_compute_unit_prices
+                .entry(*account)
+                .or_default()
+                .push(compute_unit_price);
+        }
+    }
+
+    /// Quantiles of the prices of every recorded transaction. Returns `None` if no transaction
+    /// was recorded
+    pub fn quantiles(&self) -> Option<PrioritizationFeeQuantiles> {
+        PrioritizationFeeQuantiles::from_prices(&self.compute_unit_prices)
+    }
+
+    /// Quantiles of the prices of the recorded transactions write locking `account`. Returns
+    /// `None` if no such transaction was recorded
+    pub fn account_quantiles(&self, account: &Pubkey) -> Option<PrioritizationFeeQuantiles> {
+        self.account_compute_unit_prices
+            .get(account)
+            .and_then(|prices| PrioritizationFeeQuantiles::from_prices(prices))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_landed_prioritization_fees() {
+        let mut fees = LandedPrioritizationFees::default();
+        assert_eq!(fees.quantiles(), None);
+
+        let account_a = Pubkey::new_unique();
+        let account_b = Pubkey::new_unique();
+        for price in (1..=10).rev() {
+            fees.record(price * 100, &[account_a]);
+        }
+        fees.record(5, &[account_a, account_b]);
+
+        assert_eq!(
+            fees.quantiles(),
+            Some(PrioritizationFeeQuantiles {
+                num_transactions: 11,
+                min: 5,
+                median: 500,
+                p90: 900,
+                max: 1_000,
+            })
+        );
+        assert_eq!(
+            fees.account_quantiles(&account_b),
+            Some(PrioritizationFeeQuantiles {
+                num_transactions: 1,
+                min: 5,
+                median: 5,
+                p90: 5,
+                max: 5,
+            })
+        );
+        assert_eq!(fees.account_quantiles(&Pubkey::new_unique()), None);
+    }
+}
@@ -43,8 +43,10 @@ pub mod in_mem_accounts_index;
 pub mod inline_spl_associated_token_account;
 pub mod inline_spl_token;
 pub mod inline_spl_token_2022;
+pub mod landed_prioritization_fees;
 pub mod loader_utils;
 pub mod message_processor;
+pub mod mmap_governor;
 pub mod non_circulating_supply;
 mod nonce_keyed_account;
 mod pubkey_bins;
@@ -63,6 +65,7 @@ pub mod snapshot_utils;
 pub mod sorted_storages;
 mod stake_account;
 pub mod stake_history;
+pub mod stake_snapshot;
 pub mod stake_weighted_timestamp;
 pub mod stakes;
 pub mod status_cache;
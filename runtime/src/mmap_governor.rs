@@ -0,0 +1,259 @@
+//! The `mmap_governor` module tracks the memory maps held by append vecs against the limits the
+//! operating system puts on a process. Every append vec keeps its file mapped for as long as it
+//! lives, so when storages accumulate the node eventually fails to map a new one and exits.
+//! Accounts db checks the governor to free maps while there is still headroom left.
+//!
+//! Append vecs close their file once it is mapped, so they only hold file handles while they
+//! are being opened. Those handles are drawn from a pool bounded by the open file limit, so that
+//! opening many storages at once, such as when a snapshot is loaded, waits for handles to be
+//! returned rather than failing with too many open files.
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Condvar, Mutex,
+    },
+};
+
+/// Share of `vm.max_map_count` the append vecs may map before maps are freed proactively. The
+/// rest is left to the other maps of the process, such as those of rocksdb and the heap.
+pub const DEFAULT_MMAP_PRESSURE_RATIO: f64 = 0.8;
+
+/// Share of the open file limit the append vecs may hold at once while they are being opened
+pub const DEFAULT_OPEN_FILES_RATIO: f64 = 0.5;
+
+static MAPPED_APPEND_VECS: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref APPEND_VEC_FILES: FileHandlePool = FileHandlePool::new(
+        MmapLimits::from_system()
+            .max_open_files
+            .map(|max_open_files| (max_open_files as f64 * DEFAULT_OPEN_FILES_RATIO) as usize)
+            .unwrap_or(usize::MAX),
+    );
+}
+
+pub(crate) fn append_vec_mapped() {
+    MAPPED_APPEND_VECS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn append_vec_unmapped() {
+    MAPPED_APPEND_VECS.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Number of append vecs currently mapped by this process
+pub fn mapped_append_vecs() -> usize {
+    MAPPED_APPEND_VECS.load(Ordering::Relaxed)
+}
+
+/// Takes a handle from the pool of append vec files, to be held while the file is open
+pub(crate) fn append_vec_file_handle() -> FileHandle<'static> {
+    APPEND_VEC_FILES.take()
+}
+
+/// Bounds the number of files open at once. Handles are taken before a file is opened and
+/// returned once it is closed.
+#[derive(Debug)]
+pub struct FileHandlePool {
+    max_open_files: usize,
+    open_files: Mutex<usize>,
+    returned: Condvar,
+}
+
+impl FileHandlePool {
+    pub fn new(max_open_files: usize) -> Self {
+        Self {
+            max_open_files: max_open_files.max(1),
+            open_files: Mutex::default(),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// Takes a handle, waiting for one to be returned if all are taken
+    pub fn take(&self) -> FileHandle<'_> {
+        let mut open_files = self
+            .returned
+            .wait_while(self.open_files.lock().unwrap(), |open_files| {
+                *open_files >= self.max_open_files
+            })
+            .unwrap();
+        *open_files += 1;
+        FileHandle { pool: self }
+    }
+
+    /// Number of handles currently taken
+    pub fn open_files(&self) -> usize {
+        *self.open_files.lock().unwrap()
+    }
+}
+
+/// A handle taken from a `FileHandlePool`, returned to it when dropped
+#[derive(Debug)]
+pub struct FileHandle<'a> {
+    pool: &'a FileHandlePool,
+}
+
+impl Drop for FileHandle<'_> {
+    fn drop(&mut self) {
+        *self.pool.open_files.lock().unwrap() -= 1;
+        self.pool.returned.notify_one();
+    }
+}
+
+/// Limits of the process on memory maps and open files, if known
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MmapLimits {
+    pub max_map_count: Option<usize>,
+    pub max_open_files: Option<usize>,
+}
+
+impl MmapLimits {
+    /// Reads the limits of the running process; both are unknown on platforms without procfs
+    pub fn from_system() -> Self {
+        Self {
+            max_map_count: fs::read_to_string("/proc/sys/vm/max_map_count")
+                .ok()
+                .and_then(|max_map_count| max_map_count.trim().parse().ok()),
+            max_open_files: fs::read_to_string("/proc/self/limits")
+                .ok()
+                .and_then(|limits| parse_max_open_files(&limits)),
+        }
+    }
+}
+
+// Returns the soft limit from the "Max open files" line of /proc/self/limits
+fn parse_max_open_files(limits: &str) -> Option<usize> {
+    limits
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))
+        .and_then(|limit| limit.split_whitespace().next())
+        .and_then(|soft_limit| soft_limit.parse().ok())
+}
+
+fn open_files() -> Option<usize> {
+    fs::read_dir("/proc/self/fd")
+        .ok()
+        .map(|entries| entries.count())
+}
+
+#[derive(Debug)]
+pub struct MmapGovernor {
+    limits: MmapLimits,
+    pressure_ratio: f64,
+}
+
+impl Default for MmapGovernor {
+    fn default() -> Self {
+        Self::new(MmapLimits::from_system(), DEFAULT_MMAP_PRESSURE_RATIO)
+    }
+}
+
+impl MmapGovernor {
+    pub fn new(limits: MmapLimits, pressure_ratio: f64) -> Self {
+        Self {
+            limits,
+            pressure_ratio,
+        }
+    }
+
+    pub fn limits(&self) -> &MmapLimits {
+        &self.limits
+    }
+
+    /// Number of append vecs that may still be mapped before the governor is under pressure,
+    /// or None if the map limit is unknown
+    pub fn map_headroom(&self) -> Option<usize> {
+        self.limits.max_map_count.map(|max_map_count| {
+            ((max_map_count as f64 * self.pressure_ratio) as usize)
+                .saturating_sub(mapped_append_vecs())
+        })
+    }
+
+    pub fn is_under_pressure(&self) -> bool {
+        self.map_headroom() == Some(0)
+    }
+
+    pub fn report(&self) {
+        let to_i64 = |value: Option<usize>| value.map(|value| value as i64).unwrap_or(-1);
+        datapoint_info!(
+            "accounts_db-mmap",
+            ("mapped_append_vecs", mapped_append_vecs(), i64),
+            ("max_map_count", to_i64(self.limits.max_map_count), i64),
+            ("map_headroom", to_i64(self.map_headroom()), i64),
+            ("open_files", to_i64(open_files()), i64),
+            ("open_append_vec_files", APPEND_VEC_FILES.open_files(), i64),
+            ("max_open_files", to_i64(self.limits.max_open_files), i64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crossbeam_channel::unbounded,
+        std::{sync::Arc, thread, time::Duration},
+    };
+
+    #[test]
+    fn test_parse_max_open_files() {
+        let limits = "\
+Limit                     Soft Limit           Hard Limit           Units
+Max processes             127533               127533               processes
+Max open files            1024                 524288               files
+Max locked memory         8388608              8388608              bytes
+";
+        assert_eq!(parse_max_open_files(limits), Some(1024));
+        assert_eq!(parse_max_open_files("Max processes 10 10"), None);
+    }
+
+    #[test]
+    fn test_mmap_governor_pressure() {
+        let governor = MmapGovernor::new(MmapLimits::default(), DEFAULT_MMAP_PRESSURE_RATIO);
+        assert_eq!(governor.map_headroom(), None);
+        assert!(!governor.is_under_pressure());
+
+        let limits = MmapLimits {
+            max_map_count: Some(0),
+            max_open_files: None,
+        };
+        let governor = MmapGovernor::new(limits, DEFAULT_MMAP_PRESSURE_RATIO);
+        assert_eq!(governor.map_headroom(), Some(0));
+        assert!(governor.is_under_pressure());
+
+        let limits = MmapLimits {
+            max_map_count: Some(usize::MAX / 2),
+            max_open_files: None,
+        };
+        let governor = MmapGovernor::new(limits, 1.0);
+        assert!(!governor.is_under_pressure());
+    }
+
+    #[test]
+    fn test_file_handle_pool() {
+        let pool = Arc::new(FileHandlePool::new(2));
+        let file_handle = pool.take();
+        let other_file_handle = pool.take();
+        assert_eq!(pool.open_files(), 2);
+
+        // A third file has to wait for a handle to be returned
+        let (sender, receiver) = unbounded();
+        let waiter = {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                let _file_handle = pool.take();
+                sender.send(()).unwrap();
+            })
+        };
+        assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+        drop(file_handle);
+        receiver.recv().unwrap();
+        waiter.join().unwrap();
+        assert_eq!(pool.open_files(), 1);
+
+        drop(other_file_handle);
+        assert_eq!(pool.open_files(), 0);
+        // Pools always hand out at least one handle
+        assert_eq!(FileHandlePool::new(0).take().pool.max_open_files, 1);
+    }
+}
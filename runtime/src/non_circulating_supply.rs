@@ -29,13 +29,7 @@ pub fn calculate_non_circulating_supply(bank: &Arc<Bank>) -> ScanResult<NonCircu
 
     let clock = bank.clock();
     let config = &ScanConfig::default();
-    let stake_accounts = if bank
-        .rc
-        .accounts
-        .accounts_db
-        .account_indexes
-        .contains(&AccountIndex::ProgramId)
-    {
+    let stake_accounts = if bank.account_index_ready(&AccountIndex::ProgramId) {
         bank.get_filtered_indexed_accounts(
             &IndexKey::ProgramId(stake::program::id()),
             // The program-id account index checks for Account owner on inclusion. However, due to
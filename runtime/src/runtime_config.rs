@@ -5,4 +5,7 @@ use solana_program_runtime::compute_budget::ComputeBudget;
 pub struct RuntimeConfig {
     pub bpf_jit: bool,
     pub compute_budget: Option<ComputeBudget>,
+    /// Overrides the feature-gated account lock limit of transactions. Replay enforces it, so
+    /// it is only for test validators, which don't replay the blocks of other nodes.
+    pub transaction_account_lock_limit: Option<usize>,
 }
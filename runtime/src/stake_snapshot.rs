@@ -0,0 +1,206 @@
+//! The `stake_snapshot` module exports the stake delegations in effect at an epoch boundary to
+//! a compressed file per epoch, so historical stakes can be served without scanning accounts.
+use {
+    crate::{stakes::StakesEnum, vote_account::VoteAccount},
+    bincode::Options,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{clock::Epoch, pubkey::Pubkey},
+    std::{
+        collections::BTreeMap,
+        fs::{self, File},
+        io::{self, BufReader, BufWriter, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Upper bound on the size of a decompressed stake snapshot
+const MAX_STAKE_SNAPSHOT_SIZE: u64 = 1024 * 1024 * 1024; // 1 GiB
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeDelegationSnapshot {
+    pub stake_pubkey: Pubkey,
+    pub stake: u64,
+    pub activation_epoch: Epoch,
+    pub deactivation_epoch: Epoch,
+    pub effective: u64,
+    pub activating: u64,
+    pub deactivating: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorStakeSnapshot {
+    pub vote_pubkey: Pubkey,
+    /// `None` if the vote account state could not be deserialized
+    pub node_pubkey: Option<Pubkey>,
+    pub activated_stake: u64,
+    pub delegations: Vec<StakeDelegationSnapshot>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochStakeSnapshot {
+    pub epoch: Epoch,
+    pub total_activated_stake: u64,
+    /// Sorted by vote account address
+    pub validators: Vec<ValidatorStakeSnapshot>,
+}
+
+impl EpochStakeSnapshot {
+    /// Groups every stake delegation by the vote account it is delegated to, with activation
+    /// states computed as of the epoch of `stakes`. Delegations to vote accounts that are not
+    /// cached in `stakes` are still reported, without a node identity.
+    pub fn new(stakes: &StakesEnum) -> Self {
+        let epoch = stakes.epoch();
+        let history = stakes.history();
+        let vote_accounts = stakes.vote_accounts();
+
+        let mut validators: BTreeMap<Pubkey, ValidatorStakeSnapshot> = vote_accounts
+            .iter()
+            .map(|(vote_pubkey, (activated_stake, vote_account))| {
+                (
+                    *vote_pubkey,
+                    ValidatorStakeSnapshot {
+                        vote_pubkey: *vote_pubkey,
+                        node_pubkey: node_pubkey(vote_account),
+                        activated_stake: *activated_stake,
+                        delegations: vec![],
+                    },
+                )
+            })
+            .collect();
+        for (stake_pubkey, delegation) in stakes.stake_delegations() {
+            let status = delegation.stake_activating_and_deactivating(epoch, Some(history));
+            validators
+                .entry(delegation.voter_pubkey)
+                .or_insert_with(|| ValidatorStakeSnapshot {
+                    vote_pubkey: delegation.voter_pubkey,
+                    ..ValidatorStakeSnapshot::default()
+                })
+                .delegations
+                .push(StakeDelegationSnapshot {
+                    stake_pubkey,
+                    stake: delegation.stake,
+                    activation_epoch: delegation.activation_epoch,
+                    deactivation_epoch: delegation.deactivation_epoch,
+                    effective: status.effective,
+                    activating: status.activating,
+                    deactivating: status.deactivating,
+                });
+        }
+
+        let validators: Vec<_> = validators
+            .into_values()
+            .map(|mut validator| {
+                validator
+                    .delegations
+                    .sort_unstable_by_key(|delegation| delegation.stake_pubkey);
+                validator
+            })
+            .collect();
+        Self {
+            epoch,
+            total_activated_stake: validators
+                .iter()
+                .map(|validator| validator.activated_stake)
+                .sum(),
+            validators,
+        }
+    }
+}
+
+fn node_pubkey(vote_account: &VoteAccount) -> Option<Pubkey> {
+    vote_account
+        .vote_state()
+        .as_ref()
+        .ok()
+        .map(|vote_state| vote_state.node_pubkey)
+}
+
+pub fn stake_snapshot_path(stake_snapshot_dir: &Path, epoch: Epoch) -> PathBuf {
+    stake_snapshot_dir.join(format!("stake-snapshot-{}.bin.zst", epoch))
+}
+
+/// Writes `snapshot` to `stake_snapshot_dir`, replacing any earlier export for the same epoch.
+/// The file is written under a temporary name first so readers never observe a partial file.
+pub fn write_stake_snapshot(
+    stake_snapshot_dir: &Path,
+    snapshot: &EpochStakeSnapshot,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(stake_snapshot_dir)?;
+    let path = stake_snapshot_path(stake_snapshot_dir, snapshot.epoch);
+    let tmp_path = path.with_extension("zst.tmp");
+    {
+        let file = BufWriter::new(File::create(&tmp_path)?);
+        let mut encoder = zstd::stream::Encoder::new(file, 0)?;
+        bincode::serialize_into(&mut encoder, snapshot)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        encoder.finish()?.flush()?;
+    }
+    fs::rename(&tmp_path, &path)?;
+    Ok(path)
+}
+
+/// Reads the stake snapshot exported for `epoch`, or `None` if there is none
+pub fn read_stake_snapshot(
+    stake_snapshot_dir: &Path,
+    epoch: Epoch,
+) -> io::Result<Option<EpochStakeSnapshot>> {
+    let file = match File::open(stake_snapshot_path(stake_snapshot_dir, epoch)) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let decoder = zstd::stream::read::Decoder::new(BufReader::new(file))?;
+    bincode::options()
+        .with_limit(MAX_STAKE_SNAPSHOT_SIZE)
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .deserialize_from(decoder)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::stakes::{tests::create_staked_node_accounts, Stakes, StakesCache},
+        rayon::ThreadPoolBuilder,
+    };
+
+    #[test]
+    fn test_stake_snapshot_roundtrip() {
+        let stakes_cache = StakesCache::new(Stakes::default());
+        let ((vote_pubkey, vote_account), (stake_pubkey, stake_account)) =
+            create_staked_node_accounts(10);
+        stakes_cache.check_and_store(&vote_pubkey, &vote_account);
+        stakes_cache.check_and_store(&stake_pubkey, &stake_account);
+        let thread_pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        stakes_cache.activate_epoch(3, &thread_pool);
+        let stakes = StakesEnum::from(stakes_cache.stakes().clone());
+
+        let snapshot = EpochStakeSnapshot::new(&stakes);
+        assert_eq!(snapshot.epoch, 3);
+        assert_eq!(snapshot.validators.len(), 1);
+        let validator = &snapshot.validators[0];
+        assert_eq!(validator.vote_pubkey, vote_pubkey);
+        assert_eq!(validator.delegations.len(), 1);
+        assert_eq!(validator.delegations[0].stake_pubkey, stake_pubkey);
+        assert_eq!(
+            validator.delegations[0].effective,
+            validator.activated_stake
+        );
+        assert_eq!(snapshot.total_activated_stake, validator.activated_stake);
+
+        let stake_snapshot_dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            read_stake_snapshot(stake_snapshot_dir.path(), 3).unwrap(),
+            None
+        );
+        let path = write_stake_snapshot(stake_snapshot_dir.path(), &snapshot).unwrap();
+        assert_eq!(path, stake_snapshot_path(stake_snapshot_dir.path(), 3));
+        assert_eq!(
+            read_stake_snapshot(stake_snapshot_dir.path(), 3).unwrap(),
+            Some(snapshot)
+        );
+    }
+}
@@ -391,6 +391,36 @@ impl StakesEnum {
             StakesEnum::Delegations(stakes) => stakes.staked_nodes(),
         }
     }
+
+    pub fn epoch(&self) -> Epoch {
+        match self {
+            StakesEnum::Accounts(stakes) => stakes.epoch,
+            StakesEnum::Delegations(stakes) => stakes.epoch,
+        }
+    }
+
+    pub fn history(&self) -> &StakeHistory {
+        match self {
+            StakesEnum::Accounts(stakes) => &stakes.stake_history,
+            StakesEnum::Delegations(stakes) => &stakes.stake_history,
+        }
+    }
+
+    /// Returns the delegation of every stake account
+    pub fn stake_delegations(&self) -> Vec<(Pubkey, Delegation)> {
+        match self {
+            StakesEnum::Accounts(stakes) => stakes
+                .stake_delegations
+                .iter()
+                .map(|(pubkey, stake_account)| (*pubkey, stake_account.delegation()))
+                .collect(),
+            StakesEnum::Delegations(stakes) => stakes
+                .stake_delegations
+                .iter()
+                .map(|(pubkey, delegation)| (*pubkey, *delegation))
+                .collect(),
+        }
+    }
 }
 
 impl From<Stakes<StakeAccount>> for Stakes<Delegation> {
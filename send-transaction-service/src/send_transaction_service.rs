@@ -5,10 +5,11 @@ use {
     solana_client::{connection_cache, tpu_connection::TpuConnection},
     solana_measure::measure::Measure,
     solana_metrics::datapoint_warn,
-    solana_runtime::{bank::Bank, bank_forks::BankForks},
+    solana_runtime::{bank::Bank, bank_forks::BankForks, commitment::BlockCommitmentCache},
     solana_sdk::{
-        hash::Hash, nonce_account, pubkey::Pubkey, saturating_add_assign, signature::Signature,
-        timing::AtomicInterval, transport::TransportError,
+        commitment_config::CommitmentConfig, hash::Hash, nonce_account, pubkey::Pubkey,
+        saturating_add_assign, signature::Signature, timing::AtomicInterval,
+        transport::TransportError,
     },
     std::{
         collections::{
@@ -94,6 +95,7 @@ impl TransactionInfo {
 #[derive(Default, Debug, PartialEq, Eq)]
 struct ProcessTransactionsResult {
     rooted: u64,
+    committed: u64,
     expired: u64,
     retried: u64,
     max_retries_elapsed: u64,
@@ -115,6 +117,9 @@ pub struct Config {
     pub batch_size: usize,
     /// How frequently batches are sent
     pub batch_send_rate_ms: u64,
+    /// Stop retrying a transaction once it succeeded at this commitment. Rooted transactions
+    /// are never retried, and confirmed is only observed with a block commitment cache.
+    pub retry_stop_commitment: CommitmentConfig,
 }
 
 impl Default for Config {
@@ -127,6 +132,7 @@ impl Default for Config {
             use_quic: DEFAULT_TPU_USE_QUIC,
             batch_size: DEFAULT_TRANSACTION_BATCH_SIZE,
             batch_send_rate_ms: DEFAULT_BATCH_SEND_RATE_MS,
+            retry_stop_commitment: CommitmentConfig::finalized(),
         }
     }
 }
@@ -218,6 +224,9 @@ struct SendTransactionServiceStats {
     /// Count of rooted transactions
     rooted_transactions: AtomicU64,
 
+    /// Count of transactions that succeeded at the retry stop commitment before being rooted
+    committed_transactions: AtomicU64,
+
     /// Count of expired transactions
     expired_transactions: AtomicU64,
 
@@ -298,6 +307,11 @@ impl SendTransactionServiceStatsReport {
                     self.stats.rooted_transactions.swap(0, Ordering::Relaxed),
                     i64
                 ),
+                (
+                    "committed-tx",
+                    self.stats.committed_transactions.swap(0, Ordering::Relaxed),
+                    i64
+                ),
                 (
                     "expired-tx",
                     self.stats.expired_transactions.swap(0, Ordering::Relaxed),
@@ -344,12 +358,13 @@ impl SendTransactionService {
             use_quic,
             ..Config::default()
         };
-        Self::new_with_config(tpu_address, bank_forks, leader_info, receiver, config)
+        Self::new_with_config(tpu_address, bank_forks, None, leader_info, receiver, config)
     }
 
     pub fn new_with_config<T: TpuInfo + std::marker::Send + 'static>(
         tpu_address: SocketAddr,
         bank_forks: &Arc<RwLock<BankForks>>,
+        block_commitment_cache: Option<Arc<RwLock<BlockCommitmentCache>>>,
         leader_info: Option<T>,
         receiver: Receiver<TransactionInfo>,
         config: Config,
@@ -374,6 +389,7 @@ impl SendTransactionService {
         let retry_thread = Self::retry_thread(
             tpu_address,
             bank_forks.clone(),
+            block_commitment_cache,
             leader_info_provider,
             config,
             retry_transactions,
@@ -493,6 +509,7 @@ impl SendTransactionService {
     fn retry_thread<T: TpuInfo + std::marker::Send + 'static>(
         tpu_address: SocketAddr,
         bank_forks: Arc<RwLock<BankForks>>,
+        block_commitment_cache: Option<Arc<RwLock<BlockCommitmentCache>>>,
         leader_info_provider: Arc<Mutex<CurrentLeaderInfo<T>>>,
         config: Config,
         retry_transactions: Arc<Mutex<HashMap<Signature, TransactionInfo>>>,
@@ -520,17 +537,25 @@ impl SendTransactionService {
                     stats
                         .retry_queue_size
                         .store(transactions.len() as u64, Ordering::Relaxed);
-                    let (root_bank, working_bank) = {
+                    let confirmed_slot = block_commitment_cache
+                        .as_ref()
+                        .filter(|_| config.retry_stop_commitment.is_confirmed())
+                        .map(|cache| cache.read().unwrap().highest_confirmed_slot());
+                    let (root_bank, working_bank, commitment_bank) = {
                         let bank_forks = bank_forks.read().unwrap();
-                        (
-                            bank_forks.root_bank().clone(),
-                            bank_forks.working_bank().clone(),
-                        )
+                        let working_bank = bank_forks.working_bank();
+                        let commitment_bank = if config.retry_stop_commitment.is_processed() {
+                            Some(working_bank.clone())
+                        } else {
+                            confirmed_slot.and_then(|slot| bank_forks.get(slot))
+                        };
+                        (bank_forks.root_bank(), working_bank, commitment_bank)
                     };
 
                     let _result = Self::process_transactions(
                         &working_bank,
                         &root_bank,
+                        commitment_bank.as_ref(),
                         &tpu_address,
                         &mut transactions,
                         &leader_info_provider,
@@ -565,9 +590,11 @@ impl SendTransactionService {
     }
 
     /// Retry transactions sent before.
+    /// `commitment_bank` is the bank at `config.retry_stop_commitment`, unless that is the root.
     fn process_transactions<T: TpuInfo + std::marker::Send + 'static>(
         working_bank: &Arc<Bank>,
         root_bank: &Arc<Bank>,
+        commitment_bank: Option<&Arc<Bank>>,
         tpu_address: &SocketAddr,
         transactions: &mut HashMap<Signature, TransactionInfo>,
         leader_info_provider: &Arc<Mutex<CurrentLeaderInfo<T>>>,
@@ -589,6 +616,17 @@ impl SendTransactionService {
                 stats.rooted_transactions.fetch_add(1, Ordering::Relaxed);
                 return false;
             }
+            if let Some(commitment_bank) = commitment_bank {
+                if let Some(Ok(())) = commitment_bank.get_signature_status(signature) {
+                    info!(
+                        "Transaction is {:?}: {}",
+                        config.retry_stop_commitment.commitment, signature
+                    );
+                    result.committed += 1;
+                    stats.committed_transactions.fetch_add(1, Ordering::Relaxed);
+                    return false;
+                }
+            }
             let signature_status = working_bank.get_signature_status_slot(signature);
             if let Some((nonce_pubkey, durable_nonce)) = transaction_info.durable_nonce_info {
                 let nonce_account = working_bank.get_account(&nonce_pubkey).unwrap_or_default();
@@ -847,6 +885,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -877,6 +916,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -907,6 +947,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -937,6 +978,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -969,6 +1011,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1011,6 +1054,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1029,6 +1073,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1043,6 +1088,44 @@ mod test {
                 ..ProcessTransactionsResult::default()
             }
         );
+
+        info!("Transactions that succeeded at the retry stop commitment are dropped...");
+        let config = Config {
+            retry_stop_commitment: CommitmentConfig::processed(),
+            ..config
+        };
+        for signature in [non_rooted_signature, failed_signature] {
+            transactions.insert(
+                signature,
+                TransactionInfo::new(
+                    signature,
+                    vec![],
+                    working_bank.block_height(),
+                    None,
+                    None,
+                    Some(Instant::now()),
+                ),
+            );
+        }
+        let result = SendTransactionService::process_transactions::<NullTpuInfo>(
+            &working_bank,
+            &root_bank,
+            Some(&working_bank),
+            &tpu_address,
+            &mut transactions,
+            &leader_info_provider,
+            &config,
+            &stats,
+        );
+        assert!(transactions.is_empty());
+        assert_eq!(
+            result,
+            ProcessTransactionsResult {
+                committed: 1,
+                failed: 1,
+                ..ProcessTransactionsResult::default()
+            }
+        );
     }
 
     #[test]
@@ -1111,6 +1194,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1140,6 +1224,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1171,6 +1256,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1200,6 +1286,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1230,6 +1317,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1260,6 +1348,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1292,6 +1381,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
@@ -1321,6 +1411,7 @@ mod test {
         let result = SendTransactionService::process_transactions::<NullTpuInfo>(
             &working_bank,
             &root_bank,
+            None,
             &tpu_address,
             &mut transactions,
             &leader_info_provider,
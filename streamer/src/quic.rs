@@ -1,15 +1,18 @@
 use {
+    bytes::Bytes,
     crossbeam_channel::Sender,
     futures_util::stream::StreamExt,
     pem::Pem,
     pkcs8::{der::Document, AlgorithmIdentifier, ObjectIdentifier},
-    quinn::{Endpoint, EndpointConfig, IdleTimeout, IncomingUniStreams, ServerConfig, VarInt},
+    quinn::{
+        Connection, Endpoint, EndpointConfig, IdleTimeout, IncomingUniStreams, ServerConfig, VarInt,
+    },
     rcgen::{CertificateParams, DistinguishedName, DnType, SanType},
     solana_perf::packet::PacketBatch,
     solana_sdk::{
         packet::{Packet, PACKET_DATA_SIZE},
         quic::{QUIC_MAX_CONCURRENT_STREAMS, QUIC_MAX_TIMEOUT_MS},
-        signature::Keypair,
+        signature::{Keypair, Signature, SIGNATURE_BYTES},
         timing,
     },
     std::{
@@ -33,6 +36,178 @@ pub const MAX_STAKED_CONNECTIONS: usize = 2000;
 pub const MAX_UNSTAKED_CONNECTIONS: usize = 500;
 const NUM_QUIC_STREAMER_WORKER_THREADS: usize = 4;
 
+/// First byte of every `TransactionDropNotification` frame
+pub const TRANSACTION_DROP_NOTIFICATION_TAG: u8 = 1;
+/// Size of a serialized `TransactionDropNotification`: tag, reason and signature
+pub const TRANSACTION_DROP_NOTIFICATION_SIZE: usize = 2 + SIGNATURE_BYTES;
+
+/// Why a transaction received over QUIC was dropped by the leader without being processed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TransactionDropReason {
+    /// The recent blockhash of the transaction expired while it was buffered
+    BlockhashExpired = 1,
+    /// The transaction was evicted from a full buffer by higher priority transactions
+    Evicted = 2,
+}
+
+impl TryFrom<u8> for TransactionDropReason {
+    type Error = u8;
+
+    fn try_from(reason: u8) -> Result<Self, Self::Error> {
+        match reason {
+            1 => Ok(Self::BlockhashExpired),
+            2 => Ok(Self::Evicted),
+            reason => Err(reason),
+        }
+    }
+}
+
+/// Notification sent as a QUIC datagram to the sender of a dropped transaction, so that it
+/// can re-sign and resubmit the transaction right away instead of polling for its status
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionDropNotification {
+    pub reason: TransactionDropReason,
+    pub signature: Signature,
+}
+
+impl TransactionDropNotification {
+    pub fn to_bytes(&self) -> [u8; TRANSACTION_DROP_NOTIFICATION_SIZE] {
+        let mut bytes = [0u8; TRANSACTION_DROP_NOTIFICATION_SIZE];
+        bytes[0] = TRANSACTION_DROP_NOTIFICATION_TAG;
+        bytes[1] = self.reason as u8;
+        bytes[2..].copy_from_slice(self.signature.as_ref());
+        bytes
+    }
+
+    /// Parses a notification frame, returning `None` if `bytes` is not one
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != TRANSACTION_DROP_NOTIFICATION_SIZE
+            || bytes[0] != TRANSACTION_DROP_NOTIFICATION_TAG
+        {
+            return None;
+        }
+        Some(Self {
+            reason: TransactionDropReason::try_from(bytes[1]).ok()?,
+            signature: Signature::new(&bytes[2..]),
+        })
+    }
+}
+
+/// Sends `TransactionDropNotification`s over the QUIC connections a server accepted. Only
+/// connections that are still open can be notified.
+#[derive(Default)]
+pub struct QuicDropNotifier {
+    connections: RwLock<HashMap<SocketAddr, Connection>>,
+    notifications_sent: AtomicUsize,
+    notifications_failed: AtomicUsize,
+    notifications_unconnected: AtomicUsize,
+}
+
+impl QuicDropNotifier {
+    fn add_connection(&self, connection: Connection) {
+        self.connections
+            .write()
+            .unwrap()
+            .insert(connection.remote_address(), connection);
+    }
+
+    /// Forgets `connection`, unless another connection from the same address replaced it
+    fn remove_connection(&self, connection: &Connection) {
+        if let Entry::Occupied(entry) = self
+            .connections
+            .write()
+            .unwrap()
+            .entry(connection.remote_address())
+        {
+            if entry.get().stable_id() == connection.stable_id() {
+                entry.remove();
+            }
+        }
+    }
+
+    pub fn num_connections(&self) -> usize {
+        self.connections.read().unwrap().len()
+    }
+
+    /// Sends `notification` to `remote_addr` if it is still connected. Returns true if the
+    /// notification was queued; datagrams are unreliable, so it may still never arrive.
+    pub fn notify(
+        &self,
+        remote_addr: &SocketAddr,
+        notification: TransactionDropNotification,
+    ) -> bool {
+        let connection = match self.connections.read().unwrap().get(remote_addr) {
+            Some(connection) => connection.clone(),
+            None => {
+                self.notifications_unconnected
+                    .fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+        };
+        match connection.send_datagram(Bytes::copy_from_slice(&notification.to_bytes())) {
+            Ok(()) => {
+                self.notifications_sent.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(err) => {
+                debug!(
+                    "failed to notify {} of dropped transaction: {}",
+                    remote_addr, err
+                );
+                self.notifications_failed.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    fn report(&self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("connections", self.num_connections(), i64),
+            (
+                "notifications_sent",
+                self.notifications_sent.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "notifications_failed",
+                self.notifications_failed.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "notifications_unconnected",
+                self.notifications_unconnected.swap(0, Ordering::Relaxed),
+                i64
+            ),
+        );
+    }
+}
+
+/// Connection and stream limits a QUIC server enforces on its clients. Connections from
+/// staked and unstaked nodes are tracked in separate tables, so unstaked clients can never
+/// evict staked ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuicServerQos {
+    pub max_connections_per_ip: usize,
+    pub max_staked_connections: usize,
+    pub max_unstaked_connections: usize,
+    /// Streams read per second from each connection of a staked node, 0 for no limit
+    pub max_staked_streams_per_second: u64,
+    /// Streams read per second from each connection of an unstaked node, 0 for no limit
+    pub max_unstaked_streams_per_second: u64,
+}
+
+impl QuicServerQos {
+    fn max_streams_per_second(&self, stake: u64) -> u64 {
+        if stake > 0 {
+            self.max_staked_streams_per_second
+        } else {
+            self.max_unstaked_streams_per_second
+        }
+    }
+}
+
 /// Returns default server configuration along with its PEM certificate chain.
 #[allow(clippy::field_reassign_with_default)] // https://github.com/rust-lang/rust-clippy/issues/6527
 fn configure_server(
@@ -62,7 +237,7 @@ fn configure_server(
     let timeout = IdleTimeout::from(VarInt::from_u32(QUIC_MAX_TIMEOUT_MS));
     config.max_idle_timeout(Some(timeout));
 
-    // disable bidi & datagrams
+    // disable bidi & incoming datagrams, datagrams are only sent by a `QuicDropNotifier`
     const MAX_CONCURRENT_BIDI_STREAMS: u32 = 0;
     config.max_concurrent_bidi_streams(MAX_CONCURRENT_BIDI_STREAMS.into());
     config.datagram_receive_buffer_size(None);
@@ -228,6 +403,47 @@ fn handle_chunk(
     false
 }
 
+/// Paces the streams read from a connection to a fixed number per one-second window
+struct StreamRateLimiter {
+    max_streams_per_second: u64,
+    window_start: Instant,
+    streams_in_window: u64,
+}
+
+impl StreamRateLimiter {
+    const WINDOW: Duration = Duration::from_secs(1);
+
+    fn new(max_streams_per_second: u64, now: Instant) -> Self {
+        Self {
+            max_streams_per_second,
+            window_start: now,
+            streams_in_window: 0,
+        }
+    }
+
+    /// Counts a new stream, returning how long to wait before reading it if the connection
+    /// already used up its streams for the current window
+    fn register_stream(&mut self, now: Instant) -> Option<Duration> {
+        if self.max_streams_per_second == 0 {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= Self::WINDOW {
+            self.window_start = now;
+            self.streams_in_window = 0;
+        }
+        if self.streams_in_window < self.max_streams_per_second {
+            self.streams_in_window += 1;
+            None
+        } else {
+            // the stream is read at the start of the next window and counts against it
+            self.window_start += Self::WINDOW;
+            self.streams_in_window = 1;
+            Some(Self::WINDOW.saturating_sub(elapsed))
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ConnectionEntry {
     exit: Arc<AtomicBool>,
@@ -343,12 +559,13 @@ struct StreamStats {
     num_evictions: AtomicUsize,
     connection_add_failed: AtomicUsize,
     connection_setup_timeout: AtomicUsize,
+    total_throttled_streams: AtomicUsize,
 }
 
 impl StreamStats {
-    fn report(&self) {
+    fn report(&self, name: &'static str) {
         datapoint_info!(
-            "quic-connections",
+            name,
             (
                 "active_connections",
                 self.total_connections.load(Ordering::Relaxed),
@@ -424,6 +641,11 @@ impl StreamStats {
                 self.total_stream_read_errors.swap(0, Ordering::Relaxed),
                 i64
             ),
+            (
+                "throttled_streams",
+                self.total_throttled_streams.swap(0, Ordering::Relaxed),
+                i64
+            ),
         );
     }
 }
@@ -437,8 +659,12 @@ fn handle_connection(
     stream_exit: Arc<AtomicBool>,
     stats: Arc<StreamStats>,
     stake: u64,
+    max_streams_per_second: u64,
+    drop_notifier: Option<(Arc<QuicDropNotifier>, Connection)>,
 ) {
     tokio::spawn(async move {
+        let mut stream_rate_limiter =
+            StreamRateLimiter::new(max_streams_per_second, Instant::now());
         debug!(
             "quic new connection {} streams: {} connections: {}",
             remote_addr,
@@ -451,6 +677,12 @@ fn handle_connection(
                     Ok(mut stream) => {
                         stats.total_streams.fetch_add(1, Ordering::Relaxed);
                         stats.total_new_streams.fetch_add(1, Ordering::Relaxed);
+                        if let Some(delay) = stream_rate_limiter.register_stream(Instant::now()) {
+                            stats
+                                .total_throttled_streams
+                                .fetch_add(1, Ordering::Relaxed);
+                            tokio::time::sleep(delay).await;
+                        }
                         let mut maybe_batch = None;
                         while !stream_exit.load(Ordering::Relaxed) {
                             if handle_chunk(
@@ -482,20 +714,26 @@ fn handle_connection(
             .lock()
             .unwrap()
             .remove_connection(&remote_addr);
+        if let Some((drop_notifier, connection)) = drop_notifier {
+            drop_notifier.remove_connection(&connection);
+        }
         stats.total_connections.fetch_sub(1, Ordering::Relaxed);
     });
 }
 
+/// Spawns a QUIC server reading packets from `sock`, reporting its metrics under `name`.
+/// Connections the server accepts are registered with `drop_notifier`, if any, while open.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_server(
+    name: &'static str,
     sock: UdpSocket,
     keypair: &Keypair,
     gossip_host: IpAddr,
     packet_sender: Sender<PacketBatch>,
     exit: Arc<AtomicBool>,
-    max_connections_per_ip: usize,
     staked_nodes: Arc<RwLock<HashMap<IpAddr, u64>>>,
-    max_staked_connections: usize,
-    max_unstaked_connections: usize,
+    qos: QuicServerQos,
+    drop_notifier: Option<Arc<QuicDropNotifier>>,
 ) -> Result<thread::JoinHandle<()>, QuicServerError> {
     let (config, _cert) = configure_server(keypair, gossip_host)?;
 
@@ -524,7 +762,10 @@ pub fn spawn_server(
                 .await;
 
                 if last_datapoint.elapsed().as_secs() >= 5 {
-                    stats.report();
+                    stats.report(name);
+                    if let Some(drop_notifier) = &drop_notifier {
+                        drop_notifier.report("quic_drop_notifier");
+                    }
                     last_datapoint = Instant::now();
                 }
 
@@ -548,14 +789,14 @@ pub fn spawn_server(
                                 let mut connection_table_l =
                                     staked_connection_table.lock().unwrap();
                                 let num_pruned =
-                                    connection_table_l.prune_oldest(max_staked_connections);
+                                    connection_table_l.prune_oldest(qos.max_staked_connections);
                                 stats.num_evictions.fetch_add(num_pruned, Ordering::Relaxed);
                                 (connection_table_l, stake)
                             } else {
                                 drop(staked_nodes);
                                 let mut connection_table_l = connection_table.lock().unwrap();
                                 let num_pruned =
-                                    connection_table_l.prune_oldest(max_unstaked_connections);
+                                    connection_table_l.prune_oldest(qos.max_unstaked_connections);
                                 stats.num_evictions.fetch_add(num_pruned, Ordering::Relaxed);
                                 (connection_table_l, 0)
                             }
@@ -565,13 +806,17 @@ pub fn spawn_server(
                             .try_add_connection(
                                 &remote_addr,
                                 timing::timestamp(),
-                                max_connections_per_ip,
+                                qos.max_connections_per_ip,
                             )
                         {
                             drop(connection_table_l);
                             let packet_sender = packet_sender.clone();
                             let stats = stats.clone();
                             let connection_table1 = connection_table.clone();
+                            let drop_notifier = drop_notifier.clone().map(|drop_notifier| {
+                                drop_notifier.add_connection(connection.clone());
+                                (drop_notifier, connection)
+                            });
                             handle_connection(
                                 uni_streams,
                                 packet_sender,
@@ -581,6 +826,8 @@ pub fn spawn_server(
                                 stream_exit,
                                 stats,
                                 stake,
+                                qos.max_streams_per_second(stake),
+                                drop_notifier,
                             );
                         } else {
                             stats.connection_add_failed.fetch_add(1, Ordering::Relaxed);
@@ -740,15 +987,21 @@ mod test {
         let server_address = s.local_addr().unwrap();
         let staked_nodes = Arc::new(RwLock::new(HashMap::new()));
         let t = spawn_server(
+            "quic_streamer_test",
             s,
             &keypair,
             ip,
             sender,
             exit.clone(),
-            2,
             staked_nodes,
-            10,
-            10,
+            QuicServerQos {
+                max_connections_per_ip: 2,
+                max_staked_connections: 10,
+                max_unstaked_connections: 10,
+                max_staked_streams_per_second: 0,
+                max_unstaked_streams_per_second: 0,
+            },
+            None,
         )
         .unwrap();
 
@@ -810,20 +1063,114 @@ mod test {
         let server_address = s.local_addr().unwrap();
         let staked_nodes = Arc::new(RwLock::new(HashMap::new()));
         let t = spawn_server(
+            "quic_streamer_test",
             s,
             &keypair,
             ip,
             sender,
             exit.clone(),
-            1,
             staked_nodes,
-            MAX_STAKED_CONNECTIONS,
-            MAX_UNSTAKED_CONNECTIONS,
+            QuicServerQos {
+                max_connections_per_ip: 1,
+                max_staked_connections: MAX_STAKED_CONNECTIONS,
+                max_unstaked_connections: MAX_UNSTAKED_CONNECTIONS,
+                max_staked_streams_per_second: 0,
+                max_unstaked_streams_per_second: 0,
+            },
+            None,
         )
         .unwrap();
         (t, exit, receiver, server_address)
     }
 
+    #[test]
+    fn test_transaction_drop_notification_bytes() {
+        let notification = TransactionDropNotification {
+            reason: TransactionDropReason::BlockhashExpired,
+            signature: Signature::new(&[7u8; SIGNATURE_BYTES]),
+        };
+        let bytes = notification.to_bytes();
+        assert_eq!(
+            TransactionDropNotification::from_bytes(&bytes),
+            Some(notification)
+        );
+        assert_eq!(TransactionDropNotification::from_bytes(&bytes[1..]), None);
+        let mut bad_reason = bytes;
+        bad_reason[1] = 0;
+        assert_eq!(TransactionDropNotification::from_bytes(&bad_reason), None);
+    }
+
+    #[test]
+    fn test_quic_drop_notifier() {
+        solana_logger::setup();
+        let s = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = unbounded();
+        let keypair = Keypair::new();
+        let ip = "127.0.0.1".parse().unwrap();
+        let server_address = s.local_addr().unwrap();
+        let staked_nodes = Arc::new(RwLock::new(HashMap::new()));
+        let drop_notifier = Arc::new(QuicDropNotifier::default());
+        let t = spawn_server(
+            "quic_streamer_test",
+            s,
+            &keypair,
+            ip,
+            sender,
+            exit.clone(),
+            staked_nodes,
+            QuicServerQos {
+                max_connections_per_ip: 1,
+                max_staked_connections: MAX_STAKED_CONNECTIONS,
+                max_unstaked_connections: MAX_UNSTAKED_CONNECTIONS,
+                max_staked_streams_per_second: 0,
+                max_unstaked_streams_per_second: 0,
+            },
+            Some(drop_notifier.clone()),
+        )
+        .unwrap();
+
+        let runtime = rt();
+        let _rt_guard = runtime.enter();
+        let NewConnection {
+            connection,
+            mut datagrams,
+            ..
+        } = make_client_endpoint(&runtime, &server_address);
+        runtime
+            .block_on(async {
+                let mut s1 = connection.open_uni().await?;
+                s1.write_all(&[0u8]).await?;
+                s1.finish().await
+            })
+            .unwrap();
+        let packets = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        let sender_address = packets[0].meta.socket_addr();
+        assert_eq!(drop_notifier.num_connections(), 1);
+
+        let notification = TransactionDropNotification {
+            reason: TransactionDropReason::Evicted,
+            signature: Signature::new(&[1u8; SIGNATURE_BYTES]),
+        };
+        assert!(drop_notifier.notify(&sender_address, notification));
+        let datagram = runtime
+            .block_on(timeout(Duration::from_secs(5), datagrams.next()))
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            TransactionDropNotification::from_bytes(&datagram),
+            Some(notification)
+        );
+
+        // Senders that are not connected are not notified
+        let unconnected_address = SocketAddr::from(([127, 0, 0, 1], 1));
+        assert!(!drop_notifier.notify(&unconnected_address, notification));
+
+        exit.store(true, Ordering::Relaxed);
+        t.join().unwrap();
+    }
+
     #[test]
     fn test_quic_server_multiple_writes() {
         solana_logger::setup();
@@ -904,4 +1251,45 @@ mod test {
         }
         assert_eq!(table.total_size, 0);
     }
+
+    #[test]
+    fn test_stream_rate_limiter() {
+        let start = Instant::now();
+        let mut unlimited = StreamRateLimiter::new(0, start);
+        assert!((0..100).all(|_| unlimited.register_stream(start).is_none()));
+
+        let mut rate_limiter = StreamRateLimiter::new(2, start);
+        assert_eq!(rate_limiter.register_stream(start), None);
+        assert_eq!(rate_limiter.register_stream(start), None);
+        // the third stream in the window waits for the next one, and is counted against it
+        let now = start + Duration::from_millis(400);
+        assert_eq!(
+            rate_limiter.register_stream(now),
+            Some(Duration::from_millis(600))
+        );
+        let now = start + Duration::from_millis(1000);
+        assert_eq!(rate_limiter.register_stream(now), None);
+        assert_eq!(
+            rate_limiter.register_stream(now),
+            Some(Duration::from_millis(1000))
+        );
+        // an idle connection starts a fresh window
+        let now = start + Duration::from_secs(10);
+        assert_eq!(rate_limiter.register_stream(now), None);
+        assert_eq!(rate_limiter.register_stream(now), None);
+        assert!(rate_limiter.register_stream(now).is_some());
+    }
+
+    #[test]
+    fn test_quic_server_qos_max_streams_per_second() {
+        let qos = QuicServerQos {
+            max_connections_per_ip: 1,
+            max_staked_connections: MAX_STAKED_CONNECTIONS,
+            max_unstaked_connections: MAX_UNSTAKED_CONNECTIONS,
+            max_staked_streams_per_second: 0,
+            max_unstaked_streams_per_second: 5,
+        };
+        assert_eq!(qos.max_streams_per_second(1), 0);
+        assert_eq!(qos.max_streams_per_second(0), 5);
+    }
 }
@@ -13,7 +13,8 @@ use {
         socketaddr,
     },
     solana_ledger::{
-        blockstore::create_new_ledger, blockstore_options::LedgerColumnOptions,
+        blockstore::{create_new_ledger, Blockstore},
+        blockstore_options::LedgerColumnOptions,
         create_new_tmp_ledger,
     },
     solana_net_utils::PortRange,
@@ -115,6 +116,7 @@ pub struct TestValidatorGenesis {
     pub accounts_db_caching_enabled: bool,
     deactivate_feature_set: HashSet<Pubkey>,
     compute_unit_limit: Option<u64>,
+    transaction_account_lock_limit: Option<usize>,
 }
 
 impl Default for TestValidatorGenesis {
@@ -143,6 +145,7 @@ impl Default for TestValidatorGenesis {
             accounts_db_caching_enabled: bool::default(),
             deactivate_feature_set: HashSet::<Pubkey>::default(),
             compute_unit_limit: Option::<u64>::default(),
+            transaction_account_lock_limit: Option::<usize>::default(),
         }
     }
 }
@@ -245,6 +248,14 @@ impl TestValidatorGenesis {
         self
     }
 
+    pub fn transaction_account_lock_limit(
+        &mut self,
+        transaction_account_lock_limit: usize,
+    ) -> &mut Self {
+        self.transaction_account_lock_limit = Some(transaction_account_lock_limit);
+        self
+    }
+
     #[deprecated(note = "Please use `compute_unit_limit` instead")]
     pub fn max_compute_units(&mut self, max_compute_units: u64) -> &mut Self {
         self.compute_unit_limit(max_compute_units)
@@ -698,6 +709,7 @@ impl TestValidator {
                     compute_unit_limit,
                     ..ComputeBudget::default()
                 }),
+            transaction_account_lock_limit: config.transaction_account_lock_limit,
         };
 
         let mut validator_config = ValidatorConfig {
@@ -882,6 +894,10 @@ impl TestValidator {
     pub fn bank_forks(&self) -> Arc<RwLock<BankForks>> {
         self.validator.as_ref().unwrap().bank_forks.clone()
     }
+
+    pub fn blockstore(&self) -> Arc<Blockstore> {
+        self.validator.as_ref().unwrap().blockstore.clone()
+    }
 }
 
 impl Drop for TestValidator {
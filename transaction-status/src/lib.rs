@@ -5,6 +5,7 @@ use {
     crate::{
         parse_accounts::{parse_accounts, parse_static_accounts, ParsedAccount},
         parse_instruction::{parse, ParsedInstruction},
+        parse_instruction_error::{parse_instruction_error, UiInstructionErrorDetail},
     },
     solana_account_decoder::parse_token::UiTokenAmount,
     solana_sdk::{
@@ -37,6 +38,7 @@ pub mod parse_accounts;
 pub mod parse_associated_token;
 pub mod parse_bpf_loader;
 pub mod parse_instruction;
+pub mod parse_instruction_error;
 pub mod parse_stake;
 pub mod parse_system;
 pub mod parse_token;
@@ -112,6 +114,7 @@ impl fmt::Display for UiTransactionEncoding {
 pub enum TransactionDetails {
     Full,
     Signatures,
+    Balances,
     None,
 }
 
@@ -325,6 +328,9 @@ pub struct UiTransactionStatusMeta {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub loaded_addresses: Option<UiLoadedAddresses>,
     pub return_data: Option<TransactionReturnData>,
+    /// Set on request when the transaction failed in one of its instructions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instruction_error_detail: Option<UiInstructionErrorDetail>,
 }
 
 /// A duplicate representation of LoadedAddresses
@@ -376,6 +382,7 @@ impl UiTransactionStatusMeta {
             rewards: meta.rewards,
             loaded_addresses: Some(UiLoadedAddresses::from(&meta.loaded_addresses)),
             return_data: meta.return_data,
+            instruction_error_detail: None,
         }
     }
 }
@@ -401,6 +408,7 @@ impl From<TransactionStatusMeta> for UiTransactionStatusMeta {
             rewards: meta.rewards,
             loaded_addresses: Some(UiLoadedAddresses::from(&meta.loaded_addresses)),
             return_data: meta.return_data,
+            instruction_error_detail: None,
         }
     }
 }
@@ -527,7 +535,7 @@ impl ConfirmedBlock {
         encoding: UiTransactionEncoding,
         options: BlockEncodingOptions,
     ) -> Result<UiConfirmedBlock, EncodeError> {
-        let (transactions, signatures) = match options.transaction_details {
+        let (transactions, signatures, balances) = match options.transaction_details {
             TransactionDetails::Full => (
                 Some(
                     self.transactions
@@ -538,6 +546,7 @@ impl ConfirmedBlock {
                         .collect::<Result<Vec<_>, _>>()?,
                 ),
                 None,
+                None,
             ),
             TransactionDetails::Signatures => (
                 None,
@@ -547,8 +556,21 @@ impl ConfirmedBlock {
                         .map(|tx_with_meta| tx_with_meta.transaction_signature().to_string())
                         .collect(),
                 ),
+                None,
+            ),
+            TransactionDetails::Balances => (
+                None,
+                None,
+                Some(
+                    self.transactions
+                        .into_iter()
+                        .map(|tx_with_meta| {
+                            tx_with_meta.encode_balances(options.max_supported_transaction_version)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
             ),
-            TransactionDetails::None => (None, None),
+            TransactionDetails::None => (None, None, None),
         };
         Ok(UiConfirmedBlock {
             previous_blockhash: self.previous_blockhash,
@@ -556,6 +578,7 @@ impl ConfirmedBlock {
             parent_slot: self.parent_slot,
             transactions,
             signatures,
+            balances,
             rewards: if options.show_rewards {
                 Some(self.rewards)
             } else {
@@ -604,6 +627,8 @@ pub struct UiConfirmedBlock {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub signatures: Option<Vec<String>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balances: Option<Vec<UiTransactionBalances>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub rewards: Option<Rewards>,
     pub block_time: Option<UnixTimestamp>,
     pub block_height: Option<u64>,
@@ -665,12 +690,47 @@ impl TransactionWithStatusMeta {
         }
     }
 
+    /// Encodes only the balance changes of the transaction, skipping its message and logs
+    pub fn encode_balances(
+        self,
+        max_supported_transaction_version: Option<u8>,
+    ) -> Result<UiTransactionBalances, EncodeError> {
+        match self {
+            Self::MissingMetadata(ref transaction) => Ok(UiTransactionBalances {
+                signature: transaction.signatures[0].to_string(),
+                err: None,
+                fee: 0,
+                account_keys: transaction
+                    .message
+                    .account_keys
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                pre_balances: vec![],
+                post_balances: vec![],
+                pre_token_balances: None,
+                post_token_balances: None,
+                version: None,
+            }),
+            Self::Complete(tx_with_meta) => {
+                tx_with_meta.encode_balances(max_supported_transaction_version)
+            }
+        }
+    }
+
     pub fn account_keys(&self) -> AccountKeys {
         match self {
             Self::MissingMetadata(tx) => AccountKeys::new(&tx.message.account_keys, None),
             Self::Complete(tx_with_meta) => tx_with_meta.account_keys(),
         }
     }
+
+    pub fn instruction_error_detail(&self) -> Option<UiInstructionErrorDetail> {
+        match self {
+            Self::MissingMetadata(_) => None,
+            Self::Complete(tx_with_meta) => tx_with_meta.instruction_error_detail(),
+        }
+    }
 }
 
 impl VersionedTransactionWithStatusMeta {
@@ -679,7 +739,56 @@ impl VersionedTransactionWithStatusMeta {
         encoding: UiTransactionEncoding,
         max_supported_transaction_version: Option<u8>,
     ) -> Result<EncodedTransactionWithStatusMeta, EncodeError> {
-        let version = match (
+        let version = self.validate_version(max_supported_transaction_version)?;
+
+        Ok(EncodedTransactionWithStatusMeta {
+            transaction: self.transaction.encode_with_meta(encoding, &self.meta),
+            meta: Some(match encoding {
+                UiTransactionEncoding::JsonParsed => UiTransactionStatusMeta::parse(
+                    self.meta,
+                    self.transaction.message.static_account_keys(),
+                ),
+                _ => UiTransactionStatusMeta::from(self.meta),
+            }),
+            version,
+        })
+    }
+
+    /// Encodes only the balance changes of the transaction, skipping its message and logs
+    pub fn encode_balances(
+        self,
+        max_supported_transaction_version: Option<u8>,
+    ) -> Result<UiTransactionBalances, EncodeError> {
+        let version = self.validate_version(max_supported_transaction_version)?;
+        let account_keys = self
+            .account_keys()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        Ok(UiTransactionBalances {
+            signature: self.transaction.signatures[0].to_string(),
+            err: self.meta.status.err(),
+            fee: self.meta.fee,
+            account_keys,
+            pre_balances: self.meta.pre_balances,
+            post_balances: self.meta.post_balances,
+            pre_token_balances: self
+                .meta
+                .pre_token_balances
+                .map(|balance| balance.into_iter().map(Into::into).collect()),
+            post_token_balances: self
+                .meta
+                .post_token_balances
+                .map(|balance| balance.into_iter().map(Into::into).collect()),
+            version,
+        })
+    }
+
+    fn validate_version(
+        &self,
+        max_supported_transaction_version: Option<u8>,
+    ) -> Result<Option<TransactionVersion>, EncodeError> {
+        match (
             max_supported_transaction_version,
             self.transaction.version(),
         ) {
@@ -696,19 +805,7 @@ impl VersionedTransactionWithStatusMeta {
                     Err(EncodeError::UnsupportedTransactionVersion(version))
                 }
             }
-        }?;
-
-        Ok(EncodedTransactionWithStatusMeta {
-            transaction: self.transaction.encode_with_meta(encoding, &self.meta),
-            meta: Some(match encoding {
-                UiTransactionEncoding::JsonParsed => UiTransactionStatusMeta::parse(
-                    self.meta,
-                    self.transaction.message.static_account_keys(),
-                ),
-                _ => UiTransactionStatusMeta::from(self.meta),
-            }),
-            version,
-        })
+        }
     }
 
     pub fn account_keys(&self) -> AccountKeys {
@@ -717,6 +814,68 @@ impl VersionedTransactionWithStatusMeta {
             Some(&self.meta.loaded_addresses),
         )
     }
+
+    /// Returns the instruction that failed the transaction, with its error decoded if its program
+    /// is known, or None if the transaction did not fail in an instruction
+    pub fn instruction_error_detail(&self) -> Option<UiInstructionErrorDetail> {
+        let (instruction_index, error) = match &self.meta.status {
+            Err(TransactionError::InstructionError(instruction_index, error)) => {
+                (*instruction_index, error)
+            }
+            _ => return None,
+        };
+        let account_keys = self.account_keys();
+        let program_id = self
+            .transaction
+            .message
+            .instructions()
+            .get(usize::from(instruction_index))
+            .and_then(|instruction| account_keys.get(usize::from(instruction.program_id_index)))?;
+        Some(parse_instruction_error(
+            instruction_index,
+            program_id,
+            error,
+        ))
+    }
+
+    /// Returns true if any instruction of the transaction invokes `program_id`, including the
+    /// inner instructions recorded in its metadata
+    pub fn invokes_program(&self, program_id: &Pubkey) -> bool {
+        let account_keys = self.account_keys();
+        let is_invoked = |instruction: &CompiledInstruction| {
+            account_keys.get(usize::from(instruction.program_id_index)) == Some(program_id)
+        };
+        self.transaction
+            .message
+            .instructions()
+            .iter()
+            .any(is_invoked)
+            || self
+                .meta
+                .inner_instructions
+                .iter()
+                .flatten()
+                .flat_map(|inner_instructions| &inner_instructions.instructions)
+                .any(is_invoked)
+    }
+}
+
+/// Balance changes of a transaction, for clients that do not need its message or logs
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransactionBalances {
+    pub signature: String,
+    pub err: Option<TransactionError>,
+    pub fee: u64,
+    /// Static account keys followed by the keys loaded from address lookup tables, in the order
+    /// of `pre_balances` and `post_balances`
+    pub account_keys: Vec<String>,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub pre_token_balances: Option<Vec<UiTransactionTokenBalance>>,
+    pub post_token_balances: Option<Vec<UiTransactionTokenBalance>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<TransactionVersion>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -1011,7 +1170,13 @@ pub struct TransactionByAddrInfo {
 
 #[cfg(test)]
 mod test {
-    use super::*;
+    use {
+        super::*,
+        solana_sdk::{
+            hash::Hash, instruction::InstructionError, system_instruction::SystemError,
+            system_program,
+        },
+    };
 
     #[test]
     fn test_decode_invalid_transaction() {
@@ -1027,6 +1192,98 @@ mod test {
         assert!(unsanitary_transaction.decode().is_none());
     }
 
+    #[test]
+    fn test_invokes_program() {
+        let (payer, outer_program, inner_program, other_program) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let message = Message::new_with_compiled_instructions(
+            1,
+            0,
+            3,
+            vec![payer, outer_program, inner_program, other_program],
+            Hash::default(),
+            vec![CompiledInstruction::new(1, &(), vec![0])],
+        );
+        let mut transaction_with_meta = VersionedTransactionWithStatusMeta {
+            transaction: VersionedTransaction {
+                signatures: vec![Signature::default()],
+                message: VersionedMessage::Legacy(message),
+            },
+            meta: TransactionStatusMeta::default(),
+        };
+        assert!(transaction_with_meta.invokes_program(&outer_program));
+        assert!(!transaction_with_meta.invokes_program(&inner_program));
+        assert!(!transaction_with_meta.invokes_program(&payer));
+
+        transaction_with_meta.meta.inner_instructions = Some(vec![InnerInstructions {
+            index: 0,
+            instructions: vec![CompiledInstruction::new(2, &(), vec![0])],
+        }]);
+        assert!(transaction_with_meta.invokes_program(&inner_program));
+        assert!(!transaction_with_meta.invokes_program(&other_program));
+    }
+
+    #[test]
+    fn test_instruction_error_detail() {
+        let (payer, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let message = Message::new_with_compiled_instructions(
+            1,
+            0,
+            2,
+            vec![payer, program, system_program::id()],
+            Hash::default(),
+            vec![
+                CompiledInstruction::new(1, &(), vec![0]),
+                CompiledInstruction::new(2, &(), vec![0]),
+            ],
+        );
+        let mut transaction_with_meta = VersionedTransactionWithStatusMeta {
+            transaction: VersionedTransaction {
+                signatures: vec![Signature::default()],
+                message: VersionedMessage::Legacy(message),
+            },
+            meta: TransactionStatusMeta::default(),
+        };
+        assert_eq!(transaction_with_meta.instruction_error_detail(), None);
+
+        transaction_with_meta.meta.status = Err(TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(SystemError::AccountAlreadyInUse as u32),
+        ));
+        assert_eq!(
+            transaction_with_meta.instruction_error_detail(),
+            Some(UiInstructionErrorDetail {
+                instruction_index: 1,
+                program_id: system_program::id().to_string(),
+                program: Some("system".to_string()),
+                error: InstructionError::Custom(0),
+                message: Some(SystemError::AccountAlreadyInUse.to_string()),
+            })
+        );
+
+        transaction_with_meta.meta.status = Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(42),
+        ));
+        assert_eq!(
+            transaction_with_meta.instruction_error_detail(),
+            Some(UiInstructionErrorDetail {
+                instruction_index: 0,
+                program_id: program.to_string(),
+                program: None,
+                error: InstructionError::Custom(42),
+                message: None,
+            })
+        );
+
+        transaction_with_meta.meta.status = Err(TransactionError::AccountInUse);
+        assert_eq!(transaction_with_meta.instruction_error_detail(), None);
+    }
+
     #[test]
     fn test_satisfies_commitment() {
         let status = TransactionStatus {
@@ -1105,4 +1362,65 @@ mod test {
         };
         assert!(status.satisfies_commitment(CommitmentConfig::confirmed()));
     }
+
+    #[test]
+    fn test_encode_block_balances() {
+        let payer = Pubkey::new_unique();
+        let loaded = Pubkey::new_unique();
+        let transaction = Transaction::new_with_payer(&[], Some(&payer));
+        let signature = transaction.signatures[0];
+        let block = ConfirmedBlock {
+            previous_blockhash: Hash::default().to_string(),
+            blockhash: Hash::default().to_string(),
+            parent_slot: 0,
+            transactions: vec![TransactionWithStatusMeta::Complete(
+                VersionedTransactionWithStatusMeta {
+                    transaction: VersionedTransaction::from(transaction),
+                    meta: TransactionStatusMeta {
+                        fee: 5_000,
+                        pre_balances: vec![10_000, 1],
+                        post_balances: vec![5_000, 1],
+                        pre_token_balances: Some(vec![]),
+                        post_token_balances: Some(vec![]),
+                        log_messages: Some(vec!["log".to_string()]),
+                        loaded_addresses: LoadedAddresses {
+                            writable: vec![],
+                            readonly: vec![loaded],
+                        },
+                        ..TransactionStatusMeta::default()
+                    },
+                },
+            )],
+            rewards: vec![],
+            block_time: None,
+            block_height: None,
+        };
+
+        let ui_block = block
+            .encode_with_options(
+                UiTransactionEncoding::Json,
+                BlockEncodingOptions {
+                    transaction_details: TransactionDetails::Balances,
+                    show_rewards: false,
+                    max_supported_transaction_version: None,
+                },
+            )
+            .unwrap();
+        assert!(ui_block.transactions.is_none());
+        assert!(ui_block.signatures.is_none());
+        assert_eq!(
+            ui_block.balances,
+            Some(vec![UiTransactionBalances {
+                signature: signature.to_string(),
+                err: None,
+                fee: 5_000,
+                account_keys: vec![payer.to_string(), loaded.to_string()],
+                pre_balances: vec![10_000, 1],
+                post_balances: vec![5_000, 1],
+                pre_token_balances: Some(vec![]),
+                post_token_balances: Some(vec![]),
+                version: None,
+            }])
+        );
+    }
 }
@@ -31,7 +31,7 @@ lazy_static! {
     static ref STAKE_PROGRAM_ID: Pubkey = stake::program::id();
     static ref SYSTEM_PROGRAM_ID: Pubkey = system_program::id();
     static ref VOTE_PROGRAM_ID: Pubkey = solana_vote_program::id();
-    static ref PARSABLE_PROGRAM_IDS: HashMap<Pubkey, ParsableProgram> = {
+    pub(crate) static ref PARSABLE_PROGRAM_IDS: HashMap<Pubkey, ParsableProgram> = {
         let mut m = HashMap::new();
         m.insert(
             *ASSOCIATED_TOKEN_PROGRAM_ID,
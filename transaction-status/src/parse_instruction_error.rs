@@ -0,0 +1,124 @@
+use {
+    crate::parse_instruction::{ParsableProgram, PARSABLE_PROGRAM_IDS},
+    inflector::Inflector,
+    solana_sdk::{
+        decode_error::DecodeError, instruction::InstructionError, pubkey::Pubkey,
+        stake::instruction::StakeError, system_instruction::SystemError,
+    },
+    solana_vote_program::vote_error::VoteError,
+    spl_token_2022::error::TokenError,
+};
+
+/// The instruction that failed a transaction, and its error decoded for known programs
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiInstructionErrorDetail {
+    pub instruction_index: u8,
+    /// Program invoked by the failed instruction. The error may have been returned by a program
+    /// that it invoked in turn.
+    pub program_id: String,
+    /// Name of the program, if it is a known program
+    pub program: Option<String>,
+    pub error: InstructionError,
+    /// Human-readable description of the error, if it could be decoded
+    pub message: Option<String>,
+}
+
+pub fn parse_instruction_error(
+    instruction_index: u8,
+    program_id: &Pubkey,
+    error: &InstructionError,
+) -> UiInstructionErrorDetail {
+    let program = PARSABLE_PROGRAM_IDS.get(program_id);
+    let message = match error {
+        InstructionError::Custom(code) => {
+            program.and_then(|program| parse_custom_error(program, *code))
+        }
+        error => Some(error.to_string()),
+    };
+    UiInstructionErrorDetail {
+        instruction_index,
+        program_id: program_id.to_string(),
+        program: program.map(|program| format!("{:?}", program).to_kebab_case()),
+        error: error.clone(),
+        message,
+    }
+}
+
+fn parse_custom_error(program: &ParsableProgram, code: u32) -> Option<String> {
+    match program {
+        ParsableProgram::SplToken => {
+            <TokenError as DecodeError<TokenError>>::decode_custom_error_to_enum(code)
+                .map(|error| error.to_string())
+        }
+        ParsableProgram::Stake => {
+            <StakeError as DecodeError<StakeError>>::decode_custom_error_to_enum(code)
+                .map(|error| error.to_string())
+        }
+        ParsableProgram::System => {
+            <SystemError as DecodeError<SystemError>>::decode_custom_error_to_enum(code)
+                .map(|error| error.to_string())
+        }
+        ParsableProgram::Vote => {
+            <VoteError as DecodeError<VoteError>>::decode_custom_error_to_enum(code)
+                .map(|error| error.to_string())
+        }
+        ParsableProgram::SplAssociatedTokenAccount
+        | ParsableProgram::SplMemo
+        | ParsableProgram::BpfLoader
+        | ParsableProgram::BpfUpgradeableLoader => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*, solana_account_decoder::parse_token::spl_token_2022_id,
+        solana_sdk::system_program,
+    };
+
+    #[test]
+    fn test_parse_instruction_error() {
+        assert_eq!(
+            parse_instruction_error(
+                1,
+                &system_program::id(),
+                &InstructionError::Custom(SystemError::ResultWithNegativeLamports as u32),
+            ),
+            UiInstructionErrorDetail {
+                instruction_index: 1,
+                program_id: system_program::id().to_string(),
+                program: Some("system".to_string()),
+                error: InstructionError::Custom(1),
+                message: Some(SystemError::ResultWithNegativeLamports.to_string()),
+            }
+        );
+
+        assert_eq!(
+            parse_instruction_error(
+                0,
+                &spl_token_2022_id(),
+                &InstructionError::Custom(TokenError::InsufficientFunds as u32),
+            )
+            .message,
+            Some(TokenError::InsufficientFunds.to_string())
+        );
+
+        // Codes that the program does not define are not decoded
+        assert_eq!(
+            parse_instruction_error(0, &system_program::id(), &InstructionError::Custom(1_000))
+                .message,
+            None
+        );
+
+        // Custom errors of unknown programs are not decoded, but other errors are
+        let program_id = Pubkey::new_unique();
+        let detail = parse_instruction_error(2, &program_id, &InstructionError::Custom(0));
+        assert_eq!(detail.program, None);
+        assert_eq!(detail.message, None);
+        assert_eq!(
+            parse_instruction_error(2, &program_id, &InstructionError::InsufficientFunds).message,
+            Some(InstructionError::InsufficientFunds.to_string())
+        );
+    }
+}
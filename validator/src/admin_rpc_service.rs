@@ -7,11 +7,19 @@ use {
     log::*,
     serde::{Deserialize, Serialize},
     solana_core::{
-        consensus::Tower, tower_storage::TowerStorage, validator::ValidatorStartProgress,
+        consensus::Tower,
+        tower_storage::TowerStorage,
+        validator::{PohSpeedCheckReport, ValidatorStartProgress},
     },
     solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfo},
-    solana_runtime::bank_forks::BankForks,
+    solana_ledger::blockstore::{Blockstore, BlockstoreError},
+    solana_rpc::rpc_auth::{RpcAccessControl, RpcMethodAllowlist},
+    solana_runtime::{
+        accounts_db::AccountIndexBackfill, accounts_index::AccountIndex, bank::Bank,
+        bank_forks::BankForks,
+    },
     solana_sdk::{
+        clock::Slot,
         exit::Exit,
         pubkey::Pubkey,
         signature::{read_keypair_file, Keypair, Signer},
@@ -30,7 +38,9 @@ use {
 pub struct AdminRpcRequestMetadataPostInit {
     pub cluster_info: Arc<ClusterInfo>,
     pub bank_forks: Arc<RwLock<BankForks>>,
+    pub blockstore: Arc<Blockstore>,
     pub vote_account: Pubkey,
+    pub poh_speed_check_report: Option<PohSpeedCheckReport>,
 }
 
 #[derive(Clone)]
@@ -42,6 +52,7 @@ pub struct AdminRpcRequestMetadata {
     pub authorized_voter_keypairs: Arc<RwLock<Vec<Arc<Keypair>>>>,
     pub tower_storage: Arc<dyn TowerStorage>,
     pub post_init: Arc<RwLock<Option<AdminRpcRequestMetadataPostInit>>>,
+    pub rpc_access_control: Option<Arc<RpcAccessControl>>,
 }
 impl Metadata for AdminRpcRequestMetadata {}
 
@@ -58,6 +69,14 @@ impl AdminRpcRequestMetadata {
             ))
         }
     }
+
+    fn rpc_access_control(&self) -> Result<&RpcAccessControl> {
+        self.rpc_access_control.as_deref().ok_or_else(|| {
+            jsonrpc_core::error::Error::invalid_params(
+                "RPC authentication is not enabled, restart with --rpc-require-auth",
+            )
+        })
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -165,6 +184,168 @@ pub trait AdminRpc {
 
     #[rpc(meta, name = "contactInfo")]
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo>;
+
+    #[rpc(meta, name = "pohSpeedCheckReport")]
+    fn poh_speed_check_report(&self, meta: Self::Metadata) -> Result<Option<PohSpeedCheckReport>>;
+
+    #[rpc(meta, name = "writeLockHeatMap")]
+    fn write_lock_heat_map(
+        &self,
+        meta: Self::Metadata,
+        slot: Option<Slot>,
+        max_accounts: Option<usize>,
+    ) -> Result<AdminRpcWriteLockHeatMap>;
+
+    #[rpc(meta, name = "compactRange")]
+    fn compact_range(
+        &self,
+        meta: Self::Metadata,
+        column: String,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<()>;
+
+    #[rpc(meta, name = "enableAccountIndex")]
+    fn enable_account_index(&self, meta: Self::Metadata, index: String) -> Result<()>;
+
+    #[rpc(meta, name = "setRocksPerfSampleInterval")]
+    fn set_rocks_perf_sample_interval(
+        &self,
+        meta: Self::Metadata,
+        sample_interval: usize,
+    ) -> Result<()>;
+
+    #[rpc(meta, name = "createBlockstoreCheckpoint")]
+    fn create_blockstore_checkpoint(&self, meta: Self::Metadata, path: String) -> Result<()>;
+
+    #[rpc(meta, name = "accountIndexBackfills")]
+    fn account_index_backfills(
+        &self,
+        meta: Self::Metadata,
+    ) -> Result<Vec<AdminRpcAccountIndexBackfill>>;
+
+    #[rpc(meta, name = "addRpcToken")]
+    fn add_rpc_token(
+        &self,
+        meta: Self::Metadata,
+        token: String,
+        methods: Option<Vec<String>>,
+    ) -> Result<()>;
+
+    #[rpc(meta, name = "removeRpcToken")]
+    fn remove_rpc_token(&self, meta: Self::Metadata, token: String) -> Result<()>;
+}
+
+/// Number of accounts returned by `writeLockHeatMap` if the request doesn't set a limit
+pub const DEFAULT_WRITE_LOCK_HEAT_MAP_SIZE: usize = 20;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcAccountWriteLocks {
+    pub pubkey: String,
+    pub write_locks: u64,
+    pub conflicts: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcWriteLockHeatMap {
+    pub slot: Slot,
+    pub accounts: Vec<AdminRpcAccountWriteLocks>,
+}
+
+impl AdminRpcWriteLockHeatMap {
+    fn new(bank: &Bank, max_accounts: usize) -> Self {
+        Self {
+            slot: bank.slot(),
+            accounts: bank
+                .write_lock_heat_map(max_accounts)
+                .into_iter()
+                .map(|(pubkey, counts)| AdminRpcAccountWriteLocks {
+                    pubkey: pubkey.to_string(),
+                    write_locks: counts.write_locks,
+                    conflicts: counts.conflicts,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Display for AdminRpcWriteLockHeatMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Slot: {}", self.slot)?;
+        writeln!(
+            f,
+            "{:<44}  {:>11}  {:>9}",
+            "Account", "Write Locks", "Conflicts"
+        )?;
+        for account in &self.accounts {
+            writeln!(
+                f,
+                "{:<44}  {:>11}  {:>9}",
+                account.pubkey, account.write_locks, account.conflicts
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses an account index name as accepted by `--account-index`
+pub fn parse_account_index(name: &str) -> Option<AccountIndex> {
+    match name {
+        "program-id" => Some(AccountIndex::ProgramId),
+        "spl-token-mint" => Some(AccountIndex::SplTokenMint),
+        "spl-token-owner" => Some(AccountIndex::SplTokenOwner),
+        _ => None,
+    }
+}
+
+fn account_index_name(index: &AccountIndex) -> &'static str {
+    match index {
+        AccountIndex::ProgramId => "program-id",
+        AccountIndex::SplTokenMint => "spl-token-mint",
+        AccountIndex::SplTokenOwner => "spl-token-owner",
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcAccountIndexBackfill {
+    pub index: String,
+    pub bins_scanned: usize,
+    pub num_bins: usize,
+    pub accounts_scanned: u64,
+    pub elapsed_ms: u64,
+    pub complete: bool,
+}
+
+impl From<&AccountIndexBackfill> for AdminRpcAccountIndexBackfill {
+    fn from(backfill: &AccountIndexBackfill) -> Self {
+        Self {
+            index: account_index_name(backfill.index()).to_string(),
+            bins_scanned: backfill.bins_scanned(),
+            num_bins: backfill.num_bins(),
+            accounts_scanned: backfill.accounts_scanned(),
+            elapsed_ms: backfill.elapsed().as_millis() as u64,
+            complete: backfill.is_complete(),
+        }
+    }
+}
+
+impl Display for AdminRpcAccountIndexBackfill {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({}/{} bins, {} accounts, {}ms)",
+            self.index,
+            if self.complete {
+                "complete"
+            } else {
+                "backfilling"
+            },
+            self.bins_scanned,
+            self.num_bins,
+            self.accounts_scanned,
+            self.elapsed_ms
+        )
+    }
 }
 
 pub struct AdminRpcImpl;
@@ -280,6 +461,169 @@ impl AdminRpc for AdminRpcImpl {
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo> {
         meta.with_post_init(|post_init| Ok(post_init.cluster_info.my_contact_info().into()))
     }
+
+    fn poh_speed_check_report(&self, meta: Self::Metadata) -> Result<Option<PohSpeedCheckReport>> {
+        debug!("poh_speed_check_report admin rpc request received");
+        meta.with_post_init(|post_init| Ok(post_init.poh_speed_check_report.clone()))
+    }
+
+    fn write_lock_heat_map(
+        &self,
+        meta: Self::Metadata,
+        slot: Option<Slot>,
+        max_accounts: Option<usize>,
+    ) -> Result<AdminRpcWriteLockHeatMap> {
+        debug!("write_lock_heat_map admin rpc request received");
+        meta.with_post_init(|post_init| {
+            let bank = {
+                let bank_forks = post_init.bank_forks.read().unwrap();
+                match slot {
+                    Some(slot) => bank_forks.get(slot).ok_or_else(|| {
+                        jsonrpc_core::error::Error::invalid_params(format!(
+                            "Slot {} is not in bank forks",
+                            slot
+                        ))
+                    })?,
+                    None => bank_forks.working_bank(),
+                }
+            };
+            Ok(AdminRpcWriteLockHeatMap::new(
+                &bank,
+                max_accounts.unwrap_or(DEFAULT_WRITE_LOCK_HEAT_MAP_SIZE),
+            ))
+        })
+    }
+
+    fn compact_range(
+        &self,
+        meta: Self::Metadata,
+        column: String,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<()> {
+        debug!("compact_range admin rpc request received");
+        if start_slot > end_slot {
+            return Err(jsonrpc_core::error::Error::invalid_params(format!(
+                "Start slot {} is greater than end slot {}",
+                start_slot, end_slot
+            )));
+        }
+        meta.with_post_init(|post_init| {
+            post_init
+                .blockstore
+                .compact_range(&column, start_slot, end_slot)
+                .map_err(|err| match err {
+                    BlockstoreError::UnsupportedColumn(column) => {
+                        jsonrpc_core::error::Error::invalid_params(format!(
+                            "Column {} is not keyed by slot",
+                            column
+                        ))
+                    }
+                    err => {
+                        error!("Failed to compact {}: {:?}", column, err);
+                        jsonrpc_core::error::Error::internal_error()
+                    }
+                })
+        })
+    }
+
+    fn set_rocks_perf_sample_interval(
+        &self,
+        meta: Self::Metadata,
+        sample_interval: usize,
+    ) -> Result<()> {
+        debug!("set_rocks_perf_sample_interval admin rpc request received");
+        meta.with_post_init(|post_init| {
+            post_init
+                .blockstore
+                .set_rocks_perf_sample_interval(sample_interval);
+            Ok(())
+        })
+    }
+
+    fn create_blockstore_checkpoint(&self, meta: Self::Metadata, path: String) -> Result<()> {
+        debug!("create_blockstore_checkpoint admin rpc request received");
+        meta.with_post_init(|post_init| {
+            post_init
+                .blockstore
+                .create_checkpoint(Path::new(&path))
+                .map_err(|err| match err {
+                    BlockstoreError::Io(err) => jsonrpc_core::error::Error::invalid_params(
+                        format!("Failed to create a checkpoint in {}: {}", path, err),
+                    ),
+                    err => {
+                        error!("Failed to create a checkpoint in {}: {:?}", path, err);
+                        jsonrpc_core::error::Error::internal_error()
+                    }
+                })
+        })
+    }
+
+    fn enable_account_index(&self, meta: Self::Metadata, index: String) -> Result<()> {
+        debug!("enable_account_index admin rpc request received");
+        let account_index = parse_account_index(&index).ok_or_else(|| {
+            jsonrpc_core::error::Error::invalid_params(format!("Unknown account index {}", index))
+        })?;
+        meta.with_post_init(|post_init| {
+            let bank = post_init.bank_forks.read().unwrap().root_bank();
+            bank.enable_account_index(account_index)
+                .map(|_| warn!("Account index {} enabled, backfilling", index))
+                .ok_or_else(|| {
+                    jsonrpc_core::error::Error::invalid_params(format!(
+                        "Account index {} is already enabled",
+                        index
+                    ))
+                })
+        })
+    }
+
+    fn account_index_backfills(
+        &self,
+        meta: Self::Metadata,
+    ) -> Result<Vec<AdminRpcAccountIndexBackfill>> {
+        debug!("account_index_backfills admin rpc request received");
+        meta.with_post_init(|post_init| {
+            let bank = post_init.bank_forks.read().unwrap().root_bank();
+            Ok(bank
+                .account_index_backfills()
+                .iter()
+                .map(|backfill| backfill.as_ref().into())
+                .collect())
+        })
+    }
+
+    fn add_rpc_token(
+        &self,
+        meta: Self::Metadata,
+        token: String,
+        methods: Option<Vec<String>>,
+    ) -> Result<()> {
+        debug!("add_rpc_token admin rpc request received");
+        if token.is_empty() || token.contains(char::is_whitespace) {
+            return Err(jsonrpc_core::error::Error::invalid_params(
+                "RPC tokens must be non-empty and may not contain whitespace",
+            ));
+        }
+        let allowlist = match methods {
+            Some(methods) => RpcMethodAllowlist::Methods(methods.into_iter().collect()),
+            None => RpcMethodAllowlist::All,
+        };
+        if !meta.rpc_access_control()?.add_token(token, allowlist) {
+            warn!("Replaced the allowed methods of an existing RPC token");
+        }
+        Ok(())
+    }
+
+    fn remove_rpc_token(&self, meta: Self::Metadata, token: String) -> Result<()> {
+        debug!("remove_rpc_token admin rpc request received");
+        if meta.rpc_access_control()?.remove_token(&token) {
+            Ok(())
+        } else {
+            Err(jsonrpc_core::error::Error::invalid_params(
+                "Unknown RPC token",
+            ))
+        }
+    }
 }
 
 // Start the Admin RPC interface
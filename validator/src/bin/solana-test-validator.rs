@@ -377,6 +377,14 @@ fn main() {
                 .takes_value(true)
                 .help("Override the runtime's compute unit limit per transaction")
         )
+        .arg(
+            Arg::with_name("transaction_account_lock_limit")
+                .long("transaction-account-lock-limit")
+                .value_name("NUM_ACCOUNTS")
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .help("Override the runtime's account lock limit per transaction")
+        )
         .get_matches();
 
     let output = if matches.is_present("quiet") {
@@ -486,6 +494,8 @@ fn main() {
         })
     });
     let compute_unit_limit = value_t!(matches, "compute_unit_limit", u64).ok();
+    let transaction_account_lock_limit =
+        value_t!(matches, "transaction_account_lock_limit", usize).ok();
 
     let faucet_addr = Some(SocketAddr::new(
         IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
@@ -651,6 +661,7 @@ fn main() {
             authorized_voter_keypairs: genesis.authorized_voter_keypairs.clone(),
             post_init: admin_service_post_init.clone(),
             tower_storage: tower_storage.clone(),
+            rpc_access_control: None,
         },
     );
     let dashboard = if output == Output::Dashboard {
@@ -767,13 +778,19 @@ fn main() {
         genesis.compute_unit_limit(compute_unit_limit);
     }
 
+    if let Some(transaction_account_lock_limit) = transaction_account_lock_limit {
+        genesis.transaction_account_lock_limit(transaction_account_lock_limit);
+    }
+
     match genesis.start_with_mint_address(mint_address, socket_addr_space) {
         Ok(test_validator) => {
             *admin_service_post_init.write().unwrap() =
                 Some(admin_rpc_service::AdminRpcRequestMetadataPostInit {
                     bank_forks: test_validator.bank_forks(),
+                    blockstore: test_validator.blockstore(),
                     cluster_info: test_validator.cluster_info(),
                     vote_account: test_validator.vote_account_address(),
+                    poh_speed_check_report: None,
                 });
             if let Some(dashboard) = dashboard {
                 dashboard.run(Duration::from_millis(250));
@@ -8,12 +8,12 @@ use {
     },
     console::style,
     log::*,
-    rand::{seq::SliceRandom, thread_rng},
+    rand::{distributions::Alphanumeric, seq::SliceRandom, thread_rng, Rng},
     solana_clap_utils::{
-        input_parsers::{keypair_of, keypairs_of, pubkey_of, value_of},
+        input_parsers::{commitment_of, keypair_of, keypairs_of, pubkey_of, value_of},
         input_validators::{
             is_keypair, is_keypair_or_ask_keyword, is_niceness_adjustment_valid, is_parsable,
-            is_pow2, is_pubkey, is_pubkey_or_keypair, is_slot, is_valid_percentage,
+            is_pow2, is_pubkey, is_pubkey_or_keypair, is_slot, is_url, is_valid_percentage,
             is_within_range,
         },
         keypair::SKIP_SEED_PHRASE_VALIDATION_ARG,
@@ -23,25 +23,36 @@ use {
         rpc_request::MAX_MULTIPLE_ACCOUNTS,
     },
     solana_core::{
-        ledger_cleanup_service::{DEFAULT_MAX_LEDGER_SHREDS, DEFAULT_MIN_MAX_LEDGER_SHREDS},
+        accounts_hash_verifier::AccountsHashVerificationPolicy,
+        broadcast_stage::BroadcastConfig,
+        forward_pacer::ForwardPacingConfig,
+        ledger_cleanup_service::{
+            SlotPurgeListeners, DEFAULT_MAX_LEDGER_SHREDS, DEFAULT_MIN_MAX_LEDGER_BYTES,
+            DEFAULT_MIN_MAX_LEDGER_SHREDS, DEFAULT_SLOT_PURGE_GRACE_WINDOW,
+        },
+        serve_repair_admission::ServeRepairAdmissionConfig,
         system_monitor_service::SystemMonitorService,
         tower_storage,
         tpu::DEFAULT_TPU_COALESCE_MS,
+        unprocessed_packet_batches::{PopPolicy, PopPolicyConfig, PriorityAgingConfig},
         validator::{is_snapshot_config_valid, Validator, ValidatorConfig, ValidatorStartProgress},
     },
     solana_gossip::{cluster_info::Node, contact_info::ContactInfo},
     solana_ledger::{
-        blockstore_db::DEFAULT_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES,
+        blockstore_db::{Database, DEFAULT_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES},
         blockstore_options::{
-            BlockstoreCompressionType, BlockstoreRecoveryMode, BlockstoreRocksFifoOptions,
-            LedgerColumnOptions, ShredStorageType,
+            BlockstoreColumnFilterOptions, BlockstoreCompressionType, BlockstoreIoOptions,
+            BlockstorePrefixExtractor, BlockstoreRecoveryMode, BlockstoreRocksFifoOptions,
+            ColumnLayout, LedgerColumnOptions, RocksDbTuningOptions, ShredStorageType,
         },
+        ledger_archive::{LedgerArchiveConfig, DEFAULT_LEDGER_ARCHIVE_MIN_AGE_SLOTS},
     },
     solana_net_utils::VALIDATOR_PORT_RANGE,
     solana_perf::recycler::enable_recycler_warming,
     solana_poh::poh_service,
     solana_rpc::{
         rpc::{JsonRpcConfig, RpcBigtableConfig},
+        rpc_auth::RpcAccessControl,
         rpc_pubsub_service::PubSubConfig,
     },
     solana_runtime::{
@@ -80,7 +91,7 @@ use {
         new_spinner_progress_bar, println_name_value, redirect_stderr_to_file,
     },
     std::{
-        collections::{HashSet, VecDeque},
+        collections::{HashMap, HashSet, VecDeque},
         env,
         fs::{self, File},
         net::{IpAddr, SocketAddr},
@@ -404,6 +415,48 @@ fn validators_set(
     }
 }
 
+/// Parses a `THREAD_ID:POLICY` value of --banking-thread-pop-policy
+fn parse_banking_thread_pop_policy(value: &str) -> Result<(u32, PopPolicy), String> {
+    let (thread_id, policy) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected THREAD_ID:POLICY, got {}", value))?;
+    let thread_id = thread_id
+        .parse()
+        .map_err(|err| format!("invalid thread id {}: {}", thread_id, err))?;
+    Ok((thread_id, policy.parse()?))
+}
+
+/// Splits a `COLUMN=VALUE` value of the per-column RocksDB arguments
+fn parse_rocksdb_column_value(value: &str) -> Result<(&'static str, &str), String> {
+    let (column, value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected COLUMN=VALUE, got {}", value))?;
+    let column = Database::column_name(column.trim())
+        .ok_or_else(|| format!("unknown ledger column {}", column))?;
+    Ok((column, value.trim()))
+}
+
+/// Parses a `COLUMN=BITS` value of --rocksdb-bloom-filter-bits-per-key
+fn parse_rocksdb_bloom_filter(value: &str) -> Result<(&'static str, i32), String> {
+    let (column, bits_per_key) = parse_rocksdb_column_value(value)?;
+    match bits_per_key.parse() {
+        Ok(bits_per_key) if bits_per_key > 0 => Ok((column, bits_per_key)),
+        _ => Err(format!(
+            "invalid bloom filter bits per key {}",
+            bits_per_key
+        )),
+    }
+}
+
+/// Parses a `COLUMN=PREFIX` value of --rocksdb-prefix-extractor
+fn parse_rocksdb_prefix_extractor(
+    value: &str,
+) -> Result<(&'static str, BlockstorePrefixExtractor), String> {
+    let (column, prefix_extractor) = parse_rocksdb_column_value(value)?;
+    let prefix_extractor = prefix_extractor.parse().map_err(|err| format!("{}", err))?;
+    Ok((column, prefix_extractor))
+}
+
 fn get_cluster_shred_version(entrypoints: &[SocketAddr]) -> Option<u16> {
     let entrypoints = {
         let mut index: Vec<_> = (0..entrypoints.len()).collect();
@@ -456,6 +509,9 @@ pub fn main() {
         .to_string();
     let default_rpc_threads = num_cpus::get().to_string();
     let default_accountsdb_repl_threads = num_cpus::get().to_string();
+    let default_poh_speed_test_threads = num_cpus::get().to_string();
+    let default_write_lock_heat_map_size =
+        &admin_rpc_service::DEFAULT_WRITE_LOCK_HEAT_MAP_SIZE.to_string();
     let default_maximum_full_snapshot_archives_to_retain =
         &DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN.to_string();
     let default_maximum_incremental_snapshot_archives_to_retain =
@@ -469,6 +525,7 @@ pub fn main() {
     let default_accounts_shrink_optimize_total_space =
         &DEFAULT_ACCOUNTS_SHRINK_OPTIMIZE_TOTAL_SPACE.to_string();
     let default_accounts_shrink_ratio = &DEFAULT_ACCOUNTS_SHRINK_RATIO.to_string();
+    let default_ledger_archive_min_age_slots = &DEFAULT_LEDGER_ARCHIVE_MIN_AGE_SLOTS.to_string();
     let default_rocksdb_fifo_shred_storage_size =
         &DEFAULT_ROCKS_FIFO_SHRED_STORAGE_SIZE_BYTES.to_string();
 
@@ -654,6 +711,17 @@ pub fn main() {
                 .help("Fetch historical transaction info from a BigTable instance \
                        as a fallback to local ledger data"),
         )
+        .arg(
+            Arg::with_name("rpc_ledger_archive_url")
+                .long("rpc-ledger-archive-url")
+                .value_name("URL")
+                .takes_value(true)
+                .requires("enable_rpc_transaction_history")
+                .conflicts_with("enable_rpc_bigtable_ledger_storage")
+                .help("Fetch historical blocks from the ledger archive at URL, as written by \
+                       --ledger-archive-url, as a fallback to local ledger data. Transactions \
+                       are not fetched from the archive"),
+        )
         .arg(
             Arg::with_name("enable_bigtable_ledger_upload")
                 .long("enable-bigtable-ledger-upload")
@@ -688,6 +756,34 @@ pub fn main() {
                 .help("Override the default maximum accounts accepted by \
                        the getMultipleAccounts JSON RPC method")
         )
+        .arg(
+            Arg::with_name("rpc_response_compression_threshold")
+                .long("rpc-response-compression-threshold")
+                .value_name("BYTES")
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .help("Compress JSON RPC responses larger than this many bytes with zstd \
+                       or gzip when the client sends a matching Accept-Encoding header \
+                       [default: responses are not compressed]"),
+        )
+        .arg(
+            Arg::with_name("rpc_require_auth")
+                .long("rpc-require-auth")
+                .takes_value(false)
+                .help("Require JSON RPC requests to carry a bearer token, added with the \
+                       add-rpc-token command, unless they only call methods given \
+                       with --rpc-public-method"),
+        )
+        .arg(
+            Arg::with_name("rpc_public_methods")
+                .long("rpc-public-method")
+                .value_name("METHOD")
+                .takes_value(true)
+                .multiple(true)
+                .requires("rpc_require_auth")
+                .help("JSON RPC method callable without a bearer token. \
+                       May be specified multiple times"),
+        )
         .arg(
             Arg::with_name("health_check_slot_distance")
                 .long("health-check-slot-distance")
@@ -952,6 +1048,16 @@ pub fn main() {
                 .long("no-poh-speed-test")
                 .help("Skip the check for PoH speed."),
         )
+        .arg(
+            Arg::with_name("poh_speed_test_threads")
+                .long("poh-speed-test-threads")
+                .value_name("NUMBER")
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .default_value(&default_poh_speed_test_threads)
+                .help("Number of threads hashing at once in the multi-threaded variant \
+                       of the PoH speed check. Values of 1 or less skip that variant."),
+        )
         .arg(
             Arg::with_name("no_os_network_limits_test")
                 .hidden(true)
@@ -1003,6 +1109,33 @@ pub fn main() {
                 /* .default_value() intentionally not used here! */
                 .help("Keep this amount of shreds in root slots."),
         )
+        .arg(
+            Arg::with_name("limit_ledger_bytes")
+                .long("limit-ledger-bytes")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Keep the ledger under this many bytes, as measured by RocksDB, by \
+                       purging its oldest rooted slots along with their transaction statuses \
+                       and address signatures. Applies together with --limit-ledger-size."),
+        )
+        .arg(
+            Arg::with_name("ledger_purge_tombstones")
+                .long("ledger-purge-tombstones")
+                .takes_value(false)
+                .help("Record each range of slots purged from the ledger, why and when, \
+                       so that queries for those slots can tell purged slots from missing ones. \
+                       See `solana-ledger-tool slot-tombstones`"),
+        )
+        .arg(
+            Arg::with_name("ledger_purge_grace_window")
+                .long("ledger-purge-grace-window")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Longest time geyser plugins may hold off a ledger purge, \
+                       in seconds [default: 300]"),
+        )
         .arg(
             Arg::with_name("rocksdb_shred_compaction")
                 .hidden(true)
@@ -1012,11 +1145,14 @@ pub fn main() {
                 .possible_values(&["level", "fifo"])
                 .default_value("level")
                 .help("EXPERIMENTAL: Controls how RocksDB compacts shreds. \
-                       *WARNING*: You will lose your ledger data when you switch between options. \
+                       *WARNING*: The ledger data is ignored when you switch between options, \
+                       unless it is converted with `solana-ledger-tool migrate-shred-storage`. \
                        Possible values are: \
                        'level': stores shreds using RocksDB's default (level) compaction. \
                        'fifo': stores shreds under RocksDB's FIFO compaction. \
-                           This option is more efficient on disk-write-bytes of the ledger store."),
+                           This option is more efficient on disk-write-bytes of the ledger store. \
+                           The --rocksdb-fifo-shred-storage-size is split 5:4 between data and \
+                           coding shreds."),
         )
         .arg(
             Arg::with_name("rocksdb_fifo_shred_storage_size")
@@ -1026,9 +1162,19 @@ pub fn main() {
                 .takes_value(true)
                 .validator(is_parsable::<u64>)
                 .default_value(default_rocksdb_fifo_shred_storage_size)
-                .help("The shred storage size in bytes. \
+                .help("The shred storage size in bytes, split between data and coding \
+                       shreds in a 5:4 ratio. \
                        The suggested value is 50% of your ledger storage size in bytes."),
         )
+        .arg(
+            Arg::with_name("rocksdb_fifo_clamp_to_minimum_size")
+                .hidden(true)
+                .long("rocksdb-fifo-clamp-to-minimum-size")
+                .takes_value(false)
+                .help("Raise the storage size of data or coding shreds to the minimum FIFO \
+                       compaction supports if --rocksdb-fifo-shred-storage-size leaves either \
+                       below it, rather than refusing to start"),
+        )
         .arg(
             Arg::with_name("rocksdb_ledger_compression")
                 .hidden(true)
@@ -1053,6 +1199,161 @@ pub fn main() {
                        Reads/writes perf samples are collected in 1 / ROCKS_PERF_SAMPLE_INTERVAL sampling rate."),
 
         )
+        .arg(
+            Arg::with_name("verify_blockstore_on_open")
+                .long("verify-blockstore-on-open")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Check the latest SLOTS slots of the blockstore for corruption at startup, \
+                       and refuse to start with a report of the corrupted slots if any"),
+        )
+        .arg(
+            Arg::with_name("ledger_column_path")
+                .long("ledger-column-path")
+                .value_name("COLUMN=DIR")
+                .takes_value(true)
+                .multiple(true)
+                .validator(|value| {
+                    ColumnLayout::from_column_paths([value.as_str()])
+                        .map(|_| ())
+                        .map_err(|err| err.to_string())
+                })
+                .help("Store the COLUMN column family of the blockstore in DIR rather than in \
+                       the ledger, such as data_shred on large disks or transaction_status on \
+                       fast ones. The columns of an existing blockstore have to be moved there \
+                       first with `solana-ledger-tool move-columns`"),
+        )
+        .arg(
+            Arg::with_name("ledger_archive_url")
+                .long("ledger-archive-url")
+                .value_name("URL")
+                .takes_value(true)
+                .requires("enable_rpc_transaction_history")
+                .help("Archive rooted slots to URL once they are old enough, then delete them \
+                       from the blockstore. RPC serves getBlock for archived slots from the \
+                       archive. URL is a local directory, s3://BUCKET[/PREFIX] or \
+                       gs://BUCKET[/PREFIX]; object stores are authenticated with the HMAC key \
+                       in the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY environment variables"),
+        )
+        .arg(
+            Arg::with_name("ledger_archive_min_age_slots")
+                .long("ledger-archive-min-age-slots")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .requires("ledger_archive_url")
+                .validator(is_parsable::<u64>)
+                .default_value(default_ledger_archive_min_age_slots)
+                .help("Archive rooted slots once they are SLOTS slots behind the latest root"),
+        )
+        .arg(
+            Arg::with_name("ledger_archive_endpoint")
+                .long("ledger-archive-endpoint")
+                .value_name("URL")
+                .takes_value(true)
+                .help("Endpoint of the object store of --ledger-archive-url and \
+                       --rpc-ledger-archive-url, for S3-compatible stores other than S3 and GCS"),
+        )
+        .arg(
+            Arg::with_name("ledger_archive_region")
+                .long("ledger-archive-region")
+                .value_name("REGION")
+                .takes_value(true)
+                .help("Region of the object store of --ledger-archive-url and \
+                       --rpc-ledger-archive-url [default: us-east-1 for S3]"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_write_buffer_size")
+                .hidden(true)
+                .long("rocksdb-write-buffer-size")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Size of a RocksDB memtable of the ledger columns using level compaction \
+                       [default: 268435456]"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_max_write_buffer_number")
+                .hidden(true)
+                .long("rocksdb-max-write-buffer-number")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<i32>)
+                .help("Maximum number of RocksDB memtables of each ledger column [default: 8]"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_block_cache_size")
+                .hidden(true)
+                .long("rocksdb-block-cache-size")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .help("Capacity of a RocksDB block cache shared by all ledger columns \
+                       [default: an 8 MB block cache per column]"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_bloom_filter_bits_per_key")
+                .hidden(true)
+                .long("rocksdb-bloom-filter-bits-per-key")
+                .value_name("COLUMN=BITS")
+                .takes_value(true)
+                .multiple(true)
+                .validator(|s| parse_rocksdb_bloom_filter(&s).map(|_| ()))
+                .help("Build a bloom filter with BITS bits per key for the SST files of a \
+                       ledger column, which speeds up point lookups of missing keys \
+                       [default: no bloom filters]"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_prefix_extractor")
+                .hidden(true)
+                .long("rocksdb-prefix-extractor")
+                .value_name("COLUMN=PREFIX")
+                .takes_value(true)
+                .multiple(true)
+                .validator(|s| parse_rocksdb_prefix_extractor(&s).map(|_| ()))
+                .help("Group the keys of a ledger column by prefix for its bloom filters, \
+                       either by their leading \"slot\" or by their first PREFIX bytes. Scans \
+                       within a slot are bounded to their prefix when it is no longer than the \
+                       slot.")
+        )
+        .arg(
+            Arg::with_name("rocksdb_max_background_jobs")
+                .hidden(true)
+                .long("rocksdb-max-background-jobs")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<i32>)
+                .help("Maximum number of concurrent RocksDB compactions and flushes \
+                       [default: the number of cores]"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_direct_reads")
+                .long("rocksdb-direct-reads")
+                .takes_value(false)
+                .conflicts_with("rocksdb_mmap_reads")
+                .help("Read the ledger with direct I/O, bypassing the page cache"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_direct_io_for_flush_and_compaction")
+                .long("rocksdb-direct-io-for-flush-and-compaction")
+                .takes_value(false)
+                .conflicts_with("rocksdb_mmap_writes")
+                .help("Flush and compact the ledger with direct I/O, bypassing the page cache"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_mmap_reads")
+                .hidden(true)
+                .long("rocksdb-mmap-reads")
+                .takes_value(false)
+                .help("Read the ledger through mmap"),
+        )
+        .arg(
+            Arg::with_name("rocksdb_mmap_writes")
+                .hidden(true)
+                .long("rocksdb-mmap-writes")
+                .takes_value(false)
+                .help("Write the ledger through mmap"),
+        )
         .arg(
             Arg::with_name("skip_poh_verify")
                 .long("skip-poh-verify")
@@ -1169,6 +1470,45 @@ pub fn main() {
                 .help("A list of validators to request repairs from. If specified, repair will not \
                        request from validators outside this set [default: all validators]")
         )
+        .arg(
+            Arg::with_name("serve_repair_admission")
+                .long("serve-repair-admission")
+                .takes_value(false)
+                .help("Budget the repair responses served to other validators by their stake. \
+                       Requests not sent from an address the requester advertises in gossip \
+                       are budgeted as unstaked by source IP. Known validators are never \
+                       throttled.")
+        )
+        .arg(
+            Arg::with_name("serve_repair_staked_bytes_per_second")
+                .long("serve-repair-staked-bytes-per-second")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(|s| is_within_range(s, 1, usize::MAX))
+                .requires("serve_repair_admission")
+                .help("Repair response bandwidth shared by staked validators in proportion \
+                       to their stake [default: 200000000]")
+        )
+        .arg(
+            Arg::with_name("serve_repair_unstaked_bytes_per_second")
+                .long("serve-repair-unstaked-bytes-per-second")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(|s| is_within_range(s, 1, usize::MAX))
+                .requires("serve_repair_admission")
+                .help("Repair response bandwidth shared by all unstaked requesters \
+                       [default: 10000000]")
+        )
+        .arg(
+            Arg::with_name("serve_repair_unstaked_requester_bytes_per_second")
+                .long("serve-repair-unstaked-requester-bytes-per-second")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(|s| is_within_range(s, 1, usize::MAX))
+                .requires("serve_repair_admission")
+                .help("Repair response bandwidth available to any single unstaked requester \
+                       [default: 1000000]")
+        )
         .arg(
             Arg::with_name("gossip_validators")
                 .long("gossip-validator")
@@ -1180,6 +1520,146 @@ pub fn main() {
                       will not push/pull from from validators outside this set. \
                       [default: all validators]")
         )
+        .arg(
+            Arg::with_name("banking_account_denylist")
+                .long("banking-deny-account")
+                .validator(is_pubkey)
+                .value_name("ADDRESS")
+                .multiple(true)
+                .takes_value(true)
+                .help("Drop transactions that reference this account or invoke this program \
+                       before they are buffered for processing as leader")
+        )
+        .arg(
+            Arg::with_name("banking_source_packets_per_second")
+                .long("banking-source-packets-per-second")
+                .value_name("PACKETS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Drop transactions from a source address once it has sent this many \
+                       per second for processing as leader [default: unlimited]")
+        )
+        .arg(
+            Arg::with_name("banking_max_packets_per_lock_set")
+                .long("banking-max-packets-per-lock-set")
+                .value_name("PACKETS")
+                .takes_value(true)
+                .validator(|s| is_within_range(s, 1, usize::MAX))
+                .help("Buffer only this many of the transactions sharing a fee payer and \
+                       writable accounts for processing as leader, keeping the ones of \
+                       highest priority [default: unlimited]")
+        )
+        .arg(
+            Arg::with_name("banking_notify_dropped_transactions")
+                .long("banking-notify-dropped-transactions")
+                .takes_value(false)
+                .help("Notify QUIC senders with a datagram when a transaction they sent is \
+                       dropped as leader because its blockhash expired or it was evicted by \
+                       higher priority transactions")
+        )
+        .arg(
+            Arg::with_name("banking_persist_buffered_packets")
+                .long("banking-persist-buffered-packets")
+                .takes_value(false)
+                .help("Save the transactions buffered for processing as leader to the ledger \
+                       directory on exit, and buffer them again on restart")
+        )
+        .arg(
+            Arg::with_name("banking_forward_max_packets_per_second")
+                .long("banking-forward-max-packets-per-second")
+                .value_name("PACKETS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Forward at most this many buffered transactions per second to the \
+                       upcoming leaders [default: 10000]")
+        )
+        .arg(
+            Arg::with_name("banking_forward_max_bytes_per_second")
+                .long("banking-forward-max-bytes-per-second")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Forward at most this many bytes of buffered transactions per second to \
+                       the upcoming leaders [default: 12000000]")
+        )
+        .arg(
+            Arg::with_name("banking_forward_burst_window_ms")
+                .long("banking-forward-burst-window-ms")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Forwarding budget left unused over this window can be spent in a single \
+                       burst [default: 100]")
+        )
+        .arg(
+            Arg::with_name("banking_forward_min_leader_budget_bytes")
+                .long("banking-forward-min-leader-budget-bytes")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Bytes of buffered transactions forwarded to every upcoming leader, \
+                       regardless of its stake [default: 120000]")
+        )
+        .arg(
+            Arg::with_name("banking_forward_stake_weighted_leader_budget_bytes")
+                .long("banking-forward-stake-weighted-leader-budget-bytes")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Bytes of buffered transactions forwarded to an upcoming leader on top of \
+                       the minimum, scaled by its share of the total stake [default: 48000000]")
+        )
+        .arg(
+            Arg::with_name("banking_priority_aging_interval_ms")
+                .long("banking-priority-aging-interval-ms")
+                .value_name("MILLISECONDS")
+                .takes_value(true)
+                .validator(|s| is_within_range(s, 1, usize::MAX))
+                .requires("banking_priority_aging_boost")
+                .help("Raise the priority of the transactions buffered for processing as leader \
+                       every time they have waited this long, so that low priority \
+                       transactions are not starved [default: no aging]")
+        )
+        .arg(
+            Arg::with_name("banking_priority_aging_boost")
+                .long("banking-priority-aging-boost")
+                .value_name("PRIORITY")
+                .takes_value(true)
+                .validator(|s| is_within_range(s, 1, usize::MAX))
+                .requires("banking_priority_aging_interval_ms")
+                .help("Priority added to a buffered transaction every \
+                       --banking-priority-aging-interval-ms")
+        )
+        .arg(
+            Arg::with_name("banking_priority_aging_max_boost")
+                .long("banking-priority-aging-max-boost")
+                .value_name("PRIORITY")
+                .takes_value(true)
+                .validator(|s| is_within_range(s, 1, usize::MAX))
+                .requires("banking_priority_aging_interval_ms")
+                .help("Total priority a buffered transaction can gain while it waits \
+                       [default: unbounded]")
+        )
+        .arg(
+            Arg::with_name("banking_pop_policy")
+                .long("banking-pop-policy")
+                .value_name("POLICY")
+                .takes_value(true)
+                .possible_values(&["greedy", "weighted-random"])
+                .help("How the banking threads pick the buffered transactions they execute \
+                       next as leader: highest priority first, or sampled with probability \
+                       proportional to priority [default: greedy]")
+        )
+        .arg(
+            Arg::with_name("banking_thread_pop_policy")
+                .long("banking-thread-pop-policy")
+                .value_name("THREAD_ID:POLICY")
+                .takes_value(true)
+                .multiple(true)
+                .validator(|s| parse_banking_thread_pop_policy(&s).map(|_| ()))
+                .help("Override --banking-pop-policy for one banking thread. Transaction \
+                       threads are numbered from 2.")
+        )
         .arg(
             Arg::with_name("no_rocksdb_compaction")
                 .long("no-rocksdb-compaction")
@@ -1207,6 +1687,23 @@ pub fn main() {
                 .takes_value(false)
                 .help("Use QUIC to send transactions."),
         )
+        .arg(
+            Arg::with_name("broadcast_coding_threads")
+                .long("broadcast-coding-threads")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .help("Number of threads generating erasure coding shreds when broadcasting \
+                       as leader. By default coding shares the threads shredding entries"),
+        )
+        .arg(
+            Arg::with_name("broadcast_pipeline_coding")
+                .long("broadcast-pipeline-coding")
+                .takes_value(false)
+                .help("Generate erasure coding shreds on a separate thread when broadcasting \
+                       as leader, so that the next slot is shredded while the tail of the \
+                       previous slot is still being coded"),
+        )
         .arg(
             Arg::with_name("rocksdb_max_compaction_jitter")
                 .long("rocksdb-max-compaction-jitter-slots")
@@ -1425,6 +1922,16 @@ pub fn main() {
                 .default_value(&default_rpc_send_transaction_batch_size)
                 .help("The size of transactions to be sent in batch."),
         )
+        .arg(
+            Arg::with_name("rpc_send_transaction_retry_stop_commitment")
+                .long("rpc-send-retry-stop-commitment")
+                .value_name("COMMITMENT_LEVEL")
+                .takes_value(true)
+                .possible_values(&["processed", "confirmed", "finalized"])
+                .default_value("finalized")
+                .help("Stop retrying a transaction once it succeeded at this commitment level, \
+                       rather than only once it is rooted or expired"),
+        )
         .arg(
             Arg::with_name("rpc_scan_and_fix_roots")
                 .long("rpc-scan-and-fix-roots")
@@ -1432,6 +1939,41 @@ pub fn main() {
                 .requires("enable_rpc_transaction_history")
                 .help("Verifies blockstore roots on boot and fixes any gaps"),
         )
+        .arg(
+            Arg::with_name("stake_snapshot_dir")
+                .long("stake-snapshot-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Export the stake delegations of every epoch to this directory \
+                       and serve them via the getStakeSnapshot RPC method"),
+        )
+        .arg(
+            Arg::with_name("enable_rpc_vote_latencies")
+                .long("enable-rpc-vote-latencies")
+                .takes_value(false)
+                .help("Track the latency of the votes landing in replayed blocks \
+                       and serve it via the getVoteLatencies RPC method"),
+        )
+        .arg(
+            Arg::with_name("enable_rpc_block_time_backfill")
+                .long("enable-rpc-block-time-backfill")
+                .takes_value(false)
+                .help("Estimate the block times missing from the ledger from vote timestamps \
+                       and serve them via the getBlockTime RPC method when estimates are allowed"),
+        )
+        .arg(
+            Arg::with_name("rpc_upstream_urls")
+                .long("rpc-upstream")
+                .value_name("URL")
+                .takes_value(true)
+                .multiple(true)
+                .validator(is_url)
+                .requires("enable_rpc_transaction_history")
+                .help("Forward getBlock and getTransaction requests for history this node \
+                       no longer has to this archive RPC node and relay its result. \
+                       May be specified multiple times; upstreams are tried in order, \
+                       skipping those that are unhealthy or failing"),
+        )
         .arg(
             Arg::with_name("enable_accountsdb_repl")
                 .long("enable-accountsdb-repl")
@@ -1476,6 +2018,15 @@ pub fn main() {
                 .multiple(true)
                 .help("Specify the configuration file for the Geyser plugin."),
         )
+        .arg(
+            Arg::with_name("ingest_plugin_config")
+                .long("ingest-plugin-config")
+                .value_name("FILE")
+                .takes_value(true)
+                .multiple(true)
+                .help("Specify the configuration file for a plugin screening the transaction \
+                       packets received before their signatures are verified."),
+        )
         .arg(
             Arg::with_name("halt_on_known_validators_accounts_hash_mismatch")
                 .alias("halt-on-trusted-validators-accounts-hash-mismatch")
@@ -1484,6 +2035,39 @@ pub fn main() {
                 .takes_value(false)
                 .help("Abort the validator if a bank hash mismatch is detected within known validator set"),
         )
+        .arg(
+            Arg::with_name("accounts_hash_verification_min_known_validators")
+                .long("accounts-hash-verification-min-known-validators")
+                .value_name("COUNT")
+                .takes_value(true)
+                .requires("halt_on_known_validators_accounts_hash_mismatch")
+                .validator(is_parsable::<usize>)
+                .help("Halt on a known validators accounts hash mismatch only once at least \
+                       this many known validators published hashes for the slots verified \
+                       [default: 1]"),
+        )
+        .arg(
+            Arg::with_name("accounts_hash_verification_mismatch_stake_fraction")
+                .long("accounts-hash-verification-mismatch-stake-fraction")
+                .value_name("FRACTION")
+                .takes_value(true)
+                .requires("halt_on_known_validators_accounts_hash_mismatch")
+                .validator(is_parsable::<f64>)
+                .help("Halt on a known validators accounts hash mismatch only if the known \
+                       validators that published conflicting hashes hold at least this \
+                       fraction of the stake of the known validators verified [default: 0]"),
+        )
+        .arg(
+            Arg::with_name("accounts_hash_verification_max_age_slots")
+                .long("accounts-hash-verification-max-age-slots")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .requires("halt_on_known_validators_accounts_hash_mismatch")
+                .validator(is_slot)
+                .help("Ignore the accounts hashes published by known validators for slots \
+                       more than this many slots older than the newest accounts hash \
+                       [default: unlimited]"),
+        )
         .arg(
             Arg::with_name("snapshot_archive_format")
                 .long("snapshot-archive-format")
@@ -1509,13 +2093,11 @@ pub fn main() {
                 .long("wal-recovery-mode")
                 .value_name("MODE")
                 .takes_value(true)
-                .possible_values(&[
-                    "tolerate_corrupted_tail_records",
-                    "absolute_consistency",
-                    "point_in_time",
-                    "skip_any_corrupted_record"])
+                .validator(is_parsable::<BlockstoreRecoveryMode>)
                 .help(
-                    "Mode to recovery the ledger db write ahead log."
+                    "Mode to recovery the ledger db write ahead log. \
+                     [possible values: tolerate_corrupted_tail_records, absolute_consistency, \
+                     point_in_time, skip_any_corrupted_record]"
                 ),
         )
         .arg(
@@ -1828,6 +2410,165 @@ pub fn main() {
             SubCommand::with_name("init")
             .about("Initialize the ledger directory then exit")
         )
+        .subcommand(
+            SubCommand::with_name("poh-speed-check")
+            .about("Display the result of the PoH speed check run at startup")
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .takes_value(true)
+                    .value_name("MODE")
+                    .possible_values(&["json", "json-compact"])
+                    .help("Output display mode")
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("write-lock-heat-map")
+            .about("Display the accounts write-locked most often in a slot")
+            .arg(
+                Arg::with_name("slot")
+                    .long("slot")
+                    .takes_value(true)
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .help("Slot to display [default: the validator's working bank]")
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .long("limit")
+                    .takes_value(true)
+                    .value_name("NUMBER")
+                    .validator(is_parsable::<usize>)
+                    .default_value(default_write_lock_heat_map_size)
+                    .help("Maximum number of accounts to display")
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .takes_value(true)
+                    .value_name("MODE")
+                    .possible_values(&["json", "json-compact"])
+                    .help("Output display mode")
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("compact-range")
+            .about("Compact a range of slots of a ledger column")
+            .arg(
+                Arg::with_name("column")
+                    .index(1)
+                    .takes_value(true)
+                    .value_name("COLUMN")
+                    .required(true)
+                    .help("Name of the column to compact, e.g. data_shred")
+            )
+            .arg(
+                Arg::with_name("start_slot")
+                    .long("start-slot")
+                    .takes_value(true)
+                    .value_name("SLOT")
+                    .required(true)
+                    .validator(is_slot)
+                    .help("First slot of the range to compact")
+            )
+            .arg(
+                Arg::with_name("end_slot")
+                    .long("end-slot")
+                    .takes_value(true)
+                    .value_name("SLOT")
+                    .required(true)
+                    .validator(is_slot)
+                    .help("Last slot of the range to compact")
+            )
+            .after_help("Note: only columns keyed by slot can be compacted")
+        )
+        .subcommand(
+            SubCommand::with_name("set-rocksdb-perf-sample-interval")
+            .about("Change how often RocksDB read/write performance samples are collected")
+            .arg(
+                Arg::with_name("sample_interval")
+                    .index(1)
+                    .takes_value(true)
+                    .value_name("ROCKS_PERF_SAMPLE_INTERVAL")
+                    .required(true)
+                    .validator(is_parsable::<usize>)
+                    .help("Sample one in every ROCKS_PERF_SAMPLE_INTERVAL operations of each \
+                           column, as --rocksdb-perf-sample-interval does at startup. \
+                           0 disables sampling")
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("create-ledger-checkpoint")
+            .about("Copy the blockstore and genesis of the running validator to a new ledger \
+                    directory, hard linking its files where possible")
+            .arg(
+                Arg::with_name("checkpoint_path")
+                    .index(1)
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .required(true)
+                    .help("Ledger directory to create the checkpoint in, which must not \
+                           contain a blockstore already")
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("enable-account-index")
+            .about("Enable an accounts index on the running validator")
+            .arg(
+                Arg::with_name("index")
+                    .index(1)
+                    .takes_value(true)
+                    .value_name("INDEX")
+                    .required(true)
+                    .possible_values(&["program-id", "spl-token-owner", "spl-token-mint"])
+                    .help("Accounts index to enable")
+            )
+            .arg(
+                Arg::with_name("no_wait")
+                    .long("no-wait")
+                    .takes_value(false)
+                    .help("Don't wait for the index to be backfilled with the existing accounts")
+            )
+            .after_help("Note: RPC methods keep scanning all accounts until the backfill completes. \
+                         Pass --account-index on restart to keep the index enabled")
+        )
+        .subcommand(
+            SubCommand::with_name("account-index-backfills")
+            .about("Display the progress of the accounts indexes enabled since startup")
+        )
+        .subcommand(
+            SubCommand::with_name("add-rpc-token")
+            .about("Accept a bearer token for JSON RPC requests to the running validator")
+            .arg(
+                Arg::with_name("token")
+                    .index(1)
+                    .takes_value(true)
+                    .value_name("TOKEN")
+                    .help("Token to accept [default: a new random token, which is printed]")
+            )
+            .arg(
+                Arg::with_name("methods")
+                    .long("method")
+                    .takes_value(true)
+                    .value_name("METHOD")
+                    .multiple(true)
+                    .help("JSON RPC method callable with the token. \
+                           May be specified multiple times [default: all methods]")
+            )
+            .after_help("Note: tokens are not persisted, add them again after restarting the validator")
+        )
+        .subcommand(
+            SubCommand::with_name("remove-rpc-token")
+            .about("Stop accepting a bearer token for JSON RPC requests to the running validator")
+            .arg(
+                Arg::with_name("token")
+                    .index(1)
+                    .takes_value(true)
+                    .value_name("TOKEN")
+                    .required(true)
+                    .help("Token to remove")
+            )
+        )
         .subcommand(
             SubCommand::with_name("monitor")
             .about("Monitor the validator")
@@ -1983,6 +2724,223 @@ pub fn main() {
             return;
         }
         ("init", _) => Operation::Initialize,
+        ("poh-speed-check", Some(subcommand_matches)) => {
+            let output_mode = subcommand_matches.value_of("output");
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let poh_speed_check_report = admin_rpc_service::runtime()
+                .block_on(async move { admin_client.await?.poh_speed_check_report().await })
+                .unwrap_or_else(|err| {
+                    eprintln!("PoH speed check query failed: {}", err);
+                    exit(1);
+                });
+            let poh_speed_check_report = poh_speed_check_report.unwrap_or_else(|| {
+                eprintln!("PoH speed check did not run");
+                exit(1);
+            });
+            if let Some(mode) = output_mode {
+                match mode {
+                    "json" => println!(
+                        "{}",
+                        serde_json::to_string_pretty(&poh_speed_check_report).unwrap()
+                    ),
+                    "json-compact" => {
+                        print!(
+                            "{}",
+                            serde_json::to_string(&poh_speed_check_report).unwrap()
+                        )
+                    }
+                    _ => unreachable!(),
+                }
+            } else {
+                print!("{}", poh_speed_check_report);
+            }
+            return;
+        }
+        ("write-lock-heat-map", Some(subcommand_matches)) => {
+            let slot = value_t!(subcommand_matches, "slot", Slot).ok();
+            let max_accounts = value_t_or_exit!(subcommand_matches, "limit", usize);
+            let output_mode = subcommand_matches.value_of("output");
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let write_lock_heat_map = admin_rpc_service::runtime()
+                .block_on(async move {
+                    admin_client
+                        .await?
+                        .write_lock_heat_map(slot, Some(max_accounts))
+                        .await
+                })
+                .unwrap_or_else(|err| {
+                    eprintln!("Write lock heat map query failed: {}", err);
+                    exit(1);
+                });
+            if let Some(mode) = output_mode {
+                match mode {
+                    "json" => println!(
+                        "{}",
+                        serde_json::to_string_pretty(&write_lock_heat_map).unwrap()
+                    ),
+                    "json-compact" => {
+                        print!("{}", serde_json::to_string(&write_lock_heat_map).unwrap())
+                    }
+                    _ => unreachable!(),
+                }
+            } else {
+                print!("{}", write_lock_heat_map);
+            }
+            return;
+        }
+        ("compact-range", Some(subcommand_matches)) => {
+            let column = value_t_or_exit!(subcommand_matches, "column", String);
+            let start_slot = value_t_or_exit!(subcommand_matches, "start_slot", Slot);
+            let end_slot = value_t_or_exit!(subcommand_matches, "end_slot", Slot);
+            println!(
+                "Compacting {} from slot {} to {}",
+                column, start_slot, end_slot
+            );
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            admin_rpc_service::runtime()
+                .block_on(async move {
+                    admin_client
+                        .await?
+                        .compact_range(column, start_slot, end_slot)
+                        .await
+                })
+                .unwrap_or_else(|err| {
+                    println!("compactRange request failed: {}", err);
+                    exit(1);
+                });
+            println!("Compaction complete");
+            return;
+        }
+        ("set-rocksdb-perf-sample-interval", Some(subcommand_matches)) => {
+            let sample_interval = value_t_or_exit!(subcommand_matches, "sample_interval", usize);
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            admin_rpc_service::runtime()
+                .block_on(async move {
+                    admin_client
+                        .await?
+                        .set_rocks_perf_sample_interval(sample_interval)
+                        .await
+                })
+                .unwrap_or_else(|err| {
+                    println!("setRocksPerfSampleInterval request failed: {}", err);
+                    exit(1);
+                });
+            return;
+        }
+        ("create-ledger-checkpoint", Some(subcommand_matches)) => {
+            // The validator may not run in the current directory
+            let checkpoint_path = std::env::current_dir().unwrap().join(value_t_or_exit!(
+                subcommand_matches,
+                "checkpoint_path",
+                String
+            ));
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let request_path = checkpoint_path.display().to_string();
+            admin_rpc_service::runtime()
+                .block_on(async move {
+                    admin_client
+                        .await?
+                        .create_blockstore_checkpoint(request_path)
+                        .await
+                })
+                .unwrap_or_else(|err| {
+                    println!("createBlockstoreCheckpoint request failed: {}", err);
+                    exit(1);
+                });
+            println!("Created a checkpoint in {}", checkpoint_path.display());
+            return;
+        }
+        ("enable-account-index", Some(subcommand_matches)) => {
+            let index = value_t_or_exit!(subcommand_matches, "index", String);
+            let no_wait = subcommand_matches.is_present("no_wait");
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let enable_index = index.clone();
+            admin_rpc_service::runtime()
+                .block_on(
+                    async move { admin_client.await?.enable_account_index(enable_index).await },
+                )
+                .unwrap_or_else(|err| {
+                    println!("enableAccountIndex request failed: {}", err);
+                    exit(1);
+                });
+            println!("Account index {} enabled", index);
+            if no_wait {
+                return;
+            }
+            let runtime = admin_rpc_service::runtime();
+            loop {
+                let admin_client = admin_rpc_service::connect(&ledger_path);
+                let backfill = runtime
+                    .block_on(async move { admin_client.await?.account_index_backfills().await })
+                    .unwrap_or_else(|err| {
+                        println!("accountIndexBackfills request failed: {}", err);
+                        exit(1);
+                    })
+                    .into_iter()
+                    .find(|backfill| backfill.index == index)
+                    .unwrap_or_else(|| {
+                        println!("Account index {} is not backfilling", index);
+                        exit(1);
+                    });
+                println!("{}", backfill);
+                if backfill.complete {
+                    return;
+                }
+                std::thread::sleep(Duration::from_secs(5));
+            }
+        }
+        ("account-index-backfills", _) => {
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let backfills = admin_rpc_service::runtime()
+                .block_on(async move { admin_client.await?.account_index_backfills().await })
+                .unwrap_or_else(|err| {
+                    println!("accountIndexBackfills request failed: {}", err);
+                    exit(1);
+                });
+            if backfills.is_empty() {
+                println!("No accounts indexes enabled since startup");
+            }
+            for backfill in backfills {
+                println!("{}", backfill);
+            }
+            return;
+        }
+        ("add-rpc-token", Some(subcommand_matches)) => {
+            let token = value_t!(subcommand_matches, "token", String).unwrap_or_else(|_| {
+                thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(32)
+                    .collect::<String>()
+            });
+            let methods = values_t!(subcommand_matches, "methods", String).ok();
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let request_token = token.clone();
+            admin_rpc_service::runtime()
+                .block_on(async move {
+                    admin_client
+                        .await?
+                        .add_rpc_token(request_token, methods)
+                        .await
+                })
+                .unwrap_or_else(|err| {
+                    println!("addRpcToken request failed: {}", err);
+                    exit(1);
+                });
+            println!("Added RPC token {}", token);
+            return;
+        }
+        ("remove-rpc-token", Some(subcommand_matches)) => {
+            let token = value_t_or_exit!(subcommand_matches, "token", String);
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            admin_rpc_service::runtime()
+                .block_on(async move { admin_client.await?.remove_rpc_token(token).await })
+                .unwrap_or_else(|err| {
+                    println!("removeRpcToken request failed: {}", err);
+                    exit(1);
+                });
+            println!("Removed RPC token");
+            return;
+        }
         ("exit", Some(subcommand_matches)) => {
             let min_idle_time = value_t_or_exit!(subcommand_matches, "min_idle_time", usize);
             let force = subcommand_matches.is_present("force");
@@ -2153,9 +3111,7 @@ pub fn main() {
         value_t!(matches, "rocksdb_max_compaction_jitter", u64).ok();
     let tpu_coalesce_ms =
         value_t!(matches, "tpu_coalesce_ms", u64).unwrap_or(DEFAULT_TPU_COALESCE_MS);
-    let wal_recovery_mode = matches
-        .value_of("wal_recovery_mode")
-        .map(BlockstoreRecoveryMode::from);
+    let wal_recovery_mode = value_t!(matches, "wal_recovery_mode", BlockstoreRecoveryMode).ok();
 
     // Canonicalize ledger path to avoid issues with symlink creation
     let _ = fs::create_dir_all(&ledger_path);
@@ -2164,6 +3120,82 @@ pub fn main() {
         exit(1);
     });
 
+    let default_forward_pacing_config = ForwardPacingConfig::default();
+    let banking_forward_pacing_config = ForwardPacingConfig {
+        max_packets_per_second: value_t!(matches, "banking_forward_max_packets_per_second", u64)
+            .unwrap_or(default_forward_pacing_config.max_packets_per_second),
+        max_bytes_per_second: value_t!(matches, "banking_forward_max_bytes_per_second", u64)
+            .unwrap_or(default_forward_pacing_config.max_bytes_per_second),
+        burst_window: value_t!(matches, "banking_forward_burst_window_ms", u64)
+            .map(Duration::from_millis)
+            .unwrap_or(default_forward_pacing_config.burst_window),
+        min_leader_budget_bytes: value_t!(matches, "banking_forward_min_leader_budget_bytes", u64)
+            .unwrap_or(default_forward_pacing_config.min_leader_budget_bytes),
+        stake_weighted_leader_budget_bytes: value_t!(
+            matches,
+            "banking_forward_stake_weighted_leader_budget_bytes",
+            u64
+        )
+        .unwrap_or(default_forward_pacing_config.stake_weighted_leader_budget_bytes),
+    };
+    let banking_priority_aging_config = if matches.is_present("banking_priority_aging_interval_ms")
+    {
+        PriorityAgingConfig {
+            interval: Duration::from_millis(value_t_or_exit!(
+                matches,
+                "banking_priority_aging_interval_ms",
+                u64
+            )),
+            boost_per_interval: value_t_or_exit!(matches, "banking_priority_aging_boost", u64),
+            max_boost: value_t!(matches, "banking_priority_aging_max_boost", u64)
+                .unwrap_or(u64::MAX),
+        }
+    } else {
+        PriorityAgingConfig::default()
+    };
+    let banking_pop_policy_config = PopPolicyConfig {
+        default_policy: value_t!(matches, "banking_pop_policy", PopPolicy).unwrap_or_default(),
+        thread_policies: matches
+            .values_of("banking_thread_pop_policy")
+            .map(|values| {
+                values
+                    .map(|value| parse_banking_thread_pop_policy(value).unwrap())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    let serve_repair_admission_config = matches.is_present("serve_repair_admission").then(|| {
+        let default_config = ServeRepairAdmissionConfig::default();
+        ServeRepairAdmissionConfig {
+            staked_bytes_per_second: value_t!(matches, "serve_repair_staked_bytes_per_second", u64)
+                .unwrap_or(default_config.staked_bytes_per_second),
+            unstaked_bytes_per_second: value_t!(
+                matches,
+                "serve_repair_unstaked_bytes_per_second",
+                u64
+            )
+            .unwrap_or(default_config.unstaked_bytes_per_second),
+            unstaked_requester_bytes_per_second: value_t!(
+                matches,
+                "serve_repair_unstaked_requester_bytes_per_second",
+                u64
+            )
+            .unwrap_or(default_config.unstaked_requester_bytes_per_second),
+            ..default_config
+        }
+    });
+
+    let banking_account_denylist: Option<HashSet<_>> =
+        if matches.is_present("banking_account_denylist") {
+            Some(
+                values_t_or_exit!(matches, "banking_account_denylist", Pubkey)
+                    .into_iter()
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
     let debug_keys: Option<Arc<HashSet<_>>> = if matches.is_present("debug_key") {
         Some(Arc::new(
             values_t_or_exit!(matches, "debug_key", Pubkey)
@@ -2354,6 +3386,17 @@ pub fn main() {
     }
     let accounts_db_config = Some(accounts_db_config);
 
+    let ingest_plugin_config_files = if matches.is_present("ingest_plugin_config") {
+        Some(
+            values_t_or_exit!(matches, "ingest_plugin_config", String)
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+        )
+    } else {
+        None
+    };
+
     let geyser_plugin_config_files = if matches.is_present("geyser_plugin_config") {
         Some(
             values_t_or_exit!(matches, "geyser_plugin_config", String)
@@ -2390,6 +3433,17 @@ pub fn main() {
     } else {
         None
     };
+    let rpc_ledger_archive_config =
+        matches
+            .value_of("rpc_ledger_archive_url")
+            .map(|url| LedgerArchiveConfig {
+                url: url.to_string(),
+                endpoint: matches
+                    .value_of("ledger_archive_endpoint")
+                    .map(String::from),
+                region: matches.value_of("ledger_archive_region").map(String::from),
+                ..LedgerArchiveConfig::default()
+            });
 
     if matches.is_present("accounts_db_index_hashing") {
         info!("The accounts hash is only calculated without using the index. --accounts-db-index-hashing is deprecated and can be removed from the command line");
@@ -2440,6 +3494,7 @@ pub fn main() {
             enable_extended_tx_metadata_storage: matches.is_present("enable_cpi_and_log_storage")
                 || matches.is_present("enable_extended_tx_metadata_storage"),
             rpc_bigtable_config,
+            rpc_ledger_archive_config,
             faucet_addr: matches.value_of("rpc_faucet_addr").map(|address| {
                 solana_net_utils::parse_host_port(address).expect("failed to parse faucet address")
             }),
@@ -2459,8 +3514,27 @@ pub fn main() {
             rpc_niceness_adj: value_t_or_exit!(matches, "rpc_niceness_adj", i8),
             account_indexes: account_indexes.clone(),
             rpc_scan_and_fix_roots: matches.is_present("rpc_scan_and_fix_roots"),
+            stake_snapshot_dir: matches.value_of("stake_snapshot_dir").map(PathBuf::from),
+            enable_rpc_vote_latencies: matches.is_present("enable_rpc_vote_latencies"),
+            enable_rpc_block_time_backfill: matches.is_present("enable_rpc_block_time_backfill"),
+            rpc_upstream_urls: values_t!(matches, "rpc_upstream_urls", String).unwrap_or_default(),
+            rpc_response_compression_threshold: value_t!(
+                matches,
+                "rpc_response_compression_threshold",
+                usize
+            )
+            .ok(),
+            rpc_access_control: matches.is_present("rpc_require_auth").then(|| {
+                Arc::new(RpcAccessControl::new(
+                    values_t!(matches, "rpc_public_methods", String)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect(),
+                ))
+            }),
         },
         geyser_plugin_config_files,
+        ingest_plugin_config_files,
         rpc_addrs: value_t!(matches, "rpc_port", u16).ok().map(|rpc_port| {
             (
                 SocketAddr::new(rpc_bind_address, rpc_port),
@@ -2528,8 +3602,14 @@ pub fn main() {
             use_quic: tpu_use_quic,
             batch_send_rate_ms: rpc_send_batch_send_rate_ms,
             batch_size: rpc_send_batch_size,
+            retry_stop_commitment: commitment_of(
+                &matches,
+                "rpc_send_transaction_retry_stop_commitment",
+            )
+            .unwrap_or_default(),
         },
         no_poh_speed_test: matches.is_present("no_poh_speed_test"),
+        poh_speed_test_threads: value_t_or_exit!(matches, "poh_speed_test_threads", usize),
         no_os_memory_stats_reporting: matches.is_present("no_os_memory_stats_reporting"),
         no_os_network_stats_reporting: matches.is_present("no_os_network_stats_reporting"),
         poh_pinned_cpu_core: value_of(&matches, "poh_pinned_cpu_core")
@@ -2548,6 +3628,30 @@ pub fn main() {
             bpf_jit: !matches.is_present("no_bpf_jit"),
             ..RuntimeConfig::default()
         },
+        banking_account_denylist,
+        banking_source_packets_per_second: value_t!(
+            matches,
+            "banking_source_packets_per_second",
+            u64
+        )
+        .ok(),
+        banking_max_packets_per_lock_set: value_t!(
+            matches,
+            "banking_max_packets_per_lock_set",
+            usize
+        )
+        .ok(),
+        banking_notify_dropped_transactions: matches
+            .is_present("banking_notify_dropped_transactions"),
+        banking_persist_buffered_packets: matches.is_present("banking_persist_buffered_packets"),
+        banking_forward_pacing_config,
+        banking_priority_aging_config,
+        banking_pop_policy_config,
+        serve_repair_admission_config,
+        broadcast_config: BroadcastConfig {
+            coding_threads: value_t!(matches, "broadcast_coding_threads", usize).ok(),
+            pipeline_coding: matches.is_present("broadcast_pipeline_coding"),
+        },
         ..ValidatorConfig::default()
     };
 
@@ -2746,7 +3850,46 @@ pub fn main() {
         }
         validator_config.max_ledger_shreds = Some(limit_ledger_size);
     }
+    if let Ok(limit_ledger_bytes) = value_t!(matches, "limit_ledger_bytes", u64) {
+        if limit_ledger_bytes < DEFAULT_MIN_MAX_LEDGER_BYTES {
+            eprintln!(
+                "The provided --limit-ledger-bytes value was too small, the minimum value is {}",
+                DEFAULT_MIN_MAX_LEDGER_BYTES
+            );
+            exit(1);
+        }
+        validator_config.max_ledger_bytes = Some(limit_ledger_bytes);
+    }
+    validator_config.ledger_purge_tombstones = matches.is_present("ledger_purge_tombstones");
+    validator_config.slot_purge_listeners = SlotPurgeListeners::new(
+        value_t!(matches, "ledger_purge_grace_window", u64)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_SLOT_PURGE_GRACE_WINDOW),
+    );
 
+    let mut column_filter_options: HashMap<_, BlockstoreColumnFilterOptions> = HashMap::new();
+    for value in matches
+        .values_of("rocksdb_bloom_filter_bits_per_key")
+        .into_iter()
+        .flatten()
+    {
+        let (column, bits_per_key) = parse_rocksdb_bloom_filter(value).unwrap();
+        column_filter_options
+            .entry(column)
+            .or_default()
+            .bloom_filter_bits_per_key = Some(bits_per_key);
+    }
+    for value in matches
+        .values_of("rocksdb_prefix_extractor")
+        .into_iter()
+        .flatten()
+    {
+        let (column, prefix_extractor) = parse_rocksdb_prefix_extractor(value).unwrap();
+        column_filter_options
+            .entry(column)
+            .or_default()
+            .prefix_extractor = Some(prefix_extractor);
+    }
     validator_config.ledger_column_options = LedgerColumnOptions {
         compression_type: match matches.value_of("rocksdb_ledger_compression") {
             None => BlockstoreCompressionType::default(),
@@ -2769,8 +3912,9 @@ pub fn main() {
                     let shred_storage_size =
                         value_t_or_exit!(matches, "rocksdb_fifo_shred_storage_size", u64);
                     ShredStorageType::RocksFifo(BlockstoreRocksFifoOptions {
-                        shred_data_cf_size: shred_storage_size / 2,
-                        shred_code_cf_size: shred_storage_size / 2,
+                        clamp_to_minimum_size: matches
+                            .is_present("rocksdb_fifo_clamp_to_minimum_size"),
+                        ..BlockstoreRocksFifoOptions::new_for_total_size(shred_storage_size)
                     })
                 }
                 _ => panic!(
@@ -2784,10 +3928,72 @@ pub fn main() {
             "rocksdb_perf_sample_interval",
             usize
         ),
+        column_filter_options,
+        ..LedgerColumnOptions::default()
+    };
+    let rocksdb_tuning_options = RocksDbTuningOptions {
+        write_buffer_size: value_t!(matches, "rocksdb_write_buffer_size", u64).ok(),
+        max_write_buffer_number: value_t!(matches, "rocksdb_max_write_buffer_number", i32).ok(),
+        block_cache_size: value_t!(matches, "rocksdb_block_cache_size", usize).ok(),
+        max_background_jobs: value_t!(matches, "rocksdb_max_background_jobs", i32).ok(),
     };
+    validator_config.rocksdb_io_options = BlockstoreIoOptions {
+        use_direct_reads: matches.is_present("rocksdb_direct_reads"),
+        use_direct_io_for_flush_and_compaction: matches
+            .is_present("rocksdb_direct_io_for_flush_and_compaction"),
+        allow_mmap_reads: matches.is_present("rocksdb_mmap_reads"),
+        allow_mmap_writes: matches.is_present("rocksdb_mmap_writes"),
+    };
+    validator_config.blockstore_verify_on_open =
+        value_t!(matches, "verify_blockstore_on_open", u64).ok();
+    if let Some(column_paths) = matches.values_of("ledger_column_path") {
+        validator_config.blockstore_column_layout = ColumnLayout::from_column_paths(column_paths)
+            .unwrap_or_else(|err| {
+                eprintln!("Invalid --ledger-column-path: {}", err);
+                exit(1);
+            });
+    }
+    validator_config.ledger_archive_config =
+        matches
+            .value_of("ledger_archive_url")
+            .map(|url| LedgerArchiveConfig {
+                url: url.to_string(),
+                endpoint: matches
+                    .value_of("ledger_archive_endpoint")
+                    .map(String::from),
+                region: matches.value_of("ledger_archive_region").map(String::from),
+                min_age_slots: value_t_or_exit!(matches, "ledger_archive_min_age_slots", u64),
+            });
+    validator_config.rocksdb_tuning_options =
+        (rocksdb_tuning_options != RocksDbTuningOptions::default()).then(|| rocksdb_tuning_options);
 
     if matches.is_present("halt_on_known_validators_accounts_hash_mismatch") {
         validator_config.halt_on_known_validators_accounts_hash_mismatch = true;
+        let default_policy = AccountsHashVerificationPolicy::default();
+        let mismatch_stake_fraction = value_t!(
+            matches,
+            "accounts_hash_verification_mismatch_stake_fraction",
+            f64
+        )
+        .unwrap_or(default_policy.mismatch_stake_fraction);
+        if !(0.0..=1.0).contains(&mismatch_stake_fraction) {
+            eprintln!(
+                "The specified accounts-hash-verification-mismatch-stake-fraction is invalid, it must be between 0. and 1.0 inclusive: {}",
+                mismatch_stake_fraction
+            );
+            exit(1);
+        }
+        validator_config.accounts_hash_verification_policy = AccountsHashVerificationPolicy {
+            min_known_validators: value_t!(
+                matches,
+                "accounts_hash_verification_min_known_validators",
+                usize
+            )
+            .unwrap_or(default_policy.min_known_validators),
+            mismatch_stake_fraction,
+            max_age_slots: value_t!(matches, "accounts_hash_verification_max_age_slots", Slot)
+                .unwrap_or(default_policy.max_age_slots),
+        };
     }
 
     let public_rpc_addr = matches.value_of("public_rpc_addr").map(|addr| {
@@ -2821,6 +4027,7 @@ pub fn main() {
             authorized_voter_keypairs: authorized_voter_keypairs.clone(),
             post_init: admin_service_post_init.clone(),
             tower_storage: validator_config.tower_storage.clone(),
+            rpc_access_control: validator_config.rpc_config.rpc_access_control.clone(),
         },
     );
 
@@ -2978,8 +4185,10 @@ pub fn main() {
     *admin_service_post_init.write().unwrap() =
         Some(admin_rpc_service::AdminRpcRequestMetadataPostInit {
             bank_forks: validator.bank_forks.clone(),
+            blockstore: validator.blockstore.clone(),
             cluster_info: validator.cluster_info.clone(),
             vote_account,
+            poh_speed_check_report: validator.poh_speed_check_report.clone(),
         });
 
     if let Some(filename) = init_complete_file {